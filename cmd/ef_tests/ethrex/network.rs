@@ -1,4 +1,5 @@
 use ethrex_core::types::ChainConfig;
+use ethrex_core::Address;
 use lazy_static::lazy_static;
 use serde::Deserialize;
 
@@ -41,6 +42,17 @@ lazy_static! {
         cancun_time: Some(0),
         ..*SHANGHAI_CONFIG
     };
+    pub static ref CANCUN_TO_PRAGUE_AT_15K_CONFIG: ChainConfig = ChainConfig {
+        prague_time: Some(0x3a98),
+        deposit_contract_address: Address::from_slice(
+            &hex::decode("00000000219ab540356cbb839cbe05303d7705fa").unwrap()
+        ),
+        ..*CANCUN_CONFIG
+    };
+    pub static ref PRAGUE_CONFIG: ChainConfig = ChainConfig {
+        prague_time: Some(0),
+        ..*CANCUN_TO_PRAGUE_AT_15K_CONFIG
+    };
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,6 +64,8 @@ pub enum Network {
     Shanghai,
     ShanghaiToCancunAtTime15k,
     Cancun,
+    CancunToPragueAtTime15k,
+    Prague,
 }
 
 impl Network {
@@ -62,6 +76,8 @@ impl Network {
             Network::Shanghai => &SHANGHAI_CONFIG,
             Network::ShanghaiToCancunAtTime15k => &SHANGHAI_TO_CANCUN_AT_15K_CONFIG,
             Network::Cancun => &CANCUN_CONFIG,
+            Network::CancunToPragueAtTime15k => &CANCUN_TO_PRAGUE_AT_15K_CONFIG,
+            Network::Prague => &PRAGUE_CONFIG,
         }
     }
 }
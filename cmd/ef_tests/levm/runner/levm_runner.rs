@@ -75,6 +75,7 @@ pub fn prepare_vm_for_tx(vector: &TestVector, test: &EFTest) -> Result<VM, EFTes
     let db = Arc::new(StoreWrapper {
         store: initial_state.database().unwrap().clone(),
         block_hash,
+        recorder: None,
     });
 
     let tx = test
@@ -1,5 +1,5 @@
 use clap::{Arg, ArgAction, Command};
-use ethrex_net::bootnode::BootNode;
+use ethrex_net::{bootnode::BootNode, types::Node};
 use tracing::Level;
 
 pub fn cli() -> Command {
@@ -20,6 +20,30 @@ pub fn cli() -> Command {
                 .value_name("PORT")
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("rpc.batch-request-limit")
+                .long("rpc.batch-request-limit")
+                .required(false)
+                .value_name("LIMIT")
+                .value_parser(clap::value_parser!(usize))
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("rpc.logs-max-block-range")
+                .long("rpc.logs-max-block-range")
+                .default_value("100000")
+                .value_name("BLOCKS")
+                .value_parser(clap::value_parser!(u64))
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("rpc.logs-max-results")
+                .long("rpc.logs-max-results")
+                .default_value("10000")
+                .value_name("LIMIT")
+                .value_parser(clap::value_parser!(usize))
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("log.level")
                 .long("log.level")
@@ -77,6 +101,54 @@ pub fn cli() -> Command {
                 .value_name("PORT")
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("discovery.v4")
+                .long("discovery.v4")
+                .default_value("true")
+                .value_name("ENABLED")
+                .value_parser(clap::value_parser!(bool))
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("discovery.v5")
+                .long("discovery.v5")
+                .default_value("false")
+                .value_name("ENABLED")
+                .value_parser(clap::value_parser!(bool))
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("maxpeers")
+                .long("maxpeers")
+                .required(false)
+                .value_name("PEER_COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("p2p.dialratio")
+                .long("p2p.dialratio")
+                .required(false)
+                .value_name("RATIO")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("p2p.staticpeers")
+                .long("p2p.staticpeers")
+                .value_name("ENODE_LIST")
+                .value_parser(parse_enode)
+                .value_delimiter(',')
+                .num_args(1..)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("p2p.trustedpeers")
+                .long("p2p.trustedpeers")
+                .value_name("ENODE_LIST")
+                .value_parser(parse_enode)
+                .value_delimiter(',')
+                .num_args(1..)
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("network")
                 .long("network")
@@ -110,6 +182,55 @@ pub fn cli() -> Command {
                 .required(false)
                 .value_name("SYNC_MODE"),
         )
+        .arg(
+            Arg::new("pruning")
+                .long("pruning")
+                .required(false)
+                .value_name("PRUNING_MODE")
+                .value_parser(["archive", "full"]),
+        )
+        .arg(
+            Arg::new("pruning.retain")
+                .long("pruning.retain")
+                .required(false)
+                .value_name("BLOCK_COUNT")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("txpool.globalslots")
+                .long("txpool.globalslots")
+                .required(false)
+                .value_name("TX_COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("txpool.globalqueue")
+                .long("txpool.globalqueue")
+                .required(false)
+                .value_name("TX_COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("txpool.accountslots")
+                .long("txpool.accountslots")
+                .required(false)
+                .value_name("TX_COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("txpool.accountqueue")
+                .long("txpool.accountqueue")
+                .required(false)
+                .value_name("TX_COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("txpool.pricebump")
+                .long("txpool.pricebump")
+                .required(false)
+                .value_name("PERCENTAGE")
+                .value_parser(clap::value_parser!(u64)),
+        )
         .arg(
             Arg::new("import_dir")
                 .long("import_dir")
@@ -125,3 +246,7 @@ pub fn cli() -> Command {
             ),
         )
 }
+
+fn parse_enode(input: &str) -> Result<Node, String> {
+    Node::from_enode_url(input).ok_or_else(|| format!("invalid enode url: {input}"))
+}
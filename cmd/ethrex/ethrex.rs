@@ -7,12 +7,15 @@ use ethrex_core::{
 };
 use ethrex_net::{
     bootnode::BootNode,
-    node_id_from_signing_key, peer_table,
+    node_id_from_signing_key,
+    peer_manager::{PeerLimits, PeerManager},
+    peer_table,
     sync::{SyncManager, SyncMode},
     types::Node,
+    DiscoveryProtocols,
 };
 use ethrex_rlp::decode::RLPDecode;
-use ethrex_storage::{EngineType, Store};
+use ethrex_storage::{EngineType, MempoolConfig, PruningMode, Store, DEFAULT_RETAINED_BLOCKS};
 use k256::ecdsa::SigningKey;
 use local_ip_address::local_ip;
 use std::{
@@ -76,6 +79,18 @@ async fn main() {
     let authrpc_jwtsecret = matches
         .get_one::<String>("authrpc.jwtsecret")
         .expect("authrpc.jwtsecret is required");
+    let batch_request_limit = matches
+        .get_one::<usize>("rpc.batch-request-limit")
+        .copied()
+        .unwrap_or(ethrex_rpc::DEFAULT_BATCH_REQUEST_LIMIT);
+    let logs_limits = ethrex_rpc::LogsLimits {
+        max_block_range: *matches
+            .get_one::<u64>("rpc.logs-max-block-range")
+            .expect("rpc.logs-max-block-range is required"),
+        max_results: *matches
+            .get_one::<usize>("rpc.logs-max-results")
+            .expect("rpc.logs-max-results is required"),
+    };
 
     let tcp_addr = matches
         .get_one::<String>("p2p.addr")
@@ -119,6 +134,10 @@ async fn main() {
         .map_or(set_datadir(DEFAULT_DATADIR), |datadir| set_datadir(datadir));
 
     let sync_mode = sync_mode(&matches);
+    let discovery_protocols = discovery_protocols(&matches);
+    let peer_manager = peer_manager(&matches);
+    let pruning_mode = pruning_mode(&matches);
+    let mempool_config = mempool_config(&matches);
 
     cfg_if::cfg_if! {
         if #[cfg(feature = "redb")] {
@@ -129,6 +148,8 @@ async fn main() {
             let store = Store::new(&data_dir, EngineType::InMemory).expect("Failed to create Store");
         }
     }
+    store.set_pruning_mode(pruning_mode);
+    store.set_mempool_config(mempool_config);
 
     let genesis = read_genesis_file(genesis_file_path);
     store
@@ -198,6 +219,9 @@ async fn main() {
         jwt_secret,
         local_p2p_node,
         syncer,
+        peer_manager.clone(),
+        batch_request_limit,
+        logs_limits,
     )
     .into_future();
 
@@ -227,12 +251,15 @@ async fn main() {
             tracker.spawn(block_producer_engine);
         } else {
             let networking = ethrex_net::start_network(
+                local_p2p_node,
                 udp_socket_addr,
                 tcp_socket_addr,
                 bootnodes,
                 signer,
                 peer_table,
                 store,
+                discovery_protocols,
+                peer_manager,
             )
             .into_future();
             tracker.spawn(networking);
@@ -312,6 +339,80 @@ fn sync_mode(matches: &clap::ArgMatches) -> SyncMode {
     }
 }
 
+fn discovery_protocols(matches: &clap::ArgMatches) -> DiscoveryProtocols {
+    DiscoveryProtocols {
+        v4: *matches
+            .get_one::<bool>("discovery.v4")
+            .expect("discovery.v4 is required"),
+        v5: *matches
+            .get_one::<bool>("discovery.v5")
+            .expect("discovery.v5 is required"),
+    }
+}
+
+fn peer_manager(matches: &clap::ArgMatches) -> PeerManager {
+    let default = PeerLimits::default();
+    let limits = PeerLimits {
+        max_peers: matches
+            .get_one::<usize>("maxpeers")
+            .copied()
+            .unwrap_or(default.max_peers),
+        dial_ratio: matches
+            .get_one::<usize>("p2p.dialratio")
+            .copied()
+            .unwrap_or(default.dial_ratio),
+    };
+    let static_peers = matches
+        .get_many("p2p.staticpeers")
+        .map(Iterator::copied)
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    let trusted_peers = matches
+        .get_many("p2p.trustedpeers")
+        .map(Iterator::copied)
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    PeerManager::new(limits, static_peers, trusted_peers)
+}
+
+fn pruning_mode(matches: &clap::ArgMatches) -> PruningMode {
+    let retained_blocks = matches
+        .get_one::<u64>("pruning.retain")
+        .copied()
+        .unwrap_or(DEFAULT_RETAINED_BLOCKS);
+    // The parser only accepts "archive" and "full"
+    match matches.get_one::<String>("pruning").map(String::as_str) {
+        Some("full") => PruningMode::Full { retained_blocks },
+        _ => PruningMode::Archive,
+    }
+}
+
+fn mempool_config(matches: &clap::ArgMatches) -> MempoolConfig {
+    let default = MempoolConfig::default();
+    MempoolConfig {
+        global_slots: matches
+            .get_one::<usize>("txpool.globalslots")
+            .copied()
+            .unwrap_or(default.global_slots),
+        global_queue: matches
+            .get_one::<usize>("txpool.globalqueue")
+            .copied()
+            .unwrap_or(default.global_queue),
+        account_slots: matches
+            .get_one::<usize>("txpool.accountslots")
+            .copied()
+            .unwrap_or(default.account_slots),
+        account_queue: matches
+            .get_one::<usize>("txpool.accountqueue")
+            .copied()
+            .unwrap_or(default.account_queue),
+        price_bump: matches
+            .get_one::<u64>("txpool.pricebump")
+            .copied()
+            .unwrap_or(default.price_bump),
+    }
+}
+
 fn set_datadir(datadir: &str) -> String {
     let project_dir = ProjectDirs::from("", "", datadir).expect("Couldn't find home directory");
     project_dir
@@ -8,7 +8,7 @@ use std::str::FromStr;
 #[derive(Subcommand)]
 pub(crate) enum Command {
     #[clap(
-        about = "Get latestCommittedBlock and latestVerifiedBlock from the OnChainProposer.",
+        about = "Get the latest committed and verified batches and blocks from the OnChainProposer.",
         short_flag = 'l'
     )]
     LatestBlocks,
@@ -45,6 +45,24 @@ impl Command {
                     EthClient::get_last_verified_block(&eth_client, on_chain_proposer_address)
                         .await?;
 
+                let last_committed_batch =
+                    EthClient::get_last_committed_batch(&eth_client, on_chain_proposer_address)
+                        .await?;
+
+                let last_verified_batch =
+                    EthClient::get_last_verified_batch(&eth_client, on_chain_proposer_address)
+                        .await?;
+
+                println!(
+                    "latestCommittedBatch: {}",
+                    format!("{last_committed_batch}").bright_cyan()
+                );
+
+                println!(
+                    "latestVerifiedBatch:  {}",
+                    format!("{last_verified_batch}").bright_cyan()
+                );
+
                 println!(
                     "latestCommittedBlock: {}",
                     format!("{last_committed_block}").bright_cyan()
@@ -1,5 +1,11 @@
 use crate::{config::EthrexL2Config, utils::config::confirm};
 use clap::Subcommand;
+use ethrex_core::types::Genesis;
+use ethrex_l2::utils::{
+    eth_client::EthClient,
+    state_reconstruct::{BeaconClient, BlobSource, StateReconstructor},
+};
+use ethrex_storage::{EngineType, Store};
 use eyre::ContextCompat;
 use secp256k1::SecretKey;
 use std::path::{Path, PathBuf};
@@ -65,6 +71,37 @@ pub(crate) enum Command {
         #[clap(short = 'y', long, help = "Forces the restart without confirmation.")]
         force: bool,
     },
+    #[clap(
+        about = "Reconstructs the L2 state from the blobs committed to the L1.",
+        long_about = "Applies the state diffs posted by the committer to a fresh store, checking the state root of every committed block. Running it again on the same data directory resumes from its latest block."
+    )]
+    Reconstruct {
+        #[arg(
+            short = 'g',
+            long = "genesis",
+            help = "Path to the genesis file of the L2."
+        )]
+        genesis: PathBuf,
+        #[arg(
+            short = 's',
+            long = "datadir",
+            help = "Path to the data directory of the reconstructed store."
+        )]
+        datadir: PathBuf,
+        #[arg(
+            short = 'b',
+            long = "blobs-dir",
+            help = "Directory holding the committed blobs as `<versioned hash>.blob` files.",
+            conflicts_with = "beacon_url",
+            required_unless_present = "beacon_url"
+        )]
+        blobs_dir: Option<PathBuf>,
+        #[arg(
+            long = "beacon-url",
+            help = "URL of a beacon node API serving the blob sidecars of the L1."
+        )]
+        beacon_url: Option<String>,
+    },
 }
 
 impl Command {
@@ -164,6 +201,36 @@ impl Command {
                     println!("Aborted.");
                 }
             }
+            Command::Reconstruct {
+                genesis,
+                datadir,
+                blobs_dir,
+                beacon_url,
+            } => {
+                let genesis: Genesis = serde_json::from_reader(std::fs::File::open(genesis)?)?;
+                let datadir = datadir.to_str().context("Invalid data directory path")?;
+                let store = Store::new(datadir, EngineType::Libmdbx)?;
+                store.add_initial_state(genesis)?;
+
+                let blob_source = match (blobs_dir, beacon_url) {
+                    (Some(blobs_dir), _) => BlobSource::Archive(blobs_dir),
+                    (None, Some(beacon_url)) => {
+                        BlobSource::Beacon(BeaconClient::new(&beacon_url).await?)
+                    }
+                    (None, None) => {
+                        eyre::bail!("Either a blobs directory or a beacon URL is required")
+                    }
+                };
+                let last_block = StateReconstructor::new(
+                    EthClient::new(&l1_rpc_url),
+                    cfg.contracts.on_chain_proposer,
+                    blob_source,
+                    store,
+                )
+                .reconstruct()
+                .await?;
+                println!("Reconstructed the L2 state up to block {last_block}");
+            }
         }
         Ok(())
     }
@@ -5,18 +5,19 @@ pub mod mempool;
 pub mod payload;
 mod smoke_test;
 
-use constants::{GAS_PER_BLOB, MAX_BLOB_GAS_PER_BLOCK, MAX_BLOB_NUMBER_PER_BLOCK};
+use constants::GAS_PER_BLOB;
 use error::{ChainError, InvalidBlockError};
 use ethrex_core::types::{
-    compute_receipts_root, validate_block_header, validate_cancun_header_fields,
-    validate_no_cancun_header_fields, Block, BlockHash, BlockHeader, BlockNumber,
-    EIP4844Transaction, Receipt, Transaction,
+    compute_receipts_root, compute_requests_hash, validate_block_header,
+    validate_cancun_header_fields, validate_no_cancun_header_fields, validate_prague_header_fields,
+    Block, BlockHash, BlockHeader, BlockNumber, EIP4844Transaction, EncodedRequests, Fork, Receipt,
+    Transaction,
 };
 use ethrex_core::H256;
 
 use ethrex_storage::error::StoreError;
 use ethrex_storage::Store;
-use ethrex_vm::{evm_state, execute_block, spec_id, EvmState, SpecId};
+use ethrex_vm::{evm_state, execute_block, extract_all_requests, EvmState};
 
 //TODO: Implement a struct Chain or BlockChain to encapsulate
 //functionality and canonical chain state and config
@@ -47,6 +48,10 @@ pub fn add_block(block: &Block, storage: &Store) -> Result<(), ChainError> {
 
     validate_gas_used(&receipts, &block.header)?;
 
+    let requests = extract_all_requests(&receipts, &mut state, &block.header)?;
+
+    validate_requests_hash(&block.header, &requests)?;
+
     let account_updates = get_state_transitions(&mut state);
 
     // Apply the account updates over the last block's state and compute the new state root
@@ -95,6 +100,10 @@ pub fn add_block(block: &Block, storage: &Store) -> Result<(), ChainError> {
 
     validate_gas_used(&receipts, &block.header)?;
 
+    let requests = extract_all_requests(&receipts, &mut state, &block.header)?;
+
+    validate_requests_hash(&block.header, &requests)?;
+
     // Apply the account updates over the last block's state and compute the new state root
     let new_state_root = state
         .database()
@@ -159,6 +168,25 @@ pub fn validate_receipts_root(
     }
 }
 
+/// Checks the requests_hash in the header matches the requests produced by the block
+/// Blocks previous to the Prague fork must not contain a requests_hash, this is checked pre-execution
+pub fn validate_requests_hash(
+    block_header: &BlockHeader,
+    requests: &[EncodedRequests],
+) -> Result<(), ChainError> {
+    let Some(requests_hash) = block_header.requests_hash else {
+        return Ok(());
+    };
+
+    if compute_requests_hash(requests) == requests_hash {
+        Ok(())
+    } else {
+        Err(ChainError::InvalidBlock(
+            InvalidBlockError::RequestsHashMismatch,
+        ))
+    }
+}
+
 // Returns the hash of the head of the canonical chain (the latest valid hash).
 pub fn latest_canonical_block_hash(storage: &Store) -> Result<H256, ChainError> {
     let latest_block_number = storage.get_latest_block_number()?;
@@ -191,24 +219,26 @@ pub fn validate_block(
     parent_header: &BlockHeader,
     state: &EvmState,
 ) -> Result<(), ChainError> {
-    let spec = spec_id(
-        &state.chain_config().map_err(ChainError::from)?,
-        block.header.timestamp,
-    );
+    let fork = state
+        .chain_config()
+        .map_err(ChainError::from)?
+        .get_fork(block.header.timestamp);
 
     // Verify initial header validity against parent
     validate_block_header(&block.header, parent_header).map_err(InvalidBlockError::from)?;
 
-    match spec {
-        SpecId::CANCUN => validate_cancun_header_fields(&block.header, parent_header)
+    match fork {
+        Fork::Prague => validate_prague_header_fields(&block.header, parent_header)
+            .map_err(InvalidBlockError::from)?,
+        Fork::Cancun => validate_cancun_header_fields(&block.header, parent_header)
             .map_err(InvalidBlockError::from)?,
-        _other_specs => {
+        _other_forks => {
             validate_no_cancun_header_fields(&block.header).map_err(InvalidBlockError::from)?
         }
     };
 
-    if spec == SpecId::CANCUN {
-        verify_blob_gas_usage(block)?
+    if fork >= Fork::Cancun {
+        verify_blob_gas_usage(block, fork)?
     }
     Ok(())
 }
@@ -239,7 +269,9 @@ pub fn validate_gas_used(
     Ok(())
 }
 
-fn verify_blob_gas_usage(block: &Block) -> Result<(), ChainError> {
+fn verify_blob_gas_usage(block: &Block, fork: Fork) -> Result<(), ChainError> {
+    let max_blob_number_per_block = fork.max_blobs_per_block();
+    let max_blob_gas_per_block = max_blob_number_per_block * GAS_PER_BLOB;
     let mut blob_gas_used = 0_u64;
     let mut blobs_in_block = 0_u64;
     for transaction in block.body.transactions.iter() {
@@ -248,12 +280,12 @@ fn verify_blob_gas_usage(block: &Block) -> Result<(), ChainError> {
             blobs_in_block += tx.blob_versioned_hashes.len() as u64;
         }
     }
-    if blob_gas_used > MAX_BLOB_GAS_PER_BLOCK {
+    if blob_gas_used > max_blob_gas_per_block {
         return Err(ChainError::InvalidBlock(
             InvalidBlockError::ExceededMaxBlobGasPerBlock,
         ));
     }
-    if blobs_in_block > MAX_BLOB_NUMBER_PER_BLOCK {
+    if blobs_in_block > max_blob_number_per_block {
         return Err(ChainError::InvalidBlock(
             InvalidBlockError::ExceededMaxBlobNumberPerBlock,
         ));
@@ -1,5 +1,5 @@
 use ethrex_core::types::{BlobsBundleError, InvalidBlockHeaderError};
-use ethrex_storage::error::StoreError;
+use ethrex_storage::error::{PoolError, StoreError};
 use ethrex_vm::EvmError;
 
 #[derive(Debug, thiserror::Error)]
@@ -34,6 +34,8 @@ pub enum InvalidBlockError {
     GasUsedMismatch,
     #[error("Blob gas used doesn't match value in header")]
     BlobGasUsedMismatch,
+    #[error("Requests hash doesn't match value in header")]
+    RequestsHashMismatch,
     #[error("Invalid transaction: {0}")]
     InvalidTransaction(String),
 }
@@ -44,6 +46,8 @@ pub enum MempoolError {
     NoBlockHeaderError,
     #[error("DB error: {0}")]
     StoreError(#[from] StoreError),
+    #[error(transparent)]
+    PoolError(#[from] PoolError),
     #[error("BlobsBundle error: {0}")]
     BlobsBundleError(#[from] BlobsBundleError),
     #[error("Transaction max init code size exceeded")]
@@ -6,9 +6,9 @@ use ethrex_storage::{error::StoreError, Store};
 
 use crate::{
     error::{self, InvalidForkChoice},
-    is_canonical,
+    is_canonical, mempool,
 };
-use tracing::error;
+use tracing::{error, warn};
 
 /// Applies new fork choice data to the current blockchain. It performs validity checks:
 /// - The finalized, safe and head hashes must correspond to already saved blocks.
@@ -115,6 +115,18 @@ pub fn apply_fork_choice(
 
     // Finished all validations.
 
+    // Collect the blocks that leave and join the canonical chain before updating it
+    let mut adopted_blocks = new_canonical_blocks.clone();
+    adopted_blocks.push((head.number, head_hash));
+    let mut abandoned_blocks = Vec::new();
+    for number in link_block_number..=latest {
+        if let Some(hash) = store.get_canonical_block_hash(number)? {
+            if !adopted_blocks.contains(&(number, hash)) {
+                abandoned_blocks.push(hash);
+            }
+        }
+    }
+
     // Make all ancestors to head canonical.
     for (number, hash) in new_canonical_blocks {
         store.set_canonical_block(number, hash)?;
@@ -135,6 +147,26 @@ pub fn apply_fork_choice(
     }
     store.update_latest_block_number(head.number)?;
 
+    // Move the transactions of the abandoned blocks back into the mempool and drop the ones that were included
+    // The pool is only a cache, so failing to update it doesn't invalidate the fork choice either
+    adopted_blocks.sort();
+    let adopted_blocks: Vec<_> = adopted_blocks.into_iter().map(|(_, hash)| hash).collect();
+    if let Err(err) = mempool::update_on_reorg(head_hash, &abandoned_blocks, &adopted_blocks, store)
+    {
+        warn!("Failed to update the mempool after the new fork choice: {err}");
+    }
+
+    // Remove states that are no longer needed (if pruning is enabled), this happens in the background
+    // A failed pruning doesn't invalidate the fork choice, so we only log it
+    if let Err(err) = store.prune_state(&head) {
+        warn!("Failed to prune state: {err}");
+    }
+
+    // Keep the bloom bits index used by log queries in sync with the canonical chain
+    if let Err(err) = store.update_bloom_bits_index(head.number, link_block_number) {
+        warn!("Failed to update the bloom bits index: {err}");
+    }
+
     Ok(head)
 }
 
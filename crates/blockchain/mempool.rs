@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     constants::{
@@ -11,11 +11,13 @@ use crate::{
 };
 use ethrex_core::{
     types::{
-        BlobsBundle, BlockHeader, ChainConfig, EIP4844Transaction, MempoolTransaction, Transaction,
+        BlobsBundle, BlockHash, BlockHeader, ChainConfig, EIP4844Transaction, MempoolTransaction,
+        Transaction,
     },
     Address, H256, U256,
 };
 use ethrex_storage::{error::StoreError, Store};
+use tracing::debug;
 
 /// Add a blob transaction and its blobs bundle to the mempool
 #[cfg(feature = "c-kzg")]
@@ -35,7 +37,12 @@ pub fn add_blob_transaction(
 
     // Add transaction and blobs bundle to storage
     let hash = transaction.compute_hash();
-    store.add_transaction_to_pool(hash, MempoolTransaction::new(transaction, sender))?;
+    let sender_nonce = latest_nonce(sender, &store)?;
+    store.add_transaction_to_pool(
+        hash,
+        MempoolTransaction::new(transaction, sender),
+        sender_nonce,
+    )?;
     store.add_blobs_bundle_to_pool(hash, blobs_bundle)?;
     Ok(hash)
 }
@@ -53,17 +60,74 @@ pub fn add_transaction(transaction: Transaction, store: &Store) -> Result<H256,
     let hash = transaction.compute_hash();
 
     // Add transaction to storage
-    store.add_transaction_to_pool(hash, MempoolTransaction::new(transaction, sender))?;
+    let sender_nonce = latest_nonce(sender, store)?;
+    store.add_transaction_to_pool(
+        hash,
+        MempoolTransaction::new(transaction, sender),
+        sender_nonce,
+    )?;
 
     Ok(hash)
 }
 
+/// Returns the nonce of the account at the latest block, which determines whether its pooled transactions are executable
+fn latest_nonce(address: Address, store: &Store) -> Result<u64, StoreError> {
+    Ok(store
+        .get_nonce_by_account_address(store.get_latest_block_number()?, address)?
+        .unwrap_or_default())
+}
+
+/// Updates the mempool after the canonical chain switched to a new head:
+/// - Transactions included in the blocks that became canonical are removed
+/// - Transactions of the blocks that left the canonical chain are added back, unless they are also included in the new chain
+/// - Transactions that can no longer be included on top of the new head due to their nonce are removed
+///
+/// Blob transactions from abandoned blocks are not added back, as their blobs bundles are not kept after inclusion
+pub fn update_on_reorg(
+    head_hash: BlockHash,
+    abandoned_blocks: &[BlockHash],
+    adopted_blocks: &[BlockHash],
+    store: &Store,
+) -> Result<(), StoreError> {
+    let mut included = HashSet::new();
+    for block_hash in adopted_blocks {
+        let Some(body) = store.get_block_body_by_hash(*block_hash)? else {
+            continue;
+        };
+        for tx in body.transactions {
+            let hash = tx.compute_hash();
+            store.remove_transaction_from_pool(&hash)?;
+            included.insert(hash);
+        }
+    }
+
+    // Abandoned blocks are sorted by number, so the transactions of each sender are added back in nonce order
+    for block_hash in abandoned_blocks {
+        let Some(body) = store.get_block_body_by_hash(*block_hash)? else {
+            continue;
+        };
+        for tx in body.transactions {
+            let hash = tx.compute_hash();
+            if included.contains(&hash) || matches!(tx, Transaction::EIP4844Transaction(_)) {
+                continue;
+            }
+            if let Err(err) = add_transaction(tx, store) {
+                debug!(
+                    "Dropping transaction {hash:#x} from abandoned block {block_hash:#x}: {err}"
+                );
+            }
+        }
+    }
+
+    store.remove_stale_transactions_from_pool(head_hash)
+}
+
 /// Fetch a blobs bundle from the mempool given its blob transaction hash
 pub fn get_blobs_bundle(tx_hash: H256, store: Store) -> Result<Option<BlobsBundle>, MempoolError> {
     Ok(store.get_blobs_bundle_from_pool(tx_hash)?)
 }
 
-/// Applies the filter and returns a set of suitable transactions from the mempool.
+/// Applies the filter and returns a set of suitable executable transactions from the mempool.
 /// These transactions will be grouped by sender and sorted by nonce
 pub fn filter_transactions(
     filter: &PendingTxFilter,
@@ -6,25 +6,22 @@ use std::{
 use ethrex_core::{
     types::{
         calculate_base_fee_per_blob_gas, calculate_base_fee_per_gas, compute_receipts_root,
-        compute_transactions_root, compute_withdrawals_root, BlobsBundle, Block, BlockBody,
-        BlockHash, BlockHeader, BlockNumber, ChainConfig, MempoolTransaction, Receipt, Transaction,
-        Withdrawal, DEFAULT_OMMERS_HASH,
+        compute_requests_hash, compute_transactions_root, compute_withdrawals_root, BlobsBundle,
+        Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, EncodedRequests, Fork,
+        MempoolTransaction, Receipt, Transaction, Withdrawal, DEFAULT_OMMERS_HASH,
     },
     Address, Bloom, Bytes, H256, U256,
 };
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_storage::{error::StoreError, Store};
 use ethrex_vm::{
-    beacon_root_contract_call, evm_state, execute_tx, get_state_transitions, process_withdrawals,
-    spec_id, EvmError, EvmState, SpecId,
+    beacon_root_contract_call, evm_state, execute_tx, extract_all_requests, get_state_transitions,
+    history_storage_contract_call, process_withdrawals, spec_id, EvmError, EvmState, SpecId,
 };
 use sha3::{Digest, Keccak256};
 
 use crate::{
-    constants::{
-        GAS_LIMIT_BOUND_DIVISOR, GAS_PER_BLOB, MAX_BLOB_GAS_PER_BLOCK, MIN_GAS_LIMIT,
-        TARGET_BLOB_GAS_PER_BLOCK, TX_GAS_COST,
-    },
+    constants::{GAS_LIMIT_BOUND_DIVISOR, GAS_PER_BLOB, MIN_GAS_LIMIT, TX_GAS_COST},
     error::{ChainError, InvalidBlockError},
     mempool::{self, PendingTxFilter},
 };
@@ -70,6 +67,7 @@ pub fn create_payload(args: &BuildPayloadArgs, storage: &Store) -> Result<Block,
         .get_block_header_by_hash(args.parent)?
         .ok_or_else(|| ChainError::ParentNotFound)?;
     let chain_config = storage.get_chain_config()?;
+    let fork = chain_config.get_fork(args.timestamp);
     let gas_limit = calc_gas_limit(parent_block.gas_limit, DEFAULT_BUILDER_GAS_CEIL);
 
     let header = BlockHeader {
@@ -107,9 +105,14 @@ pub fn create_payload(args: &BuildPayloadArgs, storage: &Store) -> Result<Block,
             calc_excess_blob_gas(
                 parent_block.excess_blob_gas.unwrap_or_default(),
                 parent_block.blob_gas_used.unwrap_or_default(),
+                fork.target_blob_gas_per_block(),
             ),
         ),
         parent_beacon_block_root: args.beacon_root,
+        // Set once the block's requests are known, after executing its transactions
+        requests_hash: chain_config
+            .is_prague_activated(args.timestamp)
+            .then_some(compute_requests_hash(&[])),
     };
 
     let body = BlockBody {
@@ -142,13 +145,13 @@ fn calc_gas_limit(parent_gas_limit: u64, desired_limit: u64) -> u64 {
     limit
 }
 
-fn calc_excess_blob_gas(parent_excess_blob_gas: u64, parent_blob_gas_used: u64) -> u64 {
+fn calc_excess_blob_gas(
+    parent_excess_blob_gas: u64,
+    parent_blob_gas_used: u64,
+    target_blob_gas_per_block: u64,
+) -> u64 {
     let excess_blob_gas = parent_excess_blob_gas + parent_blob_gas_used;
-    if excess_blob_gas < TARGET_BLOB_GAS_PER_BLOCK {
-        0
-    } else {
-        excess_blob_gas - TARGET_BLOB_GAS_PER_BLOCK
-    }
+    excess_blob_gas.saturating_sub(target_blob_gas_per_block)
 }
 
 pub struct PayloadBuildContext<'a> {
@@ -158,21 +161,26 @@ pub struct PayloadBuildContext<'a> {
     pub receipts: Vec<Receipt>,
     pub block_value: U256,
     base_fee_per_blob_gas: U256,
+    max_blob_gas_per_block: u64,
     pub blobs_bundle: BlobsBundle,
+    pub requests: Vec<EncodedRequests>,
 }
 
 impl<'a> PayloadBuildContext<'a> {
-    fn new(payload: &'a mut Block, evm_state: &'a mut EvmState) -> Self {
+    fn new(payload: &'a mut Block, evm_state: &'a mut EvmState, fork: Fork) -> Self {
         PayloadBuildContext {
             remaining_gas: payload.header.gas_limit,
             receipts: vec![],
             block_value: U256::zero(),
             base_fee_per_blob_gas: U256::from(calculate_base_fee_per_blob_gas(
                 payload.header.excess_blob_gas.unwrap_or_default(),
+                fork.blob_base_fee_update_fraction(),
             )),
+            max_blob_gas_per_block: fork.max_blobs_per_block() * GAS_PER_BLOB,
             payload,
             evm_state,
             blobs_bundle: BlobsBundle::default(),
+            requests: Vec::new(),
         }
     }
 }
@@ -199,26 +207,30 @@ impl<'a> PayloadBuildContext<'a> {
     }
 }
 
-/// Completes the payload building process, return the block value
+/// Completes the payload building process, returns the blobs bundle, the execution requests and the block value
 pub fn build_payload(
     payload: &mut Block,
     store: &Store,
-) -> Result<(BlobsBundle, U256), ChainError> {
+) -> Result<(BlobsBundle, Vec<EncodedRequests>, U256), ChainError> {
     debug!("Building payload");
+    let fork = store.get_chain_config()?.get_fork(payload.header.timestamp);
     let mut evm_state = evm_state(store.clone(), payload.header.parent_hash);
-    let mut context = PayloadBuildContext::new(payload, &mut evm_state);
+    let mut context = PayloadBuildContext::new(payload, &mut evm_state, fork);
     apply_withdrawals(&mut context)?;
     fill_transactions(&mut context)?;
     finalize_payload(&mut context)?;
-    Ok((context.blobs_bundle, context.block_value))
+    Ok((context.blobs_bundle, context.requests, context.block_value))
 }
 
 pub fn apply_withdrawals(context: &mut PayloadBuildContext) -> Result<(), EvmError> {
     // Apply withdrawals & call beacon root contract, and obtain the new state root
     let spec_id = spec_id(&context.chain_config()?, context.payload.header.timestamp);
-    if context.payload.header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
+    if context.payload.header.parent_beacon_block_root.is_some() && spec_id >= SpecId::CANCUN {
         beacon_root_contract_call(context.evm_state, &context.payload.header, spec_id)?;
     }
+    if spec_id >= SpecId::PRAGUE {
+        history_storage_contract_call(context.evm_state, &context.payload.header, spec_id)?;
+    }
     let withdrawals = context.payload.body.withdrawals.clone().unwrap_or_default();
     process_withdrawals(context.evm_state, &withdrawals)?;
     Ok(())
@@ -275,7 +287,8 @@ pub fn fill_transactions(context: &mut PayloadBuildContext) -> Result<(), ChainE
             break;
         };
         if !blob_txs.is_empty()
-            && context.blobs_bundle.blobs.len() as u64 * GAS_PER_BLOB >= MAX_BLOB_GAS_PER_BLOCK
+            && context.blobs_bundle.blobs.len() as u64 * GAS_PER_BLOB
+                >= context.max_blob_gas_per_block
         {
             debug!("No more blob gas to run blob transactions");
             blob_txs.clear();
@@ -382,7 +395,7 @@ fn apply_blob_transaction(
         );
     };
     if (context.blobs_bundle.blobs.len() + blobs_bundle.blobs.len()) as u64 * GAS_PER_BLOB
-        > MAX_BLOB_GAS_PER_BLOCK
+        > context.max_blob_gas_per_block
     {
         // This error will only be used for debug tracing
         return Err(EvmError::Custom("max data blobs reached".to_string()).into());
@@ -422,7 +435,16 @@ fn apply_plain_transaction(
     Ok(receipt)
 }
 
-fn finalize_payload(context: &mut PayloadBuildContext) -> Result<(), StoreError> {
+fn finalize_payload(context: &mut PayloadBuildContext) -> Result<(), ChainError> {
+    let requests = extract_all_requests(
+        &context.receipts,
+        context.evm_state,
+        &context.payload.header,
+    )?;
+    if context.payload.header.requests_hash.is_some() {
+        context.payload.header.requests_hash = Some(compute_requests_hash(&requests));
+    }
+    context.requests = requests;
     let account_updates = get_state_transitions(context.evm_state);
     // Note: This is commented because it is still being used in development.
     // dbg!(&account_updates);
@@ -14,6 +14,7 @@ mod blockchain_integration_test {
         types::{Block, BlockHeader},
         H160, H256,
     };
+    use ethrex_rlp::decode::RLPDecode;
     use ethrex_storage::{EngineType, Store};
 
     #[test]
@@ -230,6 +231,52 @@ mod blockchain_integration_test {
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_b);
     }
 
+    #[test]
+    fn reorged_transactions_return_to_the_mempool() {
+        let store = test_store();
+        // Import the chain up to its first block with transactions
+        let mut buf =
+            std::fs::read("../../test_data/chain.rlp").expect("Failed to read chain file");
+        let block_with_txs = loop {
+            let (block, rest) = Block::decode_unfinished(&buf).expect("Failed to decode block");
+            buf = rest.to_vec();
+            add_block(&block, &store).unwrap();
+            apply_fork_choice(&store, block.hash(), H256::zero(), H256::zero()).unwrap();
+            if !block.body.transactions.is_empty() {
+                break block;
+            }
+        };
+        let tx_hashes: Vec<_> = block_with_txs
+            .body
+            .transactions
+            .iter()
+            .map(|tx| tx.compute_hash())
+            .collect();
+
+        // Build a longer branch with empty blocks that doesn't include the block with transactions
+        let parent = store
+            .get_block_header_by_hash(block_with_txs.header.parent_hash)
+            .unwrap()
+            .unwrap();
+        let block_a = new_block(&store, &parent);
+        add_block(&block_a, &store).unwrap();
+        let block_b = new_block(&store, &block_a.header);
+        add_block(&block_b, &store).unwrap();
+        assert!(block_b.body.transactions.is_empty());
+
+        // The transactions of the abandoned block are added back to the pool
+        apply_fork_choice(&store, block_b.hash(), H256::zero(), H256::zero()).unwrap();
+        for hash in tx_hashes.iter() {
+            assert!(store.get_transaction_from_pool(*hash).unwrap().is_some());
+        }
+
+        // And removed once the block that includes them is canonical again
+        apply_fork_choice(&store, block_with_txs.hash(), H256::zero(), H256::zero()).unwrap();
+        for hash in tx_hashes.iter() {
+            assert!(store.get_transaction_from_pool(*hash).unwrap().is_none());
+        }
+    }
+
     fn new_block(store: &Store, parent: &BlockHeader) -> Block {
         let args = BuildPayloadArgs {
             parent: parent.compute_block_hash(),
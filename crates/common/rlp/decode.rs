@@ -360,6 +360,31 @@ impl<T1: RLPDecode, T2: RLPDecode, T3: RLPDecode, T4: RLPDecode> RLPDecode for (
     }
 }
 
+impl<T1: RLPDecode, T2: RLPDecode, T3: RLPDecode, T4: RLPDecode, T5: RLPDecode> RLPDecode
+    for (T1, T2, T3, T4, T5)
+{
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        if rlp.is_empty() {
+            return Err(RLPDecodeError::InvalidLength);
+        }
+        let (is_list, payload, input_rest) = decode_rlp_item(rlp)?;
+        if !is_list {
+            return Err(RLPDecodeError::MalformedData);
+        }
+        let (first, first_rest) = T1::decode_unfinished(payload)?;
+        let (second, second_rest) = T2::decode_unfinished(first_rest)?;
+        let (third, third_rest) = T3::decode_unfinished(second_rest)?;
+        let (fourth, fourth_rest) = T4::decode_unfinished(third_rest)?;
+        let (fifth, fifth_rest) = T5::decode_unfinished(fourth_rest)?;
+        // check that there is no more data to decode after the fifth element.
+        if !fifth_rest.is_empty() {
+            return Err(RLPDecodeError::MalformedData);
+        }
+
+        Ok(((first, second, third, fourth, fifth), input_rest))
+    }
+}
+
 /// Decodes an RLP item from a slice of bytes.
 /// It returns a 3-element tuple with the following elements:
 /// - A boolean indicating if the item is a list or not.
@@ -796,6 +821,25 @@ mod tests {
         assert_eq!(decoded, expected);
     }
 
+    #[test]
+    fn test_decode_tuples_5_elements() {
+        let rlp = vec![
+            RLP_EMPTY_LIST + 8,
+            0x01,
+            0x02,
+            0x83,
+            b'c',
+            b'a',
+            b't',
+            0x03,
+            0x01,
+        ];
+        let decoded = <(u8, u8, String, u8, bool)>::decode(&rlp).unwrap();
+        let expected = (1, 2, "cat".to_string(), 3, true);
+        assert_eq!(decoded, expected);
+        assert_eq!(crate::encode::RLPEncode::encode_to_vec(&expected), rlp);
+    }
+
     #[test]
     fn test_decode_list_as_string() {
         // [1, 2, 3, 4] != 0x01020304
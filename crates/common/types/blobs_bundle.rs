@@ -26,6 +26,9 @@ pub type Blob = [u8; BYTES_PER_BLOB];
 pub type Commitment = Bytes48;
 pub type Proof = Bytes48;
 
+/// Maximum amount of data that fits in a blob, as the first byte of every 32-bytes chunk is left empty
+pub const MAX_BLOB_DATA_SIZE: usize = BYTES_PER_BLOB * 31 / 32;
+
 #[cfg(feature = "c-kzg")]
 lazy_static! {
     static ref KZG_SETTINGS: &'static KzgSettings = ethereum_kzg_settings();
@@ -47,7 +50,7 @@ pub fn blob_from_bytes(bytes: Bytes) -> Result<Blob, BlobsBundleError> {
     // This functions moved from `l2/utils/eth_client/transaction.rs`
     // We set the first byte of every 32-bytes chunk to 0x00
     // so it's always under the field module.
-    if bytes.len() > BYTES_PER_BLOB * 31 / 32 {
+    if bytes.len() > MAX_BLOB_DATA_SIZE {
         return Err(BlobsBundleError::BlobDataInvalidBytesLength);
     }
 
@@ -63,7 +66,27 @@ pub fn blob_from_bytes(bytes: Bytes) -> Result<Blob, BlobsBundleError> {
     Ok(buf)
 }
 
-fn kzg_commitment_to_versioned_hash(data: &Commitment) -> H256 {
+/// Splits data that may not fit in a single blob across as many blobs as needed
+/// Every blob but the last one is filled up to `MAX_BLOB_DATA_SIZE`, so the original data
+/// can be recovered by concatenating the output of `bytes_from_blob` for each blob
+pub fn blobs_from_bytes(bytes: &[u8]) -> Result<Vec<Blob>, BlobsBundleError> {
+    let mut blobs = Vec::new();
+    for chunk in bytes.chunks(MAX_BLOB_DATA_SIZE) {
+        blobs.push(blob_from_bytes(Bytes::copy_from_slice(chunk))?);
+    }
+    Ok(blobs)
+}
+
+/// Inverse of `blob_from_bytes`: drops the leading zero byte of every 32-bytes chunk
+/// The returned data keeps the blob's zero padding, so its consumer must know where its content ends
+pub fn bytes_from_blob(blob: &Blob) -> Bytes {
+    blob.chunks(32)
+        .flat_map(|chunk| chunk.iter().skip(1))
+        .copied()
+        .collect()
+}
+
+pub fn kzg_commitment_to_versioned_hash(data: &Commitment) -> H256 {
     use k256::sha2::Digest;
     let mut versioned_hash: [u8; 32] = k256::sha2::Sha256::digest(data).into();
     versioned_hash[0] = VERSIONED_HASH_VERSION_KZG;
@@ -269,6 +292,32 @@ mod tests {
 
         assert!(matches!(blobs_bundle.validate(&tx), Ok(())));
     }
+
+    #[test]
+    fn bytes_from_blob_returns_the_original_data() {
+        let data: Vec<u8> = (0..100).collect();
+        let blob = blobs_bundle::blob_from_bytes(data.clone().into()).unwrap();
+        let bytes = blobs_bundle::bytes_from_blob(&blob);
+        assert_eq!(bytes.len(), BYTES_PER_BLOB * 31 / 32);
+        assert_eq!(&bytes[..data.len()], data.as_slice());
+        assert!(bytes[data.len()..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn blobs_from_bytes_splits_data_across_blobs() {
+        let data: Vec<u8> = (0..blobs_bundle::MAX_BLOB_DATA_SIZE + 100)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let blobs = blobs_bundle::blobs_from_bytes(&data).unwrap();
+        assert_eq!(blobs.len(), 2);
+        let bytes: Vec<u8> = blobs
+            .iter()
+            .flat_map(blobs_bundle::bytes_from_blob)
+            .collect();
+        assert_eq!(&bytes[..data.len()], data.as_slice());
+        assert!(bytes[data.len()..].iter().all(|byte| *byte == 0));
+    }
+
     #[test]
     fn transaction_with_invalid_proofs_should_fail() {
         // blob data taken from: https://etherscan.io/tx/0x02a623925c05c540a7633ffa4eb78474df826497faa81035c4168695656801a2#blobs, but with 0 size blobs
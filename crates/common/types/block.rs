@@ -1,6 +1,7 @@
 use super::{
-    BASE_FEE_MAX_CHANGE_DENOMINATOR, BLOB_BASE_FEE_UPDATE_FRACTION, ELASTICITY_MULTIPLIER,
-    GAS_LIMIT_ADJUSTMENT_FACTOR, GAS_LIMIT_MINIMUM, INITIAL_BASE_FEE, MIN_BASE_FEE_PER_BLOB_GAS,
+    BASE_FEE_MAX_CHANGE_DENOMINATOR, ELASTICITY_MULTIPLIER, GAS_LIMIT_ADJUSTMENT_FACTOR,
+    GAS_LIMIT_MINIMUM, INITIAL_BASE_FEE, MIN_BASE_FEE_PER_BLOB_GAS,
+    TARGET_BLOB_GAS_PER_BLOCK_CANCUN, TARGET_BLOB_GAS_PER_BLOCK_PRAGUE,
 };
 use crate::{
     types::{Receipt, Transaction},
@@ -125,6 +126,8 @@ pub struct BlockHeader {
     )]
     pub excess_blob_gas: Option<u64>,
     pub parent_beacon_block_root: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none", default = "Option::default")]
+    pub requests_hash: Option<H256>,
 }
 
 impl RLPEncode for BlockHeader {
@@ -150,6 +153,7 @@ impl RLPEncode for BlockHeader {
             .encode_optional_field(&self.blob_gas_used)
             .encode_optional_field(&self.excess_blob_gas)
             .encode_optional_field(&self.parent_beacon_block_root)
+            .encode_optional_field(&self.requests_hash)
             .finish();
     }
 }
@@ -178,6 +182,7 @@ impl RLPDecode for BlockHeader {
         let (blob_gas_used, decoder) = decoder.decode_optional_field();
         let (excess_blob_gas, decoder) = decoder.decode_optional_field();
         let (parent_beacon_block_root, decoder) = decoder.decode_optional_field();
+        let (requests_hash, decoder) = decoder.decode_optional_field();
 
         Ok((
             BlockHeader {
@@ -201,6 +206,7 @@ impl RLPDecode for BlockHeader {
                 blob_gas_used,
                 excess_blob_gas,
                 parent_beacon_block_root,
+                requests_hash,
             },
             decoder.finish()?,
         ))
@@ -341,11 +347,15 @@ fn check_gas_limit(gas_limit: u64, parent_gas_limit: u64) -> bool {
 }
 
 // Calculates the base fee per blob gas for the current block based on it's parent excess blob gas
-pub fn calculate_base_fee_per_blob_gas(parent_excess_blob_gas: u64) -> u64 {
+// The update fraction depends on the fork the block belongs to, see [`Fork::blob_base_fee_update_fraction`](super::Fork::blob_base_fee_update_fraction)
+pub fn calculate_base_fee_per_blob_gas(
+    parent_excess_blob_gas: u64,
+    blob_base_fee_update_fraction: u64,
+) -> u64 {
     fake_exponential(
         MIN_BASE_FEE_PER_BLOB_GAS,
         parent_excess_blob_gas,
-        BLOB_BASE_FEE_UPDATE_FRACTION,
+        blob_base_fee_update_fraction,
     )
 }
 
@@ -475,11 +485,16 @@ pub enum InvalidBlockHeaderError {
     ExcessBlobGasIncorrect,
     #[error("Parent beacon block root is not present")]
     ParentBeaconBlockRootNotPresent,
+    // Prague fork errors
+    #[error("Requests hash is not present")]
+    RequestsHashNotPresent,
     // Other fork errors
     #[error("Excess blob gas is present")]
     ExcessBlobGasPresent,
     #[error("Blob gas used is present")]
     BlobGasUsedPresent,
+    #[error("Requests hash is present")]
+    RequestsHashPresent,
 }
 
 /// Validates that the header fields are correct in reference to the parent_header
@@ -543,13 +558,39 @@ pub fn validate_cancun_header_fields(
     header: &BlockHeader,
     parent_header: &BlockHeader,
 ) -> Result<(), InvalidBlockHeaderError> {
-    if header.excess_blob_gas.is_none() {
-        return Err(InvalidBlockHeaderError::ExcessBlobGasNotPresent);
+    validate_blob_header_fields(header, parent_header, TARGET_BLOB_GAS_PER_BLOCK_CANCUN)?;
+    if header.requests_hash.is_some() {
+        return Err(InvalidBlockHeaderError::RequestsHashPresent);
+    }
+    Ok(())
+}
+
+/// Validates the cancun header fields using the Prague blob target ([EIP-7691](https://eips.ethereum.org/EIPS/eip-7691))
+/// and that requests_hash is present in the header ([EIP-7685](https://eips.ethereum.org/EIPS/eip-7685)).
+/// The requests_hash value can only be checked after execution.
+pub fn validate_prague_header_fields(
+    header: &BlockHeader,
+    parent_header: &BlockHeader,
+) -> Result<(), InvalidBlockHeaderError> {
+    validate_blob_header_fields(header, parent_header, TARGET_BLOB_GAS_PER_BLOCK_PRAGUE)?;
+    if header.requests_hash.is_none() {
+        return Err(InvalidBlockHeaderError::RequestsHashNotPresent);
     }
+    Ok(())
+}
+
+fn validate_blob_header_fields(
+    header: &BlockHeader,
+    parent_header: &BlockHeader,
+    target_blob_gas_per_block: u64,
+) -> Result<(), InvalidBlockHeaderError> {
+    let Some(excess_blob_gas) = header.excess_blob_gas else {
+        return Err(InvalidBlockHeaderError::ExcessBlobGasNotPresent);
+    };
     if header.blob_gas_used.is_none() {
         return Err(InvalidBlockHeaderError::BlobGasUsedNotPresent);
     }
-    if header.excess_blob_gas.unwrap() != calc_excess_blob_gas(parent_header) {
+    if excess_blob_gas != calc_excess_blob_gas(parent_header, target_blob_gas_per_block) {
         return Err(InvalidBlockHeaderError::ExcessBlobGasIncorrect);
     }
     if header.parent_beacon_block_root.is_none() {
@@ -569,19 +610,18 @@ pub fn validate_no_cancun_header_fields(
     if header.blob_gas_used.is_some() {
         return Err(InvalidBlockHeaderError::BlobGasUsedPresent);
     }
+    if header.requests_hash.is_some() {
+        return Err(InvalidBlockHeaderError::RequestsHashPresent);
+    }
     Ok(())
 }
 
-fn calc_excess_blob_gas(parent_header: &BlockHeader) -> u64 {
+fn calc_excess_blob_gas(parent_header: &BlockHeader, target_blob_gas_per_block: u64) -> u64 {
     let parent_excess_blob_gas = parent_header.excess_blob_gas.unwrap_or_default();
     let parent_blob_gas_used = parent_header.blob_gas_used.unwrap_or_default();
     let parent_blob_gas = parent_excess_blob_gas + parent_blob_gas_used;
 
-    if parent_blob_gas < 393216_u64 {
-        0u64
-    } else {
-        parent_blob_gas - 393216_u64
-    }
+    parent_blob_gas.saturating_sub(target_blob_gas_per_block)
 }
 
 #[cfg(test)]
@@ -660,6 +700,7 @@ mod test {
             blob_gas_used: Some(0x00),
             excess_blob_gas: Some(0x00),
             parent_beacon_block_root: Some(H256::zero()),
+            requests_hash: None,
         };
         let block = BlockHeader {
             parent_hash: H256::from_str(
@@ -702,6 +743,7 @@ mod test {
             blob_gas_used: Some(0x00),
             excess_blob_gas: Some(0x00),
             parent_beacon_block_root: Some(H256::zero()),
+            requests_hash: None,
         };
         assert!(validate_block_header(&block, &parent_block).is_ok())
     }
@@ -8,6 +8,20 @@ pub const INITIAL_BASE_FEE: u64 = 1_000_000_000; //Initial base fee as defined i
 pub const MIN_BASE_FEE_PER_BLOB_GAS: u64 = 1; // Defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
 pub const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3338477; // Defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
 pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01; // Defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE: u64 = 5007716; // Defined in [EIP-7691](https://eips.ethereum.org/EIPS/eip-7691)
+
+// Blob schedule related
+// Defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844) and updated in [EIP-7691](https://eips.ethereum.org/EIPS/eip-7691)
+pub const TARGET_BLOB_GAS_PER_BLOCK_CANCUN: u64 = 393216;
+pub const MAX_BLOBS_PER_BLOCK_CANCUN: u64 = 6;
+pub const TARGET_BLOB_GAS_PER_BLOCK_PRAGUE: u64 = 786432;
+pub const MAX_BLOBS_PER_BLOCK_PRAGUE: u64 = 9;
+
+// Request types
+// Defined in [EIP-6110](https://eips.ethereum.org/EIPS/eip-6110), [EIP-7002](https://eips.ethereum.org/EIPS/eip-7002) and [EIP-7251](https://eips.ethereum.org/EIPS/eip-7251)
+pub const DEPOSIT_REQUEST_TYPE: u8 = 0x00;
+pub const WITHDRAWAL_REQUEST_TYPE: u8 = 0x01;
+pub const EL_CONSOLIDATION_REQUEST_TYPE: u8 = 0x02;
 
 // Blob size related
 // Defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
@@ -10,7 +10,11 @@ use ethereum_types::H32;
 
 use super::{BlockHash, BlockNumber, ChainConfig};
 
-#[derive(Debug, PartialEq)]
+/// Fork activations above this value are timestamps instead of block numbers,
+/// it is the timestamp of the mainnet genesis block, as in other clients
+const TIMESTAMP_THRESHOLD: u64 = 1438269973;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ForkId {
     fork_hash: H32,
     fork_next: BlockNumber,
@@ -48,6 +52,77 @@ impl ForkId {
             fork_next,
         }
     }
+
+    /// Checks if a remote fork id is compatible with the local chain, following the validation rules of
+    /// [EIP-2124](https://eips.ethereum.org/EIPS/eip-2124#validation-rules)
+    pub fn is_compatible(
+        &self,
+        chain_config: ChainConfig,
+        genesis_hash: BlockHash,
+        head_timestamp: u64,
+        head_block_number: u64,
+    ) -> bool {
+        let (block_number_based_forks, timestamp_based_forks) = chain_config.gather_forks();
+        let forks: Vec<(u64, bool)> = dedup_forks(block_number_based_forks)
+            .into_iter()
+            .map(|activation| (activation, activation <= head_block_number))
+            .chain(
+                dedup_forks(timestamp_based_forks)
+                    .into_iter()
+                    .map(|activation| (activation, activation <= head_timestamp)),
+            )
+            .collect();
+        let is_passed = |activation: u64| {
+            if activation >= TIMESTAMP_THRESHOLD {
+                activation <= head_timestamp
+            } else {
+                activation <= head_block_number
+            }
+        };
+
+        let mut hasher = Hasher::new();
+        hasher.update(genesis_hash.as_bytes());
+        let mut forks = forks.into_iter();
+        for (activation, passed) in forks.by_ref() {
+            let fork_hash = H32::from_slice(&hasher.clone().finalize().to_be_bytes());
+            if !passed {
+                if self.fork_hash == fork_hash {
+                    // Same fork, unless the remote announces a fork that we have already passed
+                    return self.fork_next == 0 || !is_passed(self.fork_next);
+                }
+                // The remote can be ahead of us, in which case its fork hash includes some of our future forks
+                hasher.update(&activation.to_be_bytes());
+                for (activation, _) in forks {
+                    let fork_hash = H32::from_slice(&hasher.clone().finalize().to_be_bytes());
+                    if self.fork_hash == fork_hash {
+                        return true;
+                    }
+                    hasher.update(&activation.to_be_bytes());
+                }
+                return self.fork_hash == H32::from_slice(&hasher.finalize().to_be_bytes());
+            }
+            if self.fork_hash == fork_hash {
+                // The remote is behind us, it must be aware of the fork that follows its current one
+                return self.fork_next == activation;
+            }
+            hasher.update(&activation.to_be_bytes());
+        }
+        // All the known forks are passed
+        let fork_hash = H32::from_slice(&hasher.finalize().to_be_bytes());
+        self.fork_hash == fork_hash && (self.fork_next == 0 || !is_passed(self.fork_next))
+    }
+}
+
+/// Returns the scheduled forks in activation order, skipping the ones active at genesis and
+/// those that share their activation with the previous fork
+fn dedup_forks(forks: Vec<Option<u64>>) -> Vec<u64> {
+    let mut activations: Vec<u64> = vec![];
+    for activation in forks.into_iter().flatten() {
+        if activation != 0 && activations.last() != Some(&activation) {
+            activations.push(activation);
+        }
+    }
+    activations
 }
 
 fn update_checksum(forks: Vec<Option<u64>>, hasher: &mut Hasher, head: u64) -> u64 {
@@ -126,4 +201,102 @@ mod tests {
         let expected = hex!("ce84ffffffff88ffffffffffffffff");
         assert_eq!(fork.encode_to_vec(), expected);
     }
+
+    fn mainnet_config() -> ChainConfig {
+        ChainConfig {
+            chain_id: 1,
+            homestead_block: Some(1150000),
+            dao_fork_block: Some(1920000),
+            dao_fork_support: true,
+            eip150_block: Some(2463000),
+            eip155_block: Some(2675000),
+            eip158_block: Some(2675000),
+            byzantium_block: Some(4370000),
+            constantinople_block: Some(7280000),
+            petersburg_block: Some(7280000),
+            istanbul_block: Some(9069000),
+            muir_glacier_block: Some(9200000),
+            berlin_block: Some(12244000),
+            london_block: Some(12965000),
+            arrow_glacier_block: Some(13773000),
+            gray_glacier_block: Some(15050000),
+            shanghai_time: Some(1681338455),
+            cancun_time: Some(1710338135),
+            ..Default::default()
+        }
+    }
+
+    fn mainnet_genesis_hash() -> BlockHash {
+        BlockHash::from_str("0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3")
+            .unwrap()
+    }
+
+    fn fork_id(fork_hash: &str, fork_next: u64) -> ForkId {
+        ForkId {
+            fork_hash: H32::from_str(fork_hash).unwrap(),
+            fork_next,
+        }
+    }
+
+    #[test]
+    fn mainnet_fork_ids() {
+        let config = mainnet_config();
+        let genesis_hash = mainnet_genesis_hash();
+        assert_eq!(
+            ForkId::new(config, genesis_hash, 0, 0),
+            fork_id("0xfc64ec04", 1150000)
+        );
+        assert_eq!(
+            ForkId::new(config, genesis_hash, 1681338454, 16000000),
+            fork_id("0xf0afd0e3", 1681338455)
+        );
+        assert_eq!(
+            ForkId::new(config, genesis_hash, 1710338135, 20000000),
+            fork_id("0x9f3d2254", 0)
+        );
+    }
+
+    #[test]
+    fn validate_remote_fork_ids() {
+        let config = mainnet_config();
+        let genesis_hash = mainnet_genesis_hash();
+        // Local node is on Gray Glacier, before Shanghai
+        let is_compatible =
+            |remote: ForkId| remote.is_compatible(config, genesis_hash, 1681338454, 16000000);
+
+        // Same fork, aware of the next one
+        assert!(is_compatible(fork_id("0xf0afd0e3", 1681338455)));
+        // Same fork, not aware of the next one yet
+        assert!(is_compatible(fork_id("0xf0afd0e3", 0)));
+        // Same fork, announcing a fork that we have already passed
+        assert!(!is_compatible(fork_id("0xf0afd0e3", 15500000)));
+        // Remote on the previous fork, aware of the fork we are in
+        assert!(is_compatible(fork_id("0x20c327fc", 15050000)));
+        // Remote on the previous fork, not aware of the fork we are in
+        assert!(!is_compatible(fork_id("0x20c327fc", 0)));
+        // Remote ahead of us, on Shanghai and Cancun
+        assert!(is_compatible(fork_id("0xdce96c2d", 1710338135)));
+        assert!(is_compatible(fork_id("0x9f3d2254", 0)));
+        // Remote on a different chain
+        assert!(!is_compatible(fork_id("0xdeadbeef", 0)));
+    }
+
+    #[test]
+    fn validate_remote_fork_ids_after_last_fork() {
+        let config = mainnet_config();
+        let genesis_hash = mainnet_genesis_hash();
+        // Local node is on Cancun, the last known fork
+        let is_compatible =
+            |remote: ForkId| remote.is_compatible(config, genesis_hash, 1720000000, 20000000);
+
+        assert!(is_compatible(fork_id("0x9f3d2254", 0)));
+        // Remote knows about a future fork we are not aware of
+        assert!(is_compatible(fork_id("0x9f3d2254", 1800000000)));
+        // Remote on Shanghai, aware of Cancun
+        assert!(is_compatible(fork_id("0xdce96c2d", 1710338135)));
+        // Remote on Shanghai, not aware of Cancun
+        assert!(!is_compatible(fork_id("0xdce96c2d", 0)));
+        // Remote on a fork we don't know of
+        assert!(!is_compatible(fork_id("0xc376cf8b", 0)));
+    }
 }
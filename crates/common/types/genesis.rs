@@ -8,8 +8,11 @@ use std::collections::HashMap;
 use ethrex_rlp::encode::RLPEncode;
 
 use super::{
-    compute_receipts_root, compute_transactions_root, compute_withdrawals_root, AccountState,
-    Block, BlockBody, BlockHeader, BlockNumber, DEFAULT_OMMERS_HASH, INITIAL_BASE_FEE,
+    compute_receipts_root, compute_requests_hash, compute_transactions_root,
+    compute_withdrawals_root, AccountState, Block, BlockBody, BlockHeader, BlockNumber,
+    BLOB_BASE_FEE_UPDATE_FRACTION, BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE, DEFAULT_OMMERS_HASH,
+    INITIAL_BASE_FEE, MAX_BLOBS_PER_BLOCK_CANCUN, MAX_BLOBS_PER_BLOCK_PRAGUE,
+    TARGET_BLOB_GAS_PER_BLOCK_CANCUN, TARGET_BLOB_GAS_PER_BLOCK_PRAGUE,
 };
 
 #[allow(unused)]
@@ -85,13 +88,42 @@ pub struct ChainConfig {
     /// Network has already passed the terminal total difficult
     #[serde(default)]
     pub terminal_total_difficulty_passed: bool,
+
+    /// Address of the beacon chain deposit contract, whose logs are parsed as
+    /// deposit requests from Prague onwards ([EIP-6110](https://eips.ethereum.org/EIPS/eip-6110))
+    #[serde(default)]
+    pub deposit_contract_address: Address,
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum Fork {
     Paris = 0,
     Shanghai = 1,
     Cancun = 2,
+    Prague = 3,
+}
+
+impl Fork {
+    pub fn target_blob_gas_per_block(&self) -> u64 {
+        match self {
+            Fork::Prague => TARGET_BLOB_GAS_PER_BLOCK_PRAGUE,
+            _ => TARGET_BLOB_GAS_PER_BLOCK_CANCUN,
+        }
+    }
+
+    pub fn max_blobs_per_block(&self) -> u64 {
+        match self {
+            Fork::Prague => MAX_BLOBS_PER_BLOCK_PRAGUE,
+            _ => MAX_BLOBS_PER_BLOCK_CANCUN,
+        }
+    }
+
+    pub fn blob_base_fee_update_fraction(&self) -> u64 {
+        match self {
+            Fork::Prague => BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE,
+            _ => BLOB_BASE_FEE_UPDATE_FRACTION,
+        }
+    }
 }
 
 impl ChainConfig {
@@ -104,6 +136,10 @@ impl ChainConfig {
         self.cancun_time.is_some_and(|time| time <= block_timestamp)
     }
 
+    pub fn is_prague_activated(&self, block_timestamp: u64) -> bool {
+        self.prague_time.is_some_and(|time| time <= block_timestamp)
+    }
+
     pub fn is_istanbul_activated(&self, block_number: BlockNumber) -> bool {
         self.istanbul_block.is_some_and(|num| num <= block_number)
     }
@@ -113,7 +149,9 @@ impl ChainConfig {
     }
 
     pub fn get_fork(&self, block_timestamp: u64) -> Fork {
-        if self.is_cancun_activated(block_timestamp) {
+        if self.is_prague_activated(block_timestamp) {
+            Fork::Prague
+        } else if self.is_cancun_activated(block_timestamp) {
             Fork::Cancun
         } else if self.is_shanghai_activated(block_timestamp) {
             Fork::Shanghai
@@ -212,6 +250,10 @@ impl Genesis {
                 .config
                 .is_cancun_activated(self.timestamp)
                 .then_some(H256::zero()),
+            requests_hash: self
+                .config
+                .is_prague_activated(self.timestamp)
+                .then_some(compute_requests_hash(&[])),
         }
     }
 
@@ -5,6 +5,7 @@ mod constants;
 mod fork_id;
 mod genesis;
 mod receipt;
+mod requests;
 pub mod transaction;
 
 pub use account::*;
@@ -14,4 +15,5 @@ pub use constants::*;
 pub use fork_id::*;
 pub use genesis::*;
 pub use receipt::*;
+pub use requests::*;
 pub use transaction::*;
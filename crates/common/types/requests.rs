@@ -0,0 +1,230 @@
+use bytes::Bytes;
+use ethereum_types::{Address, H256};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode, error::RLPDecodeError};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    Receipt, DEPOSIT_REQUEST_TYPE, EL_CONSOLIDATION_REQUEST_TYPE, WITHDRAWAL_REQUEST_TYPE,
+};
+
+lazy_static! {
+    // keccak256("DepositEvent(bytes,bytes,bytes,bytes,bytes)")
+    static ref DEPOSIT_TOPIC: H256 = H256::from_slice(
+        &hex::decode("649bbc62d0e31342afea4e5cd82d4049e7e1ee912fc0889aa790803be39038c5").unwrap()
+    );
+}
+
+// Layout of the ABI encoded `DepositEvent` log data as emitted by the deposit contract
+const DEPOSIT_EVENT_DATA_LENGTH: usize = 576;
+const PUBKEY_OFFSET: usize = 192;
+const PUBKEY_LENGTH: usize = 48;
+const WITHDRAWAL_CREDENTIALS_OFFSET: usize = 288;
+const WITHDRAWAL_CREDENTIALS_LENGTH: usize = 32;
+const AMOUNT_OFFSET: usize = 352;
+const AMOUNT_LENGTH: usize = 8;
+const SIGNATURE_OFFSET: usize = 416;
+const SIGNATURE_LENGTH: usize = 96;
+const INDEX_OFFSET: usize = 544;
+const INDEX_LENGTH: usize = 8;
+
+/// Execution layer request as defined in [EIP-7685](https://eips.ethereum.org/EIPS/eip-7685)
+/// Encoded as `request_type ++ request_data`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct EncodedRequests(#[serde(with = "crate::serde_utils::bytes")] pub Bytes);
+
+impl EncodedRequests {
+    /// Returns true if the request carries no data besides its type
+    pub fn is_empty(&self) -> bool {
+        self.0.len() <= 1
+    }
+
+    pub fn request_type(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+}
+
+impl RLPEncode for EncodedRequests {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        self.0.encode(buf)
+    }
+}
+
+impl RLPDecode for EncodedRequests {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let (bytes, rest) = Bytes::decode_unfinished(rlp)?;
+        Ok((EncodedRequests(bytes), rest))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Requests {
+    Deposit(Vec<Deposit>),
+    Withdrawal(Bytes),
+    Consolidation(Bytes),
+}
+
+impl Requests {
+    pub fn encode(&self) -> EncodedRequests {
+        let mut bytes = Vec::new();
+        match self {
+            Requests::Deposit(deposits) => {
+                bytes.push(DEPOSIT_REQUEST_TYPE);
+                for deposit in deposits {
+                    bytes.extend(deposit.to_summary_bytes());
+                }
+            }
+            Requests::Withdrawal(data) => {
+                bytes.push(WITHDRAWAL_REQUEST_TYPE);
+                bytes.extend_from_slice(data);
+            }
+            Requests::Consolidation(data) => {
+                bytes.push(EL_CONSOLIDATION_REQUEST_TYPE);
+                bytes.extend_from_slice(data);
+            }
+        }
+        EncodedRequests(Bytes::from(bytes))
+    }
+
+    /// Collects the deposit requests from the `DepositEvent` logs emitted by the deposit contract
+    /// As defined in [EIP-6110](https://eips.ethereum.org/EIPS/eip-6110)
+    pub fn from_deposit_receipts(
+        deposit_contract_address: Address,
+        receipts: &[Receipt],
+    ) -> Option<Requests> {
+        let mut deposits = vec![];
+        for log in receipts
+            .iter()
+            .filter(|receipt| receipt.succeeded)
+            .flat_map(|receipt| receipt.logs.iter())
+            .filter(|log| {
+                log.address == deposit_contract_address
+                    && log.topics.first() == Some(&*DEPOSIT_TOPIC)
+            })
+        {
+            deposits.push(Deposit::from_abi_byte_array(&log.data)?);
+        }
+        Some(Requests::Deposit(deposits))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deposit {
+    pub pubkey: [u8; PUBKEY_LENGTH],
+    pub withdrawal_credentials: H256,
+    pub amount: u64,
+    pub signature: [u8; SIGNATURE_LENGTH],
+    pub index: u64,
+}
+
+impl Deposit {
+    /// Parses a deposit from the ABI encoded data of a `DepositEvent` log
+    /// Returns None if the data doesn't match the layout of the deposit contract event
+    pub fn from_abi_byte_array(data: &[u8]) -> Option<Deposit> {
+        if data.len() != DEPOSIT_EVENT_DATA_LENGTH {
+            return None;
+        }
+        let pubkey = data
+            .get(PUBKEY_OFFSET..PUBKEY_OFFSET + PUBKEY_LENGTH)?
+            .try_into()
+            .ok()?;
+        let withdrawal_credentials = H256::from_slice(data.get(
+            WITHDRAWAL_CREDENTIALS_OFFSET
+                ..WITHDRAWAL_CREDENTIALS_OFFSET + WITHDRAWAL_CREDENTIALS_LENGTH,
+        )?);
+        // Amount and index are little endian encoded as in the beacon chain
+        let amount = u64::from_le_bytes(
+            data.get(AMOUNT_OFFSET..AMOUNT_OFFSET + AMOUNT_LENGTH)?
+                .try_into()
+                .ok()?,
+        );
+        let signature = data
+            .get(SIGNATURE_OFFSET..SIGNATURE_OFFSET + SIGNATURE_LENGTH)?
+            .try_into()
+            .ok()?;
+        let index = u64::from_le_bytes(
+            data.get(INDEX_OFFSET..INDEX_OFFSET + INDEX_LENGTH)?
+                .try_into()
+                .ok()?,
+        );
+        Some(Deposit {
+            pubkey,
+            withdrawal_credentials,
+            amount,
+            signature,
+            index,
+        })
+    }
+
+    /// Returns the deposit request encoding: `pubkey ++ withdrawal_credentials ++ amount ++ signature ++ index`
+    pub fn to_summary_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            PUBKEY_LENGTH
+                + WITHDRAWAL_CREDENTIALS_LENGTH
+                + AMOUNT_LENGTH
+                + SIGNATURE_LENGTH
+                + INDEX_LENGTH,
+        );
+        bytes.extend_from_slice(&self.pubkey);
+        bytes.extend_from_slice(self.withdrawal_credentials.as_bytes());
+        bytes.extend_from_slice(&self.amount.to_le_bytes());
+        bytes.extend_from_slice(&self.signature);
+        bytes.extend_from_slice(&self.index.to_le_bytes());
+        bytes
+    }
+}
+
+/// Computes the commitment to the block's requests
+/// `sha256(sha256(requests_0) ++ sha256(requests_1) ++ ...)`, skipping requests with empty data
+/// As defined in [EIP-7685](https://eips.ethereum.org/EIPS/eip-7685)
+pub fn compute_requests_hash(requests: &[EncodedRequests]) -> H256 {
+    let mut hasher = Sha256::new();
+    for request in requests.iter().filter(|request| !request.is_empty()) {
+        hasher.update(Sha256::digest(&request.0));
+    }
+    H256::from_slice(&hasher.finalize())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn requests_hash_of_no_requests() {
+        let requests = vec![
+            Requests::Deposit(vec![]).encode(),
+            Requests::Withdrawal(Bytes::new()).encode(),
+            Requests::Consolidation(Bytes::new()).encode(),
+        ];
+        // sha256 of the empty string
+        assert_eq!(
+            compute_requests_hash(&requests),
+            H256::from(hex!(
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            ))
+        );
+    }
+
+    #[test]
+    fn decode_deposit_event_data() {
+        let mut data = vec![0u8; DEPOSIT_EVENT_DATA_LENGTH];
+        data[PUBKEY_OFFSET..PUBKEY_OFFSET + PUBKEY_LENGTH].fill(0xaa);
+        data[WITHDRAWAL_CREDENTIALS_OFFSET
+            ..WITHDRAWAL_CREDENTIALS_OFFSET + WITHDRAWAL_CREDENTIALS_LENGTH]
+            .fill(0xbb);
+        data[AMOUNT_OFFSET..AMOUNT_OFFSET + AMOUNT_LENGTH]
+            .copy_from_slice(&32_000_000_000_u64.to_le_bytes());
+        data[SIGNATURE_OFFSET..SIGNATURE_OFFSET + SIGNATURE_LENGTH].fill(0xcc);
+        data[INDEX_OFFSET..INDEX_OFFSET + INDEX_LENGTH].copy_from_slice(&7_u64.to_le_bytes());
+
+        let deposit = Deposit::from_abi_byte_array(&data).unwrap();
+        assert_eq!(deposit.amount, 32_000_000_000);
+        assert_eq!(deposit.index, 7);
+        let summary = deposit.to_summary_bytes();
+        assert_eq!(summary.len(), 192);
+        assert_eq!(&summary[..PUBKEY_LENGTH], &[0xaa; PUBKEY_LENGTH]);
+        assert!(Deposit::from_abi_byte_array(&data[1..]).is_none());
+    }
+}
@@ -9,9 +9,10 @@ use serde::{ser::SerializeStruct, Deserialize, Serialize};
 pub use serde_impl::{AccessListEntry, GenericTransaction};
 use sha3::{Digest, Keccak256};
 
+use crate::types::BlobsBundle;
 use ethrex_rlp::{
-    constants::RLP_NULL,
-    decode::{get_rlp_bytes_item_payload, is_encoded_as_bytes, RLPDecode},
+    constants::{RLP_EMPTY_LIST, RLP_NULL},
+    decode::{decode_bytes, get_rlp_bytes_item_payload, is_encoded_as_bytes, RLPDecode},
     encode::{PayloadRLPEncode, RLPEncode},
     error::RLPDecodeError,
     structs::{Decoder, Encoder},
@@ -32,9 +33,31 @@ pub enum Transaction {
     EIP2930Transaction(EIP2930Transaction),
     EIP1559Transaction(EIP1559Transaction),
     EIP4844Transaction(EIP4844Transaction),
+    EIP7702Transaction(EIP7702Transaction),
     PrivilegedL2Transaction(PrivilegedL2Transaction),
 }
 
+/// A transaction in the format used to share it over the p2p network
+/// Differs from [Transaction] in that blob transactions are sent along with their blobs bundle
+/// https://github.com/ethereum/devp2p/blob/master/caps/eth.md#pooledtransactions-0x0a
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum P2PTransaction {
+    LegacyTransaction(LegacyTransaction),
+    EIP2930Transaction(EIP2930Transaction),
+    EIP1559Transaction(EIP1559Transaction),
+    EIP4844TransactionWithBlobs(WrappedEIP4844Transaction),
+    EIP7702Transaction(EIP7702Transaction),
+    PrivilegedL2Transaction(PrivilegedL2Transaction),
+}
+
+/// A blob transaction along with its blobs bundle (aka network representation)
+/// As defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844#networking)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WrappedEIP4844Transaction {
+    pub tx: EIP4844Transaction,
+    pub blobs_bundle: BlobsBundle,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct LegacyTransaction {
     pub nonce: u64,
@@ -99,6 +122,56 @@ pub struct EIP4844Transaction {
     pub signature_s: U256,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct EIP7702Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: u64,
+    pub max_fee_per_gas: u64,
+    pub gas_limit: u64,
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    pub access_list: Vec<(Address, Vec<H256>)>,
+    pub authorization_list: AuthorizationList,
+    pub signature_y_parity: bool,
+    pub signature_r: U256,
+    pub signature_s: U256,
+}
+
+pub type AuthorizationList = Vec<AuthorizationTuple>;
+
+/// Signed authorization used by set code transactions
+/// As defined in [EIP-7702](https://eips.ethereum.org/EIPS/eip-7702)
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizationTuple {
+    pub chain_id: U256,
+    pub address: Address,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    pub nonce: u64,
+    #[serde(deserialize_with = "deserialize_y_parity")]
+    pub y_parity: U256,
+    #[serde(rename = "r")]
+    pub r_signature: U256,
+    #[serde(rename = "s")]
+    pub s_signature: U256,
+}
+
+// Authorization signatures can only have a y_parity of 0 or 1, any other value makes the transaction invalid
+fn deserialize_y_parity<'de, D>(d: D) -> Result<U256, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let y_parity = U256::deserialize(d)?;
+    if y_parity > U256::one() {
+        return Err(serde::de::Error::custom(format!(
+            "Invalid authorization y_parity: {y_parity}"
+        )));
+    }
+    Ok(y_parity)
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct PrivilegedL2Transaction {
     pub chain_id: u64,
@@ -130,6 +203,7 @@ pub enum TxType {
     EIP2930 = 0x01,
     EIP1559 = 0x02,
     EIP4844 = 0x03,
+    EIP7702 = 0x04,
     // We take the same approach as Optimism to define the privileged tx prefix
     // https://github.com/ethereum-optimism/specs/blob/c6903a3b2cad575653e1f5ef472debb573d83805/specs/protocol/deposits.md#the-deposited-transaction-type
     Privileged = 0x7e,
@@ -142,6 +216,7 @@ impl From<TxType> for u8 {
             TxType::EIP2930 => 0x01,
             TxType::EIP1559 => 0x02,
             TxType::EIP4844 => 0x03,
+            TxType::EIP7702 => 0x04,
             TxType::Privileged => 0x7e,
         }
     }
@@ -167,6 +242,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(_) => TxType::EIP2930,
             Transaction::EIP1559Transaction(_) => TxType::EIP1559,
             Transaction::EIP4844Transaction(_) => TxType::EIP4844,
+            Transaction::EIP7702Transaction(_) => TxType::EIP7702,
             Transaction::PrivilegedL2Transaction(_) => TxType::Privileged,
         }
     }
@@ -189,6 +265,13 @@ impl Transaction {
                 );
                 Some(priority_fee_per_gas + base_fee_per_gas?)
             }
+            TxType::EIP7702 => {
+                let priority_fee_per_gas = min(
+                    self.max_priority_fee()?,
+                    self.max_fee_per_gas()? - base_fee_per_gas?,
+                );
+                Some(priority_fee_per_gas + base_fee_per_gas?)
+            }
             TxType::Privileged => Some(self.gas_price()),
         }
     }
@@ -199,6 +282,7 @@ impl Transaction {
             TxType::EIP2930 => self.gas_price(),
             TxType::EIP1559 => self.max_fee_per_gas()?,
             TxType::EIP4844 => self.max_fee_per_gas()?,
+            TxType::EIP7702 => self.max_fee_per_gas()?,
             TxType::Privileged => self.gas_price(),
         };
 
@@ -247,6 +331,9 @@ impl RLPDecode for Transaction {
                 // EIP4844
                 0x3 => EIP4844Transaction::decode_unfinished(tx_encoding)
                     .map(|(tx, rem)| (Transaction::EIP4844Transaction(tx), rem)),
+                // EIP7702
+                0x4 => EIP7702Transaction::decode_unfinished(tx_encoding)
+                    .map(|(tx, rem)| (Transaction::EIP7702Transaction(tx), rem)),
                 // PriviligedL2
                 0x7e => PrivilegedL2Transaction::decode_unfinished(tx_encoding)
                     .map(|(tx, rem)| (Transaction::PrivilegedL2Transaction(tx), rem)),
@@ -262,6 +349,69 @@ impl RLPDecode for Transaction {
     }
 }
 
+impl RLPEncode for P2PTransaction {
+    /// Transactions are encoded the same way as [Transaction]s:
+    /// A) Legacy transactions: rlp(LegacyTransaction)
+    /// B) Non legacy transactions: rlp(Bytes) where Bytes represents the canonical encoding for the transaction as a bytes object.
+    /// Checkout [P2PTransaction::encode_canonical] for more information
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        match self {
+            P2PTransaction::LegacyTransaction(t) => t.encode(buf),
+            tx => Bytes::from(tx.encode_canonical_to_vec()).encode(buf),
+        };
+    }
+}
+
+impl RLPDecode for P2PTransaction {
+    /// Transactions are encoded the same way as [Transaction]s:
+    /// A) Legacy transactions: rlp(LegacyTransaction)
+    /// B) Non legacy transactions: rlp(Bytes) where Bytes represents the canonical encoding for the transaction as a bytes object.
+    /// Checkout [P2PTransaction::decode_canonical] for more information
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        match rlp.first() {
+            // Non legacy transactions are encoded as bytes
+            Some(prefix) if *prefix < RLP_EMPTY_LIST => {
+                let (payload, rest) = decode_bytes(rlp)?;
+                Ok((P2PTransaction::decode_canonical(payload)?, rest))
+            }
+            _ => LegacyTransaction::decode_unfinished(rlp)
+                .map(|(tx, rest)| (P2PTransaction::LegacyTransaction(tx), rest)),
+        }
+    }
+}
+
+impl RLPEncode for WrappedEIP4844Transaction {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        let encoder = Encoder::new(buf);
+        encoder
+            .encode_field(&self.tx)
+            .encode_field(&self.blobs_bundle.blobs)
+            .encode_field(&self.blobs_bundle.commitments)
+            .encode_field(&self.blobs_bundle.proofs)
+            .finish();
+    }
+}
+
+impl RLPDecode for WrappedEIP4844Transaction {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(WrappedEIP4844Transaction, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (tx, decoder) = decoder.decode_field("tx")?;
+        let (blobs, decoder) = decoder.decode_field("blobs")?;
+        let (commitments, decoder) = decoder.decode_field("commitments")?;
+        let (proofs, decoder) = decoder.decode_field("proofs")?;
+
+        let wrapped = WrappedEIP4844Transaction {
+            tx,
+            blobs_bundle: BlobsBundle {
+                blobs,
+                commitments,
+                proofs,
+            },
+        };
+        Ok((wrapped, decoder.finish()?))
+    }
+}
+
 /// The transaction's kind: call or create.
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub enum TxKind {
@@ -381,6 +531,39 @@ impl RLPEncode for EIP4844Transaction {
     }
 }
 
+impl RLPEncode for EIP7702Transaction {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.chain_id)
+            .encode_field(&self.nonce)
+            .encode_field(&self.max_priority_fee_per_gas)
+            .encode_field(&self.max_fee_per_gas)
+            .encode_field(&self.gas_limit)
+            .encode_field(&self.to)
+            .encode_field(&self.value)
+            .encode_field(&self.data)
+            .encode_field(&self.access_list)
+            .encode_field(&self.authorization_list)
+            .encode_field(&self.signature_y_parity)
+            .encode_field(&self.signature_r)
+            .encode_field(&self.signature_s)
+            .finish()
+    }
+}
+
+impl RLPEncode for AuthorizationTuple {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.chain_id)
+            .encode_field(&self.address)
+            .encode_field(&self.nonce)
+            .encode_field(&self.y_parity)
+            .encode_field(&self.r_signature)
+            .encode_field(&self.s_signature)
+            .finish();
+    }
+}
+
 impl RLPEncode for PrivilegedL2Transaction {
     fn encode(&self, buf: &mut dyn bytes::BufMut) {
         Encoder::new(buf)
@@ -408,6 +591,7 @@ impl PayloadRLPEncode for Transaction {
             Transaction::EIP1559Transaction(tx) => tx.encode_payload(buf),
             Transaction::EIP2930Transaction(tx) => tx.encode_payload(buf),
             Transaction::EIP4844Transaction(tx) => tx.encode_payload(buf),
+            Transaction::EIP7702Transaction(tx) => tx.encode_payload(buf),
             Transaction::PrivilegedL2Transaction(tx) => tx.encode_payload(buf),
         }
     }
@@ -475,6 +659,23 @@ impl PayloadRLPEncode for EIP4844Transaction {
     }
 }
 
+impl PayloadRLPEncode for EIP7702Transaction {
+    fn encode_payload(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.chain_id)
+            .encode_field(&self.nonce)
+            .encode_field(&self.max_priority_fee_per_gas)
+            .encode_field(&self.max_fee_per_gas)
+            .encode_field(&self.gas_limit)
+            .encode_field(&self.to)
+            .encode_field(&self.value)
+            .encode_field(&self.data)
+            .encode_field(&self.access_list)
+            .encode_field(&self.authorization_list)
+            .finish();
+    }
+}
+
 impl PayloadRLPEncode for PrivilegedL2Transaction {
     fn encode_payload(&self, buf: &mut dyn bytes::BufMut) {
         Encoder::new(buf)
@@ -626,6 +827,70 @@ impl RLPDecode for EIP4844Transaction {
     }
 }
 
+impl RLPDecode for EIP7702Transaction {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(EIP7702Transaction, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (chain_id, decoder) = decoder.decode_field("chain_id")?;
+        let (nonce, decoder) = decoder.decode_field("nonce")?;
+        let (max_priority_fee_per_gas, decoder) =
+            decoder.decode_field("max_priority_fee_per_gas")?;
+        let (max_fee_per_gas, decoder) = decoder.decode_field("max_fee_per_gas")?;
+        let (gas_limit, decoder) = decoder.decode_field("gas_limit")?;
+        let (to, decoder) = decoder.decode_field("to")?;
+        let (value, decoder) = decoder.decode_field("value")?;
+        let (data, decoder) = decoder.decode_field("data")?;
+        let (access_list, decoder) = decoder.decode_field("access_list")?;
+        let (authorization_list, decoder) = decoder.decode_field("authorization_list")?;
+        let (signature_y_parity, decoder) = decoder.decode_field("signature_y_parity")?;
+        let (signature_r, decoder) = decoder.decode_field("signature_r")?;
+        let (signature_s, decoder) = decoder.decode_field("signature_s")?;
+
+        let tx = EIP7702Transaction {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            to,
+            value,
+            data,
+            access_list,
+            authorization_list,
+            signature_y_parity,
+            signature_r,
+            signature_s,
+        };
+        Ok((tx, decoder.finish()?))
+    }
+}
+
+impl RLPDecode for AuthorizationTuple {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(AuthorizationTuple, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (chain_id, decoder) = decoder.decode_field("chain_id")?;
+        let (address, decoder) = decoder.decode_field("address")?;
+        let (nonce, decoder) = decoder.decode_field("nonce")?;
+        let (y_parity, decoder): (U256, _) = decoder.decode_field("y_parity")?;
+        if y_parity > U256::one() {
+            return Err(RLPDecodeError::Custom(format!(
+                "Invalid authorization y_parity: {y_parity}"
+            )));
+        }
+        let (r_signature, decoder) = decoder.decode_field("r_signature")?;
+        let (s_signature, decoder) = decoder.decode_field("s_signature")?;
+
+        let tuple = AuthorizationTuple {
+            chain_id,
+            address,
+            nonce,
+            y_parity,
+            r_signature,
+            s_signature,
+        };
+        Ok((tuple, decoder.finish()?))
+    }
+}
+
 impl RLPDecode for PrivilegedL2Transaction {
     fn decode_unfinished(rlp: &[u8]) -> Result<(PrivilegedL2Transaction, &[u8]), RLPDecodeError> {
         let decoder = Decoder::new(rlp)?;
@@ -670,6 +935,7 @@ impl Signable for Transaction {
             Transaction::EIP2930Transaction(tx) => tx.sign_inplace(private_key),
             Transaction::EIP1559Transaction(tx) => tx.sign_inplace(private_key),
             Transaction::EIP4844Transaction(tx) => tx.sign_inplace(private_key),
+            Transaction::EIP7702Transaction(tx) => tx.sign_inplace(private_key),
             Transaction::PrivilegedL2Transaction(tx) => tx.sign_inplace(private_key),
         }
     }
@@ -760,6 +1026,28 @@ impl Signable for EIP4844Transaction {
     }
 }
 
+impl Signable for EIP7702Transaction {
+    fn sign_inplace(&mut self, private_key: &SecretKey) {
+        let mut payload = vec![TxType::EIP7702 as u8];
+        payload.append(self.encode_payload_to_vec().as_mut());
+        let data = Message::from_digest_slice(&keccak(payload).0).unwrap();
+
+        let (recovery_id, signature) = secp256k1::SECP256K1
+            .sign_ecdsa_recoverable(&data, private_key)
+            .serialize_compact();
+
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&signature[..32]);
+        s.copy_from_slice(&signature[32..]);
+        let parity = recovery_id.to_i32() != 0;
+
+        self.signature_r = U256::from(&r);
+        self.signature_s = U256::from(&s);
+        self.signature_y_parity = parity;
+    }
+}
+
 impl Signable for PrivilegedL2Transaction {
     fn sign_inplace(&mut self, private_key: &SecretKey) {
         let mut payload = vec![TxType::Privileged as u8];
@@ -875,6 +1163,27 @@ impl Transaction {
                     &Bytes::from(buf),
                 )
             }
+            Transaction::EIP7702Transaction(tx) => {
+                let mut buf = vec![self.tx_type() as u8];
+                Encoder::new(&mut buf)
+                    .encode_field(&tx.chain_id)
+                    .encode_field(&tx.nonce)
+                    .encode_field(&tx.max_priority_fee_per_gas)
+                    .encode_field(&tx.max_fee_per_gas)
+                    .encode_field(&tx.gas_limit)
+                    .encode_field(&tx.to)
+                    .encode_field(&tx.value)
+                    .encode_field(&tx.data)
+                    .encode_field(&tx.access_list)
+                    .encode_field(&tx.authorization_list)
+                    .finish();
+                recover_address(
+                    &tx.signature_r,
+                    &tx.signature_s,
+                    tx.signature_y_parity,
+                    &Bytes::from(buf),
+                )
+            }
             Transaction::PrivilegedL2Transaction(tx) => {
                 let mut buf = vec![self.tx_type() as u8];
                 Encoder::new(&mut buf)
@@ -905,6 +1214,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(tx) => tx.gas_limit,
             Transaction::EIP1559Transaction(tx) => tx.gas_limit,
             Transaction::EIP4844Transaction(tx) => tx.gas,
+            Transaction::EIP7702Transaction(tx) => tx.gas_limit,
             Transaction::PrivilegedL2Transaction(tx) => tx.gas_limit,
         }
     }
@@ -915,6 +1225,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(tx) => tx.gas_price,
             Transaction::EIP1559Transaction(tx) => tx.max_fee_per_gas,
             Transaction::EIP4844Transaction(tx) => tx.max_fee_per_gas,
+            Transaction::EIP7702Transaction(tx) => tx.max_fee_per_gas,
             Transaction::PrivilegedL2Transaction(tx) => tx.max_fee_per_gas,
         }
     }
@@ -925,6 +1236,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(tx) => tx.to.clone(),
             Transaction::EIP1559Transaction(tx) => tx.to.clone(),
             Transaction::EIP4844Transaction(tx) => TxKind::Call(tx.to),
+            Transaction::EIP7702Transaction(tx) => TxKind::Call(tx.to),
             Transaction::PrivilegedL2Transaction(tx) => tx.to.clone(),
         }
     }
@@ -935,6 +1247,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(tx) => tx.value,
             Transaction::EIP1559Transaction(tx) => tx.value,
             Transaction::EIP4844Transaction(tx) => tx.value,
+            Transaction::EIP7702Transaction(tx) => tx.value,
             Transaction::PrivilegedL2Transaction(tx) => tx.value,
         }
     }
@@ -945,6 +1258,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(_tx) => None,
             Transaction::EIP1559Transaction(tx) => Some(tx.max_priority_fee_per_gas),
             Transaction::EIP4844Transaction(tx) => Some(tx.max_priority_fee_per_gas),
+            Transaction::EIP7702Transaction(tx) => Some(tx.max_priority_fee_per_gas),
             Transaction::PrivilegedL2Transaction(tx) => Some(tx.max_priority_fee_per_gas),
         }
     }
@@ -955,6 +1269,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(tx) => Some(tx.chain_id),
             Transaction::EIP1559Transaction(tx) => Some(tx.chain_id),
             Transaction::EIP4844Transaction(tx) => Some(tx.chain_id),
+            Transaction::EIP7702Transaction(tx) => Some(tx.chain_id),
             Transaction::PrivilegedL2Transaction(tx) => Some(tx.chain_id),
         }
     }
@@ -965,6 +1280,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(tx) => tx.access_list.clone(),
             Transaction::EIP1559Transaction(tx) => tx.access_list.clone(),
             Transaction::EIP4844Transaction(tx) => tx.access_list.clone(),
+            Transaction::EIP7702Transaction(tx) => tx.access_list.clone(),
             Transaction::PrivilegedL2Transaction(tx) => tx.access_list.clone(),
         }
     }
@@ -975,6 +1291,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(tx) => tx.nonce,
             Transaction::EIP1559Transaction(tx) => tx.nonce,
             Transaction::EIP4844Transaction(tx) => tx.nonce,
+            Transaction::EIP7702Transaction(tx) => tx.nonce,
             Transaction::PrivilegedL2Transaction(tx) => tx.nonce,
         }
     }
@@ -985,6 +1302,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(tx) => &tx.data,
             Transaction::EIP1559Transaction(tx) => &tx.data,
             Transaction::EIP4844Transaction(tx) => &tx.data,
+            Transaction::EIP7702Transaction(tx) => &tx.data,
             Transaction::PrivilegedL2Transaction(tx) => &tx.data,
         }
     }
@@ -995,6 +1313,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(_tx) => Vec::new(),
             Transaction::EIP1559Transaction(_tx) => Vec::new(),
             Transaction::EIP4844Transaction(tx) => tx.blob_versioned_hashes.clone(),
+            Transaction::EIP7702Transaction(_tx) => Vec::new(),
             Transaction::PrivilegedL2Transaction(_tx) => Vec::new(),
         }
     }
@@ -1005,16 +1324,25 @@ impl Transaction {
             Transaction::EIP2930Transaction(_tx) => None,
             Transaction::EIP1559Transaction(_tx) => None,
             Transaction::EIP4844Transaction(tx) => Some(tx.max_fee_per_blob_gas),
+            Transaction::EIP7702Transaction(_tx) => None,
             Transaction::PrivilegedL2Transaction(_tx) => None,
         }
     }
 
+    pub fn authorization_list(&self) -> Option<AuthorizationList> {
+        match self {
+            Transaction::EIP7702Transaction(tx) => Some(tx.authorization_list.clone()),
+            _ => None,
+        }
+    }
+
     pub fn is_contract_creation(&self) -> bool {
         match &self {
             Transaction::LegacyTransaction(t) => matches!(t.to, TxKind::Create),
             Transaction::EIP2930Transaction(t) => matches!(t.to, TxKind::Create),
             Transaction::EIP1559Transaction(t) => matches!(t.to, TxKind::Create),
             Transaction::EIP4844Transaction(_) => false,
+            Transaction::EIP7702Transaction(_) => false,
             Transaction::PrivilegedL2Transaction(t) => matches!(t.to, TxKind::Create),
         }
     }
@@ -1025,6 +1353,7 @@ impl Transaction {
             Transaction::EIP2930Transaction(_tx) => None,
             Transaction::EIP1559Transaction(tx) => Some(tx.max_fee_per_gas),
             Transaction::EIP4844Transaction(tx) => Some(tx.max_fee_per_gas),
+            Transaction::EIP7702Transaction(tx) => Some(tx.max_fee_per_gas),
             Transaction::PrivilegedL2Transaction(tx) => Some(tx.max_fee_per_gas),
         }
     }
@@ -1107,6 +1436,7 @@ impl TxType {
             0x01 => Some(Self::EIP2930),
             0x02 => Some(Self::EIP1559),
             0x03 => Some(Self::EIP4844),
+            0x04 => Some(Self::EIP7702),
             0x7e => Some(Self::Privileged),
             _ => None,
         }
@@ -1211,6 +1541,9 @@ mod canonic_encoding {
                         // EIP4844
                         0x3 => EIP4844Transaction::decode(tx_bytes)
                             .map(Transaction::EIP4844Transaction),
+                        // EIP7702
+                        0x4 => EIP7702Transaction::decode(tx_bytes)
+                            .map(Transaction::EIP7702Transaction),
                         0x7e => PrivilegedL2Transaction::decode(tx_bytes)
                             .map(Transaction::PrivilegedL2Transaction),
                         ty => Err(RLPDecodeError::Custom(format!(
@@ -1239,6 +1572,7 @@ mod canonic_encoding {
                 Transaction::EIP2930Transaction(t) => t.encode(buf),
                 Transaction::EIP1559Transaction(t) => t.encode(buf),
                 Transaction::EIP4844Transaction(t) => t.encode(buf),
+                Transaction::EIP7702Transaction(t) => t.encode(buf),
                 Transaction::PrivilegedL2Transaction(t) => t.encode(buf),
             };
         }
@@ -1254,6 +1588,132 @@ mod canonic_encoding {
             buf
         }
     }
+
+    impl P2PTransaction {
+        /// Decodes a single transaction in canonical format
+        /// Follows the same format as [Transaction::decode_canonical], with the exception of
+        /// blob transactions, which are encoded as `0x03 || rlp([tx_payload_body, blobs, commitments, proofs])`
+        pub fn decode_canonical(bytes: &[u8]) -> Result<Self, RLPDecodeError> {
+            // Look at the first byte to check if it corresponds to a TransactionType
+            match bytes.first() {
+                // First byte is a valid TransactionType
+                Some(tx_type) if *tx_type < 0x7f => {
+                    // Decode tx based on type
+                    let tx_bytes = &bytes[1..];
+                    match *tx_type {
+                        // Legacy
+                        0x0 => LegacyTransaction::decode(tx_bytes)
+                            .map(P2PTransaction::LegacyTransaction),
+                        // EIP2930
+                        0x1 => EIP2930Transaction::decode(tx_bytes)
+                            .map(P2PTransaction::EIP2930Transaction),
+                        // EIP1559
+                        0x2 => EIP1559Transaction::decode(tx_bytes)
+                            .map(P2PTransaction::EIP1559Transaction),
+                        // EIP4844
+                        0x3 => WrappedEIP4844Transaction::decode(tx_bytes)
+                            .map(P2PTransaction::EIP4844TransactionWithBlobs),
+                        // EIP7702
+                        0x4 => EIP7702Transaction::decode(tx_bytes)
+                            .map(P2PTransaction::EIP7702Transaction),
+                        0x7e => PrivilegedL2Transaction::decode(tx_bytes)
+                            .map(P2PTransaction::PrivilegedL2Transaction),
+                        ty => Err(RLPDecodeError::Custom(format!(
+                            "Invalid transaction type: {ty}"
+                        ))),
+                    }
+                }
+                // LegacyTransaction
+                _ => LegacyTransaction::decode(bytes).map(P2PTransaction::LegacyTransaction),
+            }
+        }
+
+        /// Encodes a transaction in canonical format
+        /// Follows the same format as [Transaction::encode_canonical], with the exception of
+        /// blob transactions, which are encoded as `0x03 || rlp([tx_payload_body, blobs, commitments, proofs])`
+        pub fn encode_canonical(&self, buf: &mut dyn bytes::BufMut) {
+            match self {
+                // Legacy transactions don't have a prefix
+                P2PTransaction::LegacyTransaction(_) => {}
+                _ => buf.put_u8(self.tx_type() as u8),
+            }
+            match self {
+                P2PTransaction::LegacyTransaction(t) => t.encode(buf),
+                P2PTransaction::EIP2930Transaction(t) => t.encode(buf),
+                P2PTransaction::EIP1559Transaction(t) => t.encode(buf),
+                P2PTransaction::EIP4844TransactionWithBlobs(t) => t.encode(buf),
+                P2PTransaction::EIP7702Transaction(t) => t.encode(buf),
+                P2PTransaction::PrivilegedL2Transaction(t) => t.encode(buf),
+            };
+        }
+
+        /// Encodes a transaction in canonical format into a newly created buffer
+        pub fn encode_canonical_to_vec(&self) -> Vec<u8> {
+            let mut buf = Vec::new();
+            self.encode_canonical(&mut buf);
+            buf
+        }
+    }
+}
+
+impl P2PTransaction {
+    /// Builds the network representation of a transaction
+    /// Returns None if the transaction is a blob transaction and its blobs bundle is not provided
+    pub fn new(transaction: Transaction, blobs_bundle: Option<BlobsBundle>) -> Option<Self> {
+        Some(match transaction {
+            Transaction::LegacyTransaction(t) => P2PTransaction::LegacyTransaction(t),
+            Transaction::EIP2930Transaction(t) => P2PTransaction::EIP2930Transaction(t),
+            Transaction::EIP1559Transaction(t) => P2PTransaction::EIP1559Transaction(t),
+            Transaction::EIP4844Transaction(tx) => {
+                P2PTransaction::EIP4844TransactionWithBlobs(WrappedEIP4844Transaction {
+                    tx,
+                    blobs_bundle: blobs_bundle?,
+                })
+            }
+            Transaction::EIP7702Transaction(t) => P2PTransaction::EIP7702Transaction(t),
+            Transaction::PrivilegedL2Transaction(t) => P2PTransaction::PrivilegedL2Transaction(t),
+        })
+    }
+
+    pub fn tx_type(&self) -> TxType {
+        match self {
+            P2PTransaction::LegacyTransaction(_) => TxType::Legacy,
+            P2PTransaction::EIP2930Transaction(_) => TxType::EIP2930,
+            P2PTransaction::EIP1559Transaction(_) => TxType::EIP1559,
+            P2PTransaction::EIP4844TransactionWithBlobs(_) => TxType::EIP4844,
+            P2PTransaction::EIP7702Transaction(_) => TxType::EIP7702,
+            P2PTransaction::PrivilegedL2Transaction(_) => TxType::Privileged,
+        }
+    }
+
+    /// Computes the hash of the transaction, which doesn't include the blobs bundle in the case of blob transactions
+    pub fn compute_hash(&self) -> H256 {
+        match self {
+            P2PTransaction::EIP4844TransactionWithBlobs(wrapped) => {
+                let mut buf = vec![TxType::EIP4844 as u8];
+                wrapped.tx.encode(&mut buf);
+                keccak_hash::keccak(buf)
+            }
+            tx => keccak_hash::keccak(tx.encode_canonical_to_vec()),
+        }
+    }
+
+    /// Splits the transaction into its consensus representation and its blobs bundle (if it is a blob transaction)
+    pub fn into_parts(self) -> (Transaction, Option<BlobsBundle>) {
+        match self {
+            P2PTransaction::LegacyTransaction(t) => (Transaction::LegacyTransaction(t), None),
+            P2PTransaction::EIP2930Transaction(t) => (Transaction::EIP2930Transaction(t), None),
+            P2PTransaction::EIP1559Transaction(t) => (Transaction::EIP1559Transaction(t), None),
+            P2PTransaction::EIP4844TransactionWithBlobs(wrapped) => (
+                Transaction::EIP4844Transaction(wrapped.tx),
+                Some(wrapped.blobs_bundle),
+            ),
+            P2PTransaction::EIP7702Transaction(t) => (Transaction::EIP7702Transaction(t), None),
+            P2PTransaction::PrivilegedL2Transaction(t) => {
+                (Transaction::PrivilegedL2Transaction(t), None)
+            }
+        }
+    }
 }
 
 // Serialization
@@ -1475,6 +1935,46 @@ mod serde_impl {
         }
     }
 
+    impl Serialize for EIP7702Transaction {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let mut struct_serializer = serializer.serialize_struct("Eip7702Transaction", 15)?;
+            struct_serializer.serialize_field("type", &TxType::EIP7702)?;
+            struct_serializer.serialize_field("nonce", &format!("{:#x}", self.nonce))?;
+            struct_serializer.serialize_field("to", &self.to)?;
+            struct_serializer.serialize_field("gas", &format!("{:#x}", self.gas_limit))?;
+            struct_serializer.serialize_field("value", &self.value)?;
+            struct_serializer.serialize_field("input", &format!("0x{:x}", self.data))?;
+            struct_serializer.serialize_field(
+                "maxPriorityFeePerGas",
+                &format!("{:#x}", self.max_priority_fee_per_gas),
+            )?;
+            struct_serializer
+                .serialize_field("maxFeePerGas", &format!("{:#x}", self.max_fee_per_gas))?;
+            struct_serializer
+                .serialize_field("gasPrice", &format!("{:#x}", self.max_fee_per_gas))?;
+            struct_serializer.serialize_field(
+                "accessList",
+                &self
+                    .access_list
+                    .iter()
+                    .map(AccessListEntry::from)
+                    .collect::<Vec<_>>(),
+            )?;
+            struct_serializer.serialize_field("authorizationList", &self.authorization_list)?;
+            struct_serializer.serialize_field("chainId", &format!("{:#x}", self.chain_id))?;
+            struct_serializer
+                .serialize_field("yParity", &format!("{:#x}", self.signature_y_parity as u8))?;
+            struct_serializer
+                .serialize_field("v", &format!("{:#x}", self.signature_y_parity as u8))?; // added to match Hive tests
+            struct_serializer.serialize_field("r", &self.signature_r)?;
+            struct_serializer.serialize_field("s", &self.signature_s)?;
+            struct_serializer.end()
+        }
+    }
+
     impl Serialize for PrivilegedL2Transaction {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
@@ -1560,6 +2060,13 @@ mod serde_impl {
                             serde::de::Error::custom(format!("Couldn't Deserialize EIP4844 {e}"))
                         })
                 }
+                TxType::EIP7702 => {
+                    EIP7702Transaction::deserialize(serde::de::value::MapDeserializer::new(iter))
+                        .map(Transaction::EIP7702Transaction)
+                        .map_err(|e| {
+                            serde::de::Error::custom(format!("Couldn't Deserialize EIP7702 {e}"))
+                        })
+                }
                 TxType::Privileged => PrivilegedL2Transaction::deserialize(
                     serde::de::value::MapDeserializer::new(iter),
                 )
@@ -1925,6 +2432,101 @@ mod serde_impl {
         }
     }
 
+    impl<'de> Deserialize<'de> for EIP7702Transaction {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let mut map = <HashMap<String, serde_json::Value>>::deserialize(deserializer)?;
+            let nonce = serde_json::from_value::<U256>(
+                map.remove("nonce")
+                    .ok_or_else(|| serde::de::Error::missing_field("nonce"))?,
+            )
+            .map_err(serde::de::Error::custom)?
+            .as_u64();
+            let to = serde_json::from_value(
+                map.remove("to")
+                    .ok_or_else(|| serde::de::Error::missing_field("to"))?,
+            )
+            .map_err(serde::de::Error::custom)?;
+            let value = serde_json::from_value(
+                map.remove("value")
+                    .ok_or_else(|| serde::de::Error::missing_field("value"))?,
+            )
+            .map_err(serde::de::Error::custom)?;
+            let data = deserialize_input_field(&mut map).map_err(serde::de::Error::custom)?;
+            let access_list = serde_json::from_value::<Vec<AccessListEntry>>(
+                map.remove("accessList")
+                    .ok_or_else(|| serde::de::Error::missing_field("accessList"))?,
+            )
+            .map_err(serde::de::Error::custom)?
+            .into_iter()
+            .map(|v| (v.address, v.storage_keys))
+            .collect::<Vec<_>>();
+            let authorization_list = serde_json::from_value(
+                map.remove("authorizationList")
+                    .ok_or_else(|| serde::de::Error::missing_field("authorizationList"))?,
+            )
+            .map_err(serde::de::Error::custom)?;
+            let r = serde_json::from_value(
+                map.remove("r")
+                    .ok_or_else(|| serde::de::Error::missing_field("r"))?,
+            )
+            .map_err(serde::de::Error::custom)?;
+            let s = serde_json::from_value(
+                map.remove("s")
+                    .ok_or_else(|| serde::de::Error::missing_field("s"))?,
+            )
+            .map_err(serde::de::Error::custom)?;
+
+            Ok(EIP7702Transaction {
+                chain_id: serde_json::from_value::<U256>(
+                    map.remove("chainId")
+                        .ok_or_else(|| serde::de::Error::missing_field("chainId"))?,
+                )
+                .map_err(serde::de::Error::custom)?
+                .as_u64(),
+                nonce,
+                max_priority_fee_per_gas: serde_json::from_value::<U256>(
+                    map.remove("maxPriorityFeePerGas")
+                        .ok_or_else(|| serde::de::Error::missing_field("maxPriorityFeePerGas"))?,
+                )
+                .map_err(serde::de::Error::custom)?
+                .as_u64(),
+                max_fee_per_gas: serde_json::from_value::<U256>(
+                    map.remove("maxFeePerGas")
+                        .ok_or_else(|| serde::de::Error::missing_field("maxFeePerGas"))?,
+                )
+                .map_err(serde::de::Error::custom)?
+                .as_u64(),
+                gas_limit: serde_json::from_value::<U256>(
+                    map.remove("gas")
+                        .ok_or_else(|| serde::de::Error::missing_field("gas"))?,
+                )
+                .map_err(serde::de::Error::custom)?
+                .as_u64(),
+                to,
+                value,
+                data,
+                access_list,
+                authorization_list,
+                signature_y_parity: u8::from_str_radix(
+                    serde_json::from_value::<String>(
+                        map.remove("yParity")
+                            .ok_or_else(|| serde::de::Error::missing_field("yParity"))?,
+                    )
+                    .map_err(serde::de::Error::custom)?
+                    .trim_start_matches("0x"),
+                    16,
+                )
+                .map_err(serde::de::Error::custom)?
+                    != 0,
+                signature_r: r,
+                signature_s: s,
+            })
+        }
+    }
+
     impl<'de> Deserialize<'de> for PrivilegedL2Transaction {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
@@ -2048,6 +2650,8 @@ mod serde_impl {
         pub blobs: Vec<Bytes>,
         #[serde(default, with = "crate::serde_utils::u64::hex_str_opt")]
         pub chain_id: Option<u64>,
+        #[serde(default)]
+        pub authorization_list: Option<Vec<AuthorizationTuple>>,
     }
 
     impl From<EIP1559Transaction> for GenericTransaction {
@@ -2072,6 +2676,7 @@ mod serde_impl {
                 blobs: vec![],
                 chain_id: Some(value.chain_id),
                 from: Address::default(),
+                authorization_list: None,
             }
         }
     }
@@ -2098,6 +2703,34 @@ mod serde_impl {
                 blobs: vec![],
                 chain_id: Some(value.chain_id),
                 from: Address::default(),
+                authorization_list: None,
+            }
+        }
+    }
+
+    impl From<EIP7702Transaction> for GenericTransaction {
+        fn from(value: EIP7702Transaction) -> Self {
+            Self {
+                r#type: TxType::EIP7702,
+                nonce: Some(value.nonce),
+                to: TxKind::Call(value.to),
+                gas: Some(value.gas_limit),
+                value: value.value,
+                input: value.data,
+                gas_price: value.max_fee_per_gas,
+                max_priority_fee_per_gas: Some(value.max_priority_fee_per_gas),
+                max_fee_per_gas: Some(value.max_fee_per_gas),
+                max_fee_per_blob_gas: None,
+                access_list: value
+                    .access_list
+                    .iter()
+                    .map(AccessListEntry::from)
+                    .collect(),
+                blob_versioned_hashes: vec![],
+                blobs: vec![],
+                chain_id: Some(value.chain_id),
+                from: Address::default(),
+                authorization_list: Some(value.authorization_list),
             }
         }
     }
@@ -2124,6 +2757,7 @@ mod serde_impl {
                 blobs: vec![],
                 chain_id: Some(value.chain_id),
                 from: Address::default(),
+                authorization_list: None,
             }
         }
     }
@@ -2369,6 +3003,82 @@ mod tests {
         assert_eq!(tx, expected_tx);
     }
 
+    #[test]
+    fn eip7702_tx_rlp_roundtrip() {
+        let tx = Transaction::EIP7702Transaction(EIP7702Transaction {
+            chain_id: 3151908,
+            nonce: 1,
+            max_priority_fee_per_gas: 17,
+            max_fee_per_gas: 78,
+            gas_limit: 63000,
+            to: Address::from_low_u64_be(0x1234),
+            value: U256::zero(),
+            data: Bytes::new(),
+            access_list: vec![],
+            authorization_list: vec![AuthorizationTuple {
+                chain_id: 3151908.into(),
+                address: Address::from_low_u64_be(0x5678),
+                nonce: 2,
+                y_parity: U256::one(),
+                r_signature: 1.into(),
+                s_signature: 2.into(),
+            }],
+            signature_y_parity: false,
+            signature_r: 3.into(),
+            signature_s: 4.into(),
+        });
+        let encoded = tx.encode_canonical_to_vec();
+        assert_eq!(encoded[0], TxType::EIP7702 as u8);
+        let decoded = Transaction::decode_canonical(&encoded).unwrap();
+        assert_eq!(decoded, tx);
+        assert_eq!(decoded.authorization_list().map(|list| list.len()), Some(1));
+    }
+
+    #[test]
+    fn authorization_with_invalid_y_parity_is_rejected() {
+        let authorization = AuthorizationTuple {
+            chain_id: 3151908.into(),
+            address: Address::from_low_u64_be(0x5678),
+            nonce: 2,
+            y_parity: U256::from(2),
+            r_signature: 1.into(),
+            s_signature: 2.into(),
+        };
+        assert!(AuthorizationTuple::decode(&authorization.encode_to_vec()).is_err());
+        let json = serde_json::to_value(&authorization).unwrap();
+        assert!(serde_json::from_value::<AuthorizationTuple>(json).is_err());
+    }
+
+    #[test]
+    fn p2p_blob_tx_rlp_roundtrip() {
+        let tx = EIP4844Transaction {
+            chain_id: 3151908,
+            nonce: 1,
+            max_fee_per_blob_gas: 1.into(),
+            blob_versioned_hashes: vec![H256::from_low_u64_be(1)],
+            ..Default::default()
+        };
+        let p2p_tx = P2PTransaction::EIP4844TransactionWithBlobs(WrappedEIP4844Transaction {
+            tx: tx.clone(),
+            blobs_bundle: BlobsBundle {
+                blobs: vec![[1; crate::types::BYTES_PER_BLOB]],
+                commitments: vec![[2; 48]],
+                proofs: vec![[3; 48]],
+            },
+        });
+        // The hash should not take the blobs into account
+        assert_eq!(
+            p2p_tx.compute_hash(),
+            Transaction::EIP4844Transaction(tx.clone()).compute_hash()
+        );
+        let encoded = p2p_tx.encode_to_vec();
+        let decoded = P2PTransaction::decode(&encoded).unwrap();
+        assert_eq!(decoded, p2p_tx);
+        let (decoded_tx, blobs_bundle) = decoded.into_parts();
+        assert_eq!(decoded_tx, Transaction::EIP4844Transaction(tx));
+        assert_eq!(blobs_bundle.map(|bundle| bundle.blobs.len()), Some(1));
+    }
+
     #[test]
     fn deserialize_tx_kind() {
         let tx_kind_create = r#""""#;
@@ -2447,6 +3157,7 @@ mod tests {
             blob_versioned_hashes: Default::default(),
             blobs: Default::default(),
             chain_id: Default::default(),
+            authorization_list: Default::default(),
         };
         assert_eq!(
             deserialized_generic_transaction,
@@ -11,30 +11,38 @@ import {IRiscZeroVerifier} from "./interfaces/IRiscZeroVerifier.sol";
 /// @title OnChainProposer contract.
 /// @author LambdaClass
 contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
-    struct BlockCommitmentInfo {
+    struct BatchCommitmentInfo {
         bytes32 commitmentHash;
         bytes32 depositLogs;
+        uint256 lastBlockNumber;
     }
 
-    /// @notice The commitments of the committed blocks.
-    /// @dev If a block is committed, the commitment is stored here.
-    /// @dev If a block was not committed yet, it won't be here.
-    /// @dev It is used by other contracts to verify if a block was committed.
-    mapping(uint256 => BlockCommitmentInfo) public blockCommitments;
+    /// @notice The commitments of the committed batches.
+    /// @dev If a batch is committed, the commitment is stored here.
+    /// @dev If a batch was not committed yet, it won't be here.
+    /// @dev It is used by other contracts to verify if a batch was committed.
+    mapping(uint256 => BatchCommitmentInfo) public batchCommitments;
+
+    /// @notice The latest verified batch number.
+    /// @dev Batches are numbered starting from 1, the value 0 means that no batch has been verified yet.
+    uint256 public lastVerifiedBatch;
+
+    /// @notice The latest committed batch number.
+    /// @dev Batches are numbered starting from 1, the value 0 means that no batch has been committed yet.
+    uint256 public lastCommittedBatch;
 
     /// @notice The latest verified block number.
-    /// @dev This variable holds the block number of the most recently verified block.
+    /// @dev This variable holds the number of the last block of the most recently verified batch.
     /// @dev All blocks with a block number less than or equal to `lastVerifiedBlock` are considered verified.
     /// @dev Blocks with a block number greater than `lastVerifiedBlock` have not been verified yet.
     /// @dev This is crucial for ensuring that only valid and confirmed blocks are processed in the contract.
     uint256 public lastVerifiedBlock;
 
     /// @notice The latest committed block number.
-    /// @dev This variable holds the block number of the most recently committed block.
+    /// @dev This variable holds the number of the last block of the most recently committed batch.
     /// @dev All blocks with a block number less than or equal to `lastCommittedBlock` are considered committed.
     /// @dev Blocks with a block number greater than `lastCommittedBlock` have not been committed yet.
-    /// @dev This is crucial for ensuring that only subsequents blocks are committed in the contract.
-    /// @dev In the initialize function, `lastCommittedBlock` is set to u64::MAX == 0xFFFFFFFFFFFFFFFF, this value is used to allow the block 0 to be committed.
+    /// @dev The genesis block is never committed, so the first batch starts at block 1.
     uint256 public lastCommittedBlock;
 
     /// @dev The sequencer addresses that are authorized to commit and verify blocks.
@@ -94,27 +102,40 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
         for (uint256 i = 0; i < sequencerAddresses.length; i++) {
             authorizedSequencerAddresses[sequencerAddresses[i]] = true;
         }
-
-        lastCommittedBlock = 0xFFFFFFFFFFFFFFFF;
     }
 
     /// @inheritdoc IOnChainProposer
+    /// @notice The commitment of the batch is the hash of the versioned hashes
+    /// of all the blobs attached to the commit transaction, in order.
     function commit(
-        uint256 blockNumber,
-        bytes32 commitment,
-        bytes32 withdrawalsLogsMerkleRoot,
-        bytes32 depositLogs
+        uint256 batchNumber,
+        uint256 lastBlockNumber,
+        bytes32 depositLogs,
+        bytes32[] calldata withdrawalsLogsMerkleRoots
     ) external override onlySequencer {
         require(
-            blockNumber == lastCommittedBlock + 1 ||
-                (blockNumber == 0 && lastCommittedBlock == 0xFFFFFFFFFFFFFFFF),
-            "OnChainProposer: blockNumber is not the immediate succesor of lastCommittedBlock"
+            batchNumber == lastCommittedBatch + 1,
+            "OnChainProposer: batchNumber is not the immediate succesor of lastCommittedBatch"
+        );
+        require(
+            lastBlockNumber > lastCommittedBlock,
+            "OnChainProposer: batch must contain at least one block"
         );
+        uint256 firstBlockNumber = lastCommittedBlock + 1;
         require(
-            blockCommitments[blockNumber].commitmentHash == bytes32(0),
-            "OnChainProposer: block already committed"
+            withdrawalsLogsMerkleRoots.length ==
+                lastBlockNumber - lastCommittedBlock,
+            "OnChainProposer: a withdrawals merkle root is required for each block of the batch"
+        );
+
+        bytes32[] memory blobVersionedHashes = _blobVersionedHashes();
+        require(
+            blobVersionedHashes.length > 0,
+            "OnChainProposer: no blobs attached to the commit transaction"
+        );
+        bytes32 commitment = keccak256(
+            abi.encodePacked(blobVersionedHashes)
         );
-        // Check if commitment is equivalent to blob's KZG commitment.
 
         if (depositLogs != bytes32(0)) {
             bytes32 savedDepositLogs = ICommonBridge(BRIDGE)
@@ -124,63 +145,86 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
                 "OnChainProposer: invalid deposit logs"
             );
         }
-        if (withdrawalsLogsMerkleRoot != bytes32(0)) {
-            ICommonBridge(BRIDGE).publishWithdrawals(
-                blockNumber,
-                withdrawalsLogsMerkleRoot
-            );
+        for (uint256 i = 0; i < withdrawalsLogsMerkleRoots.length; i++) {
+            if (withdrawalsLogsMerkleRoots[i] != bytes32(0)) {
+                ICommonBridge(BRIDGE).publishWithdrawals(
+                    firstBlockNumber + i,
+                    withdrawalsLogsMerkleRoots[i]
+                );
+            }
         }
-        blockCommitments[blockNumber] = BlockCommitmentInfo(
+        batchCommitments[batchNumber] = BatchCommitmentInfo(
             commitment,
-            depositLogs
+            depositLogs,
+            lastBlockNumber
+        );
+        lastCommittedBatch = batchNumber;
+        lastCommittedBlock = lastBlockNumber;
+        emit BatchCommitted(
+            batchNumber,
+            firstBlockNumber,
+            lastBlockNumber,
+            blobVersionedHashes
         );
-        lastCommittedBlock = blockNumber;
-        emit BlockCommitted(commitment);
     }
 
     /// @inheritdoc IOnChainProposer
-    /// @notice The first `require` checks that the block number is the subsequent block.
-    /// @notice The second `require` checks if the block has been committed.
+    /// @notice The first `require` checks that the batch number is the subsequent batch.
+    /// @notice The second `require` checks if the batch has been committed.
     /// @notice The order of these `require` statements is important.
-    /// Ordering Reason: After the verification process, we delete the `blockCommitments` for `blockNumber - 1`. This means that when checking the block,
-    /// we might get an error indicating that the block hasn’t been committed, even though it was committed but deleted. Therefore, it has already been verified.
+    /// Ordering Reason: After the verification process, we delete the `batchCommitments` for `batchNumber - 1`. This means that when checking the batch,
+    /// we might get an error indicating that the batch hasn’t been committed, even though it was committed but deleted. Therefore, it has already been verified.
     function verify(
-        uint256 blockNumber,
-        bytes calldata blockProof,
+        uint256 batchNumber,
+        bytes calldata batchProof,
         bytes32 imageId,
         bytes32 journalDigest
     ) external override onlySequencer {
         require(
-            blockNumber == lastVerifiedBlock + 1,
-            "OnChainProposer: block already verified"
+            batchNumber == lastVerifiedBatch + 1,
+            "OnChainProposer: batch already verified"
         );
 
         require(
-            blockCommitments[blockNumber].commitmentHash != bytes32(0),
-            "OnChainProposer: block not committed"
+            batchCommitments[batchNumber].commitmentHash != bytes32(0),
+            "OnChainProposer: batch not committed"
         );
 
         if (R0VERIFIER != DEV_MODE) {
             // If the verification fails, it will revert.
             IRiscZeroVerifier(R0VERIFIER).verify(
-                blockProof,
+                batchProof,
                 imageId,
                 journalDigest
             );
         }
 
-        lastVerifiedBlock = blockNumber;
+        lastVerifiedBatch = batchNumber;
+        lastVerifiedBlock = batchCommitments[batchNumber].lastBlockNumber;
         // The first 2 bytes are the number of deposits.
         uint16 deposits_amount = uint16(
-            bytes2(blockCommitments[blockNumber].depositLogs)
+            bytes2(batchCommitments[batchNumber].depositLogs)
         );
         if (deposits_amount > 0) {
             ICommonBridge(BRIDGE).removeDepositLogs(deposits_amount);
         }
 
-        // Remove previous block commitment as it is no longer needed.
-        delete blockCommitments[blockNumber - 1];
+        // Remove previous batch commitment as it is no longer needed.
+        delete batchCommitments[batchNumber - 1];
 
-        emit BlockVerified(blockNumber);
+        emit BatchVerified(batchNumber);
+    }
+
+    /// @notice Returns the versioned hashes of the blobs attached to the current transaction.
+    function _blobVersionedHashes() internal view returns (bytes32[] memory) {
+        uint256 count = 0;
+        while (blobhash(count) != bytes32(0)) {
+            count++;
+        }
+        bytes32[] memory hashes = new bytes32[](count);
+        for (uint256 i = 0; i < count; i++) {
+            hashes[i] = blobhash(i);
+        }
+        return hashes;
     }
 }
@@ -4,7 +4,7 @@ pragma solidity ^0.8.27;
 /// @title Interface for the OnChainProposer contract.
 /// @author LambdaClass
 /// @notice A OnChainProposer contract ensures the advancement of the L2. It is used
-/// by the proposer to commit blocks and verify block proofs.
+/// by the proposer to commit batches of blocks and verify batch proofs.
 interface IOnChainProposer {
     /// @notice The latest commited block number.
     function lastCommittedBlock() external view returns (uint256);
@@ -12,13 +12,25 @@ interface IOnChainProposer {
     /// @notice The latest verified block number
     function lastVerifiedBlock() external view returns (uint256);
 
-    /// @notice A block has been committed.
-    /// @dev Event emitted when a block is committed.
-    event BlockCommitted(bytes32 indexed currentBlockCommitment);
+    /// @notice The latest commited batch number.
+    function lastCommittedBatch() external view returns (uint256);
 
-    /// @notice A block has been verified.
-    /// @dev Event emitted when a block is verified.
-    event BlockVerified(uint256 indexed blockNumber);
+    /// @notice The latest verified batch number.
+    function lastVerifiedBatch() external view returns (uint256);
+
+    /// @notice A batch has been committed.
+    /// @dev Event emitted when a batch is committed.
+    /// @dev The state diff of the batch is split across the blobs with the given versioned hashes, in order.
+    event BatchCommitted(
+        uint256 indexed batchNumber,
+        uint256 firstBlockNumber,
+        uint256 lastBlockNumber,
+        bytes32[] blobVersionedHashes
+    );
+
+    /// @notice A batch has been verified.
+    /// @dev Event emitted when a batch is verified.
+    event BatchVerified(uint256 indexed batchNumber);
 
     /// @notice Initializes the contract.
     /// @dev This method is called only once after the contract is deployed.
@@ -27,31 +39,33 @@ interface IOnChainProposer {
     /// @param r0verifier the address of the risc0 groth16 verifier.
     function initialize(address bridge, address r0verifier, address[] calldata sequencerAddress) external;
 
-    /// @notice Commits to an L2 block.
-    /// @dev Committing to an L2 block means to store the block's commitment
-    /// and to publish withdrawals if any.
-    /// @param blockNumber the number of the block to be committed.
-    /// @param commitment of the block to be committed.
-    /// @param withdrawalsLogsMerkleRoot the merkle root of the withdrawal logs
-    /// of the block to be committed.
-    /// @param depositLogs the deposit logs of the block to be committed.
+    /// @notice Commits to a batch of L2 blocks.
+    /// @dev Committing to a batch means to store the commitment to the blobs
+    /// carrying the batch's state diff and to publish withdrawals if any.
+    /// @dev The batch contains every block after `lastCommittedBlock` up to
+    /// `lastBlockNumber`, both inclusive.
+    /// @param batchNumber the number of the batch to be committed.
+    /// @param lastBlockNumber the number of the last block of the batch.
+    /// @param depositLogs the deposit logs of the batch to be committed.
+    /// @param withdrawalsLogsMerkleRoots the merkle root of the withdrawal logs
+    /// of each block of the batch, zero for blocks without withdrawals.
     function commit(
-        uint256 blockNumber,
-        bytes32 commitment,
-        bytes32 withdrawalsLogsMerkleRoot,
-        bytes32 depositLogs
+        uint256 batchNumber,
+        uint256 lastBlockNumber,
+        bytes32 depositLogs,
+        bytes32[] calldata withdrawalsLogsMerkleRoots
     ) external;
 
-    /// @notice Method used to verify an L2 block proof.
-    /// @dev This method is used by the operator when a block is ready to be
+    /// @notice Method used to verify an L2 batch proof.
+    /// @dev This method is used by the operator when a batch is ready to be
     /// verified (this is after proved).
-    /// @param blockNumber is the number of the block to be verified.
-    /// @param blockProof is the proof of the block to be verified.
+    /// @param batchNumber is the number of the batch to be verified.
+    /// @param batchProof is the proof of the batch to be verified.
     /// @param imageId Digest of the zkVM imageid.
     /// @param journalDigest Digest of the public_inputs aka journal
     function verify(
-        uint256 blockNumber,
-        bytes calldata blockProof,
+        uint256 batchNumber,
+        bytes calldata batchProof,
         bytes32 imageId,
         bytes32 journalDigest
     ) external;
@@ -87,6 +87,8 @@ pub enum CommitterError {
     FailedToGetInformationFromStorage(String),
     #[error("Committer failed to encode state diff: {0}")]
     FailedToEncodeStateDiff(#[from] StateDiffError),
+    #[error("Committer state diff needs {0} blobs, more than a single transaction can carry")]
+    StateDiffTooLarge(usize),
     #[error("Committer failed to open Points file: {0}")]
     FailedToOpenPointsFile(#[from] std::io::Error),
     #[error("Committer failed to re-execute block: {0}")]
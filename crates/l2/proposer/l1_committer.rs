@@ -12,23 +12,31 @@ use crate::{
 use bytes::Bytes;
 use ethrex_core::{
     types::{
-        blobs_bundle, fake_exponential_checked, BlobsBundle, Block, PrivilegedL2Transaction,
-        PrivilegedTxType, Transaction, TxKind, BLOB_BASE_FEE_UPDATE_FRACTION,
-        MIN_BASE_FEE_PER_BLOB_GAS,
+        blobs_bundle::{self, MAX_BLOB_DATA_SIZE},
+        fake_exponential_checked, BlobsBundle, Block, BlockHeader, BlockNumber,
+        PrivilegedL2Transaction, PrivilegedTxType, Transaction, TxKind,
+        BLOB_BASE_FEE_UPDATE_FRACTION, MIN_BASE_FEE_PER_BLOB_GAS,
     },
     Address, H256, U256,
 };
-use ethrex_storage::{error::StoreError, Store};
+use ethrex_storage::{AccountUpdate, Store};
 use ethrex_vm::{evm_state, execute_block, get_state_transitions};
 use keccak_hash::keccak;
 use secp256k1::SecretKey;
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio::time::sleep;
 use tracing::{error, info};
 
 use super::errors::BlobEstimationError;
 
-const COMMIT_FUNCTION_SELECTOR: [u8; 4] = [132, 97, 12, 179];
+const COMMIT_FUNCTION_SIGNATURE: &str = "commit(uint256,uint256,bytes32,bytes32[])";
+
+/// Maximum number of blobs attached to a single commit transaction.
+/// Matches the blob limit of an L1 block.
+const MAX_BLOBS_PER_BATCH: usize = 6;
 
 pub struct Committer {
     eth_client: EthClient,
@@ -38,6 +46,18 @@ pub struct Committer {
     l1_private_key: SecretKey,
     interval_ms: u64,
     arbitrary_base_blob_gas_price: u64,
+    batch_size: u64,
+    batch_timeout_ms: u64,
+}
+
+/// Range of consecutive L2 blocks committed together to the L1
+struct Batch {
+    number: u64,
+    last_block_number: BlockNumber,
+    /// Withdrawals merkle root of each block of the batch, zero for blocks without withdrawals
+    withdrawal_logs_merkle_roots: Vec<H256>,
+    deposit_logs_hash: H256,
+    state_diff: StateDiff,
 }
 
 pub async fn start_l1_commiter(store: Store) -> Result<(), ConfigError> {
@@ -62,6 +82,8 @@ impl Committer {
             l1_private_key: committer_config.l1_private_key,
             interval_ms: committer_config.interval_ms,
             arbitrary_base_blob_gas_price: committer_config.arbitrary_base_blob_gas_price,
+            batch_size: committer_config.batch_size.max(1),
+            batch_timeout_ms: committer_config.batch_timeout_ms,
         }
     }
 
@@ -77,79 +99,43 @@ impl Committer {
 
     async fn main_logic(&self) -> Result<(), CommitterError> {
         loop {
+            let last_committed_batch = EthClient::get_last_committed_batch(
+                &self.eth_client,
+                self.on_chain_proposer_address,
+            )
+            .await?;
             let last_committed_block = EthClient::get_last_committed_block(
                 &self.eth_client,
                 self.on_chain_proposer_address,
             )
             .await?;
 
-            let block_number_to_fetch = if last_committed_block == u64::MAX {
-                0
-            } else {
-                last_committed_block + 1
-            };
-
-            if let Some(block_to_commit_body) = self
-                .store
-                .get_block_body(block_number_to_fetch)
-                .map_err(CommitterError::from)?
+            if let Some(batch) =
+                self.prepare_batch(last_committed_batch + 1, last_committed_block + 1)?
             {
-                let block_to_commit_header = self
-                    .store
-                    .get_block_header(block_number_to_fetch)
-                    .map_err(CommitterError::from)?
-                    .ok_or(CommitterError::FailedToGetInformationFromStorage(
-                        "Failed to get_block_header() after get_block_body()".to_owned(),
-                    ))?;
-
-                let block_to_commit = Block::new(block_to_commit_header, block_to_commit_body);
-
-                let withdrawals = self.get_block_withdrawals(&block_to_commit)?;
-                let deposits = self.get_block_deposits(&block_to_commit);
-
-                let mut withdrawal_hashes = vec![];
-
-                for (_, tx) in &withdrawals {
-                    let hash = tx
-                        .get_withdrawal_hash()
-                        .ok_or(CommitterError::InvalidWithdrawalTransaction)?;
-                    withdrawal_hashes.push(hash);
-                }
+                let blobs_bundle = self.generate_blobs_bundle(&batch.state_diff)?;
 
-                let withdrawal_logs_merkle_root =
-                    self.get_withdrawals_merkle_root(withdrawal_hashes)?;
-                let deposit_logs_hash = self.get_deposit_hash(
-                    deposits
-                        .iter()
-                        .filter_map(|tx| tx.get_deposit_hash())
-                        .collect(),
-                )?;
-
-                let state_diff = self.prepare_state_diff(
-                    &block_to_commit,
-                    self.store.clone(),
-                    withdrawals,
-                    deposits,
-                )?;
-
-                let blobs_bundle = self.generate_blobs_bundle(&state_diff)?;
-
-                let head_block_hash = block_to_commit.hash();
+                let batch_number = batch.number;
+                let last_block_number = batch.last_block_number;
                 match self
                     .send_commitment(
-                        block_to_commit.header.number,
-                        withdrawal_logs_merkle_root,
-                        deposit_logs_hash,
+                        batch_number,
+                        last_block_number,
+                        batch.withdrawal_logs_merkle_roots,
+                        batch.deposit_logs_hash,
                         blobs_bundle,
                     )
                     .await
                 {
                     Ok(commit_tx_hash) => {
-                        info!("Sent commitment to block {head_block_hash:#x}, with transaction hash {commit_tx_hash:#x}");
+                        info!(
+                            "Sent commitment to batch {batch_number} (blocks {} to {last_block_number}), with transaction hash {commit_tx_hash:#x}",
+                            last_committed_block + 1
+                        );
                     }
                     Err(error) => {
                         return Err(CommitterError::FailedToSendCommitment(format!(
-                            "Failed to send commitment to block {head_block_hash:#x}: {error}"
+                            "Failed to send commitment to batch {batch_number}: {error}"
                         )));
                     }
                 }
@@ -159,6 +145,133 @@ impl Committer {
         }
     }
 
+    /// Gathers the next batch, starting at `first_block_number`.
+    /// Returns `None` if the batch is not ready to be committed yet, that is, if it has less than
+    /// `batch_size` blocks and its first block is more recent than `batch_timeout_ms`.
+    /// A batch is closed earlier if adding another block would make its state diff exceed `MAX_BLOBS_PER_BATCH` blobs.
+    fn prepare_batch(
+        &self,
+        batch_number: u64,
+        first_block_number: BlockNumber,
+    ) -> Result<Option<Batch>, CommitterError> {
+        let latest_block_number = self.store.get_latest_block_number()?;
+        if latest_block_number < first_block_number {
+            return Ok(None);
+        }
+        let last_block_number = latest_block_number.min(first_block_number + self.batch_size - 1);
+
+        let mut blocks = Vec::new();
+        for block_number in first_block_number..=last_block_number {
+            let block_body = self.store.get_block_body(block_number)?.ok_or(
+                CommitterError::FailedToGetInformationFromStorage(format!(
+                    "Failed to get_block_body() of block {block_number}"
+                )),
+            )?;
+            let block_header = self.store.get_block_header(block_number)?.ok_or(
+                CommitterError::FailedToGetInformationFromStorage(
+                    "Failed to get_block_header() after get_block_body()".to_owned(),
+                ),
+            )?;
+            blocks.push(Block::new(block_header, block_body));
+        }
+
+        let first_block_header = &blocks
+            .first()
+            .ok_or(CommitterError::FailedToRetrieveDataFromStorage)?
+            .header;
+        let is_full = last_block_number - first_block_number + 1 == self.batch_size;
+        if !is_full && !self.batch_timed_out(first_block_header) {
+            return Ok(None);
+        }
+        let parent_hash = first_block_header.parent_hash;
+
+        info!(
+            "Preparing state diff for batch {batch_number} starting at block {first_block_number}"
+        );
+
+        // Blocks are executed in order on top of the same state, merging their account updates
+        let mut state = evm_state(self.store.clone(), parent_hash);
+        let mut account_updates: HashMap<Address, AccountUpdate> = HashMap::new();
+        let mut prev_nonces: HashMap<Address, u64> = HashMap::new();
+        let mut withdrawals = Vec::new();
+        let mut deposits = Vec::new();
+        let mut withdrawal_logs_merkle_roots = Vec::new();
+        let mut batch: Option<Batch> = None;
+
+        for block in &blocks {
+            execute_block(block, &mut state).map_err(CommitterError::from)?;
+            for account_update in get_state_transitions(&mut state) {
+                if let Entry::Vacant(entry) = prev_nonces.entry(account_update.address) {
+                    let prev_nonce = self
+                        .store
+                        .get_account_info_by_hash(parent_hash, account_update.address)?
+                        .map(|info| info.nonce)
+                        .unwrap_or_default();
+                    entry.insert(prev_nonce);
+                }
+                merge_account_update(
+                    account_updates
+                        .entry(account_update.address)
+                        .or_insert_with(|| AccountUpdate::new(account_update.address)),
+                    account_update,
+                );
+            }
+
+            let block_withdrawals = self.get_block_withdrawals(block)?;
+            let mut withdrawal_hashes = vec![];
+            for (_, tx) in &block_withdrawals {
+                let hash = tx
+                    .get_withdrawal_hash()
+                    .ok_or(CommitterError::InvalidWithdrawalTransaction)?;
+                withdrawal_hashes.push(hash);
+            }
+            withdrawal_logs_merkle_roots.push(self.get_withdrawals_merkle_root(withdrawal_hashes)?);
+            withdrawals.extend(block_withdrawals);
+            deposits.extend(self.get_block_deposits(block));
+
+            let state_diff = self.prepare_state_diff(
+                &block.header,
+                &account_updates,
+                &prev_nonces,
+                &withdrawals,
+                &deposits,
+            )?;
+            let fits_in_blobs = state_diff
+                .encode()
+                .is_ok_and(|encoded| encoded.len() <= MAX_BLOB_DATA_SIZE * MAX_BLOBS_PER_BATCH);
+            // A first block that doesn't fit on its own is kept, failing when generating the blobs bundle
+            if !fits_in_blobs && batch.is_some() {
+                info!("Closing batch {batch_number} at block {}, as the next block doesn't fit in its blobs", block.header.number - 1);
+                break;
+            }
+
+            let deposit_logs_hash = self.get_deposit_hash(
+                deposits
+                    .iter()
+                    .filter_map(|tx| tx.get_deposit_hash())
+                    .collect(),
+            )?;
+            batch = Some(Batch {
+                number: batch_number,
+                last_block_number: block.header.number,
+                withdrawal_logs_merkle_roots: withdrawal_logs_merkle_roots.clone(),
+                deposit_logs_hash,
+                state_diff,
+            });
+        }
+
+        Ok(batch)
+    }
+
+    /// Returns true if the batch starting with the given block has been waiting for longer than `batch_timeout_ms`
+    fn batch_timed_out(&self, first_block_header: &BlockHeader) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let first_block_age = now.saturating_sub(Duration::from_secs(first_block_header.timestamp));
+        first_block_age >= Duration::from_millis(self.batch_timeout_ms)
+    }
+
     fn get_block_withdrawals(
         &self,
         block: &Block,
@@ -237,45 +350,32 @@ impl Committer {
         }
     }
 
-    /// Prepare the state diff for the block.
+    /// Prepare the cumulative state diff of a batch, given the merged account updates of its blocks
+    /// and the nonces of the modified accounts prior to the batch.
     fn prepare_state_diff(
         &self,
-        block: &Block,
-        store: Store,
-        withdrawals: Vec<(H256, PrivilegedL2Transaction)>,
-        deposits: Vec<PrivilegedL2Transaction>,
+        last_header: &BlockHeader,
+        account_updates: &HashMap<Address, AccountUpdate>,
+        prev_nonces: &HashMap<Address, u64>,
+        withdrawals: &[(H256, PrivilegedL2Transaction)],
+        deposits: &[PrivilegedL2Transaction],
     ) -> Result<StateDiff, CommitterError> {
-        info!("Preparing state diff for block {}", block.header.number);
-
-        let mut state = evm_state(store.clone(), block.header.parent_hash);
-        execute_block(block, &mut state).map_err(CommitterError::from)?;
-        let account_updates = get_state_transitions(&mut state);
-
         let mut modified_accounts = HashMap::new();
-        for account_update in &account_updates {
-            let prev_nonce = match state
-                .database()
-                .ok_or(CommitterError::FailedToRetrieveDataFromStorage)?
-                // If we want the state_diff of a batch, we will have to change the -1 with the `batch_size`
-                // and we may have to keep track of the latestCommittedBlock (last block of the batch),
-                // the batch_size and the latestCommittedBatch in the contract.
-                .get_account_info(block.header.number - 1, account_update.address)
-                .map_err(StoreError::from)?
-            {
-                Some(acc) => acc.nonce,
-                None => 0,
-            };
+        for (address, account_update) in account_updates {
+            let new_info = account_update
+                .info
+                .clone()
+                .ok_or(CommitterError::FailedToRetrieveDataFromStorage)?;
+            let prev_nonce = prev_nonces
+                .get(address)
+                .copied()
+                .ok_or(CommitterError::FailedToRetrieveDataFromStorage)?;
 
             modified_accounts.insert(
-                account_update.address,
+                *address,
                 AccountStateDiff {
-                    new_balance: account_update.info.clone().map(|info| info.balance),
-                    nonce_diff: (account_update
-                        .info
-                        .clone()
-                        .ok_or(CommitterError::FailedToRetrieveDataFromStorage)?
-                        .nonce
-                        - prev_nonce)
+                    new_balance: Some(new_info.balance),
+                    nonce_diff: (new_info.nonce - prev_nonce)
                         .try_into()
                         .map_err(CommitterError::from)?,
                     storage: account_update.added_storage.clone().into_iter().collect(),
@@ -288,6 +388,7 @@ impl Committer {
         let state_diff = StateDiff {
             modified_accounts,
             version: StateDiff::default().version,
+            header: last_header.clone(),
             withdrawal_logs: withdrawals
                 .iter()
                 .map(|(hash, tx)| WithdrawalLog {
@@ -316,38 +417,48 @@ impl Committer {
     }
 
     /// Generate the blob bundle necessary for the EIP-4844 transaction.
+    /// The encoded state diff is split across as many blobs as needed.
     fn generate_blobs_bundle(&self, state_diff: &StateDiff) -> Result<BlobsBundle, CommitterError> {
         let blob_data = state_diff.encode().map_err(CommitterError::from)?;
 
-        let blob = blobs_bundle::blob_from_bytes(blob_data).map_err(CommitterError::from)?;
+        let blobs = blobs_bundle::blobs_from_bytes(&blob_data).map_err(CommitterError::from)?;
+        if blobs.len() > MAX_BLOBS_PER_BATCH {
+            return Err(CommitterError::StateDiffTooLarge(blobs.len()));
+        }
 
-        BlobsBundle::create_from_blobs(&vec![blob]).map_err(CommitterError::from)
+        BlobsBundle::create_from_blobs(&blobs).map_err(CommitterError::from)
     }
 
     async fn send_commitment(
         &self,
-        block_number: u64,
-        withdrawal_logs_merkle_root: H256,
+        batch_number: u64,
+        last_block_number: BlockNumber,
+        withdrawal_logs_merkle_roots: Vec<H256>,
         deposit_logs_hash: H256,
         blobs_bundle: BlobsBundle,
     ) -> Result<H256, CommitterError> {
-        info!("Sending commitment for block {block_number}");
-
-        let mut calldata = Vec::with_capacity(132);
-        calldata.extend(COMMIT_FUNCTION_SELECTOR);
-        let mut block_number_bytes = [0_u8; 32];
-        U256::from(block_number).to_big_endian(&mut block_number_bytes);
-        calldata.extend(block_number_bytes);
-
-        let blob_versioned_hashes = blobs_bundle.generate_versioned_hashes();
-        // We only actually support one versioned hash on the onChainProposer for now,
-        // but eventually this should work if we start sending multiple blobs per commit operation.
-        for blob_versioned_hash in blob_versioned_hashes {
-            let blob_versioned_hash_bytes = blob_versioned_hash.to_fixed_bytes();
-            calldata.extend(blob_versioned_hash_bytes);
+        info!("Sending commitment for batch {batch_number}");
+
+        // The batch commitment is computed by the OnChainProposer from the versioned hashes
+        // of the blobs attached to the transaction.
+        let mut calldata = keccak(COMMIT_FUNCTION_SIGNATURE)
+            .as_bytes()
+            .get(..4)
+            .ok_or(CommitterError::FailedToSendCommitment(
+                "Failed to get commit function selector".to_owned(),
+            ))?
+            .to_vec();
+        calldata.extend(H256::from_low_u64_be(batch_number).as_bytes());
+        calldata.extend(H256::from_low_u64_be(last_block_number).as_bytes());
+        calldata.extend(deposit_logs_hash.as_bytes());
+        // Offset of the withdrawals merkle roots array: 4 head words go before its contents
+        calldata.extend(H256::from_low_u64_be(4 * 32).as_bytes());
+        let mut roots_len = [0_u8; 32];
+        U256::from(withdrawal_logs_merkle_roots.len()).to_big_endian(&mut roots_len);
+        calldata.extend(roots_len);
+        for root in withdrawal_logs_merkle_roots {
+            calldata.extend(root.as_bytes());
         }
-        calldata.extend(withdrawal_logs_merkle_root.0);
-        calldata.extend(deposit_logs_hash.0);
 
         let le_bytes = estimate_blob_gas(
             &self.eth_client,
@@ -393,6 +504,21 @@ impl Committer {
     }
 }
 
+/// Merges the update of an account produced by a block into the updates of the previous blocks of its batch
+fn merge_account_update(merged: &mut AccountUpdate, update: AccountUpdate) {
+    if update.removed {
+        *merged = update;
+        return;
+    }
+    if update.info.is_some() {
+        merged.info = update.info;
+    }
+    if update.code.is_some() {
+        merged.code = update.code;
+    }
+    merged.added_storage.extend(update.added_storage);
+}
+
 /// Estimates the gas price for blob transactions based on the current state of the blockchain.
 ///
 /// # Parameters:
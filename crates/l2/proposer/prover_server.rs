@@ -32,7 +32,7 @@ use risc0_zkvm::sha::{Digest, Digestible};
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ProverInputData {
-    pub block: Block,
+    pub blocks: Vec<Block>,
     pub parent_block_header: BlockHeader,
     pub db: ExecutionDB,
 }
@@ -60,22 +60,22 @@ pub enum ProofData {
     /// The Server responds with a Response containing the ProverInputData.
     /// If the Response will is ProofData::Response{None, None}, the Client knows that the Request couldn't be performed.
     Response {
-        block_number: Option<u64>,
+        batch_number: Option<u64>,
         input: Option<ProverInputData>,
     },
 
     /// 3.
     /// The Client submits the zk Proof generated by the prover
-    /// for the specified block.
+    /// for the specified batch.
     Submit {
-        block_number: u64,
+        batch_number: u64,
         // zk Proof
         receipt: Box<(risc0_zkvm::Receipt, Vec<u32>)>,
     },
 
     /// 4.
     /// The Server acknowledges the receipt of the proof and updates its state,
-    SubmitAck { block_number: u64 },
+    SubmitAck { batch_number: u64 },
 }
 
 impl ProofData {
@@ -85,24 +85,24 @@ impl ProofData {
     }
 
     /// Builder function for creating a Response
-    pub fn response(block_number: Option<u64>, input: Option<ProverInputData>) -> Self {
+    pub fn response(batch_number: Option<u64>, input: Option<ProverInputData>) -> Self {
         ProofData::Response {
-            block_number,
+            batch_number,
             input,
         }
     }
 
     /// Builder function for creating a Submit
-    pub fn submit(block_number: u64, receipt: (risc0_zkvm::Receipt, Vec<u32>)) -> Self {
+    pub fn submit(batch_number: u64, receipt: (risc0_zkvm::Receipt, Vec<u32>)) -> Self {
         ProofData::Submit {
-            block_number,
+            batch_number,
             receipt: Box::new(receipt),
         }
     }
 
     /// Builder function for creating a SubmitAck
-    pub fn submit_ack(block_number: u64) -> Self {
-        ProofData::SubmitAck { block_number }
+    pub fn submit_ack(batch_number: u64) -> Self {
+        ProofData::SubmitAck { batch_number }
     }
 }
 
@@ -234,34 +234,28 @@ impl ProverServer {
     async fn handle_connection(&mut self, mut stream: TcpStream) -> Result<(), ProverServerError> {
         let buf_reader = BufReader::new(&stream);
 
-        let last_verified_block =
-            EthClient::get_last_verified_block(&self.eth_client, self.on_chain_proposer_address)
+        let last_verified_batch =
+            EthClient::get_last_verified_batch(&self.eth_client, self.on_chain_proposer_address)
                 .await?;
 
-        let last_verified_block = if last_verified_block == u64::MAX {
-            0
-        } else {
-            last_verified_block
-        };
-
         let data: Result<ProofData, _> = serde_json::de::from_reader(buf_reader);
         match data {
             Ok(ProofData::Request) => {
-                if let Err(e) = self.handle_request(&stream, last_verified_block + 1).await {
+                if let Err(e) = self.handle_request(&stream, last_verified_batch + 1).await {
                     warn!("Failed to handle request: {e}");
                 }
             }
             Ok(ProofData::Submit {
-                block_number,
+                batch_number,
                 receipt,
             }) => {
-                self.handle_submit(&mut stream, block_number)?;
-
-                self.handle_proof_submission(block_number, receipt).await?;
+                self.handle_submit(&mut stream, batch_number)?;
 
-                if block_number != (last_verified_block + 1) {
-                    return Err(ProverServerError::Custom(format!("Prover Client submitted an invalid block_number: {block_number}. The last_proved_block is: {}", last_verified_block)));
+                if batch_number != (last_verified_batch + 1) {
+                    return Err(ProverServerError::Custom(format!("Prover Client submitted an invalid batch_number: {batch_number}. The last_verified_batch is: {}", last_verified_batch)));
                 }
+
+                self.handle_proof_submission(batch_number, receipt).await?;
             }
             Err(e) => {
                 warn!("Failed to parse request: {e}");
@@ -278,20 +272,22 @@ impl ProverServer {
     async fn handle_request(
         &self,
         stream: &TcpStream,
-        block_number: u64,
+        batch_number: u64,
     ) -> Result<(), ProverServerError> {
         debug!("Request received");
 
-        let latest_block_number = self.store.get_latest_block_number()?;
+        let last_committed_batch =
+            EthClient::get_last_committed_batch(&self.eth_client, self.on_chain_proposer_address)
+                .await?;
 
-        let response = if block_number > latest_block_number {
+        let response = if batch_number > last_committed_batch {
             let response = ProofData::response(None, None);
             warn!("Didn't send response");
             response
         } else {
-            let input = self.create_prover_input(block_number)?;
-            let response = ProofData::response(Some(block_number), Some(input));
-            info!("Sent Response for block_number: {block_number}");
+            let input = self.create_prover_input(batch_number).await?;
+            let response = ProofData::response(Some(batch_number), Some(input));
+            info!("Sent Response for batch_number: {batch_number}");
             response
         };
 
@@ -303,11 +299,11 @@ impl ProverServer {
     fn handle_submit(
         &self,
         stream: &mut TcpStream,
-        block_number: u64,
+        batch_number: u64,
     ) -> Result<(), ProverServerError> {
-        debug!("Submit received for BlockNumber: {block_number}");
+        debug!("Submit received for BatchNumber: {batch_number}");
 
-        let response = ProofData::submit_ack(block_number);
+        let response = ProofData::submit_ack(batch_number);
         let json_string = serde_json::to_string(&response)
             .map_err(|e| ProverServerError::Custom(format!("serde_json::to_string(): {e}")))?;
         stream
@@ -319,7 +315,7 @@ impl ProverServer {
 
     async fn handle_proof_submission(
         &self,
-        block_number: u64,
+        batch_number: u64,
         receipt: Box<(risc0_zkvm::Receipt, Vec<u32>)>,
     ) -> Result<(), ProverServerError> {
         // Send Tx
@@ -356,55 +352,78 @@ impl ProverServer {
 
         let journal_digest = Digestible::digest(&receipt.0.journal);
 
-        self.send_proof(block_number, &seal, image_id, journal_digest)
+        self.send_proof(batch_number, &seal, image_id, journal_digest)
             .await?;
 
         Ok(())
     }
 
-    fn create_prover_input(&self, block_number: u64) -> Result<ProverInputData, ProverServerError> {
-        let header = self
-            .store
-            .get_block_header(block_number)?
-            .ok_or(ProverServerError::StorageDataIsNone)?;
-        let body = self
-            .store
-            .get_block_body(block_number)?
-            .ok_or(ProverServerError::StorageDataIsNone)?;
-
-        let block = Block::new(header, body);
+    /// Builds the input to prove a committed batch.
+    /// Only the batch following the last verified one can be proven, so it starts right after `lastVerifiedBlock`.
+    async fn create_prover_input(
+        &self,
+        batch_number: u64,
+    ) -> Result<ProverInputData, ProverServerError> {
+        let first_block_number =
+            EthClient::get_last_verified_block(&self.eth_client, self.on_chain_proposer_address)
+                .await?
+                + 1;
+        let last_block_number = EthClient::get_batch_last_block_number(
+            &self.eth_client,
+            self.on_chain_proposer_address,
+            batch_number,
+        )
+        .await?;
+
+        let mut blocks = Vec::new();
+        for block_number in first_block_number..=last_block_number {
+            let header = self
+                .store
+                .get_block_header(block_number)?
+                .ok_or(ProverServerError::StorageDataIsNone)?;
+            let body = self
+                .store
+                .get_block_body(block_number)?
+                .ok_or(ProverServerError::StorageDataIsNone)?;
+            blocks.push(Block::new(header, body));
+        }
 
-        let db = ExecutionDB::from_exec(&block, &self.store).map_err(EvmError::ExecutionDB)?;
+        let db = ExecutionDB::from_exec(&blocks, &self.store).map_err(EvmError::ExecutionDB)?;
 
+        let parent_hash = blocks
+            .first()
+            .ok_or(ProverServerError::StorageDataIsNone)?
+            .header
+            .parent_hash;
         let parent_block_header = self
             .store
-            .get_block_header_by_hash(block.header.parent_hash)?
+            .get_block_header_by_hash(parent_hash)?
             .ok_or(ProverServerError::StorageDataIsNone)?;
 
-        debug!("Created prover input for block {block_number}");
+        debug!("Created prover input for batch {batch_number} (blocks {first_block_number} to {last_block_number})");
 
         Ok(ProverInputData {
             db,
-            block,
+            blocks,
             parent_block_header,
         })
     }
 
     pub async fn send_proof(
         &self,
-        block_number: u64,
+        batch_number: u64,
         seal: &[u8],
         image_id: Digest,
         journal_digest: Digest,
     ) -> Result<H256, ProverServerError> {
-        debug!("Sending proof for {block_number}");
+        debug!("Sending proof for batch {batch_number}");
         let mut calldata = Vec::new();
 
         // IOnChainProposer
         // function verify(uint256,bytes,bytes32,bytes32)
         // Verifier
         // function verify(bytes,bytes32,bytes32)
-        // blockNumber, seal, imageId, journalDigest
+        // batchNumber, seal, imageId, journalDigest
         // From crates/l2/contracts/l1/interfaces/IOnChainProposer.sol
         let verify_proof_selector = keccak(b"verify(uint256,bytes,bytes32,bytes32)")
             .as_bytes()
@@ -416,15 +435,15 @@ impl ProverServer {
         calldata.extend(verify_proof_selector);
 
         // The calldata has to be structured in the following way:
-        // block_number
+        // batch_number
         // size in bytes
         // image_id digest
         // journal digest
         // size of seal
         // seal
 
-        // extend with block_number
-        calldata.extend(H256::from_low_u64_be(block_number).as_bytes());
+        // extend with batch_number
+        calldata.extend(H256::from_low_u64_be(batch_number).as_bytes());
 
         // extend with size in bytes
         // 4 u256 goes after this field so: 0x80 == 128bytes == 32bytes * 4
@@ -470,7 +489,7 @@ impl ProverServer {
             )
             .await?;
 
-        info!("Sent proof for block {block_number}, with transaction hash {verify_tx_hash:#x}");
+        info!("Sent proof for batch {batch_number}, with transaction hash {verify_tx_hash:#x}");
 
         Ok(verify_tx_hash)
     }
@@ -479,33 +498,28 @@ impl ProverServer {
         loop {
             thread::sleep(Duration::from_millis(200));
 
-            let last_committed_block = EthClient::get_last_committed_block(
+            let last_committed_batch = EthClient::get_last_committed_batch(
                 &self.eth_client,
                 self.on_chain_proposer_address,
             )
             .await?;
 
-            let last_verified_block = EthClient::get_last_verified_block(
+            let last_verified_batch = EthClient::get_last_verified_batch(
                 &self.eth_client,
                 self.on_chain_proposer_address,
             )
             .await?;
 
-            if last_committed_block == u64::MAX {
-                debug!("No blocks commited yet");
-                continue;
-            }
-
-            if last_committed_block == last_verified_block {
-                debug!("No new blocks to prove");
+            if last_committed_batch == last_verified_batch {
+                debug!("No new batches to prove");
                 continue;
             }
 
-            info!("Last committed: {last_committed_block} - Last verified: {last_verified_block}");
+            info!("Last committed batch: {last_committed_batch} - Last verified batch: {last_verified_batch}");
 
             // IOnChainProposer
             // function verify(uint256,bytes,bytes32,bytes32)
-            // blockNumber, seal, imageId, journalDigest
+            // batchNumber, seal, imageId, journalDigest
             // From crates/l2/contracts/l1/interfaces/IOnChainProposer.sol
             let mut calldata = keccak(b"verify(uint256,bytes,bytes32,bytes32)")
                 .as_bytes()
@@ -514,7 +528,7 @@ impl ProverServer {
                     "Failed to get verify_proof_selector in send_proof()".to_owned(),
                 ))?
                 .to_vec();
-            calldata.extend(H256::from_low_u64_be(last_verified_block + 1).as_bytes());
+            calldata.extend(H256::from_low_u64_be(last_verified_batch + 1).as_bytes());
             calldata.extend(H256::from_low_u64_be(128).as_bytes());
             calldata.extend(H256::zero().as_bytes());
             calldata.extend(H256::zero().as_bytes());
@@ -545,11 +559,11 @@ impl ProverServer {
                 )
                 .await?;
 
-            info!("Sent proof for block {last_verified_block}, with transaction hash {verify_tx_hash:#x}");
+            info!("Sent proof for batch {last_verified_batch}, with transaction hash {verify_tx_hash:#x}");
 
             info!(
-                "Mocked verify transaction sent for block {}",
-                last_verified_block + 1
+                "Mocked verify transaction sent for batch {}",
+                last_verified_batch + 1
             );
         }
     }
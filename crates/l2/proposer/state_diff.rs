@@ -2,10 +2,12 @@ use std::collections::HashMap;
 
 use bytes::Bytes;
 use ethereum_types::{Address, H256, U256};
+use ethrex_core::types::BlockHeader;
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 
 use super::errors::StateDiffError;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct AccountStateDiff {
     pub new_balance: Option<U256>,
     pub nonce_diff: u16,
@@ -22,23 +24,25 @@ pub enum AccountStateDiffType {
     BytecodeHash = 16,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct WithdrawalLog {
     pub address: Address,
     pub amount: U256,
     pub tx_hash: H256,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct DepositLog {
     pub address: Address,
     pub amount: U256,
     pub nonce: u64,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct StateDiff {
     pub version: u8,
+    /// Header of the committed block, used to rebuild the chain and check the state root after applying the diff
+    pub header: BlockHeader,
     pub modified_accounts: HashMap<Address, AccountStateDiff>,
     pub withdrawal_logs: Vec<WithdrawalLog>,
     pub deposit_logs: Vec<DepositLog>,
@@ -63,6 +67,7 @@ impl Default for StateDiff {
     fn default() -> Self {
         StateDiff {
             version: 1,
+            header: BlockHeader::default(),
             modified_accounts: HashMap::new(),
             withdrawal_logs: Vec::new(),
             deposit_logs: Vec::new(),
@@ -87,14 +92,31 @@ impl StateDiff {
         if self.version != 1 {
             return Err(StateDiffError::UnsupportedVersion(self.version));
         }
+        let encoded_header = self.header.encode_to_vec();
+        let header_len: u16 = encoded_header
+            .len()
+            .try_into()
+            .map_err(StateDiffError::from)?;
         let modified_accounts_len: u16 = self
             .modified_accounts
             .len()
             .try_into()
             .map_err(StateDiffError::from)?;
+        let withdrawal_logs_len: u16 = self
+            .withdrawal_logs
+            .len()
+            .try_into()
+            .map_err(StateDiffError::from)?;
+        let deposit_logs_len: u16 = self
+            .deposit_logs
+            .len()
+            .try_into()
+            .map_err(StateDiffError::from)?;
 
         let mut encoded: Vec<u8> = Vec::new();
         encoded.push(self.version);
+        encoded.extend(header_len.to_be_bytes());
+        encoded.extend(encoded_header);
         encoded.extend(modified_accounts_len.to_be_bytes());
 
         for (address, diff) in &self.modified_accounts {
@@ -104,6 +126,7 @@ impl StateDiff {
             encoded.extend(diff_encoded);
         }
 
+        encoded.extend(withdrawal_logs_len.to_be_bytes());
         for withdrawal in self.withdrawal_logs.iter() {
             encoded.extend(withdrawal.address.0);
             let buf = &mut [0u8; 32];
@@ -112,18 +135,69 @@ impl StateDiff {
             encoded.extend(&withdrawal.tx_hash.0);
         }
 
+        encoded.extend(deposit_logs_len.to_be_bytes());
         for deposit in self.deposit_logs.iter() {
             encoded.extend(deposit.address.0);
             let buf = &mut [0u8; 32];
             deposit.amount.to_big_endian(buf);
             encoded.extend_from_slice(buf);
+            encoded.extend(deposit.nonce.to_be_bytes());
         }
 
         Ok(Bytes::from(encoded))
     }
 
-    pub fn decode() -> Result<Self, String> {
-        unimplemented!()
+    /// Decodes a state diff encoded by `StateDiff::encode`
+    /// Trailing bytes are ignored, as the data read from a blob keeps the blob's zero padding
+    pub fn decode(bytes: &[u8]) -> Result<Self, StateDiffError> {
+        let mut decoder = Decoder::new(bytes);
+
+        let version = decoder.get_u8()?;
+        if version != 1 {
+            return Err(StateDiffError::UnsupportedVersion(version));
+        }
+
+        let header_len = decoder.get_u16()?;
+        let header = BlockHeader::decode(decoder.get_bytes(header_len.into())?).map_err(|err| {
+            StateDiffError::FailedToDeserializeStateDiff(format!("Invalid block header: {err}"))
+        })?;
+
+        let modified_accounts_len = decoder.get_u16()?;
+        let mut modified_accounts = HashMap::with_capacity(modified_accounts_len.into());
+        for _ in 0..modified_accounts_len {
+            let r#type = decoder.get_u8()?;
+            let address = decoder.get_address()?;
+            let diff = AccountStateDiff::decode(r#type, &mut decoder)?;
+            modified_accounts.insert(address, diff);
+        }
+
+        let withdrawal_logs_len = decoder.get_u16()?;
+        let mut withdrawal_logs = Vec::with_capacity(withdrawal_logs_len.into());
+        for _ in 0..withdrawal_logs_len {
+            withdrawal_logs.push(WithdrawalLog {
+                address: decoder.get_address()?,
+                amount: decoder.get_u256()?,
+                tx_hash: decoder.get_h256()?,
+            });
+        }
+
+        let deposit_logs_len = decoder.get_u16()?;
+        let mut deposit_logs = Vec::with_capacity(deposit_logs_len.into());
+        for _ in 0..deposit_logs_len {
+            deposit_logs.push(DepositLog {
+                address: decoder.get_address()?,
+                amount: decoder.get_u256()?,
+                nonce: decoder.get_u64()?,
+            });
+        }
+
+        Ok(StateDiff {
+            version,
+            header,
+            modified_accounts,
+            withdrawal_logs,
+            deposit_logs,
+        })
     }
 }
 
@@ -169,11 +243,7 @@ impl AccountStateDiff {
 
         if let Some(bytecode) = &self.bytecode {
             let r_type: u8 = AccountStateDiffType::Bytecode.into();
-            let bytecode_len: u16 = self
-                .storage
-                .len()
-                .try_into()
-                .map_err(StateDiffError::from)?;
+            let bytecode_len: u16 = bytecode.len().try_into().map_err(StateDiffError::from)?;
             r#type += r_type;
             encoded.extend(bytecode_len.to_be_bytes());
             encoded.extend(bytecode);
@@ -191,4 +261,193 @@ impl AccountStateDiff {
 
         Ok((r#type, Bytes::from(encoded)))
     }
+
+    /// Decodes the fields flagged in `r#type`, in the same order as `AccountStateDiff::encode` writes them
+    fn decode(r#type: u8, decoder: &mut Decoder) -> Result<Self, StateDiffError> {
+        let has_field = |field: AccountStateDiffType| r#type & u8::from(field) != 0;
+        let all_fields = [
+            AccountStateDiffType::NewBalance,
+            AccountStateDiffType::NonceDiff,
+            AccountStateDiffType::Storage,
+            AccountStateDiffType::Bytecode,
+            AccountStateDiffType::BytecodeHash,
+        ]
+        .into_iter()
+        .fold(0, |flags, field| flags | u8::from(field));
+        if r#type == 0 || r#type & !all_fields != 0 {
+            return Err(StateDiffError::InvalidAccountStateDiffType(r#type));
+        }
+
+        let new_balance = if has_field(AccountStateDiffType::NewBalance) {
+            Some(decoder.get_u256()?)
+        } else {
+            None
+        };
+
+        let nonce_diff = if has_field(AccountStateDiffType::NonceDiff) {
+            decoder.get_u16()?
+        } else {
+            0
+        };
+
+        let mut storage = Vec::new();
+        if has_field(AccountStateDiffType::Storage) {
+            let storage_len = decoder.get_u16()?;
+            for _ in 0..storage_len {
+                storage.push((decoder.get_h256()?, decoder.get_u256()?));
+            }
+        }
+
+        let bytecode = if has_field(AccountStateDiffType::Bytecode) {
+            let bytecode_len = decoder.get_u16()?;
+            Some(Bytes::copy_from_slice(
+                decoder.get_bytes(bytecode_len.into())?,
+            ))
+        } else {
+            None
+        };
+
+        let bytecode_hash = if has_field(AccountStateDiffType::BytecodeHash) {
+            Some(decoder.get_h256()?)
+        } else {
+            None
+        };
+
+        if bytecode.is_some() && bytecode_hash.is_some() {
+            return Err(StateDiffError::BytecodeAndBytecodeHashSet);
+        }
+
+        Ok(AccountStateDiff {
+            new_balance,
+            nonce_diff,
+            storage,
+            bytecode,
+            bytecode_hash,
+        })
+    }
+}
+
+/// Reads the big-endian fields of an encoded state diff sequentially
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn get_bytes(&mut self, len: usize) -> Result<&'a [u8], StateDiffError> {
+        let end = self.offset.saturating_add(len);
+        let bytes = self.bytes.get(self.offset..end).ok_or_else(|| {
+            StateDiffError::FailedToDeserializeStateDiff(format!(
+                "Unexpected end of data at offset {}",
+                self.offset
+            ))
+        })?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn get_array<const N: usize>(&mut self) -> Result<[u8; N], StateDiffError> {
+        self.get_bytes(N)?
+            .try_into()
+            .map_err(|err| StateDiffError::FailedToDeserializeStateDiff(format!("{err}")))
+    }
+
+    fn get_u8(&mut self) -> Result<u8, StateDiffError> {
+        Ok(u8::from_be_bytes(self.get_array()?))
+    }
+
+    fn get_u16(&mut self) -> Result<u16, StateDiffError> {
+        Ok(u16::from_be_bytes(self.get_array()?))
+    }
+
+    fn get_u64(&mut self) -> Result<u64, StateDiffError> {
+        Ok(u64::from_be_bytes(self.get_array()?))
+    }
+
+    fn get_u256(&mut self) -> Result<U256, StateDiffError> {
+        Ok(U256::from_big_endian(&self.get_array::<32>()?))
+    }
+
+    fn get_h256(&mut self) -> Result<H256, StateDiffError> {
+        Ok(H256::from(self.get_array::<32>()?))
+    }
+
+    fn get_address(&mut self) -> Result<Address, StateDiffError> {
+        Ok(Address::from(self.get_array::<20>()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_core::types::blobs_bundle::{blob_from_bytes, bytes_from_blob};
+
+    fn test_state_diff() -> StateDiff {
+        let mut modified_accounts = HashMap::new();
+        modified_accounts.insert(
+            Address::from_low_u64_be(1),
+            AccountStateDiff {
+                new_balance: Some(U256::from(1_000_000_000_u64)),
+                nonce_diff: 3,
+                storage: vec![
+                    (H256::from_low_u64_be(1), U256::from(7)),
+                    (H256::from_low_u64_be(2), U256::MAX),
+                ],
+                bytecode: Some(Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xf3])),
+                bytecode_hash: None,
+            },
+        );
+        modified_accounts.insert(
+            Address::from_low_u64_be(2),
+            AccountStateDiff {
+                new_balance: None,
+                nonce_diff: 0,
+                storage: vec![],
+                bytecode: None,
+                bytecode_hash: Some(H256::from_low_u64_be(3)),
+            },
+        );
+        StateDiff {
+            version: 1,
+            header: BlockHeader {
+                number: 5,
+                state_root: H256::from_low_u64_be(10),
+                base_fee_per_gas: Some(7),
+                ..Default::default()
+            },
+            modified_accounts,
+            withdrawal_logs: vec![WithdrawalLog {
+                address: Address::from_low_u64_be(3),
+                amount: U256::from(42),
+                tx_hash: H256::from_low_u64_be(4),
+            }],
+            deposit_logs: vec![DepositLog {
+                address: Address::from_low_u64_be(4),
+                amount: U256::from(43),
+                nonce: 8,
+            }],
+        }
+    }
+
+    #[test]
+    fn decode_blob_encoded_state_diff() {
+        let state_diff = test_state_diff();
+        let blob = blob_from_bytes(state_diff.encode().unwrap()).unwrap();
+        let decoded = StateDiff::decode(&bytes_from_blob(&blob)).unwrap();
+        assert_eq!(decoded, state_diff);
+    }
+
+    #[test]
+    fn decode_truncated_state_diff_fails() {
+        let encoded = test_state_diff().encode().unwrap();
+        let truncated = encoded.get(..encoded.len() - 1).unwrap();
+        assert!(matches!(
+            StateDiff::decode(truncated),
+            Err(StateDiffError::FailedToDeserializeStateDiff(_))
+        ));
+    }
 }
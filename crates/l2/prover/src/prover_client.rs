@@ -35,11 +35,11 @@ impl ProverClient {
 
         loop {
             match self.request_new_input() {
-                Ok((block_number, input)) => {
+                Ok((batch_number, input)) => {
                     match prover.prove(input) {
                         Ok(proof) => {
                             if let Err(e) =
-                                self.submit_proof(block_number, proof, prover.id.to_vec())
+                                self.submit_proof(batch_number, proof, prover.id.to_vec())
                             {
                                 // TODO: Retry?
                                 warn!("Failed to submit proof: {e}");
@@ -57,26 +57,26 @@ impl ProverClient {
     }
 
     fn request_new_input(&self) -> Result<(u64, ProgramInput), String> {
-        // Request the input with the correct block_number
+        // Request the input with the correct batch_number
         let request = ProofData::request();
         let response = connect_to_prover_server_wr(&self.prover_server_endpoint, &request)
             .map_err(|e| format!("Failed to get Response: {e}"))?;
 
         match response {
             ProofData::Response {
-                block_number,
+                batch_number,
                 input,
-            } => match (block_number, input) {
+            } => match (batch_number, input) {
                 (Some(n), Some(i)) => {
-                    info!("Received Response for block_number: {n}");
+                    info!("Received Response for batch_number: {n}");
                     Ok((n, ProgramInput {
-                        block: i.block,
+                        blocks: i.blocks,
                         parent_block_header: i.parent_block_header,
                         db: i.db
                     }))
                 }
                 _ => Err(
-                    "Received Empty Response, meaning that the ProverServer doesn't have blocks to prove.\nThe Prover may be advancing faster than the Proposer."
+                    "Received Empty Response, meaning that the ProverServer doesn't have batches to prove.\nThe Prover may be advancing faster than the Proposer."
                         .to_owned(),
                 ),
             },
@@ -86,17 +86,17 @@ impl ProverClient {
 
     fn submit_proof(
         &self,
-        block_number: u64,
+        batch_number: u64,
         receipt: risc0_zkvm::Receipt,
         prover_id: Vec<u32>,
     ) -> Result<(), String> {
-        let submit = ProofData::submit(block_number, (receipt, prover_id));
+        let submit = ProofData::submit(batch_number, (receipt, prover_id));
         let submit_ack = connect_to_prover_server_wr(&self.prover_server_endpoint, &submit)
             .map_err(|e| format!("Failed to get SubmitAck: {e}"))?;
 
         match submit_ack {
-            ProofData::SubmitAck { block_number } => {
-                info!("Received submit ack for block_number: {}", block_number);
+            ProofData::SubmitAck { batch_number } => {
+                info!("Received submit ack for batch_number: {}", batch_number);
                 Ok(())
             }
             _ => Err(format!("Expecting ProofData::SubmitAck {submit_ack:?}")),
@@ -33,7 +33,7 @@ async fn test_performance_zkvm() {
     }
     let block_to_prove = blocks.last().unwrap();
 
-    let db = ExecutionDB::from_exec(block_to_prove, &store).unwrap();
+    let db = ExecutionDB::from_exec(std::slice::from_ref(block_to_prove), &store).unwrap();
 
     let parent_block_header = store
         .get_block_header_by_hash(block_to_prove.header.parent_hash)
@@ -41,7 +41,7 @@ async fn test_performance_zkvm() {
         .unwrap();
 
     let input = ProgramInput {
-        block: block_to_prove.clone(),
+        blocks: vec![block_to_prove.clone()],
         parent_block_header,
         db,
     };
@@ -9,15 +9,12 @@ use zkvm_interface::{
 
 fn main() {
     let ProgramInput {
-        block,
+        blocks,
         parent_block_header,
         db,
     } = env::read();
     let mut state = EvmState::from(db.clone());
 
-    // Validate the block pre-execution
-    validate_block(&block, &parent_block_header, &state).expect("invalid block");
-
     // Validate the initial state
     let (mut state_trie, mut storage_tries) = db
         .build_tries()
@@ -28,16 +25,27 @@ fn main() {
         panic!("invalid initial state trie");
     }
 
-    let receipts = execute_block(&block, &mut state).expect("failed to execute block");
-    validate_gas_used(&receipts, &block.header).expect("invalid gas used");
+    // Execute the batch, every block on top of the state left by the previous one
+    let mut parent_header = &parent_block_header;
+    let mut cumulative_gas_used = 0;
+    for block in &blocks {
+        // Validate the block pre-execution
+        validate_block(block, parent_header, &state).expect("invalid block");
+
+        let receipts = execute_block(block, &mut state).expect("failed to execute block");
+        validate_gas_used(&receipts, &block.header).expect("invalid gas used");
 
-    env::write(
-        &receipts
+        // Blocks without transactions have no receipts
+        cumulative_gas_used += receipts
             .last()
-            .expect("no receipts found")
-            .cumulative_gas_used,
-    );
+            .map(|receipt| receipt.cumulative_gas_used)
+            .unwrap_or_default();
+        parent_header = &block.header;
+    }
+
+    env::write(&cumulative_gas_used);
 
+    // The transitions are accumulated by the execution state, so they hold the changes of the whole batch
     let account_updates = get_state_transitions(&mut state);
 
     // Update tries and calculate final state root hash
@@ -45,7 +53,7 @@ fn main() {
         .expect("failed to update state and storage tries");
     let final_state_hash = state_trie.hash_no_commit();
 
-    if final_state_hash != block.header.state_root {
+    if final_state_hash != parent_header.state_root {
         panic!("invalid final state trie");
     }
 
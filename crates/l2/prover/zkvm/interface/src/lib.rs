@@ -22,10 +22,10 @@ pub mod io {
     #[serde_as]
     #[derive(Serialize, Deserialize)]
     pub struct ProgramInput {
-        /// block to execute
-        #[serde_as(as = "RLPBlock")]
-        pub block: Block,
-        /// header of the previous block
+        /// batch of consecutive blocks to execute
+        #[serde_as(as = "Vec<RLPBlock>")]
+        pub blocks: Vec<Block>,
+        /// header of the block previous to the batch
         pub parent_block_header: BlockHeader,
         /// database containing only the data necessary to execute
         pub db: ExecutionDB,
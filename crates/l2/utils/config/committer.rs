@@ -13,6 +13,11 @@ pub struct CommitterConfig {
     pub l1_private_key: SecretKey,
     pub interval_ms: u64,
     pub arbitrary_base_blob_gas_price: u64,
+    /// Maximum number of blocks committed together in a single batch
+    pub batch_size: u64,
+    /// Time after which a batch is committed even if it has less than `batch_size` blocks,
+    /// measured from the timestamp of its first block
+    pub batch_timeout_ms: u64,
 }
 
 impl CommitterConfig {
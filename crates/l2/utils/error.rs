@@ -1,4 +1,7 @@
+use crate::proposer::errors::StateDiffError;
+use crate::utils::eth_client::errors::EthClientError;
 use ethrex_blockchain::error::ChainError;
+use ethrex_core::types::BlobsBundleError;
 use ethrex_storage::error::StoreError;
 use ethrex_vm::errors::ExecutionDBError;
 use keccak_hash::H256;
@@ -16,3 +19,41 @@ pub enum ProverInputError {
     #[error("ExecutionDB error: {0}")]
     ExecutionDBError(#[from] ExecutionDBError),
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum StateReconstructionError {
+    #[error("StateReconstruction failed because of an EthClient error: {0}")]
+    EthClientError(#[from] EthClientError),
+    #[error("StateReconstruction failed because of a Store error: {0}")]
+    StoreError(#[from] StoreError),
+    #[error("StateReconstruction failed to decode state diff: {0}")]
+    StateDiffError(#[from] StateDiffError),
+    #[error("StateReconstruction failed to compute blob commitment: {0}")]
+    BlobsBundleError(#[from] BlobsBundleError),
+    #[error("StateReconstruction failed to read archived blob: {0}")]
+    FailedToReadBlob(#[from] std::io::Error),
+    #[error("StateReconstruction beacon request failed: {0}")]
+    BeaconRequestError(#[from] reqwest::Error),
+    #[error("StateReconstruction found an invalid blob: {0}")]
+    InvalidBlob(String),
+    #[error("StateReconstruction could not find the blobs of batch {0}")]
+    MissingBlob(u64),
+    #[error("StateReconstruction found blobs not matching the commitment of batch {0}")]
+    BlobCommitmentMismatch(u64),
+    #[error("StateReconstruction found that the stored block {0} is not the last block of a committed batch")]
+    UnalignedStoreHead(u64),
+    #[error("StateReconstruction expected the state diff of block {expected} but got block {got}")]
+    UnexpectedBlockNumber { expected: u64, got: u64 },
+    #[error("StateReconstruction found a block hash mismatch for stored block {0}")]
+    BlockHashMismatch(u64),
+    #[error("StateReconstruction is missing the parent of block {0}")]
+    UnknownParent(u64),
+    #[error("StateReconstruction state root mismatch in block {block_number}: expected {expected:#x}, computed {computed:#x}")]
+    StateRootMismatch {
+        block_number: u64,
+        expected: H256,
+        computed: H256,
+    },
+    #[error("StateReconstruction found an invalid state diff: {0}")]
+    InvalidStateDiff(String),
+}
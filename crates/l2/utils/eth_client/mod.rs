@@ -13,6 +13,7 @@ use ethrex_core::{
     types::{
         BlobsBundle, EIP1559Transaction, EIP4844Transaction, GenericTransaction,
         PrivilegedL2Transaction, PrivilegedTxType, Signable, TxKind, TxType,
+        WrappedEIP4844Transaction,
     },
     H160,
 };
@@ -21,7 +22,6 @@ use ethrex_rpc::{
     types::{
         block::RpcBlock,
         receipt::{RpcLog, RpcReceipt},
-        transaction::WrappedEIP4844Transaction,
     },
     utils::{RpcErrorResponse, RpcRequest, RpcRequestId, RpcSuccessResponse},
 };
@@ -986,6 +986,62 @@ impl EthClient {
         .await
     }
 
+    pub async fn get_last_committed_batch(
+        eth_client: &EthClient,
+        on_chain_proposer_address: Address,
+    ) -> Result<u64, EthClientError> {
+        Self::_call_block_variable(
+            eth_client,
+            b"lastCommittedBatch()",
+            on_chain_proposer_address,
+        )
+        .await
+    }
+
+    pub async fn get_last_verified_batch(
+        eth_client: &EthClient,
+        on_chain_proposer_address: Address,
+    ) -> Result<u64, EthClientError> {
+        Self::_call_block_variable(
+            eth_client,
+            b"lastVerifiedBatch()",
+            on_chain_proposer_address,
+        )
+        .await
+    }
+
+    /// Returns the number of the last block of a committed batch.
+    /// The commitment of a batch is deleted once the following batch is verified.
+    pub async fn get_batch_last_block_number(
+        eth_client: &EthClient,
+        on_chain_proposer_address: Address,
+        batch_number: u64,
+    ) -> Result<u64, EthClientError> {
+        let mut calldata = keccak(b"batchCommitments(uint256)")
+            .as_bytes()
+            .get(..4)
+            .ok_or(EthClientError::Custom("Failed to get selector.".to_owned()))?
+            .to_vec();
+        calldata.extend(H256::from_low_u64_be(batch_number).as_bytes());
+
+        let hex_string = eth_client
+            .call(
+                on_chain_proposer_address,
+                calldata.into(),
+                Overrides::default(),
+            )
+            .await?;
+        let encoded = hex::decode(hex_string.trim_start_matches("0x")).map_err(|err| {
+            EthClientError::Custom(format!("Failed to decode batchCommitments(): {err}"))
+        })?;
+
+        // The returned tuple is (commitmentHash, depositLogs, lastBlockNumber)
+        let last_block_number = encoded.get(64..96).ok_or(EthClientError::Custom(
+            "Failed to fetch batchCommitments(): unexpected output length".to_owned(),
+        ))?;
+        Ok(U256::from_big_endian(last_block_number).as_u64())
+    }
+
     pub async fn get_last_fetched_l1_block(
         eth_client: &EthClient,
         common_bridge_address: Address,
@@ -0,0 +1,497 @@
+use std::path::PathBuf;
+
+use bytes::Bytes;
+use ethereum_types::{Address, H256, U256};
+use ethrex_core::{
+    serde_utils,
+    types::{
+        blobs_bundle::{bytes_from_blob, kzg_commitment_to_versioned_hash},
+        AccountInfo, Blob, BlobsBundle, BlockHash, BlockNumber, Commitment,
+    },
+};
+use ethrex_storage::{AccountUpdate, Store};
+use keccak_hash::keccak;
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::{debug, info};
+
+use ethrex_rpc::types::receipt::RpcLog;
+
+use crate::{
+    proposer::state_diff::StateDiff,
+    utils::{
+        error::StateReconstructionError,
+        eth_client::{BlockByNumber, EthClient},
+    },
+};
+
+/// Maximum amount of L1 blocks queried at once when looking for batch commitments
+const MAX_BLOCK_STEP: u64 = 5000;
+
+/// Source of the blobs posted to L1 by the committer
+pub enum BlobSource {
+    /// Directory holding each committed raw blob as `<versioned hash>.blob`
+    Archive(PathBuf),
+    /// Beacon node API (or a stand-in serving the same endpoints) holding the blob sidecars of the commit transactions
+    Beacon(BeaconClient),
+}
+
+#[derive(Deserialize)]
+struct BeaconResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct BeaconGenesis {
+    #[serde(deserialize_with = "serde_utils::u64::deser_dec_str")]
+    genesis_time: u64,
+}
+
+#[derive(Deserialize)]
+struct BeaconSpec {
+    #[serde(
+        rename = "SECONDS_PER_SLOT",
+        deserialize_with = "serde_utils::u64::deser_dec_str"
+    )]
+    seconds_per_slot: u64,
+}
+
+#[derive(Deserialize)]
+struct BlobSidecar {
+    #[serde(with = "serde_utils::bytes")]
+    blob: Bytes,
+    #[serde(with = "serde_utils::bytes")]
+    kzg_commitment: Bytes,
+}
+
+/// Minimal client of the beacon node API, only used to fetch blob sidecars
+pub struct BeaconClient {
+    client: Client,
+    url: String,
+    genesis_time: u64,
+    seconds_per_slot: u64,
+}
+
+impl BeaconClient {
+    pub async fn new(url: &str) -> Result<Self, StateReconstructionError> {
+        let mut beacon_client = Self {
+            client: Client::new(),
+            url: url.trim_end_matches('/').to_owned(),
+            genesis_time: 0,
+            seconds_per_slot: 0,
+        };
+        let genesis: BeaconGenesis = beacon_client.get("/eth/v1/beacon/genesis").await?;
+        let spec: BeaconSpec = beacon_client.get("/eth/v1/config/spec").await?;
+        if spec.seconds_per_slot == 0 {
+            return Err(StateReconstructionError::InvalidBlob(
+                "Beacon node reported a slot duration of zero seconds".to_owned(),
+            ));
+        }
+        beacon_client.genesis_time = genesis.genesis_time;
+        beacon_client.seconds_per_slot = spec.seconds_per_slot;
+        Ok(beacon_client)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, StateReconstructionError> {
+        let response: BeaconResponse<T> = self
+            .client
+            .get(format!("{}{path}", self.url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.data)
+    }
+
+    /// Returns the blob matching the versioned hash among the sidecars of the slot in which the L1 block was proposed
+    async fn get_blob(
+        &self,
+        l1_block_timestamp: u64,
+        versioned_hash: H256,
+    ) -> Result<Option<Blob>, StateReconstructionError> {
+        let slot = l1_block_timestamp.saturating_sub(self.genesis_time) / self.seconds_per_slot;
+        let sidecars: Vec<BlobSidecar> = self
+            .get(&format!("/eth/v1/beacon/blob_sidecars/{slot}"))
+            .await?;
+        for sidecar in sidecars {
+            let commitment: Commitment =
+                sidecar.kzg_commitment.as_ref().try_into().map_err(|_| {
+                    StateReconstructionError::InvalidBlob(format!(
+                        "Invalid KZG commitment length {}",
+                        sidecar.kzg_commitment.len()
+                    ))
+                })?;
+            if kzg_commitment_to_versioned_hash(&commitment) == versioned_hash {
+                return blob_from_slice(&sidecar.blob).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn blob_from_slice(data: &[u8]) -> Result<Blob, StateReconstructionError> {
+    data.try_into().map_err(|_| {
+        StateReconstructionError::InvalidBlob(format!("Invalid blob length {}", data.len()))
+    })
+}
+
+/// Commitment of a batch of L2 blocks found in the L1
+struct BatchCommitment {
+    number: u64,
+    first_block_number: BlockNumber,
+    last_block_number: BlockNumber,
+    /// Versioned hashes of the blobs carrying the batch's state diff, in order
+    versioned_hashes: Vec<H256>,
+    l1_block_number: BlockNumber,
+}
+
+impl BatchCommitment {
+    /// Parses a `BatchCommitted(uint256 indexed,uint256,uint256,bytes32[])` log
+    fn from_log(log: &RpcLog) -> Result<Self, StateReconstructionError> {
+        let invalid_log = || {
+            StateReconstructionError::InvalidBlob(format!(
+                "Malformed BatchCommitted log {:#x}",
+                log.transaction_hash
+            ))
+        };
+        let number = log.log.topics.get(1).ok_or_else(invalid_log)?;
+        let data = &log.log.data;
+        let word = |index: usize| {
+            data.get(index * 32..(index + 1) * 32)
+                .map(H256::from_slice)
+                .ok_or_else(invalid_log)
+        };
+        // Data layout: firstBlockNumber, lastBlockNumber, offset of the hashes array, its length and its contents
+        let hashes_len =
+            usize::try_from(U256::from(word(3)?.as_bytes())).map_err(|_| invalid_log())?;
+        let versioned_hashes = (4..hashes_len.saturating_add(4))
+            .map(word)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            number: U256::from(number.as_bytes()).as_u64(),
+            first_block_number: U256::from(word(0)?.as_bytes()).as_u64(),
+            last_block_number: U256::from(word(1)?.as_bytes()).as_u64(),
+            versioned_hashes,
+            l1_block_number: log.block_number,
+        })
+    }
+}
+
+/// Rebuilds the L2 state from the state diffs the committer posts in blobs
+pub struct StateReconstructor {
+    eth_client: EthClient,
+    on_chain_proposer_address: Address,
+    blob_source: BlobSource,
+    store: Store,
+}
+
+impl StateReconstructor {
+    /// The store must already contain the L2 genesis state
+    pub fn new(
+        eth_client: EthClient,
+        on_chain_proposer_address: Address,
+        blob_source: BlobSource,
+        store: Store,
+    ) -> Self {
+        Self {
+            eth_client,
+            on_chain_proposer_address,
+            blob_source,
+            store,
+        }
+    }
+
+    /// Applies the state diff of every batch committed to the OnChainProposer that is not yet in the store,
+    /// checking the resulting state root of each one of them
+    /// As a batch's state diff only carries its last header, only the last block of each batch is stored
+    /// Returns the number of the last reconstructed block
+    pub async fn reconstruct(&self) -> Result<BlockNumber, StateReconstructionError> {
+        let latest_block_number = self.store.get_latest_block_number()?;
+        let last_committed_batch =
+            EthClient::get_last_committed_batch(&self.eth_client, self.on_chain_proposer_address)
+                .await?;
+        if last_committed_batch == 0 {
+            info!("No batches have been committed yet");
+            return Ok(latest_block_number);
+        }
+
+        let commitments = self.get_batch_commitments(last_committed_batch).await?;
+        let mut last_block_number = latest_block_number;
+        for commitment in &commitments {
+            if commitment.last_block_number < last_block_number {
+                continue;
+            }
+            let is_stored = commitment.last_block_number == last_block_number;
+            if !is_stored && commitment.first_block_number != last_block_number.saturating_add(1) {
+                return Err(StateReconstructionError::UnalignedStoreHead(
+                    last_block_number,
+                ));
+            }
+            let state_diff = self.get_state_diff(commitment).await?;
+            if state_diff.header.number != commitment.last_block_number {
+                return Err(StateReconstructionError::UnexpectedBlockNumber {
+                    expected: commitment.last_block_number,
+                    got: state_diff.header.number,
+                });
+            }
+            // The state diff of the batch ending at the latest stored block is only used to check that the store belongs to the same chain
+            if is_stored {
+                let stored_hash = self.store.get_canonical_block_hash(last_block_number)?;
+                if stored_hash != Some(state_diff.header.compute_block_hash()) {
+                    return Err(StateReconstructionError::BlockHashMismatch(
+                        last_block_number,
+                    ));
+                }
+                continue;
+            }
+            let parent_hash = self
+                .store
+                .get_canonical_block_hash(last_block_number)?
+                .ok_or(StateReconstructionError::UnknownParent(
+                    commitment.first_block_number,
+                ))?;
+            let block_hash = apply_state_diff(&self.store, parent_hash, &state_diff)?;
+            last_block_number = commitment.last_block_number;
+            info!(
+                "Reconstructed batch {} up to block {last_block_number} with hash {block_hash:#x}",
+                commitment.number
+            );
+        }
+        Ok(last_block_number)
+    }
+
+    /// Returns the commitments of batches `1..=last_committed_batch`, in order
+    /// The OnChainProposer only accepts commitments for the batch following the last committed one,
+    /// so the n-th `BatchCommitted` event always belongs to batch n
+    async fn get_batch_commitments(
+        &self,
+        last_committed_batch: u64,
+    ) -> Result<Vec<BatchCommitment>, StateReconstructionError> {
+        let topic = keccak(b"BatchCommitted(uint256,uint256,uint256,bytes32[])");
+        let current_block = self.eth_client.get_block_number().await?.as_u64();
+        let last_committed_batch = usize::try_from(last_committed_batch).unwrap_or(usize::MAX);
+        let mut commitments = Vec::new();
+        let mut from_block = 0;
+        while from_block <= current_block && commitments.len() < last_committed_batch {
+            let to_block = from_block
+                .saturating_add(MAX_BLOCK_STEP - 1)
+                .min(current_block);
+            debug!("Looking for batch commitments from L1 block {from_block} to {to_block}");
+            let logs = self
+                .eth_client
+                .get_logs(
+                    U256::from(from_block),
+                    U256::from(to_block),
+                    self.on_chain_proposer_address,
+                    topic,
+                )
+                .await?;
+            for log in logs.iter().filter(|log| !log.removed) {
+                let commitment = BatchCommitment::from_log(log)?;
+                let expected_number = commitments.len().saturating_add(1);
+                if usize::try_from(commitment.number).ok() != Some(expected_number) {
+                    return Err(StateReconstructionError::InvalidBlob(format!(
+                        "Expected the commitment of batch {expected_number} but got batch {}",
+                        commitment.number
+                    )));
+                }
+                commitments.push(commitment);
+            }
+            from_block = to_block.saturating_add(1);
+        }
+        if commitments.len() < last_committed_batch {
+            return Err(StateReconstructionError::MissingBlob(
+                u64::try_from(commitments.len().saturating_add(1)).unwrap_or(u64::MAX),
+            ));
+        }
+        Ok(commitments)
+    }
+
+    /// Fetches the blobs committed for the batch, checks them against its commitment and decodes its state diff
+    async fn get_state_diff(
+        &self,
+        commitment: &BatchCommitment,
+    ) -> Result<StateDiff, StateReconstructionError> {
+        let mut blobs = Vec::with_capacity(commitment.versioned_hashes.len());
+        for versioned_hash in &commitment.versioned_hashes {
+            let blob = match &self.blob_source {
+                BlobSource::Archive(path) => {
+                    let data = std::fs::read(path.join(format!("{versioned_hash:#x}.blob")))?;
+                    blob_from_slice(&data)?
+                }
+                BlobSource::Beacon(beacon_client) => {
+                    let l1_block = self
+                        .eth_client
+                        .get_block_by_number(BlockByNumber::Number(commitment.l1_block_number))
+                        .await?;
+                    beacon_client
+                        .get_blob(l1_block.header.timestamp, *versioned_hash)
+                        .await?
+                        .ok_or(StateReconstructionError::MissingBlob(commitment.number))?
+                }
+            };
+            blobs.push(blob);
+        }
+
+        let blobs_bundle = BlobsBundle::create_from_blobs(&blobs)?;
+        if blobs_bundle.generate_versioned_hashes() != commitment.versioned_hashes {
+            return Err(StateReconstructionError::BlobCommitmentMismatch(
+                commitment.number,
+            ));
+        }
+        // The state diff is split across the blobs in order
+        let encoded: Vec<u8> = blobs.iter().flat_map(bytes_from_blob).collect();
+        Ok(StateDiff::decode(&encoded)?)
+    }
+}
+
+/// Applies a batch's state diff on top of the state of the block preceding the batch (`parent_hash`)
+/// and stores the batch's last header as the new canonical head
+/// Fails if the resulting state root doesn't match the one in the header
+/// Returns the hash of the reconstructed block
+pub fn apply_state_diff(
+    store: &Store,
+    parent_hash: BlockHash,
+    state_diff: &StateDiff,
+) -> Result<BlockHash, StateReconstructionError> {
+    let header = &state_diff.header;
+    match store.get_block_header_by_hash(parent_hash)? {
+        Some(parent_header) if parent_header.number < header.number => {}
+        _ => return Err(StateReconstructionError::UnknownParent(header.number)),
+    }
+
+    let mut account_updates = Vec::with_capacity(state_diff.modified_accounts.len());
+    for (address, diff) in &state_diff.modified_accounts {
+        let previous_info = store
+            .get_account_info_by_hash(parent_hash, *address)?
+            .unwrap_or_default();
+        let code_hash = match (&diff.bytecode, diff.bytecode_hash) {
+            (Some(bytecode), _) => keccak(bytecode),
+            (None, Some(bytecode_hash)) => bytecode_hash,
+            (None, None) => previous_info.code_hash,
+        };
+        let nonce = previous_info
+            .nonce
+            .checked_add(diff.nonce_diff.into())
+            .ok_or_else(|| {
+                StateReconstructionError::InvalidStateDiff(format!(
+                    "Nonce overflow for account {address:#x}"
+                ))
+            })?;
+        account_updates.push(AccountUpdate {
+            address: *address,
+            removed: false,
+            info: Some(AccountInfo {
+                code_hash,
+                balance: diff.new_balance.unwrap_or(previous_info.balance),
+                nonce,
+            }),
+            code: diff.bytecode.clone(),
+            added_storage: diff.storage.iter().copied().collect(),
+        });
+    }
+
+    let state_root = store
+        .apply_account_updates(parent_hash, &account_updates)?
+        .ok_or(StateReconstructionError::UnknownParent(header.number))?;
+    if state_root != header.state_root {
+        return Err(StateReconstructionError::StateRootMismatch {
+            block_number: header.number,
+            expected: header.state_root,
+            computed: state_root,
+        });
+    }
+
+    let block_hash = header.compute_block_hash();
+    store.add_block_header(block_hash, header.clone())?;
+    store.add_block_number(block_hash, header.number)?;
+    store.set_canonical_block(header.number, block_hash)?;
+    store.update_latest_block_number(header.number)?;
+    Ok(block_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proposer::state_diff::AccountStateDiff;
+    use ethrex_core::types::{BlockHeader, Genesis};
+    use ethrex_storage::EngineType;
+    use std::collections::HashMap;
+
+    fn genesis_store() -> Store {
+        let genesis_file = std::fs::File::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../test_data/genesis-l2.json"
+        ))
+        .unwrap();
+        let genesis: Genesis = serde_json::from_reader(genesis_file).unwrap();
+        let store = Store::new("", EngineType::InMemory).unwrap();
+        store.add_initial_state(genesis).unwrap();
+        store
+    }
+
+    #[test]
+    fn apply_state_diff_checks_state_root() {
+        let address = Address::from_low_u64_be(0xabc);
+        let bytecode = Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xf3]);
+        let storage = (H256::from_low_u64_be(1), U256::from(2));
+
+        // Compute the state root the sequencer would have reached
+        let sequencer_store = genesis_store();
+        let genesis_header = sequencer_store.get_block_header(0).unwrap().unwrap();
+        let state_root = sequencer_store
+            .apply_account_updates(
+                genesis_header.compute_block_hash(),
+                &[AccountUpdate {
+                    address,
+                    removed: false,
+                    info: Some(AccountInfo {
+                        code_hash: keccak(&bytecode),
+                        balance: U256::from(1000),
+                        nonce: 1,
+                    }),
+                    code: Some(bytecode.clone()),
+                    added_storage: HashMap::from([storage]),
+                }],
+            )
+            .unwrap()
+            .unwrap();
+
+        let mut state_diff = StateDiff {
+            // Batch of blocks 1 to 3, applied on top of the genesis
+            header: BlockHeader {
+                number: 3,
+                state_root: H256::zero(),
+                ..Default::default()
+            },
+            modified_accounts: HashMap::from([(
+                address,
+                AccountStateDiff {
+                    new_balance: Some(U256::from(1000)),
+                    nonce_diff: 1,
+                    storage: vec![storage],
+                    bytecode: Some(bytecode),
+                    bytecode_hash: None,
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let store = genesis_store();
+        let parent_hash = genesis_header.compute_block_hash();
+        assert!(matches!(
+            apply_state_diff(&store, parent_hash, &state_diff),
+            Err(StateReconstructionError::StateRootMismatch { computed, .. }) if computed == state_root
+        ));
+
+        state_diff.header.state_root = state_root;
+        let block_hash = apply_state_diff(&store, parent_hash, &state_diff).unwrap();
+        assert_eq!(store.get_latest_block_number().unwrap(), 3);
+        assert_eq!(store.get_canonical_block_hash(3).unwrap(), Some(block_hash));
+        assert_eq!(
+            store.get_storage_at(3, address, storage.0).unwrap(),
+            Some(storage.1)
+        );
+    }
+}
@@ -81,11 +81,11 @@ pub fn generate_program_input(
         .ok_or(ProverInputError::InvalidParentBlock(
             block.header.parent_hash,
         ))?;
-    let db = ExecutionDB::from_exec(&block, &store)?;
+    let db = ExecutionDB::from_exec(std::slice::from_ref(&block), &store)?;
 
     Ok(ProgramInput {
         db,
-        block,
+        blocks: vec![block],
         parent_block_header,
     })
 }
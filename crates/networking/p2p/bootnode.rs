@@ -1,27 +1,55 @@
 use ethrex_core::H512;
+use ethrex_rlp::error::RLPDecodeError;
 use std::{net::SocketAddr, num::ParseIntError, str::FromStr};
 
+use crate::types::NodeRecord;
+
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub struct BootNode {
     pub node_id: H512,
+    /// Address the node listens to for discovery packets
     pub socket_address: SocketAddr,
+    pub tcp_port: u16,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BootNodeParseError {
+    #[error("Invalid enode url, expected enode://nodeID@IPaddress:port")]
+    InvalidEnode,
+    #[error("Invalid node record: {0}")]
+    InvalidRecord(#[from] RLPDecodeError),
+    #[error("Node record is missing its address")]
+    MissingAddress,
 }
 
 impl FromStr for BootNode {
-    type Err = ParseIntError;
-    /// Takes a str with the format "enode://nodeID@IPaddress:port" and
-    /// parses it to a BootNode
-    // TODO: fix it to support different UDP and TCP ports, according to
-    // https://github.com/lambdaclass/ethrex/issues/905
-    fn from_str(input: &str) -> Result<BootNode, ParseIntError> {
-        // TODO: error handling
-        let node_id = H512::from_str(&input[8..136]).expect("Failed to parse node id");
-        let socket_address: SocketAddr = input[137..]
+    type Err = BootNodeParseError;
+    /// Takes a str with the format "enode://nodeID@IPaddress:port", or a
+    /// node record in its "enr:" text form, and parses it to a BootNode
+    fn from_str(input: &str) -> Result<BootNode, BootNodeParseError> {
+        if input.starts_with("enr:") {
+            let node = NodeRecord::from_enr_url(input)?
+                .node()
+                .ok_or(BootNodeParseError::MissingAddress)?;
+            return Ok(BootNode {
+                node_id: node.node_id,
+                socket_address: SocketAddr::new(node.ip, node.udp_port),
+                tcp_port: node.tcp_port,
+            });
+        }
+        let (node_id, socket_address) = input
+            .strip_prefix("enode://")
+            .and_then(|enode| enode.split_once('@'))
+            .ok_or(BootNodeParseError::InvalidEnode)?;
+        let node_id = H512::from_str(node_id).map_err(|_| BootNodeParseError::InvalidEnode)?;
+        let socket_address: SocketAddr = socket_address
             .parse()
-            .expect("Failed to parse bootnode address and port");
+            .map_err(|_| BootNodeParseError::InvalidEnode)?;
+        // enode urls only carry the TCP port, which is also used for discovery
         Ok(BootNode {
             node_id,
             socket_address,
+            tcp_port: socket_address.port(),
         })
     }
 }
@@ -44,6 +72,35 @@ fn parse_bootnode_from_string() {
     let expected_bootnode = BootNode {
         node_id,
         socket_address,
+        tcp_port: 30303,
     };
     assert_eq!(bootnode, expected_bootnode);
 }
+
+#[test]
+fn parse_bootnode_from_enr_string() {
+    // Example record from https://github.com/ethereum/devp2p/blob/master/enr.md#test-vectors
+    let input = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+    let bootnode = BootNode::from_str(input).unwrap();
+    let node_id = H512::from_str(
+        "ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31387574077f301b421bc84df7266c44e9e6d569fc56be00812904767bf5ccd1fc7f")
+        .unwrap();
+    let socket_address = SocketAddr::from_str("127.0.0.1:30303").unwrap();
+    // The record doesn't advertise a TCP port
+    let expected_bootnode = BootNode {
+        node_id,
+        socket_address,
+        tcp_port: 0,
+    };
+    assert_eq!(bootnode, expected_bootnode);
+}
+
+#[test]
+fn parse_invalid_bootnodes() {
+    // The record's signature doesn't match its content
+    let input = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl9";
+    assert!(BootNode::from_str(input).is_err());
+    assert!(BootNode::from_str("enr:not-base64!").is_err());
+    assert!(BootNode::from_str("enode://1234@18.138.108.67:30303").is_err());
+    assert!(BootNode::from_str("enode://d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666").is_err());
+}
@@ -243,8 +243,6 @@ impl PingMessage {
         }
     }
 
-    // TODO: remove when used
-    #[allow(unused)]
     pub fn with_enr_seq(self, enr_seq: u64) -> Self {
         Self {
             enr_seq: Some(enr_seq),
@@ -378,8 +376,6 @@ impl PongMessage {
         }
     }
 
-    // TODO: remove when used
-    #[allow(unused)]
     pub fn with_enr_seq(self, enr_seq: u64) -> Self {
         Self {
             enr_seq: Some(enr_seq),
@@ -475,7 +471,7 @@ impl RLPDecode for ENRResponseMessage {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct ENRRequestMessage {
-    expiration: u64,
+    pub expiration: u64,
 }
 
 impl RLPDecode for ENRRequestMessage {
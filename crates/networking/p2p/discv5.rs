@@ -0,0 +1,6 @@
+//! Implementation of the [discv5](https://github.com/ethereum/devp2p/blob/master/discv5/discv5.md) node discovery protocol.
+//! Nodes found through discv5 are stored in the same kademlia table as the ones found through discv4.
+pub(crate) mod crypto;
+pub(crate) mod messages;
+pub(crate) mod packet;
+pub(crate) mod server;
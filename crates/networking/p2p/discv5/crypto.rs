@@ -0,0 +1,232 @@
+use aes::{
+    cipher::{KeyIvInit, StreamCipher},
+    Aes128,
+};
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes128Gcm,
+};
+use ethrex_core::H256;
+use hkdf::Hkdf;
+use k256::{
+    ecdsa::{signature::hazmat::PrehashVerifier, Signature, SigningKey, VerifyingKey},
+    elliptic_curve::sec1::ToEncodedPoint,
+    sha2::{Digest, Sha256},
+    PublicKey, SecretKey,
+};
+
+const KEY_AGREEMENT_INFO: &[u8] = b"discovery v5 key agreement";
+const ID_SIGNATURE_TEXT: &[u8] = b"discovery v5 identity proof";
+
+type Aes128Ctr128BE = ctr::Ctr128BE<Aes128>;
+
+/// Keys used to encrypt the messages of a session, each side of the session uses one key to write and the other to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionKeys {
+    pub initiator_key: [u8; 16],
+    pub recipient_key: [u8; 16],
+}
+
+/// Masks (or unmasks) the packet header with AES-CTR, using the first 16 bytes of the destination node id as key
+pub fn mask_header(dest_id: H256, masking_iv: &[u8; 16], header: &mut [u8]) {
+    let mut cipher = Aes128Ctr128BE::new(dest_id.as_bytes()[..16].into(), masking_iv.into());
+    cipher.apply_keystream(header);
+}
+
+/// Encrypts the plain text with AES-GCM, returns the cipher text followed by the authentication tag
+pub fn aes_gcm_encrypt(key: &[u8; 16], nonce: &[u8; 12], plain_text: &[u8], ad: &[u8]) -> Vec<u8> {
+    Aes128Gcm::new(key.into())
+        .encrypt(
+            nonce.into(),
+            Payload {
+                msg: plain_text,
+                aad: ad,
+            },
+        )
+        .expect("plain text is within the AES-GCM length limit")
+}
+
+/// Decrypts a cipher text produced by `aes_gcm_encrypt`, returns None if the authentication tag doesn't match
+pub fn aes_gcm_decrypt(
+    key: &[u8; 16],
+    nonce: &[u8; 12],
+    cipher_text: &[u8],
+    ad: &[u8],
+) -> Option<Vec<u8>> {
+    Aes128Gcm::new(key.into())
+        .decrypt(
+            nonce.into(),
+            Payload {
+                msg: cipher_text,
+                aad: ad,
+            },
+        )
+        .ok()
+}
+
+/// Returns the shared secret between the two keys, as the compressed encoding of the shared point
+pub fn ecdh(secret_key: &SecretKey, public_key: &PublicKey) -> [u8; 33] {
+    let shared_point = (public_key.to_projective() * *secret_key.to_nonzero_scalar()).to_affine();
+    shared_point
+        .to_encoded_point(true)
+        .as_bytes()
+        .try_into()
+        .expect("compressed points are 33 bytes long")
+}
+
+/// Derives the session keys from the shared secret of the handshake
+pub fn derive_session_keys(
+    secret: &[u8],
+    challenge_data: &[u8],
+    initiator_id: H256,
+    recipient_id: H256,
+) -> SessionKeys {
+    let info = [
+        KEY_AGREEMENT_INFO,
+        initiator_id.as_bytes(),
+        recipient_id.as_bytes(),
+    ]
+    .concat();
+    let mut key_data = [0; 32];
+    Hkdf::<Sha256>::new(Some(challenge_data), secret)
+        .expand(&info, &mut key_data)
+        .expect("32 bytes is a valid HKDF output length");
+    SessionKeys {
+        initiator_key: key_data[..16].try_into().unwrap(),
+        recipient_key: key_data[16..].try_into().unwrap(),
+    }
+}
+
+fn id_signature_digest(
+    challenge_data: &[u8],
+    ephemeral_key: &[u8],
+    recipient_id: H256,
+) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(ID_SIGNATURE_TEXT)
+        .chain_update(challenge_data)
+        .chain_update(ephemeral_key)
+        .chain_update(recipient_id)
+        .finalize()
+        .into()
+}
+
+/// Signs the handshake challenge, proving that the initiator owns the key of its node id
+pub fn sign_id_nonce(
+    signer: &SigningKey,
+    challenge_data: &[u8],
+    ephemeral_key: &[u8],
+    recipient_id: H256,
+) -> [u8; 64] {
+    let digest = id_signature_digest(challenge_data, ephemeral_key, recipient_id);
+    let (signature, _recovery_id) = signer
+        .sign_prehash_recoverable(&digest)
+        .expect("failed to sign");
+    signature.to_bytes().into()
+}
+
+pub fn verify_id_nonce(
+    verifying_key: &VerifyingKey,
+    signature: &[u8],
+    challenge_data: &[u8],
+    ephemeral_key: &[u8],
+    recipient_id: H256,
+) -> bool {
+    let Ok(signature) = Signature::from_slice(signature) else {
+        return false;
+    };
+    let digest = id_signature_digest(challenge_data, ephemeral_key, recipient_id);
+    verifying_key.verify_prehash(&digest, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    // Test vectors from https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire-test-vectors.md
+    const CHALLENGE_DATA: [u8; 63] = hex!("000000000000000000000000000000006469736376350001010102030405060708090a0b0c00180102030405060708090a0b0c0d0e0f100000000000000000");
+    const NODE_ID_A: [u8; 32] =
+        hex!("aaaa8419e9f49d0083561b48287df592939a8d19947d8c0ef88f2a4856a69fbb");
+    const NODE_ID_B: [u8; 32] =
+        hex!("bbbb9d047f0488c0b5a93c1c3f2d8bafc7c8ff337024a55434a0d0555de64db9");
+
+    #[test]
+    fn ecdh_shared_secret() {
+        let secret_key = SecretKey::from_slice(&hex!(
+            "fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736"
+        ))
+        .unwrap();
+        let public_key = PublicKey::from_sec1_bytes(&hex!(
+            "039961e4c2356d61bedb83052c115d311acb3a96f5777296dcf297351130266231"
+        ))
+        .unwrap();
+        assert_eq!(
+            ecdh(&secret_key, &public_key),
+            hex!("033b11a2a1f214567e1537ce5e509ffd9b21373247f2a3ff6841f4976f53165e7e")
+        );
+    }
+
+    #[test]
+    fn key_derivation() {
+        let ephemeral_key = SecretKey::from_slice(&hex!(
+            "fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736"
+        ))
+        .unwrap();
+        let dest_public_key = PublicKey::from_sec1_bytes(&hex!(
+            "0317931e6e0840220642f230037d285d122bc59063221ef3226b1f403ddc69ca91"
+        ))
+        .unwrap();
+        let secret = ecdh(&ephemeral_key, &dest_public_key);
+        let keys = derive_session_keys(&secret, &CHALLENGE_DATA, H256(NODE_ID_A), H256(NODE_ID_B));
+        assert_eq!(keys.initiator_key, hex!("dccc82d81bd610f4f76d3ebe97a40571"));
+        assert_eq!(keys.recipient_key, hex!("ac74bb8773749920b0d3a8881c173ec5"));
+    }
+
+    #[test]
+    fn id_nonce_signing() {
+        let signer = SigningKey::from_slice(&hex!(
+            "fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736"
+        ))
+        .unwrap();
+        let ephemeral_key =
+            hex!("039961e4c2356d61bedb83052c115d311acb3a96f5777296dcf297351130266231");
+        let signature = sign_id_nonce(&signer, &CHALLENGE_DATA, &ephemeral_key, H256(NODE_ID_B));
+        assert_eq!(
+            signature,
+            hex!("94852a1e2318c4e5e9d422c98eaf19d1d90d876b29cd06ca7cb7546d0fff7b484fe86c09a064fe72bdbef73ba8e9c34df0cd2b53e9d65528c2c7f336d5dfc6e6")
+        );
+        assert!(verify_id_nonce(
+            signer.verifying_key(),
+            &signature,
+            &CHALLENGE_DATA,
+            &ephemeral_key,
+            H256(NODE_ID_B)
+        ));
+        assert!(!verify_id_nonce(
+            signer.verifying_key(),
+            &signature,
+            &CHALLENGE_DATA,
+            &ephemeral_key,
+            H256(NODE_ID_A)
+        ));
+    }
+
+    #[test]
+    fn aes_gcm_encryption() {
+        let key = hex!("9f2d77db7004bf8a1a85107ac686990b");
+        let nonce = hex!("27b5af763c446acd2749fe8e");
+        let plain_text = hex!("01c20101");
+        let ad = hex!("93a7400fa0d6a694ebc24d5cf570f65d04215b6ac00757875e3f3a5f42107903");
+        let cipher_text = aes_gcm_encrypt(&key, &nonce, &plain_text, &ad);
+        assert_eq!(
+            cipher_text,
+            hex!("a5d12a2d94b8ccb3ba55558229867dc13bfa3648")
+        );
+        assert_eq!(
+            aes_gcm_decrypt(&key, &nonce, &cipher_text, &ad),
+            Some(plain_text.to_vec())
+        );
+        assert_eq!(aes_gcm_decrypt(&key, &nonce, &cipher_text, &[]), None);
+    }
+}
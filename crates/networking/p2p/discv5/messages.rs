@@ -0,0 +1,330 @@
+use std::net::IpAddr;
+
+use bytes::{BufMut, Bytes};
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+
+use crate::types::NodeRecord;
+
+/// Request ids are chosen by the requester and must not be longer than 8 bytes
+const MAX_REQUEST_ID_SIZE: usize = 8;
+
+/// Reference: [discv5 messages](https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire.md#protocol-messages)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Message {
+    Ping(PingMessage),
+    Pong(PongMessage),
+    FindNode(FindNodeMessage),
+    Nodes(NodesMessage),
+    TalkReq(TalkReqMessage),
+    TalkResp(TalkRespMessage),
+}
+
+impl Message {
+    /// Encodes the message as its type followed by the RLP encoding of its data
+    pub fn encode(&self, buf: &mut dyn BufMut) {
+        buf.put_u8(self.message_type());
+        match self {
+            Message::Ping(msg) => msg.encode(buf),
+            Message::Pong(msg) => msg.encode(buf),
+            Message::FindNode(msg) => msg.encode(buf),
+            Message::Nodes(msg) => msg.encode(buf),
+            Message::TalkReq(msg) => msg.encode(buf),
+            Message::TalkResp(msg) => msg.encode(buf),
+        }
+    }
+
+    pub fn decode(msg: &[u8]) -> Result<Message, RLPDecodeError> {
+        let (message_type, msg_data) = msg.split_first().ok_or(RLPDecodeError::InvalidLength)?;
+        let message = match message_type {
+            0x01 => Message::Ping(PingMessage::decode(msg_data)?),
+            0x02 => Message::Pong(PongMessage::decode(msg_data)?),
+            0x03 => Message::FindNode(FindNodeMessage::decode(msg_data)?),
+            0x04 => Message::Nodes(NodesMessage::decode(msg_data)?),
+            0x05 => Message::TalkReq(TalkReqMessage::decode(msg_data)?),
+            0x06 => Message::TalkResp(TalkRespMessage::decode(msg_data)?),
+            _ => return Err(RLPDecodeError::MalformedData),
+        };
+        if message.req_id().len() > MAX_REQUEST_ID_SIZE {
+            return Err(RLPDecodeError::InvalidLength);
+        }
+        Ok(message)
+    }
+
+    fn message_type(&self) -> u8 {
+        match self {
+            Message::Ping(_) => 0x01,
+            Message::Pong(_) => 0x02,
+            Message::FindNode(_) => 0x03,
+            Message::Nodes(_) => 0x04,
+            Message::TalkReq(_) => 0x05,
+            Message::TalkResp(_) => 0x06,
+        }
+    }
+
+    pub fn req_id(&self) -> &Bytes {
+        match self {
+            Message::Ping(msg) => &msg.req_id,
+            Message::Pong(msg) => &msg.req_id,
+            Message::FindNode(msg) => &msg.req_id,
+            Message::Nodes(msg) => &msg.req_id,
+            Message::TalkReq(msg) => &msg.req_id,
+            Message::TalkResp(msg) => &msg.req_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PingMessage {
+    pub req_id: Bytes,
+    /// The ENR sequence number of the sender
+    pub enr_seq: u64,
+}
+
+impl RLPEncode for PingMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.enr_seq)
+            .finish();
+    }
+}
+
+impl RLPDecode for PingMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (enr_seq, decoder) = decoder.decode_field("enr_seq")?;
+        let remaining = decoder.finish_unchecked();
+        Ok((PingMessage { req_id, enr_seq }, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PongMessage {
+    pub req_id: Bytes,
+    /// The ENR sequence number of the sender
+    pub enr_seq: u64,
+    /// The endpoint the ping was received from, as seen by the sender of the pong
+    pub recipient_ip: IpAddr,
+    pub recipient_port: u16,
+}
+
+impl RLPEncode for PongMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.enr_seq)
+            .encode_field(&self.recipient_ip)
+            .encode_field(&self.recipient_port)
+            .finish();
+    }
+}
+
+impl RLPDecode for PongMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (enr_seq, decoder) = decoder.decode_field("enr_seq")?;
+        let (recipient_ip, decoder) = decoder.decode_field("recipient_ip")?;
+        let (recipient_port, decoder) = decoder.decode_field("recipient_port")?;
+        let remaining = decoder.finish_unchecked();
+        let pong = PongMessage {
+            req_id,
+            enr_seq,
+            recipient_ip,
+            recipient_port,
+        };
+        Ok((pong, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FindNodeMessage {
+    pub req_id: Bytes,
+    /// Logarithmic distances from the recipient of the requested nodes, 0 requests the recipient's own record
+    pub distances: Vec<u16>,
+}
+
+impl RLPEncode for FindNodeMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.distances)
+            .finish();
+    }
+}
+
+impl RLPDecode for FindNodeMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (distances, decoder) = decoder.decode_field("distances")?;
+        let remaining = decoder.finish_unchecked();
+        Ok((FindNodeMessage { req_id, distances }, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NodesMessage {
+    pub req_id: Bytes,
+    /// Amount of NODES messages sent in response to the request, as the records may not fit in a single packet
+    pub total: u64,
+    pub nodes: Vec<NodeRecord>,
+}
+
+impl RLPEncode for NodesMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.total)
+            .encode_field(&self.nodes)
+            .finish();
+    }
+}
+
+impl RLPDecode for NodesMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (total, decoder) = decoder.decode_field("total")?;
+        let (nodes, decoder) = decoder.decode_field("nodes")?;
+        let remaining = decoder.finish_unchecked();
+        let nodes_msg = NodesMessage {
+            req_id,
+            total,
+            nodes,
+        };
+        Ok((nodes_msg, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TalkReqMessage {
+    pub req_id: Bytes,
+    pub protocol: Bytes,
+    pub request: Bytes,
+}
+
+impl RLPEncode for TalkReqMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.protocol)
+            .encode_field(&self.request)
+            .finish();
+    }
+}
+
+impl RLPDecode for TalkReqMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (protocol, decoder) = decoder.decode_field("protocol")?;
+        let (request, decoder) = decoder.decode_field("request")?;
+        let remaining = decoder.finish_unchecked();
+        let talk_req = TalkReqMessage {
+            req_id,
+            protocol,
+            request,
+        };
+        Ok((talk_req, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TalkRespMessage {
+    pub req_id: Bytes,
+    pub response: Bytes,
+}
+
+impl RLPEncode for TalkRespMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.response)
+            .finish();
+    }
+}
+
+impl RLPDecode for TalkRespMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (response, decoder) = decoder.decode_field("response")?;
+        let remaining = decoder.finish_unchecked();
+        Ok((TalkRespMessage { req_id, response }, remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    #[test]
+    fn encode_ping_message() {
+        // Plain text of the ping message in https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire-test-vectors.md
+        let msg = Message::Ping(PingMessage {
+            req_id: Bytes::from_static(&hex!("00000001")),
+            enr_seq: 2,
+        });
+        let mut buf = Vec::new();
+        msg.encode(&mut buf);
+        assert_eq!(buf, hex!("01c6840000000102"));
+        assert_eq!(Message::decode(&buf).unwrap(), msg);
+    }
+
+    #[test]
+    fn encode_and_decode_messages() {
+        let record = NodeRecord::from_enr_url("enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8").unwrap();
+        let req_id = Bytes::from_static(&[1, 2, 3]);
+        let messages = [
+            Message::Pong(PongMessage {
+                req_id: req_id.clone(),
+                enr_seq: 7,
+                recipient_ip: IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                recipient_port: 30303,
+            }),
+            Message::FindNode(FindNodeMessage {
+                req_id: req_id.clone(),
+                distances: vec![255, 256, 0],
+            }),
+            Message::Nodes(NodesMessage {
+                req_id: req_id.clone(),
+                total: 1,
+                nodes: vec![record.clone(), record],
+            }),
+            Message::TalkReq(TalkReqMessage {
+                req_id: req_id.clone(),
+                protocol: Bytes::from_static(b"test"),
+                request: Bytes::from_static(b"request"),
+            }),
+            Message::TalkResp(TalkRespMessage {
+                req_id,
+                response: Bytes::new(),
+            }),
+        ];
+        for msg in messages {
+            let mut buf = Vec::new();
+            msg.encode(&mut buf);
+            assert_eq!(Message::decode(&buf).unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn reject_long_request_ids() {
+        let msg = Message::Ping(PingMessage {
+            req_id: Bytes::from_static(&[0; 9]),
+            enr_seq: 1,
+        });
+        let mut buf = Vec::new();
+        msg.encode(&mut buf);
+        assert!(Message::decode(&buf).is_err());
+    }
+}
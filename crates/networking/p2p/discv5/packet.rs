@@ -0,0 +1,284 @@
+use ethrex_core::H256;
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode, error::RLPDecodeError};
+
+use crate::{types::NodeRecord, MAX_DISC_PACKET_SIZE};
+
+use super::crypto::mask_header;
+
+const PROTOCOL_ID: &[u8] = b"discv5";
+const PROTOCOL_VERSION: u16 = 1;
+const MASKING_IV_SIZE: usize = 16;
+/// protocol-id || version || flag || nonce || authdata-size
+const STATIC_HEADER_SIZE: usize = 23;
+const MIN_PACKET_SIZE: usize = 63;
+
+const FLAG_MESSAGE: u8 = 0;
+const FLAG_WHOAREYOU: u8 = 1;
+const FLAG_HANDSHAKE: u8 = 2;
+
+#[derive(Debug)]
+pub enum PacketDecodeErr {
+    #[allow(unused)]
+    RLPDecodeError(RLPDecodeError),
+    InvalidSize,
+    InvalidHeader,
+}
+
+/// The authentication data of each packet kind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AuthData {
+    /// An ordinary message, encrypted with the session keys
+    Message { src_id: H256 },
+    /// A challenge sent in response to a message that couldn't be decrypted,
+    /// `enr_seq` is the sequence number of the sender's record known by the recipient, or 0 if unknown
+    WhoAreYou { id_nonce: [u8; 16], enr_seq: u64 },
+    /// A message sent in response to a challenge, along with what's needed to set up a new session
+    Handshake {
+        src_id: H256,
+        id_signature: Vec<u8>,
+        ephemeral_key: Vec<u8>,
+        record: Option<NodeRecord>,
+    },
+}
+
+impl AuthData {
+    fn flag(&self) -> u8 {
+        match self {
+            AuthData::Message { .. } => FLAG_MESSAGE,
+            AuthData::WhoAreYou { .. } => FLAG_WHOAREYOU,
+            AuthData::Handshake { .. } => FLAG_HANDSHAKE,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            AuthData::Message { src_id } => src_id.as_bytes().to_vec(),
+            AuthData::WhoAreYou { id_nonce, enr_seq } => {
+                [id_nonce.as_slice(), &enr_seq.to_be_bytes()].concat()
+            }
+            AuthData::Handshake {
+                src_id,
+                id_signature,
+                ephemeral_key,
+                record,
+            } => [
+                src_id.as_bytes(),
+                &[id_signature.len() as u8, ephemeral_key.len() as u8],
+                id_signature,
+                ephemeral_key,
+                &record
+                    .as_ref()
+                    .map(|record| record.encode_to_vec())
+                    .unwrap_or_default(),
+            ]
+            .concat(),
+        }
+    }
+
+    fn decode(flag: u8, auth_data: &[u8]) -> Result<Self, PacketDecodeErr> {
+        match flag {
+            FLAG_MESSAGE => {
+                if auth_data.len() != 32 {
+                    return Err(PacketDecodeErr::InvalidHeader);
+                }
+                Ok(AuthData::Message {
+                    src_id: H256::from_slice(auth_data),
+                })
+            }
+            FLAG_WHOAREYOU => {
+                if auth_data.len() != 24 {
+                    return Err(PacketDecodeErr::InvalidHeader);
+                }
+                Ok(AuthData::WhoAreYou {
+                    id_nonce: auth_data[..16].try_into().unwrap(),
+                    enr_seq: u64::from_be_bytes(auth_data[16..].try_into().unwrap()),
+                })
+            }
+            FLAG_HANDSHAKE => {
+                if auth_data.len() < 34 {
+                    return Err(PacketDecodeErr::InvalidHeader);
+                }
+                let src_id = H256::from_slice(&auth_data[..32]);
+                let signature_size = auth_data[32] as usize;
+                let key_size = auth_data[33] as usize;
+                let rest = &auth_data[34..];
+                if rest.len() < signature_size + key_size {
+                    return Err(PacketDecodeErr::InvalidHeader);
+                }
+                let (id_signature, rest) = rest.split_at(signature_size);
+                let (ephemeral_key, record) = rest.split_at(key_size);
+                let record = if record.is_empty() {
+                    None
+                } else {
+                    Some(NodeRecord::decode(record).map_err(PacketDecodeErr::RLPDecodeError)?)
+                };
+                Ok(AuthData::Handshake {
+                    src_id,
+                    id_signature: id_signature.to_vec(),
+                    ephemeral_key: ephemeral_key.to_vec(),
+                    record,
+                })
+            }
+            _ => Err(PacketDecodeErr::InvalidHeader),
+        }
+    }
+}
+
+/// Reference: [discv5 packet encoding](https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire.md#packet-encoding)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Packet {
+    pub masking_iv: [u8; 16],
+    pub nonce: [u8; 12],
+    pub auth_data: AuthData,
+    /// The encrypted message, empty for WHOAREYOU packets
+    pub message: Vec<u8>,
+}
+
+impl Packet {
+    fn header(&self) -> Vec<u8> {
+        let auth_data = self.auth_data.encode();
+        [
+            PROTOCOL_ID,
+            &PROTOCOL_VERSION.to_be_bytes(),
+            &[self.auth_data.flag()],
+            &self.nonce,
+            &(auth_data.len() as u16).to_be_bytes(),
+            &auth_data,
+        ]
+        .concat()
+    }
+
+    /// Returns the masking iv followed by the unmasked header.
+    /// This is the data authenticated by the message encryption, and the challenge data of WHOAREYOU packets
+    pub fn authenticated_data(&self) -> Vec<u8> {
+        [self.masking_iv.as_slice(), &self.header()].concat()
+    }
+
+    pub fn encode(&self, dest_id: H256) -> Vec<u8> {
+        let mut header = self.header();
+        mask_header(dest_id, &self.masking_iv, &mut header);
+        [self.masking_iv.as_slice(), &header, &self.message].concat()
+    }
+
+    /// Decodes a packet sent to the local node, the message is returned still encrypted
+    pub fn decode(local_id: H256, packet: &[u8]) -> Result<Packet, PacketDecodeErr> {
+        if packet.len() < MIN_PACKET_SIZE || packet.len() > MAX_DISC_PACKET_SIZE {
+            return Err(PacketDecodeErr::InvalidSize);
+        }
+        let masking_iv: [u8; MASKING_IV_SIZE] = packet[..MASKING_IV_SIZE].try_into().unwrap();
+
+        // The header is masked as a single stream, so the static header is unmasked first to find out the size of the rest
+        let mut static_header =
+            packet[MASKING_IV_SIZE..MASKING_IV_SIZE + STATIC_HEADER_SIZE].to_vec();
+        mask_header(local_id, &masking_iv, &mut static_header);
+        if &static_header[..6] != PROTOCOL_ID
+            || static_header[6..8] != PROTOCOL_VERSION.to_be_bytes()
+        {
+            return Err(PacketDecodeErr::InvalidHeader);
+        }
+        let auth_data_size = u16::from_be_bytes([static_header[21], static_header[22]]) as usize;
+        let header_end = MASKING_IV_SIZE + STATIC_HEADER_SIZE + auth_data_size;
+        if packet.len() < header_end {
+            return Err(PacketDecodeErr::InvalidSize);
+        }
+        let mut header = packet[MASKING_IV_SIZE..header_end].to_vec();
+        mask_header(local_id, &masking_iv, &mut header);
+
+        let flag = header[8];
+        let nonce = header[9..21].try_into().unwrap();
+        let auth_data = AuthData::decode(flag, &header[STATIC_HEADER_SIZE..])?;
+        Ok(Packet {
+            masking_iv,
+            nonce,
+            auth_data,
+            message: packet[header_end..].to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discv5::{
+        crypto::aes_gcm_decrypt,
+        messages::{Message, PingMessage},
+    };
+    use bytes::Bytes;
+    use hex_literal::hex;
+
+    // Test vectors from https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire-test-vectors.md
+    const SRC_ID: [u8; 32] =
+        hex!("aaaa8419e9f49d0083561b48287df592939a8d19947d8c0ef88f2a4856a69fbb");
+    const DEST_ID: [u8; 32] =
+        hex!("bbbb9d047f0488c0b5a93c1c3f2d8bafc7c8ff337024a55434a0d0555de64db9");
+
+    #[test]
+    fn decode_ping_message_packet() {
+        let encoded = hex!("00000000000000000000000000000000088b3d4342774649325f313964a39e55ea96c005ad52be8c7560413a7008f16c9e6d2f43bbea8814a546b7409ce783d34c4f53245d08dab84102ed931f66d1492acb308fa1c6715b9d139b81acbdcc");
+        let packet = Packet::decode(H256(DEST_ID), &encoded).unwrap();
+        assert_eq!(
+            packet.auth_data,
+            AuthData::Message {
+                src_id: H256(SRC_ID)
+            }
+        );
+        assert_eq!(packet.nonce, hex!("ffffffffffffffffffffffff"));
+        let plain_text = aes_gcm_decrypt(
+            &[0; 16],
+            &packet.nonce,
+            &packet.message,
+            &packet.authenticated_data(),
+        )
+        .unwrap();
+        let expected = Message::Ping(PingMessage {
+            req_id: Bytes::from_static(&hex!("00000001")),
+            enr_seq: 2,
+        });
+        assert_eq!(Message::decode(&plain_text).unwrap(), expected);
+        assert_eq!(packet.encode(H256(DEST_ID)), encoded);
+    }
+
+    #[test]
+    fn encode_whoareyou_packet() {
+        let packet = Packet {
+            masking_iv: [0; 16],
+            nonce: hex!("0102030405060708090a0b0c"),
+            auth_data: AuthData::WhoAreYou {
+                id_nonce: hex!("0102030405060708090a0b0c0d0e0f10"),
+                enr_seq: 0,
+            },
+            message: vec![],
+        };
+        assert_eq!(
+            packet.authenticated_data(),
+            hex!("000000000000000000000000000000006469736376350001010102030405060708090a0b0c00180102030405060708090a0b0c0d0e0f100000000000000000")
+        );
+        let encoded = packet.encode(H256(DEST_ID));
+        assert_eq!(
+            encoded,
+            hex!("00000000000000000000000000000000088b3d434277464933a1ccc59f5967ad1d6035f15e528627dde75cd68292f9e6c27d6b66c8100a873fcbaed4e16b8d")
+        );
+        assert_eq!(Packet::decode(H256(DEST_ID), &encoded).unwrap(), packet);
+    }
+
+    #[test]
+    fn encode_handshake_packet() {
+        let record = NodeRecord::from_enr_url("enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8").unwrap();
+        for record in [None, Some(record)] {
+            let packet = Packet {
+                masking_iv: [7; 16],
+                nonce: [3; 12],
+                auth_data: AuthData::Handshake {
+                    src_id: H256(SRC_ID),
+                    id_signature: vec![1; 64],
+                    ephemeral_key: vec![2; 33],
+                    record,
+                },
+                message: vec![4; 20],
+            };
+            let encoded = packet.encode(H256(DEST_ID));
+            assert_eq!(Packet::decode(H256(DEST_ID), &encoded).unwrap(), packet);
+            assert!(Packet::decode(H256(SRC_ID), &encoded).is_err());
+        }
+    }
+}
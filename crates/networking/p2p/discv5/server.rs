@@ -0,0 +1,959 @@
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use ethrex_core::{H256, H512, U256};
+use ethrex_storage::Store;
+use k256::{
+    ecdsa::{SigningKey, VerifyingKey},
+    elliptic_curve::sec1::ToEncodedPoint,
+    PublicKey, SecretKey,
+};
+use rand::rngs::OsRng;
+use sha3::{Digest, Keccak256};
+use tokio::{
+    net::UdpSocket,
+    sync::{broadcast, mpsc, Mutex},
+};
+use tracing::debug;
+
+use crate::{
+    bootnode::BootNode,
+    discv4::time_now_unix,
+    handle_peer_as_initiator,
+    kademlia::{DiscoveryProtocol, KademliaTable, MAX_NODES_PER_BUCKET},
+    peer_manager::PeerManager,
+    rlpx::{message::Message as RLPxMessage, utils::id2pubkey},
+    types::{Node, NodeRecord},
+    LocalNodeRecord, MAX_DISC_PACKET_SIZE,
+};
+
+use super::{
+    crypto::{
+        aes_gcm_decrypt, aes_gcm_encrypt, derive_session_keys, ecdh, sign_id_nonce, verify_id_nonce,
+    },
+    messages::{FindNodeMessage, Message, NodesMessage, PingMessage, PongMessage, TalkRespMessage},
+    packet::{AuthData, Packet},
+};
+
+/// Time after which unanswered requests, challenges and handshakes are discarded
+const REQUEST_TIMEOUT_IN_SECONDS: u64 = 10;
+/// Amount of records sent in each NODES message, so that the packet stays below the maximum size
+const RECORDS_PER_NODES_MESSAGE: usize = 3;
+/// Amount of nodes queried at the same time by a lookup
+const LOOKUP_CONCURRENCY: usize = 3;
+/// Amount of peers pinged by each revalidation
+const REVALIDATION_PEERS: usize = 3;
+/// Sessions of nodes that are not in the table are dropped once there are more than this amount
+const MAX_SESSIONS: usize = 1024;
+
+/// Returns the discv5 id of the node, the keccak hash of its public key
+pub fn node_id_hash(node_id: H512) -> H256 {
+    H256::from_slice(&Keccak256::digest(node_id))
+}
+
+/// Returns the logarithmic distance between the two ids, as used by FINDNODE requests
+pub fn log_distance(a: H256, b: H256) -> u16 {
+    U256::from_big_endian((a ^ b).as_bytes()).bits() as u16
+}
+
+struct Session {
+    node: Node,
+    write_key: [u8; 16],
+    read_key: [u8; 16],
+}
+
+struct SentMessage {
+    node: Node,
+    message: Message,
+    sent_at: u64,
+}
+
+struct Challenge {
+    data: Vec<u8>,
+    sent_at: u64,
+}
+
+/// Messages waiting for the handshake with a node to complete
+struct PendingHandshake {
+    messages: Vec<Message>,
+    started_at: u64,
+}
+
+enum RequestKind {
+    Ping,
+    FindNode { distances: Vec<u16>, responses: u64 },
+}
+
+struct PendingRequest {
+    node_id: H256,
+    kind: RequestKind,
+    sent_at: u64,
+}
+
+pub(crate) struct Discv5Server {
+    udp_socket: Arc<UdpSocket>,
+    signer: SigningKey,
+    secret_key: SecretKey,
+    local_id: H256,
+    local_record: Arc<Mutex<LocalNodeRecord>>,
+    table: Arc<Mutex<KademliaTable>>,
+    storage: Store,
+    peers: PeerManager,
+    connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
+    sessions: HashMap<H256, Session>,
+    pending_handshakes: HashMap<H256, PendingHandshake>,
+    /// Messages sent to other nodes by packet nonce, so that they can be sent again if the node answers with a challenge
+    sent_messages: HashMap<[u8; 12], SentMessage>,
+    challenges: HashMap<H256, Challenge>,
+    requests: HashMap<Bytes, PendingRequest>,
+    /// Verified records of the nodes we know about, used to answer FINDNODE requests
+    records: HashMap<H256, NodeRecord>,
+    /// Peers pinged by the last revalidation
+    revalidated_peers: Vec<H512>,
+}
+
+impl Discv5Server {
+    pub fn new(
+        udp_socket: Arc<UdpSocket>,
+        signer: SigningKey,
+        local_record: Arc<Mutex<LocalNodeRecord>>,
+        table: Arc<Mutex<KademliaTable>>,
+        storage: Store,
+        peers: PeerManager,
+        connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
+    ) -> Self {
+        let local_id = node_id_hash(crate::node_id_from_signing_key(&signer));
+        Self {
+            udp_socket,
+            secret_key: SecretKey::from(signer.as_nonzero_scalar()),
+            signer,
+            local_id,
+            local_record,
+            table,
+            storage,
+            peers,
+            connection_broadcast,
+            sessions: HashMap::new(),
+            pending_handshakes: HashMap::new(),
+            sent_messages: HashMap::new(),
+            challenges: HashMap::new(),
+            requests: HashMap::new(),
+            records: HashMap::new(),
+            revalidated_peers: Vec::new(),
+        }
+    }
+
+    /// Pings the bootnodes and then handles the received packets, every `interval_time_in_seconds`
+    /// it revalidates the least recently answered peers and runs a lookup for a random target
+    pub async fn run(
+        mut self,
+        mut packets: mpsc::UnboundedReceiver<(Vec<u8>, SocketAddr)>,
+        bootnodes: Vec<BootNode>,
+        interval_time_in_seconds: u64,
+    ) {
+        for bootnode in bootnodes {
+            let node = Node {
+                ip: bootnode.socket_address.ip(),
+                udp_port: bootnode.socket_address.port(),
+                tcp_port: bootnode.tcp_port,
+                node_id: bootnode.node_id,
+            };
+            self.table
+                .lock()
+                .await
+                .insert_node(node, DiscoveryProtocol::V5);
+            self.ping(node).await;
+        }
+
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_time_in_seconds));
+        // first tick starts immediately
+        interval.tick().await;
+
+        loop {
+            tokio::select! {
+                packet = packets.recv() => {
+                    let Some((packet, from)) = packet else {
+                        return;
+                    };
+                    self.handle_packet(&packet, from).await;
+                }
+                _ = interval.tick() => {
+                    self.prune().await;
+                    self.revalidate().await;
+                    self.lookup().await;
+                }
+            }
+        }
+    }
+
+    async fn handle_packet(&mut self, data: &[u8], from: SocketAddr) {
+        let packet = match Packet::decode(self.local_id, data) {
+            Ok(packet) => packet,
+            Err(err) => {
+                debug!("Could not decode discv5 packet: {err:?}");
+                return;
+            }
+        };
+        match packet.auth_data.clone() {
+            AuthData::Message { src_id } => self.handle_message_packet(packet, src_id, from).await,
+            AuthData::WhoAreYou { enr_seq, .. } => {
+                self.handle_whoareyou_packet(packet, enr_seq, from).await
+            }
+            AuthData::Handshake {
+                src_id,
+                id_signature,
+                ephemeral_key,
+                record,
+            } => {
+                self.handle_handshake_packet(
+                    packet,
+                    src_id,
+                    &id_signature,
+                    &ephemeral_key,
+                    record,
+                    from,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn handle_message_packet(&mut self, packet: Packet, src_id: H256, from: SocketAddr) {
+        let plain_text = self.sessions.get(&src_id).and_then(|session| {
+            aes_gcm_decrypt(
+                &session.read_key,
+                &packet.nonce,
+                &packet.message,
+                &packet.authenticated_data(),
+            )
+        });
+        let Some(plain_text) = plain_text else {
+            // Either there is no session with the node or it was reset on its side, so a new handshake is needed
+            self.send_whoareyou(src_id, packet.nonce, from).await;
+            return;
+        };
+        match Message::decode(&plain_text) {
+            Ok(message) => {
+                let node = self.sessions[&src_id].node;
+                self.handle_message(node, message, from).await;
+            }
+            Err(err) => debug!("Could not decode discv5 message: {err:?}"),
+        }
+    }
+
+    async fn send_whoareyou(&mut self, src_id: H256, nonce: [u8; 12], to: SocketAddr) {
+        let enr_seq = self
+            .records
+            .get(&src_id)
+            .map(|record| record.seq)
+            .unwrap_or_default();
+        let packet = Packet {
+            masking_iv: rand::random(),
+            nonce,
+            auth_data: AuthData::WhoAreYou {
+                id_nonce: rand::random(),
+                enr_seq,
+            },
+            message: vec![],
+        };
+        self.challenges.insert(
+            src_id,
+            Challenge {
+                data: packet.authenticated_data(),
+                sent_at: time_now_unix(),
+            },
+        );
+        self.send_packet(&packet, src_id, to).await;
+    }
+
+    /// Answers the challenge of a node by starting a new session, the challenged message is sent again within the handshake
+    async fn handle_whoareyou_packet(&mut self, packet: Packet, enr_seq: u64, from: SocketAddr) {
+        let Some(sent) = self.sent_messages.remove(&packet.nonce) else {
+            debug!("Ignoring WHOAREYOU packet as it doesn't match a sent message");
+            return;
+        };
+        if SocketAddr::new(sent.node.ip, sent.node.udp_port) != from {
+            debug!("Ignoring WHOAREYOU packet as it wasn't sent by the challenged node");
+            return;
+        }
+        let Some(remote_key) = id2pubkey(sent.node.node_id) else {
+            return;
+        };
+        let dest_id = node_id_hash(sent.node.node_id);
+        let challenge_data = packet.authenticated_data();
+
+        let ephemeral_key = SecretKey::random(&mut OsRng);
+        let ephemeral_public_key = ephemeral_key
+            .public_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec();
+        let secret = ecdh(&ephemeral_key, &remote_key);
+        let keys = derive_session_keys(&secret, &challenge_data, self.local_id, dest_id);
+        let id_signature = sign_id_nonce(
+            &self.signer,
+            &challenge_data,
+            &ephemeral_public_key,
+            dest_id,
+        );
+        let local_record = self.local_record.lock().await.record.clone();
+        // The record is only sent if the node doesn't know its latest version
+        let record = (enr_seq < local_record.seq).then_some(local_record);
+
+        self.sessions.insert(
+            dest_id,
+            Session {
+                node: sent.node,
+                write_key: keys.initiator_key,
+                read_key: keys.recipient_key,
+            },
+        );
+        let auth_data = AuthData::Handshake {
+            src_id: self.local_id,
+            id_signature: id_signature.to_vec(),
+            ephemeral_key: ephemeral_public_key,
+            record,
+        };
+        self.send_encrypted(sent.node, sent.message, auth_data)
+            .await;
+        self.send_pending_messages(dest_id, sent.node).await;
+    }
+
+    async fn handle_handshake_packet(
+        &mut self,
+        packet: Packet,
+        src_id: H256,
+        id_signature: &[u8],
+        ephemeral_key: &[u8],
+        record: Option<NodeRecord>,
+        from: SocketAddr,
+    ) {
+        // The challenge is only consumed once the handshake is verified, so a forged packet can't discard it
+        let Some(challenge_data) = self
+            .challenges
+            .get(&src_id)
+            .map(|challenge| challenge.data.clone())
+        else {
+            debug!("Ignoring handshake packet as the node wasn't challenged");
+            return;
+        };
+        if let Some(record) = &record {
+            if !record.verify_signature() || record.node_id().map(node_id_hash) != Some(src_id) {
+                debug!("Ignoring handshake packet as its record is invalid");
+                return;
+            }
+        }
+        let Some(known_record) = record.as_ref().or(self.records.get(&src_id)) else {
+            debug!("Ignoring handshake packet as the record of the node is unknown");
+            return;
+        };
+        let Some(node_id) = known_record.node_id() else {
+            return;
+        };
+        let tcp_port = known_record.get("tcp").unwrap_or_default();
+        let Some(verifying_key) = id2pubkey(node_id).map(VerifyingKey::from) else {
+            return;
+        };
+        if !verify_id_nonce(
+            &verifying_key,
+            id_signature,
+            &challenge_data,
+            ephemeral_key,
+            self.local_id,
+        ) {
+            debug!("Ignoring handshake packet as its id signature is invalid");
+            return;
+        }
+        let Ok(ephemeral_key) = PublicKey::from_sec1_bytes(ephemeral_key) else {
+            return;
+        };
+        let secret = ecdh(&self.secret_key, &ephemeral_key);
+        let keys = derive_session_keys(&secret, &challenge_data, src_id, self.local_id);
+        let message = aes_gcm_decrypt(
+            &keys.initiator_key,
+            &packet.nonce,
+            &packet.message,
+            &packet.authenticated_data(),
+        )
+        .and_then(|plain_text| Message::decode(&plain_text).ok());
+        let Some(message) = message else {
+            debug!("Ignoring handshake packet as its message can't be decrypted");
+            return;
+        };
+        self.challenges.remove(&src_id);
+        if let Some(record) = record {
+            self.update_record(src_id, record);
+        }
+
+        let node = Node {
+            ip: from.ip(),
+            udp_port: from.port(),
+            tcp_port,
+            node_id,
+        };
+        self.sessions.insert(
+            src_id,
+            Session {
+                node,
+                write_key: keys.recipient_key,
+                read_key: keys.initiator_key,
+            },
+        );
+        self.send_pending_messages(src_id, node).await;
+        self.handle_message(node, message, from).await;
+    }
+
+    async fn handle_message(&mut self, node: Node, message: Message, from: SocketAddr) {
+        let src_id = node_id_hash(node.node_id);
+        match message {
+            Message::Ping(ping) => {
+                let enr_seq = self.local_record.lock().await.seq();
+                let pong = Message::Pong(PongMessage {
+                    req_id: ping.req_id,
+                    enr_seq,
+                    recipient_ip: from.ip(),
+                    recipient_port: from.port(),
+                });
+                self.send(node, pong).await;
+                self.request_record_if_outdated(node, ping.enr_seq).await;
+                // send a ping to get the endpoint proof from our end
+                let (_, inserted_to_table) = self
+                    .table
+                    .lock()
+                    .await
+                    .insert_node(node, DiscoveryProtocol::V5);
+                if inserted_to_table {
+                    self.ping(node).await;
+                }
+            }
+            Message::Pong(pong) => {
+                if !self.take_request(&pong.req_id, src_id, |kind| {
+                    matches!(kind, RequestKind::Ping)
+                }) {
+                    debug!("Discarding discv5 pong as it doesn't answer a sent ping");
+                    return;
+                }
+                let newly_proven = {
+                    let mut table = self.table.lock().await;
+                    let Some(peer) = table.get_by_node_id(node.node_id) else {
+                        return;
+                    };
+                    let newly_proven = !peer.is_proven;
+                    table.pong_answered(node.node_id);
+                    newly_proven
+                };
+                if newly_proven {
+                    self.connect(src_id).await;
+                }
+                self.request_record_if_outdated(node, pong.enr_seq).await;
+            }
+            Message::FindNode(find_node) => {
+                let records = self.find_records(&find_node.distances).await;
+                let mut chunks: Vec<Vec<NodeRecord>> = records
+                    .chunks(RECORDS_PER_NODES_MESSAGE)
+                    .map(|chunk| chunk.to_vec())
+                    .collect();
+                if chunks.is_empty() {
+                    chunks.push(vec![]);
+                }
+                let total = chunks.len() as u64;
+                for nodes in chunks {
+                    let nodes = Message::Nodes(NodesMessage {
+                        req_id: find_node.req_id.clone(),
+                        total,
+                        nodes,
+                    });
+                    self.send(node, nodes).await;
+                }
+            }
+            Message::Nodes(nodes) => self.handle_nodes(src_id, nodes).await,
+            Message::TalkReq(talk_req) => {
+                // No talk protocols are supported, which is signaled with an empty response
+                let talk_resp = Message::TalkResp(TalkRespMessage {
+                    req_id: talk_req.req_id,
+                    response: Bytes::new(),
+                });
+                self.send(node, talk_resp).await;
+            }
+            Message::TalkResp(_) => {}
+        }
+    }
+
+    async fn handle_nodes(&mut self, src_id: H256, nodes: NodesMessage) {
+        let Some(request) = self
+            .requests
+            .get_mut(&nodes.req_id)
+            .filter(|request| request.node_id == src_id)
+        else {
+            debug!("Discarding discv5 nodes as they don't answer a sent request");
+            return;
+        };
+        let RequestKind::FindNode {
+            distances,
+            responses,
+        } = &mut request.kind
+        else {
+            return;
+        };
+        let distances = distances.clone();
+        *responses += 1;
+        if *responses >= nodes.total {
+            self.requests.remove(&nodes.req_id);
+        }
+
+        for record in nodes.nodes {
+            if !record.verify_signature() {
+                continue;
+            }
+            let Some(node) = record.node() else {
+                continue;
+            };
+            let found_id = node_id_hash(node.node_id);
+            if found_id == self.local_id {
+                continue;
+            }
+            if !distances.contains(&log_distance(found_id, src_id)) {
+                debug!("Discarding node record as it is not at a requested distance");
+                continue;
+            }
+            let is_new = self.update_record(found_id, record);
+            if found_id == src_id {
+                // the node sent its own record, which may let us know where to connect to it
+                let is_proven = self
+                    .table
+                    .lock()
+                    .await
+                    .get_by_node_id(node.node_id)
+                    .is_some_and(|peer| peer.is_proven);
+                if is_new && is_proven {
+                    self.connect(src_id).await;
+                }
+                continue;
+            }
+            let (_, inserted_to_table) = self
+                .table
+                .lock()
+                .await
+                .insert_node(node, DiscoveryProtocol::V5);
+            if inserted_to_table {
+                self.ping(node).await;
+            }
+        }
+    }
+
+    /// Removes the pending request with the given id if it was sent to the node and is of the expected kind
+    fn take_request(
+        &mut self,
+        req_id: &Bytes,
+        node_id: H256,
+        is_expected_kind: impl Fn(&RequestKind) -> bool,
+    ) -> bool {
+        let is_expected = self
+            .requests
+            .get(req_id)
+            .is_some_and(|request| request.node_id == node_id && is_expected_kind(&request.kind));
+        if is_expected {
+            self.requests.remove(req_id);
+        }
+        is_expected
+    }
+
+    /// Stores the record if it's newer than the known one, returns true if there wasn't a known record
+    fn update_record(&mut self, node_id: H256, record: NodeRecord) -> bool {
+        match self.records.get(&node_id) {
+            Some(known) if known.seq >= record.seq => false,
+            Some(_) => {
+                self.records.insert(node_id, record);
+                false
+            }
+            None => {
+                self.records.insert(node_id, record);
+                true
+            }
+        }
+    }
+
+    /// Asks the node for its own record if the one we know is older than the advertised sequence number
+    async fn request_record_if_outdated(&mut self, node: Node, enr_seq: u64) {
+        let known_seq = self
+            .records
+            .get(&node_id_hash(node.node_id))
+            .map(|record| record.seq);
+        if known_seq.is_none_or(|seq| seq < enr_seq) {
+            self.find_node(node, vec![0]).await;
+        }
+    }
+
+    /// Returns the records of the proven peers at the given distances from the local node
+    async fn find_records(&self, distances: &[u16]) -> Vec<NodeRecord> {
+        let mut records = vec![];
+        let table = self.table.lock().await;
+        for distance in distances {
+            match distance {
+                0 => records.push(self.local_record.lock().await.record.clone()),
+                1..=256 => {
+                    let bucket = &table.buckets()[*distance as usize - 1];
+                    records.extend(
+                        bucket
+                            .peers
+                            .iter()
+                            .filter(|peer| peer.is_proven)
+                            .filter_map(|peer| {
+                                self.records.get(&node_id_hash(peer.node.node_id)).cloned()
+                            }),
+                    );
+                }
+                _ => {}
+            }
+            if records.len() >= MAX_NODES_PER_BUCKET {
+                break;
+            }
+        }
+        records.truncate(MAX_NODES_PER_BUCKET);
+        records
+    }
+
+    /// Starts an RLPx connection with the node, if its record tells where to connect to it
+    async fn connect(&self, node_id: H256) {
+        let Some(node) = self.records.get(&node_id).and_then(NodeRecord::node) else {
+            return;
+        };
+        if node.tcp_port == 0 {
+            return;
+        }
+        let signer = self.signer.clone();
+        let storage = self.storage.clone();
+        let table = self.table.clone();
+        let peers = self.peers.clone();
+        let broadcaster = self.connection_broadcast.clone();
+        tokio::spawn(async move {
+            handle_peer_as_initiator(signer, &node, storage, table, peers, broadcaster).await;
+        });
+    }
+
+    async fn ping(&mut self, node: Node) {
+        let req_id = Bytes::copy_from_slice(&rand::random::<[u8; 8]>());
+        let enr_seq = self.local_record.lock().await.seq();
+        self.requests.insert(
+            req_id.clone(),
+            PendingRequest {
+                node_id: node_id_hash(node.node_id),
+                kind: RequestKind::Ping,
+                sent_at: time_now_unix(),
+            },
+        );
+        self.send(node, Message::Ping(PingMessage { req_id, enr_seq }))
+            .await;
+    }
+
+    async fn find_node(&mut self, node: Node, distances: Vec<u16>) {
+        let req_id = Bytes::copy_from_slice(&rand::random::<[u8; 8]>());
+        self.requests.insert(
+            req_id.clone(),
+            PendingRequest {
+                node_id: node_id_hash(node.node_id),
+                kind: RequestKind::FindNode {
+                    distances: distances.clone(),
+                    responses: 0,
+                },
+                sent_at: time_now_unix(),
+            },
+        );
+        let find_node = Message::FindNode(FindNodeMessage { req_id, distances });
+        self.send(node, find_node).await;
+    }
+
+    /// Sends the message within the session with the node, starting a handshake if there is none
+    async fn send(&mut self, node: Node, message: Message) {
+        let dest_id = node_id_hash(node.node_id);
+        if self.sessions.contains_key(&dest_id) {
+            let auth_data = AuthData::Message {
+                src_id: self.local_id,
+            };
+            self.send_encrypted(node, message, auth_data).await;
+            return;
+        }
+        if let Some(pending) = self.pending_handshakes.get_mut(&dest_id) {
+            pending.messages.push(message);
+            return;
+        }
+        // A packet that can't be decrypted makes the node answer with a challenge, which starts the handshake
+        let packet = Packet {
+            masking_iv: rand::random(),
+            nonce: rand::random(),
+            auth_data: AuthData::Message {
+                src_id: self.local_id,
+            },
+            message: rand::random::<[u8; 20]>().to_vec(),
+        };
+        self.sent_messages.insert(
+            packet.nonce,
+            SentMessage {
+                node,
+                message,
+                sent_at: time_now_unix(),
+            },
+        );
+        self.pending_handshakes.insert(
+            dest_id,
+            PendingHandshake {
+                messages: vec![],
+                started_at: time_now_unix(),
+            },
+        );
+        let to = SocketAddr::new(node.ip, node.udp_port);
+        self.send_packet(&packet, dest_id, to).await;
+    }
+
+    async fn send_pending_messages(&mut self, node_id: H256, node: Node) {
+        if let Some(pending) = self.pending_handshakes.remove(&node_id) {
+            for message in pending.messages {
+                self.send(node, message).await;
+            }
+        }
+    }
+
+    async fn send_encrypted(&mut self, node: Node, message: Message, auth_data: AuthData) {
+        let dest_id = node_id_hash(node.node_id);
+        let Some(session) = self.sessions.get(&dest_id) else {
+            return;
+        };
+        let mut packet = Packet {
+            masking_iv: rand::random(),
+            nonce: rand::random(),
+            auth_data,
+            message: vec![],
+        };
+        let mut plain_text = Vec::new();
+        message.encode(&mut plain_text);
+        packet.message = aes_gcm_encrypt(
+            &session.write_key,
+            &packet.nonce,
+            &plain_text,
+            &packet.authenticated_data(),
+        );
+        self.sent_messages.insert(
+            packet.nonce,
+            SentMessage {
+                node,
+                message,
+                sent_at: time_now_unix(),
+            },
+        );
+        let to = SocketAddr::new(node.ip, node.udp_port);
+        self.send_packet(&packet, dest_id, to).await;
+    }
+
+    async fn send_packet(&self, packet: &Packet, dest_id: H256, to: SocketAddr) {
+        let _ = self.udp_socket.send_to(&packet.encode(dest_id), to).await;
+    }
+
+    /// Checks that the peers pinged by the previous revalidation have answered, and pings the
+    /// discv5 peers that have answered least recently.
+    /// Liveness is tracked as in the discv4 revalidation, see `peers_revalidation`
+    async fn revalidate(&mut self) {
+        for node_id in std::mem::take(&mut self.revalidated_peers) {
+            let new_peer = {
+                let mut table = self.table.lock().await;
+                let Some(peer) = table.get_by_node_id_mut(node_id) else {
+                    continue;
+                };
+                if let Some(has_answered) = peer.revalidation {
+                    if has_answered {
+                        peer.increment_liveness();
+                    } else {
+                        peer.decrement_liveness();
+                    }
+                }
+                peer.revalidation = None;
+                if peer.liveness > 0 {
+                    continue;
+                }
+                table.replace_peer(node_id)
+            };
+            // replacements found by discv4 are revalidated by it
+            if let Some(new_peer) = new_peer.filter(|peer| peer.protocol == DiscoveryProtocol::V5) {
+                self.ping(new_peer.node).await;
+            }
+        }
+
+        let mut peers: Vec<_> = {
+            let table = self.table.lock().await;
+            table
+                .buckets()
+                .iter()
+                .flat_map(|bucket| bucket.peers.iter())
+                .filter(|peer| peer.protocol == DiscoveryProtocol::V5)
+                .map(|peer| (peer.last_pong, peer.node, peer.last_ping_hash))
+                .collect()
+        };
+        peers.sort_by_key(|(last_pong, _, _)| *last_pong);
+        for (_, node, last_ping_hash) in peers.into_iter().take(REVALIDATION_PEERS) {
+            self.table
+                .lock()
+                .await
+                .update_peer_ping_with_revalidation(node.node_id, last_ping_hash);
+            self.revalidated_peers.push(node.node_id);
+            self.ping(node).await;
+        }
+    }
+
+    /// Asks the discv5 peers closest to a random target for the nodes around it
+    async fn lookup(&mut self) {
+        let target = H256::random();
+        let mut peers: Vec<(u16, Node)> = {
+            let table = self.table.lock().await;
+            table
+                .buckets()
+                .iter()
+                .flat_map(|bucket| bucket.peers.iter())
+                .filter(|peer| peer.is_proven && peer.protocol == DiscoveryProtocol::V5)
+                .map(|peer| {
+                    let peer_id = node_id_hash(peer.node.node_id);
+                    (log_distance(peer_id, target), peer.node)
+                })
+                .collect()
+        };
+        peers.sort_by_key(|(distance, _)| *distance);
+        for (distance, node) in peers.into_iter().take(LOOKUP_CONCURRENCY) {
+            // the nodes closest to the target are at about the same distance from the peer as the target
+            let distances = [distance, distance + 1, distance.saturating_sub(1)]
+                .into_iter()
+                .filter(|distance| (1..=256).contains(distance))
+                .collect();
+            self.find_node(node, distances).await;
+        }
+    }
+
+    /// Discards expired requests, and the sessions and records of nodes that are no longer in the table
+    async fn prune(&mut self) {
+        let now = time_now_unix();
+        let is_alive = |sent_at: u64| now.saturating_sub(sent_at) < REQUEST_TIMEOUT_IN_SECONDS;
+        self.sent_messages.retain(|_, sent| is_alive(sent.sent_at));
+        self.challenges
+            .retain(|_, challenge| is_alive(challenge.sent_at));
+        self.requests.retain(|_, request| is_alive(request.sent_at));
+        self.pending_handshakes
+            .retain(|_, pending| is_alive(pending.started_at));
+
+        let table = self.table.lock().await;
+        self.records.retain(|_, record| {
+            record
+                .node_id()
+                .is_some_and(|node_id| table.get_by_node_id(node_id).is_some())
+        });
+        if self.sessions.len() > MAX_SESSIONS {
+            self.sessions
+                .retain(|_, session| table.get_by_node_id(session.node.node_id).is_some());
+        }
+    }
+}
+
+/// Hands the packets received by the socket to the discv5 server, used when discv4 isn't running
+pub(crate) async fn receive_packets(
+    udp_socket: Arc<UdpSocket>,
+    packets: mpsc::UnboundedSender<(Vec<u8>, SocketAddr)>,
+) {
+    let mut buf = vec![0; MAX_DISC_PACKET_SIZE];
+    loop {
+        let (read, from) = udp_socket.recv_from(&mut buf).await.unwrap();
+        if packets.send((buf[..read].to_vec(), from)).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_core::types::Genesis;
+    use ethrex_storage::EngineType;
+    use std::{fs::File, io::BufReader, net::IpAddr};
+
+    struct TestNode {
+        node: Node,
+        table: Arc<Mutex<KademliaTable>>,
+    }
+
+    async fn start_discv5_node(udp_port: u16, bootnodes: Vec<BootNode>) -> TestNode {
+        let addr = SocketAddr::new(IpAddr::from([127, 0, 0, 1]), udp_port);
+        let signer = SigningKey::random(&mut OsRng);
+        let node_id = crate::node_id_from_signing_key(&signer);
+        let node = Node {
+            ip: addr.ip(),
+            udp_port,
+            // no tcp port is advertised, so that no RLPx connections are started
+            tcp_port: 0,
+            node_id,
+        };
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let file = File::open("../../../test_data/genesis-execution-api.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to add genesis block to DB");
+        let table = Arc::new(Mutex::new(KademliaTable::new(node_id)));
+        let local_record = Arc::new(Mutex::new(LocalNodeRecord::new(
+            node,
+            signer.clone(),
+            storage.clone(),
+        )));
+        let (connection_broadcast, _) = broadcast::channel(10);
+        let udp_socket = Arc::new(UdpSocket::bind(addr).await.unwrap());
+        let (packets_send, packets_receive) = mpsc::unbounded_channel();
+
+        let server = Discv5Server::new(
+            udp_socket.clone(),
+            signer,
+            local_record,
+            table.clone(),
+            storage,
+            PeerManager::default(),
+            connection_broadcast,
+        );
+        tokio::spawn(receive_packets(udp_socket, packets_send));
+        tokio::spawn(server.run(packets_receive, bootnodes, 1));
+        TestNode { node, table }
+    }
+
+    async fn is_proven(table: &Arc<Mutex<KademliaTable>>, node_id: H512) -> bool {
+        table
+            .lock()
+            .await
+            .get_by_node_id(node_id)
+            .is_some_and(|peer| peer.is_proven)
+    }
+
+    #[test]
+    fn log_distance_between_ids() {
+        assert_eq!(log_distance(H256::zero(), H256::zero()), 0);
+        assert_eq!(log_distance(H256::zero(), H256::from_low_u64_be(1)), 1);
+        assert_eq!(log_distance(H256::zero(), H256::from_low_u64_be(0x80)), 8);
+        assert_eq!(log_distance(H256::repeat_byte(0xff), H256::zero()), 256);
+    }
+
+    #[tokio::test]
+    /** This is a end to end test on the discv5 server, the idea is as follows:
+     * - We start two discv5 servers, the second one uses the first one as its bootnode
+     * - The second server pings the first one, which starts the handshake and the session between them
+     * - The first server pings the second one back within that session
+     * - After both pongs are received, each server should have the other one proven in its table
+     */
+    async fn discv5_nodes_find_each_other() {
+        let server_a = start_discv5_node(8020, vec![]).await;
+        let bootnode = BootNode {
+            node_id: server_a.node.node_id,
+            socket_address: SocketAddr::new(server_a.node.ip, server_a.node.udp_port),
+            tcp_port: server_a.node.tcp_port,
+        };
+        let server_b = start_discv5_node(8021, vec![bootnode]).await;
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        assert!(is_proven(&server_a.table, server_b.node.node_id).await);
+        assert!(is_proven(&server_b.table, server_a.node.node_id).await);
+    }
+}
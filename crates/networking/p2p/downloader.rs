@@ -0,0 +1,550 @@
+use std::{future::Future, ops::Range, sync::Arc, time::Duration};
+
+use ethrex_core::{
+    types::{
+        compute_receipts_root, compute_transactions_root, compute_withdrawals_root, BlockBody,
+        BlockHash, BlockHeader, BlockNumber, Receipt,
+    },
+    H256, H512,
+};
+use ethrex_rlp::encode::RLPEncode;
+use sha3::{Digest, Keccak256};
+use tokio::{
+    sync::Mutex,
+    task::JoinSet,
+    time::{sleep, Instant},
+};
+use tracing::{debug, info};
+
+use crate::{
+    kademlia::KademliaTable, peer_channels::PeerChannels, rlpx::eth::blocks::BLOCK_HEADER_LIMIT,
+};
+
+/// Max amount of requests sent at once during a download, each of them to a different peer
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+/// Max amount of block bodies or receipts requested at once from a single peer
+const BLOCK_DATA_BATCH_SIZE: usize = 128;
+/// Amount of blocks whose bodies or receipts should be downloaded at once to make the most out of the available peers
+pub const BLOCK_DOWNLOAD_WINDOW: usize = MAX_CONCURRENT_REQUESTS * BLOCK_DATA_BATCH_SIZE;
+/// Max amount of times a request can fail in a row before the download is given up
+const MAX_REQUEST_RETRIES: usize = 5;
+/// Time waited before asking the peers again for a header none of them could provide
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Downloads the headers of the blocks following the given parent block by requesting consecutive ranges of headers from different peers at once
+/// Each range is checked to be a valid chain of headers, and ranges are only returned as long as they are linked to the parent block and to each other
+/// Returns the downloaded headers, which may be empty if no peer could provide the headers following the parent block
+pub async fn download_headers(
+    peers: &Arc<Mutex<KademliaTable>>,
+    parent_hash: BlockHash,
+    parent_number: BlockNumber,
+) -> Vec<BlockHeader> {
+    let selected_peers = select_peers(peers, MAX_CONCURRENT_REQUESTS).await;
+    let mut set = JoinSet::new();
+    for (index, (node_id, channels)) in selected_peers.into_iter().enumerate() {
+        let start = parent_number + 1 + index as u64 * BLOCK_HEADER_LIMIT;
+        set.spawn(async move {
+            let request_time = Instant::now();
+            let response = channels.request_block_headers_from_number(start).await;
+            (index, start, node_id, response, request_time.elapsed())
+        });
+    }
+    let mut responses = set.join_all().await;
+    responses.sort_by_key(|(index, ..)| *index);
+
+    let mut peers = peers.lock().await;
+    let mut ranges = vec![];
+    for (_, start, node_id, response, latency) in responses {
+        match response {
+            // An empty response means that the peer doesn't know of the requested blocks (yet)
+            Some(headers) if headers.is_empty() => ranges.push(None),
+            Some(headers) if is_valid_header_range(&headers, start) => {
+                peers.record_success(node_id, latency);
+                ranges.push(Some((node_id, headers)));
+            }
+            Some(_) => {
+                peers.record_invalid_response(node_id);
+                ranges.push(None);
+            }
+            None => {
+                peers.record_failure(node_id);
+                ranges.push(None);
+            }
+        }
+    }
+    // Link the ranges to the parent block and to each other, stopping at the first gap
+    // We can't tell which peer is at fault when two ranges don't link, so peers are only penalized if their range doesn't link to our parent block
+    let mut block_headers: Vec<BlockHeader> = vec![];
+    let mut last_hash = parent_hash;
+    for (node_id, headers) in ranges.into_iter().map_while(|range| range) {
+        if headers[0].parent_hash != last_hash {
+            if block_headers.is_empty() {
+                peers.record_failure(node_id);
+            }
+            break;
+        }
+        let complete = headers.len() as u64 == BLOCK_HEADER_LIMIT;
+        last_hash = headers
+            .last()
+            .map(BlockHeader::compute_block_hash)
+            .unwrap_or(last_hash);
+        block_headers.extend(headers);
+        // An incomplete range means that the peer doesn't know of any blocks after it
+        if !complete {
+            break;
+        }
+    }
+    debug!(
+        "Downloaded {} block headers after block {parent_number}",
+        block_headers.len()
+    );
+    block_headers
+}
+
+/// Downloads the header of the block with the given hash, trying with different peers until one of them returns it
+/// Returns None if no peer could provide the header after `MAX_REQUEST_RETRIES` rounds of requests
+pub async fn download_header(
+    peers: &Arc<Mutex<KademliaTable>>,
+    block_hash: BlockHash,
+) -> Option<BlockHeader> {
+    for attempt in 0..MAX_REQUEST_RETRIES {
+        if attempt > 0 {
+            sleep(RETRY_DELAY).await;
+        }
+        for (node_id, channels) in select_peers(peers, MAX_CONCURRENT_REQUESTS).await {
+            let request_time = Instant::now();
+            let response = channels
+                .request_block_headers_reverse(block_hash, 1, 0)
+                .await;
+            let mut peers = peers.lock().await;
+            match response {
+                Some(mut headers) if headers.len() == 1 => {
+                    let header = headers.remove(0);
+                    if header.compute_block_hash() != block_hash {
+                        peers.record_invalid_response(node_id);
+                        continue;
+                    }
+                    peers.record_success(node_id, request_time.elapsed());
+                    return Some(header);
+                }
+                // The peer doesn't know of the block
+                Some(headers) if headers.is_empty() => continue,
+                Some(_) => peers.record_invalid_response(node_id),
+                None => peers.record_failure(node_id),
+            }
+        }
+    }
+    None
+}
+
+/// Downloads the headers of the ancestors of the given block (from newest to oldest), so that they can be verified against it
+/// A skeleton made of every `BLOCK_HEADER_LIMIT`-th ancestor is requested from one peer, and the gaps between them are then filled
+/// by requesting consecutive ranges of headers from different peers at once
+/// As ranges are requested by hash and have to be linked to the given block and to each other, only actual ancestors of the block are returned,
+/// stopping at the first range that can't be linked
+/// Ancestors numbered below `lowest_number` (which must not be lower than 1) are not requested
+/// Returns the downloaded headers, which may be empty if no peer could provide the ancestors of the given block
+pub async fn download_headers_backwards(
+    peers: &Arc<Mutex<KademliaTable>>,
+    block_header: &BlockHeader,
+    lowest_number: BlockNumber,
+) -> Vec<BlockHeader> {
+    let parent_number = block_header.number.saturating_sub(1);
+    let selected_peers = select_peers(peers, MAX_CONCURRENT_REQUESTS).await;
+    // Request the skeleton (unless it would only contain the parent, which we already know of)
+    let ranges = (selected_peers.len() as u64)
+        .min(reverse_range_len(parent_number, lowest_number).div_ceil(BLOCK_HEADER_LIMIT));
+    let (skeleton_peer, skeleton_channels) = selected_peers[0].clone();
+    let skeleton = if ranges > 1 {
+        let request_time = Instant::now();
+        let response = skeleton_channels
+            .request_block_headers_reverse(block_header.parent_hash, ranges, BLOCK_HEADER_LIMIT - 1)
+            .await;
+        let mut peers = peers.lock().await;
+        match response {
+            Some(skeleton) if skeleton.is_empty() => return vec![],
+            Some(skeleton)
+                if skeleton.len() as u64 <= ranges
+                    && skeleton.iter().enumerate().all(|(index, header)| {
+                        header.number == parent_number - index as u64 * BLOCK_HEADER_LIMIT
+                    }) =>
+            {
+                peers.record_success(skeleton_peer, request_time.elapsed());
+                skeleton
+                    .iter()
+                    .map(|header| (header.compute_block_hash(), header.number))
+                    .collect()
+            }
+            Some(_) => {
+                peers.record_invalid_response(skeleton_peer);
+                return vec![];
+            }
+            None => {
+                peers.record_failure(skeleton_peer);
+                return vec![];
+            }
+        }
+    } else {
+        vec![(block_header.parent_hash, parent_number)]
+    };
+
+    // Fill the gaps between the skeleton's headers
+    let mut set = JoinSet::new();
+    for (index, ((start_hash, start_number), (node_id, channels))) in
+        skeleton.iter().copied().zip(selected_peers).enumerate()
+    {
+        let limit = reverse_range_len(start_number, lowest_number);
+        set.spawn(async move {
+            let request_time = Instant::now();
+            let response = channels
+                .request_block_headers_reverse(start_hash, limit, 0)
+                .await;
+            (index, node_id, response, request_time.elapsed())
+        });
+    }
+    let mut responses = set.join_all().await;
+    responses.sort_by_key(|(index, ..)| *index);
+
+    // Link the ranges to the given block and to each other, stopping at the first gap
+    let mut peers = peers.lock().await;
+    let mut block_headers: Vec<BlockHeader> = vec![];
+    let mut next_hash = block_header.parent_hash;
+    for ((_, node_id, response, latency), (start_hash, start_number)) in
+        responses.into_iter().zip(skeleton)
+    {
+        // Each range starts at a skeleton header, so the skeleton is wrong if it doesn't match the parent of the previous range
+        if start_hash != next_hash {
+            peers.record_invalid_response(skeleton_peer);
+            break;
+        }
+        let headers = match response {
+            // An empty response means that the peer doesn't know of the requested blocks
+            Some(headers) if headers.is_empty() => break,
+            Some(headers) if is_valid_reverse_header_range(&headers, start_hash, start_number) => {
+                peers.record_success(node_id, latency);
+                headers
+            }
+            Some(_) => {
+                peers.record_invalid_response(node_id);
+                break;
+            }
+            None => {
+                peers.record_failure(node_id);
+                break;
+            }
+        };
+        let complete = headers.len() as u64 == reverse_range_len(start_number, lowest_number);
+        // Ranges are not empty
+        next_hash = headers.last().unwrap().parent_hash;
+        block_headers.extend(headers);
+        // An incomplete range leaves a gap before the next one
+        if !complete {
+            break;
+        }
+    }
+    debug!(
+        "Downloaded {} block headers before block {}",
+        block_headers.len(),
+        block_header.number
+    );
+    block_headers
+}
+
+/// Downloads the bodies of the given blocks, spreading the requests across peers and checking each body against its header
+/// Returns None if some of the bodies couldn't be downloaded
+pub async fn download_bodies(
+    peers: &Arc<Mutex<KademliaTable>>,
+    block_headers: &[BlockHeader],
+) -> Option<Vec<BlockBody>> {
+    download_block_data(peers, block_headers).await
+}
+
+/// Downloads the receipts of the given blocks, spreading the requests across peers and checking each block's receipts against its header
+/// Returns None if some of the receipts couldn't be downloaded
+pub async fn download_receipts(
+    peers: &Arc<Mutex<KademliaTable>>,
+    block_headers: &[BlockHeader],
+) -> Option<Vec<Vec<Receipt>>> {
+    download_block_data(peers, block_headers).await
+}
+
+/// Data that is requested from peers for each block and can be checked against the block's header
+trait BlockData: Sized + Send + 'static {
+    fn request(
+        channels: PeerChannels,
+        block_hashes: Vec<BlockHash>,
+    ) -> impl Future<Output = Option<Vec<Self>>> + Send;
+
+    fn is_valid(&self, header: &BlockHeader) -> bool;
+}
+
+impl BlockData for BlockBody {
+    async fn request(channels: PeerChannels, block_hashes: Vec<BlockHash>) -> Option<Vec<Self>> {
+        channels.request_block_bodies(block_hashes).await
+    }
+
+    fn is_valid(&self, header: &BlockHeader) -> bool {
+        compute_transactions_root(&self.transactions) == header.transactions_root
+            && H256(Keccak256::digest(self.ommers.encode_to_vec()).into()) == header.ommers_hash
+            && self.withdrawals.as_deref().map(compute_withdrawals_root) == header.withdrawals_root
+    }
+}
+
+impl BlockData for Vec<Receipt> {
+    async fn request(channels: PeerChannels, block_hashes: Vec<BlockHash>) -> Option<Vec<Self>> {
+        channels.request_receipts(block_hashes).await
+    }
+
+    fn is_valid(&self, header: &BlockHeader) -> bool {
+        compute_receipts_root(self) == header.receipts_root
+    }
+}
+
+/// Downloads the data of each of the given blocks by requesting batches of it from different peers at once
+/// Batches that fail, are empty or are invalid are requested again (to a different peer if possible) until all of the data is downloaded
+/// Returns None if a batch fails `MAX_REQUEST_RETRIES` times in a row
+async fn download_block_data<T: BlockData>(
+    peers: &Arc<Mutex<KademliaTable>>,
+    block_headers: &[BlockHeader],
+) -> Option<Vec<T>> {
+    let block_hashes: Vec<BlockHash> = block_headers
+        .iter()
+        .map(BlockHeader::compute_block_hash)
+        .collect();
+    let mut data: Vec<Option<T>> = block_headers.iter().map(|_| None).collect();
+    // Ranges of block indexes whose data is yet to be downloaded, along with the amount of times they failed in a row
+    let mut pending: Vec<(Range<usize>, usize)> = (0..block_headers.len())
+        .step_by(BLOCK_DATA_BATCH_SIZE)
+        .map(|start| {
+            (
+                start..(start + BLOCK_DATA_BATCH_SIZE).min(block_headers.len()),
+                0,
+            )
+        })
+        .rev()
+        .collect();
+    while !pending.is_empty() {
+        let selected_peers = select_peers(peers, pending.len().min(MAX_CONCURRENT_REQUESTS)).await;
+        let mut set = JoinSet::new();
+        for (node_id, channels) in selected_peers {
+            // We selected at most one peer per pending range
+            let (range, failures) = pending.pop().unwrap();
+            let batch = block_hashes[range.clone()].to_vec();
+            set.spawn(async move {
+                let request_time = Instant::now();
+                let response = T::request(channels, batch).await;
+                (range, failures, node_id, response, request_time.elapsed())
+            });
+        }
+        let responses = set.join_all().await;
+        let mut peers = peers.lock().await;
+        for (range, failures, node_id, response, latency) in responses {
+            let failed = match response {
+                // An empty response means that the peer doesn't have the data, which we know exists
+                Some(response) if response.is_empty() => {
+                    peers.record_failure(node_id);
+                    true
+                }
+                Some(response)
+                    if response.len() <= range.len()
+                        && response
+                            .iter()
+                            .zip(&block_headers[range.clone()])
+                            .all(|(data, header)| data.is_valid(header)) =>
+                {
+                    peers.record_success(node_id, latency);
+                    let fetched = range.start + response.len();
+                    for (index, response) in (range.start..fetched).zip(response) {
+                        data[index] = Some(response);
+                    }
+                    // Request the rest of the range later if the response was incomplete
+                    if fetched < range.end {
+                        pending.push((fetched..range.end, 0));
+                    }
+                    false
+                }
+                Some(_) => {
+                    peers.record_invalid_response(node_id);
+                    true
+                }
+                None => {
+                    peers.record_failure(node_id);
+                    true
+                }
+            };
+            if failed {
+                if failures + 1 >= MAX_REQUEST_RETRIES {
+                    debug!(
+                        "Giving up on the data of blocks {}-{} after {MAX_REQUEST_RETRIES} failed requests",
+                        block_headers[range.start].number,
+                        block_headers[range.end - 1].number
+                    );
+                    return None;
+                }
+                pending.push((range, failures + 1));
+            }
+        }
+    }
+    // All ranges were downloaded
+    Some(data.into_iter().flatten().collect())
+}
+
+/// Returns the ids and channels of (at most `count`) of the best available peers
+/// If there are no peers available, this method will try again after 10 seconds
+async fn select_peers(
+    peers: &Arc<Mutex<KademliaTable>>,
+    count: usize,
+) -> Vec<(H512, PeerChannels)> {
+    loop {
+        let selected_peers = peers.lock().await.get_peers_channels(count);
+        if !selected_peers.is_empty() {
+            return selected_peers;
+        }
+        info!("[Sync] No peers available, retrying in 10 sec");
+        // This is the unlikely case where we just started the node and don't have peers, wait a bit and try again
+        sleep(Duration::from_secs(10)).await;
+    }
+}
+
+/// Returns true if the headers form a chain of consecutive blocks starting from the given block number
+fn is_valid_header_range(headers: &[BlockHeader], start: BlockNumber) -> bool {
+    headers.len() as u64 <= BLOCK_HEADER_LIMIT
+        && headers.first().is_some_and(|header| header.number == start)
+        && headers.windows(2).all(|pair| {
+            pair[1].number == pair[0].number + 1
+                && pair[1].parent_hash == pair[0].compute_block_hash()
+        })
+}
+
+/// Returns true if the headers form a chain of consecutive blocks (from newest to oldest) starting from the given block
+/// Amount of headers to request backwards from `start_number` without going past `lowest_number`
+fn reverse_range_len(start_number: BlockNumber, lowest_number: BlockNumber) -> u64 {
+    BLOCK_HEADER_LIMIT.min((start_number + 1).saturating_sub(lowest_number))
+}
+
+fn is_valid_reverse_header_range(
+    headers: &[BlockHeader],
+    start_hash: BlockHash,
+    start_number: BlockNumber,
+) -> bool {
+    headers.len() as u64 <= BLOCK_HEADER_LIMIT
+        && headers.first().is_some_and(|header| {
+            header.number == start_number && header.compute_block_hash() == start_hash
+        })
+        && headers.windows(2).all(|pair| {
+            pair[1].number + 1 == pair[0].number
+                && pair[1].compute_block_hash() == pair[0].parent_hash
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_core::types::{BlockHeader, Withdrawal, DEFAULT_OMMERS_HASH, EMPTY_TRIE_HASH};
+
+    fn header_chain(start: BlockNumber, len: usize) -> Vec<BlockHeader> {
+        let mut headers: Vec<BlockHeader> = vec![];
+        for number in start..start + len as u64 {
+            let parent_hash = headers
+                .last()
+                .map(BlockHeader::compute_block_hash)
+                .unwrap_or_default();
+            headers.push(BlockHeader {
+                number,
+                parent_hash,
+                ..Default::default()
+            });
+        }
+        headers
+    }
+
+    #[test]
+    fn valid_header_range() {
+        let headers = header_chain(10, 5);
+        assert!(is_valid_header_range(&headers, 10));
+    }
+
+    #[test]
+    fn header_range_with_wrong_start_is_invalid() {
+        let headers = header_chain(10, 5);
+        assert!(!is_valid_header_range(&headers, 9));
+        assert!(!is_valid_header_range(&[], 10));
+    }
+
+    #[test]
+    fn unlinked_header_range_is_invalid() {
+        let mut headers = header_chain(10, 5);
+        headers[3].parent_hash = H256::random();
+        assert!(!is_valid_header_range(&headers, 10));
+        let mut headers = header_chain(10, 5);
+        headers.remove(2);
+        assert!(!is_valid_header_range(&headers, 10));
+    }
+
+    #[test]
+    fn valid_reverse_header_range() {
+        let mut headers = header_chain(10, 5);
+        headers.reverse();
+        let start_hash = headers[0].compute_block_hash();
+        assert!(is_valid_reverse_header_range(&headers, start_hash, 14));
+        assert!(!is_valid_reverse_header_range(&headers, start_hash, 13));
+        assert!(!is_valid_reverse_header_range(&headers, H256::random(), 14));
+    }
+
+    #[test]
+    fn reverse_ranges_stop_at_the_lowest_number() {
+        assert_eq!(reverse_range_len(5000, 1), BLOCK_HEADER_LIMIT);
+        assert_eq!(reverse_range_len(5000, 4990), 11);
+        assert_eq!(reverse_range_len(10, 1), 10);
+    }
+
+    #[test]
+    fn unlinked_reverse_header_range_is_invalid() {
+        let mut headers = header_chain(10, 5);
+        headers.reverse();
+        let start_hash = headers[0].compute_block_hash();
+        headers[2].gas_limit += 1;
+        assert!(!is_valid_reverse_header_range(&headers, start_hash, 14));
+    }
+
+    #[test]
+    fn body_is_checked_against_header() {
+        let withdrawals = vec![Withdrawal {
+            index: 1,
+            validator_index: 2,
+            address: Default::default(),
+            amount: 3,
+        }];
+        let header = BlockHeader {
+            transactions_root: *EMPTY_TRIE_HASH,
+            ommers_hash: *DEFAULT_OMMERS_HASH,
+            withdrawals_root: Some(compute_withdrawals_root(&withdrawals)),
+            ..Default::default()
+        };
+        let body = BlockBody {
+            transactions: vec![],
+            ommers: vec![],
+            withdrawals: Some(withdrawals),
+        };
+        assert!(body.is_valid(&header));
+        // Withdrawals don't match
+        assert!(!BlockBody::empty().is_valid(&header));
+        // Ommers don't match
+        let body = BlockBody {
+            ommers: vec![header.clone()],
+            ..body
+        };
+        assert!(!body.is_valid(&header));
+    }
+
+    #[test]
+    fn receipts_are_checked_against_header() {
+        let header = BlockHeader {
+            receipts_root: *EMPTY_TRIE_HASH,
+            ..Default::default()
+        };
+        assert!(Vec::<Receipt>::new().is_valid(&header));
+        let receipts = vec![Receipt::new(Default::default(), true, 21000, vec![])];
+        assert!(!receipts.is_valid(&header));
+    }
+}
@@ -4,13 +4,22 @@ use crate::{
     types::Node,
 };
 use ethrex_core::{H256, H512, U256};
+use rand::seq::SliceRandom;
 use sha3::{Digest, Keccak256};
+use std::time::Duration;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::info;
+use tracing::{debug, info};
 
 pub const MAX_NODES_PER_BUCKET: usize = 16;
 const NUMBER_OF_BUCKETS: usize = 256;
 const MAX_NUMBER_OF_REPLACEMENTS: usize = 10;
+/// Score gained by a peer for each request it answers, up to `MAX_PEER_SCORE`
+const SUCCESS_REWARD: i32 = 1;
+/// Score lost by a peer for each request it fails to answer
+const FAILURE_PENALTY: i32 = 10;
+const MAX_PEER_SCORE: i32 = 50;
+/// Peers are dropped once their score falls to this value
+const MIN_PEER_SCORE: i32 = -50;
 
 #[derive(Clone, Debug, Default)]
 pub struct Bucket {
@@ -59,23 +68,33 @@ impl KademliaTable {
     /// A tuple containing:
     ///     1. PeerData: none if the peer was already in the table or as a potential replacement
     ///     2. A bool indicating if the node was inserted to the table
-    pub fn insert_node(&mut self, node: Node) -> (Option<PeerData>, bool) {
+    pub fn insert_node(
+        &mut self,
+        node: Node,
+        protocol: DiscoveryProtocol,
+    ) -> (Option<PeerData>, bool) {
         let node_id = node.node_id;
         let bucket_idx = bucket_number(node_id, self.local_node_id);
 
-        self.insert_node_inner(node, bucket_idx)
+        self.insert_node_inner(node, protocol, bucket_idx)
     }
 
     #[cfg(test)]
     pub fn insert_node_on_custom_bucket(
         &mut self,
         node: Node,
+        protocol: DiscoveryProtocol,
         bucket_idx: usize,
     ) -> (Option<PeerData>, bool) {
-        self.insert_node_inner(node, bucket_idx)
+        self.insert_node_inner(node, protocol, bucket_idx)
     }
 
-    fn insert_node_inner(&mut self, node: Node, bucket_idx: usize) -> (Option<PeerData>, bool) {
+    fn insert_node_inner(
+        &mut self,
+        node: Node,
+        protocol: DiscoveryProtocol,
+        bucket_idx: usize,
+    ) -> (Option<PeerData>, bool) {
         let node_id = node.node_id;
 
         let peer_already_in_table = self.buckets[bucket_idx]
@@ -93,7 +112,7 @@ impl KademliaTable {
             return (None, false);
         }
 
-        let peer = PeerData::new(node, time_now_unix(), 0, false);
+        let peer = PeerData::new(node, protocol, time_now_unix(), 0, false);
 
         if self.buckets[bucket_idx].peers.len() == MAX_NODES_PER_BUCKET {
             self.insert_as_replacement(&peer, bucket_idx);
@@ -123,13 +142,14 @@ impl KademliaTable {
             .collect();
     }
 
-    pub fn get_closest_nodes(&self, node_id: H512) -> Vec<Node> {
+    /// Returns the peers found through the given protocol that are closest to the node id
+    pub fn get_closest_nodes(&self, node_id: H512, protocol: DiscoveryProtocol) -> Vec<Node> {
         let mut nodes: Vec<(Node, usize)> = vec![];
 
         // todo see if there is a more efficient way of doing this
         // though the bucket isn't that large and it shouldn't be an issue I guess
         for bucket in &self.buckets {
-            for peer in &bucket.peers {
+            for peer in bucket.peers.iter().filter(|peer| peer.protocol == protocol) {
                 let distance = bucket_number(node_id, peer.node.node_id);
                 if nodes.len() < MAX_NODES_PER_BUCKET {
                     nodes.push((peer.node, distance));
@@ -189,11 +209,15 @@ impl KademliaTable {
     /// ## Dev note:
     /// This function should be improved:
     /// We might keep the `peers` list sorted by last_ping as we would avoid unnecessary loops
-    pub fn get_least_recently_pinged_peers(&self, limit: usize) -> Vec<PeerData> {
+    pub fn get_least_recently_pinged_peers(
+        &self,
+        limit: usize,
+        protocol: DiscoveryProtocol,
+    ) -> Vec<PeerData> {
         let mut peers = vec![];
 
         for bucket in &self.buckets {
-            for peer in &bucket.peers {
+            for peer in bucket.peers.iter().filter(|peer| peer.protocol == protocol) {
                 if peers.len() < limit {
                     peers.push(peer.clone());
                 } else {
@@ -259,7 +283,8 @@ impl KademliaTable {
 
     /// Set the sender end of the channel between the kademlia table and the peer's active connection
     /// This function should be called each time a connection is established so the backend can send requests to the peers
-    pub fn set_channels(&mut self, node_id: H512, channels: PeerChannels) {
+    /// Returns false if the peer is not part of the table
+    pub fn set_channels(&mut self, node_id: H512, channels: PeerChannels) -> bool {
         let bucket_idx = bucket_number(self.local_node_id, node_id);
         if let Some(peer) = self.buckets.get_mut(bucket_idx).and_then(|bucket| {
             bucket
@@ -267,29 +292,77 @@ impl KademliaTable {
                 .iter_mut()
                 .find(|peer| peer.node.node_id == node_id)
         }) {
-            peer.channels = Some(channels)
+            peer.channels = Some(channels);
+            return true;
         }
+        false
     }
 
-    /// TODO: Randomly select peer
-    pub fn get_peer(&self) -> Option<PeerData> {
-        self.get_least_recently_pinged_peers(1).pop()
+    /// Returns the ids and channel ends of (at most `limit`) peers with an active connection,
+    /// sorted from best to worst by their score and then by their latency
+    /// TODO: Filter peers by capabilities
+    pub fn get_peers_channels(&self, limit: usize) -> Vec<(H512, PeerChannels)> {
+        let mut peers: Vec<&PeerData> = self
+            .buckets
+            .iter()
+            .flat_map(|bucket| bucket.peers.iter())
+            .filter(|peer| peer.channels.is_some())
+            .collect();
+        peers.sort_by_key(|peer| (-peer.score, peer.latency.unwrap_or_default()));
+        peers
+            .into_iter()
+            .take(limit)
+            .filter_map(|peer| Some((peer.node.node_id, peer.channels.clone()?)))
+            .collect()
     }
 
     /// Returns the channel ends to an active peer connection
-    /// The peer is selected randomly (TODO), and doesn't guarantee that the selected peer is not currenlty busy
+    /// The peer is selected randomly, and doesn't guarantee that the selected peer is not currenlty busy
     /// If no peer is found, this method will try again after 10 seconds
     /// TODO: Filter peers by capabilities, set max amount of retries
     pub async fn get_peer_channels(&self) -> PeerChannels {
         loop {
-            if let Some(channels) = self.get_peer().and_then(|peer| peer.channels) {
-                return channels;
+            if let Some((_, channels)) = self
+                .get_peers_channels(usize::MAX)
+                .choose(&mut rand::thread_rng())
+            {
+                return channels.clone();
             }
             info!("[Sync] No peers available, retrying in 10 sec");
             // This is the unlikely case where we just started the node and don't have peers, wait a bit and try again
             tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
         }
     }
+
+    /// Rewards a peer for answering a request and updates its average latency
+    pub fn record_success(&mut self, node_id: H512, latency: Duration) {
+        if let Some(peer) = self.get_by_node_id_mut(node_id) {
+            peer.score = (peer.score + SUCCESS_REWARD).min(MAX_PEER_SCORE);
+            peer.latency = Some(match peer.latency {
+                Some(average) => (average * 3 + latency) / 4,
+                None => latency,
+            });
+        }
+    }
+
+    /// Penalizes a peer for failing to answer a request (due to a timeout or an empty response)
+    /// The peer is dropped if its score falls too low
+    pub fn record_failure(&mut self, node_id: H512) {
+        let Some(peer) = self.get_by_node_id_mut(node_id) else {
+            return;
+        };
+        peer.score -= FAILURE_PENALTY;
+        if peer.score <= MIN_PEER_SCORE {
+            debug!("Dropping peer {node_id} after too many failed requests");
+            self.replace_peer(node_id);
+        }
+    }
+
+    /// Drops a peer that answered a request with invalid data
+    pub fn record_invalid_response(&mut self, node_id: H512) {
+        debug!("Dropping peer {node_id} after receiving an invalid response");
+        self.replace_peer(node_id);
+    }
 }
 
 /// Computes the distance between two nodes according to the discv4 protocol
@@ -303,9 +376,18 @@ pub fn bucket_number(node_id_1: H512, node_id_2: H512) -> usize {
     distance.bits().saturating_sub(1)
 }
 
+/// Discovery protocol through which a peer was found, each protocol only revalidates and queries
+/// the peers it has found, as the others may not speak it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryProtocol {
+    V4,
+    V5,
+}
+
 #[derive(Debug, Clone)]
 pub struct PeerData {
     pub node: Node,
+    pub protocol: DiscoveryProtocol,
     pub last_ping: u64,
     pub last_pong: u64,
     pub last_ping_hash: Option<H256>,
@@ -317,12 +399,23 @@ pub struct PeerData {
     pub revalidation: Option<bool>,
     /// communication channels between the peer data and its active connection
     pub channels: Option<PeerChannels>,
+    /// reputation of the peer based on its answers to our requests
+    pub score: i32,
+    /// average time taken by the peer to answer our requests
+    pub latency: Option<Duration>,
 }
 
 impl PeerData {
-    pub fn new(record: Node, last_ping: u64, last_pong: u64, is_proven: bool) -> Self {
+    pub fn new(
+        record: Node,
+        protocol: DiscoveryProtocol,
+        last_ping: u64,
+        last_pong: u64,
+        is_proven: bool,
+    ) -> Self {
         Self {
             node: record,
+            protocol,
             last_ping,
             last_pong,
             is_proven,
@@ -331,6 +424,8 @@ impl PeerData {
             find_node_request: None,
             revalidation: None,
             channels: None,
+            score: 0,
+            latency: None,
         }
     }
 
@@ -383,7 +478,7 @@ mod tests {
             udp_port: 0,
             node_id,
         };
-        table.insert_node_on_custom_bucket(node, bucket_idx)
+        table.insert_node_on_custom_bucket(node, DiscoveryProtocol::V4, bucket_idx)
     }
 
     fn fill_table_with_random_nodes(table: &mut KademliaTable) {
@@ -406,12 +501,15 @@ mod tests {
         let mut table = get_test_table();
         let node_1_id = node_id_from_signing_key(&SigningKey::random(&mut OsRng));
         {
-            table.insert_node(Node {
-                ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-                tcp_port: 0,
-                udp_port: 0,
-                node_id: node_1_id,
-            });
+            table.insert_node(
+                Node {
+                    ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+                    tcp_port: 0,
+                    udp_port: 0,
+                    node_id: node_1_id,
+                },
+                DiscoveryProtocol::V4,
+            );
             table.get_by_node_id_mut(node_1_id).unwrap().last_pong = (SystemTime::now()
                 - Duration::from_secs(12 * 60 * 60))
             .duration_since(UNIX_EPOCH)
@@ -421,12 +519,15 @@ mod tests {
 
         let node_2_id = node_id_from_signing_key(&SigningKey::random(&mut OsRng));
         {
-            table.insert_node(Node {
-                ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-                tcp_port: 0,
-                udp_port: 0,
-                node_id: node_2_id,
-            });
+            table.insert_node(
+                Node {
+                    ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+                    tcp_port: 0,
+                    udp_port: 0,
+                    node_id: node_2_id,
+                },
+                DiscoveryProtocol::V4,
+            );
             table.get_by_node_id_mut(node_2_id).unwrap().last_pong = (SystemTime::now()
                 - Duration::from_secs(36 * 60 * 60))
             .duration_since(UNIX_EPOCH)
@@ -436,12 +537,15 @@ mod tests {
 
         let node_3_id = node_id_from_signing_key(&SigningKey::random(&mut OsRng));
         {
-            table.insert_node(Node {
-                ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-                tcp_port: 0,
-                udp_port: 0,
-                node_id: node_3_id,
-            });
+            table.insert_node(
+                Node {
+                    ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+                    tcp_port: 0,
+                    udp_port: 0,
+                    node_id: node_3_id,
+                },
+                DiscoveryProtocol::V4,
+            );
             table.get_by_node_id_mut(node_3_id).unwrap().last_pong = (SystemTime::now()
                 - Duration::from_secs(10 * 60 * 60))
             .duration_since(UNIX_EPOCH)
@@ -451,7 +555,7 @@ mod tests {
 
         // we expect the node_1 & node_2 to be returned here
         let peers: Vec<H512> = table
-            .get_least_recently_pinged_peers(2)
+            .get_least_recently_pinged_peers(2, DiscoveryProtocol::V4)
             .iter()
             .map(|p| p.node.node_id)
             .collect();
@@ -461,6 +565,39 @@ mod tests {
         assert!(!peers.contains(&node_3_id));
     }
 
+    #[test]
+    fn peers_are_only_returned_to_the_protocol_that_found_them() {
+        let mut table = get_test_table();
+        let node = |node_id| Node {
+            ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            tcp_port: 0,
+            udp_port: 0,
+            node_id,
+        };
+        let v4_node_id = node_id_from_signing_key(&SigningKey::random(&mut OsRng));
+        let v5_node_id = node_id_from_signing_key(&SigningKey::random(&mut OsRng));
+        table.insert_node(node(v4_node_id), DiscoveryProtocol::V4);
+        table.insert_node(node(v5_node_id), DiscoveryProtocol::V5);
+
+        for (protocol, node_id) in [
+            (DiscoveryProtocol::V4, v4_node_id),
+            (DiscoveryProtocol::V5, v5_node_id),
+        ] {
+            let pinged: Vec<H512> = table
+                .get_least_recently_pinged_peers(3, protocol)
+                .iter()
+                .map(|p| p.node.node_id)
+                .collect();
+            assert_eq!(pinged, vec![node_id]);
+            let closest: Vec<H512> = table
+                .get_closest_nodes(table.local_node_id, protocol)
+                .iter()
+                .map(|n| n.node_id)
+                .collect();
+            assert_eq!(closest, vec![node_id]);
+        }
+    }
+
     #[test]
     fn insert_peer_should_remove_first_replacement_when_list_is_full() {
         let mut table = get_test_table();
@@ -539,4 +676,57 @@ mod tests {
         assert!(replacement.is_none());
         assert!(len_before - 1 == len_after);
     }
+
+    fn insert_connected_node(table: &mut KademliaTable) -> H512 {
+        let node_id = node_id_from_signing_key(&SigningKey::random(&mut OsRng));
+        table.insert_node(
+            Node {
+                ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+                tcp_port: 0,
+                udp_port: 0,
+                node_id,
+            },
+            DiscoveryProtocol::V4,
+        );
+        let (channels, _, _) = PeerChannels::create();
+        assert!(table.set_channels(node_id, channels));
+        node_id
+    }
+
+    #[test]
+    fn get_peers_channels_should_sort_peers_by_score_and_latency() {
+        let mut table = get_test_table();
+        let slow_peer = insert_connected_node(&mut table);
+        let fast_peer = insert_connected_node(&mut table);
+        let failing_peer = insert_connected_node(&mut table);
+        table.record_success(slow_peer, Duration::from_millis(500));
+        table.record_success(fast_peer, Duration::from_millis(100));
+        table.record_failure(failing_peer);
+
+        let peers: Vec<H512> = table
+            .get_peers_channels(usize::MAX)
+            .into_iter()
+            .map(|(node_id, _)| node_id)
+            .collect();
+        assert_eq!(peers, vec![fast_peer, slow_peer, failing_peer]);
+        assert_eq!(table.get_peers_channels(1).len(), 1);
+    }
+
+    #[test]
+    fn failing_and_invalid_peers_should_be_dropped() {
+        let mut table = get_test_table();
+        let failing_peer = insert_connected_node(&mut table);
+        let invalid_peer = insert_connected_node(&mut table);
+
+        for _ in 0..(-MIN_PEER_SCORE / FAILURE_PENALTY - 1) {
+            table.record_failure(failing_peer);
+        }
+        assert!(table.get_by_node_id(failing_peer).is_some());
+        table.record_failure(failing_peer);
+        assert!(table.get_by_node_id(failing_peer).is_none());
+
+        table.record_invalid_response(invalid_peer);
+        assert!(table.get_by_node_id(invalid_peer).is_none());
+        assert!(table.get_peers_channels(usize::MAX).is_empty());
+    }
 }
@@ -7,31 +7,38 @@ use std::{
 
 use bootnode::BootNode;
 use discv4::{
-    get_expiration, is_expired, time_now_unix, time_since_in_hs, FindNodeMessage, Message,
-    NeighborsMessage, Packet, PingMessage, PongMessage,
+    get_expiration, is_expired, time_now_unix, time_since_in_hs, ENRResponseMessage,
+    FindNodeMessage, Message, NeighborsMessage, Packet, PacketDecodeErr, PingMessage, PongMessage,
 };
-use ethrex_core::{H256, H512};
+use discv5::server::Discv5Server;
+use ethrex_core::{types::ForkId, H256, H512};
 use ethrex_storage::Store;
 use k256::{
     ecdsa::SigningKey,
     elliptic_curve::{sec1::ToEncodedPoint, PublicKey},
 };
 pub use kademlia::KademliaTable;
-use kademlia::{bucket_number, MAX_NODES_PER_BUCKET};
+use kademlia::{bucket_number, DiscoveryProtocol, MAX_NODES_PER_BUCKET};
+use peer_manager::PeerManager;
 use rand::rngs::OsRng;
-use rlpx::{connection::RLPxConnection, message::Message as RLPxMessage};
+use rlpx::{
+    connection::RLPxConnection, eth::backend::get_fork_id, message::Message as RLPxMessage,
+};
 use tokio::{
     net::{TcpSocket, TcpStream, UdpSocket},
-    sync::{broadcast, Mutex},
+    sync::{broadcast, mpsc, Mutex},
     try_join,
 };
-use tracing::{debug, error, info};
-use types::{Endpoint, Node};
+use tracing::{debug, info};
+use types::{Endpoint, Node, NodeRecord};
 
 pub mod bootnode;
 pub(crate) mod discv4;
+pub(crate) mod discv5;
+pub(crate) mod downloader;
 pub(crate) mod kademlia;
 pub mod peer_channels;
+pub mod peer_manager;
 pub mod rlpx;
 pub(crate) mod snap;
 pub mod sync;
@@ -45,18 +52,32 @@ const MAX_DISC_PACKET_SIZE: usize = 1280;
 // we should bump this limit.
 const MAX_MESSAGES_TO_BROADCAST: usize = 1000;
 
+// Interval at which the static peers that are not connected are dialed again
+const STATIC_PEERS_DIAL_INTERVAL_IN_SECONDS: u64 = 30;
+
+/// Discovery protocols run by the node, they share the UDP socket, the node record and the peer table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoveryProtocols {
+    pub v4: bool,
+    pub v5: bool,
+}
+
 pub fn peer_table(signer: SigningKey) -> Arc<Mutex<KademliaTable>> {
     let local_node_id = node_id_from_signing_key(&signer);
     Arc::new(Mutex::new(KademliaTable::new(local_node_id)))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn start_network(
+    local_node: Node,
     udp_addr: SocketAddr,
     tcp_addr: SocketAddr,
     bootnodes: Vec<BootNode>,
     signer: SigningKey,
     peer_table: Arc<Mutex<KademliaTable>>,
     storage: Store,
+    discovery_protocols: DiscoveryProtocols,
+    peers: PeerManager,
 ) {
     info!("Starting discovery service at {udp_addr}");
     info!("Listening for requests at {tcp_addr}");
@@ -65,11 +86,14 @@ pub async fn start_network(
         Arc<RLPxMessage>,
     )>(MAX_MESSAGES_TO_BROADCAST);
     let discovery_handle = tokio::spawn(discover_peers(
+        local_node,
         udp_addr,
         signer.clone(),
         storage.clone(),
         peer_table.clone(),
         bootnodes,
+        discovery_protocols,
+        peers.clone(),
         channel_broadcast_send_end.clone(),
     ));
     let server_handle = tokio::spawn(serve_requests(
@@ -77,28 +101,83 @@ pub async fn start_network(
         signer.clone(),
         storage.clone(),
         peer_table.clone(),
+        peers.clone(),
+        channel_broadcast_send_end.clone(),
+    ));
+    let static_peers_handle = tokio::spawn(dial_static_peers(
+        signer,
+        storage,
+        peer_table,
+        peers,
         channel_broadcast_send_end,
     ));
 
-    try_join!(discovery_handle, server_handle).unwrap();
+    try_join!(discovery_handle, server_handle, static_peers_handle).unwrap();
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn discover_peers(
+    local_node: Node,
     udp_addr: SocketAddr,
     signer: SigningKey,
     storage: Store,
     table: Arc<Mutex<KademliaTable>>,
     bootnodes: Vec<BootNode>,
+    discovery_protocols: DiscoveryProtocols,
+    peers: PeerManager,
     connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
 ) {
     let udp_socket = Arc::new(UdpSocket::bind(udp_addr).await.unwrap());
+    let local_record = Arc::new(Mutex::new(LocalNodeRecord::new(
+        local_node,
+        signer.clone(),
+        storage.clone(),
+    )));
+    info!(
+        "Node record: {}",
+        local_record.lock().await.record.enr_url()
+    );
+
+    let (discv5_packets_send, discv5_packets_receive) = mpsc::unbounded_channel();
+    let discv5_handler = discovery_protocols.v5.then(|| {
+        let server = Discv5Server::new(
+            udp_socket.clone(),
+            signer.clone(),
+            local_record.clone(),
+            table.clone(),
+            storage.clone(),
+            peers.clone(),
+            connection_broadcast.clone(),
+        );
+        tokio::spawn(server.run(
+            discv5_packets_receive,
+            bootnodes.clone(),
+            REVALIDATION_INTERVAL_IN_SECONDS as u64,
+        ))
+    });
+    if !discovery_protocols.v4 {
+        let Some(discv5_handler) = discv5_handler else {
+            info!("No discovery protocol enabled, peers won't be discovered");
+            return;
+        };
+        let receiver_handler = tokio::spawn(discv5::server::receive_packets(
+            udp_socket,
+            discv5_packets_send,
+        ));
+        try_join!(discv5_handler, receiver_handler).unwrap();
+        return;
+    }
 
+    // discv4 packets are received first, the ones that it can't decode are forwarded to discv5
     let server_handler = tokio::spawn(discover_peers_server(
         udp_addr,
         udp_socket.clone(),
         storage,
         table.clone(),
         signer.clone(),
+        local_record.clone(),
+        discovery_protocols.v5.then_some(discv5_packets_send),
+        peers,
         connection_broadcast,
     ));
     let revalidation_handler = tokio::spawn(peers_revalidation(
@@ -106,6 +185,7 @@ async fn discover_peers(
         udp_socket.clone(),
         table.clone(),
         signer.clone(),
+        local_record.clone(),
         REVALIDATION_INTERVAL_IN_SECONDS as u64,
     ));
 
@@ -114,6 +194,7 @@ async fn discover_peers(
         udp_socket.clone(),
         table.clone(),
         signer.clone(),
+        local_record.lock().await.seq(),
         bootnodes,
     )
     .await;
@@ -132,12 +213,16 @@ async fn discover_peers(
     try_join!(server_handler, revalidation_handler, lookup_handler).unwrap();
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn discover_peers_server(
     udp_addr: SocketAddr,
     udp_socket: Arc<UdpSocket>,
     storage: Store,
     table: Arc<Mutex<KademliaTable>>,
     signer: SigningKey,
+    local_record: Arc<Mutex<LocalNodeRecord>>,
+    discv5_packets: Option<mpsc::UnboundedSender<(Vec<u8>, SocketAddr)>>,
+    peers: PeerManager,
     tx_broadcaster_send: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
 ) {
     let mut buf = vec![0; MAX_DISC_PACKET_SIZE];
@@ -145,8 +230,18 @@ async fn discover_peers_server(
     loop {
         let (read, from) = udp_socket.recv_from(&mut buf).await.unwrap();
         debug!("Received {read} bytes from {from}");
+        let enr_seq = local_record.lock().await.seq();
 
         let packet = Packet::decode(&buf[..read]);
+        if let (
+            Err(PacketDecodeErr::InvalidSize | PacketDecodeErr::HashMismatch),
+            Some(discv5_packets),
+        ) = (&packet, &discv5_packets)
+        {
+            // not a discv4 packet, it may be a discv5 one
+            let _ = discv5_packets.send((buf[..read].to_vec(), from));
+            continue;
+        }
         if packet.is_err() {
             debug!("Could not decode packet: {:?}", packet.err().unwrap());
             continue;
@@ -163,7 +258,7 @@ async fn discover_peers_server(
                     continue;
                 };
                 let ping_hash = packet.get_hash();
-                pong(&udp_socket, from, ping_hash, &signer).await;
+                pong(&udp_socket, from, ping_hash, &signer, enr_seq).await;
                 let node = {
                     let table = table.lock().await;
                     table.get_by_node_id(packet.get_node_id()).cloned()
@@ -171,7 +266,7 @@ async fn discover_peers_server(
                 if let Some(peer) = node {
                     // send a a ping to get an endpoint proof
                     if time_since_in_hs(peer.last_ping) >= PROOF_EXPIRATION_IN_HS as u64 {
-                        let hash = ping(&udp_socket, udp_addr, from, &signer).await;
+                        let hash = ping(&udp_socket, udp_addr, from, &signer, enr_seq).await;
                         if let Some(hash) = hash {
                             table
                                 .lock()
@@ -183,14 +278,17 @@ async fn discover_peers_server(
                     // send a ping to get the endpoint proof from our end
                     let (peer, inserted_to_table) = {
                         let mut table = table.lock().await;
-                        table.insert_node(Node {
-                            ip: from.ip(),
-                            udp_port: from.port(),
-                            tcp_port: 0,
-                            node_id: packet.get_node_id(),
-                        })
+                        table.insert_node(
+                            Node {
+                                ip: from.ip(),
+                                udp_port: from.port(),
+                                tcp_port: 0,
+                                node_id: packet.get_node_id(),
+                            },
+                            DiscoveryProtocol::V4,
+                        )
                     };
-                    let hash = ping(&udp_socket, udp_addr, from, &signer).await;
+                    let hash = ping(&udp_socket, udp_addr, from, &signer, enr_seq).await;
                     if let Some(hash) = hash {
                         if inserted_to_table && peer.is_some() {
                             let peer = peer.unwrap();
@@ -220,18 +318,17 @@ async fn discover_peers_server(
                     if peer.last_ping_hash.unwrap() == msg.ping_hash {
                         table.lock().await.pong_answered(peer.node.node_id);
 
-                        let mut msg_buf = vec![0; read - 32];
-                        buf[32..read].clone_into(&mut msg_buf);
                         let signer = signer.clone();
                         let storage = storage.clone();
+                        let peers = peers.clone();
                         let broadcaster = tx_broadcaster_send.clone();
                         tokio::spawn(async move {
                             handle_peer_as_initiator(
                                 signer,
-                                &msg_buf,
                                 &peer.node,
                                 storage,
                                 table,
+                                peers,
                                 broadcaster,
                             )
                             .await;
@@ -258,7 +355,7 @@ async fn discover_peers_server(
                     if node.is_proven {
                         let nodes = {
                             let table = table.lock().await;
-                            table.get_closest_nodes(msg.target)
+                            table.get_closest_nodes(msg.target, DiscoveryProtocol::V4)
                         };
                         let nodes_chunks = nodes.chunks(4);
                         let expiration = get_expiration(20);
@@ -321,16 +418,48 @@ async fn discover_peers_server(
 
                 if let Some(nodes) = nodes_to_insert {
                     for node in nodes {
-                        let (peer, inserted_to_table) = table.insert_node(node);
+                        let (peer, inserted_to_table) =
+                            table.insert_node(node, DiscoveryProtocol::V4);
                         if inserted_to_table && peer.is_some() {
                             let peer = peer.unwrap();
                             let node_addr = SocketAddr::new(peer.node.ip, peer.node.udp_port);
-                            let ping_hash = ping(&udp_socket, udp_addr, node_addr, &signer).await;
+                            let ping_hash =
+                                ping(&udp_socket, udp_addr, node_addr, &signer, enr_seq).await;
                             table.update_peer_ping(peer.node.node_id, ping_hash);
                         };
                     }
                 }
             }
+            Message::ENRRequest(msg) => {
+                if is_expired(msg.expiration) {
+                    debug!("Ignoring enr request msg as it is expired.");
+                    continue;
+                };
+                let node = {
+                    let table = table.lock().await;
+                    table.get_by_node_id(packet.get_node_id()).cloned()
+                };
+                if let Some(node) = node {
+                    if node.is_proven {
+                        let node_record = {
+                            let mut local_record = local_record.lock().await;
+                            local_record.update();
+                            local_record.record.clone()
+                        };
+                        let response = Message::ENRResponse(ENRResponseMessage {
+                            request_hash: packet.get_hash(),
+                            node_record,
+                        });
+                        let mut buf = Vec::new();
+                        response.encode_with_header(&mut buf, &signer);
+                        let _ = udp_socket.send_to(&buf, from).await;
+                    } else {
+                        debug!("Ignoring enr request message as the node isn't proven!");
+                    }
+                } else {
+                    debug!("Ignoring enr request message as it is not a known node");
+                }
+            }
             _ => {}
         }
     }
@@ -345,18 +474,27 @@ async fn discovery_startup(
     udp_socket: Arc<UdpSocket>,
     table: Arc<Mutex<KademliaTable>>,
     signer: SigningKey,
+    enr_seq: u64,
     bootnodes: Vec<BootNode>,
 ) {
     for bootnode in bootnodes {
-        table.lock().await.insert_node(Node {
-            ip: bootnode.socket_address.ip(),
-            udp_port: bootnode.socket_address.port(),
-            // TODO: udp port can differ from tcp port.
-            // see https://github.com/lambdaclass/ethrex/issues/905
-            tcp_port: bootnode.socket_address.port(),
-            node_id: bootnode.node_id,
-        });
-        let ping_hash = ping(&udp_socket, udp_addr, bootnode.socket_address, &signer).await;
+        table.lock().await.insert_node(
+            Node {
+                ip: bootnode.socket_address.ip(),
+                udp_port: bootnode.socket_address.port(),
+                tcp_port: bootnode.tcp_port,
+                node_id: bootnode.node_id,
+            },
+            DiscoveryProtocol::V4,
+        );
+        let ping_hash = ping(
+            &udp_socket,
+            udp_addr,
+            bootnode.socket_address,
+            &signer,
+            enr_seq,
+        )
+        .await;
         table
             .lock()
             .await
@@ -369,6 +507,7 @@ const PROOF_EXPIRATION_IN_HS: usize = 12;
 
 /// Starts a tokio scheduler that:
 /// - performs periodic revalidation of the current nodes (sends a ping to the old nodes). Currently this is configured to happen every [`REVALIDATION_INTERVAL_IN_MINUTES`]
+/// - keeps the local node record up to date, so that the pings advertise its latest sequence number
 ///
 /// **Peer revalidation**
 ///
@@ -385,6 +524,7 @@ async fn peers_revalidation(
     udp_socket: Arc<UdpSocket>,
     table: Arc<Mutex<KademliaTable>>,
     signer: SigningKey,
+    local_record: Arc<Mutex<LocalNodeRecord>>,
     interval_time_in_seconds: u64,
 ) {
     let mut interval = tokio::time::interval(Duration::from_secs(interval_time_in_seconds));
@@ -397,6 +537,11 @@ async fn peers_revalidation(
     loop {
         interval.tick().await;
         debug!("Running peer revalidation");
+        let enr_seq = {
+            let mut local_record = local_record.lock().await;
+            local_record.update();
+            local_record.seq()
+        };
 
         // first check that the peers we ping have responded
         for node_id in previously_pinged_peers {
@@ -415,12 +560,16 @@ async fn peers_revalidation(
 
             if peer.liveness == 0 {
                 let new_peer = table.replace_peer(node_id);
-                if let Some(new_peer) = new_peer {
+                // replacements found by discv5 are revalidated by it
+                if let Some(new_peer) =
+                    new_peer.filter(|peer| peer.protocol == DiscoveryProtocol::V4)
+                {
                     let ping_hash = ping(
                         &udp_socket,
                         udp_addr,
                         SocketAddr::new(new_peer.node.ip, new_peer.node.udp_port),
                         &signer,
+                        enr_seq,
                     )
                     .await;
                     table.update_peer_ping(new_peer.node.node_id, ping_hash);
@@ -431,7 +580,10 @@ async fn peers_revalidation(
         // now send a ping to the least recently pinged peers
         // this might be too expensive to run if our table is filled
         // maybe we could just pick them randomly
-        let peers = table.lock().await.get_least_recently_pinged_peers(3);
+        let peers = table
+            .lock()
+            .await
+            .get_least_recently_pinged_peers(3, DiscoveryProtocol::V4);
         previously_pinged_peers = HashSet::default();
         for peer in peers {
             let ping_hash = ping(
@@ -439,6 +591,7 @@ async fn peers_revalidation(
                 udp_addr,
                 SocketAddr::new(peer.node.ip, peer.node.udp_port),
                 &signer,
+                enr_seq,
             )
             .await;
             let mut table = table.lock().await;
@@ -530,7 +683,10 @@ async fn recursive_lookup(
 ) {
     let mut asked_peers = HashSet::default();
     // lookups start with the closest from our table
-    let closest_nodes = table.lock().await.get_closest_nodes(target);
+    let closest_nodes = table
+        .lock()
+        .await
+        .get_closest_nodes(target, DiscoveryProtocol::V4);
     let mut seen_peers: HashSet<H512> = HashSet::default();
 
     seen_peers.insert(local_node_id);
@@ -657,6 +813,7 @@ async fn ping(
     local_addr: SocketAddr,
     to_addr: SocketAddr,
     signer: &SigningKey,
+    enr_seq: u64,
 ) -> Option<H256> {
     let mut buf = Vec::new();
 
@@ -677,7 +834,8 @@ async fn ping(
         tcp_port: 0,
     };
 
-    let ping: discv4::Message = discv4::Message::Ping(PingMessage::new(from, to, expiration));
+    let ping: discv4::Message =
+        discv4::Message::Ping(PingMessage::new(from, to, expiration).with_enr_seq(enr_seq));
     ping.encode_with_header(&mut buf, signer);
     let res = socket.send_to(&buf, to_addr).await;
 
@@ -740,7 +898,13 @@ async fn find_node_and_wait_for_response(
     }
 }
 
-async fn pong(socket: &UdpSocket, to_addr: SocketAddr, ping_hash: H256, signer: &SigningKey) {
+async fn pong(
+    socket: &UdpSocket,
+    to_addr: SocketAddr,
+    ping_hash: H256,
+    signer: &SigningKey,
+    enr_seq: u64,
+) {
     let mut buf = Vec::new();
 
     let expiration: u64 = (SystemTime::now() + Duration::from_secs(20))
@@ -753,7 +917,8 @@ async fn pong(socket: &UdpSocket, to_addr: SocketAddr, ping_hash: H256, signer:
         udp_port: to_addr.port(),
         tcp_port: 0,
     };
-    let pong: discv4::Message = discv4::Message::Pong(PongMessage::new(to, ping_hash, expiration));
+    let pong: discv4::Message =
+        discv4::Message::Pong(PongMessage::new(to, ping_hash, expiration).with_enr_seq(enr_seq));
 
     pong.encode_with_header(&mut buf, signer);
     let _ = socket.send_to(&buf, to_addr).await;
@@ -764,19 +929,22 @@ async fn serve_requests(
     signer: SigningKey,
     storage: Store,
     table: Arc<Mutex<KademliaTable>>,
+    peers: PeerManager,
     connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
 ) {
     let tcp_socket = TcpSocket::new_v4().unwrap();
     tcp_socket.bind(tcp_addr).unwrap();
     let listener = tcp_socket.listen(50).unwrap();
     loop {
-        let (stream, _peer_addr) = listener.accept().await.unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
 
         tokio::spawn(handle_peer_as_receiver(
             signer.clone(),
             stream,
+            peer_addr,
             storage.clone(),
             table.clone(),
+            peers.clone(),
             connection_broadcast.clone(),
         ));
     }
@@ -785,36 +953,122 @@ async fn serve_requests(
 async fn handle_peer_as_receiver(
     signer: SigningKey,
     stream: TcpStream,
+    peer_addr: SocketAddr,
     storage: Store,
     table: Arc<Mutex<KademliaTable>>,
+    peers: PeerManager,
     connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
 ) {
     let mut conn = RLPxConnection::receiver(signer, stream, storage, connection_broadcast);
-    conn.start_peer(table).await;
+    conn.start_peer(peer_addr, None, table, peers).await;
 }
 
 async fn handle_peer_as_initiator(
     signer: SigningKey,
-    msg: &[u8],
     node: &Node,
     storage: Store,
     table: Arc<Mutex<KademliaTable>>,
+    peers: PeerManager,
     connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
 ) {
+    if !peers.start_dial(node.node_id) {
+        debug!("Not dialing {node:?}, it is connected, banned, already being dialed or there are no outbound slots left");
+        return;
+    }
     debug!("Trying RLPx connection with {node:?}");
-    let stream = TcpSocket::new_v4()
-        .unwrap()
-        .connect(SocketAddr::new(node.ip, node.tcp_port))
-        .await
-        .unwrap();
-    match RLPxConnection::initiator(signer, msg, stream, storage, connection_broadcast).await {
-        Ok(mut conn) => conn.start_peer(table).await,
-        Err(e) => {
-            error!("Error: {e}, Could not start connection with {node:?}");
+    let addr = SocketAddr::new(node.ip, node.tcp_port);
+    match TcpSocket::new_v4().unwrap().connect(addr).await {
+        Ok(stream) => {
+            let mut conn = RLPxConnection::initiator(
+                signer,
+                node.node_id,
+                stream,
+                storage,
+                connection_broadcast,
+            );
+            conn.start_peer(addr, Some(*node), table, peers.clone())
+                .await;
+        }
+        Err(e) => debug!("Could not connect to {addr}: ({e})"),
+    };
+    peers.finish_dial(node.node_id);
+}
+
+/// Keeps the static peers connected, dialing the ones that are not every `STATIC_PEERS_DIAL_INTERVAL_IN_SECONDS`,
+/// or right away when a new static peer is added
+async fn dial_static_peers(
+    signer: SigningKey,
+    storage: Store,
+    table: Arc<Mutex<KademliaTable>>,
+    peers: PeerManager,
+    connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
+) {
+    loop {
+        for node in peers.static_peers_to_dial() {
+            let signer = signer.clone();
+            let storage = storage.clone();
+            let table = table.clone();
+            let peers = peers.clone();
+            let broadcaster = connection_broadcast.clone();
+            tokio::spawn(async move {
+                handle_peer_as_initiator(signer, &node, storage, table, peers, broadcaster).await;
+            });
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(STATIC_PEERS_DIAL_INTERVAL_IN_SECONDS)) => {}
+            _ = peers.dial_requested() => {}
         }
     }
 }
 
+/// The record describing the local node, served to the discovery peers that request it
+struct LocalNodeRecord {
+    node: Node,
+    signer: SigningKey,
+    storage: Store,
+    fork_id: Option<ForkId>,
+    record: NodeRecord,
+}
+
+impl LocalNodeRecord {
+    fn new(node: Node, signer: SigningKey, storage: Store) -> Self {
+        // Starting from the current time keeps the sequence number increasing across restarts
+        let seq = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let fork_id = get_fork_id(&storage).ok();
+        let record = NodeRecord::new(&node, seq, &signer, fork_id.as_ref());
+        Self {
+            node,
+            signer,
+            storage,
+            fork_id,
+            record,
+        }
+    }
+
+    fn seq(&self) -> u64 {
+        self.record.seq
+    }
+
+    /// Signs a new record with a bumped sequence number if the fork id of the local chain changed
+    fn update(&mut self) {
+        let Ok(fork_id) = get_fork_id(&self.storage) else {
+            return;
+        };
+        if self.fork_id.as_ref() == Some(&fork_id) {
+            return;
+        }
+        self.record = NodeRecord::new(&self.node, self.seq() + 1, &self.signer, Some(&fork_id));
+        self.fork_id = Some(fork_id);
+        debug!(
+            "Updated local node record to sequence number {}",
+            self.seq()
+        );
+    }
+}
+
 pub fn node_id_from_signing_key(signer: &SigningKey) -> H512 {
     let public_key = PublicKey::from(signer.verifying_key());
     let encoded = public_key.to_encoded_point(false);
@@ -824,11 +1078,14 @@ pub fn node_id_from_signing_key(signer: &SigningKey) -> H512 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ethrex_core::types::Genesis;
     use ethrex_storage::EngineType;
     use kademlia::bucket_number;
     use rand::rngs::OsRng;
     use std::{
         collections::HashSet,
+        fs::File,
+        io::BufReader,
         net::{IpAddr, Ipv4Addr},
     };
     use tokio::time::sleep;
@@ -847,7 +1104,7 @@ mod tests {
         table
             .lock()
             .await
-            .insert_node_on_custom_bucket(node, bucket_idx);
+            .insert_node_on_custom_bucket(node, DiscoveryProtocol::V4, bucket_idx);
     }
 
     async fn fill_table_with_random_nodes(table: Arc<Mutex<KademliaTable>>) {
@@ -864,6 +1121,7 @@ mod tests {
         pub table: Arc<Mutex<KademliaTable>>,
         pub node_id: H512,
         pub udp_socket: Arc<UdpSocket>,
+        pub local_record: Arc<Mutex<LocalNodeRecord>>,
     }
 
     async fn start_mock_discovery_server(udp_port: u16, should_start_server: bool) -> MockServer {
@@ -873,7 +1131,25 @@ mod tests {
         let node_id = node_id_from_signing_key(&signer);
         let storage =
             Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let file = File::open("../../../test_data/genesis-execution-api.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to add genesis block to DB");
         let table = Arc::new(Mutex::new(KademliaTable::new(node_id)));
+        let local_node = Node {
+            ip: addr.ip(),
+            udp_port,
+            tcp_port: 0,
+            node_id,
+        };
+        let local_record = Arc::new(Mutex::new(LocalNodeRecord::new(
+            local_node,
+            signer.clone(),
+            storage.clone(),
+        )));
         let (channel_broadcast_send_end, _) = tokio::sync::broadcast::channel::<(
             tokio::task::Id,
             Arc<RLPxMessage>,
@@ -885,6 +1161,9 @@ mod tests {
                 storage.clone(),
                 table.clone(),
                 signer.clone(),
+                local_record.clone(),
+                None,
+                PeerManager::default(),
                 channel_broadcast_send_end,
             ));
         }
@@ -895,6 +1174,7 @@ mod tests {
             table,
             node_id,
             udp_socket,
+            local_record,
         }
     }
 
@@ -905,16 +1185,20 @@ mod tests {
             server_a.addr,
             server_b.addr,
             &server_a.signer,
+            server_a.local_record.lock().await.seq(),
         )
         .await;
         {
             let mut table = server_a.table.lock().await;
-            table.insert_node(Node {
-                ip: server_b.addr.ip(),
-                udp_port: server_b.addr.port(),
-                tcp_port: 0,
-                node_id: server_b.node_id,
-            });
+            table.insert_node(
+                Node {
+                    ip: server_b.addr.ip(),
+                    udp_port: server_b.addr.port(),
+                    tcp_port: 0,
+                    node_id: server_b.node_id,
+                },
+                DiscoveryProtocol::V4,
+            );
             table.update_peer_ping(server_b.node_id, ping_hash);
         }
         // allow some time for the server to respond
@@ -943,6 +1227,7 @@ mod tests {
             server_b.udp_socket.clone(),
             server_b.table.clone(),
             server_b.signer.clone(),
+            server_b.local_record.clone(),
             2,
         ));
 
@@ -1018,12 +1303,12 @@ mod tests {
             .table
             .lock()
             .await
-            .get_closest_nodes(server_b.node_id);
+            .get_closest_nodes(server_b.node_id, DiscoveryProtocol::V4);
         let nodes_to_ask = server_b
             .table
             .lock()
             .await
-            .get_closest_nodes(server_b.node_id);
+            .get_closest_nodes(server_b.node_id, DiscoveryProtocol::V4);
 
         lookup(
             server_b.udp_socket.clone(),
@@ -1075,21 +1360,21 @@ mod tests {
                 .table
                 .lock()
                 .await
-                .get_closest_nodes(server_a.node_id),
+                .get_closest_nodes(server_a.node_id, DiscoveryProtocol::V4),
         );
         expected_peers.extend(
             server_c
                 .table
                 .lock()
                 .await
-                .get_closest_nodes(server_a.node_id),
+                .get_closest_nodes(server_a.node_id, DiscoveryProtocol::V4),
         );
         expected_peers.extend(
             server_d
                 .table
                 .lock()
                 .await
-                .get_closest_nodes(server_a.node_id),
+                .get_closest_nodes(server_a.node_id, DiscoveryProtocol::V4),
         );
 
         // we'll run a recursive lookup closest to the server itself
@@ -1111,4 +1396,74 @@ mod tests {
                 .is_some());
         }
     }
+
+    #[tokio::test]
+    /** This test checks that the discovery server serves its node record:
+     * - We'll start a discovery server `b` and a bare socket `a` that is already proven by `b`
+     * - `a` sends an enr request to `b`
+     * - We expect `b` to answer with a record signed by its key, that references the request hash
+     */
+    async fn discovery_server_enr_request() {
+        let server_a = start_mock_discovery_server(8006, false).await;
+        let server_b = start_mock_discovery_server(8007, true).await;
+        let fork_id = get_fork_id(&server_b.local_record.lock().await.storage).unwrap();
+
+        {
+            let mut table = server_b.table.lock().await;
+            table.insert_node(
+                Node {
+                    ip: server_a.addr.ip(),
+                    udp_port: server_a.addr.port(),
+                    tcp_port: 0,
+                    node_id: server_a.node_id,
+                },
+                DiscoveryProtocol::V4,
+            );
+            table.pong_answered(server_a.node_id);
+        }
+
+        let msg = Message::ENRRequest(discv4::ENRRequestMessage {
+            expiration: get_expiration(20),
+        });
+        let mut buf = Vec::new();
+        msg.encode_with_header(&mut buf, &server_a.signer);
+        let request_hash = H256::from_slice(&buf[0..32]);
+        server_a
+            .udp_socket
+            .send_to(&buf, server_b.addr)
+            .await
+            .unwrap();
+
+        let mut buf = vec![0; MAX_DISC_PACKET_SIZE];
+        let (read, _from) = tokio::time::timeout(
+            Duration::from_secs(2),
+            server_a.udp_socket.recv_from(&mut buf),
+        )
+        .await
+        .expect("enr request was not answered")
+        .unwrap();
+        let packet = Packet::decode(&buf[..read]).unwrap();
+        let Message::ENRResponse(response) = packet.get_message() else {
+            panic!("Expected an enr response, got {:?}", packet.get_message());
+        };
+        assert_eq!(response.request_hash, request_hash);
+        assert!(response.node_record.verify_signature());
+        assert_eq!(
+            response.node_record.get::<Vec<ForkId>>("eth"),
+            Some(vec![fork_id])
+        );
+        assert_eq!(
+            response.node_record.seq,
+            server_b.local_record.lock().await.seq()
+        );
+        assert_eq!(
+            response.node_record.node(),
+            Some(Node {
+                ip: server_b.addr.ip(),
+                udp_port: server_b.addr.port(),
+                tcp_port: 0,
+                node_id: server_b.node_id,
+            })
+        );
+    }
 }
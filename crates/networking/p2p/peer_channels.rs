@@ -2,20 +2,25 @@ use std::{sync::Arc, time::Duration};
 
 use bytes::Bytes;
 use ethrex_core::{
-    types::{AccountState, BlockBody, BlockHeader},
+    types::{AccountState, BlockBody, BlockHeader, BlockNumber, Receipt},
     H256, U256,
 };
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_trie::verify_range;
+use ethrex_trie::{verify_range, Nibbles};
 use tokio::sync::{mpsc, Mutex};
 
 use crate::{
     rlpx::{
-        eth::blocks::{
-            BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders, BLOCK_HEADER_LIMIT,
+        eth::{
+            blocks::{
+                BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders, HashOrNumber,
+                BLOCK_HEADER_LIMIT,
+            },
+            receipts::{GetReceipts, Receipts},
         },
         snap::{
-            AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, StorageRanges,
+            AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
+            StorageRanges, TrieNodes,
         },
     },
     snap::encodable_to_proof,
@@ -58,17 +63,56 @@ impl PeerChannels {
     /// - The response timed out
     /// - The response was empty or not valid
     pub async fn request_block_headers(&self, start: H256) -> Option<Vec<BlockHeader>> {
+        let block_headers = self
+            .request_headers(start.into(), BLOCK_HEADER_LIMIT, 0, false)
+            .await?;
+        (!block_headers.is_empty()).then_some(block_headers)
+    }
+
+    /// Requests block headers from the peer, starting from the `start` block number towards newer blocks
+    /// Returns the block headers (which will be empty if the peer doesn't have the starting block) or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    pub async fn request_block_headers_from_number(
+        &self,
+        start: BlockNumber,
+    ) -> Option<Vec<BlockHeader>> {
+        self.request_headers(start.into(), BLOCK_HEADER_LIMIT, 0, false)
+            .await
+    }
+
+    /// Requests (at most `limit`) block headers from the peer, starting from the `start` block hash towards older blocks
+    /// and skipping `skip` blocks between each of them
+    /// Returns the block headers (which will be empty if the peer doesn't have the starting block) or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    pub async fn request_block_headers_reverse(
+        &self,
+        start: H256,
+        limit: u64,
+        skip: u64,
+    ) -> Option<Vec<BlockHeader>> {
+        self.request_headers(start.into(), limit, skip, true).await
+    }
+
+    async fn request_headers(
+        &self,
+        startblock: HashOrNumber,
+        limit: u64,
+        skip: u64,
+        reverse: bool,
+    ) -> Option<Vec<BlockHeader>> {
         let request_id = rand::random();
         let request = RLPxMessage::GetBlockHeaders(GetBlockHeaders {
             id: request_id,
-            startblock: start.into(),
-            limit: BLOCK_HEADER_LIMIT,
-            skip: 0,
-            reverse: false,
+            startblock,
+            limit,
+            skip,
+            reverse,
         });
         self.sender.send(request).await.ok()?;
         let mut receiver = self.receiver.lock().await;
-        let block_headers = tokio::time::timeout(PEER_REPLY_TIMOUT, async move {
+        tokio::time::timeout(PEER_REPLY_TIMOUT, async move {
             loop {
                 match receiver.recv().await {
                     Some(RLPxMessage::BlockHeaders(BlockHeaders { id, block_headers }))
@@ -83,8 +127,7 @@ impl PeerChannels {
             }
         })
         .await
-        .ok()??;
-        (!block_headers.is_empty()).then_some(block_headers)
+        .ok()?
     }
 
     /// Requests block bodies from the peer given their block hashes
@@ -121,6 +164,38 @@ impl PeerChannels {
         (!block_bodies.is_empty() && block_bodies.len() <= block_hashes_len).then_some(block_bodies)
     }
 
+    /// Requests the receipts of the given blocks from the peer
+    /// Returns the receipts of each block or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_receipts(&self, block_hashes: Vec<H256>) -> Option<Vec<Vec<Receipt>>> {
+        let block_hashes_len = block_hashes.len();
+        let request_id = rand::random();
+        let request = RLPxMessage::GetReceipts(GetReceipts {
+            id: request_id,
+            block_hashes,
+        });
+        self.sender.send(request).await.ok()?;
+        let mut receiver = self.receiver.lock().await;
+        let receipts = tokio::time::timeout(PEER_REPLY_TIMOUT, async move {
+            loop {
+                match receiver.recv().await {
+                    Some(RLPxMessage::Receipts(Receipts { id, receipts })) if id == request_id => {
+                        return Some(receipts)
+                    }
+                    // Ignore replies that don't match the expected id (such as late responses)
+                    Some(_) => continue,
+                    None => return None,
+                }
+            }
+        })
+        .await
+        .ok()??;
+        // Check that the response is not empty and does not contain more receipts than the ones requested
+        (!receipts.is_empty() && receipts.len() <= block_hashes_len).then_some(receipts)
+    }
+
     /// Requests an account range from the peer given the state trie's root and the starting hash (the limit hash will be the maximum value of H256)
     /// Will also return a boolean indicating if there is more state to be fetched towards the right of the trie
     /// Returns the response message or None if:
@@ -318,4 +393,78 @@ impl PeerChannels {
         }
         Some((storage_keys, storage_values, should_continue))
     }
+
+    /// Requests state trie nodes given the root of the trie where they are contained and their paths (as nibbles)
+    /// Returns the encoded nodes (in the same order as the paths) or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_state_trienodes(
+        &self,
+        state_root: H256,
+        paths: Vec<Nibbles>,
+    ) -> Option<Vec<Bytes>> {
+        let expected_nodes = paths.len();
+        // Each state node is requested in its own path set
+        let paths = paths
+            .iter()
+            .map(|path| vec![Bytes::from(path.encode_compact())])
+            .collect();
+        self.request_trie_nodes(state_root, paths, expected_nodes)
+            .await
+    }
+
+    /// Requests storage trie nodes given the root of the state trie where they are contained,
+    /// the hashed address of the account the storage belongs to, and the nodes' paths (as nibbles)
+    /// Returns the encoded nodes (in the same order as the paths) or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_storage_trienodes(
+        &self,
+        state_root: H256,
+        account_hash: H256,
+        paths: Vec<Nibbles>,
+    ) -> Option<Vec<Bytes>> {
+        let expected_nodes = paths.len();
+        // All storage nodes belonging to the same account are requested in a single path set, led by the account's path
+        let path_set = [Bytes::copy_from_slice(account_hash.as_bytes())]
+            .into_iter()
+            .chain(paths.iter().map(|path| Bytes::from(path.encode_compact())))
+            .collect();
+        self.request_trie_nodes(state_root, vec![path_set], expected_nodes)
+            .await
+    }
+
+    async fn request_trie_nodes(
+        &self,
+        state_root: H256,
+        paths: Vec<Vec<Bytes>>,
+        expected_nodes: usize,
+    ) -> Option<Vec<Bytes>> {
+        let request_id = rand::random();
+        let request = RLPxMessage::GetTrieNodes(GetTrieNodes {
+            id: request_id,
+            root_hash: state_root,
+            paths,
+            bytes: MAX_RESPONSE_BYTES,
+        });
+        self.sender.send(request).await.ok()?;
+        let mut receiver = self.receiver.lock().await;
+        let nodes = tokio::time::timeout(PEER_REPLY_TIMOUT, async move {
+            loop {
+                match receiver.recv().await {
+                    Some(RLPxMessage::TrieNodes(TrieNodes { id, nodes })) if id == request_id => {
+                        return Some(nodes)
+                    }
+                    // Ignore replies that don't match the expected id (such as late responses)
+                    Some(_) => continue,
+                    None => return None,
+                }
+            }
+        })
+        .await
+        .ok()??;
+        (!nodes.is_empty() && nodes.len() <= expected_nodes).then_some(nodes)
+    }
 }
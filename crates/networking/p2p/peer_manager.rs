@@ -0,0 +1,432 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use ethrex_core::H512;
+use tokio::sync::Notify;
+
+use crate::{rlpx::error::RLPxError, types::Node};
+
+/// Default maximum amount of connected peers
+pub const DEFAULT_MAX_PEERS: usize = 50;
+/// Default ratio between the maximum amount of peers and the ones dialed by the node, same as geth's
+pub const DEFAULT_DIAL_RATIO: usize = 3;
+/// Time a misbehaving peer is not allowed to connect for
+pub const BAN_DURATION: Duration = Duration::from_secs(30 * 60);
+/// Maximum amount of static peers, as they are exempt from the peer limits
+pub const MAX_STATIC_PEERS: usize = 100;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("Reached the limit of {MAX_STATIC_PEERS} static peers")]
+pub struct StaticPeersLimitReached;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerLimits {
+    pub max_peers: usize,
+    /// One out of this many peers is dialed by the node, the rest of the slots are left for inbound connections
+    pub dial_ratio: usize,
+}
+
+impl Default for PeerLimits {
+    fn default() -> Self {
+        Self {
+            max_peers: DEFAULT_MAX_PEERS,
+            dial_ratio: DEFAULT_DIAL_RATIO,
+        }
+    }
+}
+
+impl PeerLimits {
+    fn max_outbound(&self) -> usize {
+        self.max_peers / self.dial_ratio.max(1)
+    }
+
+    fn max_inbound(&self) -> usize {
+        self.max_peers - self.max_outbound()
+    }
+}
+
+/// Information about a connected peer, as returned by the admin_peers RPC method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerInfo {
+    /// The peer's node, with its listen ports if they are known
+    pub node: Node,
+    pub remote_addr: SocketAddr,
+    pub inbound: bool,
+    pub is_static: bool,
+    pub is_trusted: bool,
+}
+
+#[derive(Debug)]
+struct ConnectedPeer {
+    node: Node,
+    remote_addr: SocketAddr,
+    inbound: bool,
+    /// Notified to make the connection with the peer end
+    disconnect: Arc<Notify>,
+}
+
+#[derive(Debug, Default)]
+struct PeerManagerState {
+    limits: PeerLimits,
+    static_peers: HashMap<H512, Node>,
+    trusted_peers: HashSet<H512>,
+    banned_peers: HashMap<H512, Instant>,
+    connected_peers: HashMap<H512, ConnectedPeer>,
+    /// Peers being dialed whose connection isn't established yet
+    pending_dials: HashSet<H512>,
+}
+
+impl PeerManagerState {
+    /// Static and trusted peers are always allowed to connect, regardless of the peer limits
+    fn is_exempt(&self, node_id: &H512) -> bool {
+        self.static_peers.contains_key(node_id) || self.trusted_peers.contains(node_id)
+    }
+
+    fn is_banned(&mut self, node_id: &H512) -> bool {
+        match self.banned_peers.get(node_id) {
+            Some(expiry) if *expiry > Instant::now() => true,
+            Some(_) => {
+                self.banned_peers.remove(node_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn connected_count(&self, inbound: bool) -> usize {
+        self.connected_peers
+            .values()
+            .filter(|peer| peer.inbound == inbound)
+            .count()
+    }
+}
+
+/// Keeps track of the RLPx sessions of the node, deciding which peers are allowed to connect
+/// and holding the static, trusted and banned peers lists.
+/// It can be cloned to be shared between the network tasks and the RPC API.
+#[derive(Debug, Clone, Default)]
+pub struct PeerManager {
+    state: Arc<Mutex<PeerManagerState>>,
+    /// Notified when a new static peer is added, so that it is dialed right away
+    dial_requested: Arc<Notify>,
+}
+
+impl PeerManager {
+    pub fn new(limits: PeerLimits, static_peers: Vec<Node>, trusted_peers: Vec<Node>) -> Self {
+        let state = PeerManagerState {
+            limits,
+            static_peers: static_peers
+                .into_iter()
+                .map(|node| (node.node_id, node))
+                .collect(),
+            trusted_peers: trusted_peers.iter().map(|node| node.node_id).collect(),
+            ..Default::default()
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            dial_requested: Arc::new(Notify::new()),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, PeerManagerState> {
+        // The state is left consistent by every method, so a poisoned lock can still be used
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Adds a peer that is always dialed and kept connected, returns false if it was already a static peer
+    /// Fails if the limit of static peers has been reached
+    pub fn add_static_peer(&self, node: Node) -> Result<bool, StaticPeersLimitReached> {
+        let mut state = self.state();
+        if !state.static_peers.contains_key(&node.node_id)
+            && state.static_peers.len() >= MAX_STATIC_PEERS
+        {
+            return Err(StaticPeersLimitReached);
+        }
+        let added = state.static_peers.insert(node.node_id, node).is_none();
+        self.dial_requested.notify_one();
+        Ok(added)
+    }
+
+    /// Removes the peer from the static peers and ends the connection with it, returns false if it wasn't a static peer
+    pub fn remove_static_peer(&self, node_id: H512) -> bool {
+        let mut state = self.state();
+        if state.static_peers.remove(&node_id).is_none() {
+            return false;
+        }
+        if let Some(peer) = state.connected_peers.get(&node_id) {
+            peer.disconnect.notify_one();
+        }
+        true
+    }
+
+    /// Static peers that are not connected nor being dialed and should be dialed
+    pub fn static_peers_to_dial(&self) -> Vec<Node> {
+        let mut state = self.state();
+        let nodes: Vec<Node> = state.static_peers.values().copied().collect();
+        nodes
+            .into_iter()
+            .filter(|node| {
+                !state.connected_peers.contains_key(&node.node_id)
+                    && !state.pending_dials.contains(&node.node_id)
+                    && !state.is_banned(&node.node_id)
+            })
+            .collect()
+    }
+
+    /// Waits until a new static peer is added
+    pub async fn dial_requested(&self) {
+        self.dial_requested.notified().await
+    }
+
+    /// Bans the peer for the given time, static and trusted peers are never banned
+    pub fn ban(&self, node_id: H512, duration: Duration) {
+        let mut state = self.state();
+        if !state.is_exempt(&node_id) {
+            state
+                .banned_peers
+                .insert(node_id, Instant::now() + duration);
+        }
+    }
+
+    pub fn is_banned(&self, node_id: H512) -> bool {
+        self.state().is_banned(&node_id)
+    }
+
+    /// Returns true and marks the dial as pending if the node should be dialed: it is not banned, connected nor
+    /// being dialed, and there are outbound slots left or it is a static peer.
+    /// `finish_dial` must be called once the dial is over
+    pub fn start_dial(&self, node_id: H512) -> bool {
+        let mut state = self.state();
+        if state.is_banned(&node_id)
+            || state.connected_peers.contains_key(&node_id)
+            || state.pending_dials.contains(&node_id)
+        {
+            return false;
+        }
+        if !state.is_exempt(&node_id) && state.connected_count(false) >= state.limits.max_outbound()
+        {
+            return false;
+        }
+        state.pending_dials.insert(node_id)
+    }
+
+    pub fn finish_dial(&self, node_id: H512) {
+        self.state().pending_dials.remove(&node_id);
+    }
+
+    /// Registers the connection with the peer once its handshake is done.
+    /// Returns the notification used to end the connection, or the reason it was rejected
+    pub(crate) fn register(
+        &self,
+        node: Node,
+        remote_addr: SocketAddr,
+        inbound: bool,
+    ) -> Result<Arc<Notify>, RLPxError> {
+        let node_id = node.node_id;
+        let mut state = self.state();
+        if state.is_banned(&node_id) {
+            return Err(RLPxError::BannedPeer());
+        }
+        if state.connected_peers.contains_key(&node_id) {
+            return Err(RLPxError::AlreadyConnected());
+        }
+        let max_peers = if inbound {
+            state.limits.max_inbound()
+        } else {
+            state.limits.max_outbound()
+        };
+        if !state.is_exempt(&node_id) && state.connected_count(inbound) >= max_peers {
+            return Err(RLPxError::TooManyPeers());
+        }
+        let disconnect = Arc::new(Notify::new());
+        state.connected_peers.insert(
+            node_id,
+            ConnectedPeer {
+                node,
+                remote_addr,
+                inbound,
+                disconnect: disconnect.clone(),
+            },
+        );
+        Ok(disconnect)
+    }
+
+    /// Frees the slot of the peer once its connection ends
+    pub(crate) fn unregister(&self, node_id: H512) {
+        self.state().connected_peers.remove(&node_id);
+    }
+
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        let state = self.state();
+        state
+            .connected_peers
+            .iter()
+            .map(|(node_id, peer)| PeerInfo {
+                node: peer.node,
+                remote_addr: peer.remote_addr,
+                inbound: peer.inbound,
+                is_static: state.static_peers.contains_key(node_id),
+                is_trusted: state.trusted_peers.contains(node_id),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn example_node(port: u16) -> Node {
+        Node {
+            ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            udp_port: port,
+            tcp_port: port,
+            node_id: H512::random(),
+        }
+    }
+
+    fn addr(node: &Node) -> SocketAddr {
+        SocketAddr::new(node.ip, node.tcp_port)
+    }
+
+    #[test]
+    fn peer_limits_are_split_between_inbound_and_outbound() {
+        let peers = PeerManager::new(
+            PeerLimits {
+                max_peers: 6,
+                dial_ratio: 3,
+            },
+            vec![],
+            vec![],
+        );
+        for port in 0..2 {
+            let node = example_node(port);
+            assert!(peers.start_dial(node.node_id));
+            assert!(peers.register(node, addr(&node), false).is_ok());
+            peers.finish_dial(node.node_id);
+        }
+        let node = example_node(2);
+        assert!(!peers.start_dial(node.node_id));
+        assert!(matches!(
+            peers.register(node, addr(&node), false),
+            Err(RLPxError::TooManyPeers())
+        ));
+        for port in 3..7 {
+            let node = example_node(port);
+            assert!(peers.register(node, addr(&node), true).is_ok());
+        }
+        let node = example_node(7);
+        assert!(matches!(
+            peers.register(node, addr(&node), true),
+            Err(RLPxError::TooManyPeers())
+        ));
+        assert_eq!(peers.peers().len(), 6);
+    }
+
+    #[test]
+    fn static_and_trusted_peers_are_exempt_from_limits() {
+        let static_peer = example_node(1);
+        let trusted_peer = example_node(2);
+        let peers = PeerManager::new(
+            PeerLimits {
+                max_peers: 0,
+                dial_ratio: 3,
+            },
+            vec![static_peer],
+            vec![trusted_peer],
+        );
+        let node = example_node(3);
+        assert!(peers.register(node, addr(&node), true).is_err());
+        assert_eq!(peers.static_peers_to_dial(), vec![static_peer]);
+        assert!(peers.start_dial(static_peer.node_id));
+        // a static peer that is being dialed is not dialed again
+        assert!(!peers.start_dial(static_peer.node_id));
+        assert!(peers.static_peers_to_dial().is_empty());
+        peers.finish_dial(static_peer.node_id);
+        assert_eq!(peers.static_peers_to_dial(), vec![static_peer]);
+        assert!(peers
+            .register(static_peer, addr(&static_peer), false)
+            .is_ok());
+        assert!(peers
+            .register(trusted_peer, addr(&trusted_peer), true)
+            .is_ok());
+        assert!(peers.static_peers_to_dial().is_empty());
+        assert!(matches!(
+            peers.register(static_peer, addr(&static_peer), true),
+            Err(RLPxError::AlreadyConnected())
+        ));
+
+        // static and trusted peers can't be banned
+        peers.ban(static_peer.node_id, BAN_DURATION);
+        peers.ban(trusted_peer.node_id, BAN_DURATION);
+        assert!(!peers.is_banned(static_peer.node_id));
+        assert!(!peers.is_banned(trusted_peer.node_id));
+    }
+
+    #[test]
+    fn banned_peers_are_rejected_until_the_ban_expires() {
+        let peers = PeerManager::default();
+        let node = example_node(1);
+        peers.ban(node.node_id, BAN_DURATION);
+        assert!(!peers.start_dial(node.node_id));
+        assert!(matches!(
+            peers.register(node, addr(&node), true),
+            Err(RLPxError::BannedPeer())
+        ));
+
+        peers.ban(node.node_id, Duration::ZERO);
+        assert!(!peers.is_banned(node.node_id));
+        assert!(peers.register(node, addr(&node), true).is_ok());
+        peers.unregister(node.node_id);
+        assert!(peers.peers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn removing_a_peer_that_is_not_static_keeps_its_connection() {
+        let peers = PeerManager::default();
+        let node = example_node(1);
+        let disconnect = peers.register(node, addr(&node), true).unwrap();
+        assert!(!peers.remove_static_peer(node.node_id));
+        let notified = tokio::time::timeout(Duration::from_millis(10), disconnect.notified());
+        assert!(notified.await.is_err());
+    }
+
+    #[test]
+    fn static_peers_are_bounded() {
+        let peers = PeerManager::default();
+        let nodes: Vec<Node> = (0..MAX_STATIC_PEERS)
+            .map(|port| example_node(port as u16))
+            .collect();
+        for node in &nodes {
+            assert_eq!(peers.add_static_peer(*node), Ok(true));
+        }
+        assert_eq!(
+            peers.add_static_peer(example_node(MAX_STATIC_PEERS as u16)),
+            Err(StaticPeersLimitReached)
+        );
+        // known static peers can still be added again
+        assert_eq!(peers.add_static_peer(nodes[0]), Ok(false));
+    }
+
+    #[tokio::test]
+    async fn removing_a_static_peer_ends_its_connection() {
+        let peers = PeerManager::default();
+        let node = example_node(1);
+        assert_eq!(peers.add_static_peer(node), Ok(true));
+        assert_eq!(peers.add_static_peer(node), Ok(false));
+        let disconnect = peers.register(node, addr(&node), false).unwrap();
+        assert!(peers.peers()[0].is_static);
+        assert_eq!(peers.peers()[0].node, node);
+        assert!(peers.remove_static_peer(node.node_id));
+        assert!(!peers.remove_static_peer(node.node_id));
+        // the permit stored by the removal is consumed right away
+        disconnect.notified().await;
+    }
+}
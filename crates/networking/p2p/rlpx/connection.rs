@@ -1,23 +1,29 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashSet, VecDeque},
+    net::SocketAddr,
+    sync::Arc,
+};
 
 use crate::{
     peer_channels::PeerChannels,
+    peer_manager::{PeerManager, BAN_DURATION},
     rlpx::{
         eth::{
             backend,
             blocks::{BlockBodies, BlockHeaders},
             receipts::Receipts,
-            transactions::Transactions,
+            transactions::{NewPooledTransactionHashes, PooledTransactions, Transactions},
         },
         handshake::encode_ack_message,
         message::Message,
-        p2p::{self, DisconnectMessage, PingMessage, PongMessage},
+        p2p::{self, DisconnectMessage, PingMessage, PongMessage, SharedCapabilities},
         utils::id2pubkey,
     },
     snap::{
         process_account_range_request, process_byte_codes_request, process_storage_ranges_request,
         process_trie_nodes_request,
     },
+    types::Node,
     MAX_DISC_PACKET_SIZE,
 };
 
@@ -29,34 +35,40 @@ use super::{
     handshake::{decode_ack_message, decode_auth_message, encode_auth_message},
     message::{self as rlpx},
     p2p::Capability,
-    utils::{ecdh_xchng, pubkey2id},
+    utils::ecdh_xchng,
 };
 use aes::cipher::KeyIvInit;
 use ethrex_blockchain::mempool::{self};
-use ethrex_core::{H256, H512};
+use ethrex_core::{
+    types::{P2PTransaction, Transaction},
+    H256, H512,
+};
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_storage::Store;
-use k256::{
-    ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey},
-    PublicKey, SecretKey,
-};
+use k256::{ecdsa::SigningKey, PublicKey, SecretKey};
 use rand::random;
 use sha3::{Digest, Keccak256};
 use tokio::{
     io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     sync::{
         broadcast::{self, error::RecvError},
-        mpsc, Mutex,
+        mpsc, Mutex, Notify,
     },
     task,
     time::{sleep, Instant},
 };
 use tracing::{debug, error};
 const CAP_P2P: (Capability, u8) = (Capability::P2p, 5);
-const CAP_ETH: (Capability, u8) = (Capability::Eth, 68);
+const CAP_ETH_68: (Capability, u8) = (Capability::Eth, 68);
+const CAP_ETH_69: (Capability, u8) = (Capability::Eth, 69);
 const CAP_SNAP: (Capability, u8) = (Capability::Snap, 1);
-const SUPPORTED_CAPABILITIES: [(Capability, u8); 3] = [CAP_P2P, CAP_ETH, CAP_SNAP];
+const SUPPORTED_CAPABILITIES: [(Capability, u8); 4] = [CAP_P2P, CAP_ETH_68, CAP_ETH_69, CAP_SNAP];
+// Same interval used by geth to announce the block range of the node
+const BLOCK_RANGE_UPDATE_INTERVAL: u64 = 32;
 const PERIODIC_TASKS_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+// Limit taken from here: https://github.com/ethereum/go-ethereum/blob/df182a742cec68adcc034d4747afa5182fc75ca3/eth/protocols/eth/peer.go#L37
+const MAX_KNOWN_TRANSACTIONS: usize = 32768;
+const MAX_PENDING_TRANSACTION_REQUESTS: usize = 64;
 
 pub(crate) type Aes256Ctr64BE = ctr::Ctr64BE<aes::Aes256>;
 
@@ -66,8 +78,10 @@ pub(crate) struct RLPxConnection<S> {
     state: RLPxConnectionState,
     stream: S,
     storage: Store,
-    capabilities: Vec<(Capability, u8)>,
+    capabilities: SharedCapabilities,
     next_periodic_task_check: Instant,
+    /// Latest block announced to the peer in the status or a BlockRangeUpdate message
+    announced_latest_block: u64,
     /// Send end of the channel used to broadcast messages
     /// to other connected peers, is ok to have it here,
     /// since internally it's an Arc.
@@ -77,6 +91,36 @@ pub(crate) struct RLPxConnection<S> {
     /// The receive end is instantiated after the handshake is completed
     /// under `handle_peer`.
     connection_broadcast_send: broadcast::Sender<(task::Id, Arc<Message>)>,
+    /// Transactions the peer is known to have, either because it sent or announced
+    /// them to us or because we sent or announced them to it.
+    known_transactions: KnownTransactions,
+    /// GetPooledTransactions requests sent to the peer awaiting a response,
+    /// along with the announcement of the requested transactions
+    pending_transaction_requests: VecDeque<(u64, NewPooledTransactionHashes)>,
+}
+
+/// Bounded set of transaction hashes, evicts the oldest hashes once full
+#[derive(Default)]
+struct KnownTransactions {
+    hashes: HashSet<H256>,
+    insertion_order: VecDeque<H256>,
+}
+
+impl KnownTransactions {
+    fn insert(&mut self, hash: H256) {
+        if self.hashes.insert(hash) {
+            self.insertion_order.push_back(hash);
+        }
+        if self.insertion_order.len() > MAX_KNOWN_TRANSACTIONS {
+            if let Some(oldest) = self.insertion_order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+    }
+
+    fn contains(&self, hash: &H256) -> bool {
+        self.hashes.contains(hash)
+    }
 }
 
 impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
@@ -92,9 +136,12 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             state,
             stream,
             storage,
-            capabilities: vec![],
+            capabilities: SharedCapabilities::default(),
             next_periodic_task_check: Instant::now() + PERIODIC_TASKS_CHECK_INTERVAL,
+            announced_latest_block: 0,
             connection_broadcast_send: connection_broadcast,
+            known_transactions: KnownTransactions::default(),
+            pending_transaction_requests: VecDeque::new(),
         }
     }
 
@@ -117,43 +164,37 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         )
     }
 
-    pub async fn initiator(
+    pub fn initiator(
         signer: SigningKey,
-        msg: &[u8],
+        node_id: H512,
         stream: S,
         storage: Store,
         connection_broadcast_send: broadcast::Sender<(task::Id, Arc<Message>)>,
-    ) -> Result<Self, RLPxError> {
+    ) -> Self {
         let mut rng = rand::thread_rng();
-        let digest = Keccak256::digest(msg.get(65..).ok_or(RLPxError::InvalidMessageLength())?);
-        let signature = &Signature::from_bytes(
-            msg.get(..64)
-                .ok_or(RLPxError::InvalidMessageLength())?
-                .into(),
-        )?;
-        let rid = RecoveryId::from_byte(*msg.get(64).ok_or(RLPxError::InvalidMessageLength())?)
-            .ok_or(RLPxError::InvalidRecoveryId())?;
-        let peer_pk = VerifyingKey::recover_from_prehash(&digest, signature, rid)?;
         let state = RLPxConnectionState::Initiator(Initiator::new(
             H256::random_using(&mut rng),
             SecretKey::random(&mut rng),
-            pubkey2id(&peer_pk.into()),
+            node_id,
         ));
-        Ok(RLPxConnection::new(
-            signer,
-            stream,
-            state,
-            storage,
-            connection_broadcast_send,
-        ))
+        RLPxConnection::new(signer, stream, state, storage, connection_broadcast_send)
     }
 
     /// Starts a handshake and runs the peer connection.
-    /// It runs in it's own task and blocks until the connection is dropped
-    pub async fn start_peer(&mut self, table: Arc<Mutex<crate::kademlia::KademliaTable>>) {
+    /// It runs in it's own task and blocks until the connection is dropped.
+    /// The node is only known beforehand when dialing it
+    pub async fn start_peer(
+        &mut self,
+        remote_addr: SocketAddr,
+        node: Option<Node>,
+        table: Arc<Mutex<crate::kademlia::KademliaTable>>,
+        peers: PeerManager,
+    ) {
+        let inbound = matches!(self.state, RLPxConnectionState::Receiver(_));
         // Perform handshake
         if let Err(e) = self.handshake().await {
-            self.peer_conn_failed("Handshake failed", e, table).await;
+            self.peer_conn_failed("Handshake failed", e, table, &peers)
+                .await;
         } else {
             // Handshake OK: handle connection
             // Create channels to communicate directly to the peer
@@ -164,14 +205,49 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
                         "Error during RLPx connection",
                         RLPxError::InvalidState(),
                         table,
+                        &peers,
                     )
                     .await;
             };
-            table.lock().await.set_channels(node_id, peer_channels);
-            if let Err(e) = self.handle_peer_conn(sender, receiver).await {
-                self.peer_conn_failed("Error during RLPx connection", e, table)
+            // The listen ports of inbound peers are only known if the peer was found through discovery
+            let node = match node {
+                Some(node) => node,
+                None => table
+                    .lock()
+                    .await
+                    .get_by_node_id(node_id)
+                    .map(|peer| peer.node)
+                    .unwrap_or(Node {
+                        ip: remote_addr.ip(),
+                        udp_port: 0,
+                        tcp_port: 0,
+                        node_id,
+                    }),
+            };
+            let disconnect = match peers.register(node, remote_addr, inbound) {
+                Ok(disconnect) => disconnect,
+                Err(e) => {
+                    // The peer is fine, so it is kept in the kademlia table
+                    debug!("Rejected peer {node_id}: ({e})");
+                    self.send(Message::Disconnect(DisconnectMessage {
+                        reason: self.match_disconnect_reason(&e),
+                    }))
+                    .await
+                    .unwrap_or_else(|e| debug!("Could not send Disconnect message: ({e})"));
+                    return;
+                }
+            };
+            let registered = table
+                .lock()
+                .await
+                .set_channels(node_id, peer_channels.clone());
+            // Only peers in the kademlia table can be dropped by the backend, the channels of other peers are kept open until the connection ends
+            let _unregistered_channels = (!registered).then_some(peer_channels);
+            if let Err(e) = self.handle_peer_conn(sender, receiver, disconnect).await {
+                self.peer_conn_failed("Error during RLPx connection", e, table, &peers)
                     .await;
             }
+            peers.unregister(node_id);
         }
     }
 
@@ -180,6 +256,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         error_text: &str,
         error: RLPxError,
         table: Arc<Mutex<crate::kademlia::KademliaTable>>,
+        peers: &PeerManager,
     ) {
         self.send(Message::Disconnect(DisconnectMessage {
             reason: self.match_disconnect_reason(&error),
@@ -190,6 +267,15 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             // Discard peer from kademlia table
             debug!("{error_text}: ({error}), discarding peer {node_id}");
             table.lock().await.replace_peer(node_id);
+            if matches!(
+                error,
+                RLPxError::RLPDecodeError(_)
+                    | RLPxError::BadRequest(_)
+                    | RLPxError::IncompatiblePeer(_)
+            ) {
+                debug!("Banning misbehaving peer {node_id}");
+                peers.ban(node_id, BAN_DURATION);
+            }
         } else {
             debug!("{error_text}: ({error}), unknown peer")
         }
@@ -198,6 +284,12 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
     fn match_disconnect_reason(&self, error: &RLPxError) -> Option<u8> {
         match error {
             RLPxError::RLPDecodeError(_) => Some(2_u8),
+            RLPxError::PeerDropped() | RLPxError::BannedPeer() => Some(3_u8),
+            RLPxError::TooManyPeers() => Some(4_u8),
+            RLPxError::AlreadyConnected() => Some(5_u8),
+            RLPxError::DisconnectRequested() => Some(0_u8),
+            // Subprotocol error, the peer is on a different chain or eth version
+            RLPxError::IncompatiblePeer(_) => Some(0x10_u8),
             // TODO build a proper matching between error types and disconnection reasons
             _ => None,
         }
@@ -235,18 +327,16 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
 
         // Receive Hello message
         if let Message::Hello(hello_message) = self.receive().await? {
-            self.capabilities = hello_message.capabilities;
+            self.capabilities =
+                SharedCapabilities::negotiate(&SUPPORTED_CAPABILITIES, &hello_message.capabilities);
 
             // Check if we have any capability in common
-            for cap in self.capabilities.clone() {
-                if SUPPORTED_CAPABILITIES.contains(&cap) {
-                    return Ok(());
-                }
+            if self.capabilities.is_empty() {
+                return Err(RLPxError::HandshakeError(
+                    "No matching capabilities".to_string(),
+                ));
             }
-            // Return error if not
-            Err(RLPxError::HandshakeError(
-                "No matching capabilities".to_string(),
-            ))
+            Ok(())
         } else {
             // Fail if it is not a hello message
             Err(RLPxError::HandshakeError(
@@ -259,13 +349,14 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         &mut self,
         sender: mpsc::Sender<rlpx::Message>,
         mut receiver: mpsc::Receiver<rlpx::Message>,
+        disconnect: Arc<Notify>,
     ) -> Result<(), RLPxError> {
         if let RLPxConnectionState::Established(_) = &self.state {
             self.init_peer_conn().await?;
             debug!("Started peer main loop");
             // Wait for eth status message or timeout.
             let mut broadcaster_receive = {
-                if self.capabilities.contains(&CAP_ETH) {
+                if self.capabilities.eth.is_some() {
                     Some(self.connection_broadcast_send.subscribe())
                 } else {
                     None
@@ -291,9 +382,16 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
                     Some(broadcasted_msg) = Self::maybe_wait_for_broadcaster(&mut broadcaster_receive) => {
                         self.handle_broadcast(broadcasted_msg?).await?
                     }
-                    Some(message) = receiver.recv() => {
+                    message = receiver.recv() => {
+                        // The backend drops its end of the channel once the peer is discarded from the kademlia table
+                        let Some(message) = message else {
+                            return Err(RLPxError::PeerDropped());
+                        };
                         self.send(message).await?;
                     }
+                    _ = disconnect.notified() => {
+                        return Err(RLPxError::DisconnectRequested());
+                    }
                     _ = sleep(PERIODIC_TASKS_CHECK_INTERVAL) => {
                         // no progress on other tasks, yield control to check
                         // periodic tasks
@@ -327,6 +425,15 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         if Instant::now() >= self.next_periodic_task_check {
             self.send(Message::Ping(PingMessage {})).await?;
             debug!("Ping sent");
+            if self.capabilities.eth == Some(69) {
+                let block_range = backend::get_block_range(&self.storage)?;
+                if block_range.latest_block
+                    >= self.announced_latest_block + BLOCK_RANGE_UPDATE_INTERVAL
+                {
+                    self.announced_latest_block = block_range.latest_block;
+                    self.send(Message::BlockRangeUpdate(block_range)).await?;
+                }
+            }
             self.next_periodic_task_check = Instant::now() + PERIODIC_TASKS_CHECK_INTERVAL;
         };
         Ok(())
@@ -337,7 +444,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         message: Message,
         sender: mpsc::Sender<Message>,
     ) -> Result<(), RLPxError> {
-        let peer_supports_eth = self.capabilities.contains(&CAP_ETH);
+        let peer_supports_eth = self.capabilities.eth.is_some();
         match message {
             Message::Disconnect(msg_data) => {
                 debug!("Received Disconnect: {:?}", msg_data.reason);
@@ -352,20 +459,20 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             Message::Pong(_) => {
                 // We ignore received Pong messages
             }
-            Message::Status(msg_data) if !peer_supports_eth => {
-                debug!("Received Status");
-                backend::validate_status(msg_data, &self.storage)?
-            }
             Message::GetAccountRange(req) => {
                 let response = process_account_range_request(req, self.storage.clone())?;
                 self.send(Message::AccountRange(response)).await?
             }
-            // TODO(#1129) Add the transaction to the mempool once received.
             Message::Transactions(txs) if peer_supports_eth => {
-                for tx in &txs.transactions {
-                    mempool::add_transaction(tx.clone(), &self.storage)?;
+                let mut transactions = vec![];
+                for tx in txs.transactions {
+                    // Blob transactions can only be announced, as they need to be sent along with their blobs
+                    match P2PTransaction::new(tx, None) {
+                        Some(tx) => transactions.push(tx),
+                        None => debug!("Received blob transaction in Transactions message"),
+                    }
                 }
-                self.broadcast_message(Message::Transactions(txs)).await?;
+                self.add_transactions_to_mempool(transactions).await?;
             }
             Message::GetBlockHeaders(msg_data) if peer_supports_eth => {
                 let response = BlockHeaders {
@@ -392,16 +499,46 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
                 };
                 self.send(Message::Receipts(response)).await?;
             }
+            Message::BlockRangeUpdate(block_range) if peer_supports_eth => {
+                if block_range.earliest_block > block_range.latest_block {
+                    return Err(RLPxError::BadRequest("Invalid block range".to_string()));
+                }
+                debug!(
+                    "Peer serves blocks {} to {}",
+                    block_range.earliest_block, block_range.latest_block
+                );
+            }
             Message::NewPooledTransactionHashes(new_pooled_transaction_hashes)
                 if peer_supports_eth =>
             {
+                for hash in new_pooled_transaction_hashes.transaction_hashes() {
+                    self.known_transactions.insert(*hash);
+                }
                 //TODO(#1415): evaluate keeping track of requests to avoid sending the same twice.
                 let hashes =
                     new_pooled_transaction_hashes.get_transactions_to_request(&self.storage)?;
-
-                //TODO(#1416): Evaluate keeping track of the request-id.
-                let request = GetPooledTransactions::new(random(), hashes);
-                self.send(Message::GetPooledTransactions(request)).await?;
+                if !hashes.is_empty() {
+                    let request = GetPooledTransactions::new(random(), hashes);
+                    let requested_announcement = new_pooled_transaction_hashes
+                        .filter(|hash| request.transaction_hashes().contains(hash));
+                    // Forget about the oldest request if the peer has too many pending ones
+                    if self.pending_transaction_requests.len() >= MAX_PENDING_TRANSACTION_REQUESTS {
+                        self.pending_transaction_requests.pop_front();
+                    }
+                    self.pending_transaction_requests
+                        .push_back((request.id, requested_announcement));
+                    self.send(Message::GetPooledTransactions(request)).await?;
+                }
+            }
+            Message::GetPooledTransactions(msg) if peer_supports_eth => {
+                let response = msg.handle(&self.storage)?;
+                for tx in &response.pooled_transactions {
+                    self.known_transactions.insert(tx.compute_hash());
+                }
+                self.send(Message::PooledTransactions(response)).await?;
+            }
+            Message::PooledTransactions(msg) if peer_supports_eth => {
+                self.handle_pooled_transactions(msg).await?;
             }
             Message::GetStorageRanges(req) => {
                 let response = process_storage_ranges_request(req, self.storage.clone())?;
@@ -429,6 +566,82 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         Ok(())
     }
 
+    async fn handle_pooled_transactions(
+        &mut self,
+        msg: PooledTransactions,
+    ) -> Result<(), RLPxError> {
+        let Some(index) = self
+            .pending_transaction_requests
+            .iter()
+            .position(|(id, _)| *id == msg.id)
+        else {
+            debug!(
+                "Received PooledTransactions for an unknown request {}",
+                msg.id
+            );
+            return Ok(());
+        };
+        let Some((_, announcement)) = self.pending_transaction_requests.remove(index) else {
+            return Ok(());
+        };
+        let mut transactions = vec![];
+        for tx in msg.pooled_transactions {
+            // Only accept transactions that were requested and match their announced type
+            let hash = tx.compute_hash();
+            if announcement.get_announced_type(&hash) == Some(tx.tx_type() as u8) {
+                transactions.push(tx);
+            } else {
+                debug!("Received unrequested pooled transaction {hash:#x}");
+            }
+        }
+        self.add_transactions_to_mempool(transactions).await
+    }
+
+    /// Validates and adds the transactions received from the peer to the mempool,
+    /// then propagates the ones that were added to the rest of the peers
+    async fn add_transactions_to_mempool(
+        &mut self,
+        transactions: Vec<P2PTransaction>,
+    ) -> Result<(), RLPxError> {
+        let mut added_transactions = vec![];
+        let mut added_blob_transactions = vec![];
+        for tx in transactions {
+            let hash = tx.compute_hash();
+            self.known_transactions.insert(hash);
+            if self.storage.get_transaction_from_pool(hash)?.is_some() {
+                continue;
+            }
+            let result = match tx.clone().into_parts() {
+                (Transaction::EIP4844Transaction(blob_tx), Some(blobs_bundle)) => {
+                    mempool::add_blob_transaction(blob_tx, blobs_bundle, self.storage.clone())
+                }
+                (tx, _) => mempool::add_transaction(tx, &self.storage),
+            };
+            match result {
+                Ok(_) => match tx {
+                    P2PTransaction::EIP4844TransactionWithBlobs(_) => {
+                        added_blob_transactions.push(tx)
+                    }
+                    tx => added_transactions.push(tx.into_parts().0),
+                },
+                // Invalid transactions are dropped without penalizing the peer, as they could have
+                // become invalid after being sent (e.g. another transaction from the same sender was included)
+                Err(error) => debug!("Rejected transaction {hash:#x} received from peer: {error}"),
+            }
+        }
+        if !added_transactions.is_empty() {
+            self.broadcast_message(Message::Transactions(Transactions::new(added_transactions)))
+                .await?;
+        }
+        if !added_blob_transactions.is_empty() {
+            self.broadcast_message(Message::NewPooledTransactionHashes(
+                NewPooledTransactionHashes::new(&added_blob_transactions),
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
     async fn handle_broadcast(
         &mut self,
         (id, broadcasted_msg): (task::Id, Arc<Message>),
@@ -437,12 +650,47 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             match broadcasted_msg.as_ref() {
                 Message::Transactions(ref txs) => {
                     // TODO(#1131): Avoid cloning this vector.
-                    let cloned = txs.transactions.clone();
-                    let new_msg = Message::Transactions(Transactions {
-                        transactions: cloned,
-                    });
+                    let unknown_transactions: Vec<Transaction> = txs
+                        .transactions
+                        .iter()
+                        .filter(|tx| !self.known_transactions.contains(&tx.compute_hash()))
+                        .cloned()
+                        .collect();
+                    if unknown_transactions.is_empty() {
+                        return Ok(());
+                    }
+                    for tx in &unknown_transactions {
+                        self.known_transactions.insert(tx.compute_hash());
+                    }
+                    // Send the full transactions to roughly the square root of the connected peers,
+                    // the rest of them will only receive an announcement and request them if needed
+                    let peer_count = self.connection_broadcast_send.receiver_count().max(1);
+                    let full_broadcast_peers = (peer_count as f64).sqrt().ceil() as usize;
+                    let new_msg = if random::<usize>() % peer_count < full_broadcast_peers {
+                        Message::Transactions(Transactions::new(unknown_transactions))
+                    } else {
+                        let transactions: Vec<P2PTransaction> = unknown_transactions
+                            .into_iter()
+                            .filter_map(|tx| P2PTransaction::new(tx, None))
+                            .collect();
+                        Message::NewPooledTransactionHashes(NewPooledTransactionHashes::new(
+                            &transactions,
+                        ))
+                    };
                     self.send(new_msg).await?;
                 }
+                Message::NewPooledTransactionHashes(ref announcement) => {
+                    let unknown_announcement =
+                        announcement.filter(|hash| !self.known_transactions.contains(hash));
+                    if unknown_announcement.transaction_hashes().is_empty() {
+                        return Ok(());
+                    }
+                    for hash in unknown_announcement.transaction_hashes() {
+                        self.known_transactions.insert(*hash);
+                    }
+                    self.send(Message::NewPooledTransactionHashes(unknown_announcement))
+                        .await?;
+                }
                 msg => {
                     error!("Unsupported message was broadcasted: {msg}");
                     return Err(RLPxError::BroadcastError(format!(
@@ -457,27 +705,40 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
 
     async fn init_peer_conn(&mut self) -> Result<(), RLPxError> {
         // Sending eth Status if peer supports it
-        if self.capabilities.contains(&CAP_ETH) {
-            let status = backend::get_status(&self.storage)?;
-            debug!("Sending status");
-            self.send(Message::Status(status)).await?;
-            // The next immediate message in the ETH protocol is the
-            // status, reference here:
-            // https://github.com/ethereum/devp2p/blob/master/caps/eth.md#status-0x00
-            match self.receive().await? {
-                Message::Status(msg_data) => {
-                    // TODO: Check message status is correct.
-                    debug!("Received Status");
-                    backend::validate_status(msg_data, &self.storage)?
-                }
-                _msg => {
-                    return Err(RLPxError::HandshakeError(
-                        "Expected a Status message".to_string(),
-                    ))
+        let Some(eth_version) = self.capabilities.eth else {
+            return Ok(());
+        };
+        let status = if eth_version == 69 {
+            let status = backend::get_status69(&self.storage)?;
+            self.announced_latest_block = status.latest_block;
+            Message::Status69(status)
+        } else {
+            Message::Status68(backend::get_status68(&self.storage)?)
+        };
+        debug!("Sending status");
+        self.send(status).await?;
+        // The next immediate message in the ETH protocol is the
+        // status, reference here:
+        // https://github.com/ethereum/devp2p/blob/master/caps/eth.md#status-0x00
+        match self.receive().await? {
+            Message::Status68(msg_data) => {
+                debug!("Received Status");
+                backend::validate_status(&msg_data, eth_version, &self.storage)
+            }
+            Message::Status69(msg_data) => {
+                debug!("Received Status");
+                backend::validate_status(&msg_data, eth_version, &self.storage)?;
+                if msg_data.earliest_block > msg_data.latest_block {
+                    return Err(RLPxError::IncompatiblePeer(
+                        "Invalid block range".to_string(),
+                    ));
                 }
+                Ok(())
             }
+            _msg => Err(RLPxError::HandshakeError(
+                "Expected a Status message".to_string(),
+            )),
         }
-        Ok(())
     }
 
     async fn send_auth(&mut self) -> Result<(), RLPxError> {
@@ -620,7 +881,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
     async fn send(&mut self, message: rlpx::Message) -> Result<(), RLPxError> {
         if let RLPxConnectionState::Established(state) = &mut self.state {
             let mut frame_buffer = vec![];
-            message.encode(&mut frame_buffer)?;
+            message.encode(&mut frame_buffer, &self.capabilities)?;
             frame::write(frame_buffer, state, &mut self.stream).await?;
             Ok(())
         } else {
@@ -632,7 +893,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         if let RLPxConnectionState::Established(state) = &mut self.state {
             let frame_data = frame::read(state, &mut self.stream).await?;
             let (msg_id, msg_data): (u8, _) = RLPDecode::decode_unfinished(&frame_data)?;
-            Ok(rlpx::Message::decode(msg_id, msg_data)?)
+            Ok(rlpx::Message::decode(msg_id, msg_data, &self.capabilities)?)
         } else {
             Err(RLPxError::InvalidState())
         }
@@ -640,7 +901,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
 
     pub async fn broadcast_message(&self, msg: Message) -> Result<(), RLPxError> {
         match msg {
-            txs_msg @ Message::Transactions(_) => {
+            txs_msg @ (Message::Transactions(_) | Message::NewPooledTransactionHashes(_)) => {
                 let txs = Arc::new(txs_msg);
                 let task_id = tokio::task::id();
                 let Ok(_) = self.connection_broadcast_send.send((task_id, txs)) else {
@@ -11,12 +11,24 @@ use super::message::Message;
 pub(crate) enum RLPxError {
     #[error("{0}")]
     HandshakeError(String),
+    #[error("Incompatible peer: {0}")]
+    IncompatiblePeer(String),
     #[error("{0}")]
     ConnectionError(String),
     #[error("Invalid connection state")]
     InvalidState(),
     #[error("Disconnect received")]
     Disconnect(),
+    #[error("Peer dropped from the kademlia table")]
+    PeerDropped(),
+    #[error("Too many peers")]
+    TooManyPeers(),
+    #[error("Peer already connected")]
+    AlreadyConnected(),
+    #[error("Peer is banned")]
+    BannedPeer(),
+    #[error("Disconnect requested")]
+    DisconnectRequested(),
     #[error("Not Found: {0}")]
     NotFound(String),
     #[error("Invalid peer id")]
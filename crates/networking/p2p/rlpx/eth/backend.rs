@@ -3,11 +3,9 @@ use ethrex_storage::Store;
 
 use crate::rlpx::error::RLPxError;
 
-use super::status::StatusMessage;
+use super::status::{BlockRangeUpdate, StatusMessage, StatusMessage68, StatusMessage69};
 
-pub const ETH_VERSION: u32 = 68;
-
-pub fn get_status(storage: &Store) -> Result<StatusMessage, RLPxError> {
+pub fn get_status68(storage: &Store) -> Result<StatusMessage68, RLPxError> {
     let chain_config = storage.get_chain_config()?;
     let total_difficulty = U256::from(chain_config.terminal_total_difficulty.unwrap_or_default());
     let network_id = chain_config.chain_id;
@@ -24,8 +22,8 @@ pub fn get_status(storage: &Store) -> Result<StatusMessage, RLPxError> {
     let genesis = genesis_header.compute_block_hash();
     let block_hash = block_header.compute_block_hash();
     let fork_id = ForkId::new(chain_config, genesis, block_header.timestamp, block_number);
-    Ok(StatusMessage {
-        eth_version: ETH_VERSION,
+    Ok(StatusMessage68 {
+        eth_version: 68,
         network_id,
         total_difficulty,
         block_hash,
@@ -34,8 +32,9 @@ pub fn get_status(storage: &Store) -> Result<StatusMessage, RLPxError> {
     })
 }
 
-pub fn validate_status(msg_data: StatusMessage, storage: &Store) -> Result<(), RLPxError> {
+pub fn get_status69(storage: &Store) -> Result<StatusMessage69, RLPxError> {
     let chain_config = storage.get_chain_config()?;
+    let network_id = chain_config.chain_id;
 
     // These blocks must always be available
     let genesis_header = storage
@@ -48,29 +47,101 @@ pub fn validate_status(msg_data: StatusMessage, storage: &Store) -> Result<(), R
 
     let genesis = genesis_header.compute_block_hash();
     let fork_id = ForkId::new(chain_config, genesis, block_header.timestamp, block_number);
+    let block_range = get_block_range(storage)?;
+    Ok(StatusMessage69 {
+        eth_version: 69,
+        network_id,
+        genesis,
+        fork_id,
+        earliest_block: block_range.earliest_block,
+        latest_block: block_range.latest_block,
+        latest_block_hash: block_range.latest_block_hash,
+    })
+}
+
+/// Returns the range of blocks that can be served to peers, as advertised since eth/69
+pub fn get_block_range(storage: &Store) -> Result<BlockRangeUpdate, RLPxError> {
+    let earliest_block = storage.get_earliest_block_number()?;
+    let latest_block = storage.get_latest_block_number()?;
+    let latest_block_hash = storage
+        .get_block_header(latest_block)?
+        .ok_or(RLPxError::NotFound(format!("Block {latest_block}")))?
+        .compute_block_hash();
+    Ok(BlockRangeUpdate {
+        earliest_block,
+        latest_block,
+        latest_block_hash,
+    })
+}
+
+/// Returns the fork id of the local chain, as advertised in the status message and the node record
+pub fn get_fork_id(storage: &Store) -> Result<ForkId, RLPxError> {
+    let chain_config = storage.get_chain_config()?;
+
+    // These blocks must always be available
+    let genesis_header = storage
+        .get_block_header(0)?
+        .ok_or(RLPxError::NotFound("Genesis Block".to_string()))?;
+    let block_number = storage.get_latest_block_number()?;
+    let block_header = storage
+        .get_block_header(block_number)?
+        .ok_or(RLPxError::NotFound(format!("Block {block_number}")))?;
+
+    let genesis = genesis_header.compute_block_hash();
+    Ok(ForkId::new(
+        chain_config,
+        genesis,
+        block_header.timestamp,
+        block_number,
+    ))
+}
+
+/// Validates the status message of a peer, `eth_version` being the version negotiated with it
+pub fn validate_status(
+    msg_data: &impl StatusMessage,
+    eth_version: u8,
+    storage: &Store,
+) -> Result<(), RLPxError> {
+    let chain_config = storage.get_chain_config()?;
+
+    // These blocks must always be available
+    let genesis_header = storage
+        .get_block_header(0)?
+        .ok_or(RLPxError::NotFound("Genesis Block".to_string()))?;
+    let block_number = storage.get_latest_block_number()?;
+    let block_header = storage
+        .get_block_header(block_number)?
+        .ok_or(RLPxError::NotFound(format!("Block {block_number}")))?;
+
+    let genesis = genesis_header.compute_block_hash();
 
     //Check networkID
-    if msg_data.network_id != chain_config.chain_id {
-        return Err(RLPxError::HandshakeError(
+    if msg_data.get_network_id() != chain_config.chain_id {
+        return Err(RLPxError::IncompatiblePeer(
             "Network Id does not match".to_string(),
         ));
     }
     //Check Protocol Version
-    if msg_data.eth_version != ETH_VERSION {
-        return Err(RLPxError::HandshakeError(
+    if msg_data.get_eth_version() != eth_version as u32 {
+        return Err(RLPxError::IncompatiblePeer(
             "Eth protocol version does not match".to_string(),
         ));
     }
     //Check Genesis
-    if msg_data.genesis != genesis {
-        return Err(RLPxError::HandshakeError(
+    if msg_data.get_genesis() != genesis {
+        return Err(RLPxError::IncompatiblePeer(
             "Genesis does not match".to_string(),
         ));
     }
     // Check ForkID
-    if msg_data.fork_id != fork_id {
-        return Err(RLPxError::HandshakeError(
-            "Fork Id does not match".to_string(),
+    if !msg_data.get_fork_id().is_compatible(
+        chain_config,
+        genesis,
+        block_header.timestamp,
+        block_number,
+    ) {
+        return Err(RLPxError::IncompatiblePeer(
+            "Fork Id is not compatible".to_string(),
         ));
     }
 
@@ -79,8 +150,8 @@ pub fn validate_status(msg_data: StatusMessage, storage: &Store) -> Result<(), R
 
 #[cfg(test)]
 mod tests {
-    use super::validate_status;
-    use crate::rlpx::eth::status::StatusMessage;
+    use super::{get_status69, validate_status};
+    use crate::rlpx::{error::RLPxError, eth::status::StatusMessage68};
     use ethrex_core::{
         types::{ForkId, Genesis},
         H256, U256,
@@ -88,10 +159,7 @@ mod tests {
     use ethrex_storage::{EngineType, Store};
     use std::{fs::File, io::BufReader};
 
-    #[test]
-    // TODO add tests for failing validations
-    fn test_validate_status() {
-        // Setup
+    fn setup() -> (Store, StatusMessage68) {
         // TODO we should have this setup exported to some test_utils module and use from there
         let storage =
             Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
@@ -108,7 +176,7 @@ mod tests {
         let genesis_hash = genesis.get_block().hash();
         let fork_id = ForkId::new(config, genesis_hash, 2707305664, 123);
 
-        let message = StatusMessage {
+        let message = StatusMessage68 {
             eth_version: 68u32,
             network_id: 3503995874084926,
             total_difficulty,
@@ -116,7 +184,56 @@ mod tests {
             genesis: genesis_hash,
             fork_id,
         };
-        let result = validate_status(message, &storage);
+        (storage, message)
+    }
+
+    #[test]
+    fn test_validate_status() {
+        let (storage, message) = setup();
+        let result = validate_status(&message, 68, &storage);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_validate_status_with_different_eth_version() {
+        let (storage, message) = setup();
+        let result = validate_status(&message, 69, &storage);
+        assert!(matches!(result, Err(RLPxError::IncompatiblePeer(_))));
+    }
+
+    #[test]
+    fn test_validate_status69() {
+        let (storage, _) = setup();
+        let message = get_status69(&storage).unwrap();
+        assert_eq!(message.earliest_block, 0);
+        assert_eq!(message.latest_block, 0);
+        let result = validate_status(&message, 69, &storage);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_validate_status_with_different_network_id() {
+        let (storage, mut message) = setup();
+        message.network_id = 1;
+        let result = validate_status(&message, 68, &storage);
+        assert!(matches!(result, Err(RLPxError::IncompatiblePeer(_))));
+    }
+
+    #[test]
+    fn test_validate_status_with_different_genesis() {
+        let (storage, mut message) = setup();
+        message.genesis = H256::random();
+        let result = validate_status(&message, 68, &storage);
+        assert!(matches!(result, Err(RLPxError::IncompatiblePeer(_))));
+    }
+
+    #[test]
+    fn test_validate_status_with_incompatible_fork_id() {
+        let (storage, mut message) = setup();
+        let config = storage.get_chain_config().unwrap();
+        // A fork id computed from another genesis can't match any of the local forks
+        message.fork_id = ForkId::new(config, H256::random(), 2707305664, 123);
+        let result = validate_status(&message, 68, &storage);
+        assert!(matches!(result, Err(RLPxError::IncompatiblePeer(_))));
+    }
 }
@@ -43,6 +43,12 @@ impl From<BlockHash> for HashOrNumber {
     }
 }
 
+impl From<BlockNumber> for HashOrNumber {
+    fn from(value: BlockNumber) -> Self {
+        Self::Number(value)
+    }
+}
+
 impl RLPDecode for HashOrNumber {
     fn decode_unfinished(buf: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
         let first_byte = buf.first().ok_or(RLPDecodeError::InvalidLength)?;
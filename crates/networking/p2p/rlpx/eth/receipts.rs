@@ -2,9 +2,11 @@ use crate::rlpx::{
     message::RLPxMessage,
     utils::{snappy_compress, snappy_decompress},
 };
-use bytes::BufMut;
-use ethrex_core::types::{BlockHash, Receipt};
+use bytes::{BufMut, Bytes};
+use ethrex_core::types::{BlockHash, Log, Receipt, TxType};
 use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
     error::{RLPDecodeError, RLPEncodeError},
     structs::{Decoder, Encoder},
 };
@@ -60,6 +62,82 @@ impl Receipts {
     pub fn new(id: u64, receipts: Vec<Vec<Receipt>>) -> Self {
         Self { receipts, id }
     }
+
+    /// Encodes the message in its eth/69 format, where receipts don't include their bloom
+    pub fn encode69(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
+        let receipts: Vec<Vec<ReceiptWithoutBloom>> = self
+            .receipts
+            .iter()
+            .map(|block_receipts| {
+                block_receipts
+                    .iter()
+                    .cloned()
+                    .map(ReceiptWithoutBloom)
+                    .collect()
+            })
+            .collect();
+        let mut encoded_data = vec![];
+        Encoder::new(&mut encoded_data)
+            .encode_field(&self.id)
+            .encode_field(&receipts)
+            .finish();
+
+        let msg_data = snappy_compress(encoded_data)?;
+        buf.put_slice(&msg_data);
+        Ok(())
+    }
+
+    /// Decodes the message in its eth/69 format, the bloom of each receipt is computed from its logs
+    pub fn decode69(msg_data: &[u8]) -> Result<Self, RLPDecodeError> {
+        let decompressed_data = snappy_decompress(msg_data)?;
+        let decoder = Decoder::new(&decompressed_data)?;
+        let (id, decoder): (u64, _) = decoder.decode_field("request-id")?;
+        let (receipts, _): (Vec<Vec<ReceiptWithoutBloom>>, _) = decoder.decode_field("receipts")?;
+        let receipts = receipts
+            .into_iter()
+            .map(|block_receipts| block_receipts.into_iter().map(|r| r.0).collect())
+            .collect();
+
+        Ok(Self::new(id, receipts))
+    }
+}
+
+/// Receipt as sent in eth/69 messages: [tx-type, post-state-or-status, cumulative-gas, logs]
+struct ReceiptWithoutBloom(Receipt);
+
+impl RLPEncode for ReceiptWithoutBloom {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&u8::from(self.0.tx_type))
+            .encode_field(&self.0.succeeded)
+            .encode_field(&self.0.cumulative_gas_used)
+            .encode_field(&self.0.logs)
+            .finish();
+    }
+}
+
+impl RLPDecode for ReceiptWithoutBloom {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (tx_type, decoder): (u8, _) = decoder.decode_field("tx-type")?;
+        let tx_type = TxType::from_u8(tx_type).ok_or(RLPDecodeError::Custom(format!(
+            "Invalid transaction type: {tx_type}"
+        )))?;
+        let (post_state_or_status, decoder): (Bytes, _) =
+            decoder.decode_field("post-state-or-status")?;
+        let succeeded = match post_state_or_status.as_ref() {
+            [] => false,
+            [1] => true,
+            // Pre-Byzantium receipts carry the post-transaction state root instead of a status,
+            // there is no status to read from them so they are taken as succeeded
+            state_root if state_root.len() == 32 => true,
+            _ => return Err(RLPDecodeError::MalformedData),
+        };
+        let (cumulative_gas_used, decoder) = decoder.decode_field("cumulative-gas")?;
+        let (logs, decoder): (Vec<Log>, _) = decoder.decode_field("logs")?;
+        let receipt = Receipt::new(tx_type, succeeded, cumulative_gas_used, logs);
+        Ok((Self(receipt), decoder.finish()?))
+    }
 }
 
 impl RLPxMessage for Receipts {
@@ -87,11 +165,18 @@ impl RLPxMessage for Receipts {
 
 #[cfg(test)]
 mod tests {
-    use ethrex_core::types::{BlockHash, Receipt};
+    use bytes::Bytes;
+    use ethrex_core::{
+        types::{BlockHash, Log, Receipt, TxType},
+        Address, H256,
+    };
+
+    use ethrex_rlp::structs::Encoder;
 
     use crate::rlpx::{
         eth::receipts::{GetReceipts, Receipts},
         message::RLPxMessage,
+        utils::snappy_compress,
     };
 
     #[test]
@@ -136,4 +221,47 @@ mod tests {
         assert_eq!(decoded.id, 1);
         assert_eq!(decoded.receipts, Vec::<Vec<Receipt>>::new());
     }
+
+    #[test]
+    fn receipts69_not_empty_message() {
+        let log = Log {
+            address: Address::from_low_u64_be(1),
+            topics: vec![H256::from_low_u64_be(2)],
+            data: Bytes::from_static(b"data"),
+        };
+        let receipts = vec![
+            vec![
+                Receipt::new(TxType::Legacy, true, 21000, vec![]),
+                Receipt::new(TxType::EIP1559, false, 42000, vec![log]),
+            ],
+            vec![],
+        ];
+        let message = Receipts::new(1, receipts.clone());
+
+        let mut buf = Vec::new();
+        message.encode69(&mut buf).unwrap();
+
+        let decoded = Receipts::decode69(&buf).unwrap();
+        assert_eq!(decoded.id, 1);
+        // The blooms are computed back from the logs
+        assert_eq!(decoded.receipts, receipts);
+    }
+
+    #[test]
+    fn receipts69_with_post_state_root() {
+        // [tx-type, post-state-root, cumulative-gas, logs]
+        let receipt = (0_u8, H256::from_low_u64_be(1), 21000_u64, Vec::<Log>::new());
+        let mut encoded_data = vec![];
+        Encoder::new(&mut encoded_data)
+            .encode_field(&1_u64)
+            .encode_field(&vec![vec![receipt]])
+            .finish();
+        let buf = snappy_compress(encoded_data).unwrap();
+
+        let decoded = Receipts::decode69(&buf).unwrap();
+        assert_eq!(
+            decoded.receipts,
+            vec![vec![Receipt::new(TxType::Legacy, true, 21000, vec![])]]
+        );
+    }
 }
@@ -4,7 +4,7 @@ use crate::rlpx::{
 };
 use bytes::BufMut;
 use ethrex_core::{
-    types::{BlockHash, ForkId},
+    types::{BlockHash, BlockNumber, ForkId},
     U256,
 };
 use ethrex_rlp::{
@@ -12,8 +12,17 @@ use ethrex_rlp::{
     structs::{Decoder, Encoder},
 };
 
+/// Fields shared by the status messages of all eth versions, used to validate the peer's chain
+pub(crate) trait StatusMessage {
+    fn get_eth_version(&self) -> u32;
+    fn get_network_id(&self) -> u64;
+    fn get_genesis(&self) -> BlockHash;
+    fn get_fork_id(&self) -> &ForkId;
+}
+
+// https://github.com/ethereum/devp2p/blob/master/caps/eth.md#status-0x00
 #[derive(Debug)]
-pub(crate) struct StatusMessage {
+pub(crate) struct StatusMessage68 {
     pub(crate) eth_version: u32,
     pub(crate) network_id: u64,
     pub(crate) total_difficulty: U256,
@@ -22,7 +31,25 @@ pub(crate) struct StatusMessage {
     pub(crate) fork_id: ForkId,
 }
 
-impl RLPxMessage for StatusMessage {
+impl StatusMessage for StatusMessage68 {
+    fn get_eth_version(&self) -> u32 {
+        self.eth_version
+    }
+
+    fn get_network_id(&self) -> u64 {
+        self.network_id
+    }
+
+    fn get_genesis(&self) -> BlockHash {
+        self.genesis
+    }
+
+    fn get_fork_id(&self) -> &ForkId {
+        &self.fork_id
+    }
+}
+
+impl RLPxMessage for StatusMessage68 {
     fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
         let mut encoded_data = vec![];
         Encoder::new(&mut encoded_data)
@@ -43,9 +70,6 @@ impl RLPxMessage for StatusMessage {
         let decompressed_data = snappy_decompress(msg_data)?;
         let decoder = Decoder::new(&decompressed_data)?;
         let (eth_version, decoder): (u32, _) = decoder.decode_field("protocolVersion")?;
-
-        assert_eq!(eth_version, 68, "only eth version 68 is supported");
-
         let (network_id, decoder): (u64, _) = decoder.decode_field("networkId")?;
         let (total_difficulty, decoder): (U256, _) = decoder.decode_field("totalDifficulty")?;
         let (block_hash, decoder): (BlockHash, _) = decoder.decode_field("blockHash")?;
@@ -64,3 +88,117 @@ impl RLPxMessage for StatusMessage {
         })
     }
 }
+
+// https://github.com/ethereum/devp2p/blob/master/caps/eth.md#status-0x00
+// Since eth/69 the total difficulty is no longer sent, and the range of available blocks is sent instead
+#[derive(Debug)]
+pub(crate) struct StatusMessage69 {
+    pub(crate) eth_version: u32,
+    pub(crate) network_id: u64,
+    pub(crate) genesis: BlockHash,
+    pub(crate) fork_id: ForkId,
+    pub(crate) earliest_block: BlockNumber,
+    pub(crate) latest_block: BlockNumber,
+    pub(crate) latest_block_hash: BlockHash,
+}
+
+impl StatusMessage for StatusMessage69 {
+    fn get_eth_version(&self) -> u32 {
+        self.eth_version
+    }
+
+    fn get_network_id(&self) -> u64 {
+        self.network_id
+    }
+
+    fn get_genesis(&self) -> BlockHash {
+        self.genesis
+    }
+
+    fn get_fork_id(&self) -> &ForkId {
+        &self.fork_id
+    }
+}
+
+impl RLPxMessage for StatusMessage69 {
+    fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
+        let mut encoded_data = vec![];
+        Encoder::new(&mut encoded_data)
+            .encode_field(&self.eth_version)
+            .encode_field(&self.network_id)
+            .encode_field(&self.genesis)
+            .encode_field(&self.fork_id)
+            .encode_field(&self.earliest_block)
+            .encode_field(&self.latest_block)
+            .encode_field(&self.latest_block_hash)
+            .finish();
+
+        let msg_data = snappy_compress(encoded_data)?;
+        buf.put_slice(&msg_data);
+        Ok(())
+    }
+
+    fn decode(msg_data: &[u8]) -> Result<Self, RLPDecodeError> {
+        let decompressed_data = snappy_decompress(msg_data)?;
+        let decoder = Decoder::new(&decompressed_data)?;
+        let (eth_version, decoder): (u32, _) = decoder.decode_field("protocolVersion")?;
+        let (network_id, decoder): (u64, _) = decoder.decode_field("networkId")?;
+        let (genesis, decoder): (BlockHash, _) = decoder.decode_field("genesis")?;
+        let (fork_id, decoder): (ForkId, _) = decoder.decode_field("forkId")?;
+        let (earliest_block, decoder): (BlockNumber, _) = decoder.decode_field("earliestBlock")?;
+        let (latest_block, decoder): (BlockNumber, _) = decoder.decode_field("latestBlock")?;
+        let (latest_block_hash, decoder): (BlockHash, _) =
+            decoder.decode_field("latestBlockHash")?;
+        // Implementations must ignore any additional list elements
+        let _padding = decoder.finish_unchecked();
+
+        Ok(Self {
+            eth_version,
+            network_id,
+            genesis,
+            fork_id,
+            earliest_block,
+            latest_block,
+            latest_block_hash,
+        })
+    }
+}
+
+// https://github.com/ethereum/devp2p/blob/master/caps/eth.md#blockrangeupdate-0x11
+#[derive(Debug)]
+pub(crate) struct BlockRangeUpdate {
+    pub(crate) earliest_block: BlockNumber,
+    pub(crate) latest_block: BlockNumber,
+    pub(crate) latest_block_hash: BlockHash,
+}
+
+impl RLPxMessage for BlockRangeUpdate {
+    fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
+        let mut encoded_data = vec![];
+        Encoder::new(&mut encoded_data)
+            .encode_field(&self.earliest_block)
+            .encode_field(&self.latest_block)
+            .encode_field(&self.latest_block_hash)
+            .finish();
+
+        let msg_data = snappy_compress(encoded_data)?;
+        buf.put_slice(&msg_data);
+        Ok(())
+    }
+
+    fn decode(msg_data: &[u8]) -> Result<Self, RLPDecodeError> {
+        let decompressed_data = snappy_decompress(msg_data)?;
+        let decoder = Decoder::new(&decompressed_data)?;
+        let (earliest_block, decoder): (BlockNumber, _) = decoder.decode_field("earliestBlock")?;
+        let (latest_block, decoder): (BlockNumber, _) = decoder.decode_field("latestBlock")?;
+        let (latest_block_hash, decoder): (BlockHash, _) =
+            decoder.decode_field("latestBlockHash")?;
+        let _padding = decoder.finish_unchecked();
+
+        Ok(Self {
+            earliest_block,
+            latest_block,
+            latest_block_hash,
+        })
+    }
+}
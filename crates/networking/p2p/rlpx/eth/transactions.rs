@@ -1,6 +1,9 @@
 use bytes::BufMut;
 use bytes::Bytes;
-use ethrex_core::{types::Transaction, H256};
+use ethrex_core::{
+    types::{P2PTransaction, Transaction},
+    H256,
+};
 use ethrex_rlp::{
     error::{RLPDecodeError, RLPEncodeError},
     structs::{Decoder, Encoder},
@@ -72,22 +75,48 @@ pub(crate) struct NewPooledTransactionHashes {
 }
 
 impl NewPooledTransactionHashes {
-    // delete this after we use this in the main loop
-    #[allow(dead_code)]
-    pub fn new(transactions: Vec<Transaction>) -> Self {
+    pub fn new(transactions: &[P2PTransaction]) -> Self {
         let transactions_len = transactions.len();
         let mut transaction_types = Vec::with_capacity(transactions_len);
         let mut transaction_sizes = Vec::with_capacity(transactions_len);
         let mut transaction_hashes = Vec::with_capacity(transactions_len);
         for transaction in transactions {
-            let transaction_type = transaction.tx_type();
-            transaction_types.push(transaction_type as u8);
-            // size is defined as the len of the concatenation of tx_type and the tx_data
-            // as the tx_type goes from 0x00 to 0xff, the size of tx_type is 1 byte
-            let transaction_size = 1 + transaction.data().len();
-            transaction_sizes.push(transaction_size);
-            let transaction_hash = transaction.compute_hash();
-            transaction_hashes.push(transaction_hash);
+            transaction_types.push(transaction.tx_type() as u8);
+            // size is defined as the len of the canonical encoding of the transaction
+            // which includes the blobs bundle in the case of blob transactions
+            transaction_sizes.push(transaction.encode_canonical_to_vec().len());
+            transaction_hashes.push(transaction.compute_hash());
+        }
+        Self {
+            transaction_types: transaction_types.into(),
+            transaction_sizes,
+            transaction_hashes,
+        }
+    }
+
+    pub fn transaction_hashes(&self) -> &[H256] {
+        &self.transaction_hashes
+    }
+
+    /// Returns the announced type of the given transaction, if it is part of the announcement
+    pub fn get_announced_type(&self, hash: &H256) -> Option<u8> {
+        self.transaction_hashes
+            .iter()
+            .position(|announced| announced == hash)
+            .and_then(|index| self.transaction_types.get(index).copied())
+    }
+
+    /// Returns a new announcement only containing the transactions that match the given filter
+    pub fn filter(&self, filter: impl Fn(&H256) -> bool) -> Self {
+        let mut transaction_types = vec![];
+        let mut transaction_sizes = vec![];
+        let mut transaction_hashes = vec![];
+        for (index, hash) in self.transaction_hashes.iter().enumerate() {
+            if filter(hash) {
+                transaction_types.push(self.transaction_types[index]);
+                transaction_sizes.push(self.transaction_sizes[index]);
+                transaction_hashes.push(*hash);
+            }
         }
         Self {
             transaction_types: transaction_types.into(),
@@ -140,12 +169,16 @@ impl RLPxMessage for NewPooledTransactionHashes {
     }
 }
 
+// Limit taken from here: https://github.com/ethereum/go-ethereum/blob/df182a742cec68adcc034d4747afa5182fc75ca3/eth/handler_eth.go#L34
+// Once a response reaches this size no more transactions are added to it
+pub const POOLED_TRANSACTIONS_SOFT_LIMIT: usize = 2 * 1024 * 1024;
+
 // https://github.com/ethereum/devp2p/blob/master/caps/eth.md#getpooledtransactions-0x09
 #[derive(Debug)]
 pub(crate) struct GetPooledTransactions {
     // id is a u64 chosen by the requesting peer, the responding peer must mirror the value for the response
     // https://github.com/ethereum/devp2p/blob/master/caps/eth.md#protocol-messages
-    id: u64,
+    pub(crate) id: u64,
     transaction_hashes: Vec<H256>,
 }
 
@@ -156,6 +189,33 @@ impl GetPooledTransactions {
             id,
         }
     }
+
+    pub fn transaction_hashes(&self) -> &[H256] {
+        &self.transaction_hashes
+    }
+
+    /// Builds the response with the requested transactions we have in the mempool
+    /// Unknown transactions are skipped and the response is capped to a soft size limit
+    pub fn handle(&self, storage: &Store) -> Result<PooledTransactions, StoreError> {
+        let mut pooled_transactions = vec![];
+        let mut response_size = 0;
+        for hash in &self.transaction_hashes {
+            let Some(transaction) = storage.get_transaction_from_pool(*hash)? else {
+                continue;
+            };
+            let blobs_bundle = storage.get_blobs_bundle_from_pool(*hash)?;
+            // Blob transactions can't be sent without their blobs bundle
+            let Some(transaction) = P2PTransaction::new(transaction.into(), blobs_bundle) else {
+                continue;
+            };
+            response_size += transaction.encode_canonical_to_vec().len();
+            pooled_transactions.push(transaction);
+            if response_size >= POOLED_TRANSACTIONS_SOFT_LIMIT {
+                break;
+            }
+        }
+        Ok(PooledTransactions::new(self.id, pooled_transactions))
+    }
 }
 
 impl RLPxMessage for GetPooledTransactions {
@@ -182,15 +242,16 @@ impl RLPxMessage for GetPooledTransactions {
 }
 
 // https://github.com/ethereum/devp2p/blob/master/caps/eth.md#pooledtransactions-0x0a
+#[derive(Debug)]
 pub(crate) struct PooledTransactions {
     // id is a u64 chosen by the requesting peer, the responding peer must mirror the value for the response
     // https://github.com/ethereum/devp2p/blob/master/caps/eth.md#protocol-messages
-    id: u64,
-    pooled_transactions: Vec<Transaction>,
+    pub(crate) id: u64,
+    pub(crate) pooled_transactions: Vec<P2PTransaction>,
 }
 
 impl PooledTransactions {
-    pub fn new(id: u64, pooled_transactions: Vec<Transaction>) -> Self {
+    pub fn new(id: u64, pooled_transactions: Vec<P2PTransaction>) -> Self {
         Self {
             pooled_transactions,
             id,
@@ -214,7 +275,7 @@ impl RLPxMessage for PooledTransactions {
         let decompressed_data = snappy_decompress(msg_data)?;
         let decoder = Decoder::new(&decompressed_data)?;
         let (id, decoder): (u64, _) = decoder.decode_field("request-id")?;
-        let (pooled_transactions, _): (Vec<Transaction>, _) =
+        let (pooled_transactions, _): (Vec<P2PTransaction>, _) =
             decoder.decode_field("pooledTransactions")?;
 
         Ok(Self::new(id, pooled_transactions))
@@ -223,10 +284,18 @@ impl RLPxMessage for PooledTransactions {
 
 #[cfg(test)]
 mod tests {
-    use ethrex_core::{types::Transaction, H256};
+    use ethrex_core::{
+        types::{
+            BlobsBundle, EIP1559Transaction, P2PTransaction, WrappedEIP4844Transaction,
+            BYTES_PER_BLOB,
+        },
+        H256,
+    };
 
     use crate::rlpx::{
-        eth::transactions::{GetPooledTransactions, PooledTransactions},
+        eth::transactions::{
+            GetPooledTransactions, NewPooledTransactionHashes, PooledTransactions,
+        },
         message::RLPxMessage,
     };
 
@@ -262,7 +331,7 @@ mod tests {
 
     #[test]
     fn pooled_transactions_of_one_type() {
-        let transaction1 = Transaction::LegacyTransaction(Default::default());
+        let transaction1 = P2PTransaction::LegacyTransaction(Default::default());
         let pooled_transactions = vec![transaction1.clone()];
         let pooled_transactions = PooledTransactions::new(1, pooled_transactions);
 
@@ -272,4 +341,50 @@ mod tests {
         assert_eq!(decoded.id, 1);
         assert_eq!(decoded.pooled_transactions, vec![transaction1]);
     }
+
+    #[test]
+    fn pooled_transactions_with_blob_transaction() {
+        let transaction1 = P2PTransaction::EIP1559Transaction(EIP1559Transaction {
+            nonce: 1,
+            ..Default::default()
+        });
+        let transaction2 = P2PTransaction::EIP4844TransactionWithBlobs(WrappedEIP4844Transaction {
+            tx: Default::default(),
+            blobs_bundle: BlobsBundle {
+                blobs: vec![[1; BYTES_PER_BLOB]],
+                commitments: vec![[2; 48]],
+                proofs: vec![[3; 48]],
+            },
+        });
+        let pooled_transactions = vec![transaction1, transaction2];
+        let message = PooledTransactions::new(1, pooled_transactions.clone());
+
+        let mut buf = Vec::new();
+        message.encode(&mut buf).unwrap();
+        let decoded = PooledTransactions::decode(&buf).unwrap();
+        assert_eq!(decoded.id, 1);
+        assert_eq!(decoded.pooled_transactions, pooled_transactions);
+    }
+
+    #[test]
+    fn new_pooled_transaction_hashes_filter() {
+        let transactions = vec![
+            P2PTransaction::LegacyTransaction(Default::default()),
+            P2PTransaction::EIP1559Transaction(Default::default()),
+        ];
+        let announcement = NewPooledTransactionHashes::new(&transactions);
+        let eip1559_hash = transactions[1].compute_hash();
+        let filtered = announcement.filter(|hash| *hash == eip1559_hash);
+        assert_eq!(filtered.transaction_hashes(), &[eip1559_hash]);
+        assert_eq!(filtered.get_announced_type(&eip1559_hash), Some(0x02));
+        assert_eq!(
+            filtered.get_announced_type(&transactions[0].compute_hash()),
+            None
+        );
+
+        let mut buf = Vec::new();
+        filtered.encode(&mut buf).unwrap();
+        let decoded = NewPooledTransactionHashes::decode(&buf).unwrap();
+        assert_eq!(decoded.transaction_hashes(), &[eip1559_hash]);
+    }
 }
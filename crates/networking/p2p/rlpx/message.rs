@@ -4,9 +4,11 @@ use std::fmt::Display;
 
 use super::eth::blocks::{BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders};
 use super::eth::receipts::{GetReceipts, Receipts};
-use super::eth::status::StatusMessage;
-use super::eth::transactions::{GetPooledTransactions, NewPooledTransactionHashes, Transactions};
-use super::p2p::{DisconnectMessage, HelloMessage, PingMessage, PongMessage};
+use super::eth::status::{BlockRangeUpdate, StatusMessage68, StatusMessage69};
+use super::eth::transactions::{
+    GetPooledTransactions, NewPooledTransactionHashes, PooledTransactions, Transactions,
+};
+use super::p2p::{DisconnectMessage, HelloMessage, PingMessage, PongMessage, SharedCapabilities};
 use super::snap::{
     AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
     StorageRanges, TrieNodes,
@@ -25,7 +27,8 @@ pub(crate) enum Message {
     Disconnect(DisconnectMessage),
     Ping(PingMessage),
     Pong(PongMessage),
-    Status(StatusMessage),
+    Status68(StatusMessage68),
+    Status69(StatusMessage69),
     // https://github.com/ethereum/devp2p/blob/5713591d0366da78a913a811c7502d9ca91d29a8/caps/eth.md#getblockheaders-0x03
     GetBlockHeaders(GetBlockHeaders),
     BlockHeaders(BlockHeaders),
@@ -34,8 +37,10 @@ pub(crate) enum Message {
     BlockBodies(BlockBodies),
     GetReceipts(GetReceipts),
     Receipts(Receipts),
+    BlockRangeUpdate(BlockRangeUpdate),
     NewPooledTransactionHashes(NewPooledTransactionHashes),
     GetPooledTransactions(GetPooledTransactions),
+    PooledTransactions(PooledTransactions),
     // snap capability
     GetAccountRange(GetAccountRange),
     AccountRange(AccountRange),
@@ -48,142 +53,143 @@ pub(crate) enum Message {
 }
 
 impl Message {
-    pub fn decode(msg_id: u8, msg_data: &[u8]) -> Result<Message, RLPDecodeError> {
+    pub fn decode(
+        msg_id: u8,
+        msg_data: &[u8],
+        capabilities: &SharedCapabilities,
+    ) -> Result<Message, RLPDecodeError> {
         match msg_id {
-            0x00 => Ok(Message::Hello(HelloMessage::decode(msg_data)?)),
-            0x01 => Ok(Message::Disconnect(DisconnectMessage::decode(msg_data)?)),
-            0x02 => Ok(Message::Ping(PingMessage::decode(msg_data)?)),
-            0x03 => Ok(Message::Pong(PongMessage::decode(msg_data)?)),
-            // Subprotocols like 'eth' use offsets to identify
-            // themselves, the eth capability starts
-            // at 0x10 (16), the status message
-            // has offset 0, so a message with id 0x10
-            // identifies an eth status message.
-            // Another example is the eth getBlockHeaders message,
-            // which has 3 as its offset, so it is identified as 0x13 (19).
-            // References:
-            // - https://ethereum.stackexchange.com/questions/37051/ethereum-network-messaging
-            // - https://github.com/ethereum/devp2p/blob/master/caps/eth.md#status-0x00
-            0x10 => Ok(Message::Status(StatusMessage::decode(msg_data)?)),
-            0x12 => Ok(Message::Transactions(Transactions::decode(msg_data)?)),
-            0x13 => Ok(Message::GetBlockHeaders(GetBlockHeaders::decode(msg_data)?)),
-            0x14 => Ok(Message::BlockHeaders(BlockHeaders::decode(msg_data)?)),
-            0x15 => Ok(Message::GetBlockBodies(GetBlockBodies::decode(msg_data)?)),
-            0x16 => Ok(Message::BlockBodies(BlockBodies::decode(msg_data)?)),
-            0x18 => Ok(Message::NewPooledTransactionHashes(
-                NewPooledTransactionHashes::decode(msg_data)?,
-            )),
-            0x19 => Ok(Message::GetPooledTransactions(
-                GetPooledTransactions::decode(msg_data)?,
-            )),
-            0x1F => Ok(Message::GetReceipts(GetReceipts::decode(msg_data)?)),
-            0x20 => Ok(Message::Receipts(Receipts::decode(msg_data)?)),
-            0x21 => Ok(Message::GetAccountRange(GetAccountRange::decode(msg_data)?)),
-            0x22 => Ok(Message::AccountRange(AccountRange::decode(msg_data)?)),
-            0x23 => Ok(Message::GetStorageRanges(GetStorageRanges::decode(
-                msg_data,
-            )?)),
-            0x24 => Ok(Message::StorageRanges(StorageRanges::decode(msg_data)?)),
-            0x25 => Ok(Message::GetByteCodes(GetByteCodes::decode(msg_data)?)),
-            0x26 => Ok(Message::ByteCodes(ByteCodes::decode(msg_data)?)),
-            0x27 => Ok(Message::GetTrieNodes(GetTrieNodes::decode(msg_data)?)),
-            0x28 => Ok(Message::TrieNodes(TrieNodes::decode(msg_data)?)),
-            _ => Err(RLPDecodeError::MalformedData),
+            0x00 => return Ok(Message::Hello(HelloMessage::decode(msg_data)?)),
+            0x01 => return Ok(Message::Disconnect(DisconnectMessage::decode(msg_data)?)),
+            0x02 => return Ok(Message::Ping(PingMessage::decode(msg_data)?)),
+            0x03 => return Ok(Message::Pong(PongMessage::decode(msg_data)?)),
+            _ => {}
+        }
+        // Subprotocols like 'eth' use offsets to identify
+        // themselves, the offsets depend on the capabilities
+        // shared with the peer, the first one starts at 0x10 (16).
+        // If eth is shared, the status message
+        // has offset 0, so a message with id 0x10
+        // identifies an eth status message.
+        // Another example is the eth getBlockHeaders message,
+        // which has 3 as its offset, so it is identified as 0x13 (19).
+        // References:
+        // - https://ethereum.stackexchange.com/questions/37051/ethereum-network-messaging
+        // - https://github.com/ethereum/devp2p/blob/master/caps/eth.md#status-0x00
+        if let Some(eth_msg_id) = capabilities.eth_message_id(msg_id) {
+            let is_eth69 = capabilities.eth == Some(69);
+            return match eth_msg_id {
+                0x00 if is_eth69 => Ok(Message::Status69(StatusMessage69::decode(msg_data)?)),
+                0x00 => Ok(Message::Status68(StatusMessage68::decode(msg_data)?)),
+                0x02 => Ok(Message::Transactions(Transactions::decode(msg_data)?)),
+                0x03 => Ok(Message::GetBlockHeaders(GetBlockHeaders::decode(msg_data)?)),
+                0x04 => Ok(Message::BlockHeaders(BlockHeaders::decode(msg_data)?)),
+                0x05 => Ok(Message::GetBlockBodies(GetBlockBodies::decode(msg_data)?)),
+                0x06 => Ok(Message::BlockBodies(BlockBodies::decode(msg_data)?)),
+                0x08 => Ok(Message::NewPooledTransactionHashes(
+                    NewPooledTransactionHashes::decode(msg_data)?,
+                )),
+                0x09 => Ok(Message::GetPooledTransactions(
+                    GetPooledTransactions::decode(msg_data)?,
+                )),
+                0x0A => Ok(Message::PooledTransactions(PooledTransactions::decode(
+                    msg_data,
+                )?)),
+                0x0F => Ok(Message::GetReceipts(GetReceipts::decode(msg_data)?)),
+                0x10 if is_eth69 => Ok(Message::Receipts(Receipts::decode69(msg_data)?)),
+                0x10 => Ok(Message::Receipts(Receipts::decode(msg_data)?)),
+                0x11 => Ok(Message::BlockRangeUpdate(BlockRangeUpdate::decode(
+                    msg_data,
+                )?)),
+                _ => Err(RLPDecodeError::MalformedData),
+            };
+        }
+        if let Some(snap_msg_id) = capabilities.snap_message_id(msg_id) {
+            return match snap_msg_id {
+                0x00 => Ok(Message::GetAccountRange(GetAccountRange::decode(msg_data)?)),
+                0x01 => Ok(Message::AccountRange(AccountRange::decode(msg_data)?)),
+                0x02 => Ok(Message::GetStorageRanges(GetStorageRanges::decode(
+                    msg_data,
+                )?)),
+                0x03 => Ok(Message::StorageRanges(StorageRanges::decode(msg_data)?)),
+                0x04 => Ok(Message::GetByteCodes(GetByteCodes::decode(msg_data)?)),
+                0x05 => Ok(Message::ByteCodes(ByteCodes::decode(msg_data)?)),
+                0x06 => Ok(Message::GetTrieNodes(GetTrieNodes::decode(msg_data)?)),
+                0x07 => Ok(Message::TrieNodes(TrieNodes::decode(msg_data)?)),
+                _ => Err(RLPDecodeError::MalformedData),
+            };
         }
+        Err(RLPDecodeError::MalformedData)
     }
 
-    pub fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
+    pub fn encode(
+        &self,
+        buf: &mut dyn BufMut,
+        capabilities: &SharedCapabilities,
+    ) -> Result<(), RLPEncodeError> {
+        self.code(capabilities)?.encode(buf);
         match self {
-            Message::Hello(msg) => {
-                0x00_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::Disconnect(msg) => {
-                0x01_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::Ping(msg) => {
-                0x02_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::Pong(msg) => {
-                0x03_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::Status(msg) => {
-                0x10_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::Transactions(msg) => {
-                0x12_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::GetBlockHeaders(msg) => {
-                0x13_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::BlockHeaders(msg) => {
-                0x14_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::GetBlockBodies(msg) => {
-                0x15_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::BlockBodies(msg) => {
-                0x16_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::NewPooledTransactionHashes(msg) => {
-                0x18_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::GetPooledTransactions(msg) => {
-                0x19_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::GetReceipts(msg) => {
-                0x1F_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::Receipts(msg) => {
-                0x20_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::GetAccountRange(msg) => {
-                0x21_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::AccountRange(msg) => {
-                0x22_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::GetStorageRanges(msg) => {
-                0x23_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::StorageRanges(msg) => {
-                0x24_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::GetByteCodes(msg) => {
-                0x25_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::ByteCodes(msg) => {
-                0x26_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::GetTrieNodes(msg) => {
-                0x27_u8.encode(buf);
-                msg.encode(buf)
-            }
-            Message::TrieNodes(msg) => {
-                0x28_u8.encode(buf);
-                msg.encode(buf)
-            }
+            Message::Hello(msg) => msg.encode(buf),
+            Message::Disconnect(msg) => msg.encode(buf),
+            Message::Ping(msg) => msg.encode(buf),
+            Message::Pong(msg) => msg.encode(buf),
+            Message::Status68(msg) => msg.encode(buf),
+            Message::Status69(msg) => msg.encode(buf),
+            Message::Transactions(msg) => msg.encode(buf),
+            Message::GetBlockHeaders(msg) => msg.encode(buf),
+            Message::BlockHeaders(msg) => msg.encode(buf),
+            Message::GetBlockBodies(msg) => msg.encode(buf),
+            Message::BlockBodies(msg) => msg.encode(buf),
+            Message::NewPooledTransactionHashes(msg) => msg.encode(buf),
+            Message::GetPooledTransactions(msg) => msg.encode(buf),
+            Message::PooledTransactions(msg) => msg.encode(buf),
+            Message::GetReceipts(msg) => msg.encode(buf),
+            Message::Receipts(msg) if capabilities.eth == Some(69) => msg.encode69(buf),
+            Message::Receipts(msg) => msg.encode(buf),
+            Message::BlockRangeUpdate(msg) => msg.encode(buf),
+            Message::GetAccountRange(msg) => msg.encode(buf),
+            Message::AccountRange(msg) => msg.encode(buf),
+            Message::GetStorageRanges(msg) => msg.encode(buf),
+            Message::StorageRanges(msg) => msg.encode(buf),
+            Message::GetByteCodes(msg) => msg.encode(buf),
+            Message::ByteCodes(msg) => msg.encode(buf),
+            Message::GetTrieNodes(msg) => msg.encode(buf),
+            Message::TrieNodes(msg) => msg.encode(buf),
         }
     }
+
+    /// Returns the id of the message, given the capabilities shared with the peer
+    fn code(&self, capabilities: &SharedCapabilities) -> Result<u8, RLPEncodeError> {
+        let (offset, msg_id) = match self {
+            Message::Hello(_) => return Ok(0x00),
+            Message::Disconnect(_) => return Ok(0x01),
+            Message::Ping(_) => return Ok(0x02),
+            Message::Pong(_) => return Ok(0x03),
+            Message::Status68(_) | Message::Status69(_) => (capabilities.eth_offset(), 0x00),
+            Message::Transactions(_) => (capabilities.eth_offset(), 0x02),
+            Message::GetBlockHeaders(_) => (capabilities.eth_offset(), 0x03),
+            Message::BlockHeaders(_) => (capabilities.eth_offset(), 0x04),
+            Message::GetBlockBodies(_) => (capabilities.eth_offset(), 0x05),
+            Message::BlockBodies(_) => (capabilities.eth_offset(), 0x06),
+            Message::NewPooledTransactionHashes(_) => (capabilities.eth_offset(), 0x08),
+            Message::GetPooledTransactions(_) => (capabilities.eth_offset(), 0x09),
+            Message::PooledTransactions(_) => (capabilities.eth_offset(), 0x0A),
+            Message::GetReceipts(_) => (capabilities.eth_offset(), 0x0F),
+            Message::Receipts(_) => (capabilities.eth_offset(), 0x10),
+            Message::BlockRangeUpdate(_) => (capabilities.eth_offset(), 0x11),
+            Message::GetAccountRange(_) => (capabilities.snap_offset(), 0x00),
+            Message::AccountRange(_) => (capabilities.snap_offset(), 0x01),
+            Message::GetStorageRanges(_) => (capabilities.snap_offset(), 0x02),
+            Message::StorageRanges(_) => (capabilities.snap_offset(), 0x03),
+            Message::GetByteCodes(_) => (capabilities.snap_offset(), 0x04),
+            Message::ByteCodes(_) => (capabilities.snap_offset(), 0x05),
+            Message::GetTrieNodes(_) => (capabilities.snap_offset(), 0x06),
+            Message::TrieNodes(_) => (capabilities.snap_offset(), 0x07),
+        };
+        offset
+            .map(|offset| offset + msg_id)
+            .ok_or_else(|| RLPEncodeError::Custom(format!("{self} is not supported by the peer")))
+    }
 }
 
 impl Display for Message {
@@ -193,16 +199,18 @@ impl Display for Message {
             Message::Disconnect(_) => "p2p:Disconnect".fmt(f),
             Message::Ping(_) => "p2p:Ping".fmt(f),
             Message::Pong(_) => "p2p:Pong".fmt(f),
-            Message::Status(_) => "eth:Status".fmt(f),
+            Message::Status68(_) | Message::Status69(_) => "eth:Status".fmt(f),
             Message::GetBlockHeaders(_) => "eth:getBlockHeaders".fmt(f),
             Message::BlockHeaders(_) => "eth:BlockHeaders".fmt(f),
             Message::BlockBodies(_) => "eth:BlockBodies".fmt(f),
             Message::NewPooledTransactionHashes(_) => "eth:NewPooledTransactionHashes".fmt(f),
             Message::GetPooledTransactions(_) => "eth::GetPooledTransactions".fmt(f),
+            Message::PooledTransactions(_) => "eth::PooledTransactions".fmt(f),
             Message::Transactions(_) => "eth:TransactionsMessage".fmt(f),
             Message::GetBlockBodies(_) => "eth:GetBlockBodies".fmt(f),
             Message::GetReceipts(_) => "eth:GetReceipts".fmt(f),
             Message::Receipts(_) => "eth:Receipts".fmt(f),
+            Message::BlockRangeUpdate(_) => "eth:BlockRangeUpdate".fmt(f),
             Message::GetAccountRange(_) => "snap:GetAccountRange".fmt(f),
             Message::AccountRange(_) => "snap:AccountRange".fmt(f),
             Message::GetStorageRanges(_) => "snap:GetStorageRanges".fmt(f),
@@ -32,16 +32,91 @@ impl RLPEncode for Capability {
     }
 }
 
+impl Capability {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "p2p" => Some(Capability::P2p),
+            "eth" => Some(Capability::Eth),
+            "snap" => Some(Capability::Snap),
+            _ => None,
+        }
+    }
+}
+
 impl RLPDecode for Capability {
     fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
         let (cap_string, rest) = String::decode_unfinished(rlp)?;
-        match cap_string.as_str() {
-            "p2p" => Ok((Capability::P2p, rest)),
-            "eth" => Ok((Capability::Eth, rest)),
-            "snap" => Ok((Capability::Snap, rest)),
-            _ => Err(RLPDecodeError::UnexpectedString),
+        let capability =
+            Capability::from_name(&cap_string).ok_or(RLPDecodeError::UnexpectedString)?;
+        Ok((capability, rest))
+    }
+}
+
+/// Amount of message ids used by each eth version
+const fn eth_message_count(version: u8) -> u8 {
+    match version {
+        68 => 0x11,
+        // eth/69 adds the BlockRangeUpdate message
+        _ => 0x12,
+    }
+}
+/// Amount of message ids used by snap/1
+const SNAP_MESSAGE_COUNT: u8 = 0x08;
+/// Message ids below this one are used by the p2p capability
+const P2P_MESSAGE_COUNT: u8 = 0x10;
+
+/// Versions of the capabilities shared with a peer, they determine the ids and the encoding of its messages
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct SharedCapabilities {
+    pub(crate) eth: Option<u8>,
+    pub(crate) snap: Option<u8>,
+}
+
+impl SharedCapabilities {
+    /// Picks the highest version of each capability that is supported by both ends
+    pub fn negotiate(local: &[(Capability, u8)], remote: &[(Capability, u8)]) -> Self {
+        let highest_shared_version = |capability: Capability| {
+            local
+                .iter()
+                .filter(|cap| cap.0 == capability && remote.contains(cap))
+                .map(|(_, version)| *version)
+                .max()
+        };
+        Self {
+            eth: highest_shared_version(Capability::Eth),
+            snap: highest_shared_version(Capability::Snap),
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.eth.is_none() && self.snap.is_none()
+    }
+
+    // Shared capabilities get consecutive message ids after the p2p ones, ordered by their name
+    // https://github.com/ethereum/devp2p/blob/master/rlpx.md#message-id-based-multiplexing
+    pub fn eth_offset(&self) -> Option<u8> {
+        self.eth.map(|_| P2P_MESSAGE_COUNT)
+    }
+
+    pub fn snap_offset(&self) -> Option<u8> {
+        self.snap
+            .map(|_| P2P_MESSAGE_COUNT + self.eth.map_or(0, eth_message_count))
+    }
+
+    /// Returns the id of the message within the eth capability, if the message id belongs to it
+    pub fn eth_message_id(&self, msg_id: u8) -> Option<u8> {
+        let version = self.eth?;
+        msg_id
+            .checked_sub(self.eth_offset()?)
+            .filter(|id| *id < eth_message_count(version))
+    }
+
+    /// Returns the id of the message within the snap capability, if the message id belongs to it
+    pub fn snap_message_id(&self, msg_id: u8) -> Option<u8> {
+        msg_id
+            .checked_sub(self.snap_offset()?)
+            .filter(|id| *id < SNAP_MESSAGE_COUNT)
+    }
 }
 
 #[derive(Debug)]
@@ -76,14 +151,25 @@ impl RLPxMessage for HelloMessage {
         let decoder = Decoder::new(msg_data)?;
         let (protocol_version, decoder): (u64, _) = decoder.decode_field("protocolVersion")?;
 
-        assert_eq!(protocol_version, 5, "only protocol version 5 is supported");
+        if protocol_version != 5 {
+            return Err(RLPDecodeError::Custom(format!(
+                "Unsupported p2p protocol version: {protocol_version}"
+            )));
+        }
 
         let (_client_id, decoder): (String, _) = decoder.decode_field("clientId")?;
         // TODO: store client id for debugging purposes
 
         // [[cap1, capVersion1], [cap2, capVersion2], ...]
-        let (capabilities, decoder): (Vec<(Capability, u8)>, _) =
+        let (capabilities, decoder): (Vec<(String, u8)>, _) =
             decoder.decode_field("capabilities")?;
+        // Capabilities we don't know about can't be shared, so they are skipped
+        let capabilities = capabilities
+            .into_iter()
+            .filter_map(|(name, version)| {
+                Capability::from_name(&name).map(|capability| (capability, version))
+            })
+            .collect();
 
         // This field should be ignored
         let (_listen_port, decoder): (u16, _) = decoder.decode_field("listenPort")?;
@@ -206,3 +292,85 @@ impl RLPxMessage for PongMessage {
         Ok(Self::new())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ethrex_core::H512;
+    use ethrex_rlp::structs::Encoder;
+
+    use super::{Capability, HelloMessage, SharedCapabilities};
+    use crate::rlpx::message::RLPxMessage;
+
+    const LOCAL_CAPABILITIES: [(Capability, u8); 4] = [
+        (Capability::P2p, 5),
+        (Capability::Eth, 68),
+        (Capability::Eth, 69),
+        (Capability::Snap, 1),
+    ];
+
+    #[test]
+    fn negotiate_highest_shared_versions() {
+        let remote = [
+            (Capability::P2p, 5),
+            (Capability::Eth, 67),
+            (Capability::Eth, 68),
+            (Capability::Eth, 69),
+            (Capability::Snap, 1),
+        ];
+        let shared = SharedCapabilities::negotiate(&LOCAL_CAPABILITIES, &remote);
+        assert_eq!(shared.eth, Some(69));
+        assert_eq!(shared.snap, Some(1));
+
+        let remote = [(Capability::P2p, 5), (Capability::Eth, 68)];
+        let shared = SharedCapabilities::negotiate(&LOCAL_CAPABILITIES, &remote);
+        assert_eq!(shared.eth, Some(68));
+        assert_eq!(shared.snap, None);
+
+        let remote = [(Capability::P2p, 5), (Capability::Eth, 66)];
+        let shared = SharedCapabilities::negotiate(&LOCAL_CAPABILITIES, &remote);
+        assert!(shared.is_empty());
+    }
+
+    #[test]
+    fn message_offsets_depend_on_shared_capabilities() {
+        let eth68 = SharedCapabilities {
+            eth: Some(68),
+            snap: Some(1),
+        };
+        assert_eq!(eth68.eth_offset(), Some(0x10));
+        assert_eq!(eth68.snap_offset(), Some(0x21));
+        assert_eq!(eth68.eth_message_id(0x20), Some(0x10));
+        assert_eq!(eth68.eth_message_id(0x21), None);
+        assert_eq!(eth68.snap_message_id(0x21), Some(0x00));
+
+        let eth69 = SharedCapabilities {
+            eth: Some(69),
+            snap: Some(1),
+        };
+        assert_eq!(eth69.snap_offset(), Some(0x22));
+        assert_eq!(eth69.eth_message_id(0x21), Some(0x11));
+        assert_eq!(eth69.snap_message_id(0x29), Some(0x07));
+        assert_eq!(eth69.snap_message_id(0x2A), None);
+
+        let snap_only = SharedCapabilities {
+            eth: None,
+            snap: Some(1),
+        };
+        assert_eq!(snap_only.eth_offset(), None);
+        assert_eq!(snap_only.snap_offset(), Some(0x10));
+        assert_eq!(snap_only.eth_message_id(0x10), None);
+    }
+
+    #[test]
+    fn hello_with_unsupported_protocol_version_is_rejected() {
+        let mut buf = vec![];
+        Encoder::new(&mut buf)
+            .encode_field(&4_u8)
+            .encode_field(&"client")
+            .encode_field(&vec![("eth".to_string(), 68_u8)])
+            .encode_field(&0_u8)
+            .encode_field(&H512::zero())
+            .finish();
+        assert!(HelloMessage::decode(&buf).is_err());
+    }
+}
@@ -1,23 +1,43 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
-use ethrex_blockchain::error::ChainError;
+use bytes::Bytes;
+use ethrex_blockchain::{error::ChainError, is_canonical};
 use ethrex_core::{
-    types::{Block, BlockHash, BlockHeader, EMPTY_KECCACK_HASH},
-    H256,
+    types::{AccountState, Block, BlockHash, BlockHeader, BlockNumber, EMPTY_KECCACK_HASH},
+    H256, U256,
 };
-use ethrex_rlp::encode::RLPEncode;
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode, error::RLPDecodeError};
 use ethrex_storage::{error::StoreError, Store};
-use ethrex_trie::EMPTY_TRIE_HASH;
+use ethrex_trie::{Nibbles, TrieError, TrieHealer, EMPTY_TRIE_HASH};
 use tokio::{
     sync::{
         mpsc::{self, error::SendError, Receiver, Sender},
         Mutex,
     },
-    time::Instant,
+    task::JoinSet,
+    time::{sleep, Instant},
 };
 use tracing::{debug, info, warn};
 
-use crate::kademlia::KademliaTable;
+use crate::{
+    downloader::{self, BLOCK_DOWNLOAD_WINDOW},
+    kademlia::KademliaTable,
+};
+
+/// Amount of blocks after the snap-sync pivot that will be executed instead of having their state downloaded
+/// Keeping the pivot behind the sync head makes it more likely for peers to still serve its state while we download it
+const MIN_FULL_BLOCKS: usize = 64;
+/// Amount of consecutive failed requests for a state root after which we consider it stale and move the pivot forward
+const MAX_RETRIES: usize = 5;
+/// Max amount of trie nodes to request at once while healing
+const NODE_BATCH_SIZE: usize = 500;
+/// Amount of consecutive header downloads that can return no headers before the sync cycle is aborted
+const MAX_HEADER_DOWNLOAD_RETRIES: u32 = 5;
+/// Time waited after the first header download that returns no headers, doubled after each consecutive one
+const HEADER_DOWNLOAD_BACKOFF: Duration = Duration::from_secs(2);
 
 #[derive(Debug)]
 pub enum SyncMode {
@@ -25,17 +45,89 @@ pub enum SyncMode {
     Snap,
 }
 
+/// Progress of an ongoing sync cycle, following the fields geth reports via `eth_syncing`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncProgress {
+    /// Latest block when the sync cycle started
+    pub starting_block: BlockNumber,
+    /// Latest fully synced block
+    pub current_block: BlockNumber,
+    /// Highest block known to the sync cycle
+    pub highest_block: BlockNumber,
+    /// Accounts downloaded via snap account ranges
+    pub synced_accounts: u64,
+    pub synced_account_bytes: u64,
+    /// Storage slots downloaded via snap storage ranges
+    pub synced_storage: u64,
+    pub synced_storage_bytes: u64,
+    /// Bytecodes downloaded via snap bytecode requests
+    pub synced_bytecodes: u64,
+    pub synced_bytecode_bytes: u64,
+    /// Trie nodes and bytecodes downloaded while healing the state
+    pub healed_trienodes: u64,
+    pub healed_trienode_bytes: u64,
+    pub healed_bytecodes: u64,
+    pub healed_bytecode_bytes: u64,
+    /// Trie nodes and bytecodes pending to be healed
+    pub healing_trienodes: u64,
+    pub healing_bytecodes: u64,
+    /// Whether the state is being healed, bytecodes fetched meanwhile are reported as healed
+    pub healing: bool,
+}
+
+/// Shared view of the progress of the current sync cycle, it can be read while the sync is running
+#[derive(Debug, Default, Clone)]
+pub struct SyncStatus(Arc<RwLock<Option<SyncProgress>>>);
+
+impl SyncStatus {
+    /// Returns the progress of the current sync cycle, or None if the node is not syncing
+    pub fn progress(&self) -> Option<SyncProgress> {
+        self.0.read().unwrap().clone()
+    }
+
+    fn start(&self, starting_block: BlockNumber) {
+        *self.0.write().unwrap() = Some(SyncProgress {
+            starting_block,
+            current_block: starting_block,
+            highest_block: starting_block,
+            ..Default::default()
+        });
+    }
+
+    /// Applies an update to the progress of the current sync cycle, if any
+    fn update(&self, update: impl FnOnce(&mut SyncProgress)) {
+        if let Some(progress) = self.0.write().unwrap().as_mut() {
+            update(progress)
+        }
+    }
+
+    fn finish(&self) {
+        *self.0.write().unwrap() = None;
+    }
+}
+
 /// Manager in charge the sync process
 /// Only performs full-sync but will also be in charge of snap-sync in the future
 #[derive(Debug)]
 pub struct SyncManager {
     sync_mode: SyncMode,
     peers: Arc<Mutex<KademliaTable>>,
+    status: SyncStatus,
 }
 
 impl SyncManager {
     pub fn new(peers: Arc<Mutex<KademliaTable>>, sync_mode: SyncMode) -> Self {
-        Self { sync_mode, peers }
+        Self {
+            sync_mode,
+            peers,
+            status: SyncStatus::default(),
+        }
+    }
+
+    /// Returns a handle to the progress of the manager's sync cycles
+    /// As the manager is locked while syncing, this should be obtained beforehand
+    pub fn status(&self) -> SyncStatus {
+        self.status.clone()
     }
 
     /// Creates a dummy SyncManager for tests where syncing is not needed
@@ -45,6 +137,7 @@ impl SyncManager {
         Self {
             sync_mode: SyncMode::Full,
             peers: dummy_peer_table,
+            status: SyncStatus::default(),
         }
     }
 
@@ -57,7 +150,20 @@ impl SyncManager {
     pub async fn start_sync(&mut self, current_head: H256, sync_head: H256, store: Store) {
         info!("Syncing from current head {current_head} to sync_head {sync_head}");
         let start_time = Instant::now();
-        match self.sync_cycle(current_head, sync_head, store).await {
+        let starting_block = store
+            .get_block_number(current_head)
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        self.status.start(starting_block);
+        // Don't let the state be pruned while it is being synced
+        store.set_syncing(true);
+        let result = self
+            .sync_cycle(current_head, sync_head, store.clone())
+            .await;
+        store.set_syncing(false);
+        self.status.finish();
+        match result {
             Ok(()) => {
                 info!(
                     "Sync finished, time elapsed: {} secs",
@@ -76,86 +182,250 @@ impl SyncManager {
     /// Performs the sync cycle described in `start_sync`, returns an error if the sync fails at any given step and aborts all active processes
     async fn sync_cycle(
         &mut self,
-        mut current_head: H256,
+        current_head: H256,
         sync_head: H256,
         store: Store,
     ) -> Result<(), SyncError> {
-        // Request all block headers between the current head and the sync head
-        // We will begin from the current head so that we download the earliest state first
-        // This step is not parallelized
-        let mut all_block_headers = vec![];
-        let mut all_block_hashes = vec![];
+        // Request all block headers between our local chain and the sync head
+        // Headers are downloaded backwards from the sync head (which was given to us by the consensus client),
+        // so that each of them is verified to be its ancestor, until we reach a block of our local chain
+        // Downloaded headers are kept in the store, so that they don't have to be requested again if the sync is interrupted
+        let current_number = store.get_block_number(current_head)?.unwrap_or_default();
+        // Snap-sync keeps a checkpoint until it is complete, so an interrupted snap-sync must be resumed as such
+        if store.get_header_download_checkpoint()?.is_some() {
+            info!("Resuming interrupted snap-sync");
+            self.sync_mode = SyncMode::Snap;
+        }
+        let sync_head_header = match store.get_block_header_by_hash(sync_head)? {
+            Some(header) => header,
+            None => downloader::download_header(&self.peers, sync_head)
+                .await
+                .ok_or(SyncError::Download("sync head header"))?,
+        };
+        if is_local_block(sync_head_header.number, sync_head, current_number, &store)? {
+            return Ok(());
+        }
+        self.status
+            .update(|progress| progress.highest_block = sync_head_header.number);
+        let mut all_block_headers = vec![sync_head_header];
+        let mut all_block_hashes = vec![sync_head];
+        let mut empty_downloads = 0;
         loop {
-            let peer = self.peers.lock().await.get_peer_channels().await;
-            debug!("Requesting Block Headers from {current_head}");
-            // Request Block Headers from Peer
-            if let Some(block_headers) = peer.request_block_headers(current_head).await {
-                debug!("Received {} block headers", block_headers.len());
-                let block_hashes = block_headers
-                    .iter()
-                    .map(|header| header.compute_block_hash())
-                    .collect::<Vec<_>>();
-                // Keep headers so we can process them later
-                // Discard the first header as we already have it
-                all_block_headers.extend_from_slice(&block_headers[1..]);
-                all_block_hashes.extend_from_slice(&block_hashes[1..]);
-
-                // Check if we already reached our sync head or if we need to fetch more blocks
-                if !block_hashes.contains(&sync_head) {
-                    // Update the request to fetch the next batch
-                    current_head = *block_hashes.last().unwrap();
-                } else {
-                    // No more headers to request
-                    break;
+            // Headers are never empty
+            let lowest_header = all_block_headers.last().unwrap().clone();
+            let Some(parent_number) = lowest_header.number.checked_sub(1) else {
+                return Err(SyncError::UnknownSyncHead);
+            };
+            if is_local_block(
+                parent_number,
+                lowest_header.parent_hash,
+                current_number,
+                &store,
+            )? {
+                break;
+            }
+            // Block 1 must be a child of our genesis block
+            if parent_number == 0 {
+                return Err(SyncError::UnknownSyncHead);
+            }
+            // Headers downloaded by an interrupted sync are already stored
+            if let Some(parent_header) =
+                store.get_block_header_by_hash(lowest_header.parent_hash)?
+            {
+                all_block_headers.push(parent_header);
+                all_block_hashes.push(lowest_header.parent_hash);
+                continue;
+            }
+            debug!(
+                "Requesting Block Headers before {}",
+                all_block_hashes.last().unwrap()
+            );
+            // Blocks above our local head can't be part of our chain, so there is no need to request further back
+            // If the parent is not above our head then the chain forked from ours earlier and we have to keep looking for the fork point
+            let lowest_number = if parent_number > current_number {
+                current_number + 1
+            } else {
+                1
+            };
+            let block_headers =
+                downloader::download_headers_backwards(&self.peers, &lowest_header, lowest_number)
+                    .await;
+            debug!("Received {} block headers", block_headers.len());
+            if block_headers.is_empty() {
+                empty_downloads += 1;
+                if empty_downloads >= MAX_HEADER_DOWNLOAD_RETRIES {
+                    return Err(SyncError::Download("block headers"));
                 }
+                // Give the peers some time to learn about the blocks before asking again
+                sleep(HEADER_DOWNLOAD_BACKOFF * 2u32.pow(empty_downloads - 1)).await;
+                continue;
+            }
+            empty_downloads = 0;
+            add_headers_until_local_chain(
+                block_headers,
+                current_number,
+                &store,
+                &mut all_block_headers,
+                &mut all_block_hashes,
+            )?;
+            if matches!(self.sync_mode, SyncMode::Snap) {
+                store.set_header_download_checkpoint(*all_block_hashes.last().unwrap())?;
             }
         }
+        all_block_headers.reverse();
+        all_block_hashes.reverse();
         // We finished fetching all headers, now we can process them
         match self.sync_mode {
             SyncMode::Snap => {
-                // snap-sync: launch tasks to fetch blocks and state in parallel
-                // - Fetch each block's state via snap p2p requests
-                // - Fetch each blocks and its receipts via eth p2p requests
-                // TODO: We are currently testing against our implementation that doesn't hold an independant snapshot and can provide all historic state
-                //       We should fetch all available state and then resort to state healing to fetch the rest
+                // snap-sync: choose a pivot block near the sync head and fetch its state via snap p2p requests,
+                // while the bodies of the blocks up to the pivot are fetched in parallel via eth p2p requests
+                // The pivot's state is first downloaded in ranges and then healed until its trie is complete,
+                // the pivot is moved forward whenever peers stop serving its state
+                // The blocks after the pivot are then executed as in full-sync
+                // Progress is checkpointed in the store along the way, so that an interrupted sync can be resumed
+                if all_block_headers.is_empty() {
+                    return Ok(());
+                }
+                // Make the downloaded headers canonical
+                for (header, hash) in all_block_headers.iter().zip(all_block_hashes.iter()) {
+                    store.set_canonical_block(header.number, *hash)?;
+                }
+                // Keep the pivot of the interrupted sync (if any) as long as it is still among the headers
+                let stored_pivot = store
+                    .get_pivot_block_number()?
+                    .and_then(|number| number.checked_sub(all_block_headers[0].number))
+                    .map(|index| index as usize)
+                    .filter(|index| *index < all_block_headers.len());
+                let mut pivot = stored_pivot
+                    .unwrap_or(all_block_headers.len().saturating_sub(MIN_FULL_BLOCKS + 1));
+                // The healing frontier is only valid for the state root it was taken from
+                let mut state_heal_paths = store.get_state_heal_paths()?;
+                if stored_pivot.is_none() && state_heal_paths.is_some() {
+                    state_heal_paths = Some(vec![]);
+                    store.set_state_heal_paths(vec![])?;
+                }
+                store.set_pivot_block_number(all_block_headers[pivot].number)?;
                 let (bytecode_sender, bytecode_receiver) = mpsc::channel::<Vec<H256>>(500);
                 let mut set = tokio::task::JoinSet::new();
                 set.spawn(bytecode_fetcher(
                     bytecode_receiver,
                     self.peers.clone(),
                     store.clone(),
+                    self.status.clone(),
                 ));
-                set.spawn(fetch_blocks_and_receipts(
-                    all_block_hashes.clone(),
-                    self.peers.clone(),
-                    store.clone(),
-                ));
-                let state_roots = all_block_headers
+                // Skip the bodies fetched before the sync was interrupted
+                let mut missing_bodies = vec![];
+                for (header, hash) in all_block_headers[..=pivot]
                     .iter()
-                    .map(|header| header.state_root)
-                    .collect::<Vec<_>>();
-                set.spawn(fetch_snap_state(
-                    bytecode_sender,
-                    state_roots.clone(),
+                    .zip(all_block_hashes.iter())
+                {
+                    if store.get_block_body_by_hash(*hash)?.is_none() {
+                        missing_bodies.push(header.clone());
+                    }
+                }
+                if !missing_bodies.is_empty() {
+                    set.spawn(fetch_blocks_and_receipts(
+                        missing_bodies,
+                        self.peers.clone(),
+                        store.clone(),
+                    ));
+                }
+                // Queue the storages and bytecodes left pending by the interrupted sync
+                let mut storages_to_heal = store.get_storage_heal_paths()?.unwrap_or_default();
+                let mut pending_bytecodes = store.get_pending_bytecodes()?.unwrap_or_default();
+                if !pending_bytecodes.is_empty() {
+                    bytecode_sender.send(pending_bytecodes.clone()).await?;
+                }
+                // Fetch the state in ranges, unless this was already done before the sync was interrupted
+                if state_heal_paths.is_none() {
+                    let mut account_ranges = store
+                        .get_state_trie_ranges_checkpoint()?
+                        .map(|(next_account, partial_root)| AccountRangesProgress {
+                            next_account,
+                            partial_root,
+                        })
+                        .unwrap_or_default();
+                    while !rebuild_state_trie(
+                        bytecode_sender.clone(),
+                        all_block_headers[pivot].state_root,
+                        &mut account_ranges,
+                        &mut storages_to_heal,
+                        &mut pending_bytecodes,
+                        self.peers.clone(),
+                        store.clone(),
+                        self.status.clone(),
+                    )
+                    .await?
+                    {
+                        pivot = self
+                            .update_pivot(
+                                pivot,
+                                &mut all_block_headers,
+                                &mut all_block_hashes,
+                                &store,
+                                &mut set,
+                            )
+                            .await?;
+                    }
+                    // Mark the range download as complete, healing will start from the trie's root
+                    store.set_state_heal_paths(vec![])?;
+                }
+                // Heal the state until the pivot's state trie is complete
+                let mut state_heal_paths = state_heal_paths.unwrap_or_default();
+                self.status.update(|progress| progress.healing = true);
+                while !heal_state(
+                    bytecode_sender.clone(),
+                    all_block_headers[pivot].state_root,
+                    &mut state_heal_paths,
+                    &mut storages_to_heal,
+                    &mut pending_bytecodes,
                     self.peers.clone(),
                     store.clone(),
-                ));
-                // Store headers
-                let mut latest_block_number = 0;
-                for (header, hash) in all_block_headers
-                    .into_iter()
-                    .zip(all_block_hashes.into_iter())
+                    &self.status,
+                )
+                .await?
                 {
-                    latest_block_number = header.number;
-                    store.set_canonical_block(header.number, hash)?;
-                    store.add_block_header(hash, header)?;
+                    // Discard the frontier of the stale state root before moving the pivot
+                    store.set_state_heal_paths(vec![])?;
+                    pivot = self
+                        .update_pivot(
+                            pivot,
+                            &mut all_block_headers,
+                            &mut all_block_hashes,
+                            &store,
+                            &mut set,
+                        )
+                        .await?;
                 }
+                // We finished syncing the state, lets make the fetcher processes aware
+                // Send empty batches to signal that no more batches are incoming
+                bytecode_sender.send(vec![]).await?;
                 // If all processes failed then they are likely to have a common cause (such as unaccessible storage), so return the first error
                 for result in set.join_all().await {
                     result?;
                 }
+                self.status.update(|progress| progress.healing = false);
                 // Set latest block number here to avoid reading state that is currently being synced
-                store.update_latest_block_number(latest_block_number)?;
+                let pivot_number = all_block_headers[pivot].number;
+                store.update_latest_block_number(pivot_number)?;
+                // The state is complete so there is nothing left to resume
+                store.clear_snap_state()?;
+                self.status
+                    .update(|progress| progress.current_block = pivot_number);
+                info!("Finished syncing the state of pivot block {pivot_number}");
+                // Execute the blocks after the pivot
+                let block_hashes = all_block_hashes.split_off(pivot + 1);
+                let block_headers = all_block_headers.split_off(pivot + 1);
+                if !block_hashes.is_empty() {
+                    download_and_run_blocks(
+                        block_hashes,
+                        block_headers,
+                        self.peers.clone(),
+                        store.clone(),
+                        &self.status,
+                    )
+                    .await?
+                }
             }
             SyncMode::Full => {
                 // full-sync: Fetch all block bodies and execute them sequentially to build the state
@@ -164,114 +434,199 @@ impl SyncManager {
                     all_block_headers,
                     self.peers.clone(),
                     store.clone(),
+                    &self.status,
                 )
                 .await?
             }
         }
         Ok(())
     }
+
+    /// Moves the snap-sync pivot forward after its state went stale, fetching the headers of any blocks newer than the ones we know of
+    /// The bodies of the blocks skipped by the new pivot are fetched in the background as part of the given `set`
+    /// Returns the index of the new pivot within the block headers, which may be the same one if no newer blocks were found
+    async fn update_pivot(
+        &self,
+        pivot: usize,
+        block_headers: &mut Vec<BlockHeader>,
+        block_hashes: &mut Vec<BlockHash>,
+        store: &Store,
+        set: &mut JoinSet<Result<(), SyncError>>,
+    ) -> Result<usize, SyncError> {
+        // Headers are not empty during snap-sync
+        let latest_hash = *block_hashes.last().unwrap();
+        let latest_number = block_headers.last().unwrap().number;
+        debug!("Requesting Block Headers after {latest_hash} to update the pivot");
+        let new_headers =
+            downloader::download_headers(&self.peers, latest_hash, latest_number).await;
+        if !new_headers.is_empty() {
+            for header in new_headers {
+                let hash = header.compute_block_hash();
+                store.set_canonical_block(header.number, hash)?;
+                store.add_block_header(hash, header.clone())?;
+                self.status
+                    .update(|progress| progress.highest_block = header.number);
+                block_headers.push(header);
+                block_hashes.push(hash);
+            }
+            store.set_header_download_checkpoint(*block_hashes.last().unwrap())?;
+        }
+        let new_pivot = block_headers
+            .len()
+            .saturating_sub(MIN_FULL_BLOCKS + 1)
+            .max(pivot);
+        if new_pivot > pivot {
+            info!(
+                "Pivot block {} went stale, moving pivot to block {}",
+                block_headers[pivot].number, block_headers[new_pivot].number
+            );
+            set.spawn(fetch_blocks_and_receipts(
+                block_headers[pivot + 1..=new_pivot].to_vec(),
+                self.peers.clone(),
+                store.clone(),
+            ));
+            store.set_pivot_block_number(block_headers[new_pivot].number)?;
+        }
+        Ok(new_pivot)
+    }
+}
+
+/// Returns true if the block is part of our local chain, which ends at the given block number
+fn is_local_block(
+    number: BlockNumber,
+    hash: BlockHash,
+    local_number: BlockNumber,
+    store: &Store,
+) -> Result<bool, StoreError> {
+    Ok(number <= local_number && is_canonical(store, number, hash)?)
+}
+
+/// Stores the downloaded headers (ordered from newest to oldest) and adds them to the ones being synced
+/// Stops at the first header whose parent is part of our local chain, as we already have the blocks before it
+fn add_headers_until_local_chain(
+    block_headers: Vec<BlockHeader>,
+    current_number: BlockNumber,
+    store: &Store,
+    all_block_headers: &mut Vec<BlockHeader>,
+    all_block_hashes: &mut Vec<BlockHash>,
+) -> Result<(), StoreError> {
+    for header in block_headers {
+        let hash = header.compute_block_hash();
+        let reached_local_chain = is_local_block(
+            header.number.saturating_sub(1),
+            header.parent_hash,
+            current_number,
+            store,
+        )?;
+        store.add_block_header(hash, header.clone())?;
+        all_block_headers.push(header);
+        all_block_hashes.push(hash);
+        if reached_local_chain {
+            break;
+        }
+    }
+    Ok(())
 }
 
 /// Requests block bodies from peers via p2p, executes and stores them
-/// Returns an error if there was a problem while executing or validating the blocks
+/// Returns an error if the blocks couldn't be downloaded or there was a problem while executing or validating them
 async fn download_and_run_blocks(
     mut block_hashes: Vec<BlockHash>,
     mut block_headers: Vec<BlockHeader>,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
-) -> Result<(), ChainError> {
-    loop {
-        let peer = peers.lock().await.get_peer_channels().await;
-        debug!("Requesting Block Bodies ");
-        if let Some(block_bodies) = peer.request_block_bodies(block_hashes.clone()).await {
-            let block_bodies_len = block_bodies.len();
-            debug!("Received {} Block Bodies", block_bodies_len);
-            // Execute and store blocks
-            for body in block_bodies.into_iter() {
-                // We already validated that there are no more block bodies than the ones requested
-                let header = block_headers.remove(0);
-                let hash = block_hashes.remove(0);
-                let number = header.number;
-                let block = Block::new(header, body);
-                if let Err(error) = ethrex_blockchain::add_block(&block, &store) {
-                    warn!("Failed to add block during FullSync: {error}");
-                    return Err(error);
-                }
-                store.set_canonical_block(number, hash)?;
-                store.update_latest_block_number(number)?;
-            }
-            debug!("Executed & stored {} blocks", block_bodies_len);
-            // Check if we need to ask for another batch
-            if block_hashes.is_empty() {
-                break;
+    status: &SyncStatus,
+) -> Result<(), SyncError> {
+    while !block_headers.is_empty() {
+        let batch_len = BLOCK_DOWNLOAD_WINDOW.min(block_headers.len());
+        let batch_headers: Vec<_> = block_headers.drain(..batch_len).collect();
+        let batch_hashes: Vec<_> = block_hashes.drain(..batch_len).collect();
+        debug!("Requesting {batch_len} Block Bodies");
+        let block_bodies = downloader::download_bodies(&peers, &batch_headers)
+            .await
+            .ok_or(SyncError::Download("block bodies"))?;
+        // Execute and store blocks
+        for ((header, hash), body) in batch_headers
+            .into_iter()
+            .zip(batch_hashes)
+            .zip(block_bodies)
+        {
+            let number = header.number;
+            let block = Block::new(header, body);
+            if let Err(error) = ethrex_blockchain::add_block(&block, &store) {
+                warn!("Failed to add block during FullSync: {error}");
+                return Err(error.into());
             }
+            store.set_canonical_block(number, hash)?;
+            store.update_latest_block_number(number)?;
+            status.update(|progress| progress.current_block = number);
         }
+        debug!("Executed & stored {batch_len} blocks");
     }
     Ok(())
 }
 
+/// Requests the bodies and receipts of the given blocks from peers via p2p and stores them
 async fn fetch_blocks_and_receipts(
-    mut block_hashes: Vec<BlockHash>,
+    block_headers: Vec<BlockHeader>,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
 ) -> Result<(), SyncError> {
     // Snap state fetching will take much longer than this so we don't need to paralelize fetching blocks and receipts
-    // Fetch Block Bodies
-    loop {
-        let peer = peers.lock().await.get_peer_channels().await;
-        debug!("Requesting Block Headers ");
-        if let Some(block_bodies) = peer.request_block_bodies(block_hashes.clone()).await {
-            debug!(" Received {} Block Bodies", block_bodies.len());
-            // Track which bodies we have already fetched
-            let (fetched_hashes, remaining_hashes) = block_hashes.split_at(block_bodies.len());
-            // Store Block Bodies
-            for (hash, body) in fetched_hashes.iter().zip(block_bodies.into_iter()) {
-                store.add_block_body(*hash, body)?
-            }
-
-            // Check if we need to ask for another batch
-            if remaining_hashes.is_empty() {
-                break;
-            } else {
-                block_hashes = remaining_hashes.to_vec();
-            }
+    for batch_headers in block_headers.chunks(BLOCK_DOWNLOAD_WINDOW) {
+        debug!("Requesting {} Block Bodies", batch_headers.len());
+        let block_bodies = downloader::download_bodies(&peers, batch_headers)
+            .await
+            .ok_or(SyncError::Download("block bodies"))?;
+        debug!("Requesting {} Receipts", batch_headers.len());
+        let receipts = downloader::download_receipts(&peers, batch_headers)
+            .await
+            .ok_or(SyncError::Download("receipts"))?;
+        // Store the receipts before the body, as bodies are used to tell which blocks were already fetched
+        for ((header, body), receipts) in batch_headers.iter().zip(block_bodies).zip(receipts) {
+            let hash = header.compute_block_hash();
+            store.add_receipts(hash, receipts)?;
+            store.add_block_body(hash, body)?;
         }
     }
-    // TODO: Fetch Receipts and store them
     Ok(())
 }
 
-async fn fetch_snap_state(
-    bytecode_sender: Sender<Vec<H256>>,
-    state_roots: Vec<BlockHash>,
-    peers: Arc<Mutex<KademliaTable>>,
-    store: Store,
-) -> Result<(), SyncError> {
-    debug!("Syncing state roots: {}", state_roots.len());
-    // Fetch newer state first: This will be useful to detect where to switch to healing
-    for state_root in state_roots.into_iter().rev() {
-        // TODO: maybe spawn taks here instead of awaiting
-        rebuild_state_trie(
-            bytecode_sender.clone(),
-            state_root,
-            peers.clone(),
-            store.clone(),
-        )
-        .await?
+/// Progress of the download of the pivot's account ranges, kept across pivot changes
+struct AccountRangesProgress {
+    /// Hash of the account to start the next range request from
+    next_account: H256,
+    /// Root of the state trie built from the ranges downloaded so far
+    partial_root: H256,
+}
+
+impl Default for AccountRangesProgress {
+    fn default() -> Self {
+        Self {
+            next_account: H256::zero(),
+            partial_root: *EMPTY_TRIE_HASH,
+        }
     }
-    // We finished syncing the available state, lets make the fetcher processes aware
-    // Send empty batches to signal that no more batches are incoming
-    bytecode_sender.send(vec![]).await?;
-    Ok(())
 }
 
-/// Rebuilds a Block's state trie by requesting snap state from peers
+/// Rebuilds a Block's state trie by requesting snap account ranges from peers, along with the storage and bytecode of each account
+/// The download resumes from the given range progress, which may have been made while fetching a different state root
+/// The resulting trie can still have missing nodes (as ranges could have been fetched from different state roots), so it must be healed afterwards
+/// Accounts whose storage couldn't be fetched before the state root went stale are added to `storages_to_heal`
+/// Code hashes sent to the bytecode fetcher are added to `pending_bytecodes`
+/// The range progress is checkpointed in the store after each range, along with the storages and bytecodes yet to be fetched
+/// Returns false if the state root went stale before all account ranges were fetched
+#[allow(clippy::too_many_arguments)]
 async fn rebuild_state_trie(
     bytecode_sender: Sender<Vec<H256>>,
     state_root: H256,
+    ranges: &mut AccountRangesProgress,
+    storages_to_heal: &mut Vec<H256>,
+    pending_bytecodes: &mut Vec<H256>,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
-) -> Result<(), SyncError> {
+    status: SyncStatus,
+) -> Result<bool, SyncError> {
     // Spawn a storage fetcher for this blocks's storage
     let (storage_sender, storage_receiver) = mpsc::channel::<Vec<(H256, H256)>>(500);
     let storage_fetcher_handler = tokio::spawn(storage_fetcher(
@@ -279,83 +634,308 @@ async fn rebuild_state_trie(
         peers.clone(),
         store.clone(),
         state_root,
+        status.clone(),
     ));
-    let mut start_account_hash = H256::zero();
-    // Start from an empty state trie
-    // We cannot keep an open trie here so we will track the root between lookups
-    let mut current_state_root = *EMPTY_TRIE_HASH;
+    // Storages sent to the storage fetcher that may not have been fetched yet
+    let mut pending_storages = vec![];
+    let mut complete = false;
+    let mut retries = 0;
     // Fetch Account Ranges
-    loop {
+    while retries < MAX_RETRIES {
         let peer = peers.clone().lock().await.get_peer_channels().await;
-        debug!("Requesting Account Range for state root {state_root}, starting hash: {start_account_hash}");
-        if let Some((account_hashes, accounts, should_continue)) = peer
-            .request_account_range(state_root, start_account_hash)
+        debug!(
+            "Requesting Account Range for state root {state_root}, starting hash: {}",
+            ranges.next_account
+        );
+        let Some((account_hashes, accounts, should_continue)) = peer
+            .request_account_range(state_root, ranges.next_account)
             .await
-        {
+        else {
+            retries += 1;
+            continue;
+        };
+        retries = 0;
+        // Fetch Account Storage & Bytecode
+        let mut code_hashes = vec![];
+        let mut account_hashes_and_storage_roots = vec![];
+        for (account_hash, account) in account_hashes.iter().zip(accounts.iter()) {
+            // Build the batch of code hashes to send to the bytecode fetcher
+            // Ignore accounts without code / code we already have stored
+            if account.code_hash != *EMPTY_KECCACK_HASH
+                && store.get_account_code(account.code_hash)?.is_none()
+            {
+                code_hashes.push(account.code_hash)
+            }
+            // Build the batch of hashes and roots to send to the storage fetcher
+            // Ignore accounts without storage and accounts whose storage we already have
+            if account.storage_root != *EMPTY_TRIE_HASH
+                && !TrieHealer::new(&store.open_storage_trie(*account_hash, account.storage_root))?
+                    .is_complete()
+            {
+                account_hashes_and_storage_roots.push((*account_hash, account.storage_root));
+            }
+        }
+        // Send code hash batch to the bytecode fetcher
+        if !code_hashes.is_empty() {
+            pending_bytecodes.extend_from_slice(&code_hashes);
+            bytecode_sender.send(code_hashes).await?;
+        }
+        // Send hash and root batch to the storage fetcher
+        if !account_hashes_and_storage_roots.is_empty() {
+            pending_storages.extend_from_slice(&account_hashes_and_storage_roots);
+            storage_sender
+                .send(account_hashes_and_storage_roots)
+                .await?;
+        }
+        // Update trie
+        // We cannot keep an open trie here so we will track the root between lookups
+        let mut trie = store.open_state_trie(ranges.partial_root);
+        let mut account_bytes = 0;
+        for (account_hash, account) in account_hashes.iter().zip(accounts.iter()) {
+            let encoded_account = account.encode_to_vec();
+            account_bytes += (account_hash.0.len() + encoded_account.len()) as u64;
+            trie.insert(account_hash.0.to_vec(), encoded_account)?;
+        }
+        ranges.partial_root = trie.hash()?;
+        status.update(|progress| {
+            progress.synced_accounts += accounts.len() as u64;
+            progress.synced_account_bytes += account_bytes;
+        });
+
+        if should_continue {
             // Update starting hash for next batch
-            if should_continue {
-                start_account_hash = *account_hashes.last().unwrap();
+            ranges.next_account = *account_hashes.last().unwrap();
+            // Checkpoint the range progress, the storages and bytecodes of the fetched accounts must be persisted first
+            save_pending_downloads(
+                storages_to_heal,
+                &mut pending_storages,
+                pending_bytecodes,
+                &store,
+            )?;
+            store.set_state_trie_ranges_checkpoint(ranges.next_account, ranges.partial_root)?;
+        } else {
+            // All accounts fetched!
+            complete = true;
+            break;
+        }
+    }
+    // Send empty batch to signal that no more batches are incoming
+    storage_sender.send(vec![]).await?;
+    let unfetched_storages = storage_fetcher_handler
+        .await
+        .map_err(|_| StoreError::Custom(String::from("Failed to join storage_fetcher task")))??;
+    storages_to_heal.extend(unfetched_storages);
+    save_pending_downloads(storages_to_heal, &mut vec![], pending_bytecodes, &store)?;
+    if complete {
+        debug!("Fetched all account ranges for state root {state_root}");
+    } else {
+        debug!("State root {state_root} went stale while fetching account ranges");
+    }
+    Ok(complete)
+}
+
+/// Heals the state trie with the given root by fetching its missing nodes from peers, along with the storage tries of its accounts
+/// and the storage tries of the accounts in `storages_to_heal`. Missing bytecodes are sent to the bytecode fetcher and added to `pending_bytecodes`
+/// Healing resumes from the given `frontier` (taken from a previous healing of the same state root), which is checkpointed in the store after each request
+/// Returns false if the state root went stale before the state was complete
+#[allow(clippy::too_many_arguments)]
+async fn heal_state(
+    bytecode_sender: Sender<Vec<H256>>,
+    state_root: H256,
+    frontier: &mut Vec<(Nibbles, H256)>,
+    storages_to_heal: &mut Vec<H256>,
+    pending_bytecodes: &mut Vec<H256>,
+    peers: Arc<Mutex<KademliaTable>>,
+    store: Store,
+    status: &SyncStatus,
+) -> Result<bool, SyncError> {
+    // We cannot keep an open trie across requests so we will reopen it each time we access it
+    let mut healer =
+        TrieHealer::resume(&store.open_state_trie(state_root), std::mem::take(frontier))?;
+    let mut retries = 0;
+    while !healer.is_complete() {
+        if retries >= MAX_RETRIES {
+            debug!("State root {state_root} went stale while healing the state trie");
+            return Ok(false);
+        }
+        status.update(|progress| progress.healing_trienodes = healer.pending_nodes() as u64);
+        let paths = healer.next_paths(NODE_BATCH_SIZE);
+        let peer = peers.lock().await.get_peer_channels().await;
+        debug!(
+            "Requesting {} state trie nodes for state root {state_root}",
+            paths.len()
+        );
+        let nodes = match peer
+            .request_state_trienodes(state_root, paths.clone())
+            .await
+        {
+            Some(nodes) => {
+                retries = 0;
+                record_healed_nodes(status, &nodes);
+                nodes.into_iter().map(|node| node.to_vec()).collect()
             }
-            // Fetch Account Storage & Bytecode
-            let mut code_hashes = vec![];
-            let mut account_hashes_and_storage_roots = vec![];
-            for (account_hash, account) in account_hashes.iter().zip(accounts.iter()) {
-                // Build the batch of code hashes to send to the bytecode fetcher
-                // Ignore accounts without code / code we already have stored
-                if account.code_hash != *EMPTY_KECCACK_HASH
-                    && store.get_account_code(account.code_hash)?.is_none()
-                {
-                    code_hashes.push(account.code_hash)
-                }
-                // Build the batch of hashes and roots to send to the storage fetcher
-                // Ignore accounts without storage
-                // TODO: We could also check if the account's storage root is already part of the trie
-                // Aka, if the account was not changed shouldn't fetch the state we already have
-                if account.storage_root != *EMPTY_TRIE_HASH {
-                    account_hashes_and_storage_roots.push((*account_hash, account.storage_root));
-                }
+            None => {
+                retries += 1;
+                vec![]
             }
-            // Send code hash batch to the bytecode fetcher
-            if !code_hashes.is_empty() {
-                bytecode_sender.send(code_hashes).await?;
+        };
+        // Accounts can only be considered healed once their storage and bytecode are present too
+        let mut code_hashes = vec![];
+        let leaves = healer.process_nodes(&store.open_state_trie(state_root), paths, nodes)?;
+        for leaf in leaves {
+            let account = AccountState::decode(&leaf.value)?;
+            if leaf.key.len() != 32 {
+                return Err(SyncError::CorruptPath);
             }
-            // Send hash and root batch to the storage fetcher
-            if !account_hashes_and_storage_roots.is_empty() {
-                storage_sender
-                    .send(account_hashes_and_storage_roots)
-                    .await?;
+            let account_hash = H256::from_slice(&leaf.key);
+            if account.code_hash != *EMPTY_KECCACK_HASH
+                && store.get_account_code(account.code_hash)?.is_none()
+            {
+                code_hashes.push(account.code_hash);
             }
-            // Update trie
-            let mut trie = store.open_state_trie(current_state_root);
-            for (account_hash, account) in account_hashes.iter().zip(accounts.iter()) {
-                trie.insert(account_hash.0.to_vec(), account.encode_to_vec())
-                    .map_err(StoreError::Trie)?;
+            if account.storage_root != *EMPTY_TRIE_HASH
+                && !heal_storage(
+                    account_hash,
+                    account.storage_root,
+                    state_root,
+                    &peers,
+                    &store,
+                    status,
+                )
+                .await?
+            {
+                return Ok(false);
+            }
+            healer.release_leaf(&store.open_state_trie(state_root), &leaf.path)?;
+        }
+        if !code_hashes.is_empty() {
+            pending_bytecodes.extend_from_slice(&code_hashes);
+            bytecode_sender.send(code_hashes).await?;
+        }
+        save_pending_downloads(storages_to_heal, &mut vec![], pending_bytecodes, &store)?;
+        store.set_state_heal_paths(healer.frontier())?;
+    }
+    // Heal the storages that couldn't be fetched during the range download, using the storage roots of the complete state trie
+    while let Some(account_hash) = storages_to_heal.last() {
+        let account = store
+            .open_state_trie(state_root)
+            .get(&account_hash.0.to_vec())?
+            .map(|ref rlp| AccountState::decode(rlp))
+            .transpose()?;
+        if let Some(account) = account {
+            if account.storage_root != *EMPTY_TRIE_HASH
+                && !heal_storage(
+                    *account_hash,
+                    account.storage_root,
+                    state_root,
+                    &peers,
+                    &store,
+                    status,
+                )
+                .await?
+            {
+                return Ok(false);
             }
-            current_state_root = trie.hash().map_err(StoreError::Trie)?;
+        }
+        storages_to_heal.pop();
+        store.set_storage_heal_paths(storages_to_heal.clone())?;
+    }
+    status.update(|progress| progress.healing_trienodes = 0);
+    debug!("Healed state trie for state root {state_root}");
+    Ok(true)
+}
 
-            if !should_continue {
-                // All accounts fetched!
-                break;
+/// Heals an account's storage trie by fetching its missing nodes from peers
+/// Returns false if the state root went stale before the storage trie was complete
+async fn heal_storage(
+    account_hash: H256,
+    storage_root: H256,
+    state_root: H256,
+    peers: &Arc<Mutex<KademliaTable>>,
+    store: &Store,
+    status: &SyncStatus,
+) -> Result<bool, SyncError> {
+    let mut healer = TrieHealer::new(&store.open_storage_trie(account_hash, storage_root))?;
+    let mut retries = 0;
+    while !healer.is_complete() {
+        if retries >= MAX_RETRIES {
+            return Ok(false);
+        }
+        let paths = healer.next_paths(NODE_BATCH_SIZE);
+        let peer = peers.lock().await.get_peer_channels().await;
+        let nodes = match peer
+            .request_storage_trienodes(state_root, account_hash, paths.clone())
+            .await
+        {
+            Some(nodes) => {
+                retries = 0;
+                record_healed_nodes(status, &nodes);
+                nodes.into_iter().map(|node| node.to_vec()).collect()
+            }
+            None => {
+                retries += 1;
+                vec![]
             }
+        };
+        // Storage leaves don't reference any other data so they are complete as soon as they are fetched
+        let trie = store.open_storage_trie(account_hash, storage_root);
+        for leaf in healer.process_nodes(&trie, paths, nodes)? {
+            healer.release_leaf(&trie, &leaf.path)?;
+        }
+    }
+    Ok(true)
+}
+
+/// Checkpoints the storages and bytecodes that are yet to be fetched, so that they are not lost if the sync is interrupted
+/// The storages in `pending_storages` and the bytecodes in `pending_bytecodes` that were already fetched are removed from them
+fn save_pending_downloads(
+    storages_to_heal: &[H256],
+    pending_storages: &mut Vec<(H256, H256)>,
+    pending_bytecodes: &mut Vec<H256>,
+    store: &Store,
+) -> Result<(), SyncError> {
+    let mut unfetched_storages = vec![];
+    for (account_hash, storage_root) in pending_storages.drain(..) {
+        if !TrieHealer::new(&store.open_storage_trie(account_hash, storage_root))?.is_complete() {
+            unfetched_storages.push((account_hash, storage_root));
         }
     }
-    if current_state_root != state_root {
-        warn!("State sync failed for state root {state_root}");
+    *pending_storages = unfetched_storages;
+    let mut unfetched_bytecodes = vec![];
+    for code_hash in pending_bytecodes.drain(..) {
+        if store.get_account_code(code_hash)?.is_none() {
+            unfetched_bytecodes.push(code_hash);
+        }
     }
-    // Send empty batch to signal that no more batches are incoming
-    storage_sender.send(vec![]).await?;
-    storage_fetcher_handler
-        .await
-        .map_err(|_| StoreError::Custom(String::from("Failed to join storage_fetcher task")))??;
-    debug!("Completed state sync for state root {state_root}");
+    *pending_bytecodes = unfetched_bytecodes;
+    store.set_storage_heal_paths(
+        storages_to_heal
+            .iter()
+            .copied()
+            .chain(
+                pending_storages
+                    .iter()
+                    .map(|(account_hash, _)| *account_hash),
+            )
+            .collect(),
+    )?;
+    store.set_pending_bytecodes(pending_bytecodes.clone())?;
     Ok(())
 }
 
+fn record_healed_nodes(status: &SyncStatus, nodes: &[Bytes]) {
+    status.update(|progress| {
+        progress.healed_trienodes += nodes.len() as u64;
+        progress.healed_trienode_bytes += nodes.iter().map(|node| node.len() as u64).sum::<u64>();
+    });
+}
+
 /// Waits for incoming code hashes from the receiver channel endpoint, queues them, and fetches and stores their bytecodes in batches
 async fn bytecode_fetcher(
     mut receiver: Receiver<Vec<H256>>,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
+    status: SyncStatus,
 ) -> Result<(), SyncError> {
     const BATCH_SIZE: usize = 200;
     // Pending list of bytecodes to fetch
@@ -365,13 +945,16 @@ async fn bytecode_fetcher(
             Some(code_hashes) if !code_hashes.is_empty() => {
                 // Add hashes to the queue
                 pending_bytecodes.extend(code_hashes);
+                record_pending_bytecodes(&status, pending_bytecodes.len());
                 // If we have enought pending bytecodes to fill a batch, spawn a fetch process
                 while pending_bytecodes.len() >= BATCH_SIZE {
                     let next_batch = pending_bytecodes.drain(..BATCH_SIZE).collect::<Vec<_>>();
                     let remaining =
-                        fetch_bytecode_batch(next_batch, peers.clone(), store.clone()).await?;
+                        fetch_bytecode_batch(next_batch, peers.clone(), store.clone(), &status)
+                            .await?;
                     // Add unfeched bytecodes back to the queue
                     pending_bytecodes.extend(remaining);
+                    record_pending_bytecodes(&status, pending_bytecodes.len());
                 }
             }
             // Disconnect / Empty message signaling no more bytecodes to sync
@@ -383,23 +966,43 @@ async fn bytecode_fetcher(
         let next_batch = pending_bytecodes
             .drain(..BATCH_SIZE.min(pending_bytecodes.len()))
             .collect::<Vec<_>>();
-        let remaining = fetch_bytecode_batch(next_batch, peers.clone(), store.clone()).await?;
+        let remaining =
+            fetch_bytecode_batch(next_batch, peers.clone(), store.clone(), &status).await?;
         // Add unfeched bytecodes back to the queue
         pending_bytecodes.extend(remaining);
+        record_pending_bytecodes(&status, pending_bytecodes.len());
     }
     Ok(())
 }
 
+/// Reports the queued bytecodes as pending to be healed if the state is being healed
+fn record_pending_bytecodes(status: &SyncStatus, pending: usize) {
+    status.update(|progress| {
+        progress.healing_bytecodes = if progress.healing { pending as u64 } else { 0 };
+    });
+}
+
 /// Receives a batch of code hahses, fetches their respective bytecodes via p2p and returns a list of the code hashes that couldn't be fetched in the request (if applicable)
 async fn fetch_bytecode_batch(
     mut batch: Vec<H256>,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
+    status: &SyncStatus,
 ) -> Result<Vec<H256>, StoreError> {
     loop {
         let peer = peers.lock().await.get_peer_channels().await;
         if let Some(bytecodes) = peer.request_bytecodes(batch.clone()).await {
             debug!("Received {} bytecodes", bytecodes.len());
+            let bytes = bytecodes.iter().map(|code| code.len() as u64).sum::<u64>();
+            status.update(|progress| {
+                if progress.healing {
+                    progress.healed_bytecodes += bytecodes.len() as u64;
+                    progress.healed_bytecode_bytes += bytes;
+                } else {
+                    progress.synced_bytecodes += bytecodes.len() as u64;
+                    progress.synced_bytecode_bytes += bytes;
+                }
+            });
             // Store the bytecodes
             for code in bytecodes.into_iter() {
                 store.add_account_code(batch.remove(0), code)?;
@@ -410,58 +1013,78 @@ async fn fetch_bytecode_batch(
     }
 }
 
-/// Waits for incoming account hashes & storage roots from the receiver channel endpoint, queues them, and fetches and stores their bytecodes in batches
+/// Waits for incoming account hashes & storage roots from the receiver channel endpoint, queues them, and fetches and stores their storages in batches
+/// Returns the hashes of the accounts whose storage couldn't be fetched as the state root went stale
 async fn storage_fetcher(
     mut receiver: Receiver<Vec<(H256, H256)>>,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
     state_root: H256,
-) -> Result<(), StoreError> {
+    status: SyncStatus,
+) -> Result<Vec<H256>, StoreError> {
     const BATCH_SIZE: usize = 100;
-    // Pending list of bytecodes to fetch
+    // Pending list of storages to fetch
     let mut pending_storage: Vec<(H256, H256)> = vec![];
-    // TODO: Also add a queue for storages that were incompletely fecthed,
-    // but for the first iteration we will asume not fully fetched -> fetch again
+    // Once the state root goes stale we stop fetching and only keep track of the incoming accounts
+    let mut stale = false;
     loop {
         match receiver.recv().await {
             Some(account_and_root) if !account_and_root.is_empty() => {
                 // Add hashes to the queue
                 pending_storage.extend(account_and_root);
-                // If we have enought pending bytecodes to fill a batch, spawn a fetch process
-                while pending_storage.len() >= BATCH_SIZE {
+                // If we have enought pending storages to fill a batch, spawn a fetch process
+                while !stale && pending_storage.len() >= BATCH_SIZE {
                     let next_batch = pending_storage.drain(..BATCH_SIZE).collect::<Vec<_>>();
-                    let remaining =
-                        fetch_storage_batch(next_batch, state_root, peers.clone(), store.clone())
-                            .await?;
-                    // Add unfeched bytecodes back to the queue
+                    let (remaining, is_stale) = fetch_storage_batch(
+                        next_batch,
+                        state_root,
+                        peers.clone(),
+                        store.clone(),
+                        &status,
+                    )
+                    .await?;
+                    // Add unfeched storages back to the queue
                     pending_storage.extend(remaining);
+                    stale |= is_stale;
                 }
             }
-            // Disconnect / Empty message signaling no more bytecodes to sync
+            // Disconnect / Empty message signaling no more storages to sync
             _ => break,
         }
     }
     // We have no more incoming requests, process the remaining batches
-    while !pending_storage.is_empty() {
+    while !stale && !pending_storage.is_empty() {
         let next_batch = pending_storage
             .drain(..BATCH_SIZE.min(pending_storage.len()))
             .collect::<Vec<_>>();
-        let remaining =
-            fetch_storage_batch(next_batch, state_root, peers.clone(), store.clone()).await?;
-        // Add unfeched bytecodes back to the queue
+        let (remaining, is_stale) = fetch_storage_batch(
+            next_batch,
+            state_root,
+            peers.clone(),
+            store.clone(),
+            &status,
+        )
+        .await?;
+        // Add unfeched storages back to the queue
         pending_storage.extend(remaining);
+        stale |= is_stale;
     }
-    Ok(())
+    Ok(pending_storage
+        .into_iter()
+        .map(|(account_hash, _)| account_hash)
+        .collect())
 }
 
-/// Receives a batch of account hashes with their storage roots, fetches their respective storage ranges via p2p and returns a list of the code hashes that couldn't be fetched in the request (if applicable)
+/// Receives a batch of account hashes with their storage roots, fetches their respective storage ranges via p2p and returns a list of the accounts that couldn't be fetched in the request (if applicable)
+/// Also returns true if the state root went stale, in which case no more requests should be made for it
 async fn fetch_storage_batch(
     mut batch: Vec<(H256, H256)>,
     state_root: H256,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
-) -> Result<Vec<(H256, H256)>, StoreError> {
-    loop {
+    status: &SyncStatus,
+) -> Result<(Vec<(H256, H256)>, bool), StoreError> {
+    for _ in 0..MAX_RETRIES {
         let peer = peers.lock().await.get_peer_channels().await;
         let (batch_hahses, batch_roots) = batch.clone().into_iter().unzip();
         if let Some((mut keys, mut values, incomplete)) = peer
@@ -469,29 +1092,117 @@ async fn fetch_storage_batch(
             .await
         {
             debug!("Received {} storage ranges", keys.len());
-            let mut _last_range;
-            // Hold on to the last batch (if incomplete)
-            if incomplete {
-                // An incomplete range cannot be empty
-                _last_range = (keys.pop().unwrap(), values.pop().unwrap());
-            }
+            // Hold on to the last range (if incomplete)
+            // An incomplete range cannot be empty
+            let last_range = incomplete.then(|| (keys.pop().unwrap(), values.pop().unwrap()));
             // Store the storage ranges & rebuild the storage trie for each account
             for (keys, values) in keys.into_iter().zip(values.into_iter()) {
                 let (account_hash, storage_root) = batch.remove(0);
-                let mut trie = store.open_storage_trie(account_hash, *EMPTY_TRIE_HASH);
-                for (key, value) in keys.into_iter().zip(values.into_iter()) {
-                    trie.insert(key.0.to_vec(), value.encode_to_vec())?;
-                }
-                if trie.hash()? != storage_root {
+                let root = insert_storage_range(
+                    account_hash,
+                    *EMPTY_TRIE_HASH,
+                    keys,
+                    values,
+                    &store,
+                    status,
+                )?;
+                if root != storage_root {
                     warn!("State sync failed for storage root {storage_root}");
                 }
             }
-            // TODO: if the last range is incomplete add it to the incomplete batches queue
-            // For now we will fetch the full range again
-            // Return remaining code hashes in the batch if we couldn't fetch all of them
-            return Ok(batch);
+            // Keep fetching the last account's storage until it is complete
+            if let Some((keys, values)) = last_range {
+                let (account_hash, storage_root) = batch[0];
+                if !fetch_large_storage(
+                    account_hash,
+                    storage_root,
+                    keys,
+                    values,
+                    state_root,
+                    &peers,
+                    &store,
+                    status,
+                )
+                .await?
+                {
+                    return Ok((batch, true));
+                }
+                batch.remove(0);
+            }
+            // Return remaining accounts in the batch if we couldn't fetch all of them
+            return Ok((batch, false));
         }
     }
+    Ok((batch, true))
+}
+
+/// Fetches the rest of an account's storage that didn't fit in a single storage range response, starting after the already fetched first range
+/// Returns false if the state root went stale before the storage was complete
+#[allow(clippy::too_many_arguments)]
+async fn fetch_large_storage(
+    account_hash: H256,
+    storage_root: H256,
+    keys: Vec<H256>,
+    values: Vec<U256>,
+    state_root: H256,
+    peers: &Arc<Mutex<KademliaTable>>,
+    store: &Store,
+    status: &SyncStatus,
+) -> Result<bool, StoreError> {
+    // An incomplete range cannot be empty
+    let mut next_key = *keys.last().unwrap();
+    let mut current_root =
+        insert_storage_range(account_hash, *EMPTY_TRIE_HASH, keys, values, store, status)?;
+    let mut retries = 0;
+    while retries < MAX_RETRIES {
+        let peer = peers.lock().await.get_peer_channels().await;
+        debug!("Requesting Storage Range for account {account_hash}, starting key: {next_key}");
+        let Some((mut keys, mut values, incomplete)) = peer
+            .request_storage_ranges(state_root, vec![storage_root], vec![account_hash], next_key)
+            .await
+        else {
+            retries += 1;
+            continue;
+        };
+        retries = 0;
+        // We only requested one range, and it cannot be empty
+        let (keys, values) = (keys.remove(0), values.remove(0));
+        next_key = *keys.last().unwrap();
+        current_root =
+            insert_storage_range(account_hash, current_root, keys, values, store, status)?;
+        if !incomplete {
+            if current_root != storage_root {
+                warn!("State sync failed for storage root {storage_root}");
+            }
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Inserts a range of storage slots into an account's storage trie with the given root, returning the trie's new root
+fn insert_storage_range(
+    account_hash: H256,
+    storage_root: H256,
+    keys: Vec<H256>,
+    values: Vec<U256>,
+    store: &Store,
+    status: &SyncStatus,
+) -> Result<H256, StoreError> {
+    let mut trie = store.open_storage_trie(account_hash, storage_root);
+    let mut slots = 0;
+    let mut slot_bytes = 0;
+    for (key, value) in keys.into_iter().zip(values) {
+        let encoded_value = value.encode_to_vec();
+        slots += 1;
+        slot_bytes += (key.0.len() + encoded_value.len()) as u64;
+        trie.insert(key.0.to_vec(), encoded_value)?;
+    }
+    status.update(|progress| {
+        progress.synced_storage += slots;
+        progress.synced_storage_bytes += slot_bytes;
+    });
+    Ok(trie.hash()?)
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -501,7 +1212,73 @@ enum SyncError {
     #[error(transparent)]
     Store(#[from] StoreError),
     #[error(transparent)]
+    Trie(#[from] TrieError),
+    #[error(transparent)]
+    RLPDecode(#[from] RLPDecodeError),
+    #[error(transparent)]
     SendBytecode(#[from] SendError<Vec<H256>>),
     #[error(transparent)]
     SendStorage(#[from] SendError<Vec<(H256, H256)>>),
+    #[error("Corrupt path during state healing")]
+    CorruptPath,
+    #[error("Sync head is not a descendant of our genesis block")]
+    UnknownSyncHead,
+    #[error("No peer could provide the {0}")]
+    Download(&'static str),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_storage::EngineType;
+
+    fn header_chain(len: u64) -> Vec<BlockHeader> {
+        let mut headers: Vec<BlockHeader> = vec![];
+        for number in 0..len {
+            let parent_hash = headers
+                .last()
+                .map(BlockHeader::compute_block_hash)
+                .unwrap_or_default();
+            headers.push(BlockHeader {
+                number,
+                parent_hash,
+                ..Default::default()
+            });
+        }
+        headers
+    }
+
+    #[test]
+    fn headers_are_only_added_until_reaching_the_local_chain() {
+        let store = Store::new("", EngineType::InMemory).unwrap();
+        let chain = header_chain(16);
+        // Our local head is block 10, only a few blocks behind the sync head
+        for header in &chain[..=10] {
+            let hash = header.compute_block_hash();
+            store.add_block_header(hash, header.clone()).unwrap();
+            store.set_canonical_block(header.number, hash).unwrap();
+        }
+        // Peers return all ancestors of the sync head, including the blocks we already have
+        let mut downloaded = chain[1..15].to_vec();
+        downloaded.reverse();
+        let mut all_block_headers = vec![chain[15].clone()];
+        let mut all_block_hashes = vec![chain[15].compute_block_hash()];
+        add_headers_until_local_chain(
+            downloaded,
+            10,
+            &store,
+            &mut all_block_headers,
+            &mut all_block_hashes,
+        )
+        .unwrap();
+        let numbers: Vec<_> = all_block_headers
+            .iter()
+            .map(|header| header.number)
+            .collect();
+        assert_eq!(numbers, vec![15, 14, 13, 12, 11]);
+        assert_eq!(
+            all_block_hashes.last(),
+            Some(&chain[11].compute_block_hash())
+        );
+    }
 }
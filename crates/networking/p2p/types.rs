@@ -1,11 +1,14 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use bytes::{BufMut, Bytes};
-use ethrex_core::H512;
+use ethrex_core::{types::ForkId, H256, H264, H512};
 use ethrex_rlp::{
-    decode::RLPDecode,
+    decode::{get_item_with_prefix, RLPDecode},
     encode::RLPEncode,
     error::RLPDecodeError,
     structs::{self, Decoder, Encoder},
 };
+use k256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
 use std::net::{IpAddr, SocketAddr};
 
 const MAX_NODE_RECORD_ENCODED_SIZE: usize = 300;
@@ -88,10 +91,29 @@ impl Node {
             format!("enode://{node_id}@{node_ip}:{listener_port}")
         }
     }
+
+    /// Parses a node from its "enode://nodeID@IPaddress:port[?discport=port]" url
+    pub fn from_enode_url(input: &str) -> Option<Self> {
+        let (node_id, address) = input.strip_prefix("enode://")?.split_once('@')?;
+        let (address, discovery_port) = match address.split_once("?discport=") {
+            Some((address, discovery_port)) => (address, Some(discovery_port.parse().ok()?)),
+            None => (address, None),
+        };
+        let address: SocketAddr = address.parse().ok()?;
+        if node_id.len() != 128 {
+            return None;
+        }
+        Some(Node {
+            ip: address.ip(),
+            udp_port: discovery_port.unwrap_or(address.port()),
+            tcp_port: address.port(),
+            node_id: H512::from_slice(&hex::decode(node_id).ok()?),
+        })
+    }
 }
 
 /// Reference: [ENR records](https://github.com/ethereum/devp2p/blob/master/enr.md)
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct NodeRecord {
     pub signature: H512,
     pub seq: u64,
@@ -99,9 +121,131 @@ pub struct NodeRecord {
     pub pairs: Vec<(Bytes, Bytes)>,
 }
 
+impl NodeRecord {
+    /// Builds the "v4" identity scheme record of the given node and signs it with the node's key.
+    /// The `eth` entry advertising the fork id is only included when one is given
+    pub fn new(node: &Node, seq: u64, signer: &SigningKey, fork_id: Option<&ForkId>) -> Self {
+        let mut pairs: Vec<(Bytes, Bytes)> = Vec::new();
+        if let Some(fork_id) = fork_id {
+            // The eth entry is a list whose first element is the fork id, other elements are ignored
+            pairs.push(("eth".into(), vec![fork_id.clone()].encode_to_vec().into()));
+        }
+        pairs.push(("id".into(), "v4".encode_to_vec().into()));
+        match node.ip {
+            IpAddr::V4(ip) => pairs.push(("ip".into(), ip.encode_to_vec().into())),
+            IpAddr::V6(ip) => pairs.push(("ip6".into(), ip.encode_to_vec().into())),
+        }
+        let public_key = signer.verifying_key().to_encoded_point(true);
+        pairs.push((
+            "secp256k1".into(),
+            H264::from_slice(public_key.as_bytes())
+                .encode_to_vec()
+                .into(),
+        ));
+        if node.tcp_port != 0 {
+            pairs.push(("tcp".into(), node.tcp_port.encode_to_vec().into()));
+        }
+        pairs.push(("udp".into(), node.udp_port.encode_to_vec().into()));
+
+        let mut record = NodeRecord {
+            signature: H512::zero(),
+            seq,
+            id: String::from("v4"),
+            pairs,
+        };
+        let (signature, _recovery_id) = signer
+            .sign_prehash_recoverable(record.content_digest().as_bytes())
+            .expect("failed to sign");
+        record.signature = H512::from_slice(&signature.to_bytes());
+        record
+    }
+
+    /// Returns the keccak hash of the record's content, which is what the "v4" identity scheme signs
+    fn content_digest(&self) -> H256 {
+        let mut content = Vec::new();
+        Encoder::new(&mut content)
+            .encode_field(&self.seq)
+            .encode_key_value_list::<Bytes>(&self.pairs)
+            .finish();
+        H256::from_slice(&Keccak256::digest(&content))
+    }
+
+    /// Decodes the value of the given key, returns None if the key is missing or its value can't be decoded
+    pub fn get<T: RLPDecode>(&self, key: &str) -> Option<T> {
+        self.pairs
+            .iter()
+            .find(|(k, _v)| k.eq(key.as_bytes()))
+            .and_then(|(_k, v)| T::decode(v).ok())
+    }
+
+    fn verifying_key(&self) -> Option<VerifyingKey> {
+        let public_key: H264 = self.get("secp256k1")?;
+        VerifyingKey::from_sec1_bytes(public_key.as_bytes()).ok()
+    }
+
+    /// Returns true if the record is signed by the key of its `secp256k1` entry
+    pub fn verify_signature(&self) -> bool {
+        if self.id != "v4" {
+            return false;
+        }
+        let (Some(verifying_key), Ok(signature)) = (
+            self.verifying_key(),
+            Signature::from_slice(self.signature.as_bytes()),
+        ) else {
+            return false;
+        };
+        verifying_key
+            .verify_prehash(self.content_digest().as_bytes(), &signature)
+            .is_ok()
+    }
+
+    /// Returns the node id (the uncompressed public key) of the node described by the record
+    pub fn node_id(&self) -> Option<H512> {
+        let public_key = self.verifying_key()?.to_encoded_point(false);
+        Some(H512::from_slice(&public_key.as_bytes()[1..]))
+    }
+
+    /// Returns the node described by the record, if it advertises an ip and udp port
+    pub fn node(&self) -> Option<Node> {
+        let ip = match self.get("ip") {
+            Some(ip) => IpAddr::V4(ip),
+            None => IpAddr::V6(self.get("ip6")?),
+        };
+        Some(Node {
+            ip,
+            udp_port: self.get("udp")?,
+            tcp_port: self.get("tcp").unwrap_or_default(),
+            node_id: self.node_id()?,
+        })
+    }
+
+    /// Returns the text form of the record: "enr:" followed by the URL-safe base64 encoding of the record
+    pub fn enr_url(&self) -> String {
+        format!("enr:{}", URL_SAFE_NO_PAD.encode(self.encode_to_vec()))
+    }
+
+    /// Parses a record in text form, checking its signature
+    pub fn from_enr_url(input: &str) -> Result<Self, RLPDecodeError> {
+        let encoded = input
+            .strip_prefix("enr:")
+            .ok_or(RLPDecodeError::Custom("Missing 'enr:' prefix".into()))?;
+        let rlp = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| RLPDecodeError::Custom("Invalid base64 encoding".into()))?;
+        let record = NodeRecord::decode(&rlp)?;
+        if !record.verify_signature() {
+            return Err(RLPDecodeError::Custom(
+                "Invalid node record signature".into(),
+            ));
+        }
+        Ok(record)
+    }
+}
+
 impl RLPDecode for NodeRecord {
     fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
-        if rlp.len() > MAX_NODE_RECORD_ENCODED_SIZE {
+        let (encoded_record, _rest) = get_item_with_prefix(rlp)?;
+        if encoded_record.len() > MAX_NODE_RECORD_ENCODED_SIZE {
             return Err(RLPDecodeError::InvalidLength);
         }
         let decoder = Decoder::new(rlp)?;
@@ -166,3 +310,75 @@ impl RLPEncode for Node {
             .finish();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_core::H256;
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    // Example record from https://github.com/ethereum/devp2p/blob/master/enr.md#test-vectors
+    const EXAMPLE_RECORD: &str = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+
+    fn example_signer() -> SigningKey {
+        let key_bytes =
+            H256::from_str("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")
+                .unwrap();
+        SigningKey::from_slice(key_bytes.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parse_enode_url() {
+        let node = Node {
+            ip: Ipv4Addr::new(18, 138, 108, 67).into(),
+            udp_port: 30301,
+            tcp_port: 30303,
+            node_id: H512::from_str("d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666").unwrap(),
+        };
+        assert_eq!(Node::from_enode_url(&node.enode_url()), Some(node));
+        let node = Node {
+            udp_port: 30303,
+            ..node
+        };
+        assert_eq!(Node::from_enode_url(&node.enode_url()), Some(node));
+        assert_eq!(
+            Node::from_enode_url("enode://d860a01f@18.138.108.67:30303"),
+            None
+        );
+        assert_eq!(Node::from_enode_url("18.138.108.67:30303"), None);
+    }
+
+    #[test]
+    fn parse_example_record() {
+        let record = NodeRecord::from_enr_url(EXAMPLE_RECORD).unwrap();
+        assert_eq!(record.seq, 1);
+        assert_eq!(record.id, "v4");
+        assert_eq!(
+            record.get::<Ipv4Addr>("ip"),
+            Some(Ipv4Addr::new(127, 0, 0, 1))
+        );
+        assert_eq!(record.get::<u16>("udp"), Some(30303));
+        assert_eq!(record.enr_url(), EXAMPLE_RECORD);
+    }
+
+    #[test]
+    fn sign_example_record() {
+        let signer = example_signer();
+        let node = Node {
+            ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            udp_port: 30303,
+            tcp_port: 0,
+            node_id: H512::zero(),
+        };
+        let record = NodeRecord::new(&node, 1, &signer, None);
+        assert_eq!(record.enr_url(), EXAMPLE_RECORD);
+    }
+
+    #[test]
+    fn reject_record_with_invalid_signature() {
+        let mut record = NodeRecord::from_enr_url(EXAMPLE_RECORD).unwrap();
+        record.seq = 2;
+        assert!(!record.verify_signature());
+        assert!(NodeRecord::from_enr_url(&record.enr_url()).is_err());
+    }
+}
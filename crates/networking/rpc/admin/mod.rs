@@ -1,11 +1,12 @@
 use ethrex_core::types::ChainConfig;
-use ethrex_net::types::Node;
+use ethrex_net::{peer_manager::PeerManager, types::Node};
 use ethrex_storage::Store;
 use serde::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
+use tracing::info;
 
-use crate::utils::RpcErr;
+use crate::{utils::RpcErr, RpcApiContext, RpcHandler};
 
 #[derive(Serialize, Debug)]
 struct NodeInfo {
@@ -51,3 +52,88 @@ pub fn node_info(storage: Store, local_node: Node) -> Result<Value, RpcErr> {
     };
     serde_json::to_value(node_info).map_err(|error| RpcErr::Internal(error.to_string()))
 }
+
+#[derive(Serialize, Debug)]
+struct PeerData {
+    enode: String,
+    id: String,
+    network: PeerNetwork,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PeerNetwork {
+    remote_address: String,
+    inbound: bool,
+    trusted: bool,
+    #[serde(rename = "static")]
+    is_static: bool,
+}
+
+pub fn peers(peer_manager: PeerManager) -> Result<Value, RpcErr> {
+    let peers: Vec<PeerData> = peer_manager
+        .peers()
+        .into_iter()
+        .map(|peer| PeerData {
+            enode: peer.node.enode_url(),
+            id: hex::encode(peer.node.node_id),
+            network: PeerNetwork {
+                remote_address: peer.remote_addr.to_string(),
+                inbound: peer.inbound,
+                trusted: peer.is_trusted,
+                is_static: peer.is_static,
+            },
+        })
+        .collect();
+    serde_json::to_value(peers).map_err(|error| RpcErr::Internal(error.to_string()))
+}
+
+pub struct AddPeerRequest {
+    node: Node,
+}
+
+pub struct RemovePeerRequest {
+    node: Node,
+}
+
+fn parse_enode_param(params: &Option<Vec<Value>>) -> Result<Node, RpcErr> {
+    let params = params
+        .as_ref()
+        .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+    if params.len() != 1 {
+        return Err(RpcErr::BadParams("Expected 1 param".to_owned()));
+    };
+    let enode: String = serde_json::from_value(params[0].clone())?;
+    Node::from_enode_url(&enode).ok_or(RpcErr::BadParams(format!("Invalid enode url: {enode}")))
+}
+
+impl RpcHandler for AddPeerRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {
+            node: parse_enode_param(params)?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Adding static peer {}", self.node.enode_url());
+        context
+            .peer_manager
+            .add_static_peer(self.node)
+            .map_err(|error| RpcErr::Internal(error.to_string()))?;
+        Ok(Value::Bool(true))
+    }
+}
+
+impl RpcHandler for RemovePeerRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {
+            node: parse_enode_param(params)?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Removing static peer {}", self.node.enode_url());
+        context.peer_manager.remove_static_peer(self.node.node_id);
+        Ok(Value::Bool(true))
+    }
+}
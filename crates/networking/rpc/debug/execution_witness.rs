@@ -0,0 +1,146 @@
+use std::collections::BTreeSet;
+
+use ethrex_core::types::BlockHeader;
+use ethrex_rlp::encode::RLPEncode;
+use ethrex_storage::Store;
+use ethrex_vm::execution_db::ExecutionDB;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{types::block_identifier::BlockIdentifier, utils::RpcErr, RpcApiContext, RpcHandler};
+
+pub struct ExecutionWitnessRequest {
+    pub block: BlockIdentifier,
+}
+
+/// Data needed to statelessly execute a block, all values are hex encoded
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExecutionWitness {
+    /// RLP encoded nodes of the state trie and storage tries touched by the block
+    state: BTreeSet<String>,
+    /// Bytecodes of the contracts accessed by the block
+    codes: BTreeSet<String>,
+    /// Preimages of the trie keys: account addresses and storage slots
+    keys: BTreeSet<String>,
+    /// RLP encoded headers, from the block's parent back to the oldest block whose hash was read
+    headers: Vec<String>,
+}
+
+impl RpcHandler for ExecutionWitnessRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams("Expected 1 param".to_owned()));
+        };
+        Ok(ExecutionWitnessRequest {
+            block: BlockIdentifier::parse(params[0].clone(), 0)?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let storage = &context.storage;
+        info!("Requested execution witness of block {}", self.block);
+        let Some(block_number) = self.block.resolve_block_number(storage)? else {
+            return Ok(Value::Null);
+        };
+        let Some(block_hash) = storage.get_canonical_block_hash(block_number)? else {
+            return Ok(Value::Null);
+        };
+        let Some(block) = storage.get_block_by_hash(block_hash)? else {
+            return Ok(Value::Null);
+        };
+        let Some(parent_header) = storage.get_block_header_by_hash(block.header.parent_hash)?
+        else {
+            return Err(RpcErr::Internal(format!(
+                "Missing parent of block {block_number}"
+            )));
+        };
+        let db = ExecutionDB::from_exec(&[block], storage)?;
+        let witness = ExecutionWitness::new(&db, parent_header, storage)?;
+        serde_json::to_value(witness).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl ExecutionWitness {
+    fn new(db: &ExecutionDB, parent_header: BlockHeader, storage: &Store) -> Result<Self, RpcErr> {
+        let encode = |bytes: &[u8]| format!("0x{}", hex::encode(bytes));
+
+        let (state_root, state_nodes) = &db.pruned_state_trie;
+        let storage_nodes = db
+            .pruned_storage_tries
+            .values()
+            .flat_map(|(root, nodes)| root.iter().chain(nodes));
+        let state = state_root
+            .iter()
+            .chain(state_nodes)
+            .chain(storage_nodes)
+            .map(|node| encode(node))
+            .collect();
+
+        let codes = db
+            .code
+            .values()
+            .map(|code| encode(&code.original_bytes()))
+            .collect();
+
+        let mut keys = BTreeSet::new();
+        for address in db.accounts.keys() {
+            keys.insert(encode(address.as_slice()));
+        }
+        for slot in db.storage.values().flat_map(|storage| storage.keys()) {
+            keys.insert(encode(&slot.to_be_bytes::<32>()));
+        }
+
+        // The parent header is always needed to validate the block, older ones only if BLOCKHASH reads them
+        let oldest_block = db
+            .block_hashes
+            .keys()
+            .min()
+            .copied()
+            .unwrap_or(parent_header.number);
+        let mut headers = Vec::new();
+        let mut header = parent_header;
+        loop {
+            headers.push(encode(&header.encode_to_vec()));
+            if header.number <= oldest_block {
+                break;
+            }
+            header = storage
+                .get_block_header_by_hash(header.parent_hash)?
+                .ok_or(RpcErr::Internal(format!(
+                    "Missing parent of block {}",
+                    header.number
+                )))?;
+        }
+
+        Ok(ExecutionWitness {
+            state,
+            codes,
+            keys,
+            headers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_execution_witness_request() {
+        let params = Some(vec![json!("0x1")]);
+        let request = ExecutionWitnessRequest::parse(&params).unwrap();
+        assert!(matches!(request.block, BlockIdentifier::Number(1)));
+    }
+
+    #[test]
+    fn parse_execution_witness_request_with_too_many_params() {
+        let params = Some(vec![json!("0x1"), json!("0x2")]);
+        assert!(ExecutionWitnessRequest::parse(&params).is_err());
+    }
+}
@@ -0,0 +1,2 @@
+pub(crate) mod execution_witness;
+pub(crate) mod trace;
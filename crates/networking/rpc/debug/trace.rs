@@ -0,0 +1,174 @@
+use ethrex_core::{types::BlockHash, H256};
+use ethrex_storage::Store;
+use ethrex_vm::{evm_state, trace_block, trace_transaction, GethDebugTracingOptions, GethTrace};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{types::block_identifier::BlockIdentifier, utils::RpcErr, RpcApiContext, RpcHandler};
+
+pub struct TraceTransactionRequest {
+    pub transaction_hash: H256,
+    pub opts: GethDebugTracingOptions,
+}
+
+pub struct TraceBlockByNumberRequest {
+    pub block: BlockIdentifier,
+    pub opts: GethDebugTracingOptions,
+}
+
+pub struct TraceBlockByHashRequest {
+    pub block_hash: BlockHash,
+    pub opts: GethDebugTracingOptions,
+}
+
+/// Trace of a single transaction within a traced block
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockTraceComponent {
+    tx_hash: H256,
+    result: GethTrace,
+}
+
+/// Parses the params of the debug_trace* methods: a mandatory first param and optional tracing options
+fn parse_trace_params(
+    params: &Option<Vec<Value>>,
+) -> Result<(Value, GethDebugTracingOptions), RpcErr> {
+    let params = params
+        .as_ref()
+        .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+    if params.is_empty() || params.len() > 2 {
+        return Err(RpcErr::BadParams(format!(
+            "Expected one or two params and {} were provided",
+            params.len()
+        )));
+    }
+    let opts = match params.get(1) {
+        None | Some(Value::Null) => GethDebugTracingOptions::default(),
+        Some(opts) => serde_json::from_value(opts.clone())?,
+    };
+    Ok((params[0].clone(), opts))
+}
+
+impl RpcHandler for TraceTransactionRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let (transaction_hash, opts) = parse_trace_params(params)?;
+        Ok(TraceTransactionRequest {
+            transaction_hash: serde_json::from_value(transaction_hash)?,
+            opts,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let storage = &context.storage;
+        info!(
+            "Requested trace of transaction {:#x}",
+            self.transaction_hash
+        );
+        let Some((_, block_hash, index)) =
+            storage.get_transaction_location(self.transaction_hash)?
+        else {
+            return Ok(Value::Null);
+        };
+        let Some(block) = storage.get_block_by_hash(block_hash)? else {
+            return Ok(Value::Null);
+        };
+        let mut state = evm_state(storage.clone(), block.header.parent_hash);
+        let trace = trace_transaction(&block, index as usize, &mut state, &self.opts)?;
+        serde_json::to_value(trace).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for TraceBlockByNumberRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let (block, opts) = parse_trace_params(params)?;
+        Ok(TraceBlockByNumberRequest {
+            block: BlockIdentifier::parse(block, 0)?,
+            opts,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let storage = &context.storage;
+        info!("Requested trace of block {}", self.block);
+        let Some(block_number) = self.block.resolve_block_number(storage)? else {
+            return Ok(Value::Null);
+        };
+        let Some(block_hash) = storage.get_canonical_block_hash(block_number)? else {
+            return Ok(Value::Null);
+        };
+        trace_block_by_hash(storage, block_hash, &self.opts)
+    }
+}
+
+impl RpcHandler for TraceBlockByHashRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let (block_hash, opts) = parse_trace_params(params)?;
+        Ok(TraceBlockByHashRequest {
+            block_hash: serde_json::from_value(block_hash)?,
+            opts,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested trace of block {:#x}", self.block_hash);
+        trace_block_by_hash(&context.storage, self.block_hash, &self.opts)
+    }
+}
+
+/// Replays the block on top of its parent's state and returns the trace of each of its transactions
+fn trace_block_by_hash(
+    storage: &Store,
+    block_hash: BlockHash,
+    opts: &GethDebugTracingOptions,
+) -> Result<Value, RpcErr> {
+    let Some(block) = storage.get_block_by_hash(block_hash)? else {
+        return Ok(Value::Null);
+    };
+    let mut state = evm_state(storage.clone(), block.header.parent_hash);
+    let traces: Vec<BlockTraceComponent> = trace_block(&block, &mut state, opts)?
+        .into_iter()
+        .map(|(tx_hash, result)| BlockTraceComponent { tx_hash, result })
+        .collect();
+    serde_json::to_value(traces).map_err(|error| RpcErr::Internal(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_vm::{GethDebugBuiltInTracerType, GethDebugTracerType};
+    use serde_json::json;
+
+    #[test]
+    fn parse_trace_transaction_request_without_options() {
+        let params = Some(vec![json!(
+            "0x7cc741c553d4098f319c894d9db208999ca49ee1b5c53f6a9992e687cbffb69e"
+        )]);
+        let request = TraceTransactionRequest::parse(&params).unwrap();
+        assert_eq!(request.opts, GethDebugTracingOptions::default());
+    }
+
+    #[test]
+    fn parse_trace_block_request_with_call_tracer() {
+        let params = Some(vec![
+            json!("0x1"),
+            json!({"tracer": "callTracer", "tracerConfig": {"onlyTopCall": true}}),
+        ]);
+        let request = TraceBlockByNumberRequest::parse(&params).unwrap();
+        assert!(matches!(request.block, BlockIdentifier::Number(1)));
+        assert_eq!(
+            request.opts.tracer,
+            Some(GethDebugTracerType::BuiltInTracer(
+                GethDebugBuiltInTracerType::CallTracer
+            ))
+        );
+        let call_config = request.opts.tracer_config.into_call_config().unwrap();
+        assert_eq!(call_config.only_top_call, Some(true));
+    }
+
+    #[test]
+    fn parse_trace_request_with_too_many_params() {
+        let params = Some(vec![json!("0x1"), json!({}), json!({})]);
+        assert!(TraceBlockByNumberRequest::parse(&params).is_err());
+    }
+}
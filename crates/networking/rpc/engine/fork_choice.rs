@@ -154,18 +154,29 @@ fn handle_forkchoice(
         fork_choice_state.finalized_block_hash
     );
 
+    let previous_head_number = context.storage.get_latest_block_number()?;
     match apply_fork_choice(
         &context.storage,
         fork_choice_state.head_block_hash,
         fork_choice_state.safe_block_hash,
         fork_choice_state.finalized_block_hash,
     ) {
-        Ok(head) => Ok((
-            Some(head),
-            ForkChoiceResponse::from(PayloadStatus::valid_with_hash(
-                fork_choice_state.head_block_hash,
-            )),
-        )),
+        Ok(head) => {
+            // Failing to notify subscribers doesn't invalidate the fork choice, so we only log it
+            if let Err(error) = context.subscription_notifier.notify_new_heads(
+                &context.storage,
+                previous_head_number,
+                head.clone(),
+            ) {
+                warn!("Failed to notify new heads to subscribers: {error}");
+            }
+            Ok((
+                Some(head),
+                ForkChoiceResponse::from(PayloadStatus::valid_with_hash(
+                    fork_choice_state.head_block_hash,
+                )),
+            ))
+        }
         Err(forkchoice_error) => {
             let forkchoice_response = match forkchoice_error {
                 InvalidForkChoice::NewHeadAlreadyCanonical => {
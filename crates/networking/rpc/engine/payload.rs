@@ -1,7 +1,7 @@
 use ethrex_blockchain::add_block;
 use ethrex_blockchain::error::ChainError;
 use ethrex_blockchain::payload::build_payload;
-use ethrex_core::types::{BlobsBundle, Block, Fork};
+use ethrex_core::types::{compute_requests_hash, BlobsBundle, Block, EncodedRequests, Fork};
 use ethrex_core::{H256, U256};
 use serde_json::Value;
 use tracing::{error, info, warn};
@@ -10,7 +10,7 @@ use crate::types::payload::{ExecutionPayload, ExecutionPayloadResponse, PayloadS
 use crate::utils::RpcRequest;
 use crate::{RpcApiContext, RpcErr, RpcHandler};
 
-// NewPayload V1-V2-V3 implementations
+// NewPayload V1-V2-V3-V4 implementations
 pub struct NewPayloadV1Request {
     pub payload: ExecutionPayload,
 }
@@ -83,31 +83,80 @@ impl RpcHandler for NewPayloadV3Request {
 
     fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
         validate_execution_payload_v3(&self.payload)?;
-        let block = get_block_from_payload(&self.payload, Some(self.parent_beacon_block_root))?;
+        let block =
+            get_block_from_payload(&self.payload, Some(self.parent_beacon_block_root), None)?;
         validate_fork(&block, Fork::Cancun, &context)?;
-        let payload_status = {
-            if let Err(RpcErr::Internal(error_msg)) = validate_block_hash(&self.payload, &block) {
-                PayloadStatus::invalid_with_err(&error_msg)
-            } else {
-                let blob_versioned_hashes: Vec<H256> = block
-                    .body
-                    .transactions
-                    .iter()
-                    .flat_map(|tx| tx.blob_versioned_hashes())
-                    .collect();
-
-                if self.expected_blob_versioned_hashes != blob_versioned_hashes {
-                    PayloadStatus::invalid_with_err("Invalid blob_versioned_hashes")
-                } else {
-                    execute_payload(&block, &context)?
-                }
-            }
-        };
-        serde_json::to_value(payload_status).map_err(|error| RpcErr::Internal(error.to_string()))
+        handle_new_payload_v3_v4(
+            &self.payload,
+            &block,
+            &self.expected_blob_versioned_hashes,
+            context,
+        )
+    }
+}
+
+pub struct NewPayloadV4Request {
+    pub payload: ExecutionPayload,
+    pub expected_blob_versioned_hashes: Vec<H256>,
+    pub parent_beacon_block_root: H256,
+    pub execution_requests: Vec<EncodedRequests>,
+}
+
+impl From<NewPayloadV4Request> for RpcRequest {
+    fn from(val: NewPayloadV4Request) -> Self {
+        RpcRequest {
+            method: "engine_newPayloadV4".to_string(),
+            params: Some(vec![
+                serde_json::json!(val.payload),
+                serde_json::json!(val.expected_blob_versioned_hashes),
+                serde_json::json!(val.parent_beacon_block_root),
+                serde_json::json!(val.execution_requests),
+            ]),
+            ..Default::default()
+        }
     }
 }
 
-// GetPayload V1-V2-V3 implementations
+impl RpcHandler for NewPayloadV4Request {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 4 {
+            return Err(RpcErr::BadParams("Expected 4 params".to_owned()));
+        }
+        Ok(NewPayloadV4Request {
+            payload: serde_json::from_value(params[0].clone())
+                .map_err(|_| RpcErr::WrongParam("payload".to_string()))?,
+            expected_blob_versioned_hashes: serde_json::from_value(params[1].clone())
+                .map_err(|_| RpcErr::WrongParam("expected_blob_versioned_hashes".to_string()))?,
+            parent_beacon_block_root: serde_json::from_value(params[2].clone())
+                .map_err(|_| RpcErr::WrongParam("parent_beacon_block_root".to_string()))?,
+            execution_requests: serde_json::from_value(params[3].clone())
+                .map_err(|_| RpcErr::WrongParam("execution_requests".to_string()))?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        validate_execution_payload_v3(&self.payload)?;
+        validate_execution_requests(&self.execution_requests)?;
+        let requests_hash = compute_requests_hash(&self.execution_requests);
+        let block = get_block_from_payload(
+            &self.payload,
+            Some(self.parent_beacon_block_root),
+            Some(requests_hash),
+        )?;
+        validate_fork(&block, Fork::Prague, &context)?;
+        handle_new_payload_v3_v4(
+            &self.payload,
+            &block,
+            &self.expected_blob_versioned_hashes,
+            context,
+        )
+    }
+}
+
+// GetPayload V1-V2-V3-V4 implementations
 pub struct GetPayloadV1Request {
     pub payload_id: u64,
 }
@@ -172,6 +221,34 @@ impl RpcHandler for GetPayloadV3Request {
     }
 }
 
+pub struct GetPayloadV4Request {
+    pub payload_id: u64,
+}
+
+impl From<GetPayloadV4Request> for RpcRequest {
+    fn from(val: GetPayloadV4Request) -> Self {
+        RpcRequest {
+            method: "engine_getPayloadV4".to_string(),
+            params: Some(vec![serde_json::json!(U256::from(val.payload_id))]),
+            ..Default::default()
+        }
+    }
+}
+
+impl RpcHandler for GetPayloadV4Request {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let payload_id = parse_get_payload_request(params)?;
+        Ok(Self { payload_id })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let execution_payload_response =
+            build_execution_payload_response(self.payload_id, Fork::Prague, Some(false), context)?;
+        serde_json::to_value(execution_payload_response)
+            .map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
 fn parse_execution_payload(params: &Option<Vec<Value>>) -> Result<ExecutionPayload, RpcErr> {
     let params = params
         .as_ref()
@@ -187,7 +264,7 @@ fn handle_new_payload_v1_v2(
     fork: Fork,
     context: RpcApiContext,
 ) -> Result<Value, RpcErr> {
-    let block = get_block_from_payload(payload, None)?;
+    let block = get_block_from_payload(payload, None, None)?;
     validate_fork(&block, fork, &context)?;
     let payload_status = {
         if let Err(RpcErr::Internal(error_msg)) = validate_block_hash(payload, &block) {
@@ -199,6 +276,50 @@ fn handle_new_payload_v1_v2(
     serde_json::to_value(payload_status).map_err(|error| RpcErr::Internal(error.to_string()))
 }
 
+fn handle_new_payload_v3_v4(
+    payload: &ExecutionPayload,
+    block: &Block,
+    expected_blob_versioned_hashes: &[H256],
+    context: RpcApiContext,
+) -> Result<Value, RpcErr> {
+    let payload_status = {
+        if let Err(RpcErr::Internal(error_msg)) = validate_block_hash(payload, block) {
+            PayloadStatus::invalid_with_err(&error_msg)
+        } else {
+            let blob_versioned_hashes: Vec<H256> = block
+                .body
+                .transactions
+                .iter()
+                .flat_map(|tx| tx.blob_versioned_hashes())
+                .collect();
+
+            if expected_blob_versioned_hashes != blob_versioned_hashes {
+                PayloadStatus::invalid_with_err("Invalid blob_versioned_hashes")
+            } else {
+                execute_payload(block, &context)?
+            }
+        }
+    };
+    serde_json::to_value(payload_status).map_err(|error| RpcErr::Internal(error.to_string()))
+}
+
+/// Checks the execution requests are well formed as defined in [EIP-7685](https://eips.ethereum.org/EIPS/eip-7685):
+/// each request must carry data and they must be sorted by strictly ascending request type
+fn validate_execution_requests(execution_requests: &[EncodedRequests]) -> Result<(), RpcErr> {
+    let mut last_request_type = None;
+    for request in execution_requests {
+        if request.is_empty() {
+            return Err(RpcErr::WrongParam("execution_requests".to_string()));
+        }
+        let request_type = request.request_type();
+        if last_request_type >= request_type {
+            return Err(RpcErr::WrongParam("execution_requests".to_string()));
+        }
+        last_request_type = request_type;
+    }
+    Ok(())
+}
+
 fn validate_execution_payload_v3(payload: &ExecutionPayload) -> Result<(), RpcErr> {
     if payload.excess_blob_gas.is_none() {
         return Err(RpcErr::WrongParam("excess_blob_gas".to_string()));
@@ -212,13 +333,14 @@ fn validate_execution_payload_v3(payload: &ExecutionPayload) -> Result<(), RpcEr
 fn get_block_from_payload(
     payload: &ExecutionPayload,
     parent_beacon_block_root: Option<H256>,
+    requests_hash: Option<H256>,
 ) -> Result<Block, RpcErr> {
     let block_hash = payload.block_hash;
     info!("Received new payload with block hash: {block_hash:#x}");
 
     payload
         .clone()
-        .into_block(parent_beacon_block_root)
+        .into_block(parent_beacon_block_root, requests_hash)
         .map_err(|error| RpcErr::Internal(error.to_string()))
 }
 
@@ -307,17 +429,23 @@ fn parse_get_payload_request(params: &Option<Vec<Value>>) -> Result<u64, RpcErr>
 fn get_payload(
     payload_id: u64,
     context: &RpcApiContext,
-) -> Result<(Block, U256, BlobsBundle, bool), RpcErr> {
+) -> Result<(Block, U256, BlobsBundle, Vec<EncodedRequests>, bool), RpcErr> {
     info!("Requested payload with id: {:#018x}", payload_id);
     let payload = context.storage.get_payload(payload_id)?;
 
-    let Some((payload_block, block_value, blobs_bundle, completed)) = payload else {
+    let Some((payload_block, block_value, blobs_bundle, requests, completed)) = payload else {
         return Err(RpcErr::UnknownPayload(format!(
             "Payload with id {:#018x} not found",
             payload_id
         )));
     };
-    Ok((payload_block, block_value, blobs_bundle, completed))
+    Ok((
+        payload_block,
+        block_value,
+        blobs_bundle,
+        requests,
+        completed,
+    ))
 }
 
 fn validate_fork(block: &Block, fork: Fork, context: &RpcApiContext) -> Result<(), RpcErr> {
@@ -336,35 +464,42 @@ fn build_execution_payload_response(
     should_override_builder: Option<bool>,
     context: RpcApiContext,
 ) -> Result<ExecutionPayloadResponse, RpcErr> {
-    let (mut payload_block, block_value, blobs_bundle, completed) =
+    let (mut payload_block, block_value, blobs_bundle, requests, completed) =
         get_payload(payload_id, &context)?;
 
     validate_fork(&payload_block, fork, &context)?;
 
-    if completed {
-        Ok(ExecutionPayloadResponse {
-            execution_payload: ExecutionPayload::from_block(payload_block),
-            block_value,
-            blobs_bundle: Some(blobs_bundle),
-            should_override_builder,
-        })
+    let (blobs_bundle, requests, block_value) = if completed {
+        (blobs_bundle, requests, block_value)
     } else {
-        let (blobs_bundle, block_value) = build_payload(&mut payload_block, &context.storage)
-            .map_err(|err| RpcErr::Internal(err.to_string()))?;
+        let (blobs_bundle, requests, block_value) =
+            build_payload(&mut payload_block, &context.storage)
+                .map_err(|err| RpcErr::Internal(err.to_string()))?;
 
         context.storage.update_payload(
             payload_id,
             payload_block.clone(),
             block_value,
             blobs_bundle.clone(),
+            requests.clone(),
             true,
         )?;
+        (blobs_bundle, requests, block_value)
+    };
 
-        Ok(ExecutionPayloadResponse {
-            execution_payload: ExecutionPayload::from_block(payload_block),
-            block_value,
-            blobs_bundle: Some(blobs_bundle),
-            should_override_builder,
-        })
-    }
+    // Requests with empty data are not included in the response
+    let execution_requests = (fork >= Fork::Prague).then(|| {
+        requests
+            .into_iter()
+            .filter(|request| !request.is_empty())
+            .collect()
+    });
+
+    Ok(ExecutionPayloadResponse {
+        execution_payload: ExecutionPayload::from_block(payload_block),
+        block_value,
+        blobs_bundle: Some(blobs_bundle),
+        should_override_builder,
+        execution_requests,
+    })
 }
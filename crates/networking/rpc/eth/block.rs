@@ -331,8 +331,14 @@ impl RpcHandler for GetBlobBaseFee {
             Ok(header) => header,
             Err(error) => return Err(RpcErr::Internal(error.to_string())),
         };
-        let blob_base_fee =
-            calculate_base_fee_per_blob_gas(parent_header.excess_blob_gas.unwrap_or_default());
+        let fork = context
+            .storage
+            .get_chain_config()?
+            .get_fork(header.timestamp);
+        let blob_base_fee = calculate_base_fee_per_blob_gas(
+            parent_header.excess_blob_gas.unwrap_or_default(),
+            fork.blob_base_fee_update_fraction(),
+        );
         serde_json::to_value(format!("{:#x}", blob_base_fee))
             .map_err(|error| RpcErr::Internal(error.to_string()))
     }
@@ -349,8 +355,11 @@ pub fn get_all_block_rpc_receipts(
     if header.parent_hash.is_zero() {
         return Ok(receipts);
     }
-    let blob_gas_price =
-        calculate_base_fee_per_blob_gas(header.excess_blob_gas.unwrap_or_default());
+    let fork = storage.get_chain_config()?.get_fork(header.timestamp);
+    let blob_gas_price = calculate_base_fee_per_blob_gas(
+        header.excess_blob_gas.unwrap_or_default(),
+        fork.blob_base_fee_update_fraction(),
+    );
     // Fetch receipt info from block
     let block_info = RpcReceiptBlockInfo::from_block_header(header);
     // Fetch receipt for each tx in the block and add block and tx info
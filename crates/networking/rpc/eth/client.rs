@@ -1,4 +1,5 @@
-use serde_json::Value;
+use ethrex_net::sync::SyncProgress;
+use serde_json::{json, Value};
 use tracing::info;
 
 use crate::{utils::RpcErr, RpcApiContext, RpcHandler};
@@ -26,7 +27,54 @@ impl RpcHandler for Syncing {
         Ok(Self {})
     }
 
-    fn handle(&self, _context: RpcApiContext) -> Result<Value, RpcErr> {
-        Ok(Value::Bool(false))
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        Ok(match context.sync_status.progress() {
+            Some(progress) => sync_progress_to_json(&progress),
+            None => Value::Bool(false),
+        })
+    }
+}
+
+/// Formats the sync progress as geth does, with every value as a hex quantity
+fn sync_progress_to_json(progress: &SyncProgress) -> Value {
+    let hex = |value: u64| format!("{value:#x}");
+    json!({
+        "startingBlock": hex(progress.starting_block),
+        "currentBlock": hex(progress.current_block),
+        "highestBlock": hex(progress.highest_block),
+        "syncedAccounts": hex(progress.synced_accounts),
+        "syncedAccountBytes": hex(progress.synced_account_bytes),
+        "syncedStorage": hex(progress.synced_storage),
+        "syncedStorageBytes": hex(progress.synced_storage_bytes),
+        "syncedBytecodes": hex(progress.synced_bytecodes),
+        "syncedBytecodeBytes": hex(progress.synced_bytecode_bytes),
+        "healedTrienodes": hex(progress.healed_trienodes),
+        "healedTrienodeBytes": hex(progress.healed_trienode_bytes),
+        "healedBytecodes": hex(progress.healed_bytecodes),
+        "healedBytecodeBytes": hex(progress.healed_bytecode_bytes),
+        "healingTrienodes": hex(progress.healing_trienodes),
+        "healingBytecode": hex(progress.healing_bytecodes),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_progress_is_formatted_as_hex_quantities() {
+        let progress = SyncProgress {
+            starting_block: 10,
+            current_block: 20,
+            highest_block: 255,
+            synced_accounts: 1,
+            ..Default::default()
+        };
+        let json = sync_progress_to_json(&progress);
+        assert_eq!(json["startingBlock"], "0xa");
+        assert_eq!(json["currentBlock"], "0x14");
+        assert_eq!(json["highestBlock"], "0xff");
+        assert_eq!(json["syncedAccounts"], "0x1");
+        assert_eq!(json["healedTrienodes"], "0x0");
     }
 }
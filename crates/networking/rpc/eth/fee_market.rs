@@ -1,4 +1,4 @@
-use ethrex_blockchain::constants::MAX_BLOB_GAS_PER_BLOCK;
+use ethrex_blockchain::constants::GAS_PER_BLOB;
 use ethrex_core::types::{Block, Transaction};
 use serde::Serialize;
 use serde_json::Value;
@@ -69,6 +69,7 @@ impl RpcHandler for FeeHistoryRequest {
 
     fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
         let storage = &context.storage;
+        let chain_config = storage.get_chain_config()?;
         info!(
             "Requested fee history for {} blocks starting from {}",
             self.block_count, self.newest_block
@@ -100,14 +101,18 @@ impl RpcHandler for FeeHistoryRequest {
                 .ok_or(RpcErr::Internal(format!(
                     "Could not get body for block {block_number}"
                 )))?;
-            let blob_base_fee =
-                calculate_base_fee_per_blob_gas(header.excess_blob_gas.unwrap_or_default());
+            let fork = chain_config.get_fork(header.timestamp);
+            let blob_base_fee = calculate_base_fee_per_blob_gas(
+                header.excess_blob_gas.unwrap_or_default(),
+                fork.blob_base_fee_update_fraction(),
+            );
+            let max_blob_gas_per_block = fork.max_blobs_per_block() * GAS_PER_BLOB;
 
             base_fee_per_gas.push(header.base_fee_per_gas.unwrap_or_default());
             base_fee_per_blob_gas.push(blob_base_fee);
             gas_used_ratio.push(header.gas_used as f64 / header.gas_limit as f64);
             blob_gas_used_ratio.push(
-                header.blob_gas_used.unwrap_or_default() as f64 / MAX_BLOB_GAS_PER_BLOCK as f64,
+                header.blob_gas_used.unwrap_or_default() as f64 / max_blob_gas_per_block as f64,
             );
 
             if let Some(percentiles) = &self.reward_percentiles {
@@ -123,8 +128,12 @@ impl RpcHandler for FeeHistoryRequest {
                 "Could not get header for block {end_block}"
             )))?;
 
-        let blob_base_fee =
-            calculate_base_fee_per_blob_gas(header.excess_blob_gas.unwrap_or_default());
+        let blob_base_fee = calculate_base_fee_per_blob_gas(
+            header.excess_blob_gas.unwrap_or_default(),
+            chain_config
+                .get_fork(header.timestamp)
+                .blob_base_fee_update_fraction(),
+        );
         base_fee_per_gas.push(header.base_fee_per_gas.unwrap_or_default());
         base_fee_per_blob_gas.push(blob_base_fee);
 
@@ -195,6 +204,9 @@ impl FeeHistoryRequest {
                 Transaction::EIP4844Transaction(t) => t
                     .max_priority_fee_per_gas
                     .min(t.max_fee_per_gas.saturating_sub(base_fee_per_gas)),
+                Transaction::EIP7702Transaction(t) => t
+                    .max_priority_fee_per_gas
+                    .min(t.max_fee_per_gas.saturating_sub(base_fee_per_gas)),
                 Transaction::PrivilegedL2Transaction(t) => t
                     .max_priority_fee_per_gas
                     .min(t.max_fee_per_gas.saturating_sub(base_fee_per_gas)),
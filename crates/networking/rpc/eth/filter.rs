@@ -19,7 +19,7 @@ use crate::{
 use rand::prelude::*;
 use serde_json::{json, Value};
 
-use super::logs::{fetch_logs_with_filter, LogsFilter};
+use super::logs::{fetch_logs_with_filter, LogsFilter, LogsLimits};
 
 #[derive(Debug, Clone)]
 pub struct NewFilterRequest {
@@ -184,6 +184,7 @@ impl FilterChangesRequest {
         &self,
         storage: ethrex_storage::Store,
         filters: ActiveFilters,
+        logs_limits: &LogsLimits,
     ) -> Result<serde_json::Value, crate::utils::RpcErr> {
         let latest_block_num = storage.get_latest_block_number()?;
         let mut active_filters_guard = filters.lock().unwrap_or_else(|mut poisoned_guard| {
@@ -217,7 +218,7 @@ impl FilterChangesRequest {
                 // Drop the lock early to process this filter's query
                 // and not keep the lock more than we should.
                 drop(active_filters_guard);
-                let logs = fetch_logs_with_filter(&filter.filter_data, storage)?;
+                let logs = fetch_logs_with_filter(&filter.filter_data, storage, logs_limits)?;
                 serde_json::to_value(logs).map_err(|error| {
                     tracing::error!("Log filtering request failed with: {error}");
                     RpcErr::Internal("Failed to filter logs".to_string())
@@ -238,9 +239,10 @@ impl FilterChangesRequest {
         req: &RpcRequest,
         storage: ethrex_storage::Store,
         filters: ActiveFilters,
+        logs_limits: &LogsLimits,
     ) -> Result<serde_json::Value, crate::utils::RpcErr> {
         let request = Self::parse(&req.params)?;
-        request.handle(storage, filters)
+        request.handle(storage, filters, logs_limits)
     }
 }
 
@@ -257,18 +259,18 @@ mod tests {
     use crate::{
         eth::{
             filter::PollableFilter,
-            logs::{AddressFilter, LogsFilter, TopicFilter},
+            logs::{AddressFilter, LogsFilter, LogsLimits, TopicFilter},
         },
         map_http_requests,
         utils::test_utils::{self, start_test_api},
-        RpcApiContext, FILTER_DURATION,
+        RpcApiContext, DEFAULT_BATCH_REQUEST_LIMIT, FILTER_DURATION,
     };
     use crate::{
         types::block_identifier::BlockIdentifier,
         utils::{test_utils::example_p2p_node, RpcRequest},
     };
     use ethrex_core::types::Genesis;
-    use ethrex_net::sync::SyncManager;
+    use ethrex_net::sync::{SyncManager, SyncStatus};
     use ethrex_storage::{EngineType, Store};
 
     use serde_json::{json, Value};
@@ -439,6 +441,11 @@ mod tests {
             local_p2p_node: example_p2p_node(),
             active_filters: filters_pointer.clone(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
         };
         let request: RpcRequest = serde_json::from_value(json_req).expect("Test json is incorrect");
         let genesis_config: Genesis =
@@ -492,6 +499,11 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: active_filters.clone(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
         };
 
         map_http_requests(&uninstall_filter_req, context).unwrap();
@@ -512,6 +524,11 @@ mod tests {
             active_filters: active_filters.clone(),
             jwt_secret: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
         };
         let uninstall_filter_req: RpcRequest = serde_json::from_value(json!(
         {
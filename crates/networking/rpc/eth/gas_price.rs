@@ -101,7 +101,7 @@ mod tests {
     use crate::{
         map_http_requests,
         utils::{parse_json_hex, test_utils::example_p2p_node, RpcRequest},
-        RpcApiContext, RpcHandler,
+        LogsLimits, RpcApiContext, RpcHandler, DEFAULT_BATCH_REQUEST_LIMIT,
     };
     use bytes::Bytes;
     use ethrex_core::{
@@ -111,7 +111,10 @@ mod tests {
         },
         Address, Bloom, H256, U256,
     };
-    use ethrex_net::{sync::SyncManager, types::Node};
+    use ethrex_net::{
+        sync::{SyncManager, SyncStatus},
+        types::Node,
+    };
     use ethrex_storage::{EngineType, Store};
     use hex_literal::hex;
     use serde_json::json;
@@ -161,6 +164,7 @@ mod tests {
             blob_gas_used: Some(0x00),
             excess_blob_gas: Some(0x00),
             parent_beacon_block_root: Some(H256::zero()),
+            requests_hash: None,
         }
     }
     fn legacy_tx_for_test(nonce: u64) -> Transaction {
@@ -384,6 +388,11 @@ mod tests {
             },
             active_filters: Default::default(),
             syncer: Arc::new(Mutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
         }
     }
 }
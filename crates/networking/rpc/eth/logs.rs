@@ -6,11 +6,34 @@ use crate::{
     types::{block_identifier::BlockIdentifier, receipt::RpcLog},
     RpcApiContext, RpcErr, RpcHandler,
 };
-use ethrex_core::{H160, H256};
-use ethrex_storage::Store;
+use ethrex_core::{types::BlockNumber, Bloom, H160, H256};
+use ethrex_storage::{bloom_bit_indexes, bloom_has_bit, Store, BLOOM_BITS_SECTION_SIZE};
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashSet;
+
+/// Default maximum amount of blocks a single log query can span
+pub const DEFAULT_LOGS_MAX_BLOCK_RANGE: u64 = 100_000;
+/// Default maximum amount of logs a single log query can return
+pub const DEFAULT_LOGS_MAX_RESULTS: usize = 10_000;
+
+/// Bounds the amount of work done by a single log query
+#[derive(Debug, Clone, Copy)]
+pub struct LogsLimits {
+    /// Maximum amount of blocks between `fromBlock` and `toBlock` (both included)
+    pub max_block_range: u64,
+    /// Maximum amount of logs returned, queries matching more logs fail
+    pub max_results: usize,
+}
+
+impl Default for LogsLimits {
+    fn default() -> Self {
+        Self {
+            max_block_range: DEFAULT_LOGS_MAX_BLOCK_RANGE,
+            max_results: DEFAULT_LOGS_MAX_RESULTS,
+        }
+    }
+}
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum AddressFilter {
@@ -85,7 +108,7 @@ impl RpcHandler for LogsFilter {
         }
     }
     fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
-        let filtered_logs = fetch_logs_with_filter(self, context.storage)?;
+        let filtered_logs = fetch_logs_with_filter(self, context.storage, &context.logs_limits)?;
         serde_json::to_value(filtered_logs).map_err(|error| {
             tracing::error!("Log filtering request failed with: {error}");
             RpcErr::Internal("Failed to filter logs".to_string())
@@ -93,6 +116,153 @@ impl RpcHandler for LogsFilter {
     }
 }
 
+/// The bloom bits that a block's logs bloom must have set for the block to contain matching logs.
+/// Each group corresponds to the address filter or to a topic position, and each item within a group
+/// to one of the accepted values, which sets three bits of the bloom.
+/// A block may contain matching logs if for every group the bloom has all the bits of at least one of its items.
+struct BloomFilter {
+    groups: Vec<Vec<[u32; 3]>>,
+}
+
+impl BloomFilter {
+    fn new(filter: &LogsFilter) -> Self {
+        let mut groups = Vec::new();
+        match &filter.address_filters {
+            Some(AddressFilter::Single(address)) => {
+                groups.push(vec![bloom_bit_indexes(address.as_bytes())])
+            }
+            Some(AddressFilter::Many(addresses)) if !addresses.is_empty() => groups.push(
+                addresses
+                    .iter()
+                    .map(|address| bloom_bit_indexes(address.as_bytes()))
+                    .collect(),
+            ),
+            _ => {}
+        }
+        for topic_filter in &filter.topics {
+            // Wildcards match any log, so they don't restrict the candidate blocks
+            let topics = match topic_filter {
+                TopicFilter::Topic(Some(topic)) => vec![*topic],
+                TopicFilter::Topics(topics) if !topics.is_empty() => {
+                    match topics.iter().copied().collect::<Option<Vec<_>>>() {
+                        Some(topics) => topics,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            groups.push(
+                topics
+                    .iter()
+                    .map(|topic| bloom_bit_indexes(topic.as_bytes()))
+                    .collect(),
+            );
+        }
+        Self { groups }
+    }
+
+    /// Returns true if a block with the given logs bloom may contain matching logs
+    fn matches(&self, bloom: &Bloom) -> bool {
+        // Blocks without logs have an empty bloom
+        !bloom.is_zero()
+            && self.groups.iter().all(|group| {
+                group
+                    .iter()
+                    .any(|bits| bits.iter().all(|bit| bloom_has_bit(bloom, *bit)))
+            })
+    }
+
+    /// Combines the bloom bits of an indexed section, returning a bit vector with the bits of the blocks
+    /// that may contain matching logs set, or None if the section's bloom bits are missing
+    fn match_section(&self, section: u64, storage: &Store) -> Result<Option<Vec<u8>>, RpcErr> {
+        let vector_len = BLOOM_BITS_SECTION_SIZE as usize / 8;
+        let mut section_matches = vec![0xff_u8; vector_len];
+        for group in &self.groups {
+            let mut group_matches = vec![0_u8; vector_len];
+            for bits in group {
+                let mut item_matches = vec![0xff_u8; vector_len];
+                for bit in bits {
+                    let Some(bit_vector) = storage.get_bloom_bits(section, *bit)? else {
+                        return Ok(None);
+                    };
+                    item_matches
+                        .iter_mut()
+                        .zip(bit_vector)
+                        .for_each(|(item_byte, bit_byte)| *item_byte &= bit_byte);
+                }
+                group_matches
+                    .iter_mut()
+                    .zip(item_matches)
+                    .for_each(|(group_byte, item_byte)| *group_byte |= item_byte);
+            }
+            section_matches
+                .iter_mut()
+                .zip(group_matches)
+                .for_each(|(section_byte, group_byte)| *section_byte &= group_byte);
+        }
+        Ok(Some(section_matches))
+    }
+
+    /// Returns the blocks in `from..=to` that may contain matching logs according to the bloom bits index.
+    /// Blocks that are not indexed yet are always returned, their header's bloom is checked when fetching them
+    fn candidate_blocks(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        storage: &Store,
+    ) -> Result<Vec<BlockNumber>, RpcErr> {
+        if self.groups.is_empty() {
+            return Ok((from..=to).collect());
+        }
+        let indexed_blocks = storage.get_bloom_bits_sections()? * BLOOM_BITS_SECTION_SIZE;
+        let mut candidates = Vec::new();
+        let mut block_num = from;
+        while block_num <= to && block_num < indexed_blocks {
+            let section = block_num / BLOOM_BITS_SECTION_SIZE;
+            let section_start = section * BLOOM_BITS_SECTION_SIZE;
+            let last_block = to.min(section_start + BLOOM_BITS_SECTION_SIZE - 1);
+            let section_matches = self.match_section(section, storage)?;
+            candidates.extend((block_num..=last_block).filter(|number| {
+                let index = (number - section_start) as usize;
+                section_matches
+                    .as_ref()
+                    .is_none_or(|matches| matches[index / 8] & (0x80 >> (index % 8)) != 0)
+            }));
+            block_num = last_block + 1;
+        }
+        candidates.extend(block_num..=to);
+        Ok(candidates)
+    }
+}
+
+/// Returns true if the log's topics match the topic filters, position by position
+fn matches_topics(topic_filters: &[TopicFilter], topics: &[H256]) -> bool {
+    if topic_filters.len() > topics.len() {
+        return false;
+    }
+    for (i, topic_filter) in topic_filters.iter().enumerate() {
+        match topic_filter {
+            TopicFilter::Topic(t) => {
+                if let Some(topic) = t {
+                    if topics[i] != *topic {
+                        return false;
+                    }
+                }
+            }
+            TopicFilter::Topics(sub_topics) => {
+                if !sub_topics.is_empty()
+                    && !sub_topics
+                        .iter()
+                        .any(|st| st.is_none_or(|t| topics[i] == t))
+                {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
 // TODO: This is longer than it has the right to be, maybe we should refactor it.
 // The main problem here is the layers of indirection needed
 // to fetch tx and block data for a log rpc response, some ideas here are:
@@ -105,6 +275,7 @@ impl RpcHandler for LogsFilter {
 pub(crate) fn fetch_logs_with_filter(
     filter: &LogsFilter,
     storage: Store,
+    limits: &LogsLimits,
 ) -> Result<Vec<RpcLog>, RpcErr> {
     let from = filter
         .from_block
@@ -117,30 +288,41 @@ pub(crate) fn fetch_logs_with_filter(
     if (from..=to).is_empty() {
         return Err(RpcErr::BadParams("Empty range".to_string()));
     }
+    let block_range = to - from + 1;
+    if block_range > limits.max_block_range {
+        return Err(RpcErr::BadParams(format!(
+            "Block range of {block_range} blocks exceeds the limit of {}",
+            limits.max_block_range
+        )));
+    }
     let address_filter: HashSet<_> = match &filter.address_filters {
         Some(AddressFilter::Single(address)) => std::iter::once(address).collect(),
         Some(AddressFilter::Many(addresses)) => addresses.iter().collect(),
         None => HashSet::new(),
     };
+    let bloom_filter = BloomFilter::new(filter);
 
     let mut logs: Vec<RpcLog> = Vec::new();
-    // The idea here is to fetch every log and filter by address, if given.
-    // For that, we'll need each block in range, and its transactions,
+    // The idea here is to fetch every log matching the filters from the blocks whose bloom may contain them.
+    // For that, we'll need each candidate block and its transactions,
     // and for each transaction, we'll need its receipts, which
     // contain the actual logs we want.
-    for block_num in from..=to {
-        // Take the header of the block, we
-        // will use it to access the transactions.
-        let block_body = storage
-            .get_block_body(block_num)?
-            .ok_or(RpcErr::Internal(format!(
-                "Could not get body for block {block_num}"
-            )))?;
+    for block_num in bloom_filter.candidate_blocks(from, to, &storage)? {
+        // Take the header of the block, its bloom tells us
+        // whether it's worth looking into its transactions.
         let block_header = storage
             .get_block_header(block_num)?
             .ok_or(RpcErr::Internal(format!(
                 "Could not get header for block {block_num}"
             )))?;
+        if !bloom_filter.matches(&block_header.logs_bloom) {
+            continue;
+        }
+        let block_body = storage
+            .get_block_body(block_num)?
+            .ok_or(RpcErr::Internal(format!(
+                "Could not get body for block {block_num}"
+            )))?;
         let block_hash = block_header.compute_block_hash();
 
         let mut block_log_index = 0_u64;
@@ -155,7 +337,15 @@ pub(crate) fn fetch_logs_with_filter(
 
             if receipt.succeeded {
                 for log in &receipt.logs {
-                    if address_filter.is_empty() || address_filter.contains(&log.address) {
+                    if (address_filter.is_empty() || address_filter.contains(&log.address))
+                        && matches_topics(&filter.topics, &log.topics)
+                    {
+                        if logs.len() == limits.max_results {
+                            return Err(RpcErr::BadParams(format!(
+                                "Query returned more than {} results",
+                                limits.max_results
+                            )));
+                        }
                         // Some extra data is needed when
                         // forming the RPC response.
                         logs.push(RpcLog {
@@ -173,41 +363,117 @@ pub(crate) fn fetch_logs_with_filter(
             }
         }
     }
-    // Now that we have the logs filtered by address,
-    // we still need to filter by topics if it was a given parameter.
 
-    let filtered_logs = if filter.topics.is_empty() {
-        logs
-    } else {
-        logs.into_iter()
-            .filter(|rpc_log| {
-                if filter.topics.len() > rpc_log.log.topics.len() {
-                    return false;
-                }
-                for (i, topic_filter) in filter.topics.iter().enumerate() {
-                    match topic_filter {
-                        TopicFilter::Topic(t) => {
-                            if let Some(topic) = t {
-                                if rpc_log.log.topics[i] != *topic {
-                                    return false;
-                                }
-                            }
-                        }
-                        TopicFilter::Topics(sub_topics) => {
-                            if !sub_topics.is_empty()
-                                && !sub_topics
-                                    .iter()
-                                    .any(|st| st.map_or(true, |t| rpc_log.log.topics[i] == t))
-                            {
-                                return false;
-                            }
-                        }
-                    }
-                }
-                true
-            })
-            .collect::<Vec<RpcLog>>()
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_core::{
+        types::{
+            Block, BlockBody, BlockHeader, LegacyTransaction, Log, Receipt, Transaction, TxType,
+        },
+        Address,
     };
+    use ethrex_storage::{EngineType, BLOOM_BITS_CONFIRMATIONS};
+
+    const LOG_BLOCKS: [BlockNumber; 2] = [10, BLOOM_BITS_SECTION_SIZE + 100];
+
+    /// Builds a chain with one indexed section, where only the blocks in `LOG_BLOCKS` emit a log
+    fn chain_with_logs(address: Address, topic: H256) -> (Store, BlockNumber) {
+        let storage = Store::new("in-mem", EngineType::InMemory).unwrap();
+        let head = BLOOM_BITS_SECTION_SIZE + BLOOM_BITS_CONFIRMATIONS;
+        for number in 0..=head {
+            let mut header = BlockHeader {
+                number,
+                ..Default::default()
+            };
+            let mut body = BlockBody::default();
+            let mut receipts = Vec::new();
+            if LOG_BLOCKS.contains(&number) {
+                let log = Log {
+                    address,
+                    topics: vec![topic],
+                    data: Default::default(),
+                };
+                let receipt = Receipt::new(TxType::Legacy, true, 21000, vec![log]);
+                header.logs_bloom = receipt.bloom;
+                body.transactions
+                    .push(Transaction::LegacyTransaction(LegacyTransaction {
+                        nonce: number,
+                        ..Default::default()
+                    }));
+                receipts.push(receipt);
+            }
+            let block_hash = header.compute_block_hash();
+            storage.add_block(Block::new(header, body)).unwrap();
+            storage.add_receipts(block_hash, receipts).unwrap();
+            storage.set_canonical_block(number, block_hash).unwrap();
+        }
+        storage.update_latest_block_number(head).unwrap();
+        storage.update_bloom_bits_index(head, head).unwrap();
+        assert_eq!(storage.get_bloom_bits_sections().unwrap(), 1);
+        (storage, head)
+    }
+
+    fn logs_filter(to: BlockNumber, address: Address, topic: H256) -> LogsFilter {
+        LogsFilter {
+            from_block: BlockIdentifier::Number(0),
+            to_block: BlockIdentifier::Number(to),
+            address_filters: Some(AddressFilter::Single(address)),
+            topics: vec![TopicFilter::Topic(Some(topic))],
+        }
+    }
 
-    Ok(filtered_logs)
+    #[test]
+    fn logs_are_found_in_indexed_and_unindexed_blocks() {
+        let (address, topic) = (Address::random(), H256::random());
+        let (storage, head) = chain_with_logs(address, topic);
+
+        let filter = logs_filter(head, address, topic);
+        let logs =
+            fetch_logs_with_filter(&filter, storage.clone(), &LogsLimits::default()).unwrap();
+        let block_numbers: Vec<_> = logs.iter().map(|log| log.block_number).collect();
+        assert_eq!(block_numbers, LOG_BLOCKS);
+
+        let filter = logs_filter(head, address, H256::random());
+        let logs =
+            fetch_logs_with_filter(&filter, storage.clone(), &LogsLimits::default()).unwrap();
+        assert!(logs.is_empty());
+
+        let filter = logs_filter(head, Address::random(), topic);
+        let logs = fetch_logs_with_filter(&filter, storage, &LogsLimits::default()).unwrap();
+        assert!(logs.is_empty());
+    }
+
+    #[test]
+    fn logs_queries_over_the_limits_fail() {
+        let (address, topic) = (Address::random(), H256::random());
+        let (storage, head) = chain_with_logs(address, topic);
+        let filter = logs_filter(head, address, topic);
+
+        let limits = LogsLimits {
+            max_block_range: head,
+            ..Default::default()
+        };
+        assert!(fetch_logs_with_filter(&filter, storage.clone(), &limits).is_err());
+
+        let limits = LogsLimits {
+            max_results: 1,
+            ..Default::default()
+        };
+        assert!(fetch_logs_with_filter(&filter, storage.clone(), &limits).is_err());
+
+        let limits = LogsLimits {
+            max_results: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            fetch_logs_with_filter(&filter, storage, &limits)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
 }
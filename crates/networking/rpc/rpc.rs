@@ -5,12 +5,16 @@ use axum_extra::{
     TypedHeader,
 };
 use bytes::Bytes;
+use debug::{
+    execution_witness::ExecutionWitnessRequest,
+    trace::{TraceBlockByHashRequest, TraceBlockByNumberRequest, TraceTransactionRequest},
+};
 use engine::{
     exchange_transition_config::ExchangeTransitionConfigV1Req,
     fork_choice::{ForkChoiceUpdatedV1, ForkChoiceUpdatedV2, ForkChoiceUpdatedV3},
     payload::{
-        GetPayloadV1Request, GetPayloadV2Request, GetPayloadV3Request, NewPayloadV1Request,
-        NewPayloadV2Request, NewPayloadV3Request,
+        GetPayloadV1Request, GetPayloadV2Request, GetPayloadV3Request, GetPayloadV4Request,
+        NewPayloadV1Request, NewPayloadV2Request, NewPayloadV3Request, NewPayloadV4Request,
     },
     ExchangeCapabilitiesRequest,
 };
@@ -35,7 +39,10 @@ use eth::{
         GetTransactionByHashRequest, GetTransactionReceiptRequest,
     },
 };
-use ethrex_net::sync::SyncManager;
+use ethrex_net::{
+    peer_manager::PeerManager,
+    sync::{SyncManager, SyncStatus},
+};
 use serde_json::Value;
 use std::{
     collections::HashMap,
@@ -51,14 +58,20 @@ use utils::{
     RpcErr, RpcErrorMetadata, RpcErrorResponse, RpcNamespace, RpcRequest, RpcRequestId,
     RpcSuccessResponse,
 };
+use websocket::SubscriptionNotifier;
+
+pub use eth::logs::LogsLimits;
 mod admin;
 mod authentication;
+mod debug;
 pub mod engine;
 mod eth;
 mod net;
+mod txpool;
 pub mod types;
 pub mod utils;
 mod web3;
+mod websocket;
 
 use axum::extract::State;
 use ethrex_net::types::Node;
@@ -71,6 +84,12 @@ pub struct RpcApiContext {
     local_p2p_node: Node,
     active_filters: ActiveFilters,
     syncer: Arc<TokioMutex<SyncManager>>,
+    /// Progress of the syncer's current cycle, readable while the syncer is locked
+    sync_status: SyncStatus,
+    subscription_notifier: SubscriptionNotifier,
+    peer_manager: PeerManager,
+    batch_request_limit: usize,
+    logs_limits: LogsLimits,
 }
 
 trait RpcHandler: Sized {
@@ -84,6 +103,9 @@ trait RpcHandler: Sized {
     fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr>;
 }
 
+/// Default maximum amount of requests allowed in a single batch
+pub const DEFAULT_BATCH_REQUEST_LIMIT: usize = 1000;
+
 const FILTER_DURATION: Duration = {
     if cfg!(test) {
         Duration::from_secs(1)
@@ -92,6 +114,7 @@ const FILTER_DURATION: Duration = {
     }
 };
 
+#[allow(clippy::too_many_arguments)]
 pub async fn start_api(
     http_addr: SocketAddr,
     authrpc_addr: SocketAddr,
@@ -99,6 +122,9 @@ pub async fn start_api(
     jwt_secret: Bytes,
     local_p2p_node: Node,
     syncer: SyncManager,
+    peer_manager: PeerManager,
+    batch_request_limit: usize,
+    logs_limits: LogsLimits,
 ) {
     // TODO: Refactor how filters are handled,
     // filters are used by the filters endpoints (eth_newFilter, eth_getFilterChanges, ...etc)
@@ -108,7 +134,12 @@ pub async fn start_api(
         jwt_secret,
         local_p2p_node,
         active_filters: active_filters.clone(),
+        sync_status: syncer.status(),
         syncer: Arc::new(TokioMutex::new(syncer)),
+        subscription_notifier: SubscriptionNotifier::default(),
+        peer_manager,
+        batch_request_limit,
+        logs_limits,
     };
 
     // Periodically clean up the active filters for the filters endpoints.
@@ -124,7 +155,10 @@ pub async fn start_api(
     });
 
     let http_router = Router::new()
-        .route("/", post(handle_http_request))
+        .route(
+            "/",
+            post(handle_http_request).get(websocket::handle_websocket),
+        )
         .with_state(service_context.clone());
     let http_listener = TcpListener::bind(http_addr).await.unwrap();
 
@@ -141,6 +175,7 @@ pub async fn start_api(
         .into_future();
 
     info!("Starting HTTP server at {http_addr}");
+    info!("Accepting WebSocket connections at ws://{http_addr}");
     info!("Starting Auth-RPC server at {}", authrpc_addr);
 
     let _ = tokio::try_join!(authrpc_server, http_server)
@@ -157,9 +192,11 @@ pub async fn handle_http_request(
     State(service_context): State<RpcApiContext>,
     body: String,
 ) -> Json<Value> {
-    let req: RpcRequest = serde_json::from_str(&body).unwrap();
-    let res = map_http_requests(&req, service_context);
-    rpc_response(req.id, res)
+    Json(handle_request_body(
+        &body,
+        service_context.batch_request_limit,
+        |req| map_http_requests(req, service_context.clone()),
+    ))
 }
 
 pub async fn handle_authrpc_request(
@@ -167,13 +204,105 @@ pub async fn handle_authrpc_request(
     auth_header: Option<TypedHeader<Authorization<Bearer>>>,
     body: String,
 ) -> Json<Value> {
-    let req: RpcRequest = serde_json::from_str(&body).unwrap();
-    match authenticate(&service_context.jwt_secret, auth_header) {
-        Err(error) => rpc_response(req.id, Err(error)),
-        Ok(()) => {
+    let authentication = authenticate(&service_context.jwt_secret, auth_header);
+    Json(handle_request_body(
+        &body,
+        service_context.batch_request_limit,
+        |req| match &authentication {
+            Err(error) => Err(error.clone()),
             // Proceed with the request
-            let res = map_authrpc_requests(&req, service_context);
-            rpc_response(req.id, res)
+            Ok(()) => map_authrpc_requests(req, service_context.clone()),
+        },
+    ))
+}
+
+/// Parses a JSON-RPC body, which can contain either a single request or a batch of them,
+/// and handles each request with the given handler.
+/// Returns the response object, or the list of responses in case of a batch
+fn handle_request_body(
+    body: &str,
+    batch_request_limit: usize,
+    mut handler: impl FnMut(&RpcRequest) -> Result<Value, RpcErr>,
+) -> Value {
+    let body: Value = match serde_json::from_str(body) {
+        Ok(body) => body,
+        Err(error) => {
+            return rpc_response(
+                RpcRequestId::Null,
+                Err(RpcErr::ParseError(error.to_string())),
+            )
+            .0
+        }
+    };
+    match body {
+        Value::Array(requests) => {
+            if requests.is_empty() {
+                return rpc_response(
+                    RpcRequestId::Null,
+                    Err(RpcErr::InvalidRequest("empty batch".to_owned())),
+                )
+                .0;
+            }
+            if requests.len() > batch_request_limit {
+                return rpc_response(
+                    RpcRequestId::Null,
+                    Err(RpcErr::InvalidRequest(format!(
+                        "batch of {} requests exceeds the limit of {batch_request_limit}",
+                        requests.len()
+                    ))),
+                )
+                .0;
+            }
+            Value::Array(
+                requests
+                    .into_iter()
+                    .filter_map(|request| handle_batch_entry(request, &mut handler))
+                    .collect(),
+            )
+        }
+        request => handle_single_request(request, &mut handler),
+    }
+}
+
+/// Handles a request of a batch, returns None for notifications (requests without an id),
+/// which are handled but not replied to
+fn handle_batch_entry(
+    mut request: Value,
+    handler: &mut impl FnMut(&RpcRequest) -> Result<Value, RpcErr>,
+) -> Option<Value> {
+    let Some(entry) = request.as_object_mut() else {
+        return Some(handle_single_request(request, handler));
+    };
+    if entry.contains_key("id") {
+        return Some(handle_single_request(request, handler));
+    }
+    entry.insert("id".to_owned(), Value::Null);
+    match serde_json::from_value::<RpcRequest>(request.clone()) {
+        Ok(req) => {
+            let _ = handler(&req);
+            None
+        }
+        // Invalid requests are replied to even if they have no id
+        Err(_) => Some(handle_single_request(request, handler)),
+    }
+}
+
+fn handle_single_request(
+    request: Value,
+    handler: &mut impl FnMut(&RpcRequest) -> Result<Value, RpcErr>,
+) -> Value {
+    match serde_json::from_value::<RpcRequest>(request.clone()) {
+        Ok(req) => {
+            let res = handler(&req);
+            rpc_response(req.id, res).0
+        }
+        Err(error) => {
+            // Reply with the request id if it can be read
+            let id = request
+                .get("id")
+                .and_then(|id| serde_json::from_value(id.clone()).ok())
+                .unwrap_or(RpcRequestId::Null);
+            rpc_response(id, Err(RpcErr::InvalidRequest(error.to_string()))).0
         }
     }
 }
@@ -186,6 +315,7 @@ pub fn map_http_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Val
         Ok(RpcNamespace::Debug) => map_debug_requests(req, context),
         Ok(RpcNamespace::Web3) => map_web3_requests(req, context),
         Ok(RpcNamespace::Net) => map_net_requests(req, context),
+        Ok(RpcNamespace::TxPool) => map_txpool_requests(req, context),
         _ => Err(RpcErr::MethodNotFound(req.method.clone())),
     }
 }
@@ -195,6 +325,7 @@ pub fn map_authrpc_requests(req: &RpcRequest, context: RpcApiContext) -> Result<
     match req.namespace() {
         Ok(RpcNamespace::Engine) => map_engine_requests(req, context),
         Ok(RpcNamespace::Eth) => map_eth_requests(req, context),
+        Ok(RpcNamespace::Admin) => map_authrpc_admin_requests(req, context),
         _ => Err(RpcErr::MethodNotFound(req.method.clone())),
     }
 }
@@ -235,9 +366,12 @@ pub fn map_eth_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Valu
         "eth_uninstallFilter" => {
             DeleteFilterRequest::stateful_call(req, context.storage, context.active_filters)
         }
-        "eth_getFilterChanges" => {
-            FilterChangesRequest::stateful_call(req, context.storage, context.active_filters)
-        }
+        "eth_getFilterChanges" => FilterChangesRequest::stateful_call(
+            req,
+            context.storage,
+            context.active_filters,
+            &context.logs_limits,
+        ),
         "eth_sendRawTransaction" => SendRawTransactionRequest::call(req, context),
         "eth_getProof" => GetProofRequest::call(req, context),
         "eth_gasPrice" => GasPrice::call(req, context),
@@ -251,6 +385,10 @@ pub fn map_debug_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Va
         "debug_getRawBlock" => GetRawBlockRequest::call(req, context),
         "debug_getRawTransaction" => GetRawTransaction::call(req, context),
         "debug_getRawReceipts" => GetRawReceipts::call(req, context),
+        "debug_traceTransaction" => TraceTransactionRequest::call(req, context),
+        "debug_traceBlockByNumber" => TraceBlockByNumberRequest::call(req, context),
+        "debug_traceBlockByHash" => TraceBlockByHashRequest::call(req, context),
+        "debug_executionWitness" => ExecutionWitnessRequest::call(req, context),
         unknown_debug_method => Err(RpcErr::MethodNotFound(unknown_debug_method.to_owned())),
     }
 }
@@ -261,12 +399,14 @@ pub fn map_engine_requests(req: &RpcRequest, context: RpcApiContext) -> Result<V
         "engine_forkchoiceUpdatedV1" => ForkChoiceUpdatedV1::call(req, context),
         "engine_forkchoiceUpdatedV2" => ForkChoiceUpdatedV2::call(req, context),
         "engine_forkchoiceUpdatedV3" => ForkChoiceUpdatedV3::call(req, context),
+        "engine_newPayloadV4" => NewPayloadV4Request::call(req, context),
         "engine_newPayloadV3" => NewPayloadV3Request::call(req, context),
         "engine_newPayloadV2" => NewPayloadV2Request::call(req, context),
         "engine_newPayloadV1" => NewPayloadV1Request::call(req, context),
         "engine_exchangeTransitionConfigurationV1" => {
             ExchangeTransitionConfigV1Req::call(req, context)
         }
+        "engine_getPayloadV4" => GetPayloadV4Request::call(req, context),
         "engine_getPayloadV3" => GetPayloadV3Request::call(req, context),
         "engine_getPayloadV2" => GetPayloadV2Request::call(req, context),
         "engine_getPayloadV1" => GetPayloadV1Request::call(req, context),
@@ -277,10 +417,33 @@ pub fn map_engine_requests(req: &RpcRequest, context: RpcApiContext) -> Result<V
 pub fn map_admin_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
     match req.method.as_str() {
         "admin_nodeInfo" => admin::node_info(context.storage, context.local_p2p_node),
+        "admin_peers" => admin::peers(context.peer_manager),
         unknown_admin_method => Err(RpcErr::MethodNotFound(unknown_admin_method.to_owned())),
     }
 }
 
+/// Handle admin requests that change the node's peers, which are only served to authenticated clients
+pub fn map_authrpc_admin_requests(
+    req: &RpcRequest,
+    context: RpcApiContext,
+) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "admin_addPeer" => admin::AddPeerRequest::call(req, context),
+        "admin_removePeer" => admin::RemovePeerRequest::call(req, context),
+        _ => map_admin_requests(req, context),
+    }
+}
+
+pub fn map_txpool_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "txpool_content" => txpool::ContentRequest::call(req, context),
+        "txpool_contentFrom" => txpool::ContentFromRequest::call(req, context),
+        "txpool_status" => txpool::StatusRequest::call(req, context),
+        "txpool_inspect" => txpool::InspectRequest::call(req, context),
+        unknown_txpool_method => Err(RpcErr::MethodNotFound(unknown_txpool_method.to_owned())),
+    }
+}
+
 pub fn map_web3_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
     match req.method.as_str() {
         "web3_clientVersion" => web3::client_version(req, context.storage),
@@ -323,8 +486,18 @@ where
 mod tests {
     use super::*;
     use crate::utils::test_utils::example_p2p_node;
-    use ethrex_core::types::{ChainConfig, Genesis};
+    use ethrex_core::{
+        types::{
+            Block, ChainConfig, EIP1559Transaction, Genesis, MempoolTransaction, Transaction,
+            TxKind,
+        },
+        Address,
+    };
+    use ethrex_rlp::decode::RLPDecode;
+    use ethrex_rlp::encode::RLPEncode;
     use ethrex_storage::EngineType;
+    use ethrex_vm::{execution_db::ExecutionDB, trace_block, EvmState, GethTrace};
+    use serde_json::json;
     use std::fs::File;
     use std::io::BufReader;
 
@@ -348,15 +521,127 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
         };
         let result = map_http_requests(&request, context);
         let rpc_response = rpc_response(request.id, result);
         let expected_response = to_rpc_response_success_value(
-            r#"{"jsonrpc":"2.0","id":1,"result":{"enode":"enode://d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666@127.0.0.1:30303","id":"d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666","ip":"127.0.0.1","name":"ethrex/0.1.0/rust1.81","ports":{"discovery":30303,"listener":30303},"protocols":{"eth":{"chainId":3151908,"homesteadBlock":0,"daoForkBlock":null,"daoForkSupport":false,"eip150Block":0,"eip155Block":0,"eip158Block":0,"byzantiumBlock":0,"constantinopleBlock":0,"petersburgBlock":0,"istanbulBlock":0,"muirGlacierBlock":null,"berlinBlock":0,"londonBlock":0,"arrowGlacierBlock":null,"grayGlacierBlock":null,"mergeNetsplitBlock":0,"shanghaiTime":0,"cancunTime":0,"pragueTime":1718232101,"verkleTime":null,"terminalTotalDifficulty":0,"terminalTotalDifficultyPassed":true}}}}"#,
+            r#"{"jsonrpc":"2.0","id":1,"result":{"enode":"enode://d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666@127.0.0.1:30303","id":"d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666","ip":"127.0.0.1","name":"ethrex/0.1.0/rust1.81","ports":{"discovery":30303,"listener":30303},"protocols":{"eth":{"chainId":3151908,"homesteadBlock":0,"daoForkBlock":null,"daoForkSupport":false,"eip150Block":0,"eip155Block":0,"eip158Block":0,"byzantiumBlock":0,"constantinopleBlock":0,"petersburgBlock":0,"istanbulBlock":0,"muirGlacierBlock":null,"berlinBlock":0,"londonBlock":0,"arrowGlacierBlock":null,"grayGlacierBlock":null,"mergeNetsplitBlock":0,"shanghaiTime":0,"cancunTime":0,"pragueTime":1718232101,"verkleTime":null,"terminalTotalDifficulty":0,"terminalTotalDifficultyPassed":true,"depositContractAddress":"0x0000000000000000000000000000000000000000"}}}}"#,
         );
         assert_eq!(rpc_response.to_string(), expected_response.to_string())
     }
 
+    #[test]
+    fn admin_peer_requests() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let peer_manager = PeerManager::default();
+        let context = RpcApiContext {
+            local_p2p_node: example_p2p_node(),
+            storage,
+            jwt_secret: Default::default(),
+            active_filters: Default::default(),
+            syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: peer_manager.clone(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
+        };
+        let enode = example_p2p_node().enode_url();
+        let parse = |method: &str, params: Value| -> RpcRequest {
+            let body = json!({"jsonrpc":"2.0", "method":method, "params":params, "id":1});
+            serde_json::from_value(body).unwrap()
+        };
+        let request = |method: &str, params: Value| -> Result<Value, RpcErr> {
+            map_authrpc_requests(&parse(method, params), context.clone())
+        };
+
+        // Peers can only be managed through the authenticated endpoint
+        for method in ["admin_addPeer", "admin_removePeer"] {
+            assert!(matches!(
+                map_http_requests(&parse(method, json!([enode])), context.clone()),
+                Err(RpcErr::MethodNotFound(_))
+            ));
+        }
+        assert!(peer_manager.static_peers_to_dial().is_empty());
+        assert_eq!(
+            request("admin_addPeer", json!([enode])).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            peer_manager.static_peers_to_dial(),
+            vec![example_p2p_node()]
+        );
+        // The peer is not connected yet
+        assert_eq!(request("admin_peers", json!([])).unwrap(), json!([]));
+        assert_eq!(
+            request("admin_removePeer", json!([enode])).unwrap(),
+            json!(true)
+        );
+        assert!(peer_manager.static_peers_to_dial().is_empty());
+        assert!(matches!(
+            request("admin_addPeer", json!(["enode://invalid"])),
+            Err(RpcErr::BadParams(_))
+        ));
+    }
+
+    fn echo_method(req: &RpcRequest) -> Result<Value, RpcErr> {
+        Ok(Value::String(req.method.clone()))
+    }
+
+    #[test]
+    fn batch_request() {
+        let body = r#"[{"jsonrpc":"2.0","id":1,"method":"eth_chainId","params":[]},{"jsonrpc":"2.0","id":"two","method":"eth_blockNumber","params":[]},{"jsonrpc":"2.0","id":3}]"#;
+        let response = handle_request_body(body, 3, echo_method);
+        let expected_response = serde_json::json!([
+            {"jsonrpc":"2.0","id":1,"result":"eth_chainId"},
+            {"jsonrpc":"2.0","id":"two","result":"eth_blockNumber"},
+            {"jsonrpc":"2.0","id":3,"error":{"code":-32600,"message":"Invalid request: missing field `method`"}}
+        ]);
+        assert_eq!(response, expected_response);
+    }
+
+    #[test]
+    fn batch_request_notifications() {
+        let body = r#"[{"jsonrpc":"2.0","id":1,"method":"eth_chainId"},{"jsonrpc":"2.0","method":"eth_blockNumber"},{"jsonrpc":"2.0"}]"#;
+        let mut handled = vec![];
+        let response = handle_request_body(body, 3, |req| {
+            handled.push(req.method.clone());
+            echo_method(req)
+        });
+        // The notification is handled but only the request and the invalid entry get a response
+        assert_eq!(handled, vec!["eth_chainId", "eth_blockNumber"]);
+        let expected_response = serde_json::json!([
+            {"jsonrpc":"2.0","id":1,"result":"eth_chainId"},
+            {"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"Invalid request: missing field `method`"}}
+        ]);
+        assert_eq!(response, expected_response);
+    }
+
+    #[test]
+    fn batch_request_errors() {
+        // Malformed body
+        let response = handle_request_body(r#"{"jsonrpc":"2.0","#, 3, echo_method);
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], -32700);
+        // Empty batch
+        let response = handle_request_body("[]", 3, echo_method);
+        assert_eq!(response["error"]["code"], -32600);
+        // Batch over the limit
+        let body = r#"[{"jsonrpc":"2.0","id":1,"method":"eth_chainId"},{"jsonrpc":"2.0","id":2,"method":"eth_chainId"}]"#;
+        let response = handle_request_body(body, 1, echo_method);
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], -32600);
+        // Not a request object
+        let response = handle_request_body("1", 3, echo_method);
+        assert_eq!(response["error"]["code"], -32600);
+    }
+
     // Reads genesis file taken from https://github.com/ethereum/execution-apis/blob/main/tests/genesis.json
     fn read_execution_api_genesis_file() -> Genesis {
         let file = File::open("../../../test_data/genesis-execution-api.json")
@@ -386,6 +671,11 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
         };
         let result = map_http_requests(&request, context);
         let response = rpc_response(request.id, result);
@@ -416,6 +706,11 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
         };
         let result = map_http_requests(&request, context);
         let response =
@@ -433,6 +728,234 @@ mod tests {
         )
     }
 
+    #[test]
+    fn txpool_content_and_status() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let sender = Address::from_low_u64_be(0xaa);
+        let recipient = Address::from_low_u64_be(0xbb);
+        // Nonce 1 is missing, so the transaction with nonce 2 is queued
+        for nonce in [0, 2] {
+            let tx = Transaction::EIP1559Transaction(EIP1559Transaction {
+                nonce,
+                max_fee_per_gas: 7,
+                gas_limit: 21000,
+                to: TxKind::Call(recipient),
+                value: 100.into(),
+                ..Default::default()
+            });
+            storage
+                .add_transaction_to_pool(tx.compute_hash(), MempoolTransaction::new(tx, sender), 0)
+                .unwrap();
+        }
+        let context = RpcApiContext {
+            local_p2p_node: example_p2p_node(),
+            storage,
+            jwt_secret: Default::default(),
+            active_filters: Default::default(),
+            syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
+        };
+        let call = |method: &str, params: &str| {
+            let body =
+                format!(r#"{{"jsonrpc":"2.0","id":1,"method":"{method}","params":[{params}]}}"#);
+            let request: RpcRequest = serde_json::from_str(&body).unwrap();
+            map_http_requests(&request, context.clone()).expect("Request failed")
+        };
+
+        let status = call("txpool_status", "");
+        assert_eq!(status, json!({"pending": "0x1", "queued": "0x1"}));
+
+        let sender_key = format!("{sender:#x}");
+        let content = call("txpool_content", "");
+        assert_eq!(content["pending"][&sender_key]["0"]["nonce"], "0x0");
+        assert_eq!(content["pending"][&sender_key]["0"]["from"], sender_key);
+        assert_eq!(
+            content["pending"][&sender_key]["0"]["blockHash"],
+            Value::Null
+        );
+        assert_eq!(content["queued"][&sender_key]["2"]["nonce"], "0x2");
+
+        let content = call("txpool_contentFrom", &format!(r#""{sender_key}""#));
+        assert_eq!(content["pending"]["0"]["nonce"], "0x0");
+        assert!(
+            call("txpool_contentFrom", &format!(r#""{:#x}""#, recipient))["pending"]
+                .as_object()
+                .unwrap()
+                .is_empty()
+        );
+
+        let inspect = call("txpool_inspect", "");
+        assert_eq!(
+            inspect["queued"][&sender_key]["2"],
+            format!("{recipient:#x}: 100 wei + 21000 gas × 7 wei")
+        );
+    }
+
+    // Reads the blocks from the chain file that goes along with the execution api genesis file
+    fn read_execution_api_chain_file() -> Vec<Block> {
+        let mut buf =
+            std::fs::read("../../../test_data/chain.rlp").expect("Failed to read chain file");
+        let mut blocks = Vec::new();
+        while !buf.is_empty() {
+            let (block, rest) = Block::decode_unfinished(&buf).expect("Failed to decode block");
+            blocks.push(block);
+            buf = rest.to_vec();
+        }
+        blocks
+    }
+
+    #[test]
+    fn debug_trace_block_and_transaction() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(read_execution_api_genesis_file())
+            .expect("Failed to add genesis block to DB");
+        let blocks = read_execution_api_chain_file();
+        for block in blocks.iter() {
+            ethrex_blockchain::add_block(block, &storage).expect("Failed to add block");
+            storage
+                .set_canonical_block(block.header.number, block.hash())
+                .unwrap();
+            storage
+                .update_latest_block_number(block.header.number)
+                .unwrap();
+        }
+        let block = blocks
+            .iter()
+            .find(|block| block.body.transactions.len() > 1)
+            .expect("Chain file has no blocks with multiple transactions");
+        let context = RpcApiContext {
+            local_p2p_node: example_p2p_node(),
+            storage: storage.clone(),
+            jwt_secret: Default::default(),
+            active_filters: Default::default(),
+            syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
+        };
+
+        // Trace the whole block with the call tracer
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"debug_traceBlockByNumber","params":["{:#x}",{{"tracer":"callTracer"}}]}}"#,
+            block.header.number
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let traces = map_http_requests(&request, context.clone()).expect("Request failed");
+        let traces = traces.as_array().expect("Expected a list of traces");
+        assert_eq!(traces.len(), block.body.transactions.len());
+        for (trace, tx) in traces.iter().zip(block.body.transactions.iter()) {
+            assert_eq!(trace["txHash"], format!("{:#x}", tx.compute_hash()));
+            assert_eq!(trace["result"]["from"], format!("{:#x}", tx.sender()));
+        }
+
+        // Trace the last transaction of the block with the default struct logger
+        let tx_hash = block.body.transactions.last().unwrap().compute_hash();
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"debug_traceTransaction","params":["{tx_hash:#x}"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let trace = map_http_requests(&request, context.clone()).expect("Request failed");
+        assert_eq!(trace["failed"], false);
+        assert!(!trace["structLogs"].as_array().unwrap().is_empty());
+        // The struct logger and the call tracer must agree on the gas used by the transaction
+        let gas_used = traces.last().unwrap()["result"]["gasUsed"]
+            .as_str()
+            .unwrap();
+        assert_eq!(
+            trace["gas"].as_u64(),
+            u64::from_str_radix(gas_used.trim_start_matches("0x"), 16).ok()
+        );
+
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"debug_traceTransaction","params":["{tx_hash:#x}",{{"tracer":"prestateTracer","tracerConfig":{{"diffMode":true}}}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let trace = map_http_requests(&request, context).expect("Request failed");
+        let sender = format!("{:#x}", block.body.transactions.last().unwrap().sender());
+        let pre_nonce = trace["pre"][&sender]["nonce"].as_u64().unwrap();
+        let post_nonce = trace["post"][&sender]["nonce"].as_u64().unwrap();
+        assert_eq!(post_nonce, pre_nonce + 1);
+    }
+
+    #[test]
+    fn debug_execution_witness() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(read_execution_api_genesis_file())
+            .expect("Failed to add genesis block to DB");
+        let blocks = read_execution_api_chain_file();
+        for block in blocks.iter() {
+            ethrex_blockchain::add_block(block, &storage).expect("Failed to add block");
+            storage
+                .set_canonical_block(block.header.number, block.hash())
+                .unwrap();
+            storage
+                .update_latest_block_number(block.header.number)
+                .unwrap();
+        }
+        let block = blocks
+            .iter()
+            .find(|block| block.body.transactions.len() > 1)
+            .expect("Chain file has no blocks with multiple transactions");
+        let context = RpcApiContext {
+            local_p2p_node: example_p2p_node(),
+            storage: storage.clone(),
+            jwt_secret: Default::default(),
+            active_filters: Default::default(),
+            syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
+        };
+
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"debug_executionWitness","params":["{:#x}"]}}"#,
+            block.header.number
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let witness = map_http_requests(&request, context).expect("Request failed");
+        assert!(!witness["state"].as_array().unwrap().is_empty());
+        let keys = witness["keys"].as_array().unwrap();
+        for tx in block.body.transactions.iter() {
+            assert!(keys.contains(&Value::String(format!("{:#x}", tx.sender()))));
+        }
+        let parent_header = storage
+            .get_block_header_by_hash(block.header.parent_hash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            witness["headers"][0],
+            format!("0x{}", hex::encode(parent_header.encode_to_vec()))
+        );
+
+        // The witness must hold everything needed to re-execute the block without the store
+        let db = ExecutionDB::from_exec(std::slice::from_ref(block), &storage).unwrap();
+        db.build_tries().expect("Invalid witness tries");
+        let mut state = EvmState::from(db);
+        let traces = trace_block(block, &mut state, &Default::default())
+            .expect("Stateless execution failed");
+        let gas_used: u64 = traces
+            .into_iter()
+            .map(|(_, trace)| match trace {
+                GethTrace::Default(frame) => frame.gas,
+                _ => unreachable!(),
+            })
+            .sum();
+        assert_eq!(gas_used, block.header.gas_used);
+    }
+
     fn example_chain_config() -> ChainConfig {
         ChainConfig {
             chain_id: 3151908_u64,
@@ -476,6 +999,11 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
         };
         // Process request
         let result = map_http_requests(&request, context);
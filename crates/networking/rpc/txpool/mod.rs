@@ -0,0 +1,174 @@
+use std::collections::{BTreeMap, HashMap};
+
+use ethrex_core::{
+    types::{BlockHash, MempoolTransaction, Transaction, TxKind},
+    Address, H256,
+};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{utils::RpcErr, RpcApiContext, RpcHandler};
+
+pub struct ContentRequest;
+
+pub struct ContentFromRequest {
+    pub address: Address,
+}
+
+pub struct StatusRequest;
+
+pub struct InspectRequest;
+
+/// Transactions of a sub-pool grouped by sender and indexed by nonce, as returned by geth
+type SubPool<T> = BTreeMap<String, BTreeMap<String, T>>;
+
+/// Pool content split between executable and nonce-gapped transactions
+#[derive(Serialize)]
+struct PoolContent<T> {
+    pending: T,
+    queued: T,
+}
+
+/// A transaction in the pool, in the same format as `eth_getTransactionByHash` returns it
+/// Block fields are always null as the transaction is not included in any block yet
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcPooledTransaction {
+    #[serde(flatten)]
+    tx: Transaction,
+    block_hash: Option<BlockHash>,
+    block_number: Option<String>,
+    transaction_index: Option<String>,
+    from: Address,
+    hash: H256,
+}
+
+impl RpcPooledTransaction {
+    fn new(tx: MempoolTransaction) -> Self {
+        Self {
+            from: tx.sender(),
+            hash: tx.compute_hash(),
+            tx: tx.into(),
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+        }
+    }
+}
+
+/// Indexes the transactions of each sender by nonce, using the decimal representation of the nonce as key
+fn by_nonce<T>(
+    txs: Vec<MempoolTransaction>,
+    format: &impl Fn(MempoolTransaction) -> T,
+) -> BTreeMap<String, T> {
+    txs.into_iter()
+        .map(|tx| (tx.nonce().to_string(), format(tx)))
+        .collect()
+}
+
+fn by_sender<T>(
+    sub_pool: HashMap<Address, Vec<MempoolTransaction>>,
+    format: impl Fn(MempoolTransaction) -> T,
+) -> SubPool<T> {
+    sub_pool
+        .into_iter()
+        .map(|(sender, txs)| (format!("{sender:#x}"), by_nonce(txs, &format)))
+        .collect()
+}
+
+/// Summarizes a transaction as `<recipient>: <value> wei + <gas limit> gas × <gas price> wei`
+fn inspect(tx: MempoolTransaction) -> String {
+    let recipient = match tx.to() {
+        TxKind::Call(address) => format!("{address:#x}"),
+        TxKind::Create => "contract creation".to_string(),
+    };
+    format!(
+        "{recipient}: {} wei + {} gas × {} wei",
+        tx.value(),
+        tx.gas_limit(),
+        tx.gas_fee_cap()
+    )
+}
+
+impl RpcHandler for ContentRequest {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {})
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested mempool content");
+        let content = context.storage.get_pool_content()?;
+        let content = PoolContent {
+            pending: by_sender(content.pending, RpcPooledTransaction::new),
+            queued: by_sender(content.queued, RpcPooledTransaction::new),
+        };
+        serde_json::to_value(content).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for ContentFromRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams("Expected 1 param".to_owned()));
+        };
+        Ok(ContentFromRequest {
+            address: serde_json::from_value(params[0].clone())?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested mempool content of account {:#x}", self.address);
+        let mut content = context.storage.get_pool_content()?;
+        let sender_txs = |sub_pool: &mut HashMap<Address, Vec<MempoolTransaction>>| {
+            by_nonce(
+                sub_pool.remove(&self.address).unwrap_or_default(),
+                &RpcPooledTransaction::new,
+            )
+        };
+        let content = PoolContent {
+            pending: sender_txs(&mut content.pending),
+            queued: sender_txs(&mut content.queued),
+        };
+        serde_json::to_value(content).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for StatusRequest {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {})
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested mempool status");
+        let content = context.storage.get_pool_content()?;
+        let count = |sub_pool: HashMap<Address, Vec<MempoolTransaction>>| {
+            let count: usize = sub_pool.values().map(Vec::len).sum();
+            format!("{count:#x}")
+        };
+        let status = PoolContent {
+            pending: count(content.pending),
+            queued: count(content.queued),
+        };
+        serde_json::to_value(status).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for InspectRequest {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {})
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested mempool inspection");
+        let content = context.storage.get_pool_content()?;
+        let content = PoolContent {
+            pending: by_sender(content.pending, inspect),
+            queued: by_sender(content.queued, inspect),
+        };
+        serde_json::to_value(content).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
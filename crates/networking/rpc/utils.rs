@@ -1,12 +1,12 @@
-use ethrex_storage::error::StoreError;
-use ethrex_vm::EvmError;
+use ethrex_storage::error::{PoolError, StoreError};
+use ethrex_vm::{errors::ExecutionDBError, EvmError};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::authentication::AuthenticationError;
 use ethrex_blockchain::error::MempoolError;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub enum RpcErr {
     MethodNotFound(String),
     WrongParam(String),
@@ -22,6 +22,8 @@ pub enum RpcErr {
     InvalidForkChoiceState(String),
     InvalidPayloadAttributes(String),
     UnknownPayload(String),
+    ParseError(String),
+    InvalidRequest(String),
 }
 
 impl From<RpcErr> for RpcErrorMetadata {
@@ -116,6 +118,16 @@ impl From<RpcErr> for RpcErrorMetadata {
                 data: None,
                 message: format!("Unknown payload: {context}"),
             },
+            RpcErr::ParseError(context) => RpcErrorMetadata {
+                code: -32700,
+                data: None,
+                message: format!("Parse error: {context}"),
+            },
+            RpcErr::InvalidRequest(context) => RpcErrorMetadata {
+                code: -32600,
+                data: None,
+                message: format!("Invalid request: {context}"),
+            },
         }
     }
 }
@@ -131,7 +143,9 @@ impl From<serde_json::Error> for RpcErr {
 impl From<MempoolError> for RpcErr {
     fn from(err: MempoolError) -> Self {
         match err {
-            MempoolError::StoreError(err) => Self::Internal(err.to_string()),
+            MempoolError::StoreError(err) | MempoolError::PoolError(PoolError::Store(err)) => {
+                Self::Internal(err.to_string())
+            }
             other_err => Self::BadParams(other_err.to_string()),
         }
     }
@@ -144,6 +158,7 @@ pub enum RpcNamespace {
     Debug,
     Web3,
     Net,
+    TxPool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -151,6 +166,8 @@ pub enum RpcNamespace {
 pub enum RpcRequestId {
     Number(u64),
     String(String),
+    /// Used when replying to requests whose id could not be read
+    Null,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -172,6 +189,7 @@ impl RpcRequest {
                 "debug" => Ok(RpcNamespace::Debug),
                 "web3" => Ok(RpcNamespace::Web3),
                 "net" => Ok(RpcNamespace::Net),
+                "txpool" => Ok(RpcNamespace::TxPool),
                 _ => Err(RpcErr::MethodNotFound(self.method.clone())),
             }
         } else {
@@ -226,6 +244,12 @@ impl From<EvmError> for RpcErr {
     }
 }
 
+impl From<ExecutionDBError> for RpcErr {
+    fn from(value: ExecutionDBError) -> Self {
+        RpcErr::Vm(value.to_string())
+    }
+}
+
 fn get_message_from_revert_data(_data: &str) -> String {
     // TODO
     // Hive tests are not failing when revert message does not match, but currently it is not matching
@@ -249,10 +273,10 @@ pub mod test_utils {
     use std::{net::SocketAddr, str::FromStr};
 
     use ethrex_core::H512;
-    use ethrex_net::{sync::SyncManager, types::Node};
+    use ethrex_net::{peer_manager::PeerManager, sync::SyncManager, types::Node};
     use ethrex_storage::{EngineType, Store};
 
-    use crate::start_api;
+    use crate::{start_api, LogsLimits, DEFAULT_BATCH_REQUEST_LIMIT};
 
     pub const TEST_GENESIS: &str = include_str!("../../../test_data/genesis-l1.json");
     pub fn example_p2p_node() -> Node {
@@ -294,6 +318,9 @@ pub mod test_utils {
             jwt_secret,
             local_p2p_node,
             SyncManager::dummy(),
+            PeerManager::default(),
+            DEFAULT_BATCH_REQUEST_LIMIT,
+            LogsLimits::default(),
         )
         .await;
     }
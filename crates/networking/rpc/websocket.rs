@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    response::Response,
+};
+use ethrex_core::{
+    types::{BlockHeader, BlockNumber},
+    H256,
+};
+use ethrex_storage::{error::StoreError, Store};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::{
+    eth::logs::{fetch_logs_with_filter, AddressFilter, LogsFilter, LogsLimits, TopicFilter},
+    handle_request_body, map_http_requests,
+    types::block_identifier::BlockIdentifier,
+    utils::RpcErr,
+    RpcApiContext,
+};
+
+/// Amount of new heads a subscriber can fall behind on before missing some
+const NEW_HEADS_CAPACITY: usize = 128;
+/// Maximum amount of blocks notified after a single fork choice update
+const MAX_NOTIFIED_HEADS: usize = 64;
+
+/// Pushes the new canonical heads to the websocket connections
+#[derive(Debug, Clone)]
+pub struct SubscriptionNotifier {
+    new_heads: broadcast::Sender<BlockHeader>,
+}
+
+impl Default for SubscriptionNotifier {
+    fn default() -> Self {
+        Self {
+            new_heads: broadcast::channel(NEW_HEADS_CAPACITY).0,
+        }
+    }
+}
+
+impl SubscriptionNotifier {
+    /// Notifies the blocks that became canonical after a fork choice update, from oldest to newest.
+    /// Only the blocks past the previous head number are notified, except for the new head which is always notified
+    pub fn notify_new_heads(
+        &self,
+        storage: &Store,
+        previous_head_number: BlockNumber,
+        head: BlockHeader,
+    ) -> Result<(), StoreError> {
+        if self.new_heads.receiver_count() == 0 {
+            return Ok(());
+        }
+        let mut headers = vec![head];
+        while headers.len() < MAX_NOTIFIED_HEADS {
+            let oldest = &headers[headers.len() - 1];
+            if oldest.number <= previous_head_number + 1 {
+                break;
+            }
+            match storage.get_block_header_by_hash(oldest.parent_hash)? {
+                Some(parent) => headers.push(parent),
+                None => break,
+            }
+        }
+        for header in headers.into_iter().rev() {
+            // Sending only fails if all connections were closed in the meantime
+            let _ = self.new_heads.send(header);
+        }
+        Ok(())
+    }
+}
+
+/// Subscriptions supported by `eth_subscribe`
+#[derive(Debug, Clone)]
+enum Subscription {
+    NewHeads,
+    Logs {
+        address_filters: Option<AddressFilter>,
+        topics: Vec<TopicFilter>,
+    },
+    NewPendingTransactions,
+}
+
+impl Subscription {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        let kind: String = serde_json::from_value(
+            params
+                .first()
+                .ok_or(RpcErr::MissingParam("subscription kind".to_owned()))?
+                .clone(),
+        )?;
+        match kind.as_str() {
+            "newHeads" => Ok(Subscription::NewHeads),
+            "newPendingTransactions" => Ok(Subscription::NewPendingTransactions),
+            "logs" => {
+                let filter = match params.get(1) {
+                    Some(Value::Object(filter)) => filter.clone(),
+                    None | Some(Value::Null) => Default::default(),
+                    Some(_) => return Err(RpcErr::WrongParam("filter".to_owned())),
+                };
+                let address_filters = match filter.get("address") {
+                    Some(address) => serde_json::from_value(address.clone())
+                        .map_err(|_| RpcErr::WrongParam("address".to_owned()))?,
+                    None => None,
+                };
+                let topics = match filter.get("topics") {
+                    Some(topics) => {
+                        serde_json::from_value::<Option<Vec<TopicFilter>>>(topics.clone())
+                            .map_err(|_| RpcErr::WrongParam("topics".to_owned()))?
+                            .unwrap_or_default()
+                    }
+                    None => Vec::new(),
+                };
+                Ok(Subscription::Logs {
+                    address_filters,
+                    topics,
+                })
+            }
+            unknown => Err(RpcErr::BadParams(format!(
+                "Unsupported subscription: {unknown}"
+            ))),
+        }
+    }
+}
+
+/// Block header along with its hash, as sent to `newHeads` subscribers
+#[derive(Serialize)]
+struct RpcHeader {
+    hash: H256,
+    #[serde(flatten)]
+    header: BlockHeader,
+}
+
+pub async fn handle_websocket(
+    State(service_context): State<RpcApiContext>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_websocket_connection(socket, service_context))
+}
+
+/// Serves the requests received through the websocket and pushes the notifications of its active subscriptions
+async fn handle_websocket_connection(mut socket: WebSocket, context: RpcApiContext) {
+    let mut subscriptions: HashMap<String, Subscription> = HashMap::new();
+    let mut new_heads = context.subscription_notifier.new_heads.subscribe();
+    let mut pending_transactions = context.storage.subscribe_to_mempool();
+    loop {
+        let messages = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(body))) => {
+                    vec![handle_websocket_request(&body, &mut subscriptions, context.clone())]
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered automatically and binary messages are not supported
+                Some(Ok(_)) => Vec::new(),
+            },
+            header = new_heads.recv() => match header {
+                Ok(header) => new_head_notifications(&header, &subscriptions, &context.storage),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Websocket connection fell behind, skipped {skipped} new heads");
+                    Vec::new()
+                }
+                Err(RecvError::Closed) => break,
+            },
+            hash = pending_transactions.recv() => match hash {
+                Ok(hash) => subscriptions
+                    .iter()
+                    .filter(|(_, subscription)| {
+                        matches!(subscription, Subscription::NewPendingTransactions)
+                    })
+                    .map(|(id, _)| subscription_notification(id, json!(hash)))
+                    .collect(),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Websocket connection fell behind, skipped {skipped} pending transactions");
+                    Vec::new()
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+        for message in messages {
+            if socket
+                .send(Message::Text(message.to_string()))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// Handles a request (or batch of requests) received through a websocket and returns the response
+fn handle_websocket_request(
+    body: &str,
+    subscriptions: &mut HashMap<String, Subscription>,
+    context: RpcApiContext,
+) -> Value {
+    handle_request_body(body, context.batch_request_limit, |req| {
+        match req.method.as_str() {
+            "eth_subscribe" => Subscription::parse(&req.params).map(|subscription| {
+                let id = format!("{:#x}", rand::random::<u128>());
+                subscriptions.insert(id.clone(), subscription);
+                Value::String(id)
+            }),
+            "eth_unsubscribe" => match req.params.as_deref() {
+                Some([id]) => serde_json::from_value::<String>(id.clone())
+                    .map(|id| Value::Bool(subscriptions.remove(&id).is_some()))
+                    .map_err(RpcErr::from),
+                _ => Err(RpcErr::BadParams("Expected 1 param".to_owned())),
+            },
+            _ => map_http_requests(req, context.clone()),
+        }
+    })
+}
+
+/// Builds the notifications of the `newHeads` and `logs` subscriptions for a new canonical block
+fn new_head_notifications(
+    header: &BlockHeader,
+    subscriptions: &HashMap<String, Subscription>,
+    storage: &Store,
+) -> Vec<Value> {
+    let mut notifications = Vec::new();
+    for (id, subscription) in subscriptions {
+        match subscription {
+            Subscription::NewHeads => {
+                let header = RpcHeader {
+                    hash: header.compute_block_hash(),
+                    header: header.clone(),
+                };
+                notifications.push(subscription_notification(id, json!(header)));
+            }
+            Subscription::Logs {
+                address_filters,
+                topics,
+            } => {
+                let filter = LogsFilter {
+                    from_block: BlockIdentifier::Number(header.number),
+                    to_block: BlockIdentifier::Number(header.number),
+                    address_filters: address_filters.clone(),
+                    topics: topics.clone(),
+                };
+                // Notifications only cover a single block, so the default limits are never reached
+                match fetch_logs_with_filter(&filter, storage.clone(), &LogsLimits::default()) {
+                    Ok(logs) => notifications.extend(
+                        logs.into_iter()
+                            .map(|log| subscription_notification(id, json!(log))),
+                    ),
+                    Err(error) => {
+                        warn!("Failed to fetch logs of block {}: {error:?}", header.number)
+                    }
+                }
+            }
+            Subscription::NewPendingTransactions => {}
+        }
+    }
+    notifications
+}
+
+fn subscription_notification(id: &str, result: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": {
+            "subscription": id,
+            "result": result,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils::test_utils::example_p2p_node, DEFAULT_BATCH_REQUEST_LIMIT};
+    use ethrex_net::sync::{SyncManager, SyncStatus};
+    use ethrex_storage::EngineType;
+    use std::sync::Arc;
+    use tokio::sync::Mutex as TokioMutex;
+
+    fn test_context() -> RpcApiContext {
+        RpcApiContext {
+            storage: Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB"),
+            jwt_secret: Default::default(),
+            local_p2p_node: example_p2p_node(),
+            active_filters: Default::default(),
+            syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: SyncStatus::default(),
+            subscription_notifier: Default::default(),
+            peer_manager: Default::default(),
+            batch_request_limit: DEFAULT_BATCH_REQUEST_LIMIT,
+            logs_limits: LogsLimits::default(),
+        }
+    }
+
+    #[test]
+    fn parse_logs_subscription() {
+        let params = Some(vec![
+            json!("logs"),
+            json!({"address": "0x8320fe7702b96808f7bbc0d4a888ed1468216cfd", "topics": [null, ["0xd78a0cb8bb633d06981248b816e7bd33c2a35a6089241d099fa519e361cab902"]]}),
+        ]);
+        let Subscription::Logs {
+            address_filters,
+            topics,
+        } = Subscription::parse(&params).unwrap()
+        else {
+            panic!("Expected a logs subscription");
+        };
+        assert!(matches!(address_filters, Some(AddressFilter::Single(_))));
+        assert_eq!(topics.len(), 2);
+    }
+
+    #[test]
+    fn parse_unsupported_subscription() {
+        let params = Some(vec![json!("syncing")]);
+        assert!(Subscription::parse(&params).is_err());
+    }
+
+    #[test]
+    fn subscribe_and_unsubscribe() {
+        let context = test_context();
+        let mut subscriptions = HashMap::new();
+        let response = handle_websocket_request(
+            r#"{"jsonrpc":"2.0","id":1,"method":"eth_subscribe","params":["newHeads"]}"#,
+            &mut subscriptions,
+            context.clone(),
+        );
+        let id = response["result"].as_str().unwrap().to_string();
+        assert!(matches!(
+            subscriptions.get(&id),
+            Some(Subscription::NewHeads)
+        ));
+
+        // Notifications are only built for the connection's subscriptions
+        let header = BlockHeader::default();
+        let notifications = new_head_notifications(&header, &subscriptions, &context.storage);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0]["params"]["subscription"], id);
+        assert_eq!(
+            notifications[0]["params"]["result"]["hash"],
+            format!("{:#x}", header.compute_block_hash())
+        );
+
+        let body =
+            format!(r#"{{"jsonrpc":"2.0","id":2,"method":"eth_unsubscribe","params":["{id}"]}}"#);
+        let response = handle_websocket_request(&body, &mut subscriptions, context.clone());
+        assert_eq!(response["result"], true);
+        assert!(subscriptions.is_empty());
+        let response = handle_websocket_request(&body, &mut subscriptions, context);
+        assert_eq!(response["result"], false);
+    }
+
+    #[test]
+    fn notify_heads_past_previous_head() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let mut headers = vec![BlockHeader::default()];
+        for number in 1..5 {
+            let parent = &headers[headers.len() - 1];
+            let header = BlockHeader {
+                number,
+                parent_hash: parent.compute_block_hash(),
+                ..Default::default()
+            };
+            storage
+                .add_block_header(header.compute_block_hash(), header.clone())
+                .unwrap();
+            headers.push(header);
+        }
+        let notifier = SubscriptionNotifier::default();
+        let mut new_heads = notifier.new_heads.subscribe();
+        notifier
+            .notify_new_heads(&storage, 1, headers[4].clone())
+            .unwrap();
+        for expected in &headers[2..] {
+            assert_eq!(new_heads.try_recv().unwrap().number, expected.number);
+        }
+        assert!(new_heads.try_recv().is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use ethereum_types::Bloom;
+use ethrex_core::types::BlockNumber;
+use sha3::{Digest, Keccak256};
+use tracing::debug;
+
+use crate::{error::StoreError, Store};
+
+/// Amount of consecutive blocks covered by each section of the bloom bits index
+pub const BLOOM_BITS_SECTION_SIZE: u64 = 4096;
+/// Amount of blocks a section has to be behind the head before it is indexed, so that reorgs rarely invalidate indexed sections
+pub const BLOOM_BITS_CONFIRMATIONS: u64 = 256;
+/// Amount of bits of a logs bloom
+pub const BLOOM_BITS: u32 = 2048;
+
+/// Returns the three logs bloom bits set by an address or topic, numbered as in the yellow paper's M3:2048 function
+pub fn bloom_bit_indexes(input: &[u8]) -> [u32; 3] {
+    let hash = Keccak256::digest(input);
+    std::array::from_fn(|i| {
+        ((u32::from(hash[2 * i]) << 8) | u32::from(hash[2 * i + 1])) % BLOOM_BITS
+    })
+}
+
+/// Returns true if the given bloom has the bloom bit set
+pub fn bloom_has_bit(bloom: &Bloom, bit: u32) -> bool {
+    let byte = bloom.as_bytes()[bloom.as_bytes().len() - 1 - (bit / 8) as usize];
+    byte & (1 << (bit % 8)) != 0
+}
+
+impl Store {
+    /// Returns the amount of sections covered by the bloom bits index
+    /// All canonical blocks below `sections * BLOOM_BITS_SECTION_SIZE` are indexed
+    pub fn get_bloom_bits_sections(&self) -> Result<u64, StoreError> {
+        Ok(self.engine.get_bloom_bits_sections()?.unwrap_or_default())
+    }
+
+    /// Returns the bit vector of a bloom bit within an indexed section
+    /// The n-th bit of the vector (most significant bit first) is set if the bloom of the n-th block of the section has the bloom bit set
+    pub fn get_bloom_bits(&self, section: u64, bit: u32) -> Result<Option<Vec<u8>>, StoreError> {
+        self.engine.get_bloom_bits(section, bit)
+    }
+
+    /// Updates the bloom bits index after the canonical chain changed from block `first_changed` up to `head`.
+    /// Sections containing blocks that are no longer canonical are dropped, and the next section is indexed
+    /// once all of its blocks are at least `BLOOM_BITS_CONFIRMATIONS` blocks behind the head.
+    /// Only one section is indexed per call, so that catching up with a long chain doesn't stall block processing
+    pub fn update_bloom_bits_index(
+        &self,
+        head: BlockNumber,
+        first_changed: BlockNumber,
+    ) -> Result<(), StoreError> {
+        let mut sections = self.get_bloom_bits_sections()?;
+        if first_changed < sections * BLOOM_BITS_SECTION_SIZE {
+            sections = first_changed / BLOOM_BITS_SECTION_SIZE;
+            debug!("Reorg below the bloom bits index, rolling back to {sections} sections");
+            self.engine.update_bloom_bits_sections(sections)?;
+        }
+
+        let section_start = sections * BLOOM_BITS_SECTION_SIZE;
+        let section_end = section_start + BLOOM_BITS_SECTION_SIZE - 1;
+        if head < section_end + BLOOM_BITS_CONFIRMATIONS {
+            return Ok(());
+        }
+
+        let mut bit_vectors =
+            vec![vec![0_u8; BLOOM_BITS_SECTION_SIZE as usize / 8]; BLOOM_BITS as usize];
+        for (index, number) in (section_start..=section_end).enumerate() {
+            let header = self
+                .get_block_header(number)?
+                .ok_or(StoreError::Custom(format!(
+                    "Missing header of canonical block {number}"
+                )))?;
+            for bit in 0..BLOOM_BITS {
+                if bloom_has_bit(&header.logs_bloom, bit) {
+                    bit_vectors[bit as usize][index / 8] |= 0x80 >> (index % 8);
+                }
+            }
+        }
+        self.engine.add_bloom_bits(sections, bit_vectors)?;
+        self.engine.update_bloom_bits_sections(sections + 1)?;
+        debug!("Indexed bloom bits of blocks {section_start} to {section_end}");
+        Ok(())
+    }
+}
@@ -1,13 +1,17 @@
 use bytes::Bytes;
 use ethereum_types::{H256, U256};
 use ethrex_core::types::{
-    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index,
-    Receipt, Transaction,
+    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig,
+    EncodedRequests, Index, Receipt, Transaction,
 };
-use std::{fmt::Debug, panic::RefUnwindSafe};
+use std::{collections::HashSet, fmt::Debug, panic::RefUnwindSafe};
 
 use crate::error::StoreError;
-use ethrex_trie::Trie;
+use ethrex_trie::{Nibbles, Trie};
+
+/// A locally built payload along with its value, blobs bundle, execution requests
+/// and whether it has been fully built
+pub type PayloadBundle = (Block, U256, BlobsBundle, Vec<EncodedRequests>, bool);
 
 pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     /// Add block header
@@ -209,6 +213,64 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     // Obtain pending block number
     fn get_pending_block_number(&self) -> Result<Option<BlockNumber>, StoreError>;
 
+    // Stores the bloom bits index of a section, one bit vector per bit of the logs bloom
+    // The n-th bit of each vector is set if the bloom of the n-th block of the section has that bit set
+    fn add_bloom_bits(&self, section: u64, bit_vectors: Vec<Vec<u8>>) -> Result<(), StoreError>;
+
+    // Obtain the bit vector of a logs bloom bit within a section
+    fn get_bloom_bits(&self, section: u64, bit: u32) -> Result<Option<Vec<u8>>, StoreError>;
+
+    // Update the amount of sections covered by the bloom bits index
+    fn update_bloom_bits_sections(&self, sections: u64) -> Result<(), StoreError>;
+
+    // Obtain the amount of sections covered by the bloom bits index
+    fn get_bloom_bits_sections(&self) -> Result<Option<u64>, StoreError>;
+
+    // Snap State methods
+
+    // Sets the hash of the last block header downloaded during snap sync
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError>;
+
+    // Obtain the hash of the last block header downloaded during snap sync
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError>;
+
+    // Sets the number of the snap sync pivot block
+    fn set_pivot_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError>;
+
+    // Obtain the number of the snap sync pivot block
+    fn get_pivot_block_number(&self) -> Result<Option<BlockNumber>, StoreError>;
+
+    // Sets the next account hash to request and the root of the partial state trie built from the downloaded account ranges
+    fn set_state_trie_ranges_checkpoint(
+        &self,
+        next_account: H256,
+        partial_root: H256,
+    ) -> Result<(), StoreError>;
+
+    // Obtain the next account hash to request and the root of the partial state trie built from the downloaded account ranges
+    fn get_state_trie_ranges_checkpoint(&self) -> Result<Option<(H256, H256)>, StoreError>;
+
+    // Sets the hashed addresses of the accounts whose storage is pending to be fetched or healed
+    fn set_storage_heal_paths(&self, accounts: Vec<H256>) -> Result<(), StoreError>;
+
+    // Obtain the hashed addresses of the accounts whose storage is pending to be fetched or healed
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<H256>>, StoreError>;
+
+    // Sets the code hashes of the bytecodes pending to be fetched
+    fn set_pending_bytecodes(&self, code_hashes: Vec<H256>) -> Result<(), StoreError>;
+
+    // Obtain the code hashes of the bytecodes pending to be fetched
+    fn get_pending_bytecodes(&self) -> Result<Option<Vec<H256>>, StoreError>;
+
+    // Sets the paths and hashes of the state trie nodes pending to be healed
+    fn set_state_heal_paths(&self, paths: Vec<(Nibbles, H256)>) -> Result<(), StoreError>;
+
+    // Obtain the paths and hashes of the state trie nodes pending to be healed
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<(Nibbles, H256)>>, StoreError>;
+
+    // Removes all snap state checkpoints
+    fn clear_snap_state(&self) -> Result<(), StoreError>;
+
     // Obtain a storage trie from the given address and storage_root
     // Doesn't check if the account is stored
     // Used for internal store operations
@@ -219,6 +281,28 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     // Used for internal store operations
     fn open_state_trie(&self, state_root: H256) -> Trie;
 
+    // Removes the state trie nodes not contained in `live_nodes`, visiting at most `limit` nodes in key order
+    // starting from the node stored under `start` (or from the first node if None)
+    // Returns the amount of nodes removed and the key of the next node to visit, or None if there are no more nodes
+    fn prune_state_trie_nodes(
+        &self,
+        start: Option<Vec<u8>>,
+        limit: usize,
+        live_nodes: &HashSet<H256>,
+    ) -> Result<(usize, Option<Vec<u8>>), StoreError>;
+
+    // Returns the lowest hashed address, not lower than `start`, of an account with stored storage trie nodes
+    fn next_storage_trie_address(&self, start: H256) -> Result<Option<H256>, StoreError>;
+
+    // Same as `prune_state_trie_nodes`, but for the storage trie nodes of the given account
+    fn prune_storage_trie_nodes(
+        &self,
+        hashed_address: H256,
+        start: Option<Vec<u8>>,
+        limit: usize,
+        live_nodes: &HashSet<H256>,
+    ) -> Result<(usize, Option<Vec<u8>>), StoreError>;
+
     // Set the canonical block hash for a given block number.
     fn set_canonical_block(&self, number: BlockNumber, hash: BlockHash) -> Result<(), StoreError>;
 
@@ -227,10 +311,7 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
 
     fn add_payload(&self, payload_id: u64, block: Block) -> Result<(), StoreError>;
 
-    fn get_payload(
-        &self,
-        payload_id: u64,
-    ) -> Result<Option<(Block, U256, BlobsBundle, bool)>, StoreError>;
+    fn get_payload(&self, payload_id: u64) -> Result<Option<PayloadBundle>, StoreError>;
 
     fn update_payload(
         &self,
@@ -238,6 +319,7 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
         block: Block,
         block_value: U256,
         blobs_bundle: BlobsBundle,
+        requests: Vec<EncodedRequests>,
         completed: bool,
     ) -> Result<(), StoreError>;
 
@@ -2,16 +2,18 @@ use crate::error::StoreError;
 use bytes::Bytes;
 use ethereum_types::{H256, U256};
 use ethrex_core::types::{
-    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index, Receipt,
+    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig,
+    EncodedRequests, Index, Receipt,
 };
-use ethrex_trie::{InMemoryTrieDB, Trie};
+use ethrex_trie::{InMemoryTrieDB, Nibbles, Trie};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     sync::{Arc, Mutex, MutexGuard},
 };
 
-use super::api::StoreEngine;
+use super::api::{PayloadBundle, StoreEngine};
+use super::utils::is_live_node;
 
 pub type NodeMap = Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>;
 
@@ -36,8 +38,11 @@ struct StoreInner {
     // TODO (#307): Remove TotalDifficulty.
     block_total_difficulties: HashMap<BlockHash, U256>,
     // Stores local blocks by payload id
-    payloads: HashMap<u64, (Block, U256, BlobsBundle, bool)>,
+    payloads: HashMap<u64, PayloadBundle>,
     pending_blocks: HashMap<BlockHash, Block>,
+    // Maps each section of the bloom bits index to one bit vector per logs bloom bit
+    bloom_bits: HashMap<u64, Vec<Vec<u8>>>,
+    snap_state: SnapState,
 }
 
 #[derive(Default, Debug)]
@@ -50,6 +55,18 @@ struct ChainData {
     // TODO (#307): Remove TotalDifficulty.
     latest_total_difficulty: Option<U256>,
     pending_block_number: Option<BlockNumber>,
+    bloom_bits_sections: Option<u64>,
+}
+
+// Keeps track of the state of an ongoing snap sync so it can be resumed
+#[derive(Default, Debug)]
+struct SnapState {
+    header_download_checkpoint: Option<BlockHash>,
+    pivot_block_number: Option<BlockNumber>,
+    state_trie_ranges_checkpoint: Option<(H256, H256)>,
+    storage_heal_paths: Option<Vec<H256>>,
+    pending_bytecodes: Option<Vec<H256>>,
+    state_heal_paths: Option<Vec<(Nibbles, H256)>>,
 }
 
 impl Store {
@@ -296,6 +313,92 @@ impl StoreEngine for Store {
         Ok(self.inner().chain_data.pending_block_number)
     }
 
+    fn add_bloom_bits(&self, section: u64, bit_vectors: Vec<Vec<u8>>) -> Result<(), StoreError> {
+        self.inner().bloom_bits.insert(section, bit_vectors);
+        Ok(())
+    }
+
+    fn get_bloom_bits(&self, section: u64, bit: u32) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(self
+            .inner()
+            .bloom_bits
+            .get(&section)
+            .and_then(|bit_vectors| bit_vectors.get(bit as usize))
+            .cloned())
+    }
+
+    fn update_bloom_bits_sections(&self, sections: u64) -> Result<(), StoreError> {
+        self.inner().chain_data.bloom_bits_sections = Some(sections);
+        Ok(())
+    }
+
+    fn get_bloom_bits_sections(&self) -> Result<Option<u64>, StoreError> {
+        Ok(self.inner().chain_data.bloom_bits_sections)
+    }
+
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.inner().snap_state.header_download_checkpoint = Some(block_hash);
+        Ok(())
+    }
+
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        Ok(self.inner().snap_state.header_download_checkpoint)
+    }
+
+    fn set_pivot_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.inner().snap_state.pivot_block_number = Some(block_number);
+        Ok(())
+    }
+
+    fn get_pivot_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        Ok(self.inner().snap_state.pivot_block_number)
+    }
+
+    fn set_state_trie_ranges_checkpoint(
+        &self,
+        next_account: H256,
+        partial_root: H256,
+    ) -> Result<(), StoreError> {
+        self.inner().snap_state.state_trie_ranges_checkpoint = Some((next_account, partial_root));
+        Ok(())
+    }
+
+    fn get_state_trie_ranges_checkpoint(&self) -> Result<Option<(H256, H256)>, StoreError> {
+        Ok(self.inner().snap_state.state_trie_ranges_checkpoint)
+    }
+
+    fn set_storage_heal_paths(&self, accounts: Vec<H256>) -> Result<(), StoreError> {
+        self.inner().snap_state.storage_heal_paths = Some(accounts);
+        Ok(())
+    }
+
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<H256>>, StoreError> {
+        Ok(self.inner().snap_state.storage_heal_paths.clone())
+    }
+
+    fn set_pending_bytecodes(&self, code_hashes: Vec<H256>) -> Result<(), StoreError> {
+        self.inner().snap_state.pending_bytecodes = Some(code_hashes);
+        Ok(())
+    }
+
+    fn get_pending_bytecodes(&self) -> Result<Option<Vec<H256>>, StoreError> {
+        Ok(self.inner().snap_state.pending_bytecodes.clone())
+    }
+
+    fn set_state_heal_paths(&self, paths: Vec<(Nibbles, H256)>) -> Result<(), StoreError> {
+        self.inner().snap_state.state_heal_paths = Some(paths);
+        Ok(())
+    }
+
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<(Nibbles, H256)>>, StoreError> {
+        Ok(self.inner().snap_state.state_heal_paths.clone())
+    }
+
+    fn clear_snap_state(&self) -> Result<(), StoreError> {
+        self.inner().snap_state = Default::default();
+        Ok(())
+    }
+
     fn open_storage_trie(&self, hashed_address: H256, storage_root: H256) -> Trie {
         let mut store = self.inner();
         let trie_backend = store.storage_trie_nodes.entry(hashed_address).or_default();
@@ -309,6 +412,48 @@ impl StoreEngine for Store {
         Trie::open(db, state_root)
     }
 
+    fn prune_state_trie_nodes(
+        &self,
+        start: Option<Vec<u8>>,
+        limit: usize,
+        live_nodes: &HashSet<H256>,
+    ) -> Result<(usize, Option<Vec<u8>>), StoreError> {
+        let nodes = self.inner().state_trie_nodes.clone();
+        let mut nodes = nodes.lock().unwrap();
+        Ok(prune_node_map(&mut nodes, start, limit, live_nodes))
+    }
+
+    fn next_storage_trie_address(&self, start: H256) -> Result<Option<H256>, StoreError> {
+        Ok(self
+            .inner()
+            .storage_trie_nodes
+            .iter()
+            .filter(|(hashed_address, nodes)| {
+                **hashed_address >= start && !nodes.lock().unwrap().is_empty()
+            })
+            .map(|(hashed_address, _)| *hashed_address)
+            .min())
+    }
+
+    fn prune_storage_trie_nodes(
+        &self,
+        hashed_address: H256,
+        start: Option<Vec<u8>>,
+        limit: usize,
+        live_nodes: &HashSet<H256>,
+    ) -> Result<(usize, Option<Vec<u8>>), StoreError> {
+        let Some(nodes) = self
+            .inner()
+            .storage_trie_nodes
+            .get(&hashed_address)
+            .cloned()
+        else {
+            return Ok((0, None));
+        };
+        let mut nodes = nodes.lock().unwrap();
+        Ok(prune_node_map(&mut nodes, start, limit, live_nodes))
+    }
+
     fn get_block_body_by_hash(
         &self,
         block_hash: BlockHash,
@@ -343,15 +488,12 @@ impl StoreEngine for Store {
     fn add_payload(&self, payload_id: u64, block: Block) -> Result<(), StoreError> {
         self.inner().payloads.insert(
             payload_id,
-            (block, U256::zero(), BlobsBundle::empty(), false),
+            (block, U256::zero(), BlobsBundle::empty(), Vec::new(), false),
         );
         Ok(())
     }
 
-    fn get_payload(
-        &self,
-        payload_id: u64,
-    ) -> Result<Option<(Block, U256, BlobsBundle, bool)>, StoreError> {
+    fn get_payload(&self, payload_id: u64) -> Result<Option<PayloadBundle>, StoreError> {
         Ok(self.inner().payloads.get(&payload_id).cloned())
     }
 
@@ -405,11 +547,13 @@ impl StoreEngine for Store {
         block: Block,
         block_value: U256,
         blobs_bundle: BlobsBundle,
+        requests: Vec<EncodedRequests>,
         completed: bool,
     ) -> Result<(), StoreError> {
-        self.inner()
-            .payloads
-            .insert(payload_id, (block, block_value, blobs_bundle, completed));
+        self.inner().payloads.insert(
+            payload_id,
+            (block, block_value, blobs_bundle, requests, completed),
+        );
         Ok(())
     }
 }
@@ -419,3 +563,29 @@ impl Debug for Store {
         f.debug_struct("In Memory Store").finish()
     }
 }
+
+/// Removes the nodes not contained in `live_nodes`, visiting at most `limit` nodes in key order starting from `start`
+/// Returns the amount of nodes removed and the key of the next node to visit, if any
+fn prune_node_map(
+    nodes: &mut HashMap<Vec<u8>, Vec<u8>>,
+    start: Option<Vec<u8>>,
+    limit: usize,
+    live_nodes: &HashSet<H256>,
+) -> (usize, Option<Vec<u8>>) {
+    let mut keys: Vec<Vec<u8>> = nodes
+        .keys()
+        .filter(|key| start.as_ref().is_none_or(|start| *key >= start))
+        .cloned()
+        .collect();
+    keys.sort();
+    let next = keys.get(limit).cloned();
+    keys.truncate(limit);
+    let mut removed = 0;
+    for key in keys {
+        if !is_live_node(&key, live_nodes) {
+            nodes.remove(&key);
+            removed += 1;
+        }
+    }
+    (removed, next)
+}
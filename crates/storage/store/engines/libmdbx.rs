@@ -1,5 +1,5 @@
-use super::api::StoreEngine;
-use super::utils::ChainDataIndex;
+use super::api::{PayloadBundle, StoreEngine};
+use super::utils::{is_live_node, ChainDataIndex, SnapStateIndex};
 use crate::error::StoreError;
 use crate::rlp::{
     AccountCodeHashRLP, AccountCodeRLP, BlockBodyRLP, BlockHashRLP, BlockHeaderRLP, BlockRLP,
@@ -9,12 +9,12 @@ use anyhow::Result;
 use bytes::Bytes;
 use ethereum_types::{H256, U256};
 use ethrex_core::types::{
-    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index,
-    Receipt, Transaction,
+    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig,
+    EncodedRequests, Index, Receipt, Transaction,
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_trie::{LibmdbxDupsortTrieDB, LibmdbxTrieDB, Trie};
+use ethrex_trie::{LibmdbxDupsortTrieDB, LibmdbxTrieDB, Nibbles, Trie};
 use libmdbx::orm::{Decodable, Encodable, Table};
 use libmdbx::{
     dupsort,
@@ -23,6 +23,7 @@ use libmdbx::{
 };
 use libmdbx::{DatabaseOptions, Mode, ReadWriteOptions};
 use serde_json;
+use std::collections::HashSet;
 use std::fmt::{Debug, Formatter};
 use std::path::Path;
 use std::sync::Arc;
@@ -75,6 +76,28 @@ impl Store {
     ) -> Result<Option<BlockHash>, StoreError> {
         Ok(self.read::<CanonicalBlockHashes>(number)?.map(|a| a.to()))
     }
+
+    // Helper method to write an rlp encoded value into the snap state table
+    fn write_snap_state(
+        &self,
+        index: SnapStateIndex,
+        value: impl RLPEncode,
+    ) -> Result<(), StoreError> {
+        self.write::<SnapState>(index, value.encode_to_vec())
+    }
+
+    // Helper method to read and decode a value from the snap state table
+    fn read_snap_state<T: RLPDecode>(
+        &self,
+        index: SnapStateIndex,
+    ) -> Result<Option<T>, StoreError> {
+        match self.read::<SnapState>(index)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
 }
 
 impl StoreEngine for Store {
@@ -341,6 +364,99 @@ impl StoreEngine for Store {
         }
     }
 
+    fn add_bloom_bits(&self, section: u64, bit_vectors: Vec<Vec<u8>>) -> Result<(), StoreError> {
+        let key_values = bit_vectors
+            .into_iter()
+            .enumerate()
+            .map(|(bit, bit_vector)| ((section, bit as u32), bit_vector))
+            .collect();
+        self.write_batch::<BloomBits>(key_values)
+    }
+
+    fn get_bloom_bits(&self, section: u64, bit: u32) -> Result<Option<Vec<u8>>, StoreError> {
+        self.read::<BloomBits>((section, bit))
+    }
+
+    fn update_bloom_bits_sections(&self, sections: u64) -> Result<(), StoreError> {
+        self.write::<ChainData>(ChainDataIndex::BloomBitsSections, sections.encode_to_vec())
+    }
+
+    fn get_bloom_bits_sections(&self) -> Result<Option<u64>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::BloomBitsSections)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::HeaderDownloadCheckpoint, block_hash)
+    }
+
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        self.read_snap_state(SnapStateIndex::HeaderDownloadCheckpoint)
+    }
+
+    fn set_pivot_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::PivotBlockNumber, block_number)
+    }
+
+    fn get_pivot_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.read_snap_state(SnapStateIndex::PivotBlockNumber)
+    }
+
+    fn set_state_trie_ranges_checkpoint(
+        &self,
+        next_account: H256,
+        partial_root: H256,
+    ) -> Result<(), StoreError> {
+        self.write_snap_state(
+            SnapStateIndex::StateTrieRangesCheckpoint,
+            (next_account, partial_root),
+        )
+    }
+
+    fn get_state_trie_ranges_checkpoint(&self) -> Result<Option<(H256, H256)>, StoreError> {
+        self.read_snap_state(SnapStateIndex::StateTrieRangesCheckpoint)
+    }
+
+    fn set_storage_heal_paths(&self, accounts: Vec<H256>) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::StorageHealPaths, accounts)
+    }
+
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<H256>>, StoreError> {
+        self.read_snap_state(SnapStateIndex::StorageHealPaths)
+    }
+
+    fn set_pending_bytecodes(&self, code_hashes: Vec<H256>) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::PendingBytecodes, code_hashes)
+    }
+
+    fn get_pending_bytecodes(&self) -> Result<Option<Vec<H256>>, StoreError> {
+        self.read_snap_state(SnapStateIndex::PendingBytecodes)
+    }
+
+    fn set_state_heal_paths(&self, paths: Vec<(Nibbles, H256)>) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::StateHealPaths, paths)
+    }
+
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<(Nibbles, H256)>>, StoreError> {
+        self.read_snap_state(SnapStateIndex::StateHealPaths)
+    }
+
+    fn clear_snap_state(&self) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        for index in SnapStateIndex::ALL {
+            txn.delete::<SnapState>(index, None)
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
     fn open_storage_trie(&self, hashed_address: H256, storage_root: H256) -> Trie {
         let db = Box::new(LibmdbxDupsortTrieDB::<StorageTriesNodes, [u8; 32]>::new(
             self.db.clone(),
@@ -354,6 +470,105 @@ impl StoreEngine for Store {
         Trie::open(db, state_root)
     }
 
+    fn prune_state_trie_nodes(
+        &self,
+        start: Option<Vec<u8>>,
+        limit: usize,
+        live_nodes: &HashSet<H256>,
+    ) -> Result<(usize, Option<Vec<u8>>), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        let mut removed = 0;
+        let next = {
+            let mut cursor = txn
+                .cursor::<StateTrieNodes>()
+                .map_err(|_| StoreError::CursorError("StateTrieNodes".to_owned()))?;
+            let mut entry = match start {
+                Some(start) => cursor.seek_closest(start),
+                None => cursor.first(),
+            }
+            .map_err(StoreError::LibmdbxError)?;
+            let mut visited = 0;
+            while let Some((node_hash, _)) = entry {
+                if visited == limit {
+                    break;
+                }
+                if !is_live_node(&node_hash, live_nodes) {
+                    cursor.delete_current().map_err(StoreError::LibmdbxError)?;
+                    removed += 1;
+                }
+                visited += 1;
+                entry = cursor.next().map_err(StoreError::LibmdbxError)?;
+            }
+            entry.map(|(node_hash, _)| node_hash)
+        };
+        txn.commit().map_err(StoreError::LibmdbxError)?;
+        Ok((removed, next))
+    }
+
+    fn next_storage_trie_address(&self, start: H256) -> Result<Option<H256>, StoreError> {
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        let mut cursor = txn
+            .cursor::<StorageTriesNodes>()
+            .map_err(|_| StoreError::CursorError("StorageTriesNodes".to_owned()))?;
+        Ok(cursor
+            .seek_closest(start.0)
+            .map_err(StoreError::LibmdbxError)?
+            .map(|((hashed_address, _), _)| H256(hashed_address)))
+    }
+
+    fn prune_storage_trie_nodes(
+        &self,
+        hashed_address: H256,
+        start: Option<Vec<u8>>,
+        limit: usize,
+        live_nodes: &HashSet<H256>,
+    ) -> Result<(usize, Option<Vec<u8>>), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        let mut removed = 0;
+        let next = {
+            let mut cursor = txn
+                .cursor::<StorageTriesNodes>()
+                .map_err(|_| StoreError::CursorError("StorageTriesNodes".to_owned()))?;
+            // Resume from the given node, or from the first node of the account if it is no longer stored
+            let mut entry = match start.and_then(|start| <[u8; 33]>::try_from(start).ok()) {
+                Some(start) => cursor
+                    .seek_exact((hashed_address.0, start))
+                    .map_err(StoreError::LibmdbxError)?,
+                None => None,
+            };
+            if entry.is_none() {
+                entry = cursor
+                    .seek_closest(hashed_address.0)
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+            let mut visited = 0;
+            while let Some(((address, fixed_size_hash), _)) = entry {
+                if address != hashed_address.0 || visited == limit {
+                    break;
+                }
+                // The first byte of the fixed size node hash holds the original length of the hash
+                let node_hash = &fixed_size_hash[1..=fixed_size_hash[0] as usize];
+                if !is_live_node(node_hash, live_nodes) {
+                    cursor.delete_current().map_err(StoreError::LibmdbxError)?;
+                    removed += 1;
+                }
+                visited += 1;
+                entry = cursor.next().map_err(StoreError::LibmdbxError)?;
+            }
+            entry
+                .filter(|((address, _), _)| *address == hashed_address.0)
+                .map(|((_, fixed_size_hash), _)| fixed_size_hash.to_vec())
+        };
+        txn.commit().map_err(StoreError::LibmdbxError)?;
+        Ok((removed, next))
+    }
+
     fn set_canonical_block(&self, number: BlockNumber, hash: BlockHash) -> Result<(), StoreError> {
         self.write::<CanonicalBlockHashes>(number, hash.into())
     }
@@ -369,14 +584,11 @@ impl StoreEngine for Store {
     fn add_payload(&self, payload_id: u64, block: Block) -> Result<(), StoreError> {
         self.write::<Payloads>(
             payload_id,
-            (block, U256::zero(), BlobsBundle::empty(), false).into(),
+            (block, U256::zero(), BlobsBundle::empty(), Vec::new(), false).into(),
         )
     }
 
-    fn get_payload(
-        &self,
-        payload_id: u64,
-    ) -> Result<Option<(Block, U256, BlobsBundle, bool)>, StoreError> {
+    fn get_payload(&self, payload_id: u64) -> Result<Option<PayloadBundle>, StoreError> {
         Ok(self.read::<Payloads>(payload_id)?.map(|b| b.to()))
     }
 
@@ -386,11 +598,12 @@ impl StoreEngine for Store {
         block: Block,
         block_value: U256,
         blobs_bundle: BlobsBundle,
+        requests: Vec<EncodedRequests>,
         completed: bool,
     ) -> std::result::Result<(), StoreError> {
         self.write::<Payloads>(
             payload_id,
-            (block, block_value, blobs_bundle, completed).into(),
+            (block, block_value, blobs_bundle, requests, completed).into(),
         )
     }
 
@@ -594,7 +807,7 @@ table!(
 
 table!(
     /// payload id to payload table
-    ( Payloads ) u64 => Rlp<(Block, U256, BlobsBundle, bool)>
+    ( Payloads ) u64 => Rlp<(Block, U256, BlobsBundle, Vec<EncodedRequests>, bool)>
 );
 
 table!(
@@ -602,6 +815,17 @@ table!(
     ( PendingBlocks ) BlockHashRLP => BlockRLP
 );
 
+table!(
+    /// Bloom bits index, stores the bit vector of each logs bloom bit by section and bit
+    ( BloomBits ) (u64, u32) => Vec<u8>
+);
+
+table!(
+    /// Stores the checkpoints of an ongoing snap sync, each value is unique and stored as its rlp encoding
+    /// See [SnapStateIndex] for available values
+    ( SnapState ) SnapStateIndex => Vec<u8>
+);
+
 // Storage values are stored as bytes instead of using their rlp encoding
 // As they are stored in a dupsort table, they need to have a fixed size, and encoding them doesn't preserve their size
 pub struct AccountStorageKeyBytes(pub [u8; 32]);
@@ -669,6 +893,14 @@ impl Encodable for ChainDataIndex {
     }
 }
 
+impl Encodable for SnapStateIndex {
+    type Encoded = [u8; 4];
+
+    fn encode(self) -> Self::Encoded {
+        (self as u32).encode()
+    }
+}
+
 /// Initializes a new database with the provided path. If the path is `None`, the database
 /// will be temporary.
 pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
@@ -687,6 +919,8 @@ pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
         table_info!(CanonicalBlockHashes),
         table_info!(Payloads),
         table_info!(PendingBlocks),
+        table_info!(BloomBits),
+        table_info!(SnapState),
     ]
     .into_iter()
     .collect();
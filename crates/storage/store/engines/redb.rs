@@ -1,17 +1,23 @@
-use std::{borrow::Borrow, panic::RefUnwindSafe, sync::Arc};
+use std::{borrow::Borrow, collections::HashSet, panic::RefUnwindSafe, sync::Arc};
 
 use ethrex_core::types::BlockBody;
 use ethrex_core::{
-    types::{BlobsBundle, Block, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index, Receipt},
+    types::{
+        BlobsBundle, Block, BlockHash, BlockHeader, BlockNumber, ChainConfig, EncodedRequests,
+        Index, Receipt,
+    },
     H256, U256,
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_trie::{
     db::{redb::RedBTrie, redb_multitable::RedBMultiTableTrieDB},
-    Trie,
+    Nibbles, Trie,
+};
+use redb::{
+    AccessGuard, Database, Key, MultimapTableDefinition, ReadableMultimapTable, TableDefinition,
+    TypeName, Value,
 };
-use redb::{AccessGuard, Database, Key, MultimapTableDefinition, TableDefinition, TypeName, Value};
 
 use crate::rlp::{BlockRLP, BlockTotalDifficultyRLP, Rlp, TransactionHashRLP};
 use crate::{
@@ -22,10 +28,13 @@ use crate::{
     },
 };
 
-use super::{api::StoreEngine, utils::ChainDataIndex};
+use super::{
+    api::{PayloadBundle, StoreEngine},
+    utils::{is_live_node, ChainDataIndex, SnapStateIndex},
+};
 
-const STATE_TRIE_NODES_TABLE: TableDefinition<&[u8], &[u8]> =
-    TableDefinition::new("StateTrieNodes");
+// Same table as the one `RedBTrie` writes the state trie nodes to, existing databases store them under this name
+const STATE_TRIE_NODES_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("Trie");
 const BLOCK_NUMBERS_TABLE: TableDefinition<BlockHashRLP, BlockNumber> =
     TableDefinition::new("BlockNumbers");
 const BLOCK_TOTAL_DIFFICULTIES_TABLE: TableDefinition<BlockHashRLP, BlockTotalDifficultyRLP> =
@@ -44,10 +53,15 @@ pub const STORAGE_TRIE_NODES_TABLE: MultimapTableDefinition<([u8; 32], [u8; 33])
     MultimapTableDefinition::new("StorageTrieNodes");
 const CHAIN_DATA_TABLE: TableDefinition<ChainDataIndex, Vec<u8>> =
     TableDefinition::new("ChainData");
-const PAYLOADS_TABLE: TableDefinition<BlockNumber, Rlp<(Block, U256, BlobsBundle, bool)>> =
-    TableDefinition::new("Payloads");
+const PAYLOADS_TABLE: TableDefinition<
+    BlockNumber,
+    Rlp<(Block, U256, BlobsBundle, Vec<EncodedRequests>, bool)>,
+> = TableDefinition::new("Payloads");
 const PENDING_BLOCKS_TABLE: TableDefinition<BlockHashRLP, BlockRLP> =
     TableDefinition::new("PendingBlocks");
+const BLOOM_BITS_TABLE: TableDefinition<(u64, u32), Vec<u8>> = TableDefinition::new("BloomBits");
+const SNAP_STATE_TABLE: TableDefinition<SnapStateIndex, Vec<u8>> =
+    TableDefinition::new("SnapState");
 const TRANSACTION_LOCATIONS_TABLE: MultimapTableDefinition<
     TransactionHashRLP,
     Rlp<(BlockNumber, BlockHash, Index)>,
@@ -188,6 +202,28 @@ impl RedBStore {
             .read(CANONICAL_BLOCK_HASHES_TABLE, number)?
             .map(|a| a.value().to()))
     }
+
+    // Helper method to write an rlp encoded value into the snap state table
+    fn write_snap_state(
+        &self,
+        index: SnapStateIndex,
+        value: impl RLPEncode,
+    ) -> Result<(), StoreError> {
+        self.write(SNAP_STATE_TABLE, index, value.encode_to_vec())
+    }
+
+    // Helper method to read and decode a value from the snap state table
+    fn read_snap_state<T: RLPDecode>(
+        &self,
+        index: SnapStateIndex,
+    ) -> Result<Option<T>, StoreError> {
+        match self.read(SNAP_STATE_TABLE, index)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
 }
 
 impl StoreEngine for RedBStore {
@@ -546,6 +582,106 @@ impl StoreEngine for RedBStore {
         }
     }
 
+    fn add_bloom_bits(&self, section: u64, bit_vectors: Vec<Vec<u8>>) -> Result<(), StoreError> {
+        let key_values = bit_vectors
+            .into_iter()
+            .enumerate()
+            .map(|(bit, bit_vector)| ((section, bit as u32), bit_vector))
+            .collect();
+        self.write_batch(BLOOM_BITS_TABLE, key_values)
+    }
+
+    fn get_bloom_bits(&self, section: u64, bit: u32) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(self
+            .read(BLOOM_BITS_TABLE, (section, bit))?
+            .map(|bit_vector| bit_vector.value()))
+    }
+
+    fn update_bloom_bits_sections(&self, sections: u64) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA_TABLE,
+            ChainDataIndex::BloomBitsSections,
+            sections.encode_to_vec(),
+        )
+    }
+
+    fn get_bloom_bits_sections(&self) -> Result<Option<u64>, StoreError> {
+        match self.read(CHAIN_DATA_TABLE, ChainDataIndex::BloomBitsSections)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::HeaderDownloadCheckpoint, block_hash)
+    }
+
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        self.read_snap_state(SnapStateIndex::HeaderDownloadCheckpoint)
+    }
+
+    fn set_pivot_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::PivotBlockNumber, block_number)
+    }
+
+    fn get_pivot_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.read_snap_state(SnapStateIndex::PivotBlockNumber)
+    }
+
+    fn set_state_trie_ranges_checkpoint(
+        &self,
+        next_account: H256,
+        partial_root: H256,
+    ) -> Result<(), StoreError> {
+        self.write_snap_state(
+            SnapStateIndex::StateTrieRangesCheckpoint,
+            (next_account, partial_root),
+        )
+    }
+
+    fn get_state_trie_ranges_checkpoint(&self) -> Result<Option<(H256, H256)>, StoreError> {
+        self.read_snap_state(SnapStateIndex::StateTrieRangesCheckpoint)
+    }
+
+    fn set_storage_heal_paths(&self, accounts: Vec<H256>) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::StorageHealPaths, accounts)
+    }
+
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<H256>>, StoreError> {
+        self.read_snap_state(SnapStateIndex::StorageHealPaths)
+    }
+
+    fn set_pending_bytecodes(&self, code_hashes: Vec<H256>) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::PendingBytecodes, code_hashes)
+    }
+
+    fn get_pending_bytecodes(&self) -> Result<Option<Vec<H256>>, StoreError> {
+        self.read_snap_state(SnapStateIndex::PendingBytecodes)
+    }
+
+    fn set_state_heal_paths(&self, paths: Vec<(Nibbles, H256)>) -> Result<(), StoreError> {
+        self.write_snap_state(SnapStateIndex::StateHealPaths, paths)
+    }
+
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<(Nibbles, H256)>>, StoreError> {
+        self.read_snap_state(SnapStateIndex::StateHealPaths)
+    }
+
+    fn clear_snap_state(&self) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(SNAP_STATE_TABLE)?;
+            for index in SnapStateIndex::ALL {
+                table.remove(index)?;
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
     fn open_storage_trie(
         &self,
         hashed_address: ethrex_core::H256,
@@ -560,6 +696,84 @@ impl StoreEngine for RedBStore {
         Trie::open(db, state_root)
     }
 
+    fn prune_state_trie_nodes(
+        &self,
+        start: Option<Vec<u8>>,
+        limit: usize,
+        live_nodes: &HashSet<H256>,
+    ) -> Result<(usize, Option<Vec<u8>>), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        let mut dead_keys = Vec::new();
+        let mut next = None;
+        {
+            let mut table = write_txn.open_table(STATE_TRIE_NODES_TABLE)?;
+            let start = start.unwrap_or_default();
+            for (visited, entry) in table.range::<&[u8]>(start.as_slice()..)?.enumerate() {
+                let node_hash = entry?.0.value().to_vec();
+                if visited == limit {
+                    next = Some(node_hash);
+                    break;
+                }
+                if !is_live_node(&node_hash, live_nodes) {
+                    dead_keys.push(node_hash);
+                }
+            }
+            for key in dead_keys.iter() {
+                table.remove(key.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok((dead_keys.len(), next))
+    }
+
+    fn next_storage_trie_address(&self, start: H256) -> Result<Option<H256>, StoreError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_multimap_table(STORAGE_TRIE_NODES_TABLE)?;
+        let mut range = table.range((start.0, [0; 33])..)?;
+        Ok(match range.next() {
+            Some(entry) => Some(H256(entry?.0.value().0)),
+            None => None,
+        })
+    }
+
+    fn prune_storage_trie_nodes(
+        &self,
+        hashed_address: H256,
+        start: Option<Vec<u8>>,
+        limit: usize,
+        live_nodes: &HashSet<H256>,
+    ) -> Result<(usize, Option<Vec<u8>>), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        let mut dead_keys = Vec::new();
+        let mut next = None;
+        {
+            let mut table = write_txn.open_multimap_table(STORAGE_TRIE_NODES_TABLE)?;
+            let start = start
+                .and_then(|start| <[u8; 33]>::try_from(start).ok())
+                .unwrap_or([0; 33]);
+            for (visited, entry) in table
+                .range((hashed_address.0, start)..=(hashed_address.0, [u8::MAX; 33]))?
+                .enumerate()
+            {
+                let (_, fixed_size_hash) = entry?.0.value();
+                if visited == limit {
+                    next = Some(fixed_size_hash.to_vec());
+                    break;
+                }
+                // The first byte of the fixed size node hash holds the original length of the hash
+                let node_hash = &fixed_size_hash[1..=fixed_size_hash[0] as usize];
+                if !is_live_node(node_hash, live_nodes) {
+                    dead_keys.push((hashed_address.0, fixed_size_hash));
+                }
+            }
+            for key in dead_keys.iter().copied() {
+                table.remove_all(key)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok((dead_keys.len(), next))
+    }
+
     fn set_canonical_block(&self, number: BlockNumber, hash: BlockHash) -> Result<(), StoreError> {
         self.write(
             CANONICAL_BLOCK_HASHES_TABLE,
@@ -576,16 +790,13 @@ impl StoreEngine for RedBStore {
         self.write(
             PAYLOADS_TABLE,
             payload_id,
-            <(Block, U256, BlobsBundle, bool) as Into<Rlp<(Block, U256, BlobsBundle, bool)>>>::into(
-                (block, U256::zero(), BlobsBundle::empty(), false),
-            ),
+            <(Block, U256, BlobsBundle, Vec<EncodedRequests>, bool) as Into<
+                Rlp<(Block, U256, BlobsBundle, Vec<EncodedRequests>, bool)>,
+            >>::into((block, U256::zero(), BlobsBundle::empty(), Vec::new(), false)),
         )
     }
 
-    fn get_payload(
-        &self,
-        payload_id: u64,
-    ) -> Result<Option<(Block, U256, BlobsBundle, bool)>, StoreError> {
+    fn get_payload(&self, payload_id: u64) -> Result<Option<PayloadBundle>, StoreError> {
         Ok(self
             .read(PAYLOADS_TABLE, payload_id)?
             .map(|b| b.value().to()))
@@ -640,14 +851,15 @@ impl StoreEngine for RedBStore {
         block: Block,
         block_value: U256,
         blobs_bundle: BlobsBundle,
+        requests: Vec<EncodedRequests>,
         completed: bool,
     ) -> Result<(), StoreError> {
         self.write(
             PAYLOADS_TABLE,
             payload_id,
-            <(Block, U256, BlobsBundle, bool) as Into<Rlp<(Block, U256, BlobsBundle, bool)>>>::into(
-                (block, block_value, blobs_bundle, completed),
-            ),
+            <(Block, U256, BlobsBundle, Vec<EncodedRequests>, bool) as Into<
+                Rlp<(Block, U256, BlobsBundle, Vec<EncodedRequests>, bool)>,
+            >>::into((block, block_value, blobs_bundle, requests, completed)),
         )
     }
 
@@ -722,6 +934,47 @@ impl redb::Key for ChainDataIndex {
     }
 }
 
+impl redb::Value for SnapStateIndex {
+    type SelfType<'a>
+        = SnapStateIndex
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = [u8; 1]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        data[0].into()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'a,
+        Self: 'b,
+    {
+        [*value as u8]
+    }
+
+    fn type_name() -> redb::TypeName {
+        TypeName::new("SnapStateIndex")
+    }
+}
+
+impl redb::Key for SnapStateIndex {
+    fn compare(data1: &[u8], data2: &[u8]) -> std::cmp::Ordering {
+        data1.cmp(data2)
+    }
+}
+
 pub fn init_db() -> Result<Database, StoreError> {
     let db = Database::create("ethrex.redb")?;
 
@@ -736,6 +989,8 @@ pub fn init_db() -> Result<Database, StoreError> {
     table_creation_txn.open_table(BLOCK_BODIES_TABLE)?;
     table_creation_txn.open_table(PAYLOADS_TABLE)?;
     table_creation_txn.open_table(PENDING_BLOCKS_TABLE)?;
+    table_creation_txn.open_table(BLOOM_BITS_TABLE)?;
+    table_creation_txn.open_table(SNAP_STATE_TABLE)?;
     table_creation_txn.open_multimap_table(TRANSACTION_LOCATIONS_TABLE)?;
     table_creation_txn.commit()?;
 
@@ -1,3 +1,7 @@
+use std::collections::HashSet;
+
+use ethereum_types::H256;
+
 /// Represents the key for each unique value of the chain data stored in the db
 // (TODO: Remove this comment once full) Will store chain-specific data such as chain id and latest finalized/pending/safe block number
 #[derive(Debug, Copy, Clone)]
@@ -10,6 +14,7 @@ pub enum ChainDataIndex {
     PendingBlockNumber = 5,
     // TODO (#307): Remove TotalDifficulty.
     LatestTotalDifficulty = 6,
+    BloomBitsSections = 7,
 }
 
 impl From<u8> for ChainDataIndex {
@@ -30,7 +35,62 @@ impl From<u8> for ChainDataIndex {
             x if x == ChainDataIndex::LatestTotalDifficulty as u8 => {
                 ChainDataIndex::LatestTotalDifficulty
             }
+            x if x == ChainDataIndex::BloomBitsSections as u8 => ChainDataIndex::BloomBitsSections,
             _ => panic!("Invalid value when casting to ChainDataIndex: {}", value),
         }
     }
 }
+
+/// Represents the key for each unique value of the snap state stored in the db
+/// Snap state holds the checkpoints of an ongoing snap sync so that it can be resumed after a restart
+#[derive(Debug, Copy, Clone)]
+pub enum SnapStateIndex {
+    // Hash of the last block header downloaded
+    HeaderDownloadCheckpoint = 0,
+    // Number of the pivot block whose state is being downloaded
+    PivotBlockNumber = 1,
+    // Next account hash to request and root of the partial state trie built from the account ranges downloaded so far
+    StateTrieRangesCheckpoint = 2,
+    // Hashed addresses of the accounts whose storage is pending to be fetched or healed
+    StorageHealPaths = 3,
+    // Code hashes of the bytecodes pending to be fetched
+    PendingBytecodes = 4,
+    // Paths and hashes of the state trie nodes pending to be healed
+    StateHealPaths = 5,
+}
+
+impl From<u8> for SnapStateIndex {
+    fn from(value: u8) -> Self {
+        match value {
+            x if x == SnapStateIndex::HeaderDownloadCheckpoint as u8 => {
+                SnapStateIndex::HeaderDownloadCheckpoint
+            }
+            x if x == SnapStateIndex::PivotBlockNumber as u8 => SnapStateIndex::PivotBlockNumber,
+            x if x == SnapStateIndex::StateTrieRangesCheckpoint as u8 => {
+                SnapStateIndex::StateTrieRangesCheckpoint
+            }
+            x if x == SnapStateIndex::StorageHealPaths as u8 => SnapStateIndex::StorageHealPaths,
+            x if x == SnapStateIndex::PendingBytecodes as u8 => SnapStateIndex::PendingBytecodes,
+            x if x == SnapStateIndex::StateHealPaths as u8 => SnapStateIndex::StateHealPaths,
+            _ => panic!("Invalid value when casting to SnapStateIndex: {}", value),
+        }
+    }
+}
+
+impl SnapStateIndex {
+    /// All snap state indexes, used to clear the snap state
+    pub const ALL: [SnapStateIndex; 6] = [
+        SnapStateIndex::HeaderDownloadCheckpoint,
+        SnapStateIndex::PivotBlockNumber,
+        SnapStateIndex::StateTrieRangesCheckpoint,
+        SnapStateIndex::StorageHealPaths,
+        SnapStateIndex::PendingBytecodes,
+        SnapStateIndex::StateHealPaths,
+    ];
+}
+
+/// Returns true if the trie node stored under the given key is one of the live nodes
+/// Only hashed nodes are marked as live, as inlined nodes are not stored by themselves
+pub fn is_live_node(node_key: &[u8], live_nodes: &HashSet<H256>) -> bool {
+    node_key.len() == 32 && live_nodes.contains(&H256::from_slice(node_key))
+}
@@ -47,3 +47,22 @@ pub enum StoreError {
     #[error("Missing earliest block number")]
     MissingEarliestBlockNumber,
 }
+
+/// Reasons for the transaction pool to reject a transaction
+#[derive(Debug, Error)]
+pub enum PoolError {
+    #[error("Transaction already known")]
+    AlreadyKnown,
+    #[error("Nonce for account too low")]
+    NonceTooLow,
+    #[error("Replacement transaction underpriced")]
+    ReplacementUnderpriced,
+    #[error("Transaction pool is full and the transaction fee is too low")]
+    Underpriced,
+    #[error("Sender already has {0} executable transactions in the pool")]
+    SenderPendingLimitReached(usize),
+    #[error("Sender already has {0} queued transactions in the pool")]
+    SenderQueueLimitReached(usize),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
@@ -0,0 +1,413 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use ethereum_types::{Address, H256};
+use ethrex_core::types::{MempoolTransaction, Transaction};
+use tracing::info;
+
+use crate::{error::PoolError, Store};
+
+/// Limits and replacement rules of the transaction pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MempoolConfig {
+    /// Maximum amount of executable (pending) transactions held in the pool
+    pub global_slots: usize,
+    /// Maximum amount of non-executable (queued) transactions held in the pool
+    pub global_queue: usize,
+    /// Maximum amount of pending transactions a single sender can have in the pool
+    pub account_slots: usize,
+    /// Maximum amount of queued transactions a single sender can have in the pool
+    pub account_queue: usize,
+    /// Minimum fee increase (in percent) required to replace a transaction with the same sender and nonce
+    pub price_bump: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            global_slots: 4096,
+            global_queue: 1024,
+            account_slots: 16,
+            account_queue: 64,
+            price_bump: 10,
+        }
+    }
+}
+
+/// Snapshot of the transactions held in the pool, grouped by sender and sorted by nonce
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MempoolContent {
+    /// Transactions that can be executed on top of the latest head
+    pub pending: HashMap<Address, Vec<MempoolTransaction>>,
+    /// Transactions that can't be executed yet due to a nonce gap
+    pub queued: HashMap<Address, Vec<MempoolTransaction>>,
+}
+
+/// Transactions of a single sender, indexed by nonce
+#[derive(Debug, Default)]
+struct SenderTransactions {
+    /// Nonce of the sender's account at the latest known head
+    state_nonce: u64,
+    /// Nonce following the sender's last executable transaction, updated by `Mempool::index_sender`
+    next_nonce: u64,
+    txs: BTreeMap<u64, H256>,
+}
+
+impl SenderTransactions {
+    fn compute_next_nonce(&self) -> u64 {
+        let mut nonce = self.state_nonce;
+        while self.txs.contains_key(&nonce) {
+            nonce += 1;
+        }
+        nonce
+    }
+
+    /// Transactions that can be executed on top of the latest head, sorted by nonce
+    fn pending(&self) -> impl DoubleEndedIterator<Item = &H256> {
+        self.txs
+            .range(self.state_nonce..self.next_nonce)
+            .map(|(_, hash)| hash)
+    }
+
+    /// Transactions that can't be executed yet due to a nonce gap, sorted by nonce
+    fn queued(&self) -> impl DoubleEndedIterator<Item = &H256> {
+        self.txs.range(self.next_nonce..).map(|(_, hash)| hash)
+    }
+}
+
+/// What the transactions of a sender add to the sub-pool counters and eviction indexes
+struct SenderEntries {
+    pending: usize,
+    queued: usize,
+    /// The sender's last pending transaction, if it can be evicted
+    evictable_pending: Option<(u64, H256)>,
+    evictable_queued: Vec<(u64, H256)>,
+}
+
+/// In-memory pool of transactions waiting to be included in a block.
+/// Transactions are split into a pending sub-pool, holding the ones that are executable on top of the latest head,
+/// and a queued sub-pool, holding the ones that are waiting for a transaction with a lower nonce from the same sender.
+#[derive(Debug, Default)]
+pub struct Mempool {
+    config: MempoolConfig,
+    transactions: HashMap<H256, MempoolTransaction>,
+    senders: HashMap<Address, SenderTransactions>,
+    pending_count: usize,
+    queued_count: usize,
+    /// Transactions that can be evicted from each sub-pool, ordered by fee cap
+    /// Only the last pending transaction of each sender can be evicted so that no nonce gaps are created
+    pending_by_price: BTreeSet<(u64, H256)>,
+    queued_by_price: BTreeSet<(u64, H256)>,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn get(&self, hash: &H256) -> Option<&MempoolTransaction> {
+        self.transactions.get(hash)
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.transactions.contains_key(hash)
+    }
+
+    /// Returns the pending transactions grouped by sender and sorted by nonce
+    pub fn pending(&self) -> HashMap<Address, Vec<&MempoolTransaction>> {
+        self.sub_pool(SenderTransactions::pending)
+    }
+
+    /// Returns the queued transactions grouped by sender and sorted by nonce
+    pub fn queued(&self) -> HashMap<Address, Vec<&MempoolTransaction>> {
+        self.sub_pool(SenderTransactions::queued)
+    }
+
+    fn sub_pool<'a, I>(
+        &'a self,
+        hashes: impl Fn(&'a SenderTransactions) -> I,
+    ) -> HashMap<Address, Vec<&'a MempoolTransaction>>
+    where
+        I: Iterator<Item = &'a H256>,
+    {
+        self.senders
+            .iter()
+            .map(|(sender, sender_txs)| {
+                let txs: Vec<_> = hashes(sender_txs)
+                    .filter_map(|hash| self.transactions.get(hash))
+                    .collect();
+                (*sender, txs)
+            })
+            .filter(|(_, txs)| !txs.is_empty())
+            .collect()
+    }
+
+    /// Returns a copy of the pending and queued transactions
+    pub fn content(&self) -> MempoolContent {
+        let cloned = |sub_pool: HashMap<Address, Vec<&MempoolTransaction>>| {
+            sub_pool
+                .into_iter()
+                .map(|(sender, txs)| (sender, txs.into_iter().cloned().collect()))
+                .collect()
+        };
+        MempoolContent {
+            pending: cloned(self.pending()),
+            queued: cloned(self.queued()),
+        }
+    }
+
+    /// Adds a transaction to the pool, given the nonce of its sender's account at the latest head.
+    /// A transaction with the same sender and nonce as one already in the pool replaces it only if
+    /// it bumps both its fee cap and tip by at least `price_bump` percent.
+    /// When the pool is over its limits the cheapest transactions are evicted, only the last pending
+    /// transaction of each sender is considered for eviction so that no nonce gaps are created.
+    /// Returns the hashes of the transactions that were removed from the pool to make room for the new one
+    pub fn insert(
+        &mut self,
+        hash: H256,
+        tx: MempoolTransaction,
+        state_nonce: u64,
+    ) -> Result<Vec<H256>, PoolError> {
+        let sender = tx.sender();
+        let result = self.try_insert(hash, tx, state_nonce);
+        // Don't keep track of senders whose first transaction was rejected
+        if self
+            .senders
+            .get(&sender)
+            .is_some_and(|sender_txs| sender_txs.txs.is_empty())
+        {
+            self.senders.remove(&sender);
+        }
+        result
+    }
+
+    fn try_insert(
+        &mut self,
+        hash: H256,
+        tx: MempoolTransaction,
+        state_nonce: u64,
+    ) -> Result<Vec<H256>, PoolError> {
+        if self.transactions.contains_key(&hash) {
+            return Err(PoolError::AlreadyKnown);
+        }
+        if tx.nonce() < state_nonce {
+            return Err(PoolError::NonceTooLow);
+        }
+        let sender = tx.sender();
+        let nonce = tx.nonce();
+        // Deposits are forced through the L1, so they are not subject to the pool limits
+        let privileged = matches!(*tx, Transaction::PrivilegedL2Transaction(_));
+        self.update_sender(sender, |sender_txs| sender_txs.state_nonce = state_nonce);
+        // The sender was just added if it wasn't known
+        let sender_txs = &self.senders[&sender];
+
+        // Replace a transaction with the same nonce
+        if let Some(old_hash) = sender_txs.txs.get(&nonce).copied() {
+            let price_bump = self.config.price_bump;
+            if self
+                .transactions
+                .get(&old_hash)
+                .is_some_and(|old_tx| !is_fee_bump(old_tx, &tx, price_bump))
+            {
+                return Err(PoolError::ReplacementUnderpriced);
+            }
+            self.unindex_sender(&sender);
+            self.senders
+                .entry(sender)
+                .or_default()
+                .txs
+                .insert(nonce, hash);
+            self.transactions.remove(&old_hash);
+            self.transactions.insert(hash, tx);
+            self.index_sender(&sender);
+            return Ok(vec![old_hash]);
+        }
+
+        let is_pending = nonce == sender_txs.next_nonce;
+        if !privileged {
+            if is_pending && sender_txs.pending().count() >= self.config.account_slots {
+                return Err(PoolError::SenderPendingLimitReached(
+                    self.config.account_slots,
+                ));
+            }
+            if !is_pending && sender_txs.queued().count() >= self.config.account_queue {
+                return Err(PoolError::SenderQueueLimitReached(
+                    self.config.account_queue,
+                ));
+            }
+            // Reject the transaction upfront if it would be the first one evicted
+            let sub_pool_full = if is_pending {
+                self.pending_count >= self.config.global_slots
+            } else {
+                self.queued_count >= self.config.global_queue
+            };
+            if sub_pool_full {
+                let cheapest = self
+                    .eviction_candidate(is_pending, None)
+                    .and_then(|hash| self.transactions.get(&hash));
+                if cheapest.is_some_and(|cheapest| tx.gas_fee_cap() <= cheapest.gas_fee_cap()) {
+                    return Err(PoolError::Underpriced);
+                }
+            }
+        }
+
+        self.unindex_sender(&sender);
+        self.senders
+            .entry(sender)
+            .or_default()
+            .txs
+            .insert(nonce, hash);
+        self.transactions.insert(hash, tx);
+        self.index_sender(&sender);
+
+        // Adding a pending transaction can also promote queued ones, so the pending sub-pool might overflow by more than one
+        let mut evicted = Vec::new();
+        while self.pending_count > self.config.global_slots {
+            let Some(cheapest) = self.eviction_candidate(true, Some(hash)) else {
+                break;
+            };
+            self.remove(&cheapest);
+            evicted.push(cheapest);
+        }
+        while self.queued_count > self.config.global_queue {
+            let Some(cheapest) = self.eviction_candidate(false, Some(hash)) else {
+                break;
+            };
+            self.remove(&cheapest);
+            evicted.push(cheapest);
+        }
+        Ok(evicted)
+    }
+
+    /// Removes a transaction from the pool
+    pub fn remove(&mut self, hash: &H256) -> Option<MempoolTransaction> {
+        let sender = self.transactions.get(hash)?.sender();
+        self.unindex_sender(&sender);
+        let tx = self.transactions.remove(hash)?;
+        if let Some(sender_txs) = self.senders.get_mut(&sender) {
+            if sender_txs.txs.get(&tx.nonce()) == Some(hash) {
+                sender_txs.txs.remove(&tx.nonce());
+            }
+            if sender_txs.txs.is_empty() {
+                self.senders.remove(&sender);
+            }
+        }
+        self.index_sender(&sender);
+        Some(tx)
+    }
+
+    /// Returns the senders that have transactions in the pool
+    pub fn senders(&self) -> Vec<Address> {
+        self.senders.keys().copied().collect()
+    }
+
+    /// Updates the account nonces of the senders after a new head is set, removing the transactions that
+    /// can no longer be executed and promoting the queued transactions that no longer have a nonce gap.
+    /// Returns the hashes of the removed transactions
+    pub fn update_state_nonces(&mut self, nonces: &HashMap<Address, u64>) -> Vec<H256> {
+        let mut stale = Vec::new();
+        for (sender, nonce) in nonces {
+            if !self.senders.contains_key(sender) {
+                continue;
+            }
+            self.unindex_sender(sender);
+            if let Some(sender_txs) = self.senders.get_mut(sender) {
+                sender_txs.state_nonce = *nonce;
+                let remaining = sender_txs.txs.split_off(nonce);
+                let removed = std::mem::replace(&mut sender_txs.txs, remaining);
+                for hash in removed.into_values() {
+                    self.transactions.remove(&hash);
+                    stale.push(hash);
+                }
+                if sender_txs.txs.is_empty() {
+                    self.senders.remove(sender);
+                }
+            }
+            self.index_sender(sender);
+        }
+        stale
+    }
+
+    /// Returns the cheapest evictable transaction of the given sub-pool, never choosing `protected`
+    fn eviction_candidate(&self, pending: bool, protected: Option<H256>) -> Option<H256> {
+        let by_price = if pending {
+            &self.pending_by_price
+        } else {
+            &self.queued_by_price
+        };
+        by_price
+            .iter()
+            .map(|(_, hash)| *hash)
+            .find(|hash| Some(*hash) != protected)
+    }
+
+    /// Applies a change to the transactions of a sender (adding it if unknown), keeping the sub-pool counters and indexes up to date
+    fn update_sender(&mut self, sender: Address, update: impl FnOnce(&mut SenderTransactions)) {
+        self.unindex_sender(&sender);
+        update(self.senders.entry(sender).or_default());
+        self.index_sender(&sender);
+    }
+
+    /// Returns what the sender's transactions add to the sub-pool counters and indexes
+    fn sender_entries(&self, sender: &Address) -> Option<SenderEntries> {
+        let sender_txs = self.senders.get(sender)?;
+        let evictable = |hash: &H256| {
+            self.transactions
+                .get(hash)
+                .filter(|tx| !matches!(***tx, Transaction::PrivilegedL2Transaction(_)))
+                .map(|tx| (tx.gas_fee_cap(), *hash))
+        };
+        Some(SenderEntries {
+            pending: sender_txs.pending().count(),
+            queued: sender_txs.queued().count(),
+            evictable_pending: sender_txs.pending().next_back().and_then(evictable),
+            evictable_queued: sender_txs.queued().filter_map(evictable).collect(),
+        })
+    }
+
+    /// Removes the sender's transactions from the sub-pool counters and indexes, must be called before they change
+    fn unindex_sender(&mut self, sender: &Address) {
+        let Some(entries) = self.sender_entries(sender) else {
+            return;
+        };
+        self.pending_count -= entries.pending;
+        self.queued_count -= entries.queued;
+        if let Some(entry) = entries.evictable_pending {
+            self.pending_by_price.remove(&entry);
+        }
+        for entry in entries.evictable_queued {
+            self.queued_by_price.remove(&entry);
+        }
+    }
+
+    /// Adds the sender's transactions to the sub-pool counters and indexes, must be called after they change
+    fn index_sender(&mut self, sender: &Address) {
+        let Some(sender_txs) = self.senders.get_mut(sender) else {
+            return;
+        };
+        sender_txs.next_nonce = sender_txs.compute_next_nonce();
+        let Some(entries) = self.sender_entries(sender) else {
+            return;
+        };
+        self.pending_count += entries.pending;
+        self.queued_count += entries.queued;
+        self.pending_by_price.extend(entries.evictable_pending);
+        self.queued_by_price.extend(entries.evictable_queued);
+    }
+}
+
+/// Returns true if `new` raises both the fee cap and the tip of `old` by at least `price_bump` percent
+fn is_fee_bump(old: &Transaction, new: &Transaction, price_bump: u64) -> bool {
+    let bumped = |fee: u64| u128::from(fee) * u128::from(100 + price_bump) / 100;
+    u128::from(new.gas_fee_cap()) >= bumped(old.gas_fee_cap())
+        && u128::from(new.gas_tip_cap()) >= bumped(old.gas_tip_cap())
+}
+
+impl Store {
+    pub fn set_mempool_config(&self, config: MempoolConfig) {
+        info!("Using mempool limits {config:?}");
+        self.mempool.lock().unwrap().config = config;
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::MutexGuard,
+    thread::JoinHandle,
+};
+
+use ethereum_types::{H256, U256};
+use ethrex_core::types::{AccountState, BlockHeader, BlockNumber, EMPTY_TRIE_HASH};
+use ethrex_rlp::decode::RLPDecode;
+use tracing::{debug, info, warn};
+
+use crate::{error::StoreError, Store};
+
+/// Default amount of recent block states kept by a pruning node
+pub const DEFAULT_RETAINED_BLOCKS: u64 = 128;
+
+/// Max amount of trie nodes visited while holding the pruner's lock, new states can only be written between batches
+const SWEEP_BATCH_SIZE: usize = if cfg!(test) { 2 } else { 10_000 };
+
+/// Determines which states are kept in the DB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PruningMode {
+    /// Keep the state of every block ever processed
+    #[default]
+    Archive,
+    /// Only keep the state of the last `retained_blocks` canonical blocks (along with the safe and finalized blocks)
+    /// Trie nodes that are not reachable from any of these states are periodically removed from the DB
+    Full { retained_blocks: u64 },
+}
+
+/// Keeps track of the state roots created since the last pruning so that states belonging
+/// to recently added non-canonical blocks are not removed before they can be reorged into
+#[derive(Debug, Default)]
+pub(crate) struct StatePruner {
+    pub(crate) mode: PruningMode,
+    /// State roots produced by `apply_account_updates` indexed by the number of the block they belong to
+    pub(crate) recent_roots: BTreeMap<BlockNumber, HashSet<H256>>,
+    /// Head block number of the last pruning
+    pub(crate) last_pruned: BlockNumber,
+    /// Whether a pruning is running in the background
+    pub(crate) in_progress: bool,
+    /// Whether the state is being synced, nodes written by a sync are not linked to any block's state root until it completes
+    pub(crate) syncing: bool,
+}
+
+/// Trie nodes reachable from the retained states
+/// Storage tries are only marked right before their nodes are swept, so only one of them is kept in memory at a time
+#[derive(Default)]
+struct Marker {
+    /// State roots whose tries have already been marked
+    state_roots: HashSet<H256>,
+    state_nodes: HashSet<H256>,
+    /// Storage roots of the marked states that have not been marked yet, indexed by the hashed address of their account
+    storage_roots: BTreeMap<H256, HashSet<H256>>,
+}
+
+impl StatePruner {
+    pub(crate) fn record_root(&mut self, block_number: BlockNumber, state_root: H256) {
+        if matches!(self.mode, PruningMode::Full { .. }) {
+            self.recent_roots
+                .entry(block_number)
+                .or_default()
+                .insert(state_root);
+        }
+    }
+}
+
+impl Store {
+    pub fn set_pruning_mode(&self, mode: PruningMode) {
+        info!("Using {mode:?} pruning mode");
+        self.pruner.lock().unwrap().mode = mode;
+    }
+
+    /// Marks whether a sync is in progress, the state is not pruned while syncing
+    /// If a pruning is removing nodes this waits until it stops, so that no synced node can be removed afterwards
+    pub fn set_syncing(&self, syncing: bool) {
+        self.pruner.lock().unwrap().syncing = syncing;
+    }
+
+    /// Starts removing all trie nodes that are not part of the state of the last `retained_blocks` blocks before `head`,
+    /// the safe and finalized blocks, or any block added since the last pruning that is not older than the retained range.
+    /// Pruning only takes place once every `retained_blocks` blocks, and never when using `PruningMode::Archive`.
+    /// Nodes are removed by a background thread so that the caller is not blocked, returns its handle if a pruning was started.
+    /// Nothing is pruned while a sync is in progress or an interrupted snap sync is pending, as nodes that are not yet
+    /// linked to a block's state root would be removed
+    pub fn prune_state(&self, head: &BlockHeader) -> Result<Option<JoinHandle<()>>, StoreError> {
+        let mut pruner = self.pruner.lock().unwrap();
+        let PruningMode::Full { retained_blocks } = pruner.mode else {
+            return Ok(None);
+        };
+        if pruner.in_progress || head.number < pruner.last_pruned.saturating_add(retained_blocks) {
+            return Ok(None);
+        }
+        if pruner.syncing
+            || self.get_header_download_checkpoint()?.is_some()
+            || self.get_pivot_block_number()?.is_some()
+        {
+            debug!("Skipping state pruning while syncing");
+            return Ok(None);
+        }
+        let oldest_retained = head.number.saturating_sub(retained_blocks);
+
+        // Collect the state roots we need to keep
+        let mut retained_roots = HashSet::from([head.state_root]);
+        for number in oldest_retained..head.number {
+            if let Some(header) = self.get_block_header(number)? {
+                retained_roots.insert(header.state_root);
+            }
+        }
+        for number in [
+            self.get_finalized_block_number()?,
+            self.get_safe_block_number()?,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(header) = self.get_block_header(number)? {
+                retained_roots.insert(header.state_root);
+            }
+        }
+        for (_, roots) in pruner.recent_roots.range(oldest_retained..) {
+            retained_roots.extend(roots);
+        }
+        pruner.in_progress = true;
+        pruner.last_pruned = head.number;
+
+        let store = self.clone();
+        Ok(Some(std::thread::spawn(move || {
+            if let Err(err) = store.prune_unreachable_nodes(retained_roots, oldest_retained) {
+                warn!("Failed to prune state: {err}");
+            }
+            store.pruner.lock().unwrap().in_progress = false;
+        })))
+    }
+
+    /// Removes the trie nodes that can't be reached from the retained states nor from the ones added meanwhile
+    /// Nodes are swept in batches, new states can be written between them
+    fn prune_unreachable_nodes(
+        &self,
+        retained_roots: HashSet<H256>,
+        oldest_retained: BlockNumber,
+    ) -> Result<(), StoreError> {
+        // Mark all nodes reachable from the retained states, new states can be added meanwhile
+        let mut marker = Marker::default();
+        self.mark_states(&mut marker, retained_roots)?;
+        let mut removed = 0;
+
+        // Sweep all unmarked state trie nodes
+        let mut next_node = None;
+        loop {
+            let Some(_pruner) = self.lock_for_sweep(&mut marker, oldest_retained)? else {
+                return Ok(());
+            };
+            let (batch_removed, next) = self.engine.prune_state_trie_nodes(
+                next_node,
+                SWEEP_BATCH_SIZE,
+                &marker.state_nodes,
+            )?;
+            removed += batch_removed;
+            next_node = next;
+            if next_node.is_none() {
+                break;
+            }
+        }
+
+        // Sweep all unmarked storage trie nodes, one account at a time
+        let mut kept_storage_nodes = 0;
+        let mut next_address = Some(H256::zero());
+        while let Some(hashed_address) = next_address
+            .map(|start| self.engine.next_storage_trie_address(start))
+            .transpose()?
+            .flatten()
+        {
+            // Storage roots of the accounts that were already swept are no longer needed
+            marker.storage_roots = marker.storage_roots.split_off(&hashed_address);
+            let mut live_nodes = HashSet::new();
+            let mut next_node = None;
+            loop {
+                let Some(_pruner) = self.lock_for_sweep(&mut marker, oldest_retained)? else {
+                    return Ok(());
+                };
+                // Includes the storage roots of the states added since the last batch
+                if let Some(storage_roots) = marker.storage_roots.remove(&hashed_address) {
+                    self.mark_storage_tries(hashed_address, storage_roots, &mut live_nodes)?;
+                }
+                let (batch_removed, next) = self.engine.prune_storage_trie_nodes(
+                    hashed_address,
+                    next_node,
+                    SWEEP_BATCH_SIZE,
+                    &live_nodes,
+                )?;
+                removed += batch_removed;
+                next_node = next;
+                if next_node.is_none() {
+                    break;
+                }
+            }
+            kept_storage_nodes += live_nodes.len();
+            next_address = next_hash(hashed_address);
+        }
+
+        let Some(mut pruner) = self.lock_for_sweep(&mut marker, oldest_retained)? else {
+            return Ok(());
+        };
+        debug!(
+            "Pruned {removed} trie nodes, kept {} state nodes and {kept_storage_nodes} storage nodes",
+            marker.state_nodes.len()
+        );
+        pruner.recent_roots = pruner.recent_roots.split_off(&oldest_retained);
+        Ok(())
+    }
+
+    /// Takes the pruner's lock so that no new state can be written while sweeping a batch of nodes
+    /// States added since the last batch may use nodes that were only reachable from the states being pruned, so they are marked first
+    /// Marking them is cheap, as the subtries that were already marked are skipped
+    /// Returns None if a sync has started, as the nodes it writes are not reachable from any marked state
+    fn lock_for_sweep(
+        &self,
+        marker: &mut Marker,
+        oldest_retained: BlockNumber,
+    ) -> Result<Option<MutexGuard<'_, StatePruner>>, StoreError> {
+        let pruner = self.pruner.lock().unwrap();
+        if pruner.syncing {
+            info!("Aborting state pruning as a sync has started");
+            return Ok(None);
+        }
+        let recent_roots: HashSet<H256> = pruner
+            .recent_roots
+            .range(oldest_retained..)
+            .flat_map(|(_, roots)| roots.iter().copied())
+            .collect();
+        self.mark_states(marker, recent_roots)?;
+        Ok(Some(pruner))
+    }
+
+    /// Marks the nodes of the given states as live, their accounts' storage roots are recorded to be marked when swept
+    fn mark_states(
+        &self,
+        marker: &mut Marker,
+        state_roots: HashSet<H256>,
+    ) -> Result<(), StoreError> {
+        for state_root in state_roots {
+            if !marker.state_roots.insert(state_root) {
+                continue;
+            }
+            let mut decode_error = None;
+            self.engine.open_state_trie(state_root).mark_nodes(
+                &mut marker.state_nodes,
+                |path, value| match AccountState::decode(value) {
+                    Ok(account_state) if account_state.storage_root != *EMPTY_TRIE_HASH => {
+                        marker
+                            .storage_roots
+                            .entry(H256::from_slice(&path))
+                            .or_default()
+                            .insert(account_state.storage_root);
+                    }
+                    Ok(_) => {}
+                    Err(err) => decode_error = Some(err),
+                },
+            )?;
+            if let Some(err) = decode_error {
+                return Err(err.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the nodes of the given storage tries of an account as live
+    fn mark_storage_tries(
+        &self,
+        hashed_address: H256,
+        storage_roots: HashSet<H256>,
+        live_nodes: &mut HashSet<H256>,
+    ) -> Result<(), StoreError> {
+        for storage_root in storage_roots {
+            self.engine
+                .open_storage_trie(hashed_address, storage_root)
+                .mark_nodes(live_nodes, |_, _| {})?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the hash that follows the given one, or None if it is the highest one
+fn next_hash(hash: H256) -> Option<H256> {
+    let next = U256::from_big_endian(hash.as_bytes()).checked_add(U256::one())?;
+    let mut next_hash = H256::zero();
+    next.to_big_endian(next_hash.as_bytes_mut());
+    Some(next_hash)
+}
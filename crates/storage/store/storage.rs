@@ -1,39 +1,56 @@
 use self::engines::in_memory::Store as InMemoryStore;
 #[cfg(feature = "libmdbx")]
 use self::engines::libmdbx::Store as LibmdbxStore;
-use self::error::StoreError;
+use self::error::{PoolError, StoreError};
 use bytes::Bytes;
-use engines::api::StoreEngine;
+use engines::api::{PayloadBundle, StoreEngine};
 #[cfg(feature = "redb")]
 use engines::redb::RedBStore;
 use ethereum_types::{Address, H256, U256};
 use ethrex_core::types::{
     code_hash, AccountInfo, AccountState, BlobsBundle, Block, BlockBody, BlockHash, BlockHeader,
-    BlockNumber, ChainConfig, Genesis, GenesisAccount, Index, MempoolTransaction, Receipt,
-    Transaction, TxType, EMPTY_TRIE_HASH,
+    BlockNumber, ChainConfig, EncodedRequests, Genesis, GenesisAccount, Index, MempoolTransaction,
+    Receipt, Transaction, TxType, EMPTY_TRIE_HASH,
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_trie::Trie;
+use ethrex_trie::{Nibbles, Trie};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest as _, Keccak256};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
 use tracing::info;
 
+mod bloom_bits;
 mod engines;
 pub mod error;
+mod mempool;
+mod pruning;
 mod rlp;
 
+pub use bloom_bits::{
+    bloom_bit_indexes, bloom_has_bit, BLOOM_BITS, BLOOM_BITS_CONFIRMATIONS, BLOOM_BITS_SECTION_SIZE,
+};
+pub use mempool::{Mempool, MempoolConfig, MempoolContent};
+use pruning::StatePruner;
+pub use pruning::{PruningMode, DEFAULT_RETAINED_BLOCKS};
+
 #[derive(Debug, Clone)]
 pub struct Store {
     // TODO: Check if we can remove this mutex and move it to the in_memory::Store struct
     engine: Arc<dyn StoreEngine>,
-    pub mempool: Arc<Mutex<HashMap<H256, MempoolTransaction>>>,
+    pub mempool: Arc<Mutex<Mempool>>,
     pub blobs_bundle_pool: Arc<Mutex<HashMap<H256, BlobsBundle>>>,
+    pruner: Arc<Mutex<StatePruner>>,
+    /// Notifies the hashes of the transactions added to the mempool
+    mempool_notifier: broadcast::Sender<H256>,
 }
 
+/// Amount of mempool notifications a subscriber can fall behind on before missing some
+const MEMPOOL_NOTIFICATION_CAPACITY: usize = 1024;
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub enum EngineType {
@@ -81,19 +98,25 @@ impl Store {
             #[cfg(feature = "libmdbx")]
             EngineType::Libmdbx => Self {
                 engine: Arc::new(LibmdbxStore::new(path)?),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
+                mempool: Arc::new(Mutex::new(Mempool::default())),
                 blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
+                pruner: Arc::new(Mutex::new(StatePruner::default())),
+                mempool_notifier: broadcast::channel(MEMPOOL_NOTIFICATION_CAPACITY).0,
             },
             EngineType::InMemory => Self {
                 engine: Arc::new(InMemoryStore::new()),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
+                mempool: Arc::new(Mutex::new(Mempool::default())),
                 blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
+                pruner: Arc::new(Mutex::new(StatePruner::default())),
+                mempool_notifier: broadcast::channel(MEMPOOL_NOTIFICATION_CAPACITY).0,
             },
             #[cfg(feature = "redb")]
             EngineType::RedB => Self {
                 engine: Arc::new(RedBStore::new()?),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
+                mempool: Arc::new(Mutex::new(Mempool::default())),
                 blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
+                pruner: Arc::new(Mutex::new(StatePruner::default())),
+                mempool_notifier: broadcast::channel(MEMPOOL_NOTIFICATION_CAPACITY).0,
             },
         };
         info!("Started store engine");
@@ -259,21 +282,44 @@ impl Store {
         self.engine.get_transaction_location(transaction_hash)
     }
 
-    /// Add transaction to the pool
+    /// Add transaction to the pool, given the nonce of its sender's account at the latest block
+    /// Transactions replaced or evicted to make room for the new one are removed along with their blobs bundles
     pub fn add_transaction_to_pool(
         &self,
         hash: H256,
         transaction: MempoolTransaction,
-    ) -> Result<(), StoreError> {
-        let mut mempool = self
+        sender_nonce: u64,
+    ) -> Result<(), PoolError> {
+        let removed = self
             .mempool
             .lock()
-            .map_err(|error| StoreError::Custom(error.to_string()))?;
-        mempool.insert(hash, transaction);
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .insert(hash, transaction, sender_nonce)?;
+        self.remove_blobs_bundles_from_pool(&removed)?;
+        // Sending only fails if there are no subscribers
+        let _ = self.mempool_notifier.send(hash);
 
         Ok(())
     }
 
+    /// Returns a receiver that will be notified of the hash of each transaction added to the pool from now on
+    pub fn subscribe_to_mempool(&self) -> broadcast::Receiver<H256> {
+        self.mempool_notifier.subscribe()
+    }
+
+    /// Get a transaction from the pool given its hash
+    pub fn get_transaction_from_pool(
+        &self,
+        hash: H256,
+    ) -> Result<Option<MempoolTransaction>, StoreError> {
+        Ok(self
+            .mempool
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .get(&hash)
+            .cloned())
+    }
+
     /// Add a blobs bundle to the pool by its blob transaction hash
     pub fn add_blobs_bundle_to_pool(
         &self,
@@ -302,49 +348,84 @@ impl Store {
 
     /// Remove a transaction from the pool
     pub fn remove_transaction_from_pool(&self, hash: &H256) -> Result<(), StoreError> {
-        let mut mempool = self
+        let removed = self
             .mempool
             .lock()
-            .map_err(|error| StoreError::Custom(error.to_string()))?;
-        if let Some(tx) = mempool.get(hash) {
-            if matches!(tx.tx_type(), TxType::EIP4844) {
-                self.blobs_bundle_pool
-                    .lock()
-                    .map_err(|error| StoreError::Custom(error.to_string()))?
-                    .remove(&tx.compute_hash());
-            }
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .remove(hash);
+        if removed.is_some_and(|tx| matches!(tx.tx_type(), TxType::EIP4844)) {
+            self.remove_blobs_bundles_from_pool(&[*hash])?;
+        }
 
-            mempool.remove(hash);
-        };
+        Ok(())
+    }
 
+    fn remove_blobs_bundles_from_pool(&self, tx_hashes: &[H256]) -> Result<(), StoreError> {
+        let mut blobs_bundle_pool = self
+            .blobs_bundle_pool
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?;
+        for hash in tx_hashes {
+            blobs_bundle_pool.remove(hash);
+        }
         Ok(())
     }
 
-    /// Applies the filter and returns a set of suitable transactions from the mempool.
+    /// Removes the transactions that can no longer be included after `head` became the latest block,
+    /// that is, the ones with a nonce lower than their sender's account nonce at `head`
+    pub fn remove_stale_transactions_from_pool(&self, head: BlockHash) -> Result<(), StoreError> {
+        let senders = self
+            .mempool
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .senders();
+        let mut nonces = HashMap::new();
+        for sender in senders {
+            let nonce = self
+                .get_account_info_by_hash(head, sender)?
+                .map(|info| info.nonce)
+                .unwrap_or_default();
+            nonces.insert(sender, nonce);
+        }
+        let stale = self
+            .mempool
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .update_state_nonces(&nonces);
+        self.remove_blobs_bundles_from_pool(&stale)
+    }
+
+    /// Applies the filter and returns a set of suitable transactions from the pending sub-pool of the mempool.
     /// These transactions will be grouped by sender and sorted by nonce
     pub fn filter_pool_transactions(
         &self,
         filter: &dyn Fn(&Transaction) -> bool,
     ) -> Result<HashMap<Address, Vec<MempoolTransaction>>, StoreError> {
-        let mut txs_by_sender: HashMap<Address, Vec<MempoolTransaction>> = HashMap::new();
         let mempool = self
             .mempool
             .lock()
             .map_err(|error| StoreError::Custom(error.to_string()))?;
 
-        for (_, tx) in mempool.iter() {
-            if filter(tx) {
-                txs_by_sender
-                    .entry(tx.sender())
-                    .or_default()
-                    .push(tx.clone())
-            }
-        }
-
-        txs_by_sender.iter_mut().for_each(|(_, txs)| txs.sort());
+        let txs_by_sender = mempool
+            .pending()
+            .into_iter()
+            .filter_map(|(sender, txs)| {
+                let txs: Vec<_> = txs.into_iter().filter(|tx| filter(tx)).cloned().collect();
+                (!txs.is_empty()).then_some((sender, txs))
+            })
+            .collect();
         Ok(txs_by_sender)
     }
 
+    /// Returns the pending and queued transactions of the mempool, grouped by sender and sorted by nonce
+    pub fn get_pool_content(&self) -> Result<MempoolContent, StoreError> {
+        Ok(self
+            .mempool
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .content())
+    }
+
     /// Gets hashes from possible_hashes that are not already known in the mempool.
     pub fn filter_unknown_transactions(
         &self,
@@ -355,10 +436,9 @@ impl Store {
             .lock()
             .map_err(|error| StoreError::Custom(error.to_string()))?;
 
-        let tx_set: HashSet<_> = mempool.iter().map(|(hash, _)| hash).collect();
         Ok(possible_hashes
             .iter()
-            .filter(|hash| !tx_set.contains(hash))
+            .filter(|hash| !mempool.contains(hash))
             .copied()
             .collect())
     }
@@ -415,9 +495,12 @@ impl Store {
         block_hash: BlockHash,
         account_updates: &[AccountUpdate],
     ) -> Result<Option<H256>, StoreError> {
-        let Some(mut state_trie) = self.state_trie(block_hash)? else {
+        let Some(parent_header) = self.get_block_header_by_hash(block_hash)? else {
             return Ok(None);
         };
+        // Hold the pruner's lock while writing so that the new nodes are not pruned before they are linked to the new root
+        let mut pruner = self.pruner.lock().unwrap();
+        let mut state_trie = self.engine.open_state_trie(parent_header.state_root);
         for update in account_updates.iter() {
             let hashed_address = hash_address(&update.address);
             if update.removed {
@@ -458,7 +541,9 @@ impl Store {
                 state_trie.insert(hashed_address, account_state.encode_to_vec())?;
             }
         }
-        Ok(Some(state_trie.hash()?))
+        let state_root = state_trie.hash()?;
+        pruner.record_root(parent_header.number + 1, state_root);
+        Ok(Some(state_root))
     }
 
     /// Adds all genesis accounts and returns the genesis block's state_root
@@ -923,10 +1008,7 @@ impl Store {
         self.engine.add_payload(payload_id, block)
     }
 
-    pub fn get_payload(
-        &self,
-        payload_id: u64,
-    ) -> Result<Option<(Block, U256, BlobsBundle, bool)>, StoreError> {
+    pub fn get_payload(&self, payload_id: u64) -> Result<Option<PayloadBundle>, StoreError> {
         self.engine.get_payload(payload_id)
     }
 
@@ -936,10 +1018,17 @@ impl Store {
         block: Block,
         block_value: U256,
         blobs_bundle: BlobsBundle,
+        requests: Vec<EncodedRequests>,
         completed: bool,
     ) -> Result<(), StoreError> {
-        self.engine
-            .update_payload(payload_id, block, block_value, blobs_bundle, completed)
+        self.engine.update_payload(
+            payload_id,
+            block,
+            block_value,
+            blobs_bundle,
+            requests,
+            completed,
+        )
     }
 
     /// Creates a new state trie with an empty state root, for testing purposes only
@@ -959,6 +1048,77 @@ impl Store {
         self.engine.open_storage_trie(account_hash, storage_root)
     }
 
+    /// Sets the hash of the last block header downloaded during snap sync
+    pub fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.engine.set_header_download_checkpoint(block_hash)
+    }
+
+    /// Gets the hash of the last block header downloaded during snap sync
+    pub fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        self.engine.get_header_download_checkpoint()
+    }
+
+    /// Sets the number of the snap sync pivot block
+    pub fn set_pivot_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.engine.set_pivot_block_number(block_number)
+    }
+
+    /// Gets the number of the snap sync pivot block
+    pub fn get_pivot_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.engine.get_pivot_block_number()
+    }
+
+    /// Sets the next account hash to request and the root of the partial state trie built from the downloaded account ranges
+    pub fn set_state_trie_ranges_checkpoint(
+        &self,
+        next_account: H256,
+        partial_root: H256,
+    ) -> Result<(), StoreError> {
+        self.engine
+            .set_state_trie_ranges_checkpoint(next_account, partial_root)
+    }
+
+    /// Gets the next account hash to request and the root of the partial state trie built from the downloaded account ranges
+    pub fn get_state_trie_ranges_checkpoint(&self) -> Result<Option<(H256, H256)>, StoreError> {
+        self.engine.get_state_trie_ranges_checkpoint()
+    }
+
+    /// Sets the hashed addresses of the accounts whose storage is pending to be fetched or healed
+    pub fn set_storage_heal_paths(&self, accounts: Vec<H256>) -> Result<(), StoreError> {
+        self.engine.set_storage_heal_paths(accounts)
+    }
+
+    /// Gets the hashed addresses of the accounts whose storage is pending to be fetched or healed
+    pub fn get_storage_heal_paths(&self) -> Result<Option<Vec<H256>>, StoreError> {
+        self.engine.get_storage_heal_paths()
+    }
+
+    /// Sets the code hashes of the bytecodes pending to be fetched
+    pub fn set_pending_bytecodes(&self, code_hashes: Vec<H256>) -> Result<(), StoreError> {
+        self.engine.set_pending_bytecodes(code_hashes)
+    }
+
+    /// Gets the code hashes of the bytecodes pending to be fetched
+    pub fn get_pending_bytecodes(&self) -> Result<Option<Vec<H256>>, StoreError> {
+        self.engine.get_pending_bytecodes()
+    }
+
+    /// Sets the paths and hashes of the state trie nodes pending to be healed
+    /// The presence of this checkpoint means that all account ranges have already been downloaded
+    pub fn set_state_heal_paths(&self, paths: Vec<(Nibbles, H256)>) -> Result<(), StoreError> {
+        self.engine.set_state_heal_paths(paths)
+    }
+
+    /// Gets the paths and hashes of the state trie nodes pending to be healed
+    pub fn get_state_heal_paths(&self) -> Result<Option<Vec<(Nibbles, H256)>>, StoreError> {
+        self.engine.get_state_heal_paths()
+    }
+
+    /// Removes all snap sync checkpoints, should be called once a snap sync is complete
+    pub fn clear_snap_state(&self) -> Result<(), StoreError> {
+        self.engine.clear_snap_state()
+    }
+
     pub fn get_receipts_for_block(
         &self,
         block_hash: &BlockHash,
@@ -993,8 +1153,8 @@ mod tests {
     use bytes::Bytes;
     use ethereum_types::{H256, U256};
     use ethrex_core::{
-        types::{Transaction, TxType, BYTES_PER_BLOB},
-        Bloom,
+        types::{EIP1559Transaction, Transaction, TxType, BYTES_PER_BLOB},
+        Bloom, BloomInput,
     };
     use ethrex_rlp::decode::RLPDecode;
 
@@ -1043,7 +1203,14 @@ mod tests {
         run_test(&test_store_block_tags, engine_type);
         run_test(&test_chain_config_storage, engine_type);
         run_test(&test_genesis_block, engine_type);
+        run_test(&test_prune_state, engine_type);
+        run_test(&test_bloom_bits_index, engine_type);
+        run_test(&test_snap_state, engine_type);
         run_test(&test_filter_mempool_transactions, engine_type);
+        run_test(&test_mempool_pending_and_queued, engine_type);
+        run_test(&test_mempool_replacement, engine_type);
+        run_test(&test_mempool_limits, engine_type);
+        run_test(&test_mempool_promotion_eviction, engine_type);
         run_test(&blobs_bundle_loadtest, engine_type);
     }
 
@@ -1061,12 +1228,180 @@ mod tests {
         store
             .add_initial_state(genesis_kurtosis)
             .expect("second genesis with same block");
-        panic::catch_unwind(move || {
+        panic::catch_unwind(panic::AssertUnwindSafe(move || {
             let _ = store.add_initial_state(genesis_hive);
-        })
+        }))
         .expect_err("genesis with a different block should panic");
     }
 
+    fn test_snap_state(store: Store) {
+        assert_eq!(store.get_header_download_checkpoint().unwrap(), None);
+        assert_eq!(store.get_state_heal_paths().unwrap(), None);
+
+        let block_hash = H256::random();
+        let (next_account, partial_root) = (H256::random(), H256::random());
+        let accounts = vec![H256::random(), H256::random()];
+        let code_hashes = vec![H256::random()];
+        let heal_paths = vec![
+            (Nibbles::default(), H256::random()),
+            (Nibbles::from_hex(vec![1, 2, 3]), H256::random()),
+        ];
+        store.set_header_download_checkpoint(block_hash).unwrap();
+        store.set_pivot_block_number(42).unwrap();
+        store
+            .set_state_trie_ranges_checkpoint(next_account, partial_root)
+            .unwrap();
+        store.set_storage_heal_paths(accounts.clone()).unwrap();
+        store.set_pending_bytecodes(code_hashes.clone()).unwrap();
+        store.set_state_heal_paths(heal_paths.clone()).unwrap();
+
+        assert_eq!(
+            store.get_header_download_checkpoint().unwrap(),
+            Some(block_hash)
+        );
+        assert_eq!(store.get_pivot_block_number().unwrap(), Some(42));
+        assert_eq!(
+            store.get_state_trie_ranges_checkpoint().unwrap(),
+            Some((next_account, partial_root))
+        );
+        assert_eq!(store.get_storage_heal_paths().unwrap(), Some(accounts));
+        assert_eq!(store.get_pending_bytecodes().unwrap(), Some(code_hashes));
+        assert_eq!(store.get_state_heal_paths().unwrap(), Some(heal_paths));
+
+        store.clear_snap_state().unwrap();
+        assert_eq!(store.get_header_download_checkpoint().unwrap(), None);
+        assert_eq!(store.get_pivot_block_number().unwrap(), None);
+        assert_eq!(store.get_state_trie_ranges_checkpoint().unwrap(), None);
+        assert_eq!(store.get_storage_heal_paths().unwrap(), None);
+        assert_eq!(store.get_pending_bytecodes().unwrap(), None);
+        assert_eq!(store.get_state_heal_paths().unwrap(), None);
+    }
+
+    fn test_bloom_bits_index(store: Store) {
+        let address = Address::random();
+        let mut bloom = Bloom::zero();
+        bloom.accrue(BloomInput::Raw(address.as_bytes()));
+        // Build a chain of one section plus the confirmations it needs, with logs from `address` only in block 10
+        let head = BLOOM_BITS_SECTION_SIZE + BLOOM_BITS_CONFIRMATIONS - 1;
+        for number in 0..=head {
+            let header = BlockHeader {
+                number,
+                logs_bloom: if number == 10 { bloom } else { Bloom::zero() },
+                ..Default::default()
+            };
+            let block_hash = header.compute_block_hash();
+            store.add_block_header(block_hash, header).unwrap();
+            store.set_canonical_block(number, block_hash).unwrap();
+        }
+
+        // The section is not indexed until it has enough confirmations
+        store.update_bloom_bits_index(head - 1, 0).unwrap();
+        assert_eq!(store.get_bloom_bits_sections().unwrap(), 0);
+        store.update_bloom_bits_index(head, head).unwrap();
+        assert_eq!(store.get_bloom_bits_sections().unwrap(), 1);
+        for bit in bloom_bit_indexes(address.as_bytes()) {
+            assert!(bloom_has_bit(&bloom, bit));
+            let bit_vector = store.get_bloom_bits(0, bit).unwrap().unwrap();
+            assert_eq!(bit_vector.len(), BLOOM_BITS_SECTION_SIZE as usize / 8);
+            assert_eq!(bit_vector[1], 0x20);
+            assert!(bit_vector
+                .iter()
+                .enumerate()
+                .all(|(index, byte)| index == 1 || *byte == 0));
+        }
+
+        // A reorg within an indexed section drops the section, which is indexed again from the new canonical blocks
+        let header = BlockHeader {
+            number: 10,
+            gas_used: 1,
+            ..Default::default()
+        };
+        let block_hash = header.compute_block_hash();
+        store.add_block_header(block_hash, header).unwrap();
+        store.set_canonical_block(10, block_hash).unwrap();
+        store.update_bloom_bits_index(head, 10).unwrap();
+        assert_eq!(store.get_bloom_bits_sections().unwrap(), 1);
+        for bit in bloom_bit_indexes(address.as_bytes()) {
+            let bit_vector = store.get_bloom_bits(0, bit).unwrap().unwrap();
+            assert!(bit_vector.iter().all(|byte| *byte == 0));
+        }
+    }
+
+    fn test_prune_state(store: Store) {
+        store.set_pruning_mode(PruningMode::Full { retained_blocks: 2 });
+        let address = Address::random();
+        let storage_key = H256::random();
+        // Build a chain of 5 blocks, each updating the same account
+        let genesis_header = BlockHeader {
+            state_root: store.setup_genesis_state_trie(HashMap::new()).unwrap(),
+            ..Default::default()
+        };
+        let mut block_hashes = vec![genesis_header.compute_block_hash()];
+        let mut head = genesis_header.clone();
+        store
+            .add_block_header(block_hashes[0], genesis_header)
+            .unwrap();
+        store.set_canonical_block(0, block_hashes[0]).unwrap();
+        for number in 1..=5 {
+            let parent_hash = block_hashes[number as usize - 1];
+            let mut update = AccountUpdate::new(address);
+            update.info = Some(AccountInfo {
+                code_hash: H256::zero(),
+                balance: U256::from(number),
+                nonce: number,
+            });
+            update.added_storage = HashMap::from([(storage_key, U256::from(number))]);
+            let state_root = store
+                .apply_account_updates(parent_hash, &[update])
+                .unwrap()
+                .unwrap();
+            head = BlockHeader {
+                number,
+                parent_hash,
+                state_root,
+                ..Default::default()
+            };
+            let block_hash = head.compute_block_hash();
+            store.add_block_header(block_hash, head.clone()).unwrap();
+            store.set_canonical_block(number, block_hash).unwrap();
+            block_hashes.push(block_hash);
+        }
+
+        // Nothing is pruned while syncing or while an interrupted snap sync is pending
+        store.set_syncing(true);
+        assert!(store.prune_state(&head).unwrap().is_none());
+        store.set_syncing(false);
+        store.set_pivot_block_number(head.number).unwrap();
+        assert!(store.prune_state(&head).unwrap().is_none());
+        store.clear_snap_state().unwrap();
+
+        store
+            .prune_state(&head)
+            .unwrap()
+            .expect("pruning should start")
+            .join()
+            .unwrap();
+
+        // The states of the last 2 blocks before the head and the head itself should be kept
+        for number in 3..=5 {
+            let block_hash = block_hashes[number as usize];
+            let info = store
+                .get_account_info_by_hash(block_hash, address)
+                .unwrap()
+                .unwrap();
+            assert_eq!(info.balance, U256::from(number));
+            let storage_value = store
+                .get_storage_at_hash(block_hash, address, storage_key)
+                .unwrap();
+            assert_eq!(storage_value, Some(U256::from(number)));
+        }
+        // Older states should be removed
+        for block_hash in &block_hashes[1..3] {
+            let info = store.get_account_info_by_hash(*block_hash, address);
+            assert!(!matches!(info, Ok(Some(_))));
+        }
+    }
+
     fn remove_test_dbs(path: &str) {
         // Removes all test databases from filesystem
         if std::path::Path::new(path).exists() {
@@ -1132,6 +1467,7 @@ mod tests {
             blob_gas_used: Some(0x00),
             excess_blob_gas: Some(0x00),
             parent_beacon_block_root: Some(H256::zero()),
+            requests_hash: None,
         };
         let block_body = BlockBody {
             transactions: vec![Transaction::decode(&hex::decode("b86f02f86c8330182480114e82f618946177843db3138ae69679a54b95cf345ed759450d870aa87bee53800080c080a0151ccc02146b9b11adf516e6787b59acae3e76544fdcd75e77e67c6b598ce65da064c5dd5aae2fbb535830ebbdad0234975cd7ece3562013b63ea18cc0df6c97d4").unwrap()).unwrap(),
@@ -1305,15 +1641,148 @@ mod tests {
         let filter =
             |tx: &Transaction| -> bool { matches!(tx, Transaction::EIP4844Transaction(_)) };
         store
-            .add_transaction_to_pool(blob_tx_hash, blob_tx.clone())
+            .add_transaction_to_pool(blob_tx_hash, blob_tx.clone(), 0)
             .unwrap();
         store
-            .add_transaction_to_pool(plain_tx_hash, plain_tx)
+            .add_transaction_to_pool(plain_tx_hash, plain_tx, 0)
             .unwrap();
         let txs = store.filter_pool_transactions(&filter).unwrap();
         assert_eq!(txs, HashMap::from([(blob_tx.sender(), vec![blob_tx])]));
     }
 
+    fn pool_tx(sender: Address, nonce: u64, fee: u64) -> (H256, MempoolTransaction) {
+        let tx = Transaction::EIP1559Transaction(EIP1559Transaction {
+            nonce,
+            max_fee_per_gas: fee,
+            max_priority_fee_per_gas: fee,
+            gas_limit: 21000,
+            ..Default::default()
+        });
+        (tx.compute_hash(), MempoolTransaction::new(tx, sender))
+    }
+
+    fn pool_nonces(txs: &[&MempoolTransaction]) -> Vec<u64> {
+        txs.iter().map(|tx| tx.nonce()).collect()
+    }
+
+    fn test_mempool_pending_and_queued(store: Store) {
+        let sender = Address::random();
+        for nonce in [0, 2, 3] {
+            let (hash, tx) = pool_tx(sender, nonce, 1);
+            store.add_transaction_to_pool(hash, tx, 0).unwrap();
+        }
+        {
+            let mempool = store.mempool.lock().unwrap();
+            assert_eq!(pool_nonces(&mempool.pending()[&sender]), vec![0]);
+            assert_eq!(pool_nonces(&mempool.queued()[&sender]), vec![2, 3]);
+        }
+        // Filling the gap promotes the queued transactions
+        let (hash, tx) = pool_tx(sender, 1, 1);
+        store.add_transaction_to_pool(hash, tx, 0).unwrap();
+        let txs = store.filter_pool_transactions(&|_| true).unwrap();
+        assert_eq!(txs[&sender].len(), 4);
+        assert!(store.mempool.lock().unwrap().queued().is_empty());
+        // Transactions with a nonce lower than the account's are not valid anymore
+        store
+            .mempool
+            .lock()
+            .unwrap()
+            .update_state_nonces(&HashMap::from([(sender, 2)]));
+        let txs = store.filter_pool_transactions(&|_| true).unwrap();
+        assert_eq!(
+            txs[&sender].iter().map(|tx| tx.nonce()).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+
+    fn test_mempool_replacement(store: Store) {
+        let sender = Address::random();
+        let (old_hash, old_tx) = pool_tx(sender, 0, 100);
+        store.add_transaction_to_pool(old_hash, old_tx, 0).unwrap();
+        // The default price bump is 10%
+        let (hash, tx) = pool_tx(sender, 0, 109);
+        assert!(matches!(
+            store.add_transaction_to_pool(hash, tx, 0),
+            Err(PoolError::ReplacementUnderpriced)
+        ));
+        let (hash, tx) = pool_tx(sender, 0, 110);
+        store.add_transaction_to_pool(hash, tx, 0).unwrap();
+        assert!(store.get_transaction_from_pool(old_hash).unwrap().is_none());
+        assert!(store.get_transaction_from_pool(hash).unwrap().is_some());
+    }
+
+    fn test_mempool_limits(store: Store) {
+        store.set_mempool_config(MempoolConfig {
+            global_slots: 3,
+            global_queue: 1,
+            account_slots: 2,
+            account_queue: 1,
+            price_bump: 10,
+        });
+        let sender = Address::random();
+        for nonce in [0, 1] {
+            let (hash, tx) = pool_tx(sender, nonce, 10);
+            store.add_transaction_to_pool(hash, tx, 0).unwrap();
+        }
+        let (hash, tx) = pool_tx(sender, 2, 10);
+        assert!(matches!(
+            store.add_transaction_to_pool(hash, tx, 0),
+            Err(PoolError::SenderPendingLimitReached(2))
+        ));
+        let (hash, tx) = pool_tx(sender, 5, 10);
+        store.add_transaction_to_pool(hash, tx, 0).unwrap();
+        let (hash, tx) = pool_tx(sender, 6, 10);
+        assert!(matches!(
+            store.add_transaction_to_pool(hash, tx, 0),
+            Err(PoolError::SenderQueueLimitReached(1))
+        ));
+
+        // The pending pool is full once another sender adds a transaction
+        let (cheap_hash, cheap_tx) = pool_tx(Address::random(), 0, 5);
+        store
+            .add_transaction_to_pool(cheap_hash, cheap_tx, 0)
+            .unwrap();
+        let (hash, tx) = pool_tx(Address::random(), 0, 4);
+        assert!(matches!(
+            store.add_transaction_to_pool(hash, tx, 0),
+            Err(PoolError::Underpriced)
+        ));
+        // A better paying transaction evicts the cheapest one
+        let (hash, tx) = pool_tx(Address::random(), 0, 20);
+        store.add_transaction_to_pool(hash, tx, 0).unwrap();
+        assert!(store
+            .get_transaction_from_pool(cheap_hash)
+            .unwrap()
+            .is_none());
+        assert!(store.get_transaction_from_pool(hash).unwrap().is_some());
+    }
+
+    fn test_mempool_promotion_eviction(store: Store) {
+        store.set_mempool_config(MempoolConfig {
+            global_slots: 3,
+            global_queue: 2,
+            account_slots: 16,
+            account_queue: 4,
+            price_bump: 10,
+        });
+        let sender = Address::random();
+        for nonce in [0, 2, 3] {
+            let (hash, tx) = pool_tx(sender, nonce, 10);
+            store.add_transaction_to_pool(hash, tx, 0).unwrap();
+        }
+        let (cheap_hash, cheap_tx) = pool_tx(Address::random(), 0, 5);
+        store
+            .add_transaction_to_pool(cheap_hash, cheap_tx, 0)
+            .unwrap();
+        // Filling the gap promotes two transactions, so two pending transactions have to be evicted
+        let (hash, tx) = pool_tx(sender, 1, 10);
+        store.add_transaction_to_pool(hash, tx, 0).unwrap();
+        let mempool = store.mempool.lock().unwrap();
+        assert!(mempool.get(&cheap_hash).is_none());
+        assert_eq!(pool_nonces(&mempool.pending()[&sender]), vec![0, 1, 2]);
+        assert!(mempool.queued().is_empty());
+    }
+
     fn blobs_bundle_loadtest(store: Store) {
         // Write a bundle of 6 blobs 10 times
         // If this test fails please adjust the max_size in the DB config
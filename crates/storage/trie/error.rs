@@ -1,3 +1,4 @@
+use ethereum_types::H256;
 use ethrex_rlp::error::RLPDecodeError;
 #[cfg(feature = "redb")]
 use redb::{CommitError, StorageError, TableError, TransactionError};
@@ -27,6 +28,8 @@ pub enum TrieError {
     Verify(String),
     #[error("Inconsistent internal tree structure")]
     InconsistentTree,
+    #[error("Missing trie node {0:#x}")]
+    MissingNode(H256),
     #[error("Lock Error: Panicked when trying to acquire a lock")]
     LockError,
 }
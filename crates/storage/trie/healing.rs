@@ -0,0 +1,428 @@
+use std::collections::{HashMap, VecDeque};
+
+use ethereum_types::H256;
+
+use crate::{
+    error::TrieError, nibbles::Nibbles, node::Node, node_hash::NodeHash, state::TrieState, PathRLP,
+    Trie, ValueRLP,
+};
+
+/// Rebuilds a trie whose nodes are (partially) missing from its DB by fetching the missing nodes by path
+/// Nodes are only written to the DB once all of their children are stored, so a node being present in the DB means
+/// that its whole subtrie is present too, even if the healing process is interrupted.
+/// Leaves are held back until the owner releases them, so that data referenced by the leaf's value (such as an
+/// account's storage trie) can be healed before the leaf is considered complete.
+/// The healer doesn't hold the trie itself, so that it can be kept between requests without keeping the trie open,
+/// all methods accessing the DB take the trie being healed instead
+pub struct TrieHealer {
+    /// Nodes that still need to be fetched
+    missing: VecDeque<MissingNode>,
+    /// Nodes that were handed out by `next_paths` and are waiting for a response, by path
+    requested: HashMap<Nibbles, MissingNode>,
+    /// Fetched nodes waiting for their children (or their own release if they are leaves), by path
+    pending: HashMap<Nibbles, PendingNode>,
+    /// Root of the trie, when resuming from a frontier it is only requested after the frontier is healed
+    deferred_root: Option<MissingNode>,
+}
+
+struct MissingNode {
+    path: Nibbles,
+    hash: NodeHash,
+    parent: Option<Nibbles>,
+}
+
+struct PendingNode {
+    node: Node,
+    hash: NodeHash,
+    parent: Option<Nibbles>,
+    dependencies: usize,
+}
+
+/// A leaf fetched by a `TrieHealer`
+/// It won't be written to the DB until it is released via `TrieHealer::release_leaf`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealedLeaf {
+    /// Path of the leaf node within the trie
+    pub path: Nibbles,
+    /// Full path of the value stored in the leaf
+    pub key: PathRLP,
+    pub value: ValueRLP,
+}
+
+impl TrieHealer {
+    /// Creates a healer for the given trie, the trie's root will be requested first if it is not already stored
+    pub fn new(trie: &Trie) -> Result<Self, TrieError> {
+        let mut missing = VecDeque::new();
+        if let Some(root) = &trie.root {
+            if !trie.state.contains_node(root)? {
+                missing.push_back(MissingNode {
+                    path: Nibbles::default(),
+                    hash: root.clone(),
+                    parent: None,
+                });
+            }
+        }
+        Ok(Self {
+            missing,
+            requested: HashMap::new(),
+            pending: HashMap::new(),
+            deferred_root: None,
+        })
+    }
+
+    /// Creates a healer for the given trie that continues the work of a previous healer of the same trie, given its `frontier`
+    /// The frontier's nodes are healed first, and then the trie's root is requested (if it is not already stored) to heal the rest of the trie
+    /// As nodes are written bottom-up, the subtries healed by the previous healer won't be fetched again
+    pub fn resume(trie: &Trie, frontier: Vec<(Nibbles, H256)>) -> Result<Self, TrieError> {
+        let mut healer = Self::new(trie)?;
+        if healer.is_complete() {
+            return Ok(healer);
+        }
+        for (path, hash) in frontier {
+            let hash = NodeHash::from(hash);
+            if !trie.state.contains_node(&hash)? {
+                healer.missing.push_back(MissingNode {
+                    path,
+                    hash,
+                    parent: None,
+                });
+            }
+        }
+        // The root is the only missing node queued by `new`
+        if healer.missing.len() > 1 {
+            healer.deferred_root = healer.missing.pop_front();
+        }
+        Ok(healer)
+    }
+
+    /// Returns the paths and hashes of the nodes that are known to be missing but haven't been fetched yet
+    /// It can be used to resume healing via `resume` if the healer is dropped before the trie is complete
+    pub fn frontier(&self) -> Vec<(Nibbles, H256)> {
+        self.missing
+            .iter()
+            .chain(self.requested.values())
+            .map(|node| (node.path.clone(), node.hash.clone().finalize()))
+            .collect()
+    }
+
+    /// Returns true once all of the trie's nodes have been fetched and written to the DB
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+            && self.requested.is_empty()
+            && self.pending.is_empty()
+            && self.deferred_root.is_none()
+    }
+
+    /// Returns the amount of nodes that have yet to be fetched or written to the DB
+    pub fn pending_nodes(&self) -> usize {
+        self.missing.len()
+            + self.requested.len()
+            + self.pending.len()
+            + usize::from(self.deferred_root.is_some())
+    }
+
+    /// Returns the paths of (at most `max`) nodes to fetch next
+    /// The nodes fetched for these paths (or the lack of them) should be reported back via `process_nodes`
+    pub fn next_paths(&mut self, max: usize) -> Vec<Nibbles> {
+        // Once the resumed frontier is healed, continue from the root
+        if self.missing.is_empty() && self.requested.is_empty() && self.pending.is_empty() {
+            self.missing.extend(self.deferred_root.take());
+        }
+        let count = max.min(self.missing.len());
+        self.missing
+            .drain(..count)
+            .map(|node| {
+                let path = node.path.clone();
+                self.requested.insert(path.clone(), node);
+                path
+            })
+            .collect()
+    }
+
+    /// Processes the raw nodes fetched for the given paths, which must have been obtained via `next_paths`
+    /// The nodes must be in the same order as the paths, paths without a matching node (or with an invalid one) will be requested again
+    /// Returns the leaves found among the fetched nodes, which must be released via `release_leaf` once they are complete
+    pub fn process_nodes(
+        &mut self,
+        trie: &Trie,
+        paths: Vec<Nibbles>,
+        nodes: Vec<Vec<u8>>,
+    ) -> Result<Vec<HealedLeaf>, TrieError> {
+        let mut leaves = vec![];
+        let mut nodes = nodes.into_iter();
+        for path in paths {
+            let Some(missing) = self.requested.remove(&path) else {
+                continue;
+            };
+            let node = nodes.next().and_then(|encoded| {
+                // Discard nodes that don't match the expected hash
+                (NodeHash::from_encoded_raw(encoded.clone()).finalize()
+                    == missing.hash.clone().finalize())
+                .then(|| Node::decode_raw(&encoded).ok())
+                .flatten()
+            });
+            let Some(node) = node else {
+                self.missing.push_front(missing);
+                continue;
+            };
+            // Queue the node's missing children
+            let mut dependencies = 0;
+            let mut queue_child = |child_path: Nibbles, child: &NodeHash| {
+                if !child.is_valid() || trie.state.contains_node(child)? {
+                    return Ok::<_, TrieError>(());
+                }
+                dependencies += 1;
+                self.missing.push_back(MissingNode {
+                    path: child_path,
+                    hash: child.clone(),
+                    parent: Some(path.clone()),
+                });
+                Ok(())
+            };
+            match &node {
+                Node::Branch(branch_node) => {
+                    for (choice, child) in branch_node.choices.iter().enumerate() {
+                        let mut child_path = path.clone();
+                        child_path.append(choice as u8);
+                        queue_child(child_path, child)?;
+                    }
+                }
+                Node::Extension(extension_node) => {
+                    let mut child_path = path.clone();
+                    child_path.extend(&extension_node.prefix);
+                    queue_child(child_path, &extension_node.child)?;
+                }
+                Node::Leaf(leaf_node) => {
+                    let mut key = path.clone();
+                    key.extend(&leaf_node.partial);
+                    leaves.push(HealedLeaf {
+                        path: path.clone(),
+                        key: key.to_bytes(),
+                        value: leaf_node.value.clone(),
+                    });
+                    // Leaves are held until released
+                    dependencies += 1;
+                }
+            }
+            self.pending.insert(
+                path.clone(),
+                PendingNode {
+                    node,
+                    hash: missing.hash,
+                    parent: missing.parent,
+                    dependencies,
+                },
+            );
+            if dependencies == 0 {
+                self.resolve(&trie.state, path)?;
+            }
+        }
+        Ok(leaves)
+    }
+
+    /// Marks a leaf returned by `process_nodes` as complete, allowing it and its ancestors to be written to the DB
+    pub fn release_leaf(&mut self, trie: &Trie, path: &Nibbles) -> Result<(), TrieError> {
+        let Some(pending) = self.pending.get_mut(path) else {
+            return Ok(());
+        };
+        pending.dependencies = pending.dependencies.saturating_sub(1);
+        if pending.dependencies == 0 {
+            self.resolve(&trie.state, path.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Writes a pending node without dependencies to the DB, along with any ancestors that were only waiting for it
+    fn resolve(&mut self, state: &TrieState, mut path: Nibbles) -> Result<(), TrieError> {
+        while let Some(pending) = self.pending.remove(&path) {
+            state.write_node(&pending.node, &pending.hash)?;
+            let Some(parent_path) = pending.parent else {
+                break;
+            };
+            let Some(parent) = self.pending.get_mut(&parent_path) else {
+                break;
+            };
+            parent.dependencies = parent.dependencies.saturating_sub(1);
+            if parent.dependencies != 0 {
+                break;
+            }
+            path = parent_path;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use sha3::Digest;
+
+    use super::*;
+    use crate::InMemoryTrieDB;
+
+    type DB = Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>;
+
+    fn new_db() -> DB {
+        Arc::new(Mutex::new(HashMap::new()))
+    }
+
+    fn open(db: &DB, root: H256) -> Trie {
+        Trie::open(Box::new(InMemoryTrieDB::new(db.clone())), root)
+    }
+
+    fn source_trie(entries: &[(Vec<u8>, Vec<u8>)]) -> (Trie, H256) {
+        let mut source = Trie::new(Box::new(InMemoryTrieDB::new(new_db())));
+        for (key, value) in entries.iter() {
+            source.insert(key.clone(), value.clone()).unwrap();
+        }
+        let root = source.hash().unwrap();
+        (source, root)
+    }
+
+    fn fetch(source: &Trie, paths: &[Nibbles]) -> Vec<Vec<u8>> {
+        paths
+            .iter()
+            .map(|path| source.get_node(&path.encode_compact()).unwrap())
+            .collect()
+    }
+
+    fn heal(source: &Trie, trie: &Trie, healer: &mut TrieHealer, batch_size: usize) {
+        while !healer.is_complete() {
+            let paths = healer.next_paths(batch_size);
+            assert!(!paths.is_empty());
+            let nodes = fetch(source, &paths);
+            for leaf in healer.process_nodes(trie, paths, nodes).unwrap() {
+                healer.release_leaf(trie, &leaf.path).unwrap();
+            }
+        }
+    }
+
+    fn entries(amount: u32, value_len: usize) -> Vec<(Vec<u8>, Vec<u8>)> {
+        (0..amount)
+            .map(|i| {
+                let key = sha3::Keccak256::digest(i.to_be_bytes()).to_vec();
+                (key, vec![1; value_len + (i % 7) as usize])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn heal_trie_from_scratch() {
+        let entries = entries(500, 40);
+        let (source, root) = source_trie(&entries);
+
+        let db = new_db();
+        let trie = open(&db, root);
+        let mut healer = TrieHealer::new(&trie).unwrap();
+        heal(&source, &trie, &mut healer, 16);
+
+        let trie = open(&db, root);
+        for (key, value) in entries.iter() {
+            assert_eq!(trie.get(key).unwrap().as_ref(), Some(value));
+        }
+        // A complete trie doesn't need healing
+        assert!(TrieHealer::new(&trie).unwrap().is_complete());
+    }
+
+    #[test]
+    fn heal_trie_with_partial_state() {
+        let entries = entries(300, 50);
+        let (source, root) = source_trie(&entries);
+
+        // Start from a trie containing only part of the entries
+        let db = new_db();
+        let mut partial = Trie::new(Box::new(InMemoryTrieDB::new(db.clone())));
+        for (key, value) in entries.iter().take(200) {
+            partial.insert(key.clone(), value.clone()).unwrap();
+        }
+        partial.hash().unwrap();
+
+        let trie = open(&db, root);
+        let mut healer = TrieHealer::new(&trie).unwrap();
+        // Ignore the first response to check that unanswered paths are requested again
+        let paths = healer.next_paths(1);
+        assert!(healer
+            .process_nodes(&trie, paths, vec![])
+            .unwrap()
+            .is_empty());
+        // Nodes are not written until their children are
+        let paths = healer.next_paths(1);
+        let nodes = fetch(&source, &paths);
+        healer.process_nodes(&trie, paths, nodes).unwrap();
+        assert!(!trie.state.contains_node(&root.into()).unwrap());
+        heal(&source, &trie, &mut healer, 8);
+
+        let trie = open(&db, root);
+        for (key, value) in entries.iter() {
+            assert_eq!(trie.get(key).unwrap().as_ref(), Some(value));
+        }
+    }
+
+    #[test]
+    fn resume_healing_from_frontier() {
+        let entries = entries(400, 45);
+        let (source, root) = source_trie(&entries);
+
+        let db = new_db();
+        let trie = open(&db, root);
+        let mut healer = TrieHealer::new(&trie).unwrap();
+        // Heal part of the trie and then drop the healer, keeping its frontier
+        for _ in 0..3 {
+            let paths = healer.next_paths(4);
+            let nodes = fetch(&source, &paths);
+            for leaf in healer.process_nodes(&trie, paths, nodes).unwrap() {
+                healer.release_leaf(&trie, &leaf.path).unwrap();
+            }
+        }
+        let frontier = healer.frontier();
+        assert!(!frontier.is_empty());
+        assert!(frontier.iter().all(|(path, _)| !path.is_empty()));
+
+        let mut healer = TrieHealer::resume(&trie, frontier.clone()).unwrap();
+        // The frontier is requested before the root
+        let paths = healer.next_paths(frontier.len());
+        assert_eq!(paths.len(), frontier.len());
+        assert!(paths.iter().all(|path| !path.is_empty()));
+        let nodes = fetch(&source, &paths);
+        for leaf in healer.process_nodes(&trie, paths, nodes).unwrap() {
+            healer.release_leaf(&trie, &leaf.path).unwrap();
+        }
+        heal(&source, &trie, &mut healer, 32);
+
+        let trie = open(&db, root);
+        for (key, value) in entries.iter() {
+            assert_eq!(trie.get(key).unwrap().as_ref(), Some(value));
+        }
+    }
+
+    #[test]
+    fn leaves_are_held_until_released() {
+        let entries = vec![(vec![0x10; 32], vec![3; 40]), (vec![0x20; 32], vec![4; 40])];
+        let (source, root) = source_trie(&entries);
+
+        let db = new_db();
+        let trie = open(&db, root);
+        let mut healer = TrieHealer::new(&trie).unwrap();
+        let mut leaves = vec![];
+        while leaves.len() < 2 {
+            let paths = healer.next_paths(16);
+            let nodes = fetch(&source, &paths);
+            leaves.extend(healer.process_nodes(&trie, paths, nodes).unwrap());
+        }
+        assert_eq!(leaves[0].key, vec![0x10; 32]);
+        assert_eq!(leaves[1].value, vec![4; 40]);
+        healer.release_leaf(&trie, &leaves[0].path).unwrap();
+        assert!(!healer.is_complete());
+        assert!(!trie.state.contains_node(&root.into()).unwrap());
+        healer.release_leaf(&trie, &leaves[1].path).unwrap();
+        assert!(healer.is_complete());
+        assert_eq!(
+            open(&db, root).get(&vec![0x20; 32]).unwrap(),
+            Some(vec![4; 40])
+        );
+    }
+}
@@ -8,7 +8,7 @@ use ethrex_rlp::{
 };
 
 /// Struct representing a list of nibbles (half-bytes)
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Nibbles {
     data: Vec<u8>,
 }
@@ -76,6 +76,7 @@ impl Nibbles {
     }
 
     /// Removes and returns the first nibble
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<u8> {
         (!self.is_empty()).then(|| self.data.remove(0))
     }
@@ -7,7 +7,8 @@ use super::db::TrieDB;
 
 /// Libmdbx database representing the trie state
 /// It contains a table mapping node hashes to rlp encoded nodes
-/// All nodes are stored in the DB and no node is ever removed
+/// All nodes are stored in the DB and are never removed by the trie itself
+/// Nodes that are no longer reachable from any relevant root can be found via `Trie::mark_nodes` and pruned by the DB owner
 use super::{node::Node, node_hash::NodeHash};
 pub struct TrieState {
     db: Box<dyn TrieDB>,
@@ -46,6 +47,20 @@ impl TrieState {
         }
     }
 
+    /// Returns true if the node is stored in the DB (or inlined in its hash)
+    /// Uncommitted nodes in the cache are not taken into account
+    pub fn contains_node(&self, hash: &NodeHash) -> Result<bool, TrieError> {
+        match hash {
+            NodeHash::Inline(_) => Ok(true),
+            NodeHash::Hashed(_) => Ok(self.db.get(hash.into())?.is_some()),
+        }
+    }
+
+    /// Writes a node directly into the DB, bypassing the cache
+    pub fn write_node(&self, node: &Node, hash: &NodeHash) -> Result<(), TrieError> {
+        self.db.put(hash.into(), node.encode_to_vec())
+    }
+
     /// Commits cache changes to DB and clears it
     /// Only writes nodes that follow the root's canonical trie
     pub fn commit(&mut self, root: &NodeHash) -> Result<(), TrieError> {
@@ -1,5 +1,6 @@
 pub mod db;
 mod error;
+mod healing;
 mod nibbles;
 mod node;
 mod node_hash;
@@ -11,7 +12,7 @@ mod trie_iter;
 mod verify_range;
 use ethereum_types::H256;
 use ethrex_rlp::constants::RLP_NULL;
-use nibbles::Nibbles;
+pub use nibbles::Nibbles;
 use node::Node;
 use node_hash::NodeHash;
 use sha3::{Digest, Keccak256};
@@ -21,6 +22,7 @@ use std::collections::HashSet;
 pub use self::db::{libmdbx::LibmdbxTrieDB, libmdbx_dupsort::LibmdbxDupsortTrieDB};
 
 pub use self::db::{in_memory::InMemoryTrieDB, TrieDB};
+pub use self::healing::{HealedLeaf, TrieHealer};
 pub use self::verify_range::verify_range;
 
 pub use self::error::TrieError;
@@ -256,6 +258,59 @@ impl Trie {
         Trie::new(Box::new(NullTrieDB))
     }
 
+    /// Collects the hashes of all of the trie's nodes stored in the DB into `marked`
+    /// Subtries whose root is already marked are skipped, as all their nodes have already been collected
+    /// `on_value` is called with the path and value of each leaf found in the traversed part of the trie
+    /// A trie whose root is not stored in the DB has no nodes to collect, but any other missing node means that
+    /// the trie is incomplete and an error is returned, as not all of its nodes could be collected
+    pub fn mark_nodes(
+        &self,
+        marked: &mut HashSet<H256>,
+        mut on_value: impl FnMut(PathRLP, &ValueRLP),
+    ) -> Result<(), TrieError> {
+        let Some(root) = &self.root else {
+            return Ok(());
+        };
+        if !self.state.contains_node(root)? {
+            return Ok(());
+        }
+        let mut stack = vec![(Nibbles::default(), root.clone())];
+        while let Some((mut path, node_hash)) = stack.pop() {
+            // Inlined nodes are not stored in the DB by themselves
+            if let NodeHash::Hashed(hash) = &node_hash {
+                if !marked.insert(*hash) {
+                    continue;
+                }
+            }
+            let Some(node) = self.state.get_node(node_hash.clone())? else {
+                return Err(TrieError::MissingNode(node_hash.finalize()));
+            };
+            match node {
+                Node::Branch(branch_node) => {
+                    for (choice, child) in branch_node.choices.iter().enumerate() {
+                        if child.is_valid() {
+                            let mut child_path = path.clone();
+                            child_path.append(choice as u8);
+                            stack.push((child_path, child.clone()));
+                        }
+                    }
+                    if !branch_node.value.is_empty() {
+                        on_value(path.to_bytes(), &branch_node.value);
+                    }
+                }
+                Node::Extension(extension_node) => {
+                    path.extend(&extension_node.prefix);
+                    stack.push((path, extension_node.child));
+                }
+                Node::Leaf(leaf_node) => {
+                    path.extend(&leaf_node.partial);
+                    on_value(path.to_bytes(), &leaf_node.value);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Obtain the encoded node given its path.
     /// Allows usage of full paths (byte slice of 32 bytes) or compact-encoded nibble slices (with length lower than 32)
     pub fn get_node(&self, partial_path: &PathRLP) -> Result<Vec<u8>, TrieError> {
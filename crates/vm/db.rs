@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
 use ethrex_core::{types::BlockHash, Address as CoreAddress, H256 as CoreH256};
 use ethrex_storage::{error::StoreError, Store};
 use revm::primitives::{
@@ -8,6 +10,22 @@ use revm::primitives::{
 pub struct StoreWrapper {
     pub store: Store,
     pub block_hash: BlockHash,
+    /// If set, every read served by the store is recorded into it
+    pub recorder: Option<StateAccessRecord>,
+}
+
+/// Record of the state read from a [StoreWrapper] during an execution
+/// As reads are always served from the state at the wrapper's `block_hash`, it describes the state prior to the execution
+#[derive(Debug, Default, Clone)]
+pub struct StateAccessRecord {
+    /// Every accessed account, including the ones that don't exist
+    pub accounts: HashSet<CoreAddress>,
+    /// Accessed storage keys of each account
+    pub storage: HashMap<CoreAddress, HashSet<CoreH256>>,
+    /// Bytecode of the accessed contracts, indexed by code hash
+    pub code: HashMap<RevmB256, RevmBytecode>,
+    /// Hashes of the blocks accessed through the BLOCKHASH opcode, indexed by block number
+    pub block_hashes: BTreeMap<u64, RevmB256>,
 }
 
 cfg_if::cfg_if! {
@@ -56,9 +74,13 @@ impl revm::Database for StoreWrapper {
     type Error = StoreError;
 
     fn basic(&mut self, address: RevmAddress) -> Result<Option<RevmAccountInfo>, Self::Error> {
+        let core_address = CoreAddress::from(address.0.as_ref());
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.accounts.insert(core_address);
+        }
         let acc_info = match self
             .store
-            .get_account_info_by_hash(self.block_hash, CoreAddress::from(address.0.as_ref()))?
+            .get_account_info_by_hash(self.block_hash, core_address)?
         {
             None => return Ok(None),
             Some(acc_info) => acc_info,
@@ -67,6 +89,11 @@ impl revm::Database for StoreWrapper {
             .store
             .get_account_code(acc_info.code_hash)?
             .map(|b| RevmBytecode::new_raw(RevmBytes(b)));
+        if let (Some(recorder), Some(code)) = (self.recorder.as_mut(), &code) {
+            recorder
+                .code
+                .insert(RevmB256::from(acc_info.code_hash.0), code.clone());
+        }
 
         Ok(Some(RevmAccountInfo {
             balance: RevmU256::from_limbs(acc_info.balance.0),
@@ -77,28 +104,40 @@ impl revm::Database for StoreWrapper {
     }
 
     fn code_by_hash(&mut self, code_hash: RevmB256) -> Result<RevmBytecode, Self::Error> {
-        self.store
+        let code = self
+            .store
             .get_account_code(CoreH256::from(code_hash.as_ref()))?
             .map(|b| RevmBytecode::new_raw(RevmBytes(b)))
-            .ok_or_else(|| StoreError::Custom(format!("No code for hash {code_hash}")))
+            .ok_or_else(|| StoreError::Custom(format!("No code for hash {code_hash}")))?;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.code.insert(code_hash, code.clone());
+        }
+        Ok(code)
     }
 
     fn storage(&mut self, address: RevmAddress, index: RevmU256) -> Result<RevmU256, Self::Error> {
+        let address = CoreAddress::from(address.0.as_ref());
+        let key = CoreH256::from(index.to_be_bytes());
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.accounts.insert(address);
+            recorder.storage.entry(address).or_default().insert(key);
+        }
         Ok(self
             .store
-            .get_storage_at_hash(
-                self.block_hash,
-                CoreAddress::from(address.0.as_ref()),
-                CoreH256::from(index.to_be_bytes()),
-            )?
+            .get_storage_at_hash(self.block_hash, address, key)?
             .map(|value| RevmU256::from_limbs(value.0))
             .unwrap_or_else(|| RevmU256::ZERO))
     }
 
     fn block_hash(&mut self, number: u64) -> Result<RevmB256, Self::Error> {
-        self.store
+        let block_hash = self
+            .store
             .get_block_header(number)?
             .map(|header| RevmB256::from_slice(&header.compute_block_hash().0))
-            .ok_or_else(|| StoreError::Custom(format!("Block {number} not found")))
+            .ok_or_else(|| StoreError::Custom(format!("Block {number} not found")))?;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.block_hashes.insert(number, block_hash);
+        }
+        Ok(block_hash)
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use ethereum_types::H160;
 use ethrex_core::{
@@ -17,11 +17,11 @@ use revm::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{errors::ExecutionDBError, evm_state, execute_block, get_state_transitions};
+use crate::{errors::ExecutionDBError, execute_block, get_state_transitions, recording_evm_state};
 
 /// In-memory EVM database for caching execution data.
 ///
-/// This is mainly used to store the relevant state data for executing a particular block and then
+/// This is mainly used to store the relevant state data for executing a particular batch of blocks and then
 /// feeding the DB into a zkVM program to prove the execution.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ExecutionDB {
@@ -44,78 +44,92 @@ pub struct ExecutionDB {
 }
 
 impl ExecutionDB {
-    /// Creates a database by executing a block, without performing any validation.
-    pub fn from_exec(block: &Block, store: &Store) -> Result<Self, ExecutionDBError> {
+    /// Creates a database by executing a batch of consecutive blocks, without performing any validation.
+    /// The returned database holds the state prior to the first block of the batch, including every account,
+    /// storage value, bytecode and block hash read during the execution.
+    pub fn from_exec(blocks: &[Block], store: &Store) -> Result<Self, ExecutionDBError> {
         // TODO: perform validation to exit early
-
-        // Execute and obtain account updates
-        let mut state = evm_state(store.clone(), block.header.parent_hash);
+        let parent_hash = blocks
+            .first()
+            .ok_or(ExecutionDBError::Custom("No blocks to execute".to_owned()))?
+            .header
+            .parent_hash;
+
+        // Execute the blocks in order on top of the same state, recording everything read from the store
+        let mut state = recording_evm_state(store.clone(), parent_hash);
         let chain_config = store.get_chain_config()?;
-        execute_block(block, &mut state).map_err(Box::new)?;
-        let account_updates = get_state_transitions(&mut state);
+        let mut address_storage_keys: HashMap<H160, HashSet<H256>> = HashMap::new();
+        for block in blocks {
+            execute_block(block, &mut state).map_err(Box::new)?;
+            // Written values are also needed to apply the updates to the pruned tries
+            for account_update in get_state_transitions(&mut state) {
+                address_storage_keys
+                    .entry(account_update.address)
+                    .or_default()
+                    .extend(account_update.added_storage.into_keys());
+            }
+        }
+        let record = state.take_access_record().unwrap_or_default();
+        for address in record.accounts {
+            address_storage_keys.entry(address).or_default();
+        }
+        for (address, keys) in record.storage {
+            address_storage_keys
+                .entry(address)
+                .or_default()
+                .extend(keys);
+        }
+        let code = record.code;
+        let block_hashes = record.block_hashes.into_iter().collect();
 
-        // Store data touched by updates and get all touched storage keys for each account
+        // Store the pre-batch data of the touched accounts
         let mut accounts = HashMap::new();
-        let code = HashMap::new(); // TODO: `code` remains empty for now
         let mut storage = HashMap::new();
-        let block_hashes = HashMap::new(); // TODO: `block_hashes` remains empty for now
-
-        let mut address_storage_keys = HashMap::new();
-
-        for account_update in account_updates.iter() {
-            let address = RevmAddress::from_slice(account_update.address.as_bytes());
-            let account_state = match store.get_account_state_by_hash(
-                block.header.parent_hash,
-                H160::from_slice(address.as_slice()),
-            )? {
+        for (address, keys) in address_storage_keys.iter() {
+            let account_state = match store.get_account_state_by_hash(parent_hash, *address)? {
                 Some(state) => state,
                 None => continue,
             };
-            accounts.insert(address, account_state);
-
-            let account_storage = account_update
-                .added_storage
-                .iter()
-                .map(|(key, value)| {
-                    let mut value_bytes = [0u8; 32];
-                    value.to_big_endian(&mut value_bytes);
-                    (
-                        RevmU256::from_be_bytes(key.to_fixed_bytes()),
-                        RevmU256::from_be_slice(&value_bytes),
-                    )
-                })
-                .collect();
-            storage.insert(address, account_storage);
-            address_storage_keys.insert(
-                account_update.address,
-                account_update
-                    .added_storage
-                    .keys()
-                    .cloned()
-                    .collect::<Vec<_>>(),
-            );
+            let revm_address = RevmAddress::from_slice(address.as_bytes());
+            accounts.insert(revm_address, account_state);
+
+            let mut account_storage = HashMap::new();
+            for key in keys {
+                let value = store
+                    .get_storage_at_hash(parent_hash, *address, *key)?
+                    .unwrap_or_default();
+                let mut value_bytes = [0u8; 32];
+                value.to_big_endian(&mut value_bytes);
+                account_storage.insert(
+                    RevmU256::from_be_bytes(key.to_fixed_bytes()),
+                    RevmU256::from_be_bytes(value_bytes),
+                );
+            }
+            storage.insert(revm_address, account_storage);
         }
 
         // Get pruned state and storage tries. For this we get the "state" (all relevant nodes) of every trie.
         // "Pruned" because we're only getting the nodes that make paths to the relevant
         // key-values.
-        let state_trie = store.state_trie(block.header.parent_hash)?.ok_or(
-            ExecutionDBError::NewMissingStateTrie(block.header.parent_hash),
-        )?;
+        let state_trie = store
+            .state_trie(parent_hash)?
+            .ok_or(ExecutionDBError::NewMissingStateTrie(parent_hash))?;
 
-        // Get pruned state trie
+        // Get pruned state trie, proving the absence of the accounts that don't exist yet
         let state_paths: Vec<_> = address_storage_keys.keys().map(hash_address).collect();
         let pruned_state_trie = state_trie.get_proofs(&state_paths)?;
 
+        // Accounts that don't exist yet have no storage trie to prove
+        address_storage_keys.retain(|address, _| {
+            accounts.contains_key(&RevmAddress::from_slice(address.as_bytes()))
+        });
+
         // Get pruned storage tries for every account
         let mut pruned_storage_tries = HashMap::new();
         for (address, keys) in address_storage_keys {
-            let storage_trie = store
-                .storage_trie(block.header.parent_hash, address)?
-                .ok_or(ExecutionDBError::NewMissingStorageTrie(
-                    block.header.parent_hash,
-                    address,
-                ))?;
+            let storage_trie = store.storage_trie(parent_hash, address)?.ok_or(
+                ExecutionDBError::NewMissingStorageTrie(parent_hash, address),
+            )?;
             let storage_paths: Vec<_> = keys.iter().map(hash_key).collect();
             let (storage_trie_root, storage_trie_nodes) =
                 storage_trie.get_proofs(&storage_paths)?;
@@ -171,11 +185,14 @@ impl ExecutionDB {
             for (key, value) in storage {
                 let key = H256::from_slice(&key.to_be_bytes_vec());
                 let value = H256::from_slice(&value.to_be_bytes_vec());
-                let retrieved_value = storage_trie
-                    .get(&hash_key(&key))?
-                    .ok_or(ExecutionDBError::MissingKeyInStorageTrie(address, key))?;
-                if value.encode_to_vec() != retrieved_value {
-                    return Err(ExecutionDBError::InvalidStorageTrieValue(address, key));
+                // Zero values are not stored in the trie
+                match storage_trie.get(&hash_key(&key))? {
+                    None if value.is_zero() => {}
+                    None => return Err(ExecutionDBError::MissingKeyInStorageTrie(address, key)),
+                    Some(retrieved_value) if value.encode_to_vec() != retrieved_value => {
+                        return Err(ExecutionDBError::InvalidStorageTrieValue(address, key))
+                    }
+                    Some(_) => {}
                 }
             }
 
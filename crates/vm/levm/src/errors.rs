@@ -188,6 +188,12 @@ pub enum PrecompileError {
     GasConsumedOverflow,
     #[error("There is not enough gas to execute precompiled contract")]
     NotEnoughGas,
+    #[error("Point is not on the curve or not in the expected subgroup")]
+    InvalidEcPoint,
+    #[error("Versioned hash does not match the KZG commitment")]
+    InvalidVersionedHash,
+    #[error("KZG proof verification failed")]
+    KzgProofVerificationFailed,
     #[error("This is a default error")]
     DefaultError,
 }
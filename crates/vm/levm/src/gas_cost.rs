@@ -180,6 +180,16 @@ pub const MODEXP_STATIC_COST: u64 = 0;
 pub const MODEXP_DYNAMIC_BASE: u64 = 200;
 pub const MODEXP_DYNAMIC_QUOTIENT: u64 = 3;
 
+pub const ECADD_COST: u64 = 150;
+pub const ECMUL_COST: u64 = 6000;
+
+pub const ECPAIRING_BASE_COST: u64 = 45000;
+pub const ECPAIRING_GROUP_COST: u64 = 34000;
+
+pub const BLAKE2F_ROUND_COST: u64 = 1;
+
+pub const POINT_EVALUATION_COST: u64 = 50000;
+
 pub fn exp(exponent: U256) -> Result<u64, VMError> {
     let exponent_byte_size = (exponent
         .bits()
@@ -822,8 +832,11 @@ pub fn identity(data_size: usize) -> Result<u64, VMError> {
     precompile(data_size, IDENTITY_STATIC_COST, IDENTITY_DYNAMIC_BASE)
 }
 
+/// Gas cost of the MODEXP precompile, as defined in EIP-2565.
+/// `exponent_head` is the first (at most) 32 bytes of the exponent interpreted as a big-endian number.
+/// https://eips.ethereum.org/EIPS/eip-2565
 pub fn modexp(
-    exponent: U256,
+    exponent_head: U256,
     base_size: u64,
     exponent_size: u64,
     modulus_size: u64,
@@ -832,43 +845,28 @@ pub fn modexp(
     let words = (max_length
         .checked_add(7)
         .ok_or(OutOfGasError::GasCostOverflow)?)
-        / WORD_SIZE_IN_BYTES_U64;
+        / 8;
     let multiplication_complexity = words.checked_pow(2).ok_or(OutOfGasError::GasCostOverflow)?;
 
-    let mut iteration_count: u64 = 0;
-    if exponent_size <= WORD_SIZE_IN_BYTES_U64 && exponent.is_zero() {
-        iteration_count = 0;
-    } else if exponent_size <= WORD_SIZE_IN_BYTES_U64 {
-        iteration_count = exponent
-            .bits()
-            .checked_sub(1)
-            .ok_or(InternalError::ArithmeticOperationUnderflow)?
-            .try_into()
-            .map_err(|_| InternalError::ConversionError)?;
-    } else if exponent_size > WORD_SIZE_IN_BYTES_U64 {
-        iteration_count = 8u64
-            .checked_mul(
-                exponent_size
-                    .checked_sub(WORD_SIZE_IN_BYTES_U64)
-                    .ok_or(InternalError::ArithmeticOperationUnderflow)?,
-            )
-            .ok_or(InternalError::ArithmeticOperationOverflow)?
-            .checked_add(
-                (exponent
-                    & (2usize
-                        .checked_pow(256)
-                        .ok_or(InternalError::ArithmeticOperationOverflow)?)
-                    .checked_sub(1)
-                    .ok_or(InternalError::ArithmeticOperationOverflow)?
-                    .into())
-                .bits()
-                .checked_sub(1)
-                .ok_or(InternalError::ArithmeticOperationUnderflow)?
-                .try_into()
-                .map_err(|_| InternalError::ConversionError)?,
-            )
-            .ok_or(InternalError::ArithmeticOperationOverflow)?;
-    }
+    // Index of the highest set bit of the exponent head, 0 if the head is zero
+    let head_highest_bit: u64 = exponent_head
+        .bits()
+        .saturating_sub(1)
+        .try_into()
+        .map_err(|_| InternalError::ConversionError)?;
+
+    let iteration_count = if exponent_size <= WORD_SIZE_IN_BYTES_U64 {
+        head_highest_bit
+    } else {
+        8u64.checked_mul(
+            exponent_size
+                .checked_sub(WORD_SIZE_IN_BYTES_U64)
+                .ok_or(InternalError::ArithmeticOperationUnderflow)?,
+        )
+        .ok_or(OutOfGasError::GasCostOverflow)?
+        .checked_add(head_highest_bit)
+        .ok_or(OutOfGasError::GasCostOverflow)?
+    };
 
     let calculate_iteration_count = iteration_count.max(1);
 
@@ -886,6 +884,28 @@ pub fn modexp(
         .ok_or(OutOfGasError::GasCostOverflow)?)
 }
 
+/// Gas cost of the ECPAIRING precompile for `groups` (G1, G2) pairs, as defined in EIP-1108.
+pub fn ecpairing(groups: usize) -> Result<u64, VMError> {
+    let groups: u64 = groups
+        .try_into()
+        .map_err(|_| PrecompileError::ParsingInputError)?;
+
+    let groups_cost = ECPAIRING_GROUP_COST
+        .checked_mul(groups)
+        .ok_or(OutOfGasError::GasCostOverflow)?;
+
+    Ok(ECPAIRING_BASE_COST
+        .checked_add(groups_cost)
+        .ok_or(OutOfGasError::GasCostOverflow)?)
+}
+
+/// Gas cost of the BLAKE2F precompile, as defined in EIP-152.
+pub fn blake2f(rounds: u32) -> Result<u64, VMError> {
+    Ok(BLAKE2F_ROUND_COST
+        .checked_mul(u64::from(rounds))
+        .ok_or(OutOfGasError::GasCostOverflow)?)
+}
+
 fn precompile(data_size: usize, static_cost: u64, dynamic_base: u64) -> Result<u64, VMError> {
     let data_size: u64 = data_size
         .try_into()
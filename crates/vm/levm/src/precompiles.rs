@@ -1,13 +1,20 @@
 use bytes::Bytes;
+use c_kzg::{Bytes32, Bytes48, KzgProof};
 use ethrex_core::{Address, H160, U256};
 use keccak_hash::keccak256;
 use libsecp256k1::{self, Message, RecoveryId, Signature};
+use num_bigint::BigUint;
 use sha3::Digest;
+use substrate_bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
 
 use crate::{
     call_frame::CallFrame,
     errors::{InternalError, PrecompileError, VMError},
-    gas_cost::{ripemd_160 as ripemd_160_cost, sha2_256 as sha2_256_cost, ECRECOVER_COST},
+    gas_cost::{
+        blake2f as blake2f_cost, ecpairing as ecpairing_cost, identity as identity_cost,
+        modexp as modexp_cost, ripemd_160 as ripemd_160_cost, sha2_256 as sha2_256_cost,
+        ECADD_COST, ECMUL_COST, ECRECOVER_COST, POINT_EVALUATION_COST,
+    },
 };
 
 pub const ECRECOVER_ADDRESS: H160 = H160([
@@ -64,6 +71,25 @@ pub const PRECOMPILES: [H160; 10] = [
     POINT_EVALUATION_ADDRESS,
 ];
 
+/// Size in bytes of a BN254 (alt_bn128) G1 point encoded as (x, y)
+const BN254_G1_POINT_SIZE: usize = 64;
+/// Size in bytes of a (G1, G2) pair in the ECPAIRING input
+const ECPAIRING_PAIR_SIZE: usize = 192;
+
+/// Expected input length of the BLAKE2F precompile: rounds (4) + h (64) + m (128) + t (16) + f (1)
+const BLAKE2F_INPUT_LENGTH: usize = 213;
+
+/// Expected input length of the point evaluation precompile:
+/// versioned hash (32) + z (32) + y (32) + commitment (48) + proof (48)
+const POINT_EVALUATION_INPUT_LENGTH: usize = 192;
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+const FIELD_ELEMENTS_PER_BLOB: u64 = 4096;
+/// Order of the BLS12-381 scalar field, returned by the point evaluation precompile
+const BLS_MODULUS: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
 pub fn is_precompile(callee_address: &Address) -> bool {
     PRECOMPILES.contains(callee_address)
 }
@@ -116,6 +142,21 @@ fn increase_precompile_consumed_gas(
     Ok(())
 }
 
+/// Returns `size` bytes of `calldata` starting at `offset`. Bytes beyond the end of the calldata
+/// are considered to be zero, as if the calldata was infinitely right-padded with zeros.
+fn get_slice_or_zeros(calldata: &Bytes, offset: usize, size: usize) -> Vec<u8> {
+    let mut result = vec![0; size];
+
+    let available = calldata.get(offset..).unwrap_or_default();
+    let n = available.len().min(size);
+
+    if let (Some(dst), Some(src)) = (result.get_mut(..n), available.get(..n)) {
+        dst.copy_from_slice(src);
+    }
+
+    result
+}
+
 /// When slice length is less than 128, the rest is filled with zeros. If slice length is
 /// more than 128 the excess bytes are discarded.
 fn fill_with_zeros(slice: &[u8]) -> Result<[u8; 128], VMError> {
@@ -191,12 +232,16 @@ pub fn ecrecover(
     Ok(Bytes::from(output.to_vec()))
 }
 
-fn identity(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
+pub fn identity(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
 ) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
+    let gas_cost = identity_cost(calldata.len())?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    Ok(calldata.clone())
 }
 
 pub fn sha2_256(
@@ -232,42 +277,442 @@ pub fn ripemd_160(
     Ok(Bytes::from(output))
 }
 
-fn modexp(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
+/// Arbitrary-precision modular exponentiation, as defined in EIP-198.
+/// Input: base length (32 bytes), exponent length (32 bytes), modulus length (32 bytes),
+/// followed by base, exponent and modulus. Output: (base ** exponent) % modulus, left-padded
+/// to the modulus length.
+pub fn modexp(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
 ) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
+    let base_size = U256::from_big_endian(&get_slice_or_zeros(calldata, 0, 32));
+    let exponent_size = U256::from_big_endian(&get_slice_or_zeros(calldata, 32, 32));
+    let modulus_size = U256::from_big_endian(&get_slice_or_zeros(calldata, 64, 32));
+
+    // Sizes that don't fit in a u64 would make the gas cost overflow anyway
+    let base_size: u64 = base_size
+        .try_into()
+        .map_err(|_| PrecompileError::NotEnoughGas)?;
+    let exponent_size: u64 = exponent_size
+        .try_into()
+        .map_err(|_| PrecompileError::NotEnoughGas)?;
+    let modulus_size: u64 = modulus_size
+        .try_into()
+        .map_err(|_| PrecompileError::NotEnoughGas)?;
+
+    let base_size_usize: usize = base_size
+        .try_into()
+        .map_err(|_| PrecompileError::NotEnoughGas)?;
+    let exponent_size_usize: usize = exponent_size
+        .try_into()
+        .map_err(|_| PrecompileError::NotEnoughGas)?;
+    let modulus_size_usize: usize = modulus_size
+        .try_into()
+        .map_err(|_| PrecompileError::NotEnoughGas)?;
+
+    let exponent_offset = base_size_usize
+        .checked_add(96)
+        .ok_or(PrecompileError::NotEnoughGas)?;
+    let modulus_offset = exponent_offset
+        .checked_add(exponent_size_usize)
+        .ok_or(PrecompileError::NotEnoughGas)?;
+
+    // Only the first 32 bytes of the exponent are needed to compute the gas cost
+    let exponent_head = U256::from_big_endian(&get_slice_or_zeros(
+        calldata,
+        exponent_offset,
+        exponent_size_usize.min(32),
+    ));
+
+    let gas_cost = modexp_cost(exponent_head, base_size, exponent_size, modulus_size)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    if base_size == 0 && modulus_size == 0 {
+        return Ok(Bytes::new());
+    }
+
+    let base = BigUint::from_bytes_be(&get_slice_or_zeros(calldata, 96, base_size_usize));
+    let exponent = BigUint::from_bytes_be(&get_slice_or_zeros(
+        calldata,
+        exponent_offset,
+        exponent_size_usize,
+    ));
+    let modulus = BigUint::from_bytes_be(&get_slice_or_zeros(
+        calldata,
+        modulus_offset,
+        modulus_size_usize,
+    ));
+
+    if modulus == BigUint::ZERO {
+        return Ok(Bytes::from(vec![0u8; modulus_size_usize]));
+    }
+
+    // The result is lower than the modulus, so it always fits in modulus_size bytes
+    let result = base.modpow(&exponent, &modulus).to_bytes_be();
+    let mut output = vec![0u8; modulus_size_usize.saturating_sub(result.len())];
+    output.extend_from_slice(&result);
+
+    Ok(Bytes::from(output))
 }
 
-fn ecadd(_calldata: &Bytes, _gas_for_call: u64, _consumed_gas: &mut u64) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
+/// Parses a BN254 G1 point encoded as two big-endian 32 bytes coordinates.
+/// The point (0, 0) is interpreted as the point at infinity.
+fn parse_bn254_g1_point(x: &[u8], y: &[u8]) -> Result<G1, VMError> {
+    let x = Fq::from_slice(x).map_err(|_| PrecompileError::ParsingInputError)?;
+    let y = Fq::from_slice(y).map_err(|_| PrecompileError::ParsingInputError)?;
+
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1::zero());
+    }
+
+    Ok(AffineG1::new(x, y)
+        .map_err(|_| PrecompileError::InvalidEcPoint)?
+        .into())
 }
 
-fn ecmul(_calldata: &Bytes, _gas_for_call: u64, _consumed_gas: &mut u64) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
+/// Parses a BN254 G2 point. Each coordinate is an element of Fq2 encoded as
+/// (imaginary part, real part), both of them as big-endian 32 bytes numbers.
+/// The point (0, 0) is interpreted as the point at infinity.
+fn parse_bn254_g2_point(bytes: &[u8]) -> Result<G2, VMError> {
+    let parse_fq = |offset: usize| -> Result<Fq, VMError> {
+        let slice = bytes
+            .get(offset..offset.checked_add(32).ok_or(InternalError::SlicingError)?)
+            .ok_or(InternalError::SlicingError)?;
+        Ok(Fq::from_slice(slice).map_err(|_| PrecompileError::ParsingInputError)?)
+    };
+
+    let x = Fq2::new(parse_fq(32)?, parse_fq(0)?);
+    let y = Fq2::new(parse_fq(96)?, parse_fq(64)?);
+
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2::zero());
+    }
+
+    Ok(AffineG2::new(x, y)
+        .map_err(|_| PrecompileError::InvalidEcPoint)?
+        .into())
+}
+
+/// Encodes a BN254 G1 point as two big-endian 32 bytes coordinates,
+/// the point at infinity is encoded as (0, 0).
+fn encode_bn254_g1_point(point: G1) -> Result<Bytes, VMError> {
+    let mut output = [0u8; BN254_G1_POINT_SIZE];
+
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        let (x, y) = output.split_at_mut(32);
+        point
+            .x()
+            .to_big_endian(x)
+            .map_err(|_| InternalError::SlicingError)?;
+        point
+            .y()
+            .to_big_endian(y)
+            .map_err(|_| InternalError::SlicingError)?;
+    }
+
+    Ok(Bytes::copy_from_slice(&output))
 }
 
-fn ecpairing(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
+/// Point addition on the BN254 curve, as defined in EIP-196.
+pub fn ecadd(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    increase_precompile_consumed_gas(gas_for_call, ECADD_COST, consumed_gas)?;
+
+    let calldata = fill_with_zeros(calldata)?;
+
+    let first_point = parse_bn254_g1_point(
+        calldata.get(0..32).ok_or(InternalError::SlicingError)?,
+        calldata.get(32..64).ok_or(InternalError::SlicingError)?,
+    )?;
+    let second_point = parse_bn254_g1_point(
+        calldata.get(64..96).ok_or(InternalError::SlicingError)?,
+        calldata.get(96..128).ok_or(InternalError::SlicingError)?,
+    )?;
+
+    // Group operations can't overflow, they are performed modulo the field order
+    #[allow(clippy::arithmetic_side_effects)]
+    let result = first_point + second_point;
+
+    encode_bn254_g1_point(result)
+}
+
+/// Scalar multiplication on the BN254 curve, as defined in EIP-196.
+pub fn ecmul(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    increase_precompile_consumed_gas(gas_for_call, ECMUL_COST, consumed_gas)?;
+
+    let calldata = fill_with_zeros(calldata)?;
+
+    let point = parse_bn254_g1_point(
+        calldata.get(0..32).ok_or(InternalError::SlicingError)?,
+        calldata.get(32..64).ok_or(InternalError::SlicingError)?,
+    )?;
+    let scalar = Fr::from_slice(calldata.get(64..96).ok_or(InternalError::SlicingError)?)
+        .map_err(|_| PrecompileError::ParsingInputError)?;
+
+    // Group operations can't overflow, they are performed modulo the field order
+    #[allow(clippy::arithmetic_side_effects)]
+    let result = point * scalar;
+
+    encode_bn254_g1_point(result)
+}
+
+/// Optimal ate pairing check on the BN254 curve, as defined in EIP-197.
+/// Returns 1 (as a 32 bytes word) if the product of the pairings equals one, 0 otherwise.
+pub fn ecpairing(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
 ) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
+    let chunks = calldata.chunks_exact(ECPAIRING_PAIR_SIZE);
+    if !chunks.remainder().is_empty() {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let groups = chunks.len();
+    let gas_cost = ecpairing_cost(groups)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    let mut pairs = Vec::with_capacity(groups);
+    for pair in chunks {
+        let (g1_bytes, g2_bytes) = pair.split_at(BN254_G1_POINT_SIZE);
+
+        let g1 = parse_bn254_g1_point(
+            g1_bytes.get(0..32).ok_or(InternalError::SlicingError)?,
+            g1_bytes.get(32..64).ok_or(InternalError::SlicingError)?,
+        )?;
+        let g2 = parse_bn254_g2_point(g2_bytes)?;
+
+        pairs.push((g1, g2));
+    }
+
+    let success = substrate_bn::pairing_batch(&pairs) == Gt::one();
+
+    let mut output = [0u8; 32];
+    if success {
+        output[31] = 1;
+    }
+
+    Ok(Bytes::copy_from_slice(&output))
+}
+
+/// BLAKE2b initialization vector
+const BLAKE2F_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// BLAKE2b message word permutations, one for each of the 10 distinct rounds
+const BLAKE2F_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// BLAKE2b mixing function G
+fn blake2f_mix(a: &mut u64, b: &mut u64, c: &mut u64, d: &mut u64, x: u64, y: u64) {
+    *a = a.wrapping_add(*b).wrapping_add(x);
+    *d = (*d ^ *a).rotate_right(32);
+    *c = c.wrapping_add(*d);
+    *b = (*b ^ *c).rotate_right(24);
+    *a = a.wrapping_add(*b).wrapping_add(y);
+    *d = (*d ^ *a).rotate_right(16);
+    *c = c.wrapping_add(*d);
+    *b = (*b ^ *c).rotate_right(63);
+}
+
+/// BLAKE2b compression function F, as defined in RFC 7693 with a configurable number of rounds
+fn blake2f_compress(
+    rounds: usize,
+    h: &mut [u64; 8],
+    m: &[u64; 16],
+    t: &[u64; 2],
+    f: bool,
+) -> Result<(), VMError> {
+    let [h0, h1, h2, h3, h4, h5, h6, h7] = *h;
+    let [iv0, iv1, iv2, iv3, iv4, iv5, iv6, iv7] = BLAKE2F_IV;
+    let [t0, t1] = *t;
+
+    let mut v = [
+        h0,
+        h1,
+        h2,
+        h3,
+        h4,
+        h5,
+        h6,
+        h7,
+        iv0,
+        iv1,
+        iv2,
+        iv3,
+        iv4 ^ t0,
+        iv5 ^ t1,
+        if f { !iv6 } else { iv6 },
+        iv7,
+    ];
+
+    for sigma in BLAKE2F_SIGMA.iter().cycle().take(rounds) {
+        let mut s = [0u64; 16];
+        for (word, index) in s.iter_mut().zip(sigma) {
+            *word = *m.get(*index).ok_or(InternalError::SlicingError)?;
+        }
+        let [s0, s1, s2, s3, s4, s5, s6, s7, s8, s9, s10, s11, s12, s13, s14, s15] = s;
+
+        let [v0, v1, v2, v3, v4, v5, v6, v7, v8, v9, v10, v11, v12, v13, v14, v15] = &mut v;
+
+        blake2f_mix(v0, v4, v8, v12, s0, s1);
+        blake2f_mix(v1, v5, v9, v13, s2, s3);
+        blake2f_mix(v2, v6, v10, v14, s4, s5);
+        blake2f_mix(v3, v7, v11, v15, s6, s7);
+        blake2f_mix(v0, v5, v10, v15, s8, s9);
+        blake2f_mix(v1, v6, v11, v12, s10, s11);
+        blake2f_mix(v2, v7, v8, v13, s12, s13);
+        blake2f_mix(v3, v4, v9, v14, s14, s15);
+    }
+
+    for ((h_i, v_low), v_high) in h.iter_mut().zip(v.iter()).zip(v.iter().skip(8)) {
+        *h_i ^= v_low ^ v_high;
+    }
+
+    Ok(())
 }
 
-fn blake2f(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
+/// Reads little-endian u64 words from `bytes` into `words`
+fn read_le_words(bytes: &[u8], words: &mut [u64]) -> Result<(), VMError> {
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().map_err(|_| InternalError::SlicingError)?);
+    }
+
+    Ok(())
+}
+
+/// BLAKE2 compression function F, as defined in EIP-152.
+/// Input: rounds (4 bytes, big-endian), h (64 bytes), m (128 bytes), t (16 bytes), f (1 byte).
+pub fn blake2f(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
 ) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
+    if calldata.len() != BLAKE2F_INPUT_LENGTH {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let rounds = u32::from_be_bytes(
+        calldata
+            .get(0..4)
+            .ok_or(InternalError::SlicingError)?
+            .try_into()
+            .map_err(|_| InternalError::SlicingError)?,
+    );
+
+    let gas_cost = blake2f_cost(rounds)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    let mut h = [0u64; 8];
+    let mut m = [0u64; 16];
+    let mut t = [0u64; 2];
+    read_le_words(
+        calldata.get(4..68).ok_or(InternalError::SlicingError)?,
+        &mut h,
+    )?;
+    read_le_words(
+        calldata.get(68..196).ok_or(InternalError::SlicingError)?,
+        &mut m,
+    )?;
+    read_le_words(
+        calldata.get(196..212).ok_or(InternalError::SlicingError)?,
+        &mut t,
+    )?;
+
+    let f = match calldata.get(212) {
+        Some(0) => false,
+        Some(1) => true,
+        _ => return Err(VMError::PrecompileError(PrecompileError::ParsingInputError)),
+    };
+
+    let rounds: usize = rounds
+        .try_into()
+        .map_err(|_| InternalError::ConversionError)?;
+
+    blake2f_compress(rounds, &mut h, &m, &t, f)?;
+
+    let output: Vec<u8> = h.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+    Ok(Bytes::from(output))
 }
 
-fn point_evaluation(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
+/// Verifies that a blob commitment opens to `y` at point `z`, as defined in EIP-4844.
+/// Returns FIELD_ELEMENTS_PER_BLOB and BLS_MODULUS as two 32 bytes big-endian words.
+pub fn point_evaluation(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
 ) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
+    increase_precompile_consumed_gas(gas_for_call, POINT_EVALUATION_COST, consumed_gas)?;
+
+    if calldata.len() != POINT_EVALUATION_INPUT_LENGTH {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let versioned_hash = calldata.get(0..32).ok_or(InternalError::SlicingError)?;
+    let z = calldata.get(32..64).ok_or(InternalError::SlicingError)?;
+    let y = calldata.get(64..96).ok_or(InternalError::SlicingError)?;
+    let commitment = calldata.get(96..144).ok_or(InternalError::SlicingError)?;
+    let proof = calldata.get(144..192).ok_or(InternalError::SlicingError)?;
+
+    // The versioned hash is the sha256 of the commitment with its first byte replaced by the version
+    let mut commitment_hash = sha2::Sha256::digest(commitment);
+    if let Some(version) = commitment_hash.first_mut() {
+        *version = VERSIONED_HASH_VERSION_KZG;
+    }
+    if commitment_hash.as_slice() != versioned_hash {
+        return Err(VMError::PrecompileError(
+            PrecompileError::InvalidVersionedHash,
+        ));
+    }
+
+    let commitment =
+        Bytes48::from_bytes(commitment).map_err(|_| PrecompileError::ParsingInputError)?;
+    let z = Bytes32::from_bytes(z).map_err(|_| PrecompileError::ParsingInputError)?;
+    let y = Bytes32::from_bytes(y).map_err(|_| PrecompileError::ParsingInputError)?;
+    let proof = Bytes48::from_bytes(proof).map_err(|_| PrecompileError::ParsingInputError)?;
+
+    let verified =
+        KzgProof::verify_kzg_proof(&commitment, &z, &y, &proof, c_kzg::ethereum_kzg_settings())
+            .map_err(|_| PrecompileError::ParsingInputError)?;
+    if !verified {
+        return Err(VMError::PrecompileError(
+            PrecompileError::KzgProofVerificationFailed,
+        ));
+    }
+
+    let mut output = [0u8; 32].to_vec();
+    U256::from(FIELD_ELEMENTS_PER_BLOB).to_big_endian(&mut output);
+    output.extend_from_slice(&BLS_MODULUS);
+
+    Ok(Bytes::from(output))
 }
@@ -7,14 +7,19 @@ use ethrex_levm::{
     account::Account,
     constants::*,
     db::{cache, CacheDB, Db},
-    errors::{OutOfGasError, TxResult, VMError},
+    errors::{OutOfGasError, PrecompileError, TxResult, VMError},
     gas_cost::{
-        self, ECRECOVER_COST, RIPEMD_160_DYNAMIC_BASE, RIPEMD_160_STATIC_COST,
-        SHA2_256_DYNAMIC_BASE, SHA2_256_STATIC_COST,
+        self, ECADD_COST, ECMUL_COST, ECPAIRING_BASE_COST, ECPAIRING_GROUP_COST, ECRECOVER_COST,
+        IDENTITY_DYNAMIC_BASE, IDENTITY_STATIC_COST, POINT_EVALUATION_COST,
+        RIPEMD_160_DYNAMIC_BASE, RIPEMD_160_STATIC_COST, SHA2_256_DYNAMIC_BASE,
+        SHA2_256_STATIC_COST,
     },
     memory,
     operations::Operation,
-    precompiles::{ecrecover, ripemd_160, sha2_256},
+    precompiles::{
+        blake2f, ecadd, ecmul, ecpairing, ecrecover, identity, modexp, point_evaluation,
+        ripemd_160, sha2_256,
+    },
     utils::{new_vm_with_ops, new_vm_with_ops_addr_bal_db, new_vm_with_ops_db, ops_to_bytecode},
     vm::{word_to_address, Storage, VM},
     Environment,
@@ -4534,3 +4539,157 @@ fn ripemd_160_test() {
         (RIPEMD_160_STATIC_COST + RIPEMD_160_DYNAMIC_BASE)
     );
 }
+
+#[test]
+fn identity_test() {
+    let calldata = hex::decode("ff").unwrap();
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = identity(&calldata, 10000, &mut consumed_gas).unwrap();
+
+    assert_eq!(result, calldata);
+    assert_eq!(consumed_gas, IDENTITY_STATIC_COST + IDENTITY_DYNAMIC_BASE);
+}
+
+#[test]
+fn modexp_test() {
+    // 3 ** (p - 1) % p, with p = secp256k1 field modulus (EIP-198 example)
+    let calldata = hex::decode("00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000002003fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2efffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f").unwrap();
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = modexp(&calldata, 10000, &mut consumed_gas).unwrap();
+
+    let expected_result = Bytes::from(
+        hex::decode("0000000000000000000000000000000000000000000000000000000000000001").unwrap(),
+    );
+
+    assert_eq!(result, expected_result);
+    assert_eq!(consumed_gas, 1360);
+}
+
+#[test]
+fn modexp_zero_modulus_test() {
+    // base = 3, exponent = 2, modulus = 0 (2 bytes long)
+    let calldata = hex::decode("000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002030200").unwrap();
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = modexp(&calldata, 10000, &mut consumed_gas).unwrap();
+
+    assert_eq!(result, Bytes::from(vec![0, 0]));
+    assert_eq!(consumed_gas, 200);
+}
+
+#[test]
+fn ecadd_test() {
+    // G + G, with G = (1, 2) the BN254 generator
+    let calldata = hex::decode("0000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002").unwrap();
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = ecadd(&calldata, 10000, &mut consumed_gas).unwrap();
+
+    let expected_result = Bytes::from(hex::decode("030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4").unwrap());
+
+    assert_eq!(result, expected_result);
+    assert_eq!(consumed_gas, ECADD_COST);
+}
+
+#[test]
+fn ecadd_invalid_point_test() {
+    // (1, 3) is not on the curve
+    let calldata = hex::decode("00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000003").unwrap();
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = ecadd(&calldata, 10000, &mut consumed_gas);
+
+    assert_eq!(
+        result,
+        Err(VMError::PrecompileError(PrecompileError::InvalidEcPoint))
+    );
+}
+
+#[test]
+fn ecmul_test() {
+    // 2 * G, with G = (1, 2) the BN254 generator
+    let calldata = hex::decode("000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000002").unwrap();
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = ecmul(&calldata, 10000, &mut consumed_gas).unwrap();
+
+    let expected_result = Bytes::from(hex::decode("030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4").unwrap());
+
+    assert_eq!(result, expected_result);
+    assert_eq!(consumed_gas, ECMUL_COST);
+}
+
+#[test]
+fn ecpairing_test() {
+    // e(G, O) == 1, with O the point at infinity of G2
+    let mut calldata = hex::decode("00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002").unwrap();
+    calldata.extend_from_slice(&[0u8; 128]);
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = ecpairing(&calldata, 100000, &mut consumed_gas).unwrap();
+
+    let expected_result = Bytes::from(
+        hex::decode("0000000000000000000000000000000000000000000000000000000000000001").unwrap(),
+    );
+
+    assert_eq!(result, expected_result);
+    assert_eq!(consumed_gas, ECPAIRING_BASE_COST + ECPAIRING_GROUP_COST);
+}
+
+#[test]
+fn ecpairing_invalid_length_test() {
+    let calldata = Bytes::from(vec![0u8; 100]);
+
+    let mut consumed_gas = 0;
+    let result = ecpairing(&calldata, 100000, &mut consumed_gas);
+
+    assert_eq!(
+        result,
+        Err(VMError::PrecompileError(PrecompileError::ParsingInputError))
+    );
+}
+
+#[test]
+fn blake2f_test() {
+    // EIP-152 test vector 5: 12 rounds of BLAKE2b over "abc"
+    let calldata = hex::decode("0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b61626300000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000300000000000000000000000000000001").unwrap();
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = blake2f(&calldata, 10000, &mut consumed_gas).unwrap();
+
+    let expected_result = Bytes::from(hex::decode("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap());
+
+    assert_eq!(result, expected_result);
+    assert_eq!(consumed_gas, 12);
+}
+
+#[test]
+fn point_evaluation_test() {
+    // The zero polynomial: commitment and proof are the point at infinity, y = 0 for any z
+    let mut calldata =
+        hex::decode("010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014").unwrap();
+    calldata.extend_from_slice(&[0u8; 64]);
+    let mut infinity = [0u8; 48];
+    infinity[0] = 0xc0;
+    calldata.extend_from_slice(&infinity);
+    calldata.extend_from_slice(&infinity);
+    let calldata = Bytes::from(calldata);
+
+    let mut consumed_gas = 0;
+    let result = point_evaluation(&calldata, 100000, &mut consumed_gas).unwrap();
+
+    let expected_result = Bytes::from(hex::decode("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001").unwrap());
+
+    assert_eq!(result, expected_result);
+    assert_eq!(consumed_gas, POINT_EVALUATION_COST);
+}
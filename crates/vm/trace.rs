@@ -0,0 +1,419 @@
+use ethrex_core::{
+    types::{Block, BlockHeader, Transaction},
+    H256,
+};
+use revm::{
+    db::{CacheDB, EmptyDB},
+    inspector_handle_register,
+    interpreter::{
+        CallInputs, CallOutcome, CreateInputs, CreateOutcome, EOFCreateInputs, InstructionResult,
+        Interpreter,
+    },
+    primitives::{result::EVMError as RevmError, Address, Log, ResultAndState, KECCAK_EMPTY, U256},
+    Database, DatabaseCommit, Evm, EvmContext, Inspector,
+};
+use revm_inspectors::tracing::{TracingInspector, TracingInspectorConfig};
+use std::time::{Duration, Instant};
+
+#[cfg(not(feature = "l2"))]
+use crate::{beacon_root_contract_call, history_storage_contract_call};
+use crate::{block_env, configure_evm, execute_tx, spec_id, tx_env, EvmError, EvmState, SpecId};
+// Export the geth tracing types so that crate users can parse tracing options and serialize traces
+pub use alloy_rpc_types_trace::geth::{
+    CallConfig, GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingOptions,
+    GethDefaultTracingOptions, GethTrace, NoopFrame, PreStateConfig,
+};
+
+/// Time given to a trace when the caller sets no timeout, same as geth
+const DEFAULT_TRACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Geth tracers supported by the node, along with their configuration
+enum TracerKind {
+    /// Default struct logger, records every executed opcode
+    StructLogger(GethDefaultTracingOptions),
+    CallTracer(CallConfig),
+    PreStateTracer(PreStateConfig),
+    NoopTracer,
+}
+
+impl TracerKind {
+    fn from_options(opts: &GethDebugTracingOptions) -> Result<Self, EvmError> {
+        let invalid_config =
+            |err: serde_json::Error| EvmError::Custom(format!("Invalid tracer config: {err}"));
+        match &opts.tracer {
+            None => Ok(TracerKind::StructLogger(opts.config)),
+            Some(GethDebugTracerType::BuiltInTracer(tracer)) => match tracer {
+                GethDebugBuiltInTracerType::CallTracer => Ok(TracerKind::CallTracer(
+                    opts.tracer_config
+                        .clone()
+                        .into_call_config()
+                        .map_err(invalid_config)?,
+                )),
+                GethDebugBuiltInTracerType::PreStateTracer => Ok(TracerKind::PreStateTracer(
+                    opts.tracer_config
+                        .clone()
+                        .into_pre_state_config()
+                        .map_err(invalid_config)?,
+                )),
+                GethDebugBuiltInTracerType::NoopTracer => Ok(TracerKind::NoopTracer),
+                other => Err(EvmError::Custom(format!("Unsupported tracer: {other:?}"))),
+            },
+            Some(GethDebugTracerType::JsTracer(_)) => {
+                Err(EvmError::Custom("JS tracers are not supported".to_string()))
+            }
+        }
+    }
+
+    fn inspector_config(&self) -> TracingInspectorConfig {
+        match self {
+            TracerKind::StructLogger(config) => TracingInspectorConfig::from_geth_config(config),
+            TracerKind::CallTracer(config) => TracingInspectorConfig::from_geth_call_config(config),
+            TracerKind::PreStateTracer(config) => {
+                TracingInspectorConfig::from_geth_prestate_config(config)
+            }
+            TracerKind::NoopTracer => TracingInspectorConfig::none(),
+        }
+    }
+}
+
+/// Traces the transaction at `tx_index` within the given block using the tracer described by `opts`
+/// The state must be built from the block's parent (see `evm_state`), as all previous transactions of the block
+/// will be re-executed before tracing the requested one
+pub fn trace_transaction(
+    block: &Block,
+    tx_index: usize,
+    state: &mut EvmState,
+    opts: &GethDebugTracingOptions,
+) -> Result<GethTrace, EvmError> {
+    trace_block_transactions(block, Some(tx_index), state, opts)?
+        .pop()
+        .map(|(_, trace)| trace)
+        .ok_or_else(|| EvmError::Custom(format!("Transaction index {tx_index} out of range")))
+}
+
+/// Traces all transactions in a block using the tracer described by `opts`
+/// The state must be built from the block's parent (see `evm_state`)
+/// Returns the hash of each transaction along with its trace
+pub fn trace_block(
+    block: &Block,
+    state: &mut EvmState,
+    opts: &GethDebugTracingOptions,
+) -> Result<Vec<(H256, GethTrace)>, EvmError> {
+    trace_block_transactions(block, None, state, opts)
+}
+
+/// Executes the block's transactions, tracing either all of them or only the one at `target_index`
+fn trace_block_transactions(
+    block: &Block,
+    target_index: Option<usize>,
+    state: &mut EvmState,
+    opts: &GethDebugTracingOptions,
+) -> Result<Vec<(H256, GethTrace)>, EvmError> {
+    let tracer = TracerKind::from_options(opts)?;
+    let timeout = match opts.timeout.as_deref() {
+        Some(timeout) => parse_timeout(timeout)?,
+        None => DEFAULT_TRACE_TIMEOUT,
+    };
+    let deadline = Instant::now() + timeout;
+    let block_header = &block.header;
+    let chain_config = state.chain_config()?;
+    let spec_id = spec_id(&chain_config, block_header.timestamp);
+    cfg_if::cfg_if! {
+        if #[cfg(not(feature = "l2"))] {
+            //eip 4788: execute beacon_root_contract_call before block transactions
+            if block_header.parent_beacon_block_root.is_some() && spec_id >= SpecId::CANCUN {
+                beacon_root_contract_call(state, block_header, spec_id)?;
+            }
+            //eip 2935: stores the parent block hash before block transactions
+            if spec_id >= SpecId::PRAGUE {
+                history_storage_contract_call(state, block_header, spec_id)?;
+            }
+        }
+    }
+
+    let mut traces = Vec::new();
+    for (index, tx) in block.body.transactions.iter().enumerate() {
+        if deadline_reached(deadline) {
+            return Err(timeout_error());
+        }
+        match target_index {
+            Some(target) if index < target => {
+                execute_tx(tx, block_header, state, spec_id)?;
+            }
+            Some(target) if index > target => break,
+            _ => {
+                let trace = match state {
+                    EvmState::Store(db) => trace_tx(
+                        tx,
+                        block_header,
+                        db,
+                        spec_id,
+                        chain_config.chain_id,
+                        &tracer,
+                        deadline,
+                    )?,
+                    EvmState::Execution(db) => trace_tx(
+                        tx,
+                        block_header,
+                        db,
+                        spec_id,
+                        chain_config.chain_id,
+                        &tracer,
+                        deadline,
+                    )?,
+                };
+                traces.push((tx.compute_hash(), trace));
+            }
+        }
+    }
+    Ok(traces)
+}
+
+/// Executes a single transaction with a tracing inspector and commits its state changes
+fn trace_tx<DB>(
+    tx: &Transaction,
+    header: &BlockHeader,
+    db: &mut DB,
+    spec_id: SpecId,
+    chain_id: u64,
+    tracer: &TracerKind,
+    deadline: Instant,
+) -> Result<GethTrace, EvmError>
+where
+    DB: Database + DatabaseCommit,
+    EvmError: From<RevmError<DB::Error>>,
+{
+    let mut inspector = TimeoutInspector {
+        inner: TracingInspector::new(tracer.inspector_config()),
+        deadline,
+        timed_out: false,
+    };
+    let result_and_state = {
+        let mut evm = configure_evm(
+            Evm::builder()
+                .with_external_context(&mut inspector)
+                .with_db(&mut *db),
+            tx_env(tx),
+            block_env(header, spec_id),
+            chain_id,
+            spec_id,
+        )
+        .append_handler_register(inspector_handle_register)
+        .build();
+        evm.transact()?
+    };
+    if inspector.timed_out {
+        return Err(timeout_error());
+    }
+    let mut inspector = inspector.inner;
+
+    let gas_used = result_and_state.result.gas_used();
+    let trace = match tracer {
+        TracerKind::StructLogger(config) => {
+            let return_value = result_and_state
+                .result
+                .output()
+                .cloned()
+                .unwrap_or_default();
+            GethTrace::Default(inspector.geth_builder().geth_traces(
+                gas_used,
+                return_value,
+                *config,
+            ))
+        }
+        TracerKind::CallTracer(config) => {
+            inspector.set_transaction_gas_limit(tx.gas_limit());
+            GethTrace::CallTracer(inspector.geth_builder().geth_call_traces(*config, gas_used))
+        }
+        TracerKind::PreStateTracer(config) => {
+            let pre_state = pre_state_db(db, &result_and_state)?;
+            GethTrace::PreStateTracer(
+                inspector
+                    .geth_builder()
+                    .geth_prestate_traces(&result_and_state, config, pre_state)
+                    .map_err(|err| EvmError::Custom(err.to_string()))?,
+            )
+        }
+        TracerKind::NoopTracer => GethTrace::NoopTracer(NoopFrame::default()),
+    };
+
+    db.commit(result_and_state.state);
+    Ok(trace)
+}
+
+/// Builds a database containing the state of the accounts touched by a transaction prior to its execution
+/// Must be called before committing the transaction's state changes
+fn pre_state_db<DB>(
+    db: &mut DB,
+    result_and_state: &ResultAndState,
+) -> Result<CacheDB<EmptyDB>, EvmError>
+where
+    DB: Database,
+    EvmError: From<RevmError<DB::Error>>,
+{
+    let mut pre_state = CacheDB::new(EmptyDB::default());
+    for address in result_and_state.state.keys() {
+        let Some(mut info) = db.basic(*address).map_err(RevmError::Database)? else {
+            continue;
+        };
+        if info.code.is_none() && info.code_hash != KECCAK_EMPTY {
+            info.code = Some(
+                db.code_by_hash(info.code_hash)
+                    .map_err(RevmError::Database)?,
+            );
+        }
+        pre_state.insert_account_info(*address, info);
+    }
+    Ok(pre_state)
+}
+
+/// Parses a tracer timeout, which geth expresses as a Go duration string (such as "5s" or "1m30s")
+fn parse_timeout(timeout: &str) -> Result<Duration, EvmError> {
+    let invalid_timeout = || EvmError::Custom(format!("Invalid tracer timeout: {timeout}"));
+    if timeout.is_empty() {
+        return Err(invalid_timeout());
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = timeout;
+    while !rest.is_empty() {
+        // Each component is a decimal number followed by its unit
+        let unit_start = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(invalid_timeout)?;
+        let (value, tail) = rest.split_at(unit_start);
+        let unit_end = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+        let value: f64 = value.parse().map_err(|_| invalid_timeout())?;
+        let unit_secs = match unit {
+            "ns" => 1e-9,
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return Err(invalid_timeout()),
+        };
+        total += Duration::try_from_secs_f64(value * unit_secs).map_err(|_| invalid_timeout())?;
+        rest = tail;
+    }
+    Ok(total)
+}
+
+fn deadline_reached(deadline: Instant) -> bool {
+    Instant::now() >= deadline
+}
+
+fn timeout_error() -> EvmError {
+    EvmError::Custom("Execution timeout".to_string())
+}
+
+/// Wraps the tracing inspector, halting execution once the tracing deadline is reached
+struct TimeoutInspector {
+    inner: TracingInspector,
+    deadline: Instant,
+    timed_out: bool,
+}
+
+impl<DB: Database> Inspector<DB> for TimeoutInspector {
+    fn initialize_interp(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.inner.initialize_interp(interp, context)
+    }
+
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        if deadline_reached(self.deadline) {
+            // Stop every frame as soon as it executes its next opcode, the trace will be discarded
+            self.timed_out = true;
+            interp.instruction_result = InstructionResult::Stop;
+            return;
+        }
+        self.inner.step(interp, context)
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.inner.step_end(interp, context)
+    }
+
+    fn log(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>, log: &Log) {
+        self.inner.log(interp, context, log)
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        self.inner.call(context, inputs)
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.inner.call_end(context, inputs, outcome)
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.inner.create(context, inputs)
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.inner.create_end(context, inputs, outcome)
+    }
+
+    fn eofcreate(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut EOFCreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.inner.eofcreate(context, inputs)
+    }
+
+    fn eofcreate_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &EOFCreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.inner.eofcreate_end(context, inputs, outcome)
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        <TracingInspector as Inspector<DB>>::selfdestruct(&mut self.inner, contract, target, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_go_duration_timeouts() {
+        assert_eq!(parse_timeout("5s").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_timeout("300ms").unwrap(), Duration::from_millis(300));
+        assert_eq!(parse_timeout("1m30s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_timeout("1.5h").unwrap(), Duration::from_secs(5400));
+    }
+
+    #[test]
+    fn reject_invalid_timeouts() {
+        for timeout in ["", "5", "s", "5sec", "-1s", "1..5s"] {
+            assert!(
+                parse_timeout(timeout).is_err(),
+                "{timeout} should be invalid"
+            );
+        }
+    }
+}
@@ -4,15 +4,17 @@ pub mod execution_db;
 mod execution_result;
 #[cfg(feature = "l2")]
 mod mods;
+mod trace;
 
-use db::StoreWrapper;
+use db::{StateAccessRecord, StoreWrapper};
 use execution_db::ExecutionDB;
 use std::cmp::min;
 
 use ethrex_core::{
     types::{
-        AccountInfo, Block, BlockHash, BlockHeader, ChainConfig, Fork, GenericTransaction,
-        PrivilegedTxType, Receipt, Transaction, TxKind, Withdrawal, GWEI_TO_WEI, INITIAL_BASE_FEE,
+        calculate_base_fee_per_blob_gas, AccountInfo, AuthorizationList, Block, BlockHash,
+        BlockHeader, ChainConfig, EncodedRequests, Fork, GenericTransaction, PrivilegedTxType,
+        Receipt, Requests, Transaction, TxKind, Withdrawal, GWEI_TO_WEI, INITIAL_BASE_FEE,
     },
     Address, BigEndianHash, H256, U256,
 };
@@ -21,27 +23,50 @@ use lazy_static::lazy_static;
 use revm::{
     db::{states::bundle_state::BundleRetention, AccountState, AccountStatus},
     inspector_handle_register,
-    inspectors::TracerEip3155,
     precompile::{PrecompileSpecId, Precompiles},
     primitives::{BlobExcessGasAndPrice, BlockEnv, TxEnv, B256},
-    Database, DatabaseCommit, Evm,
+    Database, DatabaseCommit, Evm, EvmBuilder,
 };
 use revm_inspectors::access_list::AccessListInspector;
 // Rename imported types for clarity
 use revm_primitives::{
-    ruint::Uint, AccessList as RevmAccessList, AccessListItem, Bytes, FixedBytes,
+    ruint::Uint, AccessList as RevmAccessList, AccessListItem, Authorization as RevmAuthorization,
+    AuthorizationList as RevmAuthorizationList, Bytes, FixedBytes, Signature as RevmSignature,
     TxKind as RevmTxKind,
 };
 // Export needed types
 pub use errors::EvmError;
 pub use execution_result::*;
 pub use revm::primitives::{Address as RevmAddress, SpecId, U256 as RevmU256};
+pub use trace::*;
 
 type AccessList = Vec<(Address, Vec<H256>)>;
 
 pub const WITHDRAWAL_MAGIC_DATA: &[u8] = b"burn";
 pub const DEPOSIT_MAGIC_DATA: &[u8] = b"mint";
 
+lazy_static! {
+    static ref SYSTEM_ADDRESS: RevmAddress = RevmAddress::from_slice(
+        &hex::decode("fffffffffffffffffffffffffffffffffffffffe").unwrap()
+    );
+    // See [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788)
+    static ref BEACON_ROOTS_ADDRESS: RevmAddress = RevmAddress::from_slice(
+        &hex::decode("000F3df6D732807Ef1319fB7B8bB8522d0Beac02").unwrap(),
+    );
+    // See [EIP-2935](https://eips.ethereum.org/EIPS/eip-2935)
+    static ref HISTORY_STORAGE_ADDRESS: RevmAddress = RevmAddress::from_slice(
+        &hex::decode("0000F90827F1C53a10cb7A02335B175320002935").unwrap(),
+    );
+    // See [EIP-7002](https://eips.ethereum.org/EIPS/eip-7002)
+    static ref WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS: RevmAddress = RevmAddress::from_slice(
+        &hex::decode("00000961Ef480Eb55e80D19ad83579A64c007002").unwrap(),
+    );
+    // See [EIP-7251](https://eips.ethereum.org/EIPS/eip-7251)
+    static ref CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS: RevmAddress = RevmAddress::from_slice(
+        &hex::decode("0000BBdDc7CE488642fb579F8B00f3a590007251").unwrap(),
+    );
+}
+
 /// State used when running the EVM. The state can be represented with a [StoreWrapper] database, or
 /// with a [ExecutionDB] in case we only want to store the necessary data for some particular
 /// execution, for example when proving in L2 mode.
@@ -62,6 +87,14 @@ impl EvmState {
         }
     }
 
+    /// Takes the record of the state read so far, if the state was built with [recording_evm_state]
+    pub fn take_access_record(&mut self) -> Option<StateAccessRecord> {
+        match self {
+            EvmState::Store(db) => db.database.recorder.as_mut().map(std::mem::take),
+            EvmState::Execution(_) => None,
+        }
+    }
+
     /// Gets the stored chain config
     pub fn chain_config(&self) -> Result<ChainConfig, EvmError> {
         match self {
@@ -98,9 +131,13 @@ cfg_if::cfg_if! {
             cfg_if::cfg_if! {
                 if #[cfg(not(feature = "l2"))] {
                     let spec_id = spec_id(&state.chain_config()?, block_header.timestamp);
-                    if block_header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
+                    if block_header.parent_beacon_block_root.is_some() && spec_id >= SpecId::CANCUN {
                         beacon_root_contract_call(state, block_header, spec_id)?;
                     }
+                    //eip 2935: stores the parent block hash before block transactions
+                    if spec_id >= SpecId::PRAGUE {
+                        history_storage_contract_call(state, block_header, spec_id)?;
+                    }
                 }
             }
             let mut receipts = Vec::new();
@@ -109,6 +146,7 @@ cfg_if::cfg_if! {
             let store_wrapper = Arc::new(StoreWrapper {
                 store: state.database().unwrap().clone(),
                 block_hash: block.header.parent_hash,
+                recorder: None,
             });
 
             let mut account_updates: Vec<AccountUpdate> = vec![];
@@ -209,9 +247,13 @@ cfg_if::cfg_if! {
             cfg_if::cfg_if! {
                 if #[cfg(not(feature = "l2"))] {
                     //eip 4788: execute beacon_root_contract_call before block transactions
-                    if block_header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
+                    if block_header.parent_beacon_block_root.is_some() && spec_id >= SpecId::CANCUN {
                         beacon_root_contract_call(state, block_header, spec_id)?;
                     }
+                    //eip 2935: stores the parent block hash before block transactions
+                    if spec_id >= SpecId::PRAGUE {
+                        history_storage_contract_call(state, block_header, spec_id)?;
+                    }
                 }
             }
             let mut receipts = Vec::new();
@@ -245,7 +287,7 @@ pub fn execute_tx(
     state: &mut EvmState,
     spec_id: SpecId,
 ) -> Result<ExecutionResult, EvmError> {
-    let block_env = block_env(header);
+    let block_env = block_env(header, spec_id);
     let tx_env = tx_env(tx);
     run_evm(tx_env, block_env, state, spec_id)
 }
@@ -257,7 +299,7 @@ pub fn simulate_tx_from_generic(
     state: &mut EvmState,
     spec_id: SpecId,
 ) -> Result<ExecutionResult, EvmError> {
-    let block_env = block_env(header);
+    let block_env = block_env(header, spec_id);
     let tx_env = tx_env_from_generic(tx, header.base_fee_per_gas.unwrap_or(INITIAL_BASE_FEE));
     run_without_commit(tx_env, block_env, state, spec_id)
 }
@@ -286,40 +328,28 @@ fn run_evm(
     spec_id: SpecId,
 ) -> Result<ExecutionResult, EvmError> {
     let tx_result = {
-        let chain_spec = state.chain_config()?;
-        #[allow(unused_mut)]
-        let mut evm_builder = Evm::builder()
-            .with_block_env(block_env)
-            .with_tx_env(tx_env)
-            .modify_cfg_env(|cfg| cfg.chain_id = chain_spec.chain_id)
-            .with_spec_id(spec_id)
-            .with_external_context(
-                TracerEip3155::new(Box::new(std::io::stderr())).without_summary(),
-            );
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "l2")] {
-                use revm::{Handler, primitives::{CancunSpec, HandlerCfg}};
-                use std::sync::Arc;
-
-                evm_builder = evm_builder.with_handler({
-                    let mut evm_handler = Handler::new(HandlerCfg::new(SpecId::LATEST));
-                    evm_handler.pre_execution.deduct_caller = Arc::new(mods::deduct_caller::<CancunSpec, _, _>);
-                    evm_handler.validation.tx_against_state = Arc::new(mods::validate_tx_against_state::<CancunSpec, _, _>);
-                    evm_handler.execution.last_frame_return = Arc::new(mods::last_frame_return::<CancunSpec, _, _>);
-                    // TODO: Override `end` function. We should deposit even if we revert.
-                    // evm_handler.pre_execution.end
-                    evm_handler
-                });
-            }
-        }
-
+        let chain_id = state.chain_config()?.chain_id;
         match state {
             EvmState::Store(db) => {
-                let mut evm = evm_builder.with_db(db).build();
+                let mut evm = configure_evm(
+                    Evm::builder().with_db(db),
+                    tx_env,
+                    block_env,
+                    chain_id,
+                    spec_id,
+                )
+                .build();
                 evm.transact_commit().map_err(EvmError::from)?
             }
             EvmState::Execution(db) => {
-                let mut evm = evm_builder.with_db(db).build();
+                let mut evm = configure_evm(
+                    Evm::builder().with_db(db),
+                    tx_env,
+                    block_env,
+                    chain_id,
+                    spec_id,
+                )
+                .build();
                 evm.transact_commit().map_err(EvmError::from)?
             }
         }
@@ -327,6 +357,40 @@ fn run_evm(
     Ok(tx_result.into())
 }
 
+/// Sets the environment of the EVM being built and installs the l2 handler overrides when the `l2` feature is enabled
+/// The database and external context must already be set as setting them resets the handler, inspectors must be registered afterwards
+fn configure_evm<'a, STAGE, EXT, DB: Database>(
+    evm_builder: EvmBuilder<'a, STAGE, EXT, DB>,
+    tx_env: TxEnv,
+    block_env: BlockEnv,
+    chain_id: u64,
+    spec_id: SpecId,
+) -> EvmBuilder<'a, STAGE, EXT, DB> {
+    #[allow(unused_mut)]
+    let mut evm_builder = evm_builder
+        .with_block_env(block_env)
+        .with_tx_env(tx_env)
+        .modify_cfg_env(|cfg| cfg.chain_id = chain_id)
+        .with_spec_id(spec_id);
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "l2")] {
+            use revm::{Handler, primitives::{CancunSpec, HandlerCfg}};
+            use std::sync::Arc;
+
+            evm_builder = evm_builder.with_handler({
+                let mut evm_handler = Handler::new(HandlerCfg::new(spec_id));
+                evm_handler.pre_execution.deduct_caller = Arc::new(mods::deduct_caller::<CancunSpec, _, _>);
+                evm_handler.validation.tx_against_state = Arc::new(mods::validate_tx_against_state::<CancunSpec, _, _>);
+                evm_handler.execution.last_frame_return = Arc::new(mods::last_frame_return::<CancunSpec, _, _>);
+                // TODO: Override `end` function. We should deposit even if we revert.
+                // evm_handler.pre_execution.end
+                evm_handler
+            });
+        }
+    }
+    evm_builder
+}
+
 /// Runs the transaction and returns the access list and estimated gas use (when running the tx with said access list)
 pub fn create_access_list(
     tx: &GenericTransaction,
@@ -335,7 +399,7 @@ pub fn create_access_list(
     spec_id: SpecId,
 ) -> Result<(ExecutionResult, AccessList), EvmError> {
     let mut tx_env = tx_env_from_generic(tx, header.base_fee_per_gas.unwrap_or(INITIAL_BASE_FEE));
-    let block_env = block_env(header);
+    let block_env = block_env(header, spec_id);
     // Run tx with access list inspector
 
     let (execution_result, access_list) =
@@ -604,9 +668,27 @@ pub fn process_withdrawals(
 
 /// Builds EvmState from a Store
 pub fn evm_state(store: Store, block_hash: BlockHash) -> EvmState {
+    build_evm_state(StoreWrapper {
+        store,
+        block_hash,
+        recorder: None,
+    })
+}
+
+/// Builds EvmState from a Store, recording every piece of state read during execution
+/// The record can be retrieved with [EvmState::take_access_record]
+pub fn recording_evm_state(store: Store, block_hash: BlockHash) -> EvmState {
+    build_evm_state(StoreWrapper {
+        store,
+        block_hash,
+        recorder: Some(StateAccessRecord::default()),
+    })
+}
+
+fn build_evm_state(store_wrapper: StoreWrapper) -> EvmState {
     EvmState::Store(
         revm::db::State::builder()
-            .with_database(StoreWrapper { store, block_hash })
+            .with_database(store_wrapper)
             .with_bundle_update()
             .without_state_clear()
             .build(),
@@ -620,14 +702,6 @@ pub fn beacon_root_contract_call(
     header: &BlockHeader,
     spec_id: SpecId,
 ) -> Result<ExecutionResult, EvmError> {
-    lazy_static! {
-        static ref SYSTEM_ADDRESS: RevmAddress = RevmAddress::from_slice(
-            &hex::decode("fffffffffffffffffffffffffffffffffffffffe").unwrap()
-        );
-        static ref CONTRACT_ADDRESS: RevmAddress = RevmAddress::from_slice(
-            &hex::decode("000F3df6D732807Ef1319fB7B8bB8522d0Beac02").unwrap(),
-        );
-    };
     let beacon_root = match header.parent_beacon_block_root {
         None => {
             return Err(EvmError::Header(
@@ -637,14 +711,123 @@ pub fn beacon_root_contract_call(
         Some(beacon_root) => beacon_root,
     };
 
+    generic_system_contract_call(
+        state,
+        header,
+        spec_id,
+        *BEACON_ROOTS_ADDRESS,
+        revm::primitives::Bytes::copy_from_slice(beacon_root.as_bytes()),
+    )
+}
+
+/// Calls the eip2935 history storage system call contract, which stores the parent block hash
+/// As of the Prague hard-fork, it is called before executing the block's transactions.
+pub fn history_storage_contract_call(
+    state: &mut EvmState,
+    header: &BlockHeader,
+    spec_id: SpecId,
+) -> Result<ExecutionResult, EvmError> {
+    generic_system_contract_call(
+        state,
+        header,
+        spec_id,
+        *HISTORY_STORAGE_ADDRESS,
+        revm::primitives::Bytes::copy_from_slice(header.parent_hash.as_bytes()),
+    )
+}
+
+/// Collects the execution layer requests of a block, as defined in [EIP-7685](https://eips.ethereum.org/EIPS/eip-7685)
+/// Deposits are parsed from the block receipts, while withdrawal and consolidation requests are dequeued
+/// by calling the eip7002 and eip7251 system contracts, so this must be called after all transactions
+/// and withdrawals of the block were processed.
+/// Returns no requests for blocks previous to the Prague hard-fork.
+pub fn extract_all_requests(
+    receipts: &[Receipt],
+    state: &mut EvmState,
+    header: &BlockHeader,
+) -> Result<Vec<EncodedRequests>, EvmError> {
+    let config = state.chain_config()?;
+    let spec_id = spec_id(&config, header.timestamp);
+    if spec_id < SpecId::PRAGUE {
+        return Ok(Vec::new());
+    }
+
+    let deposits = Requests::from_deposit_receipts(config.deposit_contract_address, receipts)
+        .ok_or(EvmError::Custom(
+            "Invalid deposit request layout".to_string(),
+        ))?;
+
+    // Unlike other system contracts, a block is invalid if the requests contracts are not deployed
+    if !has_code(state, *WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS)? {
+        return Err(EvmError::Custom(
+            "Withdrawal requests system contract has no code".to_string(),
+        ));
+    }
+    if !has_code(state, *CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS)? {
+        return Err(EvmError::Custom(
+            "Consolidation requests system contract has no code".to_string(),
+        ));
+    }
+
+    let withdrawals_result = generic_system_contract_call(
+        state,
+        header,
+        spec_id,
+        *WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS,
+        Bytes::new(),
+    )?;
+    if !withdrawals_result.is_success() {
+        return Err(EvmError::Custom(
+            "Withdrawal requests system call failed".to_string(),
+        ));
+    }
+
+    let consolidations_result = generic_system_contract_call(
+        state,
+        header,
+        spec_id,
+        *CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS,
+        Bytes::new(),
+    )?;
+    if !consolidations_result.is_success() {
+        return Err(EvmError::Custom(
+            "Consolidation requests system call failed".to_string(),
+        ));
+    }
+
+    Ok(vec![
+        deposits.encode(),
+        Requests::Withdrawal(withdrawals_result.output()).encode(),
+        Requests::Consolidation(consolidations_result.output()).encode(),
+    ])
+}
+
+/// Returns true if there is code deployed at the given address
+fn has_code(state: &mut EvmState, address: RevmAddress) -> Result<bool, EvmError> {
+    let account_info = match state {
+        EvmState::Store(db) => db.basic(address)?,
+        EvmState::Execution(db) => db.basic(address)?,
+    };
+    Ok(account_info.is_some_and(|info| !info.is_empty_code_hash()))
+}
+
+/// Performs a system call to the given contract, committing its state changes
+/// System calls are sent from the system address, don't pay for gas and don't count against the block gas limit.
+fn generic_system_contract_call(
+    state: &mut EvmState,
+    header: &BlockHeader,
+    spec_id: SpecId,
+    contract_address: RevmAddress,
+    calldata: revm::primitives::Bytes,
+) -> Result<ExecutionResult, EvmError> {
     let tx_env = TxEnv {
         caller: *SYSTEM_ADDRESS,
-        transact_to: RevmTxKind::Call(*CONTRACT_ADDRESS),
+        transact_to: RevmTxKind::Call(contract_address),
         gas_limit: 30_000_000,
-        data: revm::primitives::Bytes::copy_from_slice(beacon_root.as_bytes()),
+        data: calldata,
         ..Default::default()
     };
-    let mut block_env = block_env(header);
+    let mut block_env = block_env(header, spec_id);
     block_env.basefee = RevmU256::ZERO;
     block_env.gas_limit = RevmU256::from(30_000_000);
 
@@ -681,7 +864,21 @@ pub fn beacon_root_contract_call(
     }
 }
 
-pub fn block_env(header: &BlockHeader) -> BlockEnv {
+pub fn block_env(header: &BlockHeader, spec_id: SpecId) -> BlockEnv {
+    let excess_blob_gas = header.excess_blob_gas.unwrap_or_default();
+    let blob_excess_gas_and_price = if spec_id >= SpecId::PRAGUE {
+        // revm only knows about the cancun blob base fee update fraction
+        BlobExcessGasAndPrice {
+            excess_blob_gas,
+            blob_gasprice: calculate_base_fee_per_blob_gas(
+                excess_blob_gas,
+                Fork::Prague.blob_base_fee_update_fraction(),
+            )
+            .into(),
+        }
+    } else {
+        BlobExcessGasAndPrice::new(excess_blob_gas)
+    };
     BlockEnv {
         number: RevmU256::from(header.number),
         coinbase: RevmAddress(header.coinbase.0.into()),
@@ -690,9 +887,7 @@ pub fn block_env(header: &BlockHeader) -> BlockEnv {
         basefee: RevmU256::from(header.base_fee_per_gas.unwrap_or(INITIAL_BASE_FEE)),
         difficulty: RevmU256::from_limbs(header.difficulty.0),
         prevrandao: Some(header.prev_randao.as_fixed_bytes().into()),
-        blob_excess_gas_and_price: Some(BlobExcessGasAndPrice::new(
-            header.excess_blob_gas.unwrap_or_default(),
-        )),
+        blob_excess_gas_and_price: Some(blob_excess_gas_and_price),
     }
 }
 
@@ -766,9 +961,7 @@ pub fn tx_env(tx: &Transaction) -> TxEnv {
             .map(|hash| B256::from(hash.0))
             .collect(),
         max_fee_per_blob_gas,
-        // TODO revise
-        // https://eips.ethereum.org/EIPS/eip-7702
-        authorization_list: None,
+        authorization_list: tx.authorization_list().map(revm_authorization_list),
     }
 }
 
@@ -811,12 +1004,33 @@ fn tx_env_from_generic(tx: &GenericTransaction, basefee: u64) -> TxEnv {
             .map(|hash| B256::from(hash.0))
             .collect(),
         max_fee_per_blob_gas: tx.max_fee_per_blob_gas.map(|x| RevmU256::from_limbs(x.0)),
-        // TODO revise
-        // https://eips.ethereum.org/EIPS/eip-7702
-        authorization_list: None,
+        authorization_list: tx.authorization_list.clone().map(revm_authorization_list),
     }
 }
 
+// Converts an EIP-7702 authorization list into revm's representation
+// Authorities are recovered by revm when applying the list
+// The y_parity of each authorization is checked to be either 0 or 1 when decoding it
+fn revm_authorization_list(authorization_list: AuthorizationList) -> RevmAuthorizationList {
+    RevmAuthorizationList::Signed(
+        authorization_list
+            .into_iter()
+            .map(|auth| {
+                RevmAuthorization {
+                    chain_id: RevmU256::from_limbs(auth.chain_id.0),
+                    address: RevmAddress(auth.address.0.into()),
+                    nonce: auth.nonce,
+                }
+                .into_signed(RevmSignature::new(
+                    RevmU256::from_limbs(auth.r_signature.0),
+                    RevmU256::from_limbs(auth.s_signature.0),
+                    (!auth.y_parity.is_zero()).into(),
+                ))
+            })
+            .collect(),
+    )
+}
+
 // Creates an AccessListInspector that will collect the accesses used by the evm execution
 fn access_list_inspector(
     tx_env: &TxEnv,
@@ -854,6 +1068,7 @@ fn access_list_inspector(
 /// WARNING: Assumes at least Merge fork is active
 pub fn spec_id(chain_config: &ChainConfig, block_timestamp: u64) -> SpecId {
     match chain_config.get_fork(block_timestamp) {
+        Fork::Prague => SpecId::PRAGUE,
         Fork::Cancun => SpecId::CANCUN,
         Fork::Shanghai => SpecId::SHANGHAI,
         Fork::Paris => SpecId::MERGE,